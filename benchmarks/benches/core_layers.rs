@@ -1,21 +1,25 @@
 use benchmarks::{
     CHUNK_PAYLOAD_SIZE, CONCURRENT_OBJECT_COUNT, CONCURRENT_OBJECT_SIZE, LARGE_STORAGE_OBJECT_SIZE,
-    LARGE_TRANSFER_SIZE, MANY_SMALL_OBJECT_COUNT, SMALL_PAYLOAD_SIZE, compressible_payload,
-    concurrent_object_paths, deterministic_payload, many_small_object_paths,
+    LARGE_TRANSFER_SIZE, LOOPBACK_PING_COUNT, MANY_SMALL_OBJECT_COUNT, SMALL_PAYLOAD_SIZE,
+    ZlibChannelConfig, compressible_payload, concurrent_object_paths, deterministic_payload,
+    loopback_channel, loopback_ping_channel, many_small_object_paths, ping_round_trips,
     put_concurrent_objects, read_all_chunks, read_concurrent_objects, read_objects,
     sample_content_output, sample_write_command, seed_many_small_objects, seeded_memory_storage,
+    write_chunk_round_trips,
 };
 use common::{
-    CompressionManager, CryptoManager, DEFAULT_TRANSFER_CHUNK_SIZE, FenrisCommand, FenrisOutput,
-    FrameLimits, IV_SIZE, KEY_SIZE, LengthPrefixedFrame, MemoryStorage, ProtobufCodec,
-    ProtocolCodec, StorageBackend, TokioFsStorage, TransferChunk, ZlibCompressor, ZstdCompressor,
-    compression::NullCompressor,
-    crypto::{AesGcmEncryptor, HkdfSha256Deriver, X25519KeyExchanger},
+    CompressionManager, CryptoManager, DEFAULT_COMPRESSION_THRESHOLD, DEFAULT_TRANSFER_CHUNK_SIZE,
+    FenrisCommand, FenrisOutput, FrameLimits, IV_SIZE, KEY_SIZE, LengthPrefixedFrame,
+    MemoryStorage, ProtobufCodec, ProtocolCodec, StorageBackend, TokioFsStorage, TransferChunk,
+    ZlibCompressor, ZstdCompressor,
+    compression::{Compressor, NullCompressor},
+    crypto::{AesGcmEncryptor, Encryptor, HkdfSha256Deriver, X25519KeyExchanger},
 };
 use criterion::{
     BatchSize, BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main,
 };
 use std::path::Path;
+use std::time::{Duration, Instant};
 
 const BENCH_SIZES: [usize; 3] = [SMALL_PAYLOAD_SIZE, CHUNK_PAYLOAD_SIZE, LARGE_TRANSFER_SIZE];
 
@@ -172,6 +176,69 @@ fn bench_crypto(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_dispatch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dispatch");
+    let size = SMALL_PAYLOAD_SIZE;
+    group.throughput(Throughput::Bytes(size as u64));
+
+    let payload = compressible_payload(size);
+    let static_compression = CompressionManager::new(ZlibCompressor::default());
+    let dynamic_compression: Box<dyn Compressor> = Box::new(ZlibCompressor::default());
+
+    group.bench_with_input(
+        BenchmarkId::new("zlib_compress_static", size),
+        &payload,
+        |b, payload| b.iter(|| black_box(static_compression.compress(black_box(payload)).unwrap())),
+    );
+
+    group.bench_with_input(
+        BenchmarkId::new("zlib_compress_dynamic", size),
+        &payload,
+        |b, payload| {
+            b.iter(|| black_box(dynamic_compression.compress(black_box(payload)).unwrap()))
+        },
+    );
+
+    let static_crypto = CryptoManager::new(
+        AesGcmEncryptor,
+        X25519KeyExchanger,
+        HkdfSha256Deriver::default(),
+    );
+    let dynamic_encryptor: Box<dyn Encryptor> = Box::new(AesGcmEncryptor);
+    let key = [7; KEY_SIZE];
+    let iv = [3; IV_SIZE];
+
+    group.bench_with_input(
+        BenchmarkId::new("aes_gcm_encrypt_static", size),
+        &payload,
+        |b, payload| {
+            b.iter(|| {
+                black_box(
+                    static_crypto
+                        .encrypt(black_box(payload), &key, &iv)
+                        .unwrap(),
+                )
+            })
+        },
+    );
+
+    group.bench_with_input(
+        BenchmarkId::new("aes_gcm_encrypt_dynamic", size),
+        &payload,
+        |b, payload| {
+            b.iter(|| {
+                black_box(
+                    dynamic_encryptor
+                        .encrypt(black_box(payload), &key, &iv)
+                        .unwrap(),
+                )
+            })
+        },
+    );
+
+    group.finish();
+}
+
 fn bench_storage(c: &mut Criterion) {
     let runtime = tokio::runtime::Runtime::new().unwrap();
     let mut group = c.benchmark_group("storage");
@@ -539,6 +606,98 @@ fn bench_transfer_pipeline(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_secure_channel(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("secure_channel");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(LOOPBACK_PING_COUNT as u64));
+
+    group.bench_function("loopback_ping_10000", |b| {
+        b.iter_custom(|iters| {
+            runtime.block_on(async {
+                let (mut channel, server) = loopback_ping_channel().await.unwrap();
+                let mut elapsed = Duration::ZERO;
+
+                for _ in 0..iters {
+                    let start = Instant::now();
+                    black_box(
+                        ping_round_trips(&mut channel, LOOPBACK_PING_COUNT)
+                            .await
+                            .unwrap(),
+                    );
+                    elapsed += start.elapsed();
+                }
+
+                drop(channel);
+                server.await.unwrap();
+                elapsed
+            })
+        })
+    });
+
+    // zlib with the default threshold against zlib on every frame (threshold 0): pings should
+    // get faster, while full chunks are above the threshold either way and should not move.
+    for (label, threshold) in [
+        ("threshold_default", DEFAULT_COMPRESSION_THRESHOLD),
+        ("always_compress", 0),
+    ] {
+        group.throughput(Throughput::Elements(LOOPBACK_PING_COUNT as u64));
+        group.bench_function(BenchmarkId::new("zlib_ping_10000", label), |b| {
+            b.iter_custom(|iters| {
+                runtime.block_on(async {
+                    let (mut channel, server) = loopback_channel::<ZlibChannelConfig>(threshold)
+                        .await
+                        .unwrap();
+                    let mut elapsed = Duration::ZERO;
+
+                    for _ in 0..iters {
+                        let start = Instant::now();
+                        black_box(
+                            ping_round_trips(&mut channel, LOOPBACK_PING_COUNT)
+                                .await
+                                .unwrap(),
+                        );
+                        elapsed += start.elapsed();
+                    }
+
+                    drop(channel);
+                    server.await.unwrap();
+                    elapsed
+                })
+            })
+        });
+
+        let chunk_count = LARGE_TRANSFER_SIZE / CHUNK_PAYLOAD_SIZE;
+        group.throughput(Throughput::Bytes(LARGE_TRANSFER_SIZE as u64));
+        group.bench_function(BenchmarkId::new("zlib_upload_1mib", label), |b| {
+            b.iter_custom(|iters| {
+                runtime.block_on(async {
+                    let (mut channel, server) = loopback_channel::<ZlibChannelConfig>(threshold)
+                        .await
+                        .unwrap();
+                    let mut elapsed = Duration::ZERO;
+
+                    for _ in 0..iters {
+                        let start = Instant::now();
+                        black_box(
+                            write_chunk_round_trips(&mut channel, CHUNK_PAYLOAD_SIZE, chunk_count)
+                                .await
+                                .unwrap(),
+                        );
+                        elapsed += start.elapsed();
+                    }
+
+                    drop(channel);
+                    server.await.unwrap();
+                    elapsed
+                })
+            })
+        });
+    }
+
+    group.finish();
+}
+
 fn sealed_content_chunks(
     payload: &[u8],
     compression: &CompressionManager<NullCompressor>,
@@ -575,7 +734,9 @@ criterion_group!(
     bench_frame_codec,
     bench_compression,
     bench_crypto,
+    bench_dispatch,
     bench_storage,
-    bench_transfer_pipeline
+    bench_transfer_pipeline,
+    bench_secure_channel
 );
 criterion_main!(benches);
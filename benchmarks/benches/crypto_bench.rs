@@ -0,0 +1,127 @@
+use benchmarks::{
+    LARGE_TRANSFER_SIZE, compressible_payload, deterministic_payload, duplex_echo_channel,
+    incompressible_payload,
+};
+use common::{
+    FenrisCommand, FenrisOutput, IV_SIZE, KEY_SIZE, ZlibCompressor,
+    compression::Compressor,
+    crypto::{AesGcmEncryptor, Encryptor, HkdfSha256Deriver, KeyDeriver},
+};
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use std::time::{Duration, Instant};
+
+const PAYLOAD_SIZES: [usize; 3] = [1024, 64 * 1024, LARGE_TRANSFER_SIZE];
+const ROUND_TRIP_SIZE: usize = 1024;
+const MEASUREMENT_TIME: Duration = Duration::from_secs(5);
+
+fn bench_aes_gcm(c: &mut Criterion) {
+    let mut group = c.benchmark_group("aes_gcm");
+    group.measurement_time(MEASUREMENT_TIME);
+    let encryptor = AesGcmEncryptor;
+    let key = [7; KEY_SIZE];
+    let iv = [3; IV_SIZE];
+
+    for size in PAYLOAD_SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+        let plaintext = deterministic_payload(size);
+        let ciphertext = encryptor.encrypt(&plaintext, &key, &iv).unwrap();
+
+        group.bench_with_input(
+            BenchmarkId::new("encrypt", size),
+            &plaintext,
+            |b, plaintext| {
+                b.iter(|| black_box(encryptor.encrypt(black_box(plaintext), &key, &iv).unwrap()))
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("decrypt", size),
+            &ciphertext,
+            |b, ciphertext| {
+                b.iter(|| black_box(encryptor.decrypt(black_box(ciphertext), &key, &iv).unwrap()))
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_zlib(c: &mut Criterion) {
+    let mut group = c.benchmark_group("zlib");
+    group.measurement_time(MEASUREMENT_TIME);
+    let compressor = ZlibCompressor::default();
+
+    for size in PAYLOAD_SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+
+        for (label, payload) in [
+            ("compress_text", compressible_payload(size)),
+            ("compress_random", incompressible_payload(size)),
+        ] {
+            group.bench_with_input(BenchmarkId::new(label, size), &payload, |b, payload| {
+                b.iter(|| black_box(compressor.compress(black_box(payload)).unwrap()))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_hkdf(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hkdf_sha256");
+    group.measurement_time(MEASUREMENT_TIME);
+    let deriver = HkdfSha256Deriver::default();
+    let shared_secret = deterministic_payload(32);
+
+    group.bench_function("derive_key", |b| {
+        b.iter(|| {
+            black_box(
+                deriver
+                    .derive_key(black_box(&shared_secret), b"fenris bench", KEY_SIZE)
+                    .unwrap(),
+            )
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_secure_channel_round_trip(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("secure_channel_duplex");
+    group.measurement_time(MEASUREMENT_TIME);
+    group.throughput(Throughput::Bytes(ROUND_TRIP_SIZE as u64));
+    let command = FenrisCommand::Echo {
+        payload: deterministic_payload(ROUND_TRIP_SIZE),
+    };
+
+    group.bench_function(BenchmarkId::new("echo", ROUND_TRIP_SIZE), |b| {
+        b.iter_custom(|iters| {
+            runtime.block_on(async {
+                let (mut channel, server) = duplex_echo_channel().await.unwrap();
+                let start = Instant::now();
+
+                for _ in 0..iters {
+                    channel.send_msg(&command).await.unwrap();
+                    black_box(channel.recv_msg::<FenrisOutput>().await.unwrap());
+                }
+
+                let elapsed = start.elapsed();
+                drop(channel);
+                server.await.unwrap();
+                elapsed
+            })
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_aes_gcm,
+    bench_zlib,
+    bench_hkdf,
+    bench_secure_channel_round_trip
+);
+criterion_main!(benches);
@@ -0,0 +1,53 @@
+use benchmarks::{
+    LARGE_STORAGE_OBJECT_SIZE, LARGE_TRANSFER_SIZE, SMALL_PAYLOAD_SIZE, deterministic_payload,
+};
+use common::{DefaultFileOperations, FileOperations};
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use std::path::Path;
+use std::time::Duration;
+
+const FILE_SIZES: [usize; 3] = [
+    SMALL_PAYLOAD_SIZE,
+    LARGE_TRANSFER_SIZE,
+    LARGE_STORAGE_OBJECT_SIZE,
+];
+
+fn bench_file_ops(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file_ops = runtime
+        .block_on(DefaultFileOperations::new(temp_dir.path().to_path_buf()))
+        .unwrap();
+    let path = Path::new("bench.bin");
+
+    let mut group = c.benchmark_group("file_ops");
+    group.measurement_time(Duration::from_secs(5));
+    group.sample_size(10);
+
+    for size in FILE_SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+        let payload = deterministic_payload(size);
+
+        group.bench_with_input(
+            BenchmarkId::new("write_file", size),
+            &payload,
+            |b, payload| {
+                b.to_async(&runtime)
+                    .iter(|| async { file_ops.write_file(path, payload).await.unwrap() })
+            },
+        );
+
+        runtime
+            .block_on(file_ops.write_file(path, &payload))
+            .unwrap();
+        group.bench_function(BenchmarkId::new("read_file", size), |b| {
+            b.to_async(&runtime)
+                .iter(|| async { black_box(file_ops.read_file(path).await.unwrap()) })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_file_ops);
+criterion_main!(benches);
@@ -1,9 +1,13 @@
 use common::{
-    DEFAULT_TRANSFER_CHUNK_SIZE, FenrisCommand, FenrisError, FenrisOutput, MemoryStorage,
-    ObjectWriteMode, Result, StorageBackend, TransferChunk,
+    Config, DEFAULT_COMPRESSION_THRESHOLD, DEFAULT_TRANSFER_CHUNK_SIZE, DefaultSecureChannel,
+    DefaultSuite, FenrisCommand, FenrisError, FenrisOutput, MemoryStorage, ObjectWriteMode,
+    Protobuf, ProtocolCodec, ProtocolCodecOf, Result, SecureChannel, SecureChannelConfig,
+    StorageBackend, TransferChunk, Zlib,
 };
 use std::path::{Path, PathBuf};
-use tokio::task::JoinSet;
+use tokio::io::DuplexStream;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::{JoinHandle, JoinSet};
 
 pub const SMALL_PAYLOAD_SIZE: usize = 4 * 1024;
 pub const CHUNK_PAYLOAD_SIZE: usize = DEFAULT_TRANSFER_CHUNK_SIZE;
@@ -12,6 +16,7 @@ pub const LARGE_STORAGE_OBJECT_SIZE: usize = 16 * 1024 * 1024;
 pub const MANY_SMALL_OBJECT_COUNT: usize = 256;
 pub const CONCURRENT_OBJECT_COUNT: usize = 8;
 pub const CONCURRENT_OBJECT_SIZE: usize = 1024 * 1024;
+pub const LOOPBACK_PING_COUNT: usize = 10_000;
 
 pub fn deterministic_payload(size: usize) -> Vec<u8> {
     (0..size).map(|index| (index % 251) as u8).collect()
@@ -21,6 +26,19 @@ pub fn compressible_payload(size: usize) -> Vec<u8> {
     b"fenris benchmark payload ".repeat(size.div_ceil(25))[..size].to_vec()
 }
 
+/// Xorshift output, which zlib cannot shrink, seeded the same on every run.
+pub fn incompressible_payload(size: usize) -> Vec<u8> {
+    let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+    (0..size)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
 pub fn sample_write_command(size: usize) -> FenrisCommand {
     FenrisCommand::WriteObjectChunk(TransferChunk {
         offset: 0,
@@ -146,7 +164,7 @@ async fn join_storage_tasks(mut tasks: JoinSet<Result<usize>>) -> Result<usize>
 
     while let Some(result) = tasks.join_next().await {
         total += result.map_err(|e| {
-            FenrisError::FileOperationError(format!("storage benchmark task failed: {e}"))
+            FenrisError::file_operation(format!("storage benchmark task failed: {e}"))
         })??;
     }
 
@@ -172,6 +190,114 @@ pub async fn read_all_chunks<S: StorageBackend>(
     }
 }
 
+/// The default stack with zlib in place of the null compressor, so the secure channel benches
+/// can show what skipping compression below the threshold saves.
+pub struct ZlibChannelConfig;
+
+impl SecureChannelConfig for ZlibChannelConfig {
+    type CryptoConfig = DefaultSuite;
+    type CompressionConfig = Zlib;
+    type ProtocolConfig = Protobuf;
+}
+
+/// Connects a client channel to a loopback task that answers every command with `Pong`
+/// until the client hangs up.
+pub async fn loopback_ping_channel() -> Result<(DefaultSecureChannel, JoinHandle<()>)> {
+    loopback_channel(DEFAULT_COMPRESSION_THRESHOLD).await
+}
+
+/// Like [`loopback_ping_channel`] for any channel config, with both ends sending everything
+/// shorter than `compression_threshold` uncompressed.
+pub async fn loopback_channel<Cfg>(
+    compression_threshold: usize,
+) -> Result<(SecureChannel<Cfg>, JoinHandle<()>)>
+where
+    Cfg: SecureChannelConfig + Send + 'static,
+    ProtocolCodecOf<Cfg>: ProtocolCodec<FenrisCommand> + ProtocolCodec<FenrisOutput>,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let server = tokio::spawn(async move {
+        let Ok((stream, _)) = listener.accept().await else {
+            return;
+        };
+        let Ok(mut channel) = SecureChannel::<Cfg>::server_handshake(stream).await else {
+            return;
+        };
+        channel.set_compression_threshold(compression_threshold);
+        while channel.recv_msg::<FenrisCommand>().await.is_ok() {
+            if channel.send_msg(&FenrisOutput::Pong).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    let mut client =
+        SecureChannel::<Cfg>::client_handshake(TcpStream::connect(addr).await?).await?;
+    client.set_compression_threshold(compression_threshold);
+    Ok((client, server))
+}
+
+/// Connects a client channel over an in-memory duplex pipe to a task that answers every
+/// `Echo` with the same payload, so round trips measure the channel without a socket.
+pub async fn duplex_echo_channel() -> Result<(SecureChannel<Config, DuplexStream>, JoinHandle<()>)>
+{
+    let (client_stream, server_stream) = tokio::io::duplex(LARGE_TRANSFER_SIZE);
+
+    let server = tokio::spawn(async move {
+        let Ok(mut channel) = SecureChannel::<Config, _>::server_handshake(server_stream).await
+        else {
+            return;
+        };
+        while let Ok(FenrisCommand::Echo { payload }) = channel.recv_msg().await {
+            if channel
+                .send_msg(&FenrisOutput::Echo { payload })
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    });
+
+    let client = SecureChannel::<Config, _>::client_handshake(client_stream).await?;
+    Ok((client, server))
+}
+
+pub async fn ping_round_trips<Cfg>(channel: &mut SecureChannel<Cfg>, count: usize) -> Result<usize>
+where
+    Cfg: SecureChannelConfig,
+    ProtocolCodecOf<Cfg>: ProtocolCodec<FenrisCommand> + ProtocolCodec<FenrisOutput>,
+{
+    for _ in 0..count {
+        channel.send_msg(&FenrisCommand::Ping).await?;
+        channel.recv_msg::<FenrisOutput>().await?;
+    }
+
+    Ok(count)
+}
+
+/// Sends `count` write chunks of `size` bytes, waiting for each reply, and returns the bytes
+/// sent.
+pub async fn write_chunk_round_trips<Cfg>(
+    channel: &mut SecureChannel<Cfg>,
+    size: usize,
+    count: usize,
+) -> Result<usize>
+where
+    Cfg: SecureChannelConfig,
+    ProtocolCodecOf<Cfg>: ProtocolCodec<FenrisCommand> + ProtocolCodec<FenrisOutput>,
+{
+    let command = sample_write_command(size);
+    for _ in 0..count {
+        channel.send_msg(&command).await?;
+        channel.recv_msg::<FenrisOutput>().await?;
+    }
+
+    Ok(size * count)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -283,4 +409,14 @@ mod tests {
         assert_eq!(written, CONCURRENT_OBJECT_COUNT * CONCURRENT_OBJECT_SIZE);
         assert_eq!(read, written);
     }
+
+    #[tokio::test]
+    async fn loopback_ping_channel_answers_pings() {
+        let (mut channel, server) = loopback_ping_channel().await.unwrap();
+
+        assert_eq!(ping_round_trips(&mut channel, 3).await.unwrap(), 3);
+
+        drop(channel);
+        server.await.unwrap();
+    }
 }
@@ -1,10 +1,185 @@
-use std::time::Instant;
+use common::{FenrisMetadata, WatchEvent};
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use ratatui::layout::Rect;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+use crate::bookmarks;
+use crate::connection_manager::ConnectionManager;
+use crate::request_manager::RequestManager;
+use crate::response_manager::FormattedResponse;
+use crate::session_log;
+use crate::transfers::{self, TransferRecord};
+use crate::ui::Theme;
+use crate::workspace::{WORKSPACE_MESSAGE_LIMIT, Workspace};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Screen {
     Connection,
     Command,
     Help,
+    CommandPalette,
+}
+
+/// Commands the TUI handles itself; everything else is listed in `request_manager::COMMANDS`.
+pub const LOCAL_COMMANDS: &[(&str, &str)] = &[
+    ("help", "Show this help"),
+    (
+        "tailf [file]",
+        "Follow a file as it grows; no file stops following",
+    ),
+    (
+        "workspace save|load [path]",
+        "Save or restore server, history, bookmarks and messages",
+    ),
+    ("theme reload", "Reload colors from ~/.fenris_theme.toml"),
+    (
+        "bookmark add <alias> [path]",
+        "Bookmark the current (or given) directory",
+    ),
+    ("bookmark rm <alias>", "Remove a bookmark"),
+    ("bookmark list", "Show all bookmarks"),
+    ("go <alias>", "Change to a bookmarked directory"),
+    ("history transfers", "Show the last 50 uploads"),
+    ("history transfers clear", "Forget past transfers"),
+    (
+        "session-log [path]",
+        "Log commands and responses to a file (default ~/.fenris_sessions)",
+    ),
+    ("watch <path>", "Watch a file or directory for changes"),
+    ("unwatch <path>", "Stop watching a path"),
+    (
+        "<command> > <file> | >> <file>",
+        "Save a command's output locally (>> appends)",
+    ),
+];
+
+/// Hostnames, IPv4 and IPv6 addresses only ever need these.
+fn is_address_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ':')
+}
+
+/// Usage and description of every command, as shown by the help screen and the palette.
+pub fn known_commands() -> Vec<(&'static str, &'static str)> {
+    RequestManager::command_list()
+        .iter()
+        .map(|command| (command.usage, command.description))
+        .chain(LOCAL_COMMANDS.iter().copied())
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaneIndex {
+    Left,
+    Right,
+}
+
+impl PaneIndex {
+    pub fn other(self) -> Self {
+        match self {
+            PaneIndex::Left => PaneIndex::Right,
+            PaneIndex::Right => PaneIndex::Left,
+        }
+    }
+}
+
+/// One side of the split view: a server directory and its last listing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PaneState {
+    pub current_dir: String,
+    pub listing: Vec<FenrisMetadata>,
+    pub selected: usize,
+}
+
+impl PaneState {
+    pub fn selected_entry(&self) -> Option<&FenrisMetadata> {
+        self.listing.get(self.selected)
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        if self.listing.is_empty() {
+            return;
+        }
+        self.selected = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(self.listing.len() - 1);
+    }
+
+    pub fn set_listing(&mut self, listing: Vec<FenrisMetadata>) {
+        self.listing = listing;
+        self.selected = self.selected.min(self.listing.len().saturating_sub(1));
+    }
+
+    pub fn path_of(&self, name: &str) -> String {
+        bookmarks::absolute_path(&self.current_dir, name)
+    }
+
+    fn change_dir(&mut self, dir: String) {
+        self.current_dir = dir;
+        self.listing.clear();
+        self.selected = 0;
+    }
+}
+
+/// Network work a split-view key leaves for the client loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaneAction {
+    Refresh(PaneIndex),
+    /// Copy the focused pane's selected file into the other pane's directory.
+    Copy,
+    /// Like `Copy`, removing the original.
+    Move,
+    /// Show the focused pane's selected file in the message log.
+    Read,
+    /// Show the metadata of the focused pane's selection.
+    Info,
+    /// Delete the focused pane's selection.
+    Delete,
+}
+
+/// Two left clicks on the same pane row within this long open it.
+pub const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextMenuItem {
+    Open,
+    Info,
+    Delete,
+    CopyPath,
+}
+
+impl ContextMenuItem {
+    pub const ALL: [ContextMenuItem; 4] = [
+        ContextMenuItem::Open,
+        ContextMenuItem::Info,
+        ContextMenuItem::Delete,
+        ContextMenuItem::CopyPath,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ContextMenuItem::Open => "Open",
+            ContextMenuItem::Info => "Info",
+            ContextMenuItem::Delete => "Delete",
+            ContextMenuItem::CopyPath => "Copy Path",
+        }
+    }
+}
+
+/// Where the last frame put the things a mouse click can hit, in terminal cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderLayout {
+    /// Left and right pane, borders included, while the split view is shown.
+    pub panes: Option<[Rect; 2]>,
+    pub context_menu: Option<Rect>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,23 +188,110 @@ pub enum ConnectionFocus {
     Port,
 }
 
-pub struct App {
+pub struct TabState {
+    pub connection_manager: ConnectionManager,
     pub screen: Screen,
-    pub should_quit: bool,
 
     pub server_addr: String,
     pub server_port: String,
     pub connection_focus: ConnectionFocus,
+    pub connection_error: Option<String>,
     pub connected: bool,
+    pub connected_at: Option<Instant>,
+    /// The health check lost the connection and is retrying it in the background.
+    pub reconnecting: bool,
     pub current_dir: String,
 
+    pub messages: Vec<Message>,
+    /// Headers and rows of the last response that reads best as a table.
+    pub table: Option<(Vec<String>, Vec<Vec<String>>)>,
+    pub table_selection: Option<usize>,
+    pub watched_paths: Vec<String>,
+    pub watch_events: Vec<mpsc::Receiver<WatchEvent>>,
+    pub last_watch_poll: Instant,
+    pub tailf_path: Option<String>,
+    pub tailf_offset: u64,
+    pub last_tailf_poll: Instant,
+
+    /// Directory entries by absolute server path; directories carry a trailing `/`.
+    pub completion_cache: HashMap<String, Vec<String>>,
+    /// A directory the completer needs listed before it can offer anything.
+    pub pending_completion: Option<String>,
+}
+
+pub struct App {
+    pub should_quit: bool,
+
+    pub tabs: Vec<TabState>,
+    pub active_tab: usize,
+
     pub command_input: String,
     pub command_history: Vec<String>,
     pub history_index: Option<usize>,
 
-    pub messages: Vec<Message>,
     pub cursor_position: usize,
     pub last_tick: Instant,
+
+    pub palette_query: String,
+    pub palette_selection: usize,
+
+    pub theme: Theme,
+
+    pub transfer_log: Option<PathBuf>,
+
+    pub bookmarks: Vec<(String, String)>,
+    pub bookmarks_file: Option<PathBuf>,
+
+    pub completion: Option<Completion>,
+
+    pub visible_kinds: HashSet<MessageKind>,
+
+    /// `Some` while the message search bar is open, even before anything is typed.
+    pub search_query: Option<String>,
+    /// Indices into the active tab's `messages` that match `search_query`.
+    pub search_results: Vec<usize>,
+    pub search_selection: usize,
+
+    /// Two directory panes replace the message log while set.
+    pub split_pane: bool,
+    pub left_pane: PaneState,
+    pub right_pane: PaneState,
+    pub active_pane: PaneIndex,
+    pub pending_pane_actions: Vec<PaneAction>,
+
+    /// Every command and response of the session, while `--session-log` or `session-log` is on.
+    pub session_log: Option<BufWriter<File>>,
+    /// Logs file contents shown by `read` as `[REDACTED: N bytes]`.
+    pub redact_session_log: bool,
+
+    /// Clicks select, open and pop up a context menu in the split view; set by `--mouse`.
+    pub mouse_enabled: bool,
+    /// Written by every render so clicks can be mapped back to what was drawn.
+    pub last_render_layout: Cell<RenderLayout>,
+    /// Top-left corner of the open right-click menu; it acts on the focused pane's selection.
+    pub context_menu: Option<(u16, u16)>,
+    pub last_click: Option<(Instant, PaneIndex, usize)>,
+}
+
+/// The candidates Tab cycles through for the path token starting at `start`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub start: usize,
+    pub typed_len: usize,
+    pub candidates: Vec<String>,
+    pub index: usize,
+}
+
+impl Completion {
+    fn current(&self) -> &str {
+        &self.candidates[self.index]
+    }
+
+    /// Whether the input still holds the last candidate with the cursor right after it.
+    fn is_applied(&self, input: &str, cursor_position: usize) -> bool {
+        cursor_position == self.start + self.current().len()
+            && input.get(self.start..cursor_position) == Some(self.current())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -39,29 +301,108 @@ pub struct Message {
     pub content: String,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum MessageKind {
     Info,
+    Warning,
     Error,
     Success,
+    DiffAdded,
+    DiffRemoved,
+    DiffContext,
 }
 
-impl App {
-    pub fn new() -> Self {
+impl MessageKind {
+    pub const ALL: [MessageKind; 7] = [
+        MessageKind::Info,
+        MessageKind::Warning,
+        MessageKind::Error,
+        MessageKind::Success,
+        MessageKind::DiffAdded,
+        MessageKind::DiffRemoved,
+        MessageKind::DiffContext,
+    ];
+}
+
+impl TabState {
+    pub fn new(connection_manager: ConnectionManager) -> Self {
         Self {
+            connection_manager,
             screen: Screen::Connection,
-            should_quit: false,
             server_addr: String::from("127.0.0.1"),
             server_port: String::from("5555"),
             connection_focus: ConnectionFocus::Address,
+            connection_error: None,
             connected: false,
+            connected_at: None,
+            reconnecting: false,
             current_dir: String::from("/"),
-            command_input: String::new(),
-            command_history: Vec::new(),
-            history_index: None,
             messages: Vec::new(),
-            cursor_position: 0,
-            last_tick: Instant::now(),
+            table: None,
+            table_selection: None,
+            watched_paths: Vec::new(),
+            watch_events: Vec::new(),
+            last_watch_poll: Instant::now(),
+            tailf_path: None,
+            tailf_offset: 0,
+            last_tailf_poll: Instant::now(),
+            completion_cache: HashMap::new(),
+            pending_completion: None,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format!("{}:{}", self.server_addr, self.server_port)
+    }
+
+    pub fn toggle_connection_focus(&mut self) {
+        self.connection_focus = match self.connection_focus {
+            ConnectionFocus::Address => ConnectionFocus::Port,
+            ConnectionFocus::Port => ConnectionFocus::Address,
+        };
+    }
+
+    pub fn connection_insert_char(&mut self, c: char) {
+        match self.connection_focus {
+            ConnectionFocus::Address => {
+                self.server_addr.push(c);
+                self.connection_error = None;
+            }
+            ConnectionFocus::Port => {
+                if c.is_ascii_digit() && self.server_port.len() < 5 {
+                    self.server_port.push(c);
+                    self.connection_error = None;
+                }
+            }
+        }
+    }
+
+    /// Validates the connection form, focusing the first invalid field and
+    /// recording an inline error for it.
+    pub fn connection_target(&mut self) -> Option<(String, u16)> {
+        let address = self.server_addr.trim();
+        if address.is_empty() {
+            self.connection_focus = ConnectionFocus::Address;
+            self.connection_error = Some("Server address is required".to_string());
+            return None;
+        }
+        if let Some(c) = address.chars().find(|c| !is_address_char(*c)) {
+            self.connection_focus = ConnectionFocus::Address;
+            self.connection_error = Some(format!("Server address cannot contain '{}'", c));
+            return None;
+        }
+
+        match self.server_port.trim().parse::<u16>() {
+            Ok(port) if port != 0 => {
+                self.connection_error = None;
+                Some((address.to_string(), port))
+            }
+            _ => {
+                self.connection_focus = ConnectionFocus::Port;
+                self.connection_error = Some("Port must be between 1 and 65535".to_string());
+                None
+            }
         }
     }
 
@@ -81,6 +422,10 @@ impl App {
         self.add_message(MessageKind::Info, content.into());
     }
 
+    pub fn warn(&mut self, content: impl Into<String>) {
+        self.add_message(MessageKind::Warning, content.into());
+    }
+
     pub fn error(&mut self, content: impl Into<String>) {
         self.add_message(MessageKind::Error, content.into());
     }
@@ -89,6 +434,323 @@ impl App {
         self.add_message(MessageKind::Success, content.into());
     }
 
+    pub fn show_table(&mut self, table: Option<(Vec<String>, Vec<Vec<String>>)>) {
+        self.table = table;
+        self.table_selection = None;
+    }
+
+    /// Moves the highlighted table row by `delta`, starting from the first row.
+    pub fn move_table_selection(&mut self, delta: isize) {
+        let Some((_, rows)) = &self.table else {
+            return;
+        };
+        if rows.is_empty() {
+            return;
+        }
+
+        let last = rows.len() - 1;
+        self.table_selection = Some(match self.table_selection {
+            Some(selected) => selected.saturating_add_signed(delta).min(last),
+            None => 0,
+        });
+    }
+}
+
+impl App {
+    pub fn new(connection_manager: ConnectionManager) -> Self {
+        let mut app = Self {
+            should_quit: false,
+            tabs: vec![TabState::new(connection_manager)],
+            active_tab: 0,
+            command_input: String::new(),
+            command_history: Vec::new(),
+            history_index: None,
+            cursor_position: 0,
+            last_tick: Instant::now(),
+            palette_query: String::new(),
+            palette_selection: 0,
+            theme: Theme::default(),
+            transfer_log: transfers::transfer_log_path(),
+            bookmarks: Vec::new(),
+            bookmarks_file: bookmarks::bookmarks_path(),
+            completion: None,
+            visible_kinds: MessageKind::ALL.into_iter().collect(),
+            search_query: None,
+            search_results: Vec::new(),
+            search_selection: 0,
+            split_pane: false,
+            left_pane: PaneState::default(),
+            right_pane: PaneState::default(),
+            active_pane: PaneIndex::Left,
+            pending_pane_actions: Vec::new(),
+            session_log: None,
+            redact_session_log: false,
+            mouse_enabled: false,
+            last_render_layout: Cell::new(RenderLayout::default()),
+            context_menu: None,
+            last_click: None,
+        };
+
+        if let Err(e) = app.reload_theme() {
+            app.warn(format!("Using default theme: {:#}", e));
+        }
+        if let Some(path) = &app.bookmarks_file {
+            match bookmarks::load(path) {
+                Ok(loaded) => app.bookmarks = loaded,
+                Err(e) => app.warn(format!("Bookmarks not loaded: {:#}", e)),
+            }
+        }
+
+        app
+    }
+
+    pub fn reload_theme(&mut self) -> anyhow::Result<()> {
+        self.theme = Theme::load_user()?;
+        Ok(())
+    }
+
+    /// Appends to `~/.fenris_transfers.log`; a no-op when `HOME` is unset.
+    pub fn record_transfer(&self, record: TransferRecord) -> anyhow::Result<()> {
+        match &self.transfer_log {
+            Some(path) => transfers::append_record(path, &record),
+            None => Ok(()),
+        }
+    }
+
+    pub fn transfer_history(&self) -> anyhow::Result<Vec<TransferRecord>> {
+        match &self.transfer_log {
+            Some(path) => transfers::read_recent(path, transfers::TRANSFER_HISTORY_LIMIT),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn clear_transfer_history(&self) -> anyhow::Result<()> {
+        match &self.transfer_log {
+            Some(path) => transfers::clear(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Starts logging to `path`, closing any log already open.
+    pub fn start_session_log(&mut self, path: &Path) -> anyhow::Result<()> {
+        self.close_session_log()?;
+        self.session_log = Some(session_log::open(path)?);
+        Ok(())
+    }
+
+    pub fn log_command(&mut self, command: &str) {
+        self.write_session_log(|log| session_log::write_command(log, command));
+    }
+
+    /// `redact` is honoured only with `redact_session_log` set.
+    pub fn log_response(&mut self, result: &common::Result<FormattedResponse>, redact: bool) {
+        let redact = redact && self.redact_session_log;
+        self.write_session_log(|log| match result {
+            Ok(formatted) => session_log::write_response(log, formatted, redact),
+            Err(e) => session_log::write_error(log, e),
+        });
+    }
+
+    pub fn close_session_log(&mut self) -> std::io::Result<()> {
+        match self.session_log.take() {
+            Some(mut log) => log.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// A log that fails once is closed rather than warning on every later command.
+    fn write_session_log(
+        &mut self,
+        write: impl FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+    ) {
+        let Some(log) = &mut self.session_log else {
+            return;
+        };
+        if let Err(e) = write(log) {
+            self.session_log = None;
+            self.warn(format!("Session log closed: {}", e));
+        }
+    }
+
+    /// Adds or replaces `alias` and writes the list to `~/.fenris_bookmarks.toml`.
+    pub fn add_bookmark(&mut self, alias: &str, path: String) -> anyhow::Result<()> {
+        match self.bookmarks.iter_mut().find(|(name, _)| name == alias) {
+            Some(bookmark) => bookmark.1 = path,
+            None => self.bookmarks.push((alias.to_string(), path)),
+        }
+        self.save_bookmarks()
+    }
+
+    /// Returns whether a bookmark was removed.
+    pub fn remove_bookmark(&mut self, alias: &str) -> anyhow::Result<bool> {
+        let before = self.bookmarks.len();
+        self.bookmarks.retain(|(name, _)| name != alias);
+        if self.bookmarks.len() == before {
+            return Ok(false);
+        }
+        self.save_bookmarks()?;
+        Ok(true)
+    }
+
+    pub fn expand_bookmark(&self, alias: &str) -> Option<String> {
+        self.bookmarks
+            .iter()
+            .find(|(name, _)| name == alias)
+            .map(|(_, path)| path.clone())
+    }
+
+    fn save_bookmarks(&self) -> anyhow::Result<()> {
+        match &self.bookmarks_file {
+            Some(path) => bookmarks::save(path, &self.bookmarks),
+            None => Ok(()),
+        }
+    }
+
+    pub fn toggle_kind(&mut self, kind: MessageKind) {
+        if !self.visible_kinds.remove(&kind) {
+            self.visible_kinds.insert(kind);
+        }
+    }
+
+    pub fn show_all_kinds(&mut self) {
+        self.visible_kinds = MessageKind::ALL.into_iter().collect();
+    }
+
+    pub fn show_only_errors(&mut self) {
+        self.visible_kinds = HashSet::from([MessageKind::Error]);
+    }
+
+    /// Saves the active tab's server and recent messages along with the history and
+    /// bookmarks.
+    pub fn save_workspace(&self, path: &Path) -> anyhow::Result<()> {
+        let tab = self.tab();
+        let skip = tab.messages.len().saturating_sub(WORKSPACE_MESSAGE_LIMIT);
+        Workspace {
+            server_addr: tab.server_addr.clone(),
+            server_port: tab.server_port.parse().unwrap_or(0),
+            command_history: self.command_history.clone(),
+            bookmarks: self.bookmarks.clone(),
+            recent_messages: tab.messages[skip..]
+                .iter()
+                .map(|message| (message.kind, message.content.clone()))
+                .collect(),
+        }
+        .save(path)
+    }
+
+    /// Replaces the history and bookmarks, fills the active tab's connection form and
+    /// appends the saved messages to its log. An open connection is left alone.
+    pub fn load_workspace(&mut self, path: &Path) -> anyhow::Result<()> {
+        let workspace = Workspace::load(path)?;
+
+        self.command_history = workspace.command_history;
+        self.history_index = None;
+        self.bookmarks = workspace.bookmarks;
+
+        let tab = self.tab_mut();
+        tab.server_addr = workspace.server_addr;
+        if workspace.server_port != 0 {
+            tab.server_port = workspace.server_port.to_string();
+        }
+        tab.connection_error = None;
+        for (kind, content) in workspace.recent_messages {
+            tab.add_message(kind, content);
+        }
+        Ok(())
+    }
+
+    pub fn tab(&self) -> &TabState {
+        &self.tabs[self.active_tab]
+    }
+
+    pub fn tab_mut(&mut self) -> &mut TabState {
+        &mut self.tabs[self.active_tab]
+    }
+
+    pub fn tab_labels(&self) -> Vec<String> {
+        self.tabs.iter().map(TabState::label).collect()
+    }
+
+    pub fn open_tab(&mut self, connection_manager: ConnectionManager) {
+        self.close_search();
+        self.tabs.push(TabState::new(connection_manager));
+        self.active_tab = self.tabs.len() - 1;
+    }
+
+    pub fn next_tab(&mut self) {
+        self.close_search();
+        self.active_tab = (self.active_tab + 1) % self.tabs.len();
+    }
+
+    pub fn close_active_tab(&mut self, replacement: ConnectionManager) -> TabState {
+        self.close_search();
+        let closed = if self.tabs.len() == 1 {
+            std::mem::replace(&mut self.tabs[0], TabState::new(replacement))
+        } else {
+            self.tabs.remove(self.active_tab)
+        };
+
+        if self.active_tab >= self.tabs.len() {
+            self.active_tab = self.tabs.len() - 1;
+        }
+
+        closed
+    }
+
+    pub fn add_message(&mut self, kind: MessageKind, content: String) {
+        self.tab_mut().add_message(kind, content);
+    }
+
+    pub fn info(&mut self, content: impl Into<String>) {
+        self.add_message(MessageKind::Info, content.into());
+    }
+
+    pub fn warn(&mut self, content: impl Into<String>) {
+        self.add_message(MessageKind::Warning, content.into());
+    }
+
+    pub fn error(&mut self, content: impl Into<String>) {
+        self.add_message(MessageKind::Error, content.into());
+    }
+
+    pub fn paste(&mut self) {
+        match clipboard_text() {
+            Ok(text) => self.insert_text(&text),
+            Err(e) => {
+                tracing::warn!("Clipboard paste failed: {}", e);
+                self.error(format!("Paste failed: {}", e));
+            }
+        }
+    }
+
+    /// Inserts into whichever input has focus. Control characters, including newlines, are
+    /// dropped so a pasted line can't submit itself.
+    pub fn insert_text(&mut self, text: &str) {
+        let on_connection_screen = self.tab().screen == Screen::Connection;
+        for c in text.chars().filter(|c| !c.is_control()) {
+            if on_connection_screen {
+                self.tab_mut().connection_insert_char(c);
+            } else {
+                self.insert_char(c);
+            }
+        }
+    }
+
+    pub fn copy_last_response(&mut self) {
+        let Some(content) = self.tab().messages.last().map(|m| m.content.clone()) else {
+            self.warn("Nothing to copy");
+            return;
+        };
+
+        match set_clipboard_text(content) {
+            Ok(()) => self.info("Copied last response to clipboard"),
+            Err(e) => {
+                tracing::warn!("Clipboard copy failed: {}", e);
+                self.error(format!("Copy failed: {}", e));
+            }
+        }
+    }
+
     pub fn add_to_history(&mut self, command: String) {
         if !command.is_empty() {
             self.command_history.push(command);
@@ -122,31 +784,70 @@ impl App {
         }
     }
 
+    // `cursor_position` is a byte index into `command_input`, so it moves a whole UTF-8
+    // sequence at a time.
     pub fn insert_char(&mut self, c: char) {
         self.command_input.insert(self.cursor_position, c);
-        self.cursor_position += 1;
+        self.cursor_position += c.len_utf8();
         self.history_index = None;
     }
 
     pub fn delete_char(&mut self) {
-        if self.cursor_position > 0 {
-            self.command_input.remove(self.cursor_position - 1);
-            self.cursor_position -= 1;
+        if let Some(c) = self.command_input[..self.cursor_position]
+            .chars()
+            .next_back()
+        {
+            self.cursor_position -= c.len_utf8();
+            self.command_input.remove(self.cursor_position);
         }
     }
 
     pub fn move_cursor_left(&mut self) {
-        if self.cursor_position > 0 {
-            self.cursor_position -= 1;
+        if let Some(c) = self.command_input[..self.cursor_position]
+            .chars()
+            .next_back()
+        {
+            self.cursor_position -= c.len_utf8();
         }
     }
 
     pub fn move_cursor_right(&mut self) {
-        if self.cursor_position < self.command_input.len() {
-            self.cursor_position += 1;
+        if let Some(c) = self.command_input[self.cursor_position..].chars().next() {
+            self.cursor_position += c.len_utf8();
         }
     }
 
+    pub fn move_cursor_word_left(&mut self) {
+        self.cursor_position = self.previous_word_start();
+    }
+
+    pub fn move_cursor_word_right(&mut self) {
+        let after = &self.command_input[self.cursor_position..];
+        self.cursor_position = after
+            .char_indices()
+            .skip_while(|(_, c)| !is_word_char(*c))
+            .find(|(_, c)| !is_word_char(*c))
+            .map(|(i, _)| self.cursor_position + i)
+            .unwrap_or(self.command_input.len());
+    }
+
+    pub fn delete_word_left(&mut self) {
+        let start = self.previous_word_start();
+        self.command_input.drain(start..self.cursor_position);
+        self.cursor_position = start;
+    }
+
+    /// Start of the word before the cursor, skipping any separators directly to its left.
+    fn previous_word_start(&self) -> usize {
+        self.command_input[..self.cursor_position]
+            .char_indices()
+            .rev()
+            .skip_while(|(_, c)| !is_word_char(*c))
+            .take_while(|(_, c)| is_word_char(*c))
+            .last()
+            .map_or(0, |(i, _)| i)
+    }
+
     pub fn move_cursor_start(&mut self) {
         self.cursor_position = 0;
     }
@@ -163,13 +864,1019 @@ impl App {
         cmd
     }
 
+    /// Completes the path token under the cursor from the active tab's listing cache; repeated
+    /// calls cycle through the matches. An uncached directory is queued in
+    /// `pending_completion` for the client to list.
+    pub fn auto_complete(&mut self, forward: bool) {
+        if let Some(completion) = &mut self.completion
+            && completion.is_applied(&self.command_input, self.cursor_position)
+        {
+            let count = completion.candidates.len();
+            completion.index = if forward {
+                (completion.index + 1) % count
+            } else {
+                (completion.index + count - 1) % count
+            };
+            self.apply_completion();
+            return;
+        }
+
+        self.completion = None;
+        let Some((start, dir, prefix)) = path_token(&self.command_input, self.cursor_position)
+        else {
+            return;
+        };
+        let key = completion_dir(&self.tab().current_dir, dir);
+        let Some(entries) = self.tab().completion_cache.get(&key) else {
+            self.tab_mut().pending_completion = Some(key);
+            return;
+        };
+
+        let candidates: Vec<String> = entries
+            .iter()
+            .filter(|entry| entry.starts_with(prefix))
+            .map(|entry| format!("{}{}", dir, entry))
+            .collect();
+        if candidates.is_empty() {
+            return;
+        }
+
+        self.completion = Some(Completion {
+            start,
+            typed_len: self.cursor_position - start,
+            index: if forward { 0 } else { candidates.len() - 1 },
+            candidates,
+        });
+        self.apply_completion();
+    }
+
+    fn apply_completion(&mut self) {
+        let Some(completion) = &self.completion else {
+            return;
+        };
+        let candidate = completion.current().to_string();
+        self.command_input
+            .replace_range(completion.start..self.cursor_position, &candidate);
+        self.cursor_position = completion.start + candidate.len();
+        self.history_index = None;
+    }
+
+    /// Stores a listing for `dir` and retries the completion that was waiting on it.
+    pub fn cache_completions(&mut self, dir: String, entries: Vec<String>) {
+        self.tab_mut().completion_cache.insert(dir, entries);
+        self.auto_complete(true);
+    }
+
+    pub fn invalidate_completion_cache(&mut self, dir: &str) {
+        let key = completion_dir(dir, "");
+        self.tab_mut().completion_cache.remove(&key);
+    }
+
+    /// Drops the cached listings of whatever directories `command` changes.
+    pub fn invalidate_completions_for(&mut self, command: &str) {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        let targets = match words.as_slice() {
+            ["mv", from, to] => vec![*from, *to],
+            ["tar", dir] => vec![*dir],
+            ["tar" | "untar", _, dst] => vec![*dst],
+            ["upload" | "symlink" | "fetch", .., last] => vec![*last],
+            [
+                "write" | "create" | "rm" | "mkdir" | "rmdir" | "compress" | "decompress",
+                path,
+                ..,
+            ] => vec![*path],
+            _ => return,
+        };
+
+        for target in targets {
+            let path = bookmarks::absolute_path(&self.tab().current_dir, target);
+            let parent = match path.trim_end_matches('/').rsplit_once('/') {
+                Some(("", _)) | None => "/".to_string(),
+                Some((parent, _)) => parent.to_string(),
+            };
+            self.invalidate_completion_cache(&parent);
+        }
+    }
+
+    /// The part of the input the last completion filled in, for dimmed rendering.
+    pub fn completed_range(&self) -> Option<Range<usize>> {
+        self.completion
+            .as_ref()
+            .filter(|completion| completion.is_applied(&self.command_input, self.cursor_position))
+            .map(|completion| completion.start + completion.typed_len..self.cursor_position)
+    }
+
     pub fn tick(&mut self) {
         self.last_tick = Instant::now();
     }
+
+    pub fn open_palette(&mut self) {
+        self.palette_query.clear();
+        self.palette_selection = 0;
+        self.tab_mut().screen = Screen::CommandPalette;
+    }
+
+    pub fn close_palette(&mut self) {
+        self.tab_mut().screen = Screen::Command;
+    }
+
+    pub fn palette_matches(&self) -> Vec<(&'static str, &'static str)> {
+        if self.palette_query.is_empty() {
+            return known_commands();
+        }
+
+        let matcher = SkimMatcherV2::default();
+        let mut scored: Vec<_> = known_commands()
+            .into_iter()
+            .filter_map(|(cmd, desc)| {
+                matcher
+                    .fuzzy_match(cmd, &self.palette_query)
+                    .map(|score| (score, cmd, desc))
+            })
+            .collect();
+        scored.sort_by_key(|&(score, _, _)| std::cmp::Reverse(score));
+        scored
+            .into_iter()
+            .map(|(_, cmd, desc)| (cmd, desc))
+            .collect()
+    }
+
+    pub fn palette_insert_char(&mut self, c: char) {
+        self.palette_query.push(c);
+        self.palette_selection = 0;
+    }
+
+    pub fn palette_delete_char(&mut self) {
+        self.palette_query.pop();
+        self.palette_selection = 0;
+    }
+
+    pub fn palette_previous(&mut self) {
+        self.palette_selection = self.palette_selection.saturating_sub(1);
+    }
+
+    pub fn palette_next(&mut self) {
+        let count = self.palette_matches().len();
+        if self.palette_selection + 1 < count {
+            self.palette_selection += 1;
+        }
+    }
+
+    pub fn accept_palette(&mut self) {
+        if let Some((cmd, _)) = self.palette_matches().get(self.palette_selection) {
+            let name = cmd.split_whitespace().next().unwrap_or(cmd);
+            self.command_input = if name.len() < cmd.len() {
+                format!("{} ", name)
+            } else {
+                name.to_string()
+            };
+            self.cursor_position = self.command_input.len();
+            self.history_index = None;
+        }
+        self.close_palette();
+    }
+
+    /// Indices of the active tab's shown messages containing `query`, ignoring case.
+    pub fn match_messages(&self, query: &str) -> Vec<usize> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        self.tab()
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(_, msg)| self.visible_kinds.contains(&msg.kind))
+            .filter(|(_, msg)| !find_matches(&msg.content, query).is_empty())
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    pub fn open_search(&mut self) {
+        self.search_query = Some(String::new());
+        self.search_results.clear();
+        self.search_selection = 0;
+    }
+
+    pub fn close_search(&mut self) {
+        self.search_query = None;
+        self.search_results.clear();
+        self.search_selection = 0;
+    }
+
+    pub fn search_insert_char(&mut self, c: char) {
+        if let Some(query) = &mut self.search_query {
+            query.push(c);
+        }
+        self.refresh_search();
+        self.search_selection = 0;
+    }
+
+    pub fn search_delete_char(&mut self) {
+        if let Some(query) = &mut self.search_query {
+            query.pop();
+        }
+        self.refresh_search();
+        self.search_selection = 0;
+    }
+
+    /// Moves to the next match, wrapping around after the last one.
+    pub fn search_next(&mut self) {
+        self.refresh_search();
+        if !self.search_results.is_empty() {
+            self.search_selection = (self.search_selection + 1) % self.search_results.len();
+        }
+    }
+
+    pub fn search_previous(&mut self) {
+        self.refresh_search();
+        let count = self.search_results.len();
+        if count > 0 {
+            self.search_selection = (self.search_selection + count - 1) % count;
+        }
+    }
+
+    /// The index into `messages` of the match the search bar is on.
+    pub fn current_search_match(&self) -> Option<usize> {
+        self.search_results.get(self.search_selection).copied()
+    }
+
+    /// Opening the split view points both panes at the current directory and lists it.
+    pub fn toggle_split_pane(&mut self) {
+        self.split_pane = !self.split_pane;
+        self.context_menu = None;
+        self.last_click = None;
+        if !self.split_pane {
+            self.pending_pane_actions.clear();
+            return;
+        }
+
+        let current_dir = self.tab().current_dir.clone();
+        self.left_pane.change_dir(current_dir.clone());
+        self.right_pane.change_dir(current_dir);
+        self.active_pane = PaneIndex::Left;
+        self.pending_pane_actions = vec![
+            PaneAction::Refresh(PaneIndex::Left),
+            PaneAction::Refresh(PaneIndex::Right),
+        ];
+    }
+
+    pub fn pane(&self, index: PaneIndex) -> &PaneState {
+        match index {
+            PaneIndex::Left => &self.left_pane,
+            PaneIndex::Right => &self.right_pane,
+        }
+    }
+
+    pub fn pane_mut(&mut self, index: PaneIndex) -> &mut PaneState {
+        match index {
+            PaneIndex::Left => &mut self.left_pane,
+            PaneIndex::Right => &mut self.right_pane,
+        }
+    }
+
+    pub fn switch_pane(&mut self) {
+        self.active_pane = self.active_pane.other();
+    }
+
+    pub fn pane_move_selection(&mut self, delta: isize) {
+        self.pane_mut(self.active_pane).move_selection(delta);
+    }
+
+    /// Enters the selected directory of the focused pane; files are left alone.
+    pub fn pane_open_selected(&mut self) {
+        let active = self.active_pane;
+        let pane = self.pane_mut(active);
+        let Some(entry) = pane.selected_entry().filter(|entry| entry.is_namespace) else {
+            return;
+        };
+        let dir = pane.path_of(&entry.name);
+        pane.change_dir(dir);
+        self.pending_pane_actions.push(PaneAction::Refresh(active));
+    }
+
+    /// Focuses `pane` and selects its row `index`; a second click on the same row within
+    /// `DOUBLE_CLICK_INTERVAL` opens it.
+    pub fn click_pane_item(&mut self, pane: PaneIndex, index: usize, now: Instant) {
+        self.select_pane_item(pane, index);
+        let double_click = matches!(
+            self.last_click,
+            Some((at, last_pane, last_index))
+                if last_pane == pane
+                    && last_index == index
+                    && now.duration_since(at) <= DOUBLE_CLICK_INTERVAL
+        );
+        if double_click {
+            self.last_click = None;
+            self.pane_activate_selected();
+        } else {
+            self.last_click = Some((now, pane, index));
+        }
+    }
+
+    pub fn select_pane_item(&mut self, pane: PaneIndex, index: usize) {
+        self.active_pane = pane;
+        let pane = self.pane_mut(pane);
+        if index < pane.listing.len() {
+            pane.selected = index;
+        }
+    }
+
+    /// Enters the focused pane's selected directory, or reads its selected file.
+    pub fn pane_activate_selected(&mut self) {
+        match self.pane(self.active_pane).selected_entry() {
+            Some(entry) if entry.is_namespace => self.pane_open_selected(),
+            Some(_) => self.pending_pane_actions.push(PaneAction::Read),
+            None => {}
+        }
+    }
+
+    pub fn run_context_menu_item(&mut self, item: ContextMenuItem) {
+        self.context_menu = None;
+        let pane = self.pane(self.active_pane);
+        let Some(path) = pane.selected_entry().map(|entry| pane.path_of(&entry.name)) else {
+            self.warn("Nothing selected");
+            return;
+        };
+
+        match item {
+            ContextMenuItem::Open => self.pane_activate_selected(),
+            ContextMenuItem::Info => self.pending_pane_actions.push(PaneAction::Info),
+            ContextMenuItem::Delete => self.pending_pane_actions.push(PaneAction::Delete),
+            ContextMenuItem::CopyPath => match set_clipboard_text(path.clone()) {
+                Ok(()) => self.info(format!("Copied {} to clipboard", path)),
+                Err(e) => {
+                    tracing::warn!("Clipboard copy failed: {}", e);
+                    self.error(format!("Copy failed: {}", e));
+                }
+            },
+        }
+    }
+
+    pub fn pane_parent(&mut self) {
+        let active = self.active_pane;
+        let pane = self.pane_mut(active);
+        let parent = match pane.current_dir.trim_end_matches('/').rsplit_once('/') {
+            Some(("", _)) | None => "/".to_string(),
+            Some((parent, _)) => parent.to_string(),
+        };
+        if parent == pane.current_dir {
+            return;
+        }
+        pane.change_dir(parent);
+        self.pending_pane_actions.push(PaneAction::Refresh(active));
+    }
+
+    /// Queues a copy or move of the focused pane's selected file to the other pane.
+    pub fn queue_pane_transfer(&mut self, action: PaneAction) {
+        match self.pane(self.active_pane).selected_entry() {
+            Some(entry) if !entry.is_namespace => self.pending_pane_actions.push(action),
+            Some(_) => self.warn("Only files can be copied or moved between panes"),
+            None => self.warn("Nothing selected"),
+        }
+    }
+
+    // New messages arrive (and old ones are dropped) while the bar is open, so the results
+    // are recomputed before every move rather than trusted from the last keystroke.
+    fn refresh_search(&mut self) {
+        self.search_results = match &self.search_query {
+            Some(query) => self.match_messages(query),
+            None => Vec::new(),
+        };
+        if self.search_selection >= self.search_results.len() {
+            self.search_selection = 0;
+        }
+    }
+}
+
+/// Byte ranges of the non-overlapping, case-insensitive occurrences of `needle` in `haystack`.
+pub fn find_matches(haystack: &str, needle: &str) -> Vec<Range<usize>> {
+    let mut matches = Vec::new();
+    if needle.is_empty() {
+        return matches;
+    }
+
+    let mut from = 0;
+    while from < haystack.len() {
+        match match_len_at(&haystack[from..], needle) {
+            Some(len) => {
+                matches.push(from..from + len);
+                from += len;
+            }
+            None => {
+                from += haystack[from..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+    matches
+}
+
+/// Length in bytes of `haystack`'s prefix matching `needle` char by char, ignoring case.
+fn match_len_at(haystack: &str, needle: &str) -> Option<usize> {
+    let mut chars = haystack.char_indices();
+    for expected in needle.chars() {
+        let (_, actual) = chars.next()?;
+        if !actual.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.next().map_or(haystack.len(), |(index, _)| index))
 }
 
 impl Default for App {
     fn default() -> Self {
-        Self::new()
+        Self::new(ConnectionManager::default())
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Splits the argument before the cursor into its start offset, directory part (up to and
+/// including the last `/`) and the name prefix being typed. The command word is never a path.
+fn path_token(input: &str, cursor_position: usize) -> Option<(usize, &str, &str)> {
+    let before = &input[..cursor_position];
+    let start = before.rfind(char::is_whitespace)? + 1;
+    let token = &before[start..];
+    let (dir, prefix) = match token.rfind('/') {
+        Some(i) => token.split_at(i + 1),
+        None => ("", token),
+    };
+    Some((start, dir, prefix))
+}
+
+/// The cache key for `dir` typed relative to `current_dir`: absolute, without a trailing `/`.
+fn completion_dir(current_dir: &str, dir: &str) -> String {
+    let path = if dir.is_empty() {
+        current_dir.to_string()
+    } else {
+        bookmarks::absolute_path(current_dir, dir)
+    };
+    match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+#[cfg(feature = "clipboard")]
+fn clipboard_text() -> Result<String, String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "clipboard")]
+fn set_clipboard_text(text: String) -> Result<(), String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn clipboard_text() -> Result<String, String> {
+    Err("client was built without the clipboard feature".to_string())
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn set_clipboard_text(_text: String) -> Result<(), String> {
+    Err("client was built without the clipboard feature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_messages_ignores_case_and_hidden_kinds() {
+        let mut app = App::default();
+        app.tab_mut().messages.clear();
+        app.tab_mut().info("Connected to server");
+        app.tab_mut().error("Read failed: FILE not found");
+        app.tab_mut().success("Wrote file.txt");
+        app.tab_mut().info("nothing here");
+
+        assert_eq!(app.match_messages("file"), vec![1, 2]);
+        assert!(app.match_messages("").is_empty());
+        assert!(app.match_messages("missing").is_empty());
+
+        app.toggle_kind(MessageKind::Error);
+        assert_eq!(app.match_messages("FILE"), vec![2]);
+    }
+
+    fn entry(name: &str, is_namespace: bool) -> FenrisMetadata {
+        FenrisMetadata {
+            name: name.to_string(),
+            size: 0,
+            is_namespace,
+            modified_time: 0,
+            created_time: 0,
+            permissions: 0o644,
+        }
+    }
+
+    #[test]
+    fn split_pane_navigates_directories_and_queues_refreshes() {
+        let mut app = App::default();
+        app.tab_mut().current_dir = "/home".to_string();
+
+        app.toggle_split_pane();
+        assert_eq!(app.left_pane.current_dir, "/home");
+        assert_eq!(app.right_pane.current_dir, "/home");
+        assert_eq!(
+            std::mem::take(&mut app.pending_pane_actions),
+            vec![
+                PaneAction::Refresh(PaneIndex::Left),
+                PaneAction::Refresh(PaneIndex::Right)
+            ]
+        );
+
+        app.switch_pane();
+        app.right_pane
+            .set_listing(vec![entry("notes.txt", false), entry("docs", true)]);
+        app.pane_move_selection(5);
+        assert_eq!(app.right_pane.selected, 1);
+
+        app.pane_open_selected();
+        assert_eq!(app.right_pane.current_dir, "/home/docs");
+        assert!(app.right_pane.listing.is_empty());
+        app.pane_parent();
+        assert_eq!(app.right_pane.current_dir, "/home");
+        app.pane_parent();
+        assert_eq!(app.right_pane.current_dir, "/");
+        app.pane_parent();
+        assert_eq!(
+            app.pending_pane_actions,
+            vec![PaneAction::Refresh(PaneIndex::Right); 3]
+        );
+    }
+
+    #[test]
+    fn pane_transfers_are_only_queued_for_files() {
+        let mut app = App::default();
+        app.toggle_split_pane();
+        app.pending_pane_actions.clear();
+        app.left_pane
+            .set_listing(vec![entry("docs", true), entry("notes.txt", false)]);
+
+        app.queue_pane_transfer(PaneAction::Copy);
+        assert!(app.pending_pane_actions.is_empty());
+
+        app.pane_move_selection(1);
+        app.queue_pane_transfer(PaneAction::Move);
+        assert_eq!(app.pending_pane_actions, vec![PaneAction::Move]);
+    }
+
+    #[test]
+    fn double_clicks_open_dirs_and_read_files() {
+        let mut app = App::default();
+        app.toggle_split_pane();
+        app.pending_pane_actions.clear();
+        app.right_pane
+            .set_listing(vec![entry("docs", true), entry("notes.txt", false)]);
+        let start = Instant::now();
+
+        app.click_pane_item(PaneIndex::Right, 1, start);
+        assert_eq!(app.active_pane, PaneIndex::Right);
+        assert_eq!(app.right_pane.selected, 1);
+        assert!(app.pending_pane_actions.is_empty());
+
+        app.click_pane_item(PaneIndex::Right, 1, start + DOUBLE_CLICK_INTERVAL);
+        assert_eq!(app.pending_pane_actions, vec![PaneAction::Read]);
+
+        // Too slow for a double click: the second click only selects again.
+        app.pending_pane_actions.clear();
+        app.click_pane_item(PaneIndex::Right, 0, start);
+        app.click_pane_item(PaneIndex::Right, 0, start + Duration::from_secs(1));
+        assert!(app.pending_pane_actions.is_empty());
+
+        app.click_pane_item(PaneIndex::Right, 0, start + Duration::from_millis(1100));
+        assert_eq!(app.right_pane.current_dir, "/docs");
+        assert_eq!(
+            app.pending_pane_actions,
+            vec![PaneAction::Refresh(PaneIndex::Right)]
+        );
+    }
+
+    #[test]
+    fn context_menu_items_queue_actions_for_the_selection() {
+        let mut app = App::default();
+        app.toggle_split_pane();
+        app.pending_pane_actions.clear();
+        app.left_pane.set_listing(vec![entry("notes.txt", false)]);
+
+        app.context_menu = Some((4, 4));
+        app.run_context_menu_item(ContextMenuItem::Delete);
+        assert_eq!(app.context_menu, None);
+        assert_eq!(app.pending_pane_actions, vec![PaneAction::Delete]);
+
+        app.pending_pane_actions.clear();
+        app.run_context_menu_item(ContextMenuItem::Open);
+        assert_eq!(app.pending_pane_actions, vec![PaneAction::Read]);
+    }
+
+    #[test]
+    fn find_matches_returns_every_case_insensitive_occurrence() {
+        assert_eq!(find_matches("Error: error", "ERROR"), vec![0..5, 7..12]);
+        assert_eq!(find_matches("aaaa", "aa"), vec![0..2, 2..4]);
+        assert_eq!(find_matches("Ünïcode ünï", "ÜNÏ"), vec![0..5, 10..15]);
+        assert!(find_matches("abc", "").is_empty());
+    }
+
+    #[test]
+    fn search_navigation_wraps_in_both_directions() {
+        let mut app = App::default();
+        app.tab_mut().messages.clear();
+        for content in ["one match", "none", "two match", "three match"] {
+            app.tab_mut().info(content);
+        }
+
+        app.open_search();
+        for c in "match".chars() {
+            app.search_insert_char(c);
+        }
+        assert_eq!(app.search_results, vec![0, 2, 3]);
+        assert_eq!(app.current_search_match(), Some(0));
+
+        app.search_next();
+        assert_eq!(app.current_search_match(), Some(2));
+        app.search_previous();
+        app.search_previous();
+        assert_eq!(app.current_search_match(), Some(3));
+
+        app.close_search();
+        assert_eq!(app.search_query, None);
+        assert_eq!(app.current_search_match(), None);
+    }
+
+    #[test]
+    fn bookmarks_are_added_replaced_removed_and_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = App::default();
+        app.bookmarks.clear();
+        app.bookmarks_file = Some(dir.path().join(bookmarks::BOOKMARKS_FILE));
+
+        app.add_bookmark("logs", "/var/log".to_string()).unwrap();
+        app.add_bookmark("docs", "/docs".to_string()).unwrap();
+        app.add_bookmark("logs", "/srv/log".to_string()).unwrap();
+        assert_eq!(app.expand_bookmark("logs").as_deref(), Some("/srv/log"));
+        assert_eq!(app.bookmarks.len(), 2);
+
+        assert!(app.remove_bookmark("docs").unwrap());
+        assert!(!app.remove_bookmark("docs").unwrap());
+        assert_eq!(app.expand_bookmark("docs"), None);
+
+        let saved = bookmarks::load(app.bookmarks_file.as_ref().unwrap()).unwrap();
+        assert_eq!(saved, vec![("logs".to_string(), "/srv/log".to_string())]);
+    }
+
+    #[test]
+    fn workspace_round_trips_history_bookmarks_server_and_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(crate::workspace::WORKSPACE_FILE);
+        let mut app = App::default();
+        app.tab_mut().messages.clear();
+        app.tab_mut().server_addr = "files.example".to_string();
+        app.tab_mut().server_port = "7000".to_string();
+        app.add_to_history("ls /".to_string());
+        app.bookmarks = vec![("logs".to_string(), "/var/log".to_string())];
+        app.error("denied");
+        app.save_workspace(&path).unwrap();
+
+        let mut restored = App::default();
+        restored.tab_mut().messages.clear();
+        restored.load_workspace(&path).unwrap();
+
+        assert_eq!(restored.tab().server_addr, "files.example");
+        assert_eq!(restored.tab().server_port, "7000");
+        assert_eq!(restored.command_history, vec!["ls /".to_string()]);
+        assert_eq!(restored.bookmarks, app.bookmarks);
+        let message = restored.tab().messages.last().unwrap();
+        assert_eq!(
+            (message.kind, message.content.as_str()),
+            (MessageKind::Error, "denied")
+        );
+        assert!(!restored.tab().connected);
+        assert!(
+            restored
+                .load_workspace(&dir.path().join("missing"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn table_selection_starts_at_the_top_and_stays_in_range() {
+        let mut tab = TabState::new(ConnectionManager::default());
+        tab.move_table_selection(1);
+        assert_eq!(tab.table_selection, None);
+
+        let rows = vec![vec!["a".to_string()], vec!["b".to_string()]];
+        tab.show_table(Some((vec!["Name".to_string()], rows)));
+        tab.move_table_selection(1);
+        assert_eq!(tab.table_selection, Some(0));
+        tab.move_table_selection(5);
+        assert_eq!(tab.table_selection, Some(1));
+        tab.move_table_selection(-5);
+        assert_eq!(tab.table_selection, Some(0));
+
+        tab.show_table(None);
+        assert_eq!(tab.table_selection, None);
+    }
+
+    #[test]
+    fn message_filters_hide_toggled_kinds() {
+        let mut app = App::default();
+        app.tab_mut().messages.clear();
+        app.info("listing");
+        app.error("denied");
+        app.tab_mut().success("written");
+        app.warn("slow");
+
+        let kinds = |app: &App| {
+            app.tab()
+                .messages
+                .iter()
+                .map(|m| m.kind)
+                .filter(|kind| app.visible_kinds.contains(kind))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(kinds(&app).len(), 4);
+
+        app.toggle_kind(MessageKind::Info);
+        app.toggle_kind(MessageKind::Success);
+        assert_eq!(kinds(&app), vec![MessageKind::Error, MessageKind::Warning]);
+        app.toggle_kind(MessageKind::Success);
+        assert!(kinds(&app).contains(&MessageKind::Success));
+
+        app.show_only_errors();
+        assert_eq!(kinds(&app), vec![MessageKind::Error]);
+        app.show_all_kinds();
+        assert_eq!(kinds(&app).len(), 4);
+    }
+
+    fn app_with_input(input: &str, cursor_position: usize) -> App {
+        App {
+            command_input: input.to_string(),
+            cursor_position,
+            ..App::default()
+        }
+    }
+
+    #[test]
+    fn word_left_stops_at_the_start_of_the_previous_word() {
+        let cases = [
+            ("read some_file.txt", 18, 15),
+            ("read some_file.txt", 14, 5),
+            ("read some_file.txt", 8, 5),
+            ("read some_file.txt", 5, 0),
+            ("read   docs", 7, 0),
+            ("read   docs", 0, 0),
+            ("  ", 2, 0),
+        ];
+
+        for (input, from, expected) in cases {
+            let mut app = app_with_input(input, from);
+            app.move_cursor_word_left();
+            assert_eq!(app.cursor_position, expected, "{:?} from {}", input, from);
+        }
+    }
+
+    #[test]
+    fn word_right_stops_at_the_end_of_the_next_word() {
+        let cases = [
+            ("read some_file.txt", 0, 4),
+            ("read some_file.txt", 4, 14),
+            ("read some_file.txt", 7, 14),
+            ("read some_file.txt", 14, 18),
+            ("read   docs", 4, 11),
+            ("read   docs", 11, 11),
+            ("  ", 0, 2),
+        ];
+
+        for (input, from, expected) in cases {
+            let mut app = app_with_input(input, from);
+            app.move_cursor_word_right();
+            assert_eq!(app.cursor_position, expected, "{:?} from {}", input, from);
+        }
+    }
+
+    #[test]
+    fn delete_word_left_removes_the_previous_word_and_separators() {
+        let mut app = app_with_input("cd docs  ", 9);
+        app.delete_word_left();
+        assert_eq!(app.command_input, "cd ");
+        assert_eq!(app.cursor_position, 3);
+
+        let mut app = app_with_input("cd docs", 5);
+        app.delete_word_left();
+        assert_eq!(app.command_input, "cd cs");
+        assert_eq!(app.cursor_position, 3);
+
+        let mut app = app_with_input("cd", 0);
+        app.delete_word_left();
+        assert_eq!(app.command_input, "cd");
+        assert_eq!(app.cursor_position, 0);
+    }
+
+    #[test]
+    fn pasted_text_goes_to_the_focused_input() {
+        let mut app = app_with_input("cat ", 4);
+        app.tab_mut().screen = Screen::Command;
+        app.insert_text("notes.txt\n");
+        assert_eq!(app.command_input, "cat notes.txt");
+        assert_eq!(app.cursor_position, 13);
+
+        let mut app = app_with_input("cat ", 4);
+        app.tab_mut().screen = Screen::Command;
+        app.insert_text("café.txt");
+        assert_eq!(app.cursor_position, app.command_input.len());
+        app.move_cursor_word_left();
+        app.move_cursor_word_right();
+        app.move_cursor_left();
+        app.move_cursor_left();
+        app.move_cursor_left();
+        app.move_cursor_left();
+        app.delete_char();
+        app.insert_char('è');
+        app.move_cursor_right();
+        assert_eq!(app.command_input, "cat cafè.txt");
+        assert_eq!(app.cursor_position, "cat cafè.".len());
+
+        let mut app = App::default();
+        app.tab_mut().connection_focus = ConnectionFocus::Port;
+        app.tab_mut().server_port.clear();
+        app.insert_text("80a80");
+        assert_eq!(app.tab().server_port, "8080");
+        assert!(app.command_input.is_empty());
+    }
+
+    #[test]
+    fn tab_completion_lists_then_cycles_matching_entries() {
+        let mut app = app_with_input("cat docs/re", 11);
+        app.tab_mut().current_dir = "/home".to_string();
+
+        app.auto_complete(true);
+        assert_eq!(app.tab().pending_completion.as_deref(), Some("/home/docs"));
+        assert_eq!(app.command_input, "cat docs/re");
+
+        let entries = ["readme.md", "notes.txt", "reports/"];
+        app.cache_completions(
+            "/home/docs".to_string(),
+            entries.iter().map(|e| e.to_string()).collect(),
+        );
+        assert_eq!(app.command_input, "cat docs/readme.md");
+        assert_eq!(app.completed_range(), Some(11..18));
+
+        app.auto_complete(true);
+        assert_eq!(app.command_input, "cat docs/reports/");
+        app.auto_complete(true);
+        assert_eq!(app.command_input, "cat docs/readme.md");
+        app.auto_complete(false);
+        assert_eq!(app.command_input, "cat docs/reports/");
+
+        app.insert_char('x');
+        assert_eq!(app.completed_range(), None);
+
+        app.invalidate_completions_for("rm docs/readme.md");
+        assert!(app.tab().completion_cache.is_empty());
+    }
+
+    #[test]
+    fn tab_completion_skips_the_command_word() {
+        let mut app = app_with_input("re", 2);
+        app.auto_complete(true);
+
+        assert_eq!(app.tab().pending_completion, None);
+        assert_eq!(app.command_input, "re");
+    }
+
+    #[test]
+    fn copying_with_no_messages_warns() {
+        let mut app = App::default();
+        app.copy_last_response();
+
+        let message = app.tab().messages.last().unwrap();
+        assert_eq!(message.kind, MessageKind::Warning);
+    }
+
+    #[test]
+    fn messages_go_to_the_active_tab() {
+        let mut app = App::default();
+        app.info("first");
+        app.open_tab(ConnectionManager::default());
+        app.tab_mut().error("second");
+
+        assert_eq!(app.tabs[0].messages.len(), 1);
+        assert_eq!(app.tabs[1].messages[0].content, "second");
+        assert_eq!(app.tab().screen, Screen::Connection);
+    }
+
+    #[test]
+    fn connection_target_rejects_invalid_fields() {
+        let mut tab = TabState::new(ConnectionManager::default());
+        tab.server_port = "0".to_string();
+        assert_eq!(tab.connection_target(), None);
+        assert_eq!(tab.connection_focus, ConnectionFocus::Port);
+        assert!(tab.connection_error.is_some());
+
+        tab.server_port = "8080".to_string();
+        tab.server_addr = "  ".to_string();
+        tab.toggle_connection_focus();
+        assert_eq!(tab.connection_target(), None);
+        assert_eq!(tab.connection_focus, ConnectionFocus::Address);
+
+        tab.server_addr = "fenris local".to_string();
+        tab.toggle_connection_focus();
+        assert_eq!(tab.connection_target(), None);
+        assert_eq!(tab.connection_focus, ConnectionFocus::Address);
+        assert_eq!(
+            tab.connection_error.as_deref(),
+            Some("Server address cannot contain ' '")
+        );
+
+        tab.server_addr = "::1".to_string();
+        assert_eq!(tab.connection_target(), Some(("::1".to_string(), 8080)));
+
+        tab.server_addr = "fenris.local".to_string();
+        assert_eq!(
+            tab.connection_target(),
+            Some(("fenris.local".to_string(), 8080))
+        );
+        assert_eq!(tab.connection_error, None);
+    }
+
+    #[test]
+    fn warnings_are_their_own_message_kind() {
+        let mut app = App::default();
+        app.warn("watch poll failed");
+
+        let message = app.tab().messages.last().unwrap();
+        assert_eq!(message.kind, MessageKind::Warning);
+        assert_eq!(message.content, "watch poll failed");
+    }
+
+    #[test]
+    fn next_tab_wraps_around() {
+        let mut app = App::default();
+        app.open_tab(ConnectionManager::default());
+        app.open_tab(ConnectionManager::default());
+        assert_eq!(app.active_tab, 2);
+
+        app.next_tab();
+        assert_eq!(app.active_tab, 0);
+        app.next_tab();
+        assert_eq!(app.active_tab, 1);
+    }
+
+    #[test]
+    fn closing_tabs_keeps_active_index_valid() {
+        let mut app = App::default();
+        app.tab_mut().server_addr = String::from("first");
+        app.open_tab(ConnectionManager::default());
+        app.tab_mut().server_addr = String::from("second");
+
+        let closed = app.close_active_tab(ConnectionManager::default());
+        assert_eq!(closed.server_addr, "second");
+        assert_eq!(app.active_tab, 0);
+        assert_eq!(app.tab_labels(), vec!["first:5555"]);
+
+        let closed = app.close_active_tab(ConnectionManager::default());
+        assert_eq!(closed.server_addr, "first");
+        assert_eq!(app.tabs.len(), 1);
+        assert_eq!(app.tab().server_addr, "127.0.0.1");
+    }
+
+    #[test]
+    fn palette_filters_and_pastes_selected_command() {
+        let mut app = App::default();
+        app.open_palette();
+        assert_eq!(app.tab().screen, Screen::CommandPalette);
+        assert_eq!(app.palette_matches().len(), known_commands().len());
+
+        for c in "mkd".chars() {
+            app.palette_insert_char(c);
+        }
+        assert_eq!(app.palette_matches()[0].0, "mkdir [-p] <dir>");
+
+        app.palette_next();
+        app.palette_previous();
+        app.accept_palette();
+        assert_eq!(app.command_input, "mkdir ");
+        assert_eq!(app.cursor_position, 6);
+        assert_eq!(app.tab().screen, Screen::Command);
+    }
+
+    #[test]
+    fn palette_without_matches_leaves_input_untouched() {
+        let mut app = App::default();
+        app.insert_char('l');
+        app.insert_char('s');
+        app.open_palette();
+        app.palette_insert_char('#');
+
+        assert!(app.palette_matches().is_empty());
+        app.accept_palette();
+        assert_eq!(app.command_input, "ls");
+        assert_eq!(app.tab().screen, Screen::Command);
     }
 }
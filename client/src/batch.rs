@@ -1,18 +1,27 @@
 use anyhow::Result;
 use clap::ValueEnum;
+#[cfg(feature = "tls")]
+use common::TlsClientConfig;
 use common::{FenrisError, ServerIdentityPublicKey};
 use serde::Serialize;
 use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
 
-use crate::connection_manager::{ConnectionManager, ServerInfo};
-use crate::response_manager::FormattedResponse;
+use crate::connection_manager::{ConnectionManager, Credentials, ServerInfo};
+use crate::response_manager::{DetailsFormat, FormattedResponse};
 
 #[derive(Debug, Clone)]
 pub struct BatchConfig {
     pub address: String,
     pub port: u16,
+    pub unix_socket: Option<PathBuf>,
     pub commands: Vec<String>,
     pub output: BatchOutputFormat,
+    pub psk: Option<String>,
+    pub credentials: Option<Credentials>,
+    #[cfg(feature = "tls")]
+    pub tls: Option<TlsClientConfig>,
+    pub pipeline: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
@@ -54,15 +63,29 @@ pub async fn run_batch(
     server_identity: ServerIdentityPublicKey,
 ) -> Result<BatchSummary> {
     let mut manager = ConnectionManager::with_server_identity(
-        crate::request_manager::RequestManager,
-        crate::response_manager::ResponseManager,
+        crate::request_manager::RequestManager::default(),
+        crate::response_manager::ResponseManager::default(),
         server_identity,
     );
     manager.set_server_info(ServerInfo::new(config.address, config.port))?;
-    manager.connect().await?;
+    if let Some(psk) = config.psk {
+        manager.set_psk(psk)?;
+    }
+    if let Some(credentials) = config.credentials {
+        manager.set_credentials(credentials)?;
+    }
+    #[cfg(feature = "tls")]
+    if let Some(tls) = config.tls {
+        manager.set_tls(tls)?;
+    }
+    manager.connect_via(config.unix_socket.as_deref()).await?;
 
     let mut stdout = io::stdout().lock();
-    let summary = run_commands(&mut manager, &config.commands, config.output, &mut stdout).await;
+    let summary = if config.pipeline {
+        run_pipelined(&mut manager, &config.commands, config.output, &mut stdout).await
+    } else {
+        run_commands(&mut manager, &config.commands, config.output, &mut stdout).await
+    };
 
     manager.disconnect().await;
     summary
@@ -105,6 +128,8 @@ async fn run_commands<W: Write>(
                             message: error.to_string(),
                             details: None,
                             current_dir: None,
+                            details_format: DetailsFormat::Plain,
+                            table_data: None,
                         },
                     },
                 )?;
@@ -124,7 +149,38 @@ async fn run_commands<W: Write>(
     })
 }
 
-fn should_abort(error: &FenrisError) -> bool {
+async fn run_pipelined<W: Write>(
+    manager: &mut ConnectionManager,
+    commands: &[String],
+    output: BatchOutputFormat,
+    writer: &mut W,
+) -> Result<BatchSummary> {
+    let command_refs: Vec<&str> = commands.iter().map(String::as_str).collect();
+    let responses = manager.send_concurrent(&command_refs).await?;
+
+    let mut failed = 0;
+    for (command, response) in commands.iter().zip(responses) {
+        if !response.success {
+            failed += 1;
+        }
+        write_result(
+            writer,
+            output,
+            &BatchCommandResult {
+                command: command.clone(),
+                response,
+            },
+        )?;
+    }
+
+    Ok(BatchSummary {
+        total: commands.len(),
+        failed,
+        aborted: false,
+    })
+}
+
+pub(crate) fn should_abort(error: &FenrisError) -> bool {
     matches!(
         error,
         FenrisError::ConnectionClosed | FenrisError::NetworkError(_)
@@ -224,6 +280,8 @@ mod tests {
                 message: "File content (5 bytes):".to_string(),
                 details: Some("hello".to_string()),
                 current_dir: None,
+                details_format: DetailsFormat::Plain,
+                table_data: None,
             },
         };
         let mut output = Vec::new();
@@ -245,6 +303,8 @@ mod tests {
                 message: "Changed directory to /tmp".to_string(),
                 details: None,
                 current_dir: Some("/tmp".to_string()),
+                details_format: DetailsFormat::Plain,
+                table_data: None,
             },
         };
         let mut output = Vec::new();
@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const BOOKMARKS_FILE: &str = ".fenris_bookmarks.toml";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BookmarkFile {
+    #[serde(default)]
+    bookmarks: Vec<Bookmark>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Bookmark {
+    alias: String,
+    path: String,
+}
+
+pub fn bookmarks_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(BOOKMARKS_FILE))
+}
+
+/// A missing file is an empty bookmark list.
+pub fn load(path: &Path) -> Result<Vec<(String, String)>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let file: BookmarkFile =
+        toml::from_str(&contents).with_context(|| format!("failed to parse {}", path.display()))?;
+
+    Ok(file
+        .bookmarks
+        .into_iter()
+        .map(|bookmark| (bookmark.alias, bookmark.path))
+        .collect())
+}
+
+pub fn save(path: &Path, bookmarks: &[(String, String)]) -> Result<()> {
+    let file = BookmarkFile {
+        bookmarks: bookmarks
+            .iter()
+            .map(|(alias, path)| Bookmark {
+                alias: alias.clone(),
+                path: path.clone(),
+            })
+            .collect(),
+    };
+    std::fs::write(path, toml::to_string(&file)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Relative paths are taken from `current_dir`, so a bookmark always names an absolute
+/// server path.
+pub fn absolute_path(current_dir: &str, path: &str) -> String {
+    if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("{}/{}", current_dir.trim_end_matches('/'), path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bookmarks_survive_a_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(BOOKMARKS_FILE);
+        assert!(load(&path).unwrap().is_empty());
+
+        let bookmarks = vec![
+            ("logs".to_string(), "/var/log".to_string()),
+            ("home".to_string(), "/home/me".to_string()),
+        ];
+        save(&path, &bookmarks).unwrap();
+
+        assert_eq!(load(&path).unwrap(), bookmarks);
+    }
+
+    #[test]
+    fn absolute_path_resolves_against_current_dir() {
+        assert_eq!(absolute_path("/", "docs"), "/docs");
+        assert_eq!(absolute_path("/home", "docs"), "/home/docs");
+        assert_eq!(absolute_path("/home", "/etc"), "/etc");
+    }
+}
@@ -1,57 +1,188 @@
 use anyhow::Result;
-use common::ServerIdentityPublicKey;
+use chrono::Local;
+#[cfg(feature = "tls")]
+use common::TlsClientConfig;
+use common::{FenrisCommand, FenrisOutput, ListSort, ServerIdentityPublicKey, TransferChunk};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 
 use crate::{
-    app::{App, Screen},
-    connection_manager::{ConnectionManager, ServerInfo},
-    ui,
+    app::{App, MessageKind, PaneAction, PaneIndex, Screen, TabState},
+    bookmarks,
+    connection_manager::{ConnectionManager, Credentials, HealthEvent, ServerInfo},
+    request_manager::{ClientCommandPlan, DefaultRequestBuilder, RequestManager, split_pipeline},
+    response_manager::{DetailsFormat, FormattedResponse, ResponseManager},
+    script, session_log,
+    transfers::{self, TransferDirection, TransferRecord},
+    ui, workspace,
 };
 
+const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const TAILF_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const TAILF_BYTES: u64 = 4096;
+const HEALTH_CHECK_FAILURE_THRESHOLD: u32 = 3;
+
 pub struct TuiClient {
     app: App,
-    connection_manager: ConnectionManager,
+    server_identity: Option<ServerIdentityPublicKey>,
+    psk: Option<String>,
+    credentials: Option<Credentials>,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsClientConfig>,
+    keepalive: Option<Duration>,
+    health_check: Option<Duration>,
 }
 
 impl TuiClient {
     pub fn new() -> Self {
-        Self {
-            app: App::new(),
-            connection_manager: ConnectionManager::default(),
-        }
+        Self::with_identity(None)
     }
 
     pub fn with_server_identity(server_identity: ServerIdentityPublicKey) -> Self {
+        Self::with_identity(Some(server_identity))
+    }
+
+    fn with_identity(server_identity: Option<ServerIdentityPublicKey>) -> Self {
         Self {
-            app: App::new(),
-            connection_manager: ConnectionManager::with_server_identity(
-                crate::request_manager::RequestManager,
-                crate::response_manager::ResponseManager,
-                server_identity,
-            ),
+            app: App::new(build_connection_manager(server_identity, None, None, None)),
+            server_identity,
+            psk: None,
+            credentials: None,
+            #[cfg(feature = "tls")]
+            tls: None,
+            keepalive: None,
+            health_check: None,
         }
     }
 
+    pub fn set_psk(&mut self, psk: String) -> Result<()> {
+        for tab in &mut self.app.tabs {
+            tab.connection_manager.set_psk(psk.clone())?;
+        }
+        self.psk = Some(psk);
+        Ok(())
+    }
+
+    pub fn set_credentials(&mut self, credentials: Credentials) -> Result<()> {
+        for tab in &mut self.app.tabs {
+            tab.connection_manager
+                .set_credentials(credentials.clone())?;
+        }
+        self.credentials = Some(credentials);
+        Ok(())
+    }
+
+    /// Runs every tab's TCP connections over TLS, including tabs opened later.
+    #[cfg(feature = "tls")]
+    pub fn set_tls(&mut self, tls: TlsClientConfig) -> Result<()> {
+        for tab in &mut self.app.tabs {
+            tab.connection_manager.set_tls(tls.clone())?;
+        }
+        self.tls = Some(tls);
+        Ok(())
+    }
+
+    pub fn set_keepalive(&mut self, interval: Duration) {
+        for tab in &mut self.app.tabs {
+            tab.connection_manager.enable_keepalive(interval);
+        }
+        self.keepalive = Some(interval);
+    }
+
+    /// Logs the session to `path`, or to `~/.fenris_sessions/<timestamp>.log` without one.
+    pub fn set_session_log(&mut self, path: Option<PathBuf>, redact: bool) -> Result<()> {
+        let path = match path.or_else(session_log::default_session_log_path) {
+            Some(path) => path,
+            None => anyhow::bail!("HOME is not set; pass a path to --session-log"),
+        };
+        self.app.start_session_log(&path)?;
+        self.app.redact_session_log = redact;
+        Ok(())
+    }
+
+    /// Lets clicks select and open split-view entries; the terminal must capture the mouse.
+    pub fn set_mouse_enabled(&mut self, enabled: bool) {
+        self.app.mouse_enabled = enabled;
+    }
+
+    /// Pings every `interval` after each connect and reconnects after three failed pings.
+    pub fn set_health_check(&mut self, interval: Duration) {
+        self.health_check = Some(interval);
+    }
+
+    fn new_connection_manager(&self) -> ConnectionManager {
+        #[cfg_attr(not(feature = "tls"), allow(unused_mut))]
+        let mut manager = build_connection_manager(
+            self.server_identity,
+            self.psk.clone(),
+            self.credentials.clone(),
+            self.keepalive,
+        );
+        #[cfg(feature = "tls")]
+        if let Some(tls) = self.tls.clone() {
+            manager
+                .set_tls(tls)
+                .expect("new connection manager cannot already be connected");
+        }
+        manager
+    }
+
     pub async fn run(&mut self, terminal: &mut ui::terminal::Tui) -> Result<()> {
         self.app.info("Welcome to Fenris Client!");
         self.app.info("Press F1 for help, Ctrl+C to quit.");
+        if let Some(path) = workspace::workspace_path()
+            && path.exists()
+        {
+            match self.app.load_workspace(&path) {
+                Ok(()) => self
+                    .app
+                    .info("Restored the last workspace; connect to resume"),
+                Err(e) => self.app.warn(format!("Workspace not restored: {:#}", e)),
+            }
+        }
 
         loop {
             terminal.draw(|frame| ui::render(frame, &self.app))?;
 
-            if let Some(crossterm::event::Event::Key(key)) =
-                ui::poll_events(Duration::from_millis(100))?
-                && key.kind == crossterm::event::KeyEventKind::Press
-            {
-                self.handle_key_event(key).await?;
+            match ui::poll_events(Duration::from_millis(100))? {
+                Some(crossterm::event::Event::Key(key))
+                    if key.kind == crossterm::event::KeyEventKind::Press =>
+                {
+                    self.handle_key_event(key).await?;
+                }
+                Some(crossterm::event::Event::Mouse(mouse)) => {
+                    ui::handle_mouse_event(&mut self.app, mouse);
+                }
+                _ => {}
             }
 
+            for tab in &mut self.app.tabs {
+                poll_watch_events(tab).await;
+                poll_tailf(tab).await;
+                poll_keepalive(tab).await;
+                poll_health_check(tab).await;
+                drain_watch_events(tab);
+                drain_broadcasts(tab);
+            }
+            fetch_completions(&mut self.app).await;
+            run_pane_actions(&mut self.app).await;
             self.app.tick();
 
             if self.app.should_quit {
-                if self.connection_manager.is_connected() {
-                    self.connection_manager.disconnect().await;
+                for tab in &mut self.app.tabs {
+                    if tab.connection_manager.is_connected() {
+                        tab.connection_manager.disconnect().await;
+                    }
+                }
+                if let Some(path) = workspace::workspace_path()
+                    && let Err(e) = self.app.save_workspace(&path)
+                {
+                    tracing::warn!("Workspace not saved: {:#}", e);
+                }
+                if let Err(e) = self.app.close_session_log() {
+                    tracing::warn!("Session log not flushed: {}", e);
                 }
                 break;
             }
@@ -60,25 +191,63 @@ impl TuiClient {
     }
 
     async fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
-        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
-            self.app.should_quit = true;
-            return Ok(());
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('c') => {
+                    self.app.should_quit = true;
+                    return Ok(());
+                }
+                KeyCode::Char('t') => {
+                    let manager = self.new_connection_manager();
+                    self.app.open_tab(manager);
+                    return Ok(());
+                }
+                KeyCode::Char('w') => {
+                    self.close_active_tab().await;
+                    return Ok(());
+                }
+                _ => {}
+            }
         }
 
-        match self.app.screen {
+        match self.app.tab().screen {
             Screen::Connection => {
                 if key.code == KeyCode::Enter {
                     self.handle_connect().await?;
                     return Ok(());
                 }
             }
-            Screen::Command => {
-                if key.code == KeyCode::Enter && !self.app.command_input.is_empty() {
+            Screen::Command | Screen::Help => {
+                // The search bar takes every key until Esc closes it.
+                if self.app.search_query.is_some() {
+                    ui::handle_key_event(&mut self.app, key)?;
+                    return Ok(());
+                }
+                // The split view spends Tab on moving between its panes.
+                if key.code == KeyCode::Tab
+                    && self.app.split_pane
+                    && self.app.tab().screen == Screen::Command
+                    && self.app.command_input.is_empty()
+                {
+                    self.app.switch_pane();
+                    return Ok(());
+                }
+                // With something typed, Tab completes paths instead of switching tabs.
+                if key.code == KeyCode::Tab
+                    && (self.app.tab().screen == Screen::Help || self.app.command_input.is_empty())
+                {
+                    self.app.next_tab();
+                    return Ok(());
+                }
+                if self.app.tab().screen == Screen::Command
+                    && key.code == KeyCode::Enter
+                    && !self.app.command_input.is_empty()
+                {
                     self.handle_command().await?;
                     return Ok(());
                 }
             }
-            Screen::Help => { /* No async actions needed here */ }
+            Screen::CommandPalette => {}
         }
 
         ui::handle_key_event(&mut self.app, key)?;
@@ -86,35 +255,54 @@ impl TuiClient {
         Ok(())
     }
 
+    async fn close_active_tab(&mut self) {
+        let replacement = self.new_connection_manager();
+        let mut closed = self.app.close_active_tab(replacement);
+        if closed.connection_manager.is_connected() {
+            closed.connection_manager.disconnect().await;
+        }
+        self.app.info(format!("Closed tab {}", closed.label()));
+    }
+
     async fn handle_connect(&mut self) -> Result<()> {
-        let address = self.app.server_addr.trim().to_string();
-        let port: u16 = match self.app.server_port.trim().parse() {
-            Ok(p) => p,
-            Err(_) => {
-                self.app.error("Invalid port number");
-                return Ok(());
-            }
+        let health_check = self.health_check;
+        let tab = self.app.tab_mut();
+        let Some((address, port)) = tab.connection_target() else {
+            return Ok(());
         };
 
-        self.app
-            .info(format!("Connecting to {}:{}...", address, port));
-        if let Err(e) = self
+        tab.info(format!("Connecting to {}:{}...", address, port));
+        if let Err(e) = tab
             .connection_manager
             .set_server_info(ServerInfo::new(address.clone(), port))
         {
-            self.app.error(format!("Failed to set server info: {}", e));
+            tab.error(format!("Failed to set server info: {}", e));
             return Ok(());
         }
-        match self.connection_manager.connect().await {
+        match tab.connection_manager.connect().await {
             Ok(()) => {
-                self.app.connected = true;
-                self.app
-                    .success(format!("Connected to {}:{}", address, port));
-                self.app.screen = Screen::Command;
+                tab.connected = true;
+                tab.connected_at = Some(Instant::now());
+                tab.reconnecting = false;
+                tab.completion_cache.clear();
+                if let Some(interval) = health_check {
+                    // Dropping the handle detaches the timer; disconnect() still stops it.
+                    drop(
+                        tab.connection_manager
+                            .start_health_check(interval, HEALTH_CHECK_FAILURE_THRESHOLD),
+                    );
+                }
+                tab.success(format!("Connected to {}:{}", address, port));
+                if let Some(motd) = tab.connection_manager.server_motd().map(str::to_string) {
+                    tab.info(motd);
+                }
+                tab.screen = Screen::Command;
             }
             Err(e) => {
-                self.app.connected = false;
-                self.app.error(format!("Connection failed: {}", e));
+                tab.connected = false;
+                tab.connected_at = None;
+                tab.connection_error = Some(format!("Connection failed: {}", e));
+                tab.error(format!("Connection failed: {}", e));
             }
         }
 
@@ -123,58 +311,741 @@ impl TuiClient {
 
     async fn handle_command(&mut self) -> Result<()> {
         let command = self.app.take_command();
-        self.app.add_to_history(command.clone());
+        let shown = redact_password(&command);
+        self.app.add_to_history(shown.clone());
+        self.app.log_command(&shown);
 
-        self.app.info(format!("> {}", command));
+        let tab = self.app.tab_mut();
+        tab.info(format!("> {}", shown));
 
-        if command.trim() == "exit" || command.trim() == "quit" {
-            self.app.info("Disconnecting...");
-            self.connection_manager.disconnect().await;
-            self.app.connected = false;
-            self.app.screen = Screen::Connection;
+        if command.trim() == "help" {
+            tab.screen = Screen::Help;
             return Ok(());
         }
 
-        if command.trim() == "clear" {
-            self.app.messages.clear();
+        if command.split_whitespace().eq(["theme", "reload"]) {
+            let result = self.app.reload_theme();
+            let tab = self.app.tab_mut();
+            match result {
+                Ok(()) => tab.success("Theme reloaded"),
+                Err(e) => tab.warn(format!("Theme reload failed: {:#}", e)),
+            }
             return Ok(());
         }
 
-        if command.trim() == "help" {
-            self.app.screen = Screen::Help;
-            return Ok(());
+        let words: Vec<&str> = command.split_whitespace().collect();
+        match words.as_slice() {
+            ["history", "transfers"] => {
+                self.show_transfer_history();
+                return Ok(());
+            }
+            ["history", "transfers", "clear"] => {
+                let result = self.app.clear_transfer_history();
+                let tab = self.app.tab_mut();
+                match result {
+                    Ok(()) => tab.success("Transfer history cleared"),
+                    Err(e) => tab.error(format!("Failed to clear transfer history: {:#}", e)),
+                }
+                return Ok(());
+            }
+            ["bookmark", args @ ..] => {
+                self.handle_bookmark(args);
+                return Ok(());
+            }
+            ["workspace", args @ ..] => {
+                self.handle_workspace(args);
+                return Ok(());
+            }
+            ["session-log", args @ ..] if args.len() <= 1 => {
+                self.handle_session_log(args.first().copied());
+                return Ok(());
+            }
+            _ => {}
         }
 
-        match self.connection_manager.send_command(&command).await {
-            Ok(formatted) => {
-                if formatted.success {
-                    self.app.success(formatted.message);
-                } else {
-                    self.app.error(formatted.message);
+        let command = match words.as_slice() {
+            ["go", alias] => match self.app.expand_bookmark(alias) {
+                Some(path) => format!("cd {}", path),
+                None => {
+                    self.app
+                        .tab_mut()
+                        .error(format!("Unknown bookmark: {}", alias));
+                    return Ok(());
                 }
+            },
+            _ => command.clone(),
+        };
 
-                if let Some(details) = formatted.details {
-                    for line in details.lines() {
-                        self.app.info(line.to_string());
+        let tab = self.app.tab_mut();
+        let mut parts = command.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (Some("watch"), Some(path)) => {
+                handle_watch(tab, path).await;
+                return Ok(());
+            }
+            (Some("unwatch"), Some(path)) => {
+                handle_unwatch(tab, path).await;
+                return Ok(());
+            }
+            (Some("tailf"), path) => {
+                handle_tailf(tab, path);
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        // A redirect only changes where the output goes, so look through it.
+        let plan = DefaultRequestBuilder
+            .build_request(&command)
+            .map(|plan| plan.into_parts().0);
+        let shows_file_contents = plan.as_ref().is_ok_and(session_log::carries_file_contents);
+        let upload = match plan {
+            Ok(ClientCommandPlan::LocalScript {
+                path,
+                ignore_errors,
+            }) => {
+                handle_script(tab, &path, ignore_errors).await;
+                return Ok(());
+            }
+            Ok(ClientCommandPlan::ClearMessages) => {
+                tab.messages.clear();
+                tab.show_table(None);
+                return Ok(());
+            }
+            Ok(ClientCommandPlan::Single(FenrisCommand::Login { .. })) => {
+                if tab.connection_manager.is_connected() {
+                    end_session(tab).await;
+                }
+                match tab.connection_manager.set_login(&command) {
+                    Ok(credentials) => {
+                        tab.info(format!("Logging in as {}", credentials.username));
+                        return self.handle_connect().await;
+                    }
+                    Err(e) => {
+                        tab.error(format!("Login failed: {}", e));
+                        return Ok(());
                     }
                 }
-
-                if let Some(ref dir) = formatted.current_dir {
-                    self.app.current_dir = dir.clone();
+            }
+            Ok(ClientCommandPlan::Single(FenrisCommand::Terminate)) => {
+                end_session(tab).await;
+                // `logout` returns to the connection screen; `exit` and `quit` leave the client.
+                if !command.trim().eq_ignore_ascii_case("logout") {
+                    self.app.should_quit = true;
                 }
+                return Ok(());
+            }
+            Ok(ClientCommandPlan::ChunkedUpload {
+                source,
+                destination,
+                total_size,
+            }) => Some((source, destination, total_size)),
+            _ => None,
+        };
+
+        let started = Instant::now();
+        let result = tab.connection_manager.send_command(&command).await;
+        self.app.log_response(&result, shows_file_contents);
+
+        let tab = self.app.tab_mut();
+        let success = match result {
+            Ok(formatted) => {
+                let success = formatted.success;
+                show_response(tab, formatted);
+                success
             }
             Err(e) => {
-                self.app.error(format!("Command failed: {}", e));
+                show_command_error(tab, e);
+                false
+            }
+        };
+        if success {
+            // Any stage of a pipeline may have changed a directory.
+            for stage in split_pipeline(&command) {
+                self.app.invalidate_completions_for(stage);
+            }
+        }
 
-                if matches!(e, common::FenrisError::ConnectionClosed) {
-                    self.app.connected = false;
-                    self.app.screen = Screen::Connection;
-                }
+        if let Some((source, destination, total_size)) = upload {
+            let record = TransferRecord {
+                timestamp: Local::now(),
+                direction: TransferDirection::Upload,
+                remote_path: destination.display().to_string(),
+                local_path: source.display().to_string(),
+                bytes: total_size,
+                duration_ms: started.elapsed().as_millis() as u64,
+                success,
+            };
+            if let Err(e) = self.app.record_transfer(record) {
+                self.app
+                    .tab_mut()
+                    .warn(format!("Transfer not recorded: {:#}", e));
             }
         }
 
         Ok(())
     }
+
+    fn handle_bookmark(&mut self, args: &[&str]) {
+        match args {
+            ["add", alias] | ["add", alias, _] => {
+                let current_dir = &self.app.tab().current_dir;
+                let path = match args.get(2) {
+                    Some(path) => bookmarks::absolute_path(current_dir, path),
+                    None => current_dir.clone(),
+                };
+                let result = self.app.add_bookmark(alias, path.clone());
+                let tab = self.app.tab_mut();
+                match result {
+                    Ok(()) => tab.success(format!("Bookmarked {} as {}", path, alias)),
+                    Err(e) => tab.error(format!("Failed to save bookmarks: {:#}", e)),
+                }
+            }
+            ["rm", alias] => {
+                let result = self.app.remove_bookmark(alias);
+                let tab = self.app.tab_mut();
+                match result {
+                    Ok(true) => tab.success(format!("Removed bookmark {}", alias)),
+                    Ok(false) => tab.warn(format!("Unknown bookmark: {}", alias)),
+                    Err(e) => tab.error(format!("Failed to save bookmarks: {:#}", e)),
+                }
+            }
+            ["list"] => {
+                let lines: Vec<String> = self
+                    .app
+                    .bookmarks
+                    .iter()
+                    .map(|(alias, path)| format!("{:<16} {}", alias, path))
+                    .collect();
+                let tab = self.app.tab_mut();
+                if lines.is_empty() {
+                    tab.info("No bookmarks");
+                }
+                for line in lines {
+                    tab.info(line);
+                }
+            }
+            _ => self
+                .app
+                .tab_mut()
+                .error("Usage: bookmark add <alias> [path] | bookmark rm <alias> | bookmark list"),
+        }
+    }
+
+    fn handle_workspace(&mut self, args: &[&str]) {
+        let (action, path) = match args {
+            [action] => (*action, workspace::workspace_path()),
+            [action, path] => (*action, Some(PathBuf::from(path))),
+            _ => ("", None),
+        };
+        let Some(path) = path.filter(|_| matches!(action, "save" | "load")) else {
+            self.app
+                .tab_mut()
+                .error("Usage: workspace save [path] | workspace load [path]");
+            return;
+        };
+
+        if action == "save" {
+            let result = self.app.save_workspace(&path);
+            let tab = self.app.tab_mut();
+            match result {
+                Ok(()) => tab.success(format!("Workspace saved to {}", path.display())),
+                Err(e) => tab.error(format!("Failed to save workspace: {:#}", e)),
+            }
+        } else {
+            let result = self.app.load_workspace(&path);
+            let tab = self.app.tab_mut();
+            match result {
+                Ok(()) if tab.connected => tab.success(format!(
+                    "Workspace loaded from {}; reconnect to use its server",
+                    path.display()
+                )),
+                Ok(()) => tab.success(format!("Workspace loaded from {}", path.display())),
+                Err(e) => tab.error(format!("Failed to load workspace: {:#}", e)),
+            }
+        }
+    }
+
+    fn handle_session_log(&mut self, path: Option<&str>) {
+        let Some(path) = path
+            .map(PathBuf::from)
+            .or_else(session_log::default_session_log_path)
+        else {
+            self.app
+                .tab_mut()
+                .error("HOME is not set; usage: session-log <path>");
+            return;
+        };
+        let result = self.app.start_session_log(&path);
+        let tab = self.app.tab_mut();
+        match result {
+            Ok(()) => tab.success(format!("Logging session to {}", path.display())),
+            Err(e) => tab.error(format!("Session log not started: {:#}", e)),
+        }
+    }
+
+    fn show_transfer_history(&mut self) {
+        let result = self.app.transfer_history();
+        let tab = self.app.tab_mut();
+        match result {
+            Ok(records) if records.is_empty() => tab.info("No transfers recorded"),
+            Ok(records) => {
+                for line in transfers::format_table(&records) {
+                    tab.info(line);
+                }
+            }
+            Err(e) => tab.error(format!("Failed to read transfer history: {:#}", e)),
+        }
+    }
+}
+
+fn build_connection_manager(
+    server_identity: Option<ServerIdentityPublicKey>,
+    psk: Option<String>,
+    credentials: Option<Credentials>,
+    keepalive: Option<Duration>,
+) -> ConnectionManager {
+    let mut manager = match server_identity {
+        Some(server_identity) => ConnectionManager::with_server_identity(
+            RequestManager::default(),
+            ResponseManager::default(),
+            server_identity,
+        ),
+        None => ConnectionManager::default(),
+    };
+
+    if let Some(psk) = psk {
+        manager
+            .set_psk(psk)
+            .expect("new connection manager cannot already be connected");
+    }
+    if let Some(credentials) = credentials {
+        manager
+            .set_credentials(credentials)
+            .expect("new connection manager cannot already be connected");
+    }
+    if let Some(interval) = keepalive {
+        manager.enable_keepalive(interval);
+    }
+
+    manager
+}
+
+async fn end_session(tab: &mut TabState) {
+    tab.info("Disconnecting...");
+    match tab
+        .connection_manager
+        .send_request_receive_response(&FenrisCommand::Terminate)
+        .await
+    {
+        Ok(FenrisOutput::Terminated) => {}
+        Ok(other) => tab.warn(format!("Unexpected reply to terminate: {:?}", other)),
+        Err(e) => tab.warn(format!("Server did not confirm terminate: {}", e)),
+    }
+
+    tab.connection_manager.disconnect().await;
+    tab.connected = false;
+    tab.connected_at = None;
+    tab.reconnecting = false;
+    tab.watched_paths.clear();
+    tab.tailf_path = None;
+    tab.screen = Screen::Connection;
+}
+
+async fn handle_script(tab: &mut TabState, path: &Path, ignore_errors: bool) {
+    let commands = match script::read_script(path) {
+        Ok(commands) => commands,
+        Err(e) => {
+            tab.error(format!("Script failed: {}", e));
+            return;
+        }
+    };
+
+    let steps = script::run_script(&mut tab.connection_manager, commands, ignore_errors).await;
+    for step in steps {
+        tab.info(format!("+ {}", step.command));
+        match step.result {
+            Ok(formatted) => show_response(tab, formatted),
+            Err(e) => show_command_error(tab, e),
+        }
+    }
+}
+
+/// Keeps the password in `login <user> <password>` out of the history and message log.
+fn redact_password(command: &str) -> String {
+    match command.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [login, username, _] if login.eq_ignore_ascii_case("login") => {
+            format!("{} {} ********", login, username)
+        }
+        _ => command.to_string(),
+    }
+}
+
+fn show_response(tab: &mut TabState, formatted: FormattedResponse) {
+    if formatted.success {
+        tab.success(formatted.message);
+    } else {
+        tab.error(formatted.message);
+    }
+
+    // A table replaces the text rendering of the same data.
+    tab.show_table(formatted.table_data);
+    if tab.table.is_none()
+        && let Some(details) = formatted.details
+    {
+        for line in details.lines() {
+            let kind = match formatted.details_format {
+                DetailsFormat::Plain => MessageKind::Info,
+                DetailsFormat::Diff => diff_line_kind(line),
+            };
+            tab.add_message(kind, line.to_string());
+        }
+    }
+
+    if let Some(ref dir) = formatted.current_dir {
+        tab.current_dir = dir.clone();
+    }
+}
+
+fn diff_line_kind(line: &str) -> MessageKind {
+    if line.starts_with('+') {
+        MessageKind::DiffAdded
+    } else if line.starts_with('-') {
+        MessageKind::DiffRemoved
+    } else {
+        MessageKind::DiffContext
+    }
+}
+
+fn show_command_error(tab: &mut TabState, e: common::FenrisError) {
+    tab.error(format!("Command failed: {}", e));
+
+    if matches!(e, common::FenrisError::ConnectionClosed) {
+        tab.connected = false;
+        tab.connected_at = None;
+        tab.screen = Screen::Connection;
+    }
+}
+
+async fn handle_watch(tab: &mut TabState, path: &str) {
+    match tab.connection_manager.subscribe(path).await {
+        Ok(events) => {
+            tab.watch_events.push(events);
+            tab.success(format!("Watching {}", path));
+            sync_watched_paths(tab);
+        }
+        Err(e) => tab.error(format!("Watch failed: {}", e)),
+    }
+}
+
+async fn handle_unwatch(tab: &mut TabState, path: &str) {
+    match tab.connection_manager.unsubscribe(path).await {
+        Ok(subscription) => {
+            tab.success(format!(
+                "Stopped watching {}",
+                subscription.to_string_lossy()
+            ));
+            sync_watched_paths(tab);
+        }
+        Err(e) => tab.error(format!("Unwatch failed: {}", e)),
+    }
+}
+
+fn sync_watched_paths(tab: &mut TabState) {
+    tab.watched_paths = tab
+        .connection_manager
+        .watched_paths()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    tab.watched_paths.sort();
+}
+
+async fn poll_watch_events(tab: &mut TabState) {
+    if tab.watch_events.is_empty()
+        || !tab.connection_manager.is_connected()
+        || tab.last_watch_poll.elapsed() < WATCH_POLL_INTERVAL
+    {
+        return;
+    }
+
+    tab.last_watch_poll = Instant::now();
+    if let Err(e) = tab.connection_manager.poll_watch_events().await {
+        tab.warn(format!("Watch poll failed: {}", e));
+    }
+}
+
+async fn poll_keepalive(tab: &mut TabState) {
+    if let Err(e) = tab.connection_manager.poll_keepalive().await {
+        show_command_error(tab, e);
+    }
+}
+
+async fn poll_health_check(tab: &mut TabState) {
+    match tab.connection_manager.poll_health_check().await {
+        Some(HealthEvent::Lost) => {
+            tab.connected = false;
+            tab.connected_at = None;
+            tab.reconnecting = true;
+            tab.warn("Connection lost, reconnecting...");
+        }
+        Some(HealthEvent::Recovered) => {
+            tab.connected = true;
+            tab.connected_at = Some(Instant::now());
+            tab.reconnecting = false;
+            tab.watched_paths.clear();
+            tab.success("Reconnected to server");
+        }
+        None => {}
+    }
+}
+
+fn handle_tailf(tab: &mut TabState, path: Option<&str>) {
+    match (path, tab.tailf_path.take()) {
+        (Some(path), _) => {
+            tab.success(format!("Following {}", path));
+            tab.tailf_path = Some(path.to_string());
+            tab.tailf_offset = 0;
+            tab.last_tailf_poll = Instant::now();
+        }
+        (None, Some(previous)) => tab.success(format!("Stopped following {}", previous)),
+        (None, None) => tab.warn("Not following any file"),
+    }
+}
+
+async fn poll_tailf(tab: &mut TabState) {
+    let Some(path) = tab.tailf_path.clone() else {
+        return;
+    };
+    if !tab.connection_manager.is_connected() || tab.last_tailf_poll.elapsed() < TAILF_POLL_INTERVAL
+    {
+        return;
+    }
+
+    tab.last_tailf_poll = Instant::now();
+    let command = FenrisCommand::TailObject {
+        path: PathBuf::from(&path),
+        bytes: TAILF_BYTES,
+    };
+    match tab
+        .connection_manager
+        .send_request_receive_response(&command)
+        .await
+    {
+        Ok(FenrisOutput::ObjectContentChunk(chunk)) => show_tail_growth(tab, chunk),
+        Ok(FenrisOutput::Error { message }) => {
+            tab.warn(format!("Stopped following {}: {}", path, message));
+            tab.tailf_path = None;
+        }
+        Ok(output) => {
+            tab.warn(format!(
+                "Stopped following {}: unexpected {:?}",
+                path, output
+            ));
+            tab.tailf_path = None;
+        }
+        Err(e) => tab.warn(format!("Tail poll failed: {}", e)),
+    }
+}
+
+fn show_tail_growth(tab: &mut TabState, chunk: TransferChunk) {
+    if chunk.total_size < tab.tailf_offset {
+        tab.warn("File was truncated, following from its new end");
+        tab.tailf_offset = 0;
+    }
+
+    let seen = tab.tailf_offset.saturating_sub(chunk.offset) as usize;
+    let fresh = chunk.data.get(seen..).unwrap_or_default();
+    for line in String::from_utf8_lossy(fresh).lines() {
+        tab.info(line.to_string());
+    }
+    tab.tailf_offset = chunk.total_size;
+}
+
+/// Lists the directory the completer is waiting on, if any, for the active tab.
+async fn fetch_completions(app: &mut App) {
+    let tab = app.tab_mut();
+    let Some(dir) = tab.pending_completion.take() else {
+        return;
+    };
+    if !tab.connection_manager.is_connected() {
+        return;
+    }
+
+    let command = FenrisCommand::ListNamespace {
+        path: PathBuf::from(&dir),
+        sort: ListSort::default(),
+    };
+    match tab
+        .connection_manager
+        .send_request_receive_response(&command)
+        .await
+    {
+        Ok(FenrisOutput::NamespaceListing { entries, .. }) => {
+            let names = entries
+                .into_iter()
+                .map(|entry| {
+                    if entry.is_namespace {
+                        format!("{}/", entry.name)
+                    } else {
+                        entry.name
+                    }
+                })
+                .collect();
+            app.cache_completions(dir, names);
+        }
+        // Nothing to complete in a missing or unreadable directory; don't ask again.
+        Ok(_) => {
+            tab.completion_cache.insert(dir, Vec::new());
+        }
+        Err(e) => tab.warn(format!("Completion listing failed: {}", e)),
+    }
+}
+
+/// Lists, copies and moves what the split view's keys queued, against the active tab.
+async fn run_pane_actions(app: &mut App) {
+    if !app.tab().connection_manager.is_connected() {
+        app.pending_pane_actions.clear();
+        return;
+    }
+
+    for action in std::mem::take(&mut app.pending_pane_actions) {
+        match action {
+            PaneAction::Refresh(index) => refresh_pane(app, index).await,
+            PaneAction::Copy | PaneAction::Move => transfer_between_panes(app, action).await,
+            PaneAction::Read | PaneAction::Info | PaneAction::Delete => {
+                run_pane_command(app, action).await
+            }
+        }
+    }
+}
+
+async fn refresh_pane(app: &mut App, index: PaneIndex) {
+    let command = FenrisCommand::ListNamespace {
+        path: PathBuf::from(&app.pane(index).current_dir),
+        sort: ListSort::default(),
+    };
+    match app
+        .tab_mut()
+        .connection_manager
+        .send_request_receive_response(&command)
+        .await
+    {
+        Ok(FenrisOutput::NamespaceListing { entries, .. }) => {
+            app.pane_mut(index).set_listing(entries);
+        }
+        Ok(FenrisOutput::Error { message }) => {
+            app.pane_mut(index).set_listing(Vec::new());
+            app.error(format!("Listing failed: {}", message));
+        }
+        Ok(output) => app.warn(format!("Unexpected listing response: {:?}", output)),
+        Err(e) => app.error(format!("Listing failed: {}", e)),
+    }
+}
+
+async fn transfer_between_panes(app: &mut App, action: PaneAction) {
+    let source = app.active_pane;
+    let Some(name) = app
+        .pane(source)
+        .selected_entry()
+        .map(|entry| entry.name.clone())
+    else {
+        return;
+    };
+    let from = app.pane(source).path_of(&name);
+    let to = app.pane(source.other()).path_of(&name);
+    if from == to {
+        app.warn("Both panes show the same directory");
+        return;
+    }
+
+    let manager = &mut app.tab_mut().connection_manager;
+    let result = match action {
+        PaneAction::Move => {
+            manager
+                .send_request_receive_response(&FenrisCommand::MoveObject {
+                    from: PathBuf::from(&from),
+                    to: PathBuf::from(&to),
+                })
+                .await
+        }
+        _ => {
+            manager
+                .copy_object(PathBuf::from(&from), PathBuf::from(&to))
+                .await
+        }
+    };
+    let verb = if action == PaneAction::Move {
+        "Moved"
+    } else {
+        "Copied"
+    };
+    match result {
+        Ok(FenrisOutput::Error { message }) => app.error(format!("{} failed: {}", verb, message)),
+        Ok(_) => app
+            .tab_mut()
+            .success(format!("{} {} to {}", verb, from, to)),
+        Err(e) => app.error(format!("{} failed: {}", verb, e)),
+    }
+
+    if action == PaneAction::Move {
+        refresh_pane(app, source).await;
+    }
+    refresh_pane(app, source.other()).await;
+}
+
+/// Runs `read`, `info` or `rm`/`rmdir` on the focused pane's selection as if it were typed.
+async fn run_pane_command(app: &mut App, action: PaneAction) {
+    let index = app.active_pane;
+    let pane = app.pane(index);
+    let Some(entry) = pane.selected_entry() else {
+        return;
+    };
+    let path = pane.path_of(&entry.name);
+    let command = match action {
+        PaneAction::Read => format!("read {}", path),
+        PaneAction::Info => format!("info {}", path),
+        _ if entry.is_namespace => format!("rmdir {}", path),
+        _ => format!("rm {}", path),
+    };
+
+    let tab = app.tab_mut();
+    tab.info(format!("> {}", command));
+    match tab.connection_manager.send_command(&command).await {
+        Ok(formatted) => show_response(tab, formatted),
+        Err(e) => show_command_error(tab, e),
+    }
+
+    if action == PaneAction::Delete {
+        refresh_pane(app, index).await;
+        if app.pane(index.other()).current_dir == app.pane(index).current_dir {
+            refresh_pane(app, index.other()).await;
+        }
+    }
+}
+
+fn drain_watch_events(tab: &mut TabState) {
+    let mut events = Vec::new();
+    tab.watch_events.retain_mut(|receiver| {
+        loop {
+            match receiver.try_recv() {
+                Ok(event) => events.push(event),
+                Err(mpsc::error::TryRecvError::Empty) => return true,
+                Err(mpsc::error::TryRecvError::Disconnected) => return false,
+            }
+        }
+    });
+
+    for event in events {
+        let formatted = ResponseManager::default().format_watch_event(&event);
+        tab.info(formatted.message);
+    }
+}
+
+fn drain_broadcasts(tab: &mut TabState) {
+    for message in tab.connection_manager.take_broadcasts() {
+        let formatted = ResponseManager::default().format_broadcast(&message);
+        tab.info(formatted.message);
+    }
 }
 
 impl Default for TuiClient {
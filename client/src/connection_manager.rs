@@ -1,21 +1,38 @@
 use common::{
-    DEFAULT_TRANSFER_CHUNK_SIZE, DefaultSecureChannel, FenrisCommand, FenrisError, FenrisOutput,
-    ObjectWriteMode, Result, ServerIdentityPublicKey, TransferChunk,
+    Config, DEFAULT_TRANSFER_CHUNK_SIZE, FenrisCommand, FenrisError, FenrisOutput, ObjectWriteMode,
+    Result, SecureChannel, ServerIdentityPublicKey, TransferChunk, Transport, WatchEvent,
+};
+#[cfg(feature = "tls")]
+use common::{TlsClientConfig, TlsTransport};
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
-
-use std::{io, path::PathBuf};
 
 use tokio::io::AsyncReadExt;
 use tokio::net::TcpStream;
-use tracing::{debug, info};
+use tokio::sync::mpsc;
+use tokio::task::{AbortHandle, JoinHandle};
+use tracing::{debug, info, warn};
 
-use crate::response_manager::ResponseManager;
+use crate::response_manager::{FormatterOptions, ResponseManager};
 use crate::{
-    request_manager::{ClientCommandPlan, RequestManager},
-    response_manager::FormattedResponse,
+    manifest,
+    request_manager::{ClientCommandPlan, OutputRedirect, RequestManager},
+    response_manager::{DetailsFormat, FormattedResponse},
 };
 
+type ClientChannel = SecureChannel<Config, Transport>;
+
 const READ_PREVIEW_LIMIT: usize = 500;
+const WATCH_EVENT_CAPACITY: usize = 64;
+const LATENCY_SAMPLE_LIMIT: usize = 100;
+const KEEPALIVE_MAX_MISSED: u32 = 3;
+/// How long to wait after the handshake for a server's message of the day.
+const MOTD_WAIT: Duration = Duration::from_secs(1);
 
 #[derive(Debug, Clone)]
 pub struct ServerInfo {
@@ -32,10 +49,82 @@ impl ServerInfo {
         format!("{}:{}", self.address, self.port)
     }
 }
+
+/// A user account on a server that requires logging in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Round-trip times over the most recent pings; all zero before the first sample.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub min: Duration,
+    pub max: Duration,
+    pub avg: Duration,
+    pub p99: Duration,
+}
+
+impl LatencyStats {
+    fn from_samples(samples: &VecDeque<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+
+        let mut sorted: Vec<Duration> = samples.iter().copied().collect();
+        sorted.sort();
+        let p99_index = (sorted.len() * 99).div_ceil(100) - 1;
+
+        Self {
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            avg: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+            p99: sorted[p99_index],
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Keepalive {
+    interval: Duration,
+    last_ping: Instant,
+    missed: u32,
+}
+
+/// What [`ConnectionManager::poll_health_check`] noticed since it was last polled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthEvent {
+    /// Enough pings failed in a row that the connection was dropped; reconnects follow.
+    Lost,
+    /// A reconnect after [`HealthEvent::Lost`] succeeded.
+    Recovered,
+}
+
+#[derive(Debug)]
+struct HealthCheck {
+    pings: mpsc::Receiver<FenrisCommand>,
+    timer: AbortHandle,
+    interval: Duration,
+    failure_threshold: u32,
+    failures: u32,
+    reconnecting: bool,
+}
+
 pub struct ConnectionManager {
     server_info: Option<ServerInfo>,
     server_identity: Option<ServerIdentityPublicKey>,
-    channel: Option<DefaultSecureChannel>,
+    psk: Option<String>,
+    credentials: Option<Credentials>,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsClientConfig>,
+    channel: Option<ClientChannel>,
+    watchers: HashMap<PathBuf, mpsc::Sender<WatchEvent>>,
+    broadcasts: Vec<String>,
+    server_motd: Option<String>,
+    latency_samples: VecDeque<Duration>,
+    keepalive: Option<Keepalive>,
+    health_check: Option<HealthCheck>,
     request_manager: RequestManager,
     response_manager: ResponseManager,
 }
@@ -45,7 +134,17 @@ impl ConnectionManager {
         Self {
             server_info: None,
             server_identity: None,
+            psk: None,
+            credentials: None,
+            #[cfg(feature = "tls")]
+            tls: None,
             channel: None,
+            watchers: HashMap::new(),
+            broadcasts: Vec::new(),
+            server_motd: None,
+            latency_samples: VecDeque::with_capacity(LATENCY_SAMPLE_LIMIT),
+            keepalive: None,
+            health_check: None,
             request_manager,
             response_manager,
         }
@@ -67,14 +166,22 @@ impl ConnectionManager {
         self.channel.is_some()
     }
 
+    /// The banner the server sent when this connection was set up, if it has a message of
+    /// the day.
+    pub fn server_motd(&self) -> Option<&str> {
+        self.server_motd.as_deref()
+    }
+
     pub async fn connect(&mut self) -> Result<()> {
-        let addr = self
-            .server_info
-            .as_ref()
-            .ok_or(FenrisError::NetworkError(io::Error::other(
-                "Server info not set",
-            )))?
-            .to_socket_addr();
+        let server_info =
+            self.server_info
+                .as_ref()
+                .ok_or(FenrisError::NetworkError(io::Error::other(
+                    "Server info not set",
+                )))?;
+        let addr = server_info.to_socket_addr();
+        #[cfg(feature = "tls")]
+        let host = server_info.address.clone();
         let expected_identity = self.server_identity.ok_or_else(|| {
             FenrisError::AuthenticationError(
                 "server identity is required before connecting".to_string(),
@@ -86,8 +193,60 @@ impl ConnectionManager {
             .await
             .map_err(FenrisError::NetworkError)?;
 
-        let channel =
-            DefaultSecureChannel::client_handshake_authenticated(stream, expected_identity).await?;
+        #[cfg(feature = "tls")]
+        if let Some(tls) = &self.tls {
+            let server_name = tls.server_name.as_deref().unwrap_or(&host);
+            let stream = TlsTransport::connect(stream, &tls.connector()?, server_name).await?;
+            return self.establish(stream.into(), expected_identity).await;
+        }
+
+        self.establish(stream.into(), expected_identity).await
+    }
+
+    #[cfg(unix)]
+    pub async fn connect_unix(&mut self, path: &Path) -> Result<()> {
+        let expected_identity = self.server_identity.ok_or_else(|| {
+            FenrisError::AuthenticationError(
+                "server identity is required before connecting".to_string(),
+            )
+        })?;
+        info!("Connecting to server at unix:{}", path.display());
+
+        let stream = tokio::net::UnixStream::connect(path)
+            .await
+            .map_err(FenrisError::NetworkError)?;
+
+        self.establish(stream.into(), expected_identity).await
+    }
+
+    /// Connects over the Unix socket when one is given, otherwise over TCP to the server info.
+    pub async fn connect_via(&mut self, unix_socket: Option<&Path>) -> Result<()> {
+        match unix_socket {
+            #[cfg(unix)]
+            Some(path) => self.connect_unix(path).await,
+            #[cfg(not(unix))]
+            Some(_) => Err(FenrisError::NetworkError(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "unix sockets are not supported on this platform",
+            ))),
+            None => self.connect().await,
+        }
+    }
+
+    async fn establish(
+        &mut self,
+        stream: Transport,
+        expected_identity: ServerIdentityPublicKey,
+    ) -> Result<()> {
+        let mut channel =
+            ClientChannel::client_handshake_authenticated(stream, expected_identity).await?;
+        if let Some(psk) = self.psk.as_deref() {
+            channel.client_psk_response(psk).await?;
+        }
+        if let Some(credentials) = &self.credentials {
+            client_login(&mut channel, credentials).await?;
+        }
+        self.server_motd = receive_banner(&mut channel, &mut self.broadcasts).await?;
         self.channel = Some(channel);
 
         info!("Successfully connected to server");
@@ -96,23 +255,203 @@ impl ConnectionManager {
     }
 
     pub async fn disconnect(&mut self) {
-        self.channel.take();
+        if let Some(health_check) = self.health_check.take() {
+            health_check.timer.abort();
+        }
+        self.drop_connection();
         info!("Disconnected from server");
     }
 
+    fn drop_connection(&mut self) {
+        self.channel.take();
+        self.server_motd = None;
+        self.watchers.clear();
+        self.latency_samples.clear();
+    }
+
     pub async fn send_command(&mut self, command: &str) -> Result<FormattedResponse> {
         if !self.is_connected() {
             return Err(FenrisError::ConnectionClosed);
         }
         debug!("Sending command: {}", command);
-        let plan = self.request_manager.build_request(command)?;
+        let (plan, redirect) = self.build_plan(command)?.into_parts();
+
+        let formatted = match plan {
+            ClientCommandPlan::Single(FenrisCommand::Ping) => {
+                let rtt = self.ping().await?;
+                let stats = self.latency_stats();
+                let mut formatted = self.response_manager.format_pong(Some(rtt));
+                formatted.details = Some(format!(
+                    "Last {} pings: min {:.2} ms, avg {:.2} ms, max {:.2} ms, p99 {:.2} ms",
+                    self.latency_samples.len(),
+                    stats.min.as_secs_f64() * 1000.0,
+                    stats.avg.as_secs_f64() * 1000.0,
+                    stats.max.as_secs_f64() * 1000.0,
+                    stats.p99.as_secs_f64() * 1000.0,
+                ));
+                formatted
+            }
+            ClientCommandPlan::Pipeline { stages } => self.run_pipeline(stages).await?,
+            ClientCommandPlan::ManifestCheck { path, manifest } => {
+                self.check_manifest(path, manifest).await?
+            }
+            plan => {
+                let started = Instant::now();
+                let response = self.execute_plan(plan).await?;
+                match &response {
+                    FenrisOutput::Echo { payload } => self
+                        .response_manager
+                        .format_echo(payload, Some(started.elapsed())),
+                    _ => self.response_manager.format_response(&response),
+                }
+            }
+        };
+        match redirect {
+            Some(redirect) => write_redirect(&redirect, formatted).await,
+            None => Ok(formatted),
+        }
+    }
 
-        let response = self.execute_plan(plan).await?;
+    /// Runs a batch of commands, pipelining them when every command is a single request.
+    /// All commands are parsed before anything is sent; one parse error aborts the batch.
+    pub async fn send_concurrent(&mut self, commands: &[&str]) -> Result<Vec<FormattedResponse>> {
+        if !self.is_connected() {
+            return Err(FenrisError::ConnectionClosed);
+        }
+        let (plans, redirects): (Vec<_>, Vec<_>) = commands
+            .iter()
+            .map(|command| self.build_plan(command).map(ClientCommandPlan::into_parts))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+
+        let requests: Option<Vec<FenrisCommand>> = plans
+            .iter()
+            .map(|plan| match plan {
+                ClientCommandPlan::Single(request) => Some(request.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let outputs = match requests {
+            Some(requests) => self.send_pipelined(&requests).await?,
+            None => {
+                let mut outputs = Vec::with_capacity(plans.len());
+                for plan in plans {
+                    outputs.push(self.execute_plan(plan).await?);
+                }
+                outputs
+            }
+        };
 
-        let formatted = self.response_manager.format_response(&response);
+        let mut formatted = Vec::with_capacity(outputs.len());
+        for (output, redirect) in outputs.iter().zip(redirects) {
+            let response = self.response_manager.format_response(output);
+            formatted.push(match redirect {
+                Some(redirect) => write_redirect(&redirect, response).await?,
+                None => response,
+            });
+        }
         Ok(formatted)
     }
 
+    fn build_plan(&self, command: &str) -> Result<ClientCommandPlan> {
+        let plan = self.request_manager.build_request(command)?;
+        if let ClientCommandPlan::Single(FenrisCommand::Login { .. }) = plan {
+            return Err(FenrisError::InvalidRequest(
+                "login is sent while connecting; set credentials and reconnect".to_string(),
+            ));
+        }
+        let stages = match &plan {
+            ClientCommandPlan::Pipeline { stages } => stages.as_slice(),
+            plan => std::slice::from_ref(plan),
+        };
+        if stages.iter().any(|stage| {
+            matches!(
+                stage,
+                ClientCommandPlan::Single(FenrisCommand::Broadcast { .. })
+            )
+        }) && self.psk.is_none()
+        {
+            return Err(FenrisError::AuthenticationError(
+                "broadcast requires a PSK-authenticated connection".to_string(),
+            ));
+        }
+
+        Ok(plan)
+    }
+
+    /// Runs each stage with the previous stage's output as its data and shows the last one.
+    /// Stops at the first stage that fails, since there is nothing sensible to pipe onwards.
+    async fn run_pipeline(&mut self, stages: Vec<ClientCommandPlan>) -> Result<FormattedResponse> {
+        let last = stages.len().saturating_sub(1);
+        let mut input = None;
+        let mut formatted = None;
+
+        for (index, stage) in stages.into_iter().enumerate() {
+            let stage = match input.take() {
+                Some(data) => stage.with_input(data),
+                None => stage,
+            };
+            let output = match stage {
+                // A plain read keeps only a preview; the next stage needs every byte.
+                ClientCommandPlan::ChunkedRead { path } if index < last => {
+                    self.read_object_fully(path).await?
+                }
+                stage => self.execute_plan(stage).await?,
+            };
+
+            let response = self.response_manager.format_response(&output);
+            if !response.success {
+                return Ok(response);
+            }
+            input = Some(match output {
+                FenrisOutput::ObjectContent { data, .. } => data,
+                FenrisOutput::Echo { payload } => payload,
+                _ => response.details.clone().unwrap_or_default().into_bytes(),
+            });
+            formatted = Some(response);
+        }
+
+        formatted.ok_or_else(|| FenrisError::MissingField("pipeline is empty".to_string()))
+    }
+
+    async fn send_pipelined(&mut self, requests: &[FenrisCommand]) -> Result<Vec<FenrisOutput>> {
+        let channel = self.channel.take().ok_or(FenrisError::ConnectionClosed)?;
+        let (mut reader, mut writer) = channel.into_split();
+        let watchers = &mut self.watchers;
+        let broadcasts = &mut self.broadcasts;
+
+        let request_ids: Vec<u64> = requests
+            .iter()
+            .map(|_| self.request_manager.next_request_id())
+            .collect();
+
+        let send = async {
+            for (request, request_id) in requests.iter().zip(&request_ids) {
+                writer.send_msg(&correlate(*request_id, request)).await?;
+            }
+            Ok::<_, FenrisError>(())
+        };
+        // The server answers a connection's requests in the order it received them.
+        let receive = async {
+            let mut outputs = Vec::with_capacity(requests.len());
+            while outputs.len() < requests.len() {
+                match reader.recv_msg::<FenrisOutput>().await? {
+                    FenrisOutput::WatchEvent(event) => dispatch_watch_event(watchers, event),
+                    FenrisOutput::Broadcast { message } => broadcasts.push(message),
+                    output => outputs.push(answer_to(request_ids[outputs.len()], output)?),
+                }
+            }
+            Ok(outputs)
+        };
+
+        let ((), outputs) = tokio::try_join!(send, receive)?;
+        self.channel = Some(ClientChannel::reunite(reader, writer)?);
+
+        Ok(outputs)
+    }
+
     async fn execute_plan(&mut self, plan: ClientCommandPlan) -> Result<FenrisOutput> {
         match plan {
             ClientCommandPlan::Single(request) => {
@@ -127,18 +466,288 @@ impl ConnectionManager {
                 destination,
                 total_size,
             } => self.send_upload(source, destination, total_size).await,
+            ClientCommandPlan::LocalScript { path, .. } => Err(FenrisError::InvalidRequest(
+                format!("script {} must be run by the client", path.display()),
+            )),
+            ClientCommandPlan::ClearMessages => Err(FenrisError::InvalidRequest(
+                "clear must be run by the client".to_string(),
+            )),
+            ClientCommandPlan::Redirected { .. } => Err(FenrisError::InvalidRequest(
+                "output redirects are applied after the plan runs".to_string(),
+            )),
+            ClientCommandPlan::Pipeline { .. } => Err(FenrisError::InvalidRequest(
+                "pipelines must be run through send_command".to_string(),
+            )),
+            ClientCommandPlan::ManifestCheck { .. } => Err(FenrisError::InvalidRequest(
+                "manifest-check must be run through send_command".to_string(),
+            )),
         }
     }
 
+    /// Compares the server's manifest of `path` with the `sha256sum` output in the local file
+    /// `manifest_path`.
+    async fn check_manifest(
+        &mut self,
+        path: PathBuf,
+        manifest_path: PathBuf,
+    ) -> Result<FormattedResponse> {
+        let text = tokio::fs::read_to_string(&manifest_path)
+            .await
+            .map_err(|e| {
+                FenrisError::file_operation_from(
+                    format!("Failed to read {}: {}", manifest_path.display(), e),
+                    e,
+                )
+            })?;
+        let local = manifest::parse(&text)?;
+
+        let response = self
+            .send_request_receive_response(&FenrisCommand::ChecksumManifest { path })
+            .await?;
+        Ok(match &response {
+            FenrisOutput::ChecksumManifest { entries, truncated } => {
+                self.response_manager.format_manifest_check(
+                    &manifest::compare(&local, entries),
+                    local.len(),
+                    *truncated,
+                )
+            }
+            _ => self.response_manager.format_response(&response),
+        })
+    }
+
     pub async fn send_request_receive_response(
         &mut self,
         request: &FenrisCommand,
     ) -> Result<FenrisOutput> {
         let channel = self.channel.as_mut().ok_or(FenrisError::ConnectionClosed)?;
+        let request_id = self.request_manager.next_request_id();
+
+        channel.send_msg(&correlate(request_id, request)).await?;
+        debug!("Request {} sent, awaiting response...", request_id);
+        loop {
+            match recv_output(channel, &mut self.watchers, &mut self.broadcasts).await? {
+                // A late reply to a request whose caller stopped waiting, such as a timed-out
+                // keepalive ping.
+                FenrisOutput::Correlated {
+                    request_id: answered,
+                    ..
+                } if answered < request_id => {
+                    debug!("Dropping late response to request {}", answered);
+                }
+                output => return answer_to(request_id, output),
+            }
+        }
+    }
+
+    pub async fn subscribe(&mut self, path: &str) -> Result<mpsc::Receiver<WatchEvent>> {
+        let output = self
+            .send_request_receive_response(&FenrisCommand::Subscribe {
+                path: PathBuf::from(path),
+            })
+            .await?;
+
+        let subscription = expect_watch_path(output)?;
+        let (tx, rx) = mpsc::channel(WATCH_EVENT_CAPACITY);
+        self.watchers.insert(subscription, tx);
+
+        Ok(rx)
+    }
+
+    pub async fn unsubscribe(&mut self, path: &str) -> Result<PathBuf> {
+        let output = self
+            .send_request_receive_response(&FenrisCommand::Unsubscribe {
+                path: PathBuf::from(path),
+            })
+            .await?;
+
+        let subscription = expect_watch_path(output)?;
+        self.watchers.remove(&subscription);
+
+        Ok(subscription)
+    }
+
+    pub fn watched_paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.watchers.keys()
+    }
+
+    pub fn bytes_sent(&self) -> u64 {
+        self.channel.as_ref().map_or(0, ClientChannel::bytes_sent)
+    }
+
+    pub fn bytes_received(&self) -> u64 {
+        self.channel
+            .as_ref()
+            .map_or(0, ClientChannel::bytes_received)
+    }
 
-        channel.send_msg(request).await?;
-        debug!("Request sent, awaiting response...");
-        channel.recv_msg::<FenrisOutput>().await
+    pub fn take_broadcasts(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.broadcasts)
+    }
+
+    pub async fn poll_watch_events(&mut self) -> Result<()> {
+        if self.watchers.is_empty() {
+            return Ok(());
+        }
+
+        self.ping().await.map(|_| ())
+    }
+
+    /// Sends a ping and records its round-trip time.
+    pub async fn ping(&mut self) -> Result<Duration> {
+        let started = Instant::now();
+        match self
+            .send_request_receive_response(&FenrisCommand::Ping)
+            .await?
+        {
+            FenrisOutput::Pong => {
+                let rtt = started.elapsed();
+                if self.latency_samples.len() == LATENCY_SAMPLE_LIMIT {
+                    self.latency_samples.pop_front();
+                }
+                self.latency_samples.push_back(rtt);
+                Ok(rtt)
+            }
+            output => Err(FenrisError::InvalidRequest(format!(
+                "unexpected ping response: {:?}",
+                output
+            ))),
+        }
+    }
+
+    pub fn latest_latency(&self) -> Option<Duration> {
+        self.latency_samples.back().copied()
+    }
+
+    pub fn latency_stats(&self) -> LatencyStats {
+        LatencyStats::from_samples(&self.latency_samples)
+    }
+
+    /// Pings every `interval` from [`Self::poll_keepalive`]; a ping that takes longer than
+    /// `interval` counts as missed.
+    pub fn enable_keepalive(&mut self, interval: Duration) {
+        self.keepalive = Some(Keepalive {
+            interval,
+            last_ping: Instant::now(),
+            missed: 0,
+        });
+    }
+
+    /// Disconnects after three consecutive missed pings and reports the connection as closed.
+    pub async fn poll_keepalive(&mut self) -> Result<()> {
+        let interval = match &self.keepalive {
+            Some(keepalive) if keepalive.last_ping.elapsed() >= keepalive.interval => {
+                keepalive.interval
+            }
+            _ => return Ok(()),
+        };
+        if !self.is_connected() {
+            return Ok(());
+        }
+
+        let result = tokio::time::timeout(interval, self.ping()).await;
+        let Some(keepalive) = self.keepalive.as_mut() else {
+            return Ok(());
+        };
+        keepalive.last_ping = Instant::now();
+        match result {
+            Ok(rtt) => {
+                keepalive.missed = 0;
+                rtt.map(|_| ())
+            }
+            Err(_) => {
+                keepalive.missed += 1;
+                warn!(
+                    "Keepalive ping {} of {} timed out after {:?}",
+                    keepalive.missed, KEEPALIVE_MAX_MISSED, interval
+                );
+                if keepalive.missed < KEEPALIVE_MAX_MISSED {
+                    return Ok(());
+                }
+
+                self.disconnect().await;
+                Err(FenrisError::ConnectionClosed)
+            }
+        }
+    }
+
+    /// Spawns a timer that queues a ping every `interval`; [`Self::poll_health_check`] sends
+    /// them, so the checks never interleave with a command in flight. After
+    /// `failure_threshold` consecutive failed pings the connection is dropped and every later
+    /// tick tries to reconnect. [`Self::disconnect`] stops the timer.
+    pub fn start_health_check(
+        &mut self,
+        interval: Duration,
+        failure_threshold: u32,
+    ) -> JoinHandle<()> {
+        let (pings, queued) = mpsc::channel(1);
+        let timer = tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                if pings.send(FenrisCommand::Ping).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        if let Some(previous) = self.health_check.replace(HealthCheck {
+            pings: queued,
+            timer: timer.abort_handle(),
+            interval,
+            failure_threshold: failure_threshold.max(1),
+            failures: 0,
+            reconnecting: false,
+        }) {
+            previous.timer.abort();
+        }
+
+        timer
+    }
+
+    /// Sends the ping the health check timer queued, if any, and reports a lost or recovered
+    /// connection.
+    pub async fn poll_health_check(&mut self) -> Option<HealthEvent> {
+        let health_check = self.health_check.as_mut()?;
+        let request = health_check.pings.try_recv().ok()?;
+        let interval = health_check.interval;
+
+        if health_check.reconnecting {
+            if let Err(e) = self.connect().await {
+                debug!("Reconnect attempt failed: {}", e);
+                return None;
+            }
+            let health_check = self.health_check.as_mut()?;
+            health_check.reconnecting = false;
+            health_check.failures = 0;
+            info!("Connection recovered");
+            return Some(HealthEvent::Recovered);
+        }
+
+        let healthy = matches!(
+            tokio::time::timeout(interval, self.send_request_receive_response(&request)).await,
+            Ok(Ok(FenrisOutput::Pong))
+        );
+        let health_check = self.health_check.as_mut()?;
+        if healthy {
+            health_check.failures = 0;
+            return None;
+        }
+
+        health_check.failures += 1;
+        warn!(
+            "Health check ping {} of {} failed",
+            health_check.failures, health_check.failure_threshold
+        );
+        if health_check.failures < health_check.failure_threshold {
+            return None;
+        }
+
+        health_check.reconnecting = true;
+        self.drop_connection();
+        warn!("Connection lost, reconnecting every {:?}", interval);
+        Some(HealthEvent::Lost)
     }
 
     async fn send_inline_write(
@@ -161,11 +770,10 @@ impl ConnectionManager {
             .begin_transfer(destination, ObjectWriteMode::Upload, total_size)
             .await?;
         let mut file = tokio::fs::File::open(&source).await.map_err(|e| {
-            FenrisError::FileOperationError(format!(
-                "Failed to open file {}: {}",
-                source.display(),
-                e
-            ))
+            FenrisError::file_operation_from(
+                format!("Failed to open file {}: {}", source.display(), e),
+                e,
+            )
         })?;
 
         if total_size == 0 {
@@ -177,15 +785,14 @@ impl ConnectionManager {
 
         loop {
             let read = file.read(&mut buffer).await.map_err(|e| {
-                FenrisError::FileOperationError(format!(
-                    "Failed to read file {}: {}",
-                    source.display(),
-                    e
-                ))
+                FenrisError::file_operation_from(
+                    format!("Failed to read file {}: {}", source.display(), e),
+                    e,
+                )
             })?;
 
             if read == 0 {
-                return Err(FenrisError::FileOperationError(format!(
+                return Err(FenrisError::file_operation(format!(
                     "File {} ended before {} bytes were read",
                     source.display(),
                     total_size
@@ -222,7 +829,7 @@ impl ConnectionManager {
             })
             .await?;
 
-        match channel.recv_msg::<FenrisOutput>().await? {
+        match recv_output(channel, &mut self.watchers, &mut self.broadcasts).await? {
             FenrisOutput::TransferReady { chunk_size } => {
                 Ok(chunk_size.clamp(1, DEFAULT_TRANSFER_CHUNK_SIZE))
             }
@@ -279,7 +886,7 @@ impl ConnectionManager {
                 total_size,
             }))
             .await?;
-        channel.recv_msg::<FenrisOutput>().await
+        recv_output(channel, &mut self.watchers, &mut self.broadcasts).await
     }
 
     async fn receive_chunked_read(&mut self, path: PathBuf) -> Result<FenrisOutput> {
@@ -291,7 +898,7 @@ impl ConnectionManager {
         let mut preview = Vec::new();
 
         loop {
-            match channel.recv_msg::<FenrisOutput>().await? {
+            match recv_output(channel, &mut self.watchers, &mut self.broadcasts).await? {
                 FenrisOutput::ObjectContentChunk(chunk) => {
                     if preview.len() < READ_PREVIEW_LIMIT {
                         let remaining = READ_PREVIEW_LIMIT - preview.len();
@@ -318,6 +925,55 @@ impl ConnectionManager {
         }
     }
 
+    /// Copies a file by reading it in full and writing it back under `to`. The server has no
+    /// copy request, so the bytes make a round trip through the client.
+    pub async fn copy_object(&mut self, from: PathBuf, to: PathBuf) -> Result<FenrisOutput> {
+        match self.read_object_fully(from).await? {
+            FenrisOutput::ObjectContent { data, .. } => {
+                self.send_inline_write(to, ObjectWriteMode::Write, data)
+                    .await
+            }
+            output => Ok(output),
+        }
+    }
+
+    /// Like a chunked read but keeps every byte instead of a preview.
+    async fn read_object_fully(&mut self, path: PathBuf) -> Result<FenrisOutput> {
+        let channel = self.channel.as_mut().ok_or(FenrisError::ConnectionClosed)?;
+        channel
+            .send_msg(&FenrisCommand::ReadObject { path })
+            .await?;
+
+        let mut data = Vec::new();
+        loop {
+            match recv_output(channel, &mut self.watchers, &mut self.broadcasts).await? {
+                FenrisOutput::ObjectContentChunk(chunk) => {
+                    data.extend_from_slice(&chunk.data);
+                    if chunk.is_last {
+                        break;
+                    }
+                }
+                FenrisOutput::ObjectContent { data: content, .. } => {
+                    data = content;
+                    break;
+                }
+                error @ FenrisOutput::Error { .. } => return Ok(error),
+                output => {
+                    return Err(FenrisError::InvalidRequest(format!(
+                        "unexpected read response: {:?}",
+                        output
+                    )));
+                }
+            }
+        }
+
+        Ok(FenrisOutput::ObjectContent {
+            total_size: data.len() as u64,
+            data,
+            truncated: false,
+        })
+    }
+
     pub fn set_server_info(&mut self, server_info: ServerInfo) -> Result<()> {
         if self.is_connected() {
             tracing::error!("Cannot change server info while connected");
@@ -341,31 +997,268 @@ impl ConnectionManager {
         self.server_identity = Some(server_identity);
         Ok(())
     }
+
+    pub fn set_psk(&mut self, psk: String) -> Result<()> {
+        if self.is_connected() {
+            tracing::error!("Cannot change PSK while connected");
+            return Err(FenrisError::NetworkError(io::Error::other(
+                "Cannot change PSK while connected",
+            )));
+        }
+
+        self.psk = Some(psk);
+        Ok(())
+    }
+
+    /// TCP connections made after this run over TLS, checked against `tls.ca_pem_path`. The
+    /// secure channel handshake still happens inside the TLS session.
+    #[cfg(feature = "tls")]
+    pub fn set_tls(&mut self, tls: TlsClientConfig) -> Result<()> {
+        if self.is_connected() {
+            return Err(FenrisError::NetworkError(io::Error::other(
+                "Cannot change TLS settings while connected",
+            )));
+        }
+
+        self.tls = Some(tls);
+        Ok(())
+    }
+
+    pub fn set_credentials(&mut self, credentials: Credentials) -> Result<()> {
+        if self.is_connected() {
+            return Err(FenrisError::NetworkError(io::Error::other(
+                "Cannot change credentials while connected",
+            )));
+        }
+
+        self.credentials = Some(credentials);
+        Ok(())
+    }
+
+    /// Reads `login <user> <password>` and keeps the account for the next connection.
+    pub fn set_login(&mut self, command: &str) -> Result<Credentials> {
+        match self.request_manager.build_request(command)? {
+            ClientCommandPlan::Single(FenrisCommand::Login { username, password }) => {
+                let credentials = Credentials { username, password };
+                self.set_credentials(credentials.clone())?;
+                Ok(credentials)
+            }
+            _ => Err(FenrisError::InvalidRequest(
+                "expected login <user> <password>".to_string(),
+            )),
+        }
+    }
 }
 
-fn expect_transfer_progress(output: FenrisOutput) -> Result<()> {
-    match output {
-        FenrisOutput::TransferProgress { .. } => Ok(()),
-        FenrisOutput::Error { message } => Err(FenrisError::InvalidRequest(message)),
-        output => Err(FenrisError::InvalidRequest(format!(
-            "unexpected transfer response: {:?}",
-            output
-        ))),
+async fn client_login(channel: &mut ClientChannel, credentials: &Credentials) -> Result<()> {
+    debug!("Logging in as {}", credentials.username);
+    channel
+        .send_msg(&FenrisCommand::Login {
+            username: credentials.username.clone(),
+            password: credentials.password.clone(),
+        })
+        .await?;
+
+    match channel.recv_msg::<FenrisOutput>().await? {
+        FenrisOutput::Success { .. } => Ok(()),
+        FenrisOutput::Error { message } => Err(FenrisError::AuthenticationError(message)),
+        output => {
+            warn!("Unexpected login response: {:?}", output);
+            Err(FenrisError::InvalidProtocolMessage)
+        }
     }
 }
 
-impl Default for ConnectionManager {
-    fn default() -> Self {
-        Self::new(RequestManager, ResponseManager)
+/// Servers with a message of the day send it before answering anything, so waiting
+/// [`MOTD_WAIT`] for it is enough; a server without one sends nothing.
+async fn receive_banner(
+    channel: &mut ClientChannel,
+    broadcasts: &mut Vec<String>,
+) -> Result<Option<String>> {
+    let deadline = Instant::now() + MOTD_WAIT;
+    loop {
+        match tokio::time::timeout_at(deadline.into(), channel.recv_msg::<FenrisOutput>()).await {
+            Err(_) => return Ok(None),
+            Ok(Ok(FenrisOutput::Banner { message })) => return Ok(Some(message)),
+            Ok(Ok(FenrisOutput::Broadcast { message })) => broadcasts.push(message),
+            Ok(Ok(output)) => {
+                warn!("Unexpected message before the first request: {:?}", output);
+                return Err(FenrisError::InvalidProtocolMessage);
+            }
+            Ok(Err(e)) => return Err(e),
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
+fn correlate(request_id: u64, request: &FenrisCommand) -> FenrisCommand {
+    FenrisCommand::Correlated {
+        request_id,
+        command: Box::new(request.clone()),
+    }
+}
+
+/// Unwraps the response to `request_id`; anything else means the stream is out of step.
+fn answer_to(request_id: u64, output: FenrisOutput) -> Result<FenrisOutput> {
+    match output {
+        FenrisOutput::Correlated {
+            request_id: answered,
+            output,
+        } if answered == request_id => Ok(*output),
+        output => {
+            warn!("Response to request {} was {:?}", request_id, output);
+            Err(FenrisError::InvalidProtocolMessage)
+        }
+    }
+}
+
+async fn recv_output(
+    channel: &mut ClientChannel,
+    watchers: &mut HashMap<PathBuf, mpsc::Sender<WatchEvent>>,
+    broadcasts: &mut Vec<String>,
+) -> Result<FenrisOutput> {
+    loop {
+        match channel.recv_msg::<FenrisOutput>().await? {
+            FenrisOutput::WatchEvent(event) => dispatch_watch_event(watchers, event),
+            FenrisOutput::Broadcast { message } => broadcasts.push(message),
+            output => return Ok(output),
+        }
+    }
+}
+
+fn dispatch_watch_event(
+    watchers: &mut HashMap<PathBuf, mpsc::Sender<WatchEvent>>,
+    event: WatchEvent,
+) {
+    let Some(sender) = watchers.get(&event.subscription) else {
+        debug!("Ignoring watch event for {:?}", event.subscription);
+        return;
+    };
+
+    match sender.try_send(event) {
+        Ok(()) => {}
+        Err(mpsc::error::TrySendError::Full(event)) => {
+            warn!("Dropping watch event for {:?}: queue full", event.path);
+        }
+        Err(mpsc::error::TrySendError::Closed(event)) => {
+            watchers.remove(&event.subscription);
+        }
+    }
+}
+
+fn expect_watch_path(output: FenrisOutput) -> Result<PathBuf> {
+    match output {
+        FenrisOutput::Success { message } => Ok(PathBuf::from(message)),
+        FenrisOutput::Error { message } => Err(FenrisError::InvalidRequest(message)),
+        output => Err(FenrisError::InvalidRequest(format!(
+            "unexpected watch response: {:?}",
+            output
+        ))),
+    }
+}
+
+fn expect_transfer_progress(output: FenrisOutput) -> Result<()> {
+    match output {
+        FenrisOutput::TransferProgress { .. } => Ok(()),
+        FenrisOutput::Error { message } => Err(FenrisError::InvalidRequest(message)),
+        output => Err(FenrisError::InvalidRequest(format!(
+            "unexpected transfer response: {:?}",
+            output
+        ))),
+    }
+}
+
+/// Writes a successful response to the redirect target and replaces it with a short
+/// confirmation. Failed responses are returned untouched so the error stays visible.
+async fn write_redirect(
+    redirect: &OutputRedirect,
+    formatted: FormattedResponse,
+) -> Result<FormattedResponse> {
+    use tokio::io::AsyncWriteExt;
+
+    if !formatted.success {
+        return Ok(formatted);
+    }
+
+    let mut contents = formatted.message;
+    if let Some(details) = formatted.details {
+        contents.push('\n');
+        contents.push_str(&details);
+    }
+    if !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+
+    let path = &redirect.path;
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(redirect.append)
+        .truncate(!redirect.append)
+        .open(path)
+        .await
+        .map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to open {}: {}", path.display(), e), e)
+        })?;
+    let written = match file.write_all(contents.as_bytes()).await {
+        Ok(()) => file.flush().await,
+        Err(e) => Err(e),
+    };
+    written.map_err(|e| {
+        FenrisError::file_operation_from(format!("Failed to write {}: {}", path.display(), e), e)
+    })?;
+
+    Ok(FormattedResponse {
+        success: true,
+        message: format!(
+            "Output {} {} ({} bytes)",
+            if redirect.append {
+                "appended to"
+            } else {
+                "written to"
+            },
+            path.display(),
+            contents.len()
+        ),
+        details: None,
+        current_dir: formatted.current_dir,
+        details_format: DetailsFormat::Plain,
+        table_data: None,
+    })
+}
+
+impl Default for ConnectionManager {
+    fn default() -> Self {
+        Self::new(
+            RequestManager::default(),
+            ResponseManager::default().with_options(FormatterOptions::for_terminal()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
 
     use super::*;
+    use common::DefaultSecureChannel;
     use tokio::net::{TcpListener, TcpStream};
 
+    async fn recv_correlated(server: &mut DefaultSecureChannel) -> (u64, FenrisCommand) {
+        match server.recv_msg().await.unwrap() {
+            FenrisCommand::Correlated {
+                request_id,
+                command,
+            } => (request_id, *command),
+            command => panic!("request was not correlated: {:?}", command),
+        }
+    }
+
+    fn correlated(request_id: u64, output: FenrisOutput) -> FenrisOutput {
+        FenrisOutput::Correlated {
+            request_id,
+            output: Box::new(output),
+        }
+    }
+
     async fn connected_manager_and_server() -> (ConnectionManager, DefaultSecureChannel) {
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
         let addr = listener.local_addr().unwrap();
@@ -376,16 +1269,26 @@ mod tests {
         let client_stream = client_stream.unwrap();
         let (server_stream, _) = server_stream.unwrap();
 
-        let client = DefaultSecureChannel::client_handshake(client_stream);
+        let client = ClientChannel::client_handshake(client_stream.into());
         let server = DefaultSecureChannel::server_handshake(server_stream);
         let (client, server) = tokio::join!(client, server);
 
         let manager = ConnectionManager {
             server_info: None,
             server_identity: None,
+            psk: None,
+            credentials: None,
+            #[cfg(feature = "tls")]
+            tls: None,
             channel: Some(client.unwrap()),
-            request_manager: RequestManager,
-            response_manager: ResponseManager,
+            watchers: HashMap::new(),
+            broadcasts: Vec::new(),
+            server_motd: None,
+            latency_samples: VecDeque::new(),
+            keepalive: None,
+            health_check: None,
+            request_manager: RequestManager::default(),
+            response_manager: ResponseManager::default(),
         };
 
         (manager, server.unwrap())
@@ -394,8 +1297,8 @@ mod tests {
     #[test]
     fn test_connection_manager_creation() {
         let server_info = ServerInfo::new("127.0.0.1".to_string(), 8080);
-        let request_manager = RequestManager;
-        let response_manager = ResponseManager;
+        let request_manager = RequestManager::default();
+        let response_manager = ResponseManager::default();
 
         let mut manager = ConnectionManager::new(request_manager, response_manager);
         manager.set_server_info(server_info.clone()).unwrap();
@@ -409,13 +1312,24 @@ mod tests {
     #[test]
     fn test_connection_manager_stores_server_identity() {
         let identity = common::ServerIdentityKey::generate().public_key();
-        let mut manager = ConnectionManager::new(RequestManager, ResponseManager);
+        let mut manager =
+            ConnectionManager::new(RequestManager::default(), ResponseManager::default());
 
         manager.set_server_identity(identity).unwrap();
 
         assert_eq!(manager.server_identity, Some(identity));
     }
 
+    #[test]
+    fn test_connection_manager_stores_psk() {
+        let mut manager =
+            ConnectionManager::new(RequestManager::default(), ResponseManager::default());
+
+        manager.set_psk("token".to_string()).unwrap();
+
+        assert_eq!(manager.psk.as_deref(), Some("token"));
+    }
+
     #[test]
     fn test_server_info_to_socket_addr() {
         let info = ServerInfo::new("localhost".to_string(), 8080);
@@ -424,7 +1338,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_send_command_when_disconnected() {
-        let mut manager = ConnectionManager::new(RequestManager, ResponseManager);
+        let mut manager =
+            ConnectionManager::new(RequestManager::default(), ResponseManager::default());
 
         let result = manager.send_command("ping").await;
 
@@ -437,7 +1352,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_connect_requires_server_identity_before_network_connection() {
-        let mut manager = ConnectionManager::new(RequestManager, ResponseManager);
+        let mut manager =
+            ConnectionManager::new(RequestManager::default(), ResponseManager::default());
         manager
             .set_server_info(ServerInfo::new("127.0.0.1".to_string(), 9))
             .unwrap();
@@ -521,6 +1437,67 @@ mod tests {
         server_task.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_copy_object_reads_every_chunk_and_writes_it_back() {
+        let (mut manager, mut server) = connected_manager_and_server().await;
+
+        let server_task = tokio::spawn(async move {
+            let command: FenrisCommand = server.recv_msg().await.unwrap();
+            assert_eq!(
+                command,
+                FenrisCommand::ReadObject {
+                    path: PathBuf::from("/a/notes.txt")
+                }
+            );
+            for (offset, data, is_last) in [(0, b"abc", false), (3, b"def", true)] {
+                server
+                    .send_msg(&FenrisOutput::ObjectContentChunk(TransferChunk {
+                        offset,
+                        data: data.to_vec(),
+                        is_last,
+                        total_size: 6,
+                    }))
+                    .await
+                    .unwrap();
+            }
+
+            let command: FenrisCommand = server.recv_msg().await.unwrap();
+            assert_eq!(
+                command,
+                FenrisCommand::BeginObjectWrite {
+                    path: PathBuf::from("/b/notes.txt"),
+                    mode: ObjectWriteMode::Write,
+                    total_size: 6,
+                }
+            );
+            server
+                .send_msg(&FenrisOutput::TransferReady { chunk_size: 64 })
+                .await
+                .unwrap();
+
+            let command: FenrisCommand = server.recv_msg().await.unwrap();
+            assert!(matches!(
+                command,
+                FenrisCommand::WriteObjectChunk(TransferChunk { data, is_last: true, .. })
+                    if data == b"abcdef"
+            ));
+            server
+                .send_msg(&FenrisOutput::Success {
+                    message: "done".to_string(),
+                })
+                .await
+                .unwrap();
+        });
+
+        let output = manager
+            .copy_object(PathBuf::from("/a/notes.txt"), PathBuf::from("/b/notes.txt"))
+            .await
+            .unwrap();
+
+        assert!(matches!(output, FenrisOutput::Success { .. }));
+        server_task.await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_execute_plan_collects_chunked_read_preview() {
         let (mut manager, mut server) = connected_manager_and_server().await;
@@ -571,4 +1548,483 @@ mod tests {
         );
         server_task.await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_watch_events_are_routed_while_awaiting_responses() {
+        let (mut manager, mut server) = connected_manager_and_server().await;
+
+        let server_task = tokio::spawn(async move {
+            let (request_id, command) = recv_correlated(&mut server).await;
+            assert_eq!(
+                command,
+                FenrisCommand::Subscribe {
+                    path: PathBuf::from("docs")
+                }
+            );
+            server
+                .send_msg(&correlated(
+                    request_id,
+                    FenrisOutput::Success {
+                        message: "/docs".to_string(),
+                    },
+                ))
+                .await
+                .unwrap();
+
+            let (request_id, command) = recv_correlated(&mut server).await;
+            assert_eq!(command, FenrisCommand::Ping);
+            server
+                .send_msg(&FenrisOutput::WatchEvent(WatchEvent {
+                    path: PathBuf::from("/docs/a.txt"),
+                    kind: common::WatchEventKind::Created,
+                    subscription: PathBuf::from("/docs"),
+                }))
+                .await
+                .unwrap();
+            server
+                .send_msg(&correlated(request_id, FenrisOutput::Pong))
+                .await
+                .unwrap();
+        });
+
+        let mut events = manager.subscribe("docs").await.unwrap();
+        let output = manager.send_command("ping").await.unwrap();
+
+        assert!(output.success);
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.path, PathBuf::from("/docs/a.txt"));
+        assert_eq!(event.kind, common::WatchEventKind::Created);
+        server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_command_redirects_output_to_a_local_file() {
+        let (mut manager, mut server) = connected_manager_and_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("out.txt");
+
+        let server_task = tokio::spawn(async move {
+            for message in ["first", "second", "denied"] {
+                let (request_id, _) = recv_correlated(&mut server).await;
+                let output = if message == "denied" {
+                    FenrisOutput::Error {
+                        message: message.to_string(),
+                    }
+                } else {
+                    FenrisOutput::Success {
+                        message: message.to_string(),
+                    }
+                };
+                server
+                    .send_msg(&correlated(request_id, output))
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let command = format!("mkdir a > {}", target.display());
+        let output = manager.send_command(&command).await.unwrap();
+        assert!(output.success);
+        assert!(output.message.contains("written to"));
+
+        let command = format!("mkdir b >> {}", target.display());
+        manager.send_command(&command).await.unwrap();
+        let contents = std::fs::read_to_string(&target).unwrap();
+        assert!(contents.contains("first") && contents.contains("second"));
+
+        let output = manager.send_command(&command).await.unwrap();
+        assert!(!output.success);
+        assert!(!std::fs::read_to_string(&target).unwrap().contains("denied"));
+        server_task.await.unwrap();
+
+        let missing = dir.path().join("missing").join("out.txt");
+        let result = write_redirect(
+            &OutputRedirect {
+                path: missing,
+                append: false,
+            },
+            manager
+                .response_manager
+                .format_response(&FenrisOutput::Pong),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(FenrisError::FileOperationError { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_broadcasts_are_collected_while_awaiting_responses() {
+        let (mut manager, mut server) = connected_manager_and_server().await;
+
+        let server_task = tokio::spawn(async move {
+            let (request_id, command) = recv_correlated(&mut server).await;
+            assert_eq!(command, FenrisCommand::Ping);
+            server
+                .send_msg(&FenrisOutput::Broadcast {
+                    message: "restarting in 5 minutes".to_string(),
+                })
+                .await
+                .unwrap();
+            server
+                .send_msg(&correlated(request_id, FenrisOutput::Pong))
+                .await
+                .unwrap();
+        });
+
+        let output = manager.send_command("ping").await.unwrap();
+
+        assert!(output.success);
+        assert_eq!(manager.take_broadcasts(), vec!["restarting in 5 minutes"]);
+        assert!(manager.take_broadcasts().is_empty());
+        server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_concurrent_pipelines_requests_and_keeps_channel_usable() {
+        let (mut manager, mut server) = connected_manager_and_server().await;
+
+        let server_task = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for _ in 0..3 {
+                requests.push(recv_correlated(&mut server).await);
+            }
+            for (request_id, _) in &requests {
+                server
+                    .send_msg(&correlated(*request_id, FenrisOutput::Pong))
+                    .await
+                    .unwrap();
+            }
+            let (request_id, command) = recv_correlated(&mut server).await;
+            server
+                .send_msg(&correlated(request_id, FenrisOutput::Pong))
+                .await
+                .unwrap();
+            requests.push((request_id, command));
+            requests
+        });
+
+        let responses = manager
+            .send_concurrent(&["ping", "ping", "ping"])
+            .await
+            .unwrap();
+        assert_eq!(responses.len(), 3);
+        assert!(responses.iter().all(|response| response.success));
+
+        assert!(manager.send_command("ping").await.unwrap().success);
+        assert_eq!(
+            server_task.await.unwrap(),
+            vec![
+                (1, FenrisCommand::Ping),
+                (2, FenrisCommand::Ping),
+                (3, FenrisCommand::Ping),
+                (4, FenrisCommand::Ping),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_latency_stats_summarize_samples() {
+        assert_eq!(
+            LatencyStats::from_samples(&VecDeque::new()),
+            LatencyStats::default()
+        );
+
+        let samples: VecDeque<Duration> = (1..=100).rev().map(Duration::from_millis).collect();
+        let stats = LatencyStats::from_samples(&samples);
+
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.max, Duration::from_millis(100));
+        assert_eq!(stats.avg, Duration::from_micros(50_500));
+        assert_eq!(stats.p99, Duration::from_millis(99));
+    }
+
+    #[tokio::test]
+    async fn test_ping_records_round_trip_and_keeps_the_last_samples() {
+        let (mut manager, mut server) = connected_manager_and_server().await;
+        manager
+            .latency_samples
+            .extend(std::iter::repeat_n(Duration::ZERO, LATENCY_SAMPLE_LIMIT));
+
+        let server_task = tokio::spawn(async move {
+            for _ in 0..2 {
+                let (request_id, command) = recv_correlated(&mut server).await;
+                assert_eq!(command, FenrisCommand::Ping);
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                server
+                    .send_msg(&correlated(request_id, FenrisOutput::Pong))
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let rtt = manager.ping().await.unwrap();
+        let output = manager.send_command("ping").await.unwrap();
+
+        assert!(rtt >= Duration::from_millis(5));
+        assert!(output.message.ends_with(" ms)"), "{}", output.message);
+        assert!(
+            output
+                .details
+                .unwrap()
+                .starts_with("Last 100 pings: min 0.00 ms")
+        );
+        assert_eq!(manager.latency_samples.len(), LATENCY_SAMPLE_LIMIT);
+        assert!(manager.latency_stats().max >= Duration::from_millis(5));
+        assert!(manager.latest_latency().unwrap() >= Duration::from_millis(5));
+        server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_keepalive_disconnects_after_three_missed_pings() {
+        let (mut manager, mut server) = connected_manager_and_server().await;
+        let interval = Duration::from_millis(20);
+        manager.enable_keepalive(interval);
+
+        let server_task = tokio::spawn(async move {
+            let mut pings = 0;
+            while let Ok(FenrisCommand::Correlated { .. }) = server.recv_msg().await {
+                pings += 1;
+            }
+            pings
+        });
+
+        for _ in 0..KEEPALIVE_MAX_MISSED - 1 {
+            tokio::time::sleep(interval).await;
+            manager.poll_keepalive().await.unwrap();
+            assert!(manager.is_connected());
+        }
+        tokio::time::sleep(interval).await;
+        let result = manager.poll_keepalive().await;
+
+        assert!(matches!(result, Err(FenrisError::ConnectionClosed)));
+        assert!(!manager.is_connected());
+        assert_eq!(server_task.await.unwrap(), KEEPALIVE_MAX_MISSED);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_reports_lost_connection_after_failed_pings() {
+        let (mut manager, mut server) = connected_manager_and_server().await;
+        let interval = Duration::from_millis(20);
+        let _timer = manager.start_health_check(interval, 2);
+
+        // The server reads pings but never answers them.
+        let server_task = tokio::spawn(async move {
+            let mut pings = 0;
+            while let Ok(FenrisCommand::Correlated { .. }) = server.recv_msg().await {
+                pings += 1;
+            }
+            pings
+        });
+
+        tokio::time::sleep(interval * 2).await;
+        assert_eq!(manager.poll_health_check().await, None);
+        assert!(manager.is_connected());
+        tokio::time::sleep(interval * 2).await;
+        assert_eq!(manager.poll_health_check().await, Some(HealthEvent::Lost));
+
+        assert!(!manager.is_connected());
+        assert_eq!(server_task.await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_stops_the_health_check() {
+        let (mut manager, _server) = connected_manager_and_server().await;
+        let timer = manager.start_health_check(Duration::from_millis(20), 3);
+
+        manager.disconnect().await;
+
+        assert!(timer.await.unwrap_err().is_cancelled());
+        assert_eq!(manager.poll_health_check().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_late_response_to_an_abandoned_request_is_dropped() {
+        let (mut manager, mut server) = connected_manager_and_server().await;
+
+        let server_task = tokio::spawn(async move {
+            let (abandoned, _) = recv_correlated(&mut server).await;
+            let (request_id, _) = recv_correlated(&mut server).await;
+            server
+                .send_msg(&correlated(abandoned, FenrisOutput::Pong))
+                .await
+                .unwrap();
+            server
+                .send_msg(&correlated(request_id, FenrisOutput::Pong))
+                .await
+                .unwrap();
+        });
+
+        let abandoned = tokio::time::timeout(Duration::from_millis(20), manager.ping()).await;
+        assert!(abandoned.is_err());
+        manager.ping().await.unwrap();
+
+        assert_eq!(manager.latency_samples.len(), 1);
+        server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_response_to_another_request_is_rejected() {
+        let (mut manager, mut server) = connected_manager_and_server().await;
+
+        let server_task = tokio::spawn(async move {
+            let (request_id, _) = recv_correlated(&mut server).await;
+            server
+                .send_msg(&correlated(request_id + 1, FenrisOutput::Pong))
+                .await
+                .unwrap();
+        });
+
+        let result = manager.send_command("ping").await;
+
+        assert!(matches!(result, Err(FenrisError::InvalidProtocolMessage)));
+        server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_concurrent_rejects_batch_with_invalid_command() {
+        let (mut manager, mut server) = connected_manager_and_server().await;
+
+        let result = manager.send_concurrent(&["ping", "bogus"]).await;
+
+        assert!(result.is_err());
+        assert!(manager.is_connected());
+        manager.disconnect().await;
+        assert!(server.recv_msg::<FenrisCommand>().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_requires_psk() {
+        let (mut manager, _server) = connected_manager_and_server().await;
+
+        let result = manager.send_command("broadcast maintenance soon").await;
+
+        assert!(matches!(result, Err(FenrisError::AuthenticationError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_connect_keeps_the_server_motd() {
+        let identity = common::ServerIdentityKey::generate();
+        let public_key = identity.public_key();
+        let config = server::ServerConfig::builder()
+            .motd("Maintenance at 02:00 UTC".to_string())
+            .build()
+            .unwrap();
+        let (server, handle) = server::Server::bind_authenticated(
+            "127.0.0.1:0",
+            std::sync::Arc::new(common::MemoryStorage::new()),
+            std::sync::Arc::new(identity),
+            config,
+        )
+        .await
+        .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let mut manager = ConnectionManager::with_server_identity(
+            RequestManager::default(),
+            ResponseManager::default(),
+            public_key,
+        );
+        manager
+            .set_server_info(ServerInfo::new(addr.ip().to_string(), addr.port()))
+            .unwrap();
+        manager.connect().await.unwrap();
+
+        assert_eq!(manager.server_motd(), Some("Maintenance at 02:00 UTC"));
+        let pong = manager.send_command("ping").await.unwrap();
+        handle.shutdown();
+
+        assert!(pong.success);
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_tls_connection_round_trips_through_a_tls_server() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_path = dir.path().join("server.crt");
+        let key_path = dir.path().join("server.key");
+        std::fs::write(&cert_path, certified.cert.pem()).unwrap();
+        std::fs::write(&key_path, certified.signing_key.serialize_pem()).unwrap();
+
+        let identity = common::ServerIdentityKey::generate();
+        let public_key = identity.public_key();
+        let config = server::ServerConfig::builder()
+            .tls(common::TlsConfig::new(&cert_path, &key_path))
+            .build()
+            .unwrap();
+        let (server, handle) = server::Server::bind_authenticated(
+            "127.0.0.1:0",
+            std::sync::Arc::new(common::MemoryStorage::new()),
+            std::sync::Arc::new(identity),
+            config,
+        )
+        .await
+        .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let mut manager = ConnectionManager::with_server_identity(
+            RequestManager::default(),
+            ResponseManager::default(),
+            public_key,
+        );
+        manager
+            .set_server_info(ServerInfo::new(addr.ip().to_string(), addr.port()))
+            .unwrap();
+        manager
+            .set_tls(TlsClientConfig::new(&cert_path).with_server_name("localhost"))
+            .unwrap();
+        manager.connect().await.unwrap();
+
+        let written = manager.send_command("write tls.txt hello").await.unwrap();
+        let read = manager.send_command("read tls.txt").await.unwrap();
+        handle.shutdown();
+
+        assert!(written.success);
+        assert_eq!(read.details.as_deref(), Some("hello"));
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_runs_stages_against_a_real_server() {
+        let identity = common::ServerIdentityKey::generate();
+        let public_key = identity.public_key();
+        let (server, handle) = server::Server::bind_authenticated(
+            "127.0.0.1:0",
+            std::sync::Arc::new(common::MemoryStorage::new()),
+            std::sync::Arc::new(identity),
+            server::ServerConfig::default(),
+        )
+        .await
+        .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let mut manager = ConnectionManager::with_server_identity(
+            RequestManager::default(),
+            ResponseManager::default(),
+            public_key,
+        );
+        manager
+            .set_server_info(ServerInfo::new(addr.ip().to_string(), addr.port()))
+            .unwrap();
+        manager.connect().await.unwrap();
+
+        let written = manager
+            .send_command("write test.txt hello | read test.txt")
+            .await
+            .unwrap();
+        let copied = manager
+            .send_command("read test.txt | write copy.txt")
+            .await
+            .unwrap();
+        let copy = manager.send_command("read copy.txt").await.unwrap();
+        handle.shutdown();
+
+        assert_eq!(written.details.as_deref(), Some("hello"));
+        assert!(copied.success);
+        assert_eq!(copy.details.as_deref(), Some("hello"));
+    }
 }
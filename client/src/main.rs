@@ -1,17 +1,30 @@
 mod app;
 mod batch;
+mod bookmarks;
 mod client;
 mod connection_manager;
+mod manifest;
+mod non_interactive;
 mod request_manager;
 mod response_manager;
+mod script;
+mod session_log;
+mod transfers;
 mod ui;
+mod workspace;
 
 use anyhow::Result;
 use batch::{BatchConfig, BatchOutputFormat};
 use clap::{Args as ClapArgs, Parser, Subcommand};
 use client::TuiClient;
 use common::ServerIdentityPublicKey;
+#[cfg(feature = "tls")]
+use common::TlsClientConfig;
+use connection_manager::Credentials;
+use non_interactive::{NonInteractiveConfig, OutputFormat};
+use std::path::PathBuf;
 use std::process::ExitCode;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[command(name = "fenris-client")]
@@ -20,6 +33,81 @@ struct Args {
     #[arg(long)]
     server_identity: String,
 
+    #[arg(long)]
+    psk: Option<String>,
+
+    /// Log in as this user on servers started with a users file.
+    #[arg(long, requires = "password")]
+    user: Option<String>,
+
+    #[arg(long, requires = "user")]
+    password: Option<String>,
+
+    #[arg(long)]
+    non_interactive: bool,
+
+    /// Ping the server every this many seconds in the TUI, disconnecting after 3 missed pings.
+    #[arg(
+        long,
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with = "non_interactive"
+    )]
+    keepalive: Option<u64>,
+
+    /// Health-check the connection every this many seconds in the TUI, reconnecting in the
+    /// background after 3 failed pings.
+    #[arg(
+        long,
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with = "non_interactive"
+    )]
+    health_check: Option<u64>,
+
+    /// Record every command and response in the TUI to this file, or to
+    /// `~/.fenris_sessions/<timestamp>.log` when no path is given.
+    #[arg(long, value_name = "PATH", num_args = 0..=1, conflicts_with = "non_interactive")]
+    session_log: Option<Option<PathBuf>>,
+
+    /// Log file contents shown by `read` as `[REDACTED: N bytes]`.
+    #[arg(long, requires = "session_log")]
+    redact_session_log: bool,
+
+    /// Click to select and double-click to open entries in the TUI's split view; right-click
+    /// for a context menu. Takes over the terminal's own mouse selection.
+    #[arg(long, conflicts_with = "non_interactive")]
+    mouse: bool,
+
+    #[arg(long, default_value = "127.0.0.1", requires = "non_interactive")]
+    address: String,
+
+    #[arg(long, default_value_t = 5555, requires = "non_interactive")]
+    port: u16,
+
+    /// Connect through a Unix domain socket instead of `--address`/`--port`.
+    #[arg(long, requires = "non_interactive")]
+    unix_socket: Option<PathBuf>,
+
+    /// Connect over TLS, trusting the CA certificates in this PEM file.
+    #[cfg(feature = "tls")]
+    #[arg(long, value_name = "CA_PEM")]
+    tls: Option<PathBuf>,
+
+    /// Name to check the server's TLS certificate against instead of the host dialled.
+    #[cfg(feature = "tls")]
+    #[arg(long, requires = "tls")]
+    tls_server_name: Option<String>,
+
+    /// Print each response as a JSON object instead of text.
+    #[arg(
+        long,
+        value_enum,
+        default_value = "human",
+        requires = "non_interactive"
+    )]
+    output: OutputFormat,
+
     #[command(subcommand)]
     mode: Option<ClientMode>,
 }
@@ -38,11 +126,17 @@ struct BatchArgs {
     #[arg(long, default_value_t = 5555)]
     port: u16,
 
+    #[arg(long)]
+    unix_socket: Option<PathBuf>,
+
     #[arg(long)]
     commands_file: String,
 
     #[arg(long, value_enum, default_value = "human")]
     output: BatchOutputFormat,
+
+    #[arg(long)]
+    pipeline: bool,
 }
 
 #[tokio::main]
@@ -55,16 +149,75 @@ async fn main() -> Result<ExitCode> {
         .with_ansi(false)
         .init();
 
+    #[cfg(feature = "tls")]
+    let tls = args.tls.map(|ca_pem_path| {
+        let tls = TlsClientConfig::new(ca_pem_path);
+        match args.tls_server_name {
+            Some(server_name) => tls.with_server_name(server_name),
+            None => tls,
+        }
+    });
+    let psk = args.psk;
+    let credentials = args
+        .user
+        .zip(args.password)
+        .map(|(username, password)| Credentials { username, password });
+
+    if args.non_interactive {
+        let success = non_interactive::run_non_interactive(
+            NonInteractiveConfig {
+                address: args.address,
+                port: args.port,
+                unix_socket: args.unix_socket,
+                psk,
+                credentials,
+                #[cfg(feature = "tls")]
+                tls,
+                output: args.output,
+            },
+            server_identity,
+        )
+        .await?;
+
+        return Ok(if success {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+
     match args.mode.unwrap_or(ClientMode::Tui) {
-        ClientMode::Tui => run_tui(server_identity).await?,
+        ClientMode::Tui => {
+            run_tui(
+                server_identity,
+                psk,
+                credentials,
+                TuiOptions {
+                    keepalive: args.keepalive.map(Duration::from_secs),
+                    health_check: args.health_check.map(Duration::from_secs),
+                    session_log: args.session_log,
+                    redact_session_log: args.redact_session_log,
+                    mouse: args.mouse,
+                    #[cfg(feature = "tls")]
+                    tls,
+                },
+            )
+            .await?
+        }
         ClientMode::Batch(args) => {
             let commands = batch::read_commands_from_source(&args.commands_file)?;
             let summary = batch::run_batch(
                 BatchConfig {
                     address: args.address,
                     port: args.port,
+                    unix_socket: args.unix_socket,
                     commands,
                     output: args.output,
+                    psk,
+                    credentials,
+                    #[cfg(feature = "tls")]
+                    tls,
+                    pipeline: args.pipeline,
                 },
                 server_identity,
             )
@@ -79,10 +232,46 @@ async fn main() -> Result<ExitCode> {
     Ok(ExitCode::SUCCESS)
 }
 
-async fn run_tui(server_identity: ServerIdentityPublicKey) -> Result<()> {
-    let mut terminal = ui::terminal::init()?;
+/// Flags that only apply to the TUI.
+struct TuiOptions {
+    keepalive: Option<Duration>,
+    health_check: Option<Duration>,
+    session_log: Option<Option<PathBuf>>,
+    redact_session_log: bool,
+    mouse: bool,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsClientConfig>,
+}
 
+async fn run_tui(
+    server_identity: ServerIdentityPublicKey,
+    psk: Option<String>,
+    credentials: Option<Credentials>,
+    options: TuiOptions,
+) -> Result<()> {
     let mut client = TuiClient::with_server_identity(server_identity);
+    if let Some(psk) = psk {
+        client.set_psk(psk)?;
+    }
+    if let Some(credentials) = credentials {
+        client.set_credentials(credentials)?;
+    }
+    #[cfg(feature = "tls")]
+    if let Some(tls) = options.tls {
+        client.set_tls(tls)?;
+    }
+    if let Some(interval) = options.keepalive {
+        client.set_keepalive(interval);
+    }
+    if let Some(interval) = options.health_check {
+        client.set_health_check(interval);
+    }
+    if let Some(path) = options.session_log {
+        client.set_session_log(path, options.redact_session_log)?;
+    }
+    client.set_mouse_enabled(options.mouse);
+
+    let mut terminal = ui::terminal::init(options.mouse)?;
     let result = client.run(&mut terminal).await;
 
     ui::terminal::restore()?;
@@ -113,6 +302,64 @@ mod tests {
         assert!(args.mode.is_none());
     }
 
+    #[test]
+    fn args_reject_zero_keepalive() {
+        let identity = common::ServerIdentityKey::generate().public_key();
+        let hex = identity.to_hex();
+
+        let args = Args::try_parse_from([
+            "fenris-client",
+            "--server-identity",
+            &hex,
+            "--keepalive",
+            "15",
+        ])
+        .unwrap();
+        assert_eq!(args.keepalive, Some(15));
+        assert!(
+            Args::try_parse_from([
+                "fenris-client",
+                "--server-identity",
+                &hex,
+                "--keepalive",
+                "0"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn args_parse_session_log_with_and_without_path() {
+        let identity = common::ServerIdentityKey::generate().public_key();
+        let hex = identity.to_hex();
+
+        let args = Args::try_parse_from([
+            "fenris-client",
+            "--server-identity",
+            &hex,
+            "--session-log",
+            "audit.log",
+            "--redact-session-log",
+        ])
+        .unwrap();
+        assert_eq!(args.session_log, Some(Some(PathBuf::from("audit.log"))));
+        assert!(args.redact_session_log);
+
+        let args =
+            Args::try_parse_from(["fenris-client", "--server-identity", &hex, "--session-log"])
+                .unwrap();
+        assert_eq!(args.session_log, Some(None));
+        assert!(
+            Args::try_parse_from([
+                "fenris-client",
+                "--server-identity",
+                &hex,
+                "--redact-session-log"
+            ])
+            .is_err()
+        );
+    }
+
     #[test]
     fn args_parse_tui_subcommand() {
         let identity = common::ServerIdentityKey::generate().public_key();
@@ -149,6 +396,7 @@ mod tests {
         assert_eq!(batch.port, 5555);
         assert_eq!(batch.commands_file, "commands.txt");
         assert_eq!(batch.output, BatchOutputFormat::Human);
+        assert!(!batch.pipeline);
     }
 
     #[test]
@@ -168,6 +416,7 @@ mod tests {
             "6000",
             "--output",
             "jsonl",
+            "--pipeline",
         ])
         .unwrap();
 
@@ -178,6 +427,77 @@ mod tests {
         assert_eq!(batch.port, 6000);
         assert_eq!(batch.commands_file, "-");
         assert_eq!(batch.output, BatchOutputFormat::Jsonl);
+        assert!(batch.pipeline);
+    }
+
+    #[test]
+    fn args_parse_non_interactive_connection() {
+        let identity = common::ServerIdentityKey::generate().public_key();
+
+        let args = Args::try_parse_from([
+            "fenris-client",
+            "--server-identity",
+            &identity.to_hex(),
+            "--non-interactive",
+            "--address",
+            "localhost",
+            "--port",
+            "6000",
+            "--output",
+            "json",
+        ])
+        .unwrap();
+
+        assert!(args.non_interactive);
+        assert_eq!(args.address, "localhost");
+        assert_eq!(args.port, 6000);
+        assert_eq!(args.output, OutputFormat::Json);
+    }
+
+    #[test]
+    fn args_reject_address_without_non_interactive() {
+        let identity = common::ServerIdentityKey::generate().public_key();
+
+        assert!(
+            Args::try_parse_from([
+                "fenris-client",
+                "--server-identity",
+                &identity.to_hex(),
+                "--port",
+                "6000",
+            ])
+            .is_err()
+        );
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn args_parse_tls_options() {
+        let identity = common::ServerIdentityKey::generate().public_key();
+        let hex = identity.to_hex();
+
+        let args = Args::try_parse_from([
+            "fenris-client",
+            "--server-identity",
+            &hex,
+            "--tls",
+            "ca.pem",
+            "--tls-server-name",
+            "files.example.com",
+        ])
+        .unwrap();
+        assert_eq!(args.tls, Some(PathBuf::from("ca.pem")));
+        assert_eq!(args.tls_server_name.as_deref(), Some("files.example.com"));
+        assert!(
+            Args::try_parse_from([
+                "fenris-client",
+                "--server-identity",
+                &hex,
+                "--tls-server-name",
+                "files.example.com"
+            ])
+            .is_err()
+        );
     }
 
     #[test]
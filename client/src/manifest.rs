@@ -0,0 +1,146 @@
+use common::{FenrisError, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Files by path relative to the directory the manifest was taken of.
+pub type Manifest = Vec<(PathBuf, [u8; 32])>;
+
+/// Paths that differ between a local manifest and the server's.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ManifestDiff {
+    /// Listed locally but not on the server.
+    pub missing: Vec<PathBuf>,
+    /// On the server but not listed locally.
+    pub unexpected: Vec<PathBuf>,
+    /// On both sides with different digests.
+    pub mismatched: Vec<PathBuf>,
+}
+
+impl ManifestDiff {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+/// `<hex digest>  <path>`, the line `sha256sum` writes and `sha256sum -c` reads.
+pub fn format_line(path: &Path, digest: &[u8; 32]) -> String {
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}  {}", hex, path.to_string_lossy())
+}
+
+/// Reads `sha256sum` output, e.g. from `find . -type f -exec sha256sum {} +`. Blank lines are
+/// skipped and the `*` binary marker and a leading `./` are dropped from paths.
+pub fn parse(text: &str) -> Result<Manifest> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            parse_line(line).ok_or_else(|| {
+                FenrisError::InvalidRequest(format!(
+                    "line {} is not a sha256sum entry: {}",
+                    index + 1,
+                    line
+                ))
+            })
+        })
+        .collect()
+}
+
+fn parse_line(line: &str) -> Option<(PathBuf, [u8; 32])> {
+    let (hex, rest) = line.split_at_checked(64)?;
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let path = rest
+        .strip_prefix("  ")
+        .or_else(|| rest.strip_prefix(" *"))?;
+    let path = path.strip_prefix("./").unwrap_or(path);
+    if path.is_empty() {
+        return None;
+    }
+
+    let mut digest = [0u8; 32];
+    for (byte, pair) in digest.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some((PathBuf::from(path), digest))
+}
+
+/// Each list comes out in the order of the manifest it was found in.
+pub fn compare(local: &[(PathBuf, [u8; 32])], remote: &[(PathBuf, [u8; 32])]) -> ManifestDiff {
+    let remote_digests: HashMap<&PathBuf, &[u8; 32]> =
+        remote.iter().map(|(path, digest)| (path, digest)).collect();
+    let local_digests: HashMap<&PathBuf, &[u8; 32]> =
+        local.iter().map(|(path, digest)| (path, digest)).collect();
+
+    let mut diff = ManifestDiff::default();
+    for (path, digest) in local {
+        match remote_digests.get(path) {
+            None => diff.missing.push(path.clone()),
+            Some(remote_digest) if *remote_digest != digest => diff.mismatched.push(path.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.unexpected = remote
+        .iter()
+        .filter(|(path, _)| !local_digests.contains_key(path))
+        .map(|(path, _)| path.clone())
+        .collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    fn digest(byte: u8) -> [u8; 32] {
+        [byte; 32]
+    }
+
+    #[test]
+    fn test_parse_reads_what_format_line_writes() {
+        let line = format_line(Path::new("2024/beach.jpg"), &digest(0xab));
+        let text = format!("{}\n\n{}  ./notes.txt\n{} *raw.bin\n", line, ABC, ABC);
+
+        let manifest = parse(&text).unwrap();
+
+        assert_eq!(manifest.len(), 3);
+        assert_eq!(manifest[0], (PathBuf::from("2024/beach.jpg"), digest(0xab)));
+        assert_eq!(manifest[1].0, PathBuf::from("notes.txt"));
+        assert_eq!(manifest[1].1[..2], [0xba, 0x78]);
+        assert_eq!(manifest[2].0, PathBuf::from("raw.bin"));
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_lines() {
+        assert!(parse("not a manifest").is_err());
+        assert!(parse(&format!("{} notes.txt", ABC)).is_err());
+        assert!(parse(&format!("{}  notes.txt", ABC.replace('a', "g"))).is_err());
+    }
+
+    #[test]
+    fn test_compare_reports_missing_unexpected_and_mismatched_files() {
+        let local = vec![
+            (PathBuf::from("a.txt"), digest(1)),
+            (PathBuf::from("b.txt"), digest(2)),
+            (PathBuf::from("c.txt"), digest(3)),
+        ];
+        let remote = vec![
+            (PathBuf::from("a.txt"), digest(1)),
+            (PathBuf::from("b.txt"), digest(9)),
+            (PathBuf::from("d.txt"), digest(4)),
+        ];
+
+        assert!(compare(&local, &local).is_clean());
+        assert_eq!(
+            compare(&local, &remote),
+            ManifestDiff {
+                missing: vec![PathBuf::from("c.txt")],
+                unexpected: vec![PathBuf::from("d.txt")],
+                mismatched: vec![PathBuf::from("b.txt")],
+            }
+        );
+    }
+}
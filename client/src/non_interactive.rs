@@ -0,0 +1,217 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use common::ServerIdentityPublicKey;
+#[cfg(feature = "tls")]
+use common::TlsClientConfig;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+
+use crate::batch::should_abort;
+use crate::connection_manager::{ConnectionManager, Credentials, ServerInfo};
+use crate::response_manager::{DetailsFormat, FormattedResponse, colorize_diff_ansi};
+
+#[derive(Debug, Clone)]
+pub struct NonInteractiveConfig {
+    pub address: String,
+    pub port: u16,
+    pub unix_socket: Option<PathBuf>,
+    pub psk: Option<String>,
+    pub credentials: Option<Credentials>,
+    #[cfg(feature = "tls")]
+    pub tls: Option<TlsClientConfig>,
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    /// One JSON object per response; see `FormattedResponse::to_json`.
+    Json,
+}
+
+pub async fn run_non_interactive(
+    config: NonInteractiveConfig,
+    server_identity: ServerIdentityPublicKey,
+) -> Result<bool> {
+    let mut manager = ConnectionManager::with_server_identity(
+        crate::request_manager::RequestManager::default(),
+        crate::response_manager::ResponseManager::default(),
+        server_identity,
+    );
+    manager.set_server_info(ServerInfo::new(config.address, config.port))?;
+    if let Some(psk) = config.psk {
+        manager.set_psk(psk)?;
+    }
+    if let Some(credentials) = config.credentials {
+        manager.set_credentials(credentials)?;
+    }
+    #[cfg(feature = "tls")]
+    if let Some(tls) = config.tls {
+        manager.set_tls(tls)?;
+    }
+    manager.connect_via(config.unix_socket.as_deref()).await?;
+
+    let stdin = BufReader::new(tokio::io::stdin());
+    let mut stdout = io::stdout();
+    let mut stderr = io::stderr();
+    let result = run_lines(&mut manager, stdin, config.output, &mut stdout, &mut stderr).await;
+
+    manager.disconnect().await;
+    result
+}
+
+async fn run_lines<R, W, E>(
+    manager: &mut ConnectionManager,
+    reader: R,
+    output: OutputFormat,
+    stdout: &mut W,
+    stderr: &mut E,
+) -> Result<bool>
+where
+    R: AsyncBufRead + Unpin,
+    W: Write,
+    E: Write,
+{
+    let mut lines = reader.lines();
+    let mut success = true;
+
+    while let Some(line) = lines.next_line().await? {
+        let command = line.trim();
+        if command.is_empty() {
+            continue;
+        }
+        if command == "exit" || command == "quit" {
+            break;
+        }
+
+        match manager.send_command(command).await {
+            Ok(response) if response.success => write_response(stdout, output, &response)?,
+            Ok(response) => {
+                success = false;
+                write_response(stderr, output, &response)?;
+            }
+            Err(error) => {
+                success = false;
+                match output {
+                    OutputFormat::Human => writeln!(stderr, "{}", error)?,
+                    OutputFormat::Json => writeln!(
+                        stderr,
+                        "{}",
+                        serde_json::json!({
+                            "success": false,
+                            "message": error.to_string(),
+                            "details": null,
+                            "current_dir": null,
+                        })
+                    )?,
+                }
+                if should_abort(&error) {
+                    break;
+                }
+            }
+        }
+        stdout.flush()?;
+    }
+
+    Ok(success)
+}
+
+fn write_response<W: Write>(
+    writer: &mut W,
+    output: OutputFormat,
+    response: &FormattedResponse,
+) -> Result<()> {
+    if output == OutputFormat::Json {
+        writeln!(writer, "{}", response.to_json())?;
+        return Ok(());
+    }
+
+    writeln!(writer, "{}", response.message)?;
+
+    match (&response.details, response.details_format) {
+        (Some(details), DetailsFormat::Diff) => write!(writer, "{}", colorize_diff_ansi(details))?,
+        (Some(details), DetailsFormat::Plain) => writeln!(writer, "{}", details)?,
+        (None, _) => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_response_includes_details() {
+        let response = FormattedResponse {
+            success: true,
+            message: "File content (5 bytes):".to_string(),
+            details: Some("hello".to_string()),
+            current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        };
+        let mut output = Vec::new();
+
+        write_response(&mut output, OutputFormat::Human, &response).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "File content (5 bytes):\nhello\n"
+        );
+    }
+
+    #[test]
+    fn write_response_as_json_is_one_line() {
+        let response = FormattedResponse {
+            success: true,
+            message: "Changed directory".to_string(),
+            details: None,
+            current_dir: Some("/docs".to_string()),
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        };
+        let mut output = Vec::new();
+
+        write_response(&mut output, OutputFormat::Json, &response).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 1);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&output).unwrap(),
+            serde_json::json!({
+                "success": true,
+                "message": "Changed directory",
+                "details": null,
+                "current_dir": "/docs",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn run_lines_stops_at_exit_without_sending() {
+        let mut manager = ConnectionManager::new(
+            crate::request_manager::RequestManager::default(),
+            crate::response_manager::ResponseManager::default(),
+        );
+        let input = "\n  exit  \nping\n".as_bytes();
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+
+        let success = run_lines(
+            &mut manager,
+            input,
+            OutputFormat::Json,
+            &mut stdout,
+            &mut stderr,
+        )
+        .await
+        .unwrap();
+
+        assert!(success);
+        assert!(stdout.is_empty());
+        assert!(stderr.is_empty());
+    }
+}
@@ -1,10 +1,356 @@
-use std::{fs, path::PathBuf};
-
-use common::{FenrisCommand, FenrisError, ObjectWriteMode, Result};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use common::{
+    DirFilter, FenrisCommand, FenrisError, ListSort, ListSortKey, ObjectWriteMode, Result,
+    is_valid_env_name,
+};
 use tracing::{debug, warn};
 
-#[derive(Debug, Clone, Default)]
-pub struct RequestManager;
+const DEFAULT_LIST_DEPTH: u32 = 5;
+const DEFAULT_HEAD_LINES: u32 = 10;
+const DEFAULT_TAIL_BYTES: u64 = 4096;
+
+/// How a built-in command is spelled and how many words may follow it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandMetadata {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub usage: &'static str,
+    pub min_args: usize,
+    pub max_args: Option<usize>,
+}
+
+const fn command(
+    name: &'static str,
+    usage: &'static str,
+    description: &'static str,
+    min_args: usize,
+    max_args: Option<usize>,
+) -> CommandMetadata {
+    CommandMetadata {
+        name,
+        description,
+        usage,
+        min_args,
+        max_args,
+    }
+}
+
+/// Every command `DefaultRequestBuilder` understands. The help screen and the command palette
+/// are generated from this table.
+pub const COMMANDS: &[CommandMetadata] = &[
+    command("ping", "ping", "Test connection to server", 0, Some(0)),
+    command(
+        "echo",
+        "echo <message...>",
+        "Round-trip a message through every protocol layer",
+        1,
+        None,
+    ),
+    command(
+        "login",
+        "login <user> <password>",
+        "Reconnect as a user on a server with accounts",
+        2,
+        Some(2),
+    ),
+    command(
+        "ls",
+        "ls [dir] [--sort=name|size|mtime] [--reverse] [--dirs-first]",
+        "List directory contents",
+        0,
+        None,
+    ),
+    command(
+        "lsf",
+        "lsf <dir> [--min-size <bytes>] [--max-size <bytes>] [--type f|d|l] [--name <pattern>]",
+        "List directory entries matching a filter",
+        1,
+        None,
+    ),
+    command(
+        "lsr",
+        "lsr [dir] [depth]",
+        "List directory tree (depth 0 = max)",
+        0,
+        Some(2),
+    ),
+    command("cd", "cd [dir]", "Change directory", 0, Some(1)),
+    command("read", "read <file>", "Read file contents", 1, Some(1)),
+    command(
+        "write",
+        "write <file> <content...>",
+        "Write to file",
+        2,
+        None,
+    ),
+    command(
+        "append",
+        "append <file> <content...>",
+        "Append to file",
+        2,
+        None,
+    ),
+    command("create", "create <file>", "Create new file", 1, Some(1)),
+    command("rm", "rm <file>", "Delete file", 1, Some(1)),
+    command(
+        "mkdir",
+        "mkdir [-p] <dir>",
+        "Create directory (-p also creates parents)",
+        1,
+        Some(2),
+    ),
+    command("rmdir", "rmdir <dir>", "Delete directory", 1, Some(1)),
+    command(
+        "upload",
+        "upload <client_file> <server_location>",
+        "Upload a file from local machine to server",
+        2,
+        Some(2),
+    ),
+    command(
+        "info",
+        "info <file> [--mime]",
+        "Get file information; --mime also guesses its content type",
+        1,
+        Some(2),
+    ),
+    command(
+        "fetch",
+        "fetch <url> <server_location>",
+        "Download a URL directly into server storage",
+        2,
+        Some(2),
+    ),
+    command(
+        "diff",
+        "diff <file1> <file2>",
+        "Show a unified diff of two files",
+        2,
+        Some(2),
+    ),
+    command(
+        "sha256",
+        "sha256 <file>",
+        "Show the SHA-256 checksum of a file",
+        1,
+        Some(1),
+    ),
+    command(
+        "manifest",
+        "manifest <dir>",
+        "List SHA-256 checksums of every file under a directory",
+        1,
+        Some(1),
+    ),
+    command(
+        "manifest-check",
+        "manifest-check <dir> <local-manifest-file>",
+        "Compare a directory against a local sha256sum manifest",
+        2,
+        Some(2),
+    ),
+    command(
+        "broadcast",
+        "broadcast <message...>",
+        "Send a message to all connected clients (PSK only)",
+        1,
+        None,
+    ),
+    command(
+        "compress",
+        "compress <file> [ext]",
+        "Compress a file on the server into <file>.<ext>",
+        1,
+        Some(2),
+    ),
+    command(
+        "decompress",
+        "decompress <file> [ext]",
+        "Decompress a <file>.<ext> on the server",
+        1,
+        Some(2),
+    ),
+    command(
+        "head",
+        "head <file> [lines]",
+        "Show the first lines of a file (default 10)",
+        1,
+        Some(2),
+    ),
+    command(
+        "tail",
+        "tail <file> [bytes]",
+        "Show the last bytes of a file (default 4096)",
+        1,
+        Some(2),
+    ),
+    command(
+        "symlink",
+        "symlink <target> <link>",
+        "Create a symbolic link; the target is stored as given",
+        2,
+        Some(2),
+    ),
+    command(
+        "junction",
+        "junction <target_dir> <link>",
+        "Link to a directory; a junction on Windows servers, a symlink elsewhere",
+        2,
+        Some(2),
+    ),
+    command(
+        "readlink",
+        "readlink <link>",
+        "Show where a symbolic link points",
+        1,
+        Some(1),
+    ),
+    command(
+        "mv",
+        "mv <src> <dst>",
+        "Move or rename a file or directory",
+        2,
+        Some(2),
+    ),
+    command(
+        "tar",
+        "tar <dir> [archive]",
+        "Pack a directory into a .tar.gz on the server",
+        1,
+        Some(2),
+    ),
+    command(
+        "untar",
+        "untar <archive> [dst_dir]",
+        "Unpack a .tar.gz on the server",
+        1,
+        Some(2),
+    ),
+    command(
+        "quota",
+        "quota [set <bytes>]",
+        "Show storage usage; set changes the limit (admin PSK sessions only)",
+        0,
+        Some(2),
+    ),
+    command(
+        "touch-time",
+        "touch-time <path> <unix_timestamp>",
+        "Set a file's modification time",
+        2,
+        Some(2),
+    ),
+    command(
+        "getattr",
+        "getattr <file> <name>",
+        "Read an extended attribute of a file",
+        2,
+        Some(2),
+    ),
+    command(
+        "setattr",
+        "setattr <file> <name> <value>",
+        "Set an extended attribute on a file",
+        3,
+        None,
+    ),
+    command(
+        "getenv",
+        "getenv <name>",
+        "Show a variable from this session's server-side environment",
+        1,
+        Some(1),
+    ),
+    command(
+        "setenv",
+        "setenv <name> <value>",
+        "Set a variable for the rest of this session on the server",
+        2,
+        None,
+    ),
+    command(
+        "script",
+        "script <file> [--ignore-errors]",
+        "Run commands from a local script file",
+        1,
+        Some(2),
+    ),
+    command(
+        "multi",
+        "multi [--fail-fast] <cmd> ; <cmd>...",
+        "Run several commands in one round trip",
+        1,
+        None,
+    ),
+    command("clear", "clear", "Clear the message log", 0, Some(0)),
+    command(
+        "logout",
+        "logout",
+        "Disconnect and return to the connection screen",
+        0,
+        Some(0),
+    ),
+    command("exit", "exit", "Disconnect and quit", 0, Some(0)),
+    command("quit", "quit", "Disconnect and quit", 0, Some(0)),
+];
+
+/// Turns a line typed by the user into the plan the connection manager executes.
+pub trait RequestBuilder: Send + Sync {
+    fn build_request(&self, command: &str) -> Result<ClientCommandPlan>;
+}
+
+/// The built-in command syntax (`ls`, `read`, `upload`, ...).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultRequestBuilder;
+
+pub struct RequestManager {
+    builder: Box<dyn RequestBuilder>,
+    next_request_id: AtomicU64,
+}
+
+impl RequestManager {
+    pub fn new(builder: Box<dyn RequestBuilder>) -> Self {
+        Self {
+            builder,
+            next_request_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn with_builder<B: RequestBuilder + 'static>(builder: B) -> Self {
+        Self::new(Box::new(builder))
+    }
+
+    pub fn build_request(&self, command: &str) -> Result<ClientCommandPlan> {
+        self.builder.build_request(command)
+    }
+
+    pub fn command_list() -> &'static [CommandMetadata] {
+        COMMANDS
+    }
+
+    /// Ids start at 1 because a request id of 0 means the response is not correlated.
+    pub fn next_request_id(&self) -> u64 {
+        self.next_request_id.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+impl Default for RequestManager {
+    fn default() -> Self {
+        Self::with_builder(DefaultRequestBuilder)
+    }
+}
+
+impl RequestBuilder for DefaultRequestBuilder {
+    fn build_request(&self, command: &str) -> Result<ClientCommandPlan> {
+        DefaultRequestBuilder::build_request(self, command)
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ClientCommandPlan {
@@ -22,10 +368,77 @@ pub enum ClientCommandPlan {
         destination: PathBuf,
         total_size: u64,
     },
+    LocalScript {
+        path: PathBuf,
+        ignore_errors: bool,
+    },
+    /// Clears the message log; never sent to the server.
+    ClearMessages,
+    /// Runs `plan` and writes its output to a local file instead of showing it.
+    Redirected {
+        plan: Box<ClientCommandPlan>,
+        redirect: OutputRedirect,
+    },
+    /// `a | b`: runs each stage in turn, feeding one stage's output to the next as its data.
+    Pipeline {
+        stages: Vec<ClientCommandPlan>,
+    },
+    /// Fetches the checksum manifest of `path` and compares it with the local `manifest`.
+    ManifestCheck {
+        path: PathBuf,
+        manifest: PathBuf,
+    },
 }
 
-impl RequestManager {
+impl ClientCommandPlan {
+    pub fn into_parts(self) -> (ClientCommandPlan, Option<OutputRedirect>) {
+        match self {
+            ClientCommandPlan::Redirected { plan, redirect } => (*plan, Some(redirect)),
+            plan => (plan, None),
+        }
+    }
+
+    /// Fills in the payload of a command that takes one. Commands with nothing to fill in run
+    /// as typed, the way a shell command ignores input it never reads.
+    pub fn with_input(self, input: Vec<u8>) -> Self {
+        match self {
+            ClientCommandPlan::ChunkedInlineWrite { path, mode, .. } => {
+                ClientCommandPlan::ChunkedInlineWrite {
+                    path,
+                    mode,
+                    data: input,
+                }
+            }
+            ClientCommandPlan::Single(FenrisCommand::Echo { .. }) => {
+                ClientCommandPlan::Single(FenrisCommand::Echo { payload: input })
+            }
+            plan => plan,
+        }
+    }
+}
+
+/// A trailing `> file` (truncate) or `>> file` (append).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputRedirect {
+    pub path: PathBuf,
+    pub append: bool,
+}
+
+impl DefaultRequestBuilder {
     pub fn build_request(&self, command: &str) -> Result<ClientCommandPlan> {
+        if let Some((command, redirect)) = split_redirect(command)? {
+            return self.build_redirected(command, redirect);
+        }
+
+        let stages = split_pipeline(command);
+        if stages.len() > 1 {
+            return self.build_pipeline(&stages);
+        }
+
+        if let Some(timed) = command.trim_start().strip_prefix("timeout:") {
+            return self.build_timed(timed);
+        }
+
         let parts: Vec<&str> = command.split_whitespace().collect();
 
         if parts.is_empty() {
@@ -33,10 +446,17 @@ impl RequestManager {
         }
 
         let cmd = parts[0].to_lowercase();
+        self.validate_args(&cmd, &parts[1..])?;
 
         match cmd.as_str() {
             "ping" => self.build_ping(),
+            "echo" => self.build_echo(&parts[1..]),
+            "login" => self.build_login(&parts[1..]),
+            "exit" | "quit" | "logout" => self.build_terminate(),
+            "clear" => Ok(ClientCommandPlan::ClearMessages),
             "ls" => self.build_list_namespace(&parts[1..]),
+            "lsr" => self.build_list_namespace_recursive(&parts[1..]),
+            "lsf" => self.build_list_dir_with_filter(&parts[1..]),
             "cd" => self.build_change_namespace(&parts[1..]),
             "read" => self.build_read_object(&parts[1..]),
             "write" => self.build_write_object(&parts[1..]),
@@ -47,6 +467,33 @@ impl RequestManager {
             "info" => self.build_object_info(&parts[1..]),
             "append" => self.build_append_object(&parts[1..]),
             "upload" => self.build_upload_object(&parts[1..]),
+            "fetch" => self.build_fetch_url(&parts[1..]),
+            "script" => self.build_local_script(&parts[1..]),
+            "diff" => self.build_diff_objects(&parts[1..]),
+            "sha256" => self.build_checksum_object(&parts[1..]),
+            "manifest" => self.build_checksum_manifest(&parts[1..]),
+            "manifest-check" => self.build_manifest_check(&parts[1..]),
+            "broadcast" => self.build_broadcast(&parts[1..]),
+            "compress" => self.build_compress_object(&parts[1..]),
+            "decompress" => self.build_decompress_object(&parts[1..]),
+            "head" => self.build_head_object(&parts[1..]),
+            "tail" => self.build_tail_object(&parts[1..]),
+            "go" => Err(FenrisError::InvalidRequest(
+                "bookmarks are only available in the interactive client".to_string(),
+            )),
+            "symlink" => self.build_create_symlink(&parts[1..]),
+            "readlink" => self.build_read_symlink(&parts[1..]),
+            "junction" => self.build_dir_junction(&parts[1..]),
+            "mv" => self.build_move(&parts[1..]),
+            "tar" => self.build_create_archive(&parts[1..]),
+            "untar" => self.build_extract_archive(&parts[1..]),
+            "touch-time" => self.build_set_mtime(&parts[1..]),
+            "getattr" => self.build_get_xattr(&parts[1..]),
+            "setattr" => self.build_set_xattr(&parts[1..]),
+            "getenv" => self.build_get_env(&parts[1..]),
+            "setenv" => self.build_set_env(&parts[1..]),
+            "quota" => self.build_quota(&parts[1..]),
+            "multi" => self.build_multi_op(command.trim_start()[parts[0].len()..].trim()),
             _ => {
                 warn!("Unknown command:  {}", cmd);
                 Err(FenrisError::InvalidProtocolMessage)
@@ -54,190 +501,1344 @@ impl RequestManager {
         }
     }
 
-    fn build_ping(&self) -> Result<ClientCommandPlan> {
-        debug!("Building PING command");
-        Ok(ClientCommandPlan::Single(FenrisCommand::Ping))
+    /// Commands missing from `COMMANDS` are left for the dispatch below to reject.
+    fn validate_args(&self, cmd: &str, args: &[&str]) -> Result<()> {
+        let Some(metadata) = COMMANDS.iter().find(|metadata| metadata.name == cmd) else {
+            return Ok(());
+        };
+        if args.len() < metadata.min_args || metadata.max_args.is_some_and(|max| args.len() > max) {
+            return Err(FenrisError::InvalidRequest(format!(
+                "Usage: {}",
+                metadata.usage
+            )));
+        }
+        Ok(())
+    }
+
+    fn build_timed(&self, timed: &str) -> Result<ClientCommandPlan> {
+        let (ms, command) = timed
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| FenrisError::MissingField("timeout requires a command".to_string()))?;
+        let ms: u32 = ms.parse().map_err(|_| {
+            FenrisError::InvalidRequest(format!("invalid timeout in milliseconds: {}", ms))
+        })?;
+
+        debug!("Building command with a {}ms timeout", ms);
+        match self.build_request(command)? {
+            ClientCommandPlan::Single(FenrisCommand::Timed { .. }) => Err(
+                FenrisError::InvalidRequest("timeout cannot be given twice".to_string()),
+            ),
+            ClientCommandPlan::Single(command) => {
+                Ok(ClientCommandPlan::Single(FenrisCommand::Timed {
+                    timeout: Duration::from_millis(ms.into()),
+                    command: Box::new(command),
+                }))
+            }
+            _ => Err(FenrisError::InvalidRequest(
+                "timeout only applies to single-request commands".to_string(),
+            )),
+        }
+    }
+
+    fn build_redirected(
+        &self,
+        command: &str,
+        redirect: OutputRedirect,
+    ) -> Result<ClientCommandPlan> {
+        debug!("Redirecting output to {}", redirect.path.display());
+        match self.build_request(command)? {
+            ClientCommandPlan::Redirected { .. } => Err(FenrisError::InvalidRequest(
+                "output can only be redirected once".to_string(),
+            )),
+            ClientCommandPlan::LocalScript { .. }
+            | ClientCommandPlan::ClearMessages
+            | ClientCommandPlan::Single(FenrisCommand::Terminate) => {
+                Err(FenrisError::InvalidRequest(
+                    "this command's output cannot be redirected".to_string(),
+                ))
+            }
+            plan => Ok(ClientCommandPlan::Redirected {
+                plan: Box::new(plan),
+                redirect,
+            }),
+        }
+    }
+
+    fn build_pipeline(&self, stages: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building pipeline of {} commands", stages.len());
+        let mut plans = Vec::with_capacity(stages.len());
+        for (index, stage) in stages.iter().enumerate() {
+            if stage.is_empty() {
+                return Err(FenrisError::MissingField(
+                    "pipeline stage requires a command".to_string(),
+                ));
+            }
+            let plan = match (
+                index,
+                stage.split_whitespace().collect::<Vec<_>>().as_slice(),
+            ) {
+                // Commands after the first may leave their payload to the pipe.
+                (1.., [cmd, path]) if cmd.eq_ignore_ascii_case("write") => {
+                    self.build_write_object(&[*path])?
+                }
+                (1.., [cmd, path]) if cmd.eq_ignore_ascii_case("append") => {
+                    self.build_append_object(&[*path])?
+                }
+                (1.., [cmd]) if cmd.eq_ignore_ascii_case("echo") => self.build_echo(&[])?,
+                _ => self.build_request(stage)?,
+            };
+            match plan {
+                ClientCommandPlan::Single(
+                    FenrisCommand::Login { .. } | FenrisCommand::Terminate,
+                )
+                | ClientCommandPlan::LocalScript { .. }
+                | ClientCommandPlan::ClearMessages
+                | ClientCommandPlan::ManifestCheck { .. }
+                | ClientCommandPlan::Redirected { .. }
+                | ClientCommandPlan::Pipeline { .. } => {
+                    return Err(FenrisError::InvalidRequest(format!(
+                        "{} cannot be part of a pipeline",
+                        stage
+                    )));
+                }
+                plan => plans.push(plan),
+            }
+        }
+        Ok(ClientCommandPlan::Pipeline { stages: plans })
+    }
+
+    fn build_ping(&self) -> Result<ClientCommandPlan> {
+        debug!("Building PING command");
+        Ok(ClientCommandPlan::Single(FenrisCommand::Ping))
+    }
+
+    fn build_echo(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building ECHO command");
+        Ok(ClientCommandPlan::Single(FenrisCommand::Echo {
+            payload: args.join(" ").into_bytes(),
+        }))
+    }
+
+    fn build_login(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let [username, password] = args else {
+            return Err(FenrisError::MissingField(
+                "login requires a username and a password".to_string(),
+            ));
+        };
+
+        debug!("Building LOGIN command");
+        Ok(ClientCommandPlan::Single(FenrisCommand::Login {
+            username: username.to_string(),
+            password: password.to_string(),
+        }))
+    }
+
+    fn build_terminate(&self) -> Result<ClientCommandPlan> {
+        debug!("Building TERMINATE command");
+        Ok(ClientCommandPlan::Single(FenrisCommand::Terminate))
+    }
+
+    fn build_list_namespace(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let mut path = ".";
+        let mut sort = ListSort::default();
+        for arg in args {
+            match *arg {
+                "--reverse" => sort.reverse = true,
+                "--dirs-first" => sort.dirs_first = true,
+                "--sort=name" => sort.key = Some(ListSortKey::Name),
+                "--sort=size" => sort.key = Some(ListSortKey::Size),
+                "--sort=mtime" => sort.key = Some(ListSortKey::Modified),
+                flag if flag.starts_with("--") => {
+                    return Err(FenrisError::InvalidRequest(format!(
+                        "unknown ls option: {}",
+                        flag
+                    )));
+                }
+                dir => path = dir,
+            }
+        }
+
+        debug!("Building LIST_NAMESPACE command for: {} ({})", path, sort);
+        Ok(ClientCommandPlan::Single(FenrisCommand::ListNamespace {
+            path: PathBuf::from(path),
+            sort,
+        }))
+    }
+
+    fn build_list_dir_with_filter(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let path = PathBuf::from(args[0]);
+        let mut filter = DirFilter::default();
+        let mut flags = args[1..].iter();
+        while let Some(flag) = flags.next() {
+            let value = flags
+                .next()
+                .ok_or_else(|| FenrisError::MissingField(format!("{} requires a value", flag)))?;
+            let size = || {
+                value.parse::<u64>().map_err(|_| {
+                    FenrisError::InvalidRequest(format!("invalid size for {}: {}", flag, value))
+                })
+            };
+            match *flag {
+                "--min-size" => filter.min_size = Some(size()?),
+                "--max-size" => filter.max_size = Some(size()?),
+                "--type" => {
+                    filter.file_types |= match *value {
+                        "f" => DirFilter::FILES,
+                        "d" => DirFilter::DIRS,
+                        "l" => DirFilter::SYMLINKS,
+                        other => {
+                            return Err(FenrisError::InvalidRequest(format!(
+                                "unknown entry type: {} (expected f, d or l)",
+                                other
+                            )));
+                        }
+                    }
+                }
+                "--name" => filter.name_pattern = Some(value.to_string()),
+                other => {
+                    return Err(FenrisError::InvalidRequest(format!(
+                        "unknown lsf option: {}",
+                        other
+                    )));
+                }
+            }
+        }
+
+        debug!(
+            "Building LIST_DIR_WITH_FILTER command for: {}",
+            path.display()
+        );
+        Ok(ClientCommandPlan::Single(
+            FenrisCommand::ListNamespaceFiltered { path, filter },
+        ))
+    }
+
+    fn build_list_namespace_recursive(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let path = args.first().unwrap_or(&".");
+        let max_depth = match args.get(1) {
+            Some(depth) => depth
+                .parse()
+                .map_err(|_| FenrisError::InvalidRequest(format!("invalid depth: {}", depth)))?,
+            None => DEFAULT_LIST_DEPTH,
+        };
+
+        debug!(
+            "Building LIST_DIR_RECURSIVE command for: {} (depth {})",
+            path, max_depth
+        );
+        Ok(ClientCommandPlan::Single(
+            FenrisCommand::ListNamespaceRecursive {
+                path: PathBuf::from(path),
+                max_depth,
+            },
+        ))
+    }
+
+    fn build_change_namespace(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let path = args.first().unwrap_or(&"~");
+        debug!("Building CHANGE_NAMESPACE command for: {}", path);
+        Ok(ClientCommandPlan::Single(FenrisCommand::ChangeNamespace {
+            path: PathBuf::from(path),
+        }))
+    }
+
+    fn build_read_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let path = PathBuf::from(args[0]);
+        debug!("Building READ_OBJECT command for: {}", path.display());
+        Ok(ClientCommandPlan::ChunkedRead { path })
+    }
+
+    fn build_write_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let path = PathBuf::from(args[0]);
+        let content = args[1..].join(" ");
+        debug!("Building WRITE_OBJECT command for: {}", path.display());
+        Ok(ClientCommandPlan::ChunkedInlineWrite {
+            path,
+            mode: ObjectWriteMode::Write,
+            data: content.into_bytes(),
+        })
+    }
+
+    fn build_create_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let path = PathBuf::from(args[0]);
+        debug!("Building CREATE_OBJECT command for: {}", path.display());
+        Ok(ClientCommandPlan::Single(FenrisCommand::CreateObject {
+            path,
+        }))
+    }
+
+    fn build_delete_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let path = PathBuf::from(args[0]);
+        debug!("Building DELETE_OBJECT command for: {}", path.display());
+        Ok(ClientCommandPlan::Single(FenrisCommand::DeleteObject {
+            path,
+        }))
+    }
+
+    fn build_create_namespace(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        match args {
+            ["-p", path] => {
+                debug!("Building CREATE_DIR_ALL command for: {}", path);
+                Ok(ClientCommandPlan::Single(
+                    FenrisCommand::CreateNamespaceAll {
+                        path: PathBuf::from(path),
+                    },
+                ))
+            }
+            [path] if *path != "-p" => {
+                debug!("Building CREATE_NAMESPACE command for: {}", path);
+                Ok(ClientCommandPlan::Single(FenrisCommand::CreateNamespace {
+                    path: PathBuf::from(path),
+                }))
+            }
+            _ => Err(FenrisError::InvalidRequest(
+                "usage: mkdir [-p] <dir>".to_string(),
+            )),
+        }
+    }
+
+    fn build_delete_namespace(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let path = PathBuf::from(args[0]);
+        debug!("Building DELETE_NAMESPACE command for: {}", path.display());
+        Ok(ClientCommandPlan::Single(FenrisCommand::DeleteNamespace {
+            path,
+        }))
+    }
+
+    fn build_object_info(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let detect_mime = args.contains(&"--mime");
+        let path = args
+            .iter()
+            .find(|arg| **arg != "--mime")
+            .map(PathBuf::from)
+            .ok_or_else(|| FenrisError::MissingField("info requires a file".to_string()))?;
+
+        debug!("Building OBJECT_INFO command for: {}", path.display());
+        Ok(ClientCommandPlan::Single(FenrisCommand::ObjectInfo {
+            path,
+            detect_mime,
+        }))
+    }
+
+    fn build_append_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let path = PathBuf::from(args[0]);
+        let content = args[1..].join(" ");
+        debug!("Building APPEND_OBJECT command for: {}", path.display());
+        Ok(ClientCommandPlan::ChunkedInlineWrite {
+            path,
+            mode: ObjectWriteMode::Append,
+            data: content.into_bytes(),
+        })
+    }
+
+    fn build_upload_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let source = PathBuf::from(args[0]);
+        let metadata = fs::metadata(&source).map_err(|e| {
+            FenrisError::file_operation_from(
+                format!("Failed to inspect file {}: {}", source.display(), e),
+                e,
+            )
+        })?;
+
+        Ok(ClientCommandPlan::ChunkedUpload {
+            source,
+            destination: PathBuf::from(args[1]),
+            total_size: metadata.len(),
+        })
+    }
+
+    fn build_fetch_url(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let path = PathBuf::from(args[1]);
+        debug!("Building FETCH_URL command for: {}", path.display());
+        Ok(ClientCommandPlan::Single(FenrisCommand::FetchUrl {
+            url: args[0].to_string(),
+            path,
+        }))
+    }
+
+    fn build_diff_objects(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building DIFF_FILES command for: {} {}", args[0], args[1]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::DiffObjects {
+            left: PathBuf::from(args[0]),
+            right: PathBuf::from(args[1]),
+        }))
+    }
+
+    fn build_checksum_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building CHECKSUM command for: {}", args[0]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::ChecksumObject {
+            path: PathBuf::from(args[0]),
+        }))
+    }
+
+    fn build_checksum_manifest(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building CHECKSUM_MANIFEST command for: {}", args[0]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::ChecksumManifest {
+            path: PathBuf::from(args[0]),
+        }))
+    }
+
+    fn build_manifest_check(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building manifest check of {} against {}", args[0], args[1]);
+        Ok(ClientCommandPlan::ManifestCheck {
+            path: PathBuf::from(args[0]),
+            manifest: PathBuf::from(args[1]),
+        })
+    }
+
+    fn build_broadcast(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building BROADCAST command");
+        Ok(ClientCommandPlan::Single(FenrisCommand::Broadcast {
+            message: args.join(" "),
+        }))
+    }
+
+    fn build_compress_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building COMPRESS command for: {}", args[0]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::CompressObject {
+            path: PathBuf::from(args[0]),
+            extension: args.get(1).map(|extension| extension.to_string()),
+        }))
+    }
+
+    fn build_decompress_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building DECOMPRESS command for: {}", args[0]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::DecompressObject {
+            path: PathBuf::from(args[0]),
+            extension: args.get(1).map(|extension| extension.to_string()),
+        }))
+    }
+
+    fn build_head_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let lines = match args.get(1) {
+            Some(lines) => lines.parse().map_err(|_| {
+                FenrisError::InvalidRequest(format!("invalid line count: {}", lines))
+            })?,
+            None => DEFAULT_HEAD_LINES,
+        };
+
+        debug!("Building HEAD command for: {} ({} lines)", args[0], lines);
+        Ok(ClientCommandPlan::Single(FenrisCommand::HeadObject {
+            path: PathBuf::from(args[0]),
+            lines,
+        }))
+    }
+
+    fn build_quota(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let limit = match args {
+            [] => None,
+            ["set", bytes] => Some(bytes.parse().map_err(|_| {
+                FenrisError::InvalidRequest(format!("invalid byte count: {}", bytes))
+            })?),
+            _ => {
+                return Err(FenrisError::InvalidRequest(
+                    "usage: quota [set <bytes>]".to_string(),
+                ));
+            }
+        };
+
+        debug!("Building QUOTA command (limit: {:?})", limit);
+        Ok(ClientCommandPlan::Single(FenrisCommand::Quota { limit }))
+    }
+
+    /// Only commands that are one request each can be batched, plus `read`, `write` and
+    /// `append`, which are sent whole instead of in chunks.
+    fn build_multi_op(&self, args: &str) -> Result<ClientCommandPlan> {
+        let (fail_fast, args) = match args.strip_prefix("--fail-fast") {
+            Some(rest) => (true, rest),
+            None => (false, args),
+        };
+
+        let mut commands = Vec::new();
+        for part in args
+            .split(';')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            let command = match self.build_request(part)? {
+                ClientCommandPlan::Single(FenrisCommand::MultiOp { .. }) => {
+                    return Err(FenrisError::InvalidRequest(
+                        "multi cannot be nested".to_string(),
+                    ));
+                }
+                ClientCommandPlan::Single(command) => command,
+                ClientCommandPlan::ChunkedRead { path } => FenrisCommand::ReadObject { path },
+                ClientCommandPlan::ChunkedInlineWrite {
+                    path,
+                    mode: ObjectWriteMode::Append,
+                    data,
+                } => FenrisCommand::AppendObject { path, data },
+                ClientCommandPlan::ChunkedInlineWrite { path, data, .. } => {
+                    FenrisCommand::WriteObject { path, data }
+                }
+                _ => {
+                    return Err(FenrisError::InvalidRequest(format!(
+                        "{} cannot be part of multi",
+                        part
+                    )));
+                }
+            };
+            commands.push(command);
+        }
+        if commands.is_empty() {
+            return Err(FenrisError::MissingField(
+                "multi requires at least one command".to_string(),
+            ));
+        }
+
+        debug!("Building MULTI_OP command with {} commands", commands.len());
+        Ok(ClientCommandPlan::Single(FenrisCommand::MultiOp {
+            commands,
+            fail_fast,
+        }))
+    }
+
+    fn build_tail_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let bytes = match args.get(1) {
+            Some(bytes) => bytes.parse().map_err(|_| {
+                FenrisError::InvalidRequest(format!("invalid byte count: {}", bytes))
+            })?,
+            None => DEFAULT_TAIL_BYTES,
+        };
+
+        debug!("Building TAIL command for: {} ({} bytes)", args[0], bytes);
+        Ok(ClientCommandPlan::Single(FenrisCommand::TailObject {
+            path: PathBuf::from(args[0]),
+            bytes,
+        }))
+    }
+
+    fn build_create_symlink(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building SYMLINK command: {} -> {}", args[1], args[0]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::CreateSymlink {
+            target: PathBuf::from(args[0]),
+            link: PathBuf::from(args[1]),
+        }))
+    }
+
+    fn build_dir_junction(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building JUNCTION_DIR command: {} -> {}", args[1], args[0]);
+        Ok(ClientCommandPlan::Single(
+            FenrisCommand::CreateDirJunction {
+                target: PathBuf::from(args[0]),
+                link: PathBuf::from(args[1]),
+            },
+        ))
+    }
+
+    fn build_move(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building RENAME_FILE command: {} -> {}", args[0], args[1]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::MoveObject {
+            from: PathBuf::from(args[0]),
+            to: PathBuf::from(args[1]),
+        }))
+    }
+
+    fn build_create_archive(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building TAR command for: {}", args[0]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::CreateArchive {
+            dir: PathBuf::from(args[0]),
+            archive: args.get(1).map(PathBuf::from),
+        }))
+    }
+
+    fn build_extract_archive(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building UNTAR command for: {}", args[0]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::ExtractArchive {
+            archive: PathBuf::from(args[0]),
+            dest: args.get(1).map(PathBuf::from),
+        }))
+    }
+
+    fn build_read_symlink(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building READ_SYMLINK command for: {}", args[0]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::ReadSymlink {
+            link: PathBuf::from(args[0]),
+        }))
+    }
+
+    fn build_set_mtime(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let mtime = args[1].parse().map_err(|_| {
+            FenrisError::InvalidRequest(format!("invalid Unix timestamp: {}", args[1]))
+        })?;
+
+        debug!("Building SET_MTIME command for: {} ({})", args[0], mtime);
+        Ok(ClientCommandPlan::Single(FenrisCommand::SetMtime {
+            path: PathBuf::from(args[0]),
+            mtime,
+        }))
+    }
+
+    fn build_get_xattr(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building GET_ATTR command for: {} ({})", args[0], args[1]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::GetXattr {
+            path: PathBuf::from(args[0]),
+            name: args[1].to_string(),
+        }))
+    }
+
+    fn build_set_xattr(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building SET_ATTR command for: {} ({})", args[0], args[1]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::SetXattr {
+            path: PathBuf::from(args[0]),
+            name: args[1].to_string(),
+            value: args[2..].join(" ").into_bytes(),
+        }))
+    }
+
+    fn build_get_env(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building GET_ENV command for: {}", args[0]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::GetEnv {
+            name: env_name(args[0])?,
+        }))
+    }
+
+    fn build_set_env(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        debug!("Building SET_ENV command for: {}", args[0]);
+        Ok(ClientCommandPlan::Single(FenrisCommand::SetEnv {
+            name: env_name(args[0])?,
+            value: args[1..].join(" "),
+        }))
+    }
+
+    fn build_local_script(&self, args: &[&str]) -> Result<ClientCommandPlan> {
+        let ignore_errors = args.contains(&"--ignore-errors");
+        let path = args
+            .iter()
+            .find(|arg| **arg != "--ignore-errors")
+            .ok_or_else(|| FenrisError::MissingField("script requires a file".to_string()))?;
+
+        debug!("Building local script for: {}", path);
+        Ok(ClientCommandPlan::LocalScript {
+            path: PathBuf::from(path),
+            ignore_errors,
+        })
+    }
+}
+
+fn env_name(name: &str) -> Result<String> {
+    if is_valid_env_name(name) {
+        Ok(name.to_string())
+    } else {
+        Err(FenrisError::InvalidRequest(format!(
+            "Invalid variable name: {} (use letters, digits and _, not starting with a digit)",
+            name
+        )))
+    }
+}
+
+/// Splits a trailing `> file` or `>> file` off `command`. The operator must be its own word.
+fn split_redirect(command: &str) -> Result<Option<(&str, OutputRedirect)>> {
+    let Some((rest, path)) = command.trim_end().rsplit_once(char::is_whitespace) else {
+        return Ok(None);
+    };
+    if path == ">" || path == ">>" {
+        return Err(FenrisError::MissingField(
+            "output redirect requires a file path".to_string(),
+        ));
+    }
+
+    let Some((command, operator)) = rest.trim_end().rsplit_once(char::is_whitespace) else {
+        return Ok(None);
+    };
+    let append = match operator {
+        ">" => false,
+        ">>" => true,
+        _ => return Ok(None),
+    };
+
+    Ok(Some((
+        command,
+        OutputRedirect {
+            path: PathBuf::from(path),
+            append,
+        },
+    )))
+}
+
+/// Splits on every `|` that stands alone as a word outside quotes, so `write f a|b` and
+/// `echo "x | y"` stay single commands. Returns the whole command when there is no pipe.
+pub(crate) fn split_pipeline(command: &str) -> Vec<&str> {
+    let bytes = command.as_bytes();
+    let mut stages = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+
+    for (index, &byte) in bytes.iter().enumerate() {
+        match (quote, byte) {
+            (Some(open), _) if byte == open => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(byte),
+            (None, b'|')
+                if index
+                    .checked_sub(1)
+                    .is_none_or(|i| bytes[i].is_ascii_whitespace())
+                    && bytes.get(index + 1).is_none_or(u8::is_ascii_whitespace) =>
+            {
+                stages.push(command[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    stages.push(command[start..].trim());
+    stages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+
+    struct MockRequestBuilder;
+
+    impl RequestBuilder for MockRequestBuilder {
+        fn build_request(&self, _command: &str) -> Result<ClientCommandPlan> {
+            Ok(ClientCommandPlan::Single(FenrisCommand::Ping))
+        }
+    }
+
+    #[test]
+    fn test_custom_builder_replaces_default_syntax() {
+        for manager in [
+            RequestManager::new(Box::new(MockRequestBuilder)),
+            RequestManager::with_builder(MockRequestBuilder),
+        ] {
+            assert_eq!(
+                manager.build_request("anything at all").unwrap(),
+                ClientCommandPlan::Single(FenrisCommand::Ping)
+            );
+        }
+        assert!(
+            RequestManager::default()
+                .build_request("anything at all")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_request_ids_increase_from_one() {
+        let manager = RequestManager::default();
+
+        assert_eq!(manager.next_request_id(), 1);
+        assert_eq!(manager.next_request_id(), 2);
+    }
+
+    #[test]
+    fn test_every_listed_command_builds_with_its_minimum_arguments() {
+        let manager = RequestManager::default();
+        let local_file = tempfile::NamedTempFile::new().unwrap();
+        let first_arg = local_file.path().to_str().unwrap();
+
+        for metadata in RequestManager::command_list() {
+            // multi takes whole commands and the env commands variable names rather than paths.
+            let first_arg = match metadata.name {
+                "multi" => "ping",
+                "getenv" | "setenv" => "HOME",
+                _ => first_arg,
+            };
+            let args = (0..metadata.min_args).map(|i| if i == 0 { first_arg } else { "1" });
+            let command = std::iter::once(metadata.name)
+                .chain(args)
+                .collect::<Vec<_>>()
+                .join(" ");
+            assert!(
+                manager.build_request(&command).is_ok(),
+                "{} did not build",
+                command
+            );
+        }
+
+        assert!(matches!(
+            manager.build_request("ping now").unwrap_err(),
+            FenrisError::InvalidRequest(message) if message == "Usage: ping"
+        ));
+    }
+
+    #[test]
+    fn test_build_multi_op() {
+        let manager = RequestManager::default();
+
+        assert_eq!(
+            manager
+                .build_request("multi write a.txt hi there ; read a.txt;rm a.txt")
+                .unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::MultiOp {
+                commands: vec![
+                    FenrisCommand::WriteObject {
+                        path: PathBuf::from("a.txt"),
+                        data: b"hi there".to_vec(),
+                    },
+                    FenrisCommand::ReadObject {
+                        path: PathBuf::from("a.txt"),
+                    },
+                    FenrisCommand::DeleteObject {
+                        path: PathBuf::from("a.txt"),
+                    },
+                ],
+                fail_fast: false,
+            })
+        );
+        assert!(matches!(
+            manager.build_request("multi --fail-fast ping").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::MultiOp {
+                fail_fast: true,
+                ..
+            })
+        ));
+        assert!(manager.build_request("multi ;").is_err());
+        assert!(manager.build_request("multi ping ; multi ping").is_err());
+        assert!(manager.build_request("multi ping ; clear").is_err());
+        assert!(manager.build_request("multi ping ; read").is_err());
+    }
+
+    #[test]
+    fn test_build_ping() {
+        let manager = RequestManager::default();
+        let command = manager.build_request("ping").unwrap();
+
+        assert_eq!(command, ClientCommandPlan::Single(FenrisCommand::Ping));
+    }
+
+    #[test]
+    fn test_build_session_commands() {
+        let manager = RequestManager::default();
+
+        for command in ["exit", "quit", "logout", "QUIT"] {
+            assert_eq!(
+                manager.build_request(command).unwrap(),
+                ClientCommandPlan::Single(FenrisCommand::Terminate)
+            );
+        }
+        assert_eq!(
+            manager.build_request("clear").unwrap(),
+            ClientCommandPlan::ClearMessages
+        );
+    }
+
+    #[test]
+    fn test_build_list_namespace_recursive() {
+        let manager = RequestManager::default();
+
+        assert_eq!(
+            manager.build_request("lsr").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::ListNamespaceRecursive {
+                path: PathBuf::from("."),
+                max_depth: DEFAULT_LIST_DEPTH,
+            })
+        );
+        assert_eq!(
+            manager.build_request("lsr docs 0").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::ListNamespaceRecursive {
+                path: PathBuf::from("docs"),
+                max_depth: 0,
+            })
+        );
+        assert!(manager.build_request("lsr docs deep").is_err());
+    }
+
+    #[test]
+    fn test_build_diff_objects() {
+        let manager = RequestManager::default();
+
+        assert_eq!(
+            manager.build_request("diff a.conf b.conf").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::DiffObjects {
+                left: PathBuf::from("a.conf"),
+                right: PathBuf::from("b.conf"),
+            })
+        );
+        assert!(manager.build_request("diff a.conf").is_err());
+    }
+
+    #[test]
+    fn test_build_broadcast() {
+        let manager = RequestManager::default();
+
+        assert_eq!(
+            manager.build_request("broadcast restart at  noon").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::Broadcast {
+                message: "restart at noon".to_string(),
+            })
+        );
+        assert!(manager.build_request("broadcast").is_err());
+    }
+
+    #[test]
+    fn test_build_checksum_object() {
+        let manager = RequestManager::default();
+
+        assert_eq!(
+            manager.build_request("sha256 a.bin").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::ChecksumObject {
+                path: PathBuf::from("a.bin"),
+            })
+        );
+        assert!(manager.build_request("sha256").is_err());
+    }
+
+    #[test]
+    fn test_build_manifest_commands() {
+        let manager = RequestManager::default();
+
+        assert_eq!(
+            manager.build_request("manifest photos").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::ChecksumManifest {
+                path: PathBuf::from("photos"),
+            })
+        );
+        assert_eq!(
+            manager
+                .build_request("manifest-check photos photos.sha256")
+                .unwrap(),
+            ClientCommandPlan::ManifestCheck {
+                path: PathBuf::from("photos"),
+                manifest: PathBuf::from("photos.sha256"),
+            }
+        );
+        assert!(manager.build_request("manifest-check photos").is_err());
+        assert!(
+            manager
+                .build_request("manifest-check photos a | write out.txt")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_build_compress_and_decompress() {
+        let manager = RequestManager::default();
+
+        assert_eq!(
+            manager.build_request("compress app.log").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::CompressObject {
+                path: PathBuf::from("app.log"),
+                extension: None,
+            })
+        );
+        assert_eq!(
+            manager.build_request("decompress app.log.gz gz").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::DecompressObject {
+                path: PathBuf::from("app.log.gz"),
+                extension: Some("gz".to_string()),
+            })
+        );
+        assert!(manager.build_request("compress").is_err());
+    }
+
+    #[test]
+    fn test_build_head_and_tail() {
+        let manager = RequestManager::default();
+
+        assert_eq!(
+            manager.build_request("head app.log").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::HeadObject {
+                path: PathBuf::from("app.log"),
+                lines: DEFAULT_HEAD_LINES,
+            })
+        );
+        assert_eq!(
+            manager.build_request("tail app.log 128").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::TailObject {
+                path: PathBuf::from("app.log"),
+                bytes: 128,
+            })
+        );
+        assert!(manager.build_request("tail").is_err());
+        assert!(manager.build_request("head app.log many").is_err());
+    }
+
+    #[test]
+    fn test_build_symlink_commands() {
+        let manager = RequestManager::default();
+
+        assert_eq!(
+            manager.build_request("symlink releases/v2 latest").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::CreateSymlink {
+                target: PathBuf::from("releases/v2"),
+                link: PathBuf::from("latest"),
+            })
+        );
+        assert_eq!(
+            manager.build_request("readlink latest").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::ReadSymlink {
+                link: PathBuf::from("latest"),
+            })
+        );
+        assert_eq!(
+            manager
+                .build_request("junction releases/v2 current")
+                .unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::CreateDirJunction {
+                target: PathBuf::from("releases/v2"),
+                link: PathBuf::from("current"),
+            })
+        );
+        assert!(manager.build_request("symlink only-target").is_err());
+        assert!(manager.build_request("junction only-target").is_err());
+        assert!(manager.build_request("readlink").is_err());
+    }
+
+    #[test]
+    fn test_build_echo_command() {
+        let manager = RequestManager::default();
+
+        assert_eq!(
+            manager.build_request("echo héllo  world").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::Echo {
+                payload: "héllo world".as_bytes().to_vec(),
+            })
+        );
+        assert!(manager.build_request("echo").is_err());
     }
 
-    fn build_list_namespace(&self, args: &[&str]) -> Result<ClientCommandPlan> {
-        let path = args.first().unwrap_or(&".");
-        debug!("Building LIST_NAMESPACE command for: {}", path);
-        Ok(ClientCommandPlan::Single(FenrisCommand::ListNamespace {
-            path: PathBuf::from(path),
-        }))
+    #[test]
+    fn test_build_login_command() {
+        let manager = RequestManager::default();
+
+        assert_eq!(
+            manager.build_request("login alice hunter2").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::Login {
+                username: "alice".to_string(),
+                password: "hunter2".to_string(),
+            })
+        );
+        assert!(manager.build_request("login alice").is_err());
+        assert!(manager.build_request("login alice hunter 2").is_err());
     }
 
-    fn build_change_namespace(&self, args: &[&str]) -> Result<ClientCommandPlan> {
-        let path = args.first().unwrap_or(&"~");
-        debug!("Building CHANGE_NAMESPACE command for: {}", path);
-        Ok(ClientCommandPlan::Single(FenrisCommand::ChangeNamespace {
-            path: PathBuf::from(path),
-        }))
+    #[test]
+    fn test_build_quota_command() {
+        let manager = RequestManager::default();
+
+        assert_eq!(
+            manager.build_request("quota").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::Quota { limit: None })
+        );
+        assert_eq!(
+            manager.build_request("quota set 1048576").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::Quota {
+                limit: Some(1_048_576)
+            })
+        );
+        assert!(manager.build_request("quota set lots").is_err());
+        assert!(manager.build_request("quota 10").is_err());
     }
 
-    fn build_read_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
-        if args.is_empty() {
-            return Err(FenrisError::MissingField(
-                "read requires a filename".to_string(),
-            ));
-        }
+    #[test]
+    fn test_build_move_command() {
+        let manager = RequestManager::default();
 
-        let path = PathBuf::from(args[0]);
-        debug!("Building READ_OBJECT command for: {}", path.display());
-        Ok(ClientCommandPlan::ChunkedRead { path })
+        assert_eq!(
+            manager.build_request("mv draft.txt archive/").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::MoveObject {
+                from: PathBuf::from("draft.txt"),
+                to: PathBuf::from("archive/"),
+            })
+        );
+        assert!(manager.build_request("mv draft.txt").is_err());
     }
 
-    fn build_write_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
-        if args.len() < 2 {
-            return Err(FenrisError::MissingField(
-                "write requires filename as well as data".to_string(),
-            ));
-        }
+    #[test]
+    fn test_build_archive_commands() {
+        let manager = RequestManager::default();
 
-        let path = PathBuf::from(args[0]);
-        let content = args[1..].join(" ");
-        debug!("Building WRITE_OBJECT command for: {}", path.display());
-        Ok(ClientCommandPlan::ChunkedInlineWrite {
-            path,
-            mode: ObjectWriteMode::Write,
-            data: content.into_bytes(),
-        })
+        assert_eq!(
+            manager
+                .build_request("tar project backups/project.tar.gz")
+                .unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::CreateArchive {
+                dir: PathBuf::from("project"),
+                archive: Some(PathBuf::from("backups/project.tar.gz")),
+            })
+        );
+        assert_eq!(
+            manager.build_request("untar project.tar.gz").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::ExtractArchive {
+                archive: PathBuf::from("project.tar.gz"),
+                dest: None,
+            })
+        );
+        assert!(manager.build_request("untar").is_err());
     }
 
-    fn build_create_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
-        if args.is_empty() {
-            return Err(FenrisError::MissingField(
-                "create requires a filename".to_string(),
-            ));
-        }
+    #[test]
+    fn test_build_set_mtime() {
+        let manager = RequestManager::default();
 
-        let path = PathBuf::from(args[0]);
-        debug!("Building CREATE_OBJECT command for: {}", path.display());
-        Ok(ClientCommandPlan::Single(FenrisCommand::CreateObject {
-            path,
-        }))
+        assert_eq!(
+            manager.build_request("touch-time restored.txt 0").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::SetMtime {
+                path: PathBuf::from("restored.txt"),
+                mtime: 0,
+            })
+        );
+        assert!(manager.build_request("touch-time restored.txt").is_err());
+        assert!(manager.build_request("touch-time restored.txt -5").is_err());
     }
 
-    fn build_delete_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
-        if args.is_empty() {
-            return Err(FenrisError::MissingField(
-                "rm requires a filename".to_string(),
-            ));
-        }
+    #[test]
+    fn test_build_xattr_commands() {
+        let manager = RequestManager::default();
 
-        let path = PathBuf::from(args[0]);
-        debug!("Building DELETE_OBJECT command for: {}", path.display());
-        Ok(ClientCommandPlan::Single(FenrisCommand::DeleteObject {
-            path,
-        }))
+        assert_eq!(
+            manager
+                .build_request("getattr photo.jpg user.origin")
+                .unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::GetXattr {
+                path: PathBuf::from("photo.jpg"),
+                name: "user.origin".to_string(),
+            })
+        );
+        assert_eq!(
+            manager
+                .build_request("setattr photo.jpg user.origin Camera roll")
+                .unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::SetXattr {
+                path: PathBuf::from("photo.jpg"),
+                name: "user.origin".to_string(),
+                value: b"Camera roll".to_vec(),
+            })
+        );
+        assert!(
+            manager
+                .build_request("setattr photo.jpg user.origin")
+                .is_err()
+        );
     }
 
-    fn build_create_namespace(&self, args: &[&str]) -> Result<ClientCommandPlan> {
-        if args.is_empty() {
-            return Err(FenrisError::MissingField(
-                "mkdir requires a directory name".to_string(),
-            ));
-        }
+    #[test]
+    fn test_build_env_commands() {
+        let manager = RequestManager::default();
 
-        let path = PathBuf::from(args[0]);
-        debug!("Building CREATE_NAMESPACE command for: {}", path.display());
-        Ok(ClientCommandPlan::Single(FenrisCommand::CreateNamespace {
-            path,
-        }))
+        assert_eq!(
+            manager.build_request("getenv BUILD_ID").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::GetEnv {
+                name: "BUILD_ID".to_string(),
+            })
+        );
+        assert_eq!(
+            manager
+                .build_request("setenv GREETING hello there")
+                .unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::SetEnv {
+                name: "GREETING".to_string(),
+                value: "hello there".to_string(),
+            })
+        );
+        assert!(manager.build_request("setenv GREETING").is_err());
+        assert!(manager.build_request("getenv 1ST").is_err());
     }
 
-    fn build_delete_namespace(&self, args: &[&str]) -> Result<ClientCommandPlan> {
-        if args.is_empty() {
-            return Err(FenrisError::MissingField(
-                "rmdir requires a directory name".to_string(),
-            ));
-        }
+    #[test]
+    fn test_build_timed_request() {
+        let manager = RequestManager::default();
 
-        let path = PathBuf::from(args[0]);
-        debug!("Building DELETE_NAMESPACE command for: {}", path.display());
-        Ok(ClientCommandPlan::Single(FenrisCommand::DeleteNamespace {
-            path,
-        }))
+        assert_eq!(
+            manager.build_request("timeout:500 sha256 big.iso").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::Timed {
+                timeout: Duration::from_millis(500),
+                command: Box::new(FenrisCommand::ChecksumObject {
+                    path: PathBuf::from("big.iso"),
+                }),
+            })
+        );
+        assert!(manager.build_request("timeout:abc ping").is_err());
+        assert!(manager.build_request("timeout:500").is_err());
+        assert!(
+            manager
+                .build_request("timeout:500 timeout:20 ping")
+                .is_err()
+        );
     }
 
-    fn build_object_info(&self, args: &[&str]) -> Result<ClientCommandPlan> {
-        if args.is_empty() {
-            return Err(FenrisError::MissingField(
-                "info requires a filename".to_string(),
-            ));
-        }
+    #[test]
+    fn test_build_redirected_request() {
+        let manager = RequestManager::default();
 
-        let path = PathBuf::from(args[0]);
-        debug!("Building OBJECT_INFO command for: {}", path.display());
-        Ok(ClientCommandPlan::Single(FenrisCommand::ObjectInfo {
-            path,
-        }))
+        assert_eq!(
+            manager
+                .build_request("read remote.txt > local.txt")
+                .unwrap(),
+            ClientCommandPlan::Redirected {
+                plan: Box::new(ClientCommandPlan::ChunkedRead {
+                    path: PathBuf::from("remote.txt"),
+                }),
+                redirect: OutputRedirect {
+                    path: PathBuf::from("local.txt"),
+                    append: false,
+                },
+            }
+        );
+        let (plan, redirect) = manager
+            .build_request("timeout:500 ls  >>  listing.log ")
+            .unwrap()
+            .into_parts();
+        assert!(matches!(
+            plan,
+            ClientCommandPlan::Single(FenrisCommand::Timed { .. })
+        ));
+        assert_eq!(
+            redirect,
+            Some(OutputRedirect {
+                path: PathBuf::from("listing.log"),
+                append: true,
+            })
+        );
+
+        assert!(manager.build_request("ls >").is_err());
+        assert!(manager.build_request("ls > a.txt > b.txt").is_err());
+        assert!(
+            manager
+                .build_request("script setup.fenris > out.txt")
+                .is_err()
+        );
     }
 
-    fn build_append_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
-        if args.len() < 2 {
-            return Err(FenrisError::MissingField(
-                "append requires filename as well as data".to_string(),
-            ));
-        }
+    #[test]
+    fn test_build_list_dir_with_filter() {
+        let manager = RequestManager::default();
 
-        let path = PathBuf::from(args[0]);
-        let content = args[1..].join(" ");
-        debug!("Building APPEND_OBJECT command for: {}", path.display());
-        Ok(ClientCommandPlan::ChunkedInlineWrite {
-            path,
-            mode: ObjectWriteMode::Append,
-            data: content.into_bytes(),
-        })
+        assert_eq!(
+            manager
+                .build_request("lsf logs --min-size 1024 --type f --type l --name *.log")
+                .unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::ListNamespaceFiltered {
+                path: PathBuf::from("logs"),
+                filter: DirFilter {
+                    min_size: Some(1024),
+                    max_size: None,
+                    file_types: DirFilter::FILES | DirFilter::SYMLINKS,
+                    name_pattern: Some("*.log".to_string()),
+                },
+            })
+        );
+
+        assert!(manager.build_request("lsf").is_err());
+        assert!(manager.build_request("lsf logs --max-size").is_err());
+        assert!(manager.build_request("lsf logs --max-size big").is_err());
+        assert!(manager.build_request("lsf logs --type x").is_err());
+        assert!(manager.build_request("lsf logs --depth 2").is_err());
     }
 
-    fn build_upload_object(&self, args: &[&str]) -> Result<ClientCommandPlan> {
-        if args.len() < 2 {
-            return Err(FenrisError::MissingField(
-                "upload requires current location as well as destination path".to_string(),
-            ));
-        }
+    #[test]
+    fn test_build_pipeline() {
+        let manager = RequestManager::default();
 
-        let source = PathBuf::from(args[0]);
-        let metadata = fs::metadata(&source).map_err(|e| {
-            FenrisError::FileOperationError(format!(
-                "Failed to inspect file {}: {}",
-                source.display(),
-                e
-            ))
-        })?;
+        assert_eq!(
+            manager
+                .build_request("read file.txt | write output.txt")
+                .unwrap(),
+            ClientCommandPlan::Pipeline {
+                stages: vec![
+                    ClientCommandPlan::ChunkedRead {
+                        path: PathBuf::from("file.txt"),
+                    },
+                    ClientCommandPlan::ChunkedInlineWrite {
+                        path: PathBuf::from("output.txt"),
+                        mode: ObjectWriteMode::Write,
+                        data: Vec::new(),
+                    },
+                ],
+            }
+        );
+        assert!(matches!(
+            manager.build_request("read a.txt | write b.txt > out.txt").unwrap(),
+            ClientCommandPlan::Redirected { plan, .. }
+                if matches!(*plan, ClientCommandPlan::Pipeline { .. })
+        ));
 
-        Ok(ClientCommandPlan::ChunkedUpload {
-            source,
-            destination: PathBuf::from(args[1]),
-            total_size: metadata.len(),
-        })
+        assert!(manager.build_request("read a.txt |").is_err());
+        assert!(manager.build_request("read a.txt | exit").is_err());
+        assert!(manager.build_request("write a.txt | read a.txt").is_err());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs::File;
-    use std::io::Write;
+    #[test]
+    fn test_split_pipeline_ignores_quoted_and_embedded_pipes() {
+        assert_eq!(
+            split_pipeline("ls | write a.txt"),
+            vec!["ls", "write a.txt"]
+        );
+        assert_eq!(split_pipeline("write a.txt x|y"), vec!["write a.txt x|y"]);
+        assert_eq!(split_pipeline("echo \"a | b\""), vec!["echo \"a | b\""]);
+        assert_eq!(
+            split_pipeline("echo 'a | b' | echo"),
+            vec!["echo 'a | b'", "echo"]
+        );
+    }
 
     #[test]
-    fn test_build_ping() {
-        let manager = RequestManager;
-        let command = manager.build_request("ping").unwrap();
+    fn test_with_input_replaces_payloads_only() {
+        let write = ClientCommandPlan::ChunkedInlineWrite {
+            path: PathBuf::from("a.txt"),
+            mode: ObjectWriteMode::Append,
+            data: b"old".to_vec(),
+        };
+        assert_eq!(
+            write.with_input(b"new".to_vec()),
+            ClientCommandPlan::ChunkedInlineWrite {
+                path: PathBuf::from("a.txt"),
+                mode: ObjectWriteMode::Append,
+                data: b"new".to_vec(),
+            }
+        );
 
-        assert_eq!(command, ClientCommandPlan::Single(FenrisCommand::Ping));
+        let read = ClientCommandPlan::ChunkedRead {
+            path: PathBuf::from("a.txt"),
+        };
+        assert_eq!(read.clone().with_input(b"ignored".to_vec()), read);
+    }
+
+    #[test]
+    fn test_build_local_script() {
+        let manager = RequestManager::default();
+
+        assert_eq!(
+            manager.build_request("script setup.fenris").unwrap(),
+            ClientCommandPlan::LocalScript {
+                path: PathBuf::from("setup.fenris"),
+                ignore_errors: false,
+            }
+        );
+        assert_eq!(
+            manager
+                .build_request("script --ignore-errors setup.fenris")
+                .unwrap(),
+            ClientCommandPlan::LocalScript {
+                path: PathBuf::from("setup.fenris"),
+                ignore_errors: true,
+            }
+        );
+        assert!(manager.build_request("script --ignore-errors").is_err());
+    }
+
+    #[test]
+    fn test_build_fetch_url() {
+        let manager = RequestManager::default();
+        let command = manager
+            .build_request("fetch https://example.com/a.bin downloads/a.bin")
+            .unwrap();
+
+        assert_eq!(
+            command,
+            ClientCommandPlan::Single(FenrisCommand::FetchUrl {
+                url: "https://example.com/a.bin".to_string(),
+                path: PathBuf::from("downloads/a.bin"),
+            })
+        );
+        assert!(manager.build_request("fetch https://example.com").is_err());
     }
 
     #[test]
     fn test_build_list_dir() {
-        let manager = RequestManager;
+        let manager = RequestManager::default();
 
         let command = manager.build_request("ls /home").unwrap();
         assert_eq!(
             command,
             ClientCommandPlan::Single(FenrisCommand::ListNamespace {
-                path: PathBuf::from("/home")
+                path: PathBuf::from("/home"),
+                sort: ListSort::default(),
             })
         );
 
@@ -245,14 +1846,30 @@ mod tests {
         assert_eq!(
             command_default,
             ClientCommandPlan::Single(FenrisCommand::ListNamespace {
-                path: PathBuf::from(".")
+                path: PathBuf::from("."),
+                sort: ListSort::default(),
             })
         );
+
+        assert_eq!(
+            manager
+                .build_request("ls docs --sort=name --reverse --dirs-first")
+                .unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::ListNamespace {
+                path: PathBuf::from("docs"),
+                sort: ListSort {
+                    key: Some(ListSortKey::Name),
+                    reverse: true,
+                    dirs_first: true,
+                },
+            })
+        );
+        assert!(manager.build_request("ls docs --sort=color").is_err());
     }
 
     #[test]
     fn test_build_change_dir() {
-        let manager = RequestManager;
+        let manager = RequestManager::default();
         let command = manager.build_request("cd /tmp").unwrap();
         assert_eq!(
             command,
@@ -272,7 +1889,7 @@ mod tests {
 
     #[test]
     fn test_build_read_file() {
-        let manager = RequestManager;
+        let manager = RequestManager::default();
         let command = manager.build_request("read test.txt").unwrap();
 
         assert_eq!(
@@ -283,12 +1900,15 @@ mod tests {
         );
 
         let result = manager.build_request("read");
-        assert!(matches!(result.unwrap_err(), FenrisError::MissingField(_)));
+        assert!(matches!(
+            result.unwrap_err(),
+            FenrisError::InvalidRequest(_)
+        ));
     }
 
     #[test]
     fn test_build_write_file() {
-        let manager = RequestManager;
+        let manager = RequestManager::default();
         let command = manager.build_request("write test.txt Hello World").unwrap();
 
         assert_eq!(
@@ -301,12 +1921,15 @@ mod tests {
         );
 
         let result = manager.build_request("write test.txt");
-        assert!(matches!(result.unwrap_err(), FenrisError::MissingField(_)));
+        assert!(matches!(
+            result.unwrap_err(),
+            FenrisError::InvalidRequest(message) if message == "Usage: write <file> <content...>"
+        ));
     }
 
     #[test]
     fn test_build_create_file() {
-        let manager = RequestManager;
+        let manager = RequestManager::default();
         let command = manager.build_request("create newfile.txt").unwrap();
 
         assert_eq!(
@@ -317,12 +1940,15 @@ mod tests {
         );
 
         let result = manager.build_request("create");
-        assert!(matches!(result.unwrap_err(), FenrisError::MissingField(_)));
+        assert!(matches!(
+            result.unwrap_err(),
+            FenrisError::InvalidRequest(_)
+        ));
     }
 
     #[test]
     fn test_build_delete_file() {
-        let manager = RequestManager;
+        let manager = RequestManager::default();
         let command = manager.build_request("rm oldfile.txt").unwrap();
 
         assert_eq!(
@@ -333,12 +1959,15 @@ mod tests {
         );
 
         let result = manager.build_request("rm");
-        assert!(matches!(result.unwrap_err(), FenrisError::MissingField(_)));
+        assert!(matches!(
+            result.unwrap_err(),
+            FenrisError::InvalidRequest(_)
+        ));
     }
 
     #[test]
     fn test_build_create_dir() {
-        let manager = RequestManager;
+        let manager = RequestManager::default();
         let command = manager.build_request("mkdir newdir").unwrap();
 
         assert_eq!(
@@ -349,12 +1978,24 @@ mod tests {
         );
 
         let result = manager.build_request("mkdir");
-        assert!(matches!(result.unwrap_err(), FenrisError::MissingField(_)));
+        assert!(matches!(
+            result.unwrap_err(),
+            FenrisError::InvalidRequest(_)
+        ));
+
+        assert_eq!(
+            manager.build_request("mkdir -p a/b/c").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::CreateNamespaceAll {
+                path: PathBuf::from("a/b/c")
+            })
+        );
+        assert!(manager.build_request("mkdir -p").is_err());
+        assert!(manager.build_request("mkdir a b").is_err());
     }
 
     #[test]
     fn test_build_delete_dir() {
-        let manager = RequestManager;
+        let manager = RequestManager::default();
         let command = manager.build_request("rmdir olddir").unwrap();
 
         assert_eq!(
@@ -365,28 +2006,42 @@ mod tests {
         );
 
         let result = manager.build_request("rmdir");
-        assert!(matches!(result.unwrap_err(), FenrisError::MissingField(_)));
+        assert!(matches!(
+            result.unwrap_err(),
+            FenrisError::InvalidRequest(_)
+        ));
     }
 
     #[test]
     fn test_build_file_info() {
-        let manager = RequestManager;
+        let manager = RequestManager::default();
         let command = manager.build_request("info myfile.txt").unwrap();
 
         assert_eq!(
             command,
             ClientCommandPlan::Single(FenrisCommand::ObjectInfo {
-                path: PathBuf::from("myfile.txt")
+                path: PathBuf::from("myfile.txt"),
+                detect_mime: false,
+            })
+        );
+        assert_eq!(
+            manager.build_request("info --mime photo.jpg").unwrap(),
+            ClientCommandPlan::Single(FenrisCommand::ObjectInfo {
+                path: PathBuf::from("photo.jpg"),
+                detect_mime: true,
             })
         );
 
         let result = manager.build_request("info");
-        assert!(matches!(result.unwrap_err(), FenrisError::MissingField(_)));
+        assert!(matches!(
+            result.unwrap_err(),
+            FenrisError::InvalidRequest(_)
+        ));
     }
 
     #[test]
     fn test_build_append_file() {
-        let manager = RequestManager;
+        let manager = RequestManager::default();
         let command = manager.build_request("append log.txt new entry").unwrap();
 
         assert_eq!(
@@ -399,12 +2054,15 @@ mod tests {
         );
 
         let result = manager.build_request("append log.txt");
-        assert!(matches!(result.unwrap_err(), FenrisError::MissingField(_)));
+        assert!(matches!(
+            result.unwrap_err(),
+            FenrisError::InvalidRequest(_)
+        ));
     }
 
     #[test]
     fn test_build_upload_file() {
-        let manager = RequestManager;
+        let manager = RequestManager::default();
 
         let mut temp_path = std::env::temp_dir();
         temp_path.push("fenris_test_upload.txt");
@@ -433,18 +2091,21 @@ mod tests {
         let _ = fs::remove_file(temp_path);
 
         let result = manager.build_request("upload local_file");
-        assert!(matches!(result.unwrap_err(), FenrisError::MissingField(_)));
+        assert!(matches!(
+            result.unwrap_err(),
+            FenrisError::InvalidRequest(_)
+        ));
 
         let result = manager.build_request("upload non_existent_file.txt dest.txt");
         assert!(matches!(
             result.unwrap_err(),
-            FenrisError::FileOperationError(_)
+            FenrisError::FileOperationError { .. }
         ));
     }
 
     #[test]
     fn test_invalid_command() {
-        let manager = RequestManager;
+        let manager = RequestManager::default();
         let result = manager.build_request("invalid");
 
         assert!(result.is_err());
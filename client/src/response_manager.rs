@@ -1,4 +1,9 @@
-use common::{FenrisMetadata, FenrisOutput};
+use crate::manifest::{self, ManifestDiff};
+use common::{
+    FenrisMetadata, FenrisOutput, ListSort, MAX_MANIFEST_ENTRIES, WatchEvent, WatchEventKind,
+};
+use std::path::PathBuf;
+use std::time::Duration;
 use tracing::debug;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -7,17 +12,108 @@ pub struct FormattedResponse {
     pub message: String,
     pub details: Option<String>,
     pub current_dir: Option<String>,
+    pub details_format: DetailsFormat,
+    /// Headers and rows for responses that read best as a table. `details` still holds
+    /// the same data as text for plain-output callers.
+    pub table_data: Option<(Vec<String>, Vec<Vec<String>>)>,
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct ResponseManager;
+impl FormattedResponse {
+    /// One JSON object with `success`, `message`, `details` and `current_dir`, for scripts.
+    pub fn to_json(&self) -> String {
+        serde_json::json!({
+            "success": self.success,
+            "message": self.message,
+            "details": self.details,
+            "current_dir": self.current_dir,
+        })
+        .to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetailsFormat {
+    Plain,
+    Diff,
+}
+
+const HEX_DUMP_WIDTH: usize = 16;
+const HEX_DUMP_MAX_ROWS: usize = 64;
+const DEFAULT_BINARY_THRESHOLD: f32 = 0.1;
+/// Width of the Type, Size and Modified columns plus their separators.
+const LISTING_FIXED_COLUMNS_WIDTH: usize = 44;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatterOptions {
+    /// Minimum width of the Name column; it widens to fill `terminal_width`.
+    pub name_width: usize,
+    pub terminal_width: usize,
+    /// A `chrono` strftime pattern.
+    pub date_format: String,
+}
+
+impl Default for FormatterOptions {
+    fn default() -> Self {
+        Self {
+            name_width: 40,
+            terminal_width: 80,
+            date_format: "%Y-%m-%d %H:%M:%S".to_string(),
+        }
+    }
+}
+
+impl FormatterOptions {
+    /// Defaults, with `terminal_width` taken from the terminal when there is one.
+    pub fn for_terminal() -> Self {
+        let mut options = Self::default();
+        if let Ok((columns, _)) = crossterm::terminal::size()
+            && columns > 0
+        {
+            options.terminal_width = columns as usize;
+        }
+        options
+    }
+
+    fn listing_name_width(&self) -> usize {
+        self.name_width.max(
+            self.terminal_width
+                .saturating_sub(LISTING_FIXED_COLUMNS_WIDTH),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ResponseManager {
+    binary_threshold: f32,
+    options: FormatterOptions,
+}
+
+impl Default for ResponseManager {
+    fn default() -> Self {
+        Self::new_with_binary_threshold(DEFAULT_BINARY_THRESHOLD)
+    }
+}
 
 impl ResponseManager {
+    /// `threshold` is the fraction of non-printable bytes above which file
+    /// content is shown as a hex dump instead of text.
+    pub fn new_with_binary_threshold(threshold: f32) -> Self {
+        Self {
+            binary_threshold: threshold,
+            options: FormatterOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: FormatterOptions) -> Self {
+        self.options = options;
+        self
+    }
+
     pub fn format_response(&self, response: &FenrisOutput) -> FormattedResponse {
         debug!("Formatting domain response: {:?}", response);
 
         match response {
-            FenrisOutput::Pong => self.format_pong(),
+            FenrisOutput::Pong => self.format_pong(None),
             FenrisOutput::Success { message } => self.format_success(message),
             FenrisOutput::ObjectContent {
                 data,
@@ -27,8 +123,16 @@ impl ResponseManager {
             FenrisOutput::ObjectContentChunk(chunk) => {
                 self.format_object_content(&chunk.data, chunk.total_size, !chunk.is_last)
             }
-            FenrisOutput::ObjectInfo { metadata } => self.format_object_info(metadata),
-            FenrisOutput::NamespaceListing { entries } => self.format_namespace_listing(entries),
+            FenrisOutput::ObjectInfo {
+                metadata,
+                mime_type,
+            } => self.format_object_info(metadata, mime_type.as_deref()),
+            FenrisOutput::NamespaceListing { entries, sort } => {
+                self.format_namespace_listing(entries, sort)
+            }
+            FenrisOutput::RecursiveNamespaceListing { entries } => {
+                self.format_recursive_namespace_listing(entries)
+            }
             FenrisOutput::NamespaceChanged { path } => {
                 self.format_namespace_changed(&path.to_string_lossy())
             }
@@ -37,34 +141,136 @@ impl ResponseManager {
                 message: format!("Transfer ready ({} byte chunks)", chunk_size),
                 details: None,
                 current_dir: None,
+                details_format: DetailsFormat::Plain,
+                table_data: None,
             },
             FenrisOutput::TransferProgress { offset } => FormattedResponse {
                 success: true,
                 message: format!("Transferred {} bytes", offset),
                 details: None,
                 current_dir: None,
+                details_format: DetailsFormat::Plain,
+                table_data: None,
             },
+            FenrisOutput::WatchEvent(event) => self.format_watch_event(event),
+            FenrisOutput::ObjectDiff { diff } => self.format_object_diff(diff),
+            FenrisOutput::ObjectChecksum { digest } => self.format_object_checksum(digest),
+            FenrisOutput::ChecksumManifest { entries, truncated } => {
+                self.format_checksum_manifest(entries, *truncated)
+            }
+            FenrisOutput::Broadcast { message } => self.format_broadcast(message),
+            FenrisOutput::Banner { message } => self.format_banner(message),
+            FenrisOutput::Echo { payload } => self.format_echo(payload, None),
+            FenrisOutput::QuotaInfo {
+                used_bytes,
+                limit_bytes,
+            } => self.format_quota_info(*used_bytes, *limit_bytes),
+            FenrisOutput::SymlinkTarget { target } => FormattedResponse {
+                success: true,
+                message: format!("-> {}", target.to_string_lossy()),
+                details: None,
+                current_dir: None,
+                details_format: DetailsFormat::Plain,
+                table_data: None,
+            },
+            FenrisOutput::XattrValue { value } => self.format_xattr_value(value),
+            FenrisOutput::EnvValue { value } => self.format_env_value(value),
             FenrisOutput::Terminated => FormattedResponse {
                 success: true,
                 message: "Server terminated".to_string(),
                 details: None,
                 current_dir: None,
+                details_format: DetailsFormat::Plain,
+                table_data: None,
             },
+            FenrisOutput::MultiOp { outputs } => self.format_multi_op(outputs),
+            FenrisOutput::Correlated { output, .. } => self.format_response(output),
             FenrisOutput::Error { message } => FormattedResponse {
                 success: false,
                 message: message.clone(),
                 details: None,
                 current_dir: None,
+                details_format: DetailsFormat::Plain,
+                table_data: None,
             },
         }
     }
 
-    fn format_pong(&self) -> FormattedResponse {
+    /// `latency` is the full round trip, when the caller timed it.
+    pub fn format_pong(&self, latency: Option<Duration>) -> FormattedResponse {
+        let message = match latency {
+            Some(latency) => format!(
+                "PONG - Server is alive! ({:.2} ms)",
+                latency.as_secs_f64() * 1000.0
+            ),
+            None => "PONG - Server is alive!".to_string(),
+        };
+        FormattedResponse {
+            success: true,
+            message,
+            details: None,
+            current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        }
+    }
+
+    /// `latency` is the full round trip, when the caller timed it.
+    pub fn format_echo(&self, payload: &[u8], latency: Option<Duration>) -> FormattedResponse {
+        let message = match latency {
+            Some(latency) => format!(
+                "Echoed {} bytes in {:.2} ms",
+                payload.len(),
+                latency.as_secs_f64() * 1000.0
+            ),
+            None => format!("Echoed {} bytes", payload.len()),
+        };
+        let details = if payload.is_empty() {
+            None
+        } else if self.is_binary(payload) {
+            Some(format_hex_dump(payload, HEX_DUMP_MAX_ROWS))
+        } else {
+            Some(String::from_utf8_lossy(payload).to_string())
+        };
+
+        FormattedResponse {
+            success: true,
+            message,
+            details,
+            current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        }
+    }
+
+    fn format_xattr_value(&self, value: &[u8]) -> FormattedResponse {
+        let (message, details) = if self.is_binary(value) {
+            (
+                format!("{} bytes", value.len()),
+                Some(format_hex_dump(value, HEX_DUMP_MAX_ROWS)),
+            )
+        } else {
+            (String::from_utf8_lossy(value).to_string(), None)
+        };
+
         FormattedResponse {
             success: true,
-            message: "PONG - Server is alive! ".to_string(),
+            message,
+            details,
+            current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        }
+    }
+
+    fn format_env_value(&self, value: &str) -> FormattedResponse {
+        FormattedResponse {
+            success: true,
+            message: value.to_string(),
             details: None,
             current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
         }
     }
 
@@ -80,6 +286,235 @@ impl ResponseManager {
             message,
             details: None,
             current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        }
+    }
+
+    pub fn format_watch_event(&self, event: &WatchEvent) -> FormattedResponse {
+        let change = match event.kind {
+            WatchEventKind::Created => "created",
+            WatchEventKind::Modified => "modified",
+            WatchEventKind::Deleted => "deleted",
+        };
+
+        FormattedResponse {
+            success: true,
+            message: format!("📁 {} {}", event.path.to_string_lossy(), change),
+            details: None,
+            current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        }
+    }
+
+    pub fn format_broadcast(&self, message: &str) -> FormattedResponse {
+        FormattedResponse {
+            success: true,
+            message: format!("📢 {}", message),
+            details: None,
+            current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        }
+    }
+
+    fn format_banner(&self, message: &str) -> FormattedResponse {
+        FormattedResponse {
+            success: true,
+            message: message.to_string(),
+            details: None,
+            current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        }
+    }
+
+    fn format_quota_info(&self, used_bytes: u64, limit_bytes: Option<u64>) -> FormattedResponse {
+        let message = match limit_bytes {
+            Some(limit) => format!(
+                "Using {} of {} ({:.1}%)",
+                format_size(used_bytes),
+                format_size(limit),
+                used_bytes as f64 * 100.0 / limit.max(1) as f64
+            ),
+            None => format!("Using {} (no quota)", format_size(used_bytes)),
+        };
+
+        FormattedResponse {
+            success: true,
+            message,
+            details: None,
+            current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        }
+    }
+
+    /// One `sha256sum` line per file, so the details can be saved and checked with
+    /// `sha256sum -c` from inside the same directory.
+    fn format_checksum_manifest(
+        &self,
+        entries: &[(PathBuf, [u8; 32])],
+        truncated: bool,
+    ) -> FormattedResponse {
+        let message = if truncated {
+            format!(
+                "SHA-256 manifest of the first {} files (warning: the directory has more)",
+                entries.len()
+            )
+        } else {
+            format!("SHA-256 manifest of {} files", entries.len())
+        };
+        let details = entries
+            .iter()
+            .map(|(path, digest)| manifest::format_line(path, digest))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        FormattedResponse {
+            success: true,
+            message,
+            details: (!details.is_empty()).then_some(details),
+            current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        }
+    }
+
+    /// `checked` is the number of files in the local manifest. Mismatches are listed the way
+    /// `sha256sum -c` reports them.
+    pub fn format_manifest_check(
+        &self,
+        diff: &ManifestDiff,
+        checked: usize,
+        truncated: bool,
+    ) -> FormattedResponse {
+        let mut lines: Vec<String> = diff
+            .mismatched
+            .iter()
+            .map(|path| format!("{}: FAILED", path.to_string_lossy()))
+            .chain(
+                diff.missing
+                    .iter()
+                    .map(|path| format!("{}: MISSING on server", path.to_string_lossy())),
+            )
+            .chain(
+                diff.unexpected
+                    .iter()
+                    .map(|path| format!("{}: not in local manifest", path.to_string_lossy())),
+            )
+            .collect();
+        if truncated {
+            lines.push(format!(
+                "warning: the server listed only the first {} files",
+                MAX_MANIFEST_ENTRIES
+            ));
+        }
+
+        let message = if diff.is_clean() {
+            format!("All {} files match", checked)
+        } else {
+            format!(
+                "{} mismatched, {} missing, {} unexpected of {} files",
+                diff.mismatched.len(),
+                diff.missing.len(),
+                diff.unexpected.len(),
+                checked
+            )
+        };
+
+        FormattedResponse {
+            success: diff.is_clean(),
+            message,
+            details: (!lines.is_empty()).then(|| lines.join("\n")),
+            current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        }
+    }
+
+    fn format_object_checksum(&self, digest: &[u8; 32]) -> FormattedResponse {
+        let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        FormattedResponse {
+            success: true,
+            message: format!("SHA-256: {}", hex),
+            details: None,
+            current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        }
+    }
+
+    fn format_object_diff(&self, diff: &str) -> FormattedResponse {
+        if diff.is_empty() {
+            return FormattedResponse {
+                success: true,
+                message: "Files are identical".to_string(),
+                details: None,
+                current_dir: None,
+                details_format: DetailsFormat::Plain,
+                table_data: None,
+            };
+        }
+
+        let changed = |prefix: char, header: &str| {
+            diff.lines()
+                .filter(|line| line.starts_with(prefix) && !line.starts_with(header))
+                .count()
+        };
+
+        FormattedResponse {
+            success: true,
+            message: format!(
+                "Diff: {} additions, {} deletions",
+                changed('+', "+++ "),
+                changed('-', "--- ")
+            ),
+            details: Some(diff.to_string()),
+            current_dir: None,
+            details_format: DetailsFormat::Diff,
+            table_data: None,
+        }
+    }
+
+    /// Succeeds only if every command did. The last directory change wins.
+    fn format_multi_op(&self, outputs: &[FenrisOutput]) -> FormattedResponse {
+        let total = outputs.len();
+        let mut failed = 0;
+        let mut current_dir = None;
+        let mut details = String::new();
+
+        for (index, output) in outputs.iter().enumerate() {
+            let formatted = self.format_response(output);
+            if !formatted.success {
+                failed += 1;
+            }
+            if formatted.current_dir.is_some() {
+                current_dir = formatted.current_dir;
+            }
+
+            details.push_str(&format!(
+                "[{}/{}] {}\n",
+                index + 1,
+                total,
+                formatted.message
+            ));
+            if let Some(sub_details) = formatted.details {
+                for line in sub_details.lines() {
+                    details.push_str(&format!("  {}\n", line));
+                }
+            }
+        }
+
+        FormattedResponse {
+            success: failed == 0,
+            message: format!("Ran {} operations ({} failed)", total, failed),
+            details: (!details.is_empty()).then_some(details),
+            current_dir,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
         }
     }
 
@@ -91,6 +526,8 @@ impl ResponseManager {
             message: format!("Changed directory to {}", path),
             details: None,
             current_dir: Some(path.to_string()),
+            details_format: DetailsFormat::Plain,
+            table_data: None,
         }
     }
 
@@ -100,6 +537,17 @@ impl ResponseManager {
         total_size: u64,
         already_truncated: bool,
     ) -> FormattedResponse {
+        if self.is_binary(data) {
+            return FormattedResponse {
+                success: true,
+                message: format!("Binary file content ({} bytes):", total_size),
+                details: Some(format_hex_dump(data, HEX_DUMP_MAX_ROWS)),
+                current_dir: None,
+                details_format: DetailsFormat::Plain,
+                table_data: None,
+            };
+        }
+
         let content = String::from_utf8_lossy(data).to_string();
         let preview_text: String = content.chars().take(500).collect();
         let display_truncated = already_truncated || content.chars().count() > 500;
@@ -114,10 +562,33 @@ impl ResponseManager {
             message: format!("File content ({} bytes):", total_size),
             details: Some(preview),
             current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
         }
     }
 
-    fn format_object_info(&self, metadata: &FenrisMetadata) -> FormattedResponse {
+    fn is_binary(&self, data: &[u8]) -> bool {
+        if data.is_empty() {
+            return false;
+        }
+
+        let valid_utf8 = std::str::from_utf8(data).is_ok();
+        let non_printable = data
+            .iter()
+            .filter(|byte| match byte {
+                b'\t' | b'\n' | b'\r' | 0x20..=0x7e => false,
+                0x80.. => !valid_utf8,
+                _ => true,
+            })
+            .count();
+        non_printable as f32 / data.len() as f32 > self.binary_threshold
+    }
+
+    fn format_object_info(
+        &self,
+        metadata: &FenrisMetadata,
+        mime_type: Option<&str>,
+    ) -> FormattedResponse {
         let object_type = if metadata.is_namespace {
             "Directory"
         } else {
@@ -131,40 +602,67 @@ impl ResponseManager {
         };
 
         let permissions = format_permissions(metadata.permissions);
-        let modified = format_timestamp(metadata.modified_time);
+        let modified = format_timestamp(metadata.modified_time, &self.options.date_format);
 
-        let details = format!(
+        let mut details = format!(
             "{}\nType: {}\nSize: {}\nPermissions: {}\nModified: {}",
             metadata.name, object_type, size, permissions, modified
         );
+        if metadata.created_time != 0 {
+            details.push_str(&format!(
+                "\nCreated: {}",
+                format_timestamp(metadata.created_time, &self.options.date_format)
+            ));
+        }
+        if let Some(mime_type) = mime_type {
+            details.push_str(&format!("\nMIME: {}", mime_type));
+        }
 
         FormattedResponse {
             success: true,
             message: "File information: ".to_string(),
             details: Some(details),
             current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
         }
     }
 
-    fn format_namespace_listing(&self, entries: &[FenrisMetadata]) -> FormattedResponse {
+    fn format_namespace_listing(
+        &self,
+        entries: &[FenrisMetadata],
+        sort: &ListSort,
+    ) -> FormattedResponse {
         if entries.is_empty() {
             return FormattedResponse {
                 success: true,
                 message: "Directory is empty".to_string(),
                 details: None,
                 current_dir: None,
+                details_format: DetailsFormat::Plain,
+                table_data: None,
             };
         }
 
         let mut output = String::new();
-        output.push_str(&format!("Found {} entries:\n\n", entries.len()));
+        if sort.is_default() {
+            output.push_str(&format!("Found {} entries:\n\n", entries.len()));
+        } else {
+            output.push_str(&format!(
+                "Found {} entries (sorted by {}):\n\n",
+                entries.len(),
+                sort
+            ));
+        }
+        let name_width = self.options.listing_name_width();
         output.push_str(&format!(
-            "{:40} {: >10} {:>12} {}\n",
+            "{:name_width$} {: >10} {:>12} {}\n",
             "Name", "Type", "Size", "Modified"
         ));
-        output.push_str(&"-".repeat(80));
+        output.push_str(&"-".repeat(self.options.terminal_width));
         output.push('\n');
 
+        let mut rows = Vec::with_capacity(entries.len());
         for entry in entries {
             let object_type = if entry.is_namespace { "DIR" } else { "FILE" };
             let size = if entry.is_namespace {
@@ -172,24 +670,102 @@ impl ResponseManager {
             } else {
                 format_size(entry.size)
             };
-            let modified = format_timestamp(entry.modified_time);
+            let modified = format_timestamp(entry.modified_time, &self.options.date_format);
 
             output.push_str(&format!(
-                "{:40} {:>10} {:>12} {}\n",
-                entry.name, object_type, size, modified
+                "{:name_width$} {:>10} {:>12} {}\n",
+                truncate_name(&entry.name, name_width),
+                object_type,
+                size,
+                modified
             ));
+            rows.push(vec![
+                entry.name.clone(),
+                object_type.to_string(),
+                size,
+                modified,
+            ]);
         }
+        let headers = ["Name", "Type", "Size", "Modified"]
+            .map(String::from)
+            .to_vec();
 
         FormattedResponse {
             success: true,
             message: "Directory listing:".to_string(),
             details: Some(output),
             current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: Some((headers, rows)),
         }
     }
+
+    fn format_recursive_namespace_listing(
+        &self,
+        entries: &[(PathBuf, FenrisMetadata)],
+    ) -> FormattedResponse {
+        if entries.is_empty() {
+            return FormattedResponse {
+                success: true,
+                message: "Directory is empty".to_string(),
+                details: None,
+                current_dir: None,
+                details_format: DetailsFormat::Plain,
+                table_data: None,
+            };
+        }
+
+        let mut output = String::new();
+        for (path, entry) in entries {
+            let depth = path.components().count().saturating_sub(1);
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| entry.name.clone());
+
+            if entry.is_namespace {
+                output.push_str(&format!("{}{}/\n", "  ".repeat(depth), name));
+            } else {
+                output.push_str(&format!(
+                    "{}{} ({})\n",
+                    "  ".repeat(depth),
+                    name,
+                    format_size(entry.size)
+                ));
+            }
+        }
+
+        FormattedResponse {
+            success: true,
+            message: format!("Recursive listing ({} entries):", entries.len()),
+            details: Some(output),
+            current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        }
+    }
+}
+
+pub fn colorize_diff_ansi(diff: &str) -> String {
+    let mut output = String::with_capacity(diff.len());
+
+    for line in diff.lines() {
+        let color = if line.starts_with('+') {
+            "\x1b[32m"
+        } else if line.starts_with('-') {
+            "\x1b[31m"
+        } else {
+            "\x1b[90m"
+        };
+        output.push_str(color);
+        output.push_str(line);
+        output.push_str("\x1b[0m\n");
+    }
+
+    output
 }
 
-fn format_size(bytes: u64) -> String {
+pub(crate) fn format_size(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
 
     if bytes == 0 {
@@ -211,6 +787,43 @@ fn format_size(bytes: u64) -> String {
     }
 }
 
+pub fn format_hex_dump(data: &[u8], max_rows: usize) -> String {
+    let mut output = String::new();
+
+    for (row, chunk) in data.chunks(HEX_DUMP_WIDTH).take(max_rows).enumerate() {
+        output.push_str(&format!("{:04x}:", row * HEX_DUMP_WIDTH));
+        for column in 0..HEX_DUMP_WIDTH {
+            if column > 0 && column % 4 == 0 {
+                output.push(' ');
+            }
+            match chunk.get(column) {
+                Some(byte) => output.push_str(&format!(" {:02x}", byte)),
+                None => output.push_str("   "),
+            }
+        }
+
+        output.push_str("  ");
+        output.extend(chunk.iter().map(|byte| {
+            if byte.is_ascii_graphic() || *byte == b' ' {
+                *byte as char
+            } else {
+                '.'
+            }
+        }));
+        output.push('\n');
+    }
+
+    let shown = data.len().min(max_rows * HEX_DUMP_WIDTH);
+    if shown < data.len() {
+        output.push_str(&format!(
+            "... {} more bytes not shown\n",
+            data.len() - shown
+        ));
+    }
+
+    output
+}
+
 fn format_permissions(perms: u32) -> String {
     let user = (perms >> 6) & 0x7;
     let group = (perms >> 3) & 0x7;
@@ -234,12 +847,22 @@ fn format_permissions(perms: u32) -> String {
     )
 }
 
-fn format_timestamp(timestamp: u64) -> String {
+pub(crate) fn format_timestamp(timestamp: u64, date_format: &str) -> String {
     use std::time::{Duration, UNIX_EPOCH};
 
     let datetime = UNIX_EPOCH + Duration::from_secs(timestamp);
     let datetime: chrono::DateTime<chrono::Local> = datetime.into();
-    datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+    datetime.format(date_format).to_string()
+}
+
+/// Shortens `name` to at most `width` characters, marking the cut with `…`.
+fn truncate_name(name: &str, width: usize) -> String {
+    if name.chars().count() <= width {
+        return name.to_string();
+    }
+    let mut truncated: String = name.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
 }
 
 #[cfg(test)]
@@ -248,7 +871,7 @@ mod tests {
 
     #[test]
     fn test_default_formatter() {
-        let formatter = ResponseManager;
+        let formatter = ResponseManager::default();
 
         let response = FenrisOutput::Success {
             message: "Test data".to_string(),
@@ -261,16 +884,51 @@ mod tests {
 
     #[test]
     fn test_response_manager_wrapper() {
-        let manager = ResponseManager;
+        let manager = ResponseManager::default();
 
         let formatted = manager.format_response(&FenrisOutput::Pong);
         assert!(formatted.success);
         assert!(formatted.message.contains("PONG"));
+
+        let timed = manager.format_pong(Some(Duration::from_micros(2250)));
+        assert_eq!(timed.message, "PONG - Server is alive! (2.25 ms)");
+    }
+
+    #[test]
+    fn test_format_echo_reports_latency_and_hex_dumps_binary() {
+        let manager = ResponseManager::default();
+
+        let text = manager.format_echo(b"hello", Some(Duration::from_micros(1500)));
+        assert_eq!(text.message, "Echoed 5 bytes in 1.50 ms");
+        assert_eq!(text.details.as_deref(), Some("hello"));
+
+        let binary = manager.format_response(&FenrisOutput::Echo {
+            payload: vec![0, 1, 2, 0xff],
+        });
+        assert_eq!(binary.message, "Echoed 4 bytes");
+        assert!(binary.details.unwrap().starts_with("0000: 00 01 02 ff"));
+    }
+
+    #[test]
+    fn test_format_xattr_value() {
+        let manager = ResponseManager::default();
+
+        let text = manager.format_response(&FenrisOutput::XattrValue {
+            value: b"Camera roll".to_vec(),
+        });
+        assert_eq!(text.message, "Camera roll");
+        assert!(text.details.is_none());
+
+        let binary = manager.format_response(&FenrisOutput::XattrValue {
+            value: vec![0, 1, 2, 0xff],
+        });
+        assert_eq!(binary.message, "4 bytes");
+        assert!(binary.details.unwrap().starts_with("0000: 00 01 02 ff"));
     }
 
     #[test]
     fn test_format_object_content() {
-        let manager = ResponseManager;
+        let manager = ResponseManager::default();
 
         let formatted = manager.format_response(&FenrisOutput::ObjectContent {
             data: b"hello".to_vec(),
@@ -285,7 +943,7 @@ mod tests {
 
     #[test]
     fn test_format_truncated_object_content() {
-        let manager = ResponseManager;
+        let manager = ResponseManager::default();
 
         let formatted = manager.format_response(&FenrisOutput::ObjectContent {
             data: b"preview".to_vec(),
@@ -298,9 +956,39 @@ mod tests {
         assert!(formatted.details.unwrap().contains("2048 bytes total"));
     }
 
+    #[test]
+    fn test_format_hex_dump_row_layout() {
+        assert_eq!(
+            format_hex_dump(b"Hello, World!\n", HEX_DUMP_MAX_ROWS),
+            "0000: 48 65 6c 6c  6f 2c 20 57  6f 72 6c 64  21 0a        Hello, World!.\n"
+        );
+
+        let dump = format_hex_dump(&[0u8; 40], 2);
+        assert_eq!(dump.lines().count(), 3);
+        assert!(dump.starts_with("0000: 00 00"));
+        assert!(dump.contains("0010: 00 00"));
+        assert!(dump.ends_with("... 8 more bytes not shown\n"));
+    }
+
+    #[test]
+    fn test_binary_object_content_uses_hex_dump() {
+        let binary = FenrisOutput::ObjectContent {
+            data: vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0xff],
+            total_size: 10,
+            truncated: false,
+        };
+
+        let formatted = ResponseManager::default().format_response(&binary);
+        assert!(formatted.message.contains("Binary"));
+        assert!(formatted.details.unwrap().starts_with("0000: 89 50 4e 47"));
+
+        let lenient = ResponseManager::new_with_binary_threshold(1.0).format_response(&binary);
+        assert!(!lenient.message.contains("Binary"));
+    }
+
     #[test]
     fn test_format_object_info() {
-        let manager = ResponseManager;
+        let manager = ResponseManager::default();
 
         let formatted = manager.format_response(&FenrisOutput::ObjectInfo {
             metadata: FenrisMetadata {
@@ -308,18 +996,38 @@ mod tests {
                 size: 12,
                 is_namespace: false,
                 modified_time: 0,
+                created_time: 0,
                 permissions: 0o644,
             },
+            mime_type: None,
         });
 
         assert!(formatted.success);
         assert!(formatted.message.contains("File information"));
-        assert!(formatted.details.unwrap().contains("file.txt"));
+        let details = formatted.details.unwrap();
+        assert!(details.contains("file.txt"));
+        assert!(!details.contains("Created"));
+        assert!(!details.contains("MIME"));
+
+        let formatted = manager.format_response(&FenrisOutput::ObjectInfo {
+            metadata: FenrisMetadata {
+                name: "file.txt".to_string(),
+                size: 12,
+                is_namespace: false,
+                modified_time: 0,
+                created_time: 1_700_000_000,
+                permissions: 0o644,
+            },
+            mime_type: Some("text/plain".to_string()),
+        });
+        let details = formatted.details.unwrap();
+        assert!(details.contains("\nCreated: "));
+        assert!(details.ends_with("\nMIME: text/plain"));
     }
 
     #[test]
     fn test_format_namespace_listing() {
-        let manager = ResponseManager;
+        let manager = ResponseManager::default();
 
         let formatted = manager.format_response(&FenrisOutput::NamespaceListing {
             entries: vec![FenrisMetadata {
@@ -327,18 +1035,104 @@ mod tests {
                 size: 0,
                 is_namespace: true,
                 modified_time: 0,
+                created_time: 0,
                 permissions: 0o755,
             }],
+            sort: ListSort::default(),
         });
 
         assert!(formatted.success);
         assert!(formatted.message.contains("Directory listing"));
         assert!(formatted.details.unwrap().contains("dir"));
+        let (headers, rows) = formatted.table_data.unwrap();
+        assert_eq!(headers, ["Name", "Type", "Size", "Modified"]);
+        assert_eq!(rows[0][..3], ["dir", "DIR", "-"]);
+    }
+
+    #[test]
+    fn test_format_namespace_listing_truncates_long_names() {
+        let long_name = "a".repeat(50);
+        let manager = ResponseManager::default().with_options(FormatterOptions {
+            date_format: "%Y".to_string(),
+            ..FormatterOptions::default()
+        });
+
+        let formatted = manager.format_response(&FenrisOutput::NamespaceListing {
+            entries: vec![FenrisMetadata {
+                name: long_name.clone(),
+                size: 1,
+                is_namespace: false,
+                modified_time: 0,
+                created_time: 0,
+                permissions: 0o644,
+            }],
+            sort: ListSort::default(),
+        });
+
+        let details = formatted.details.unwrap();
+        let row = details.lines().last().unwrap();
+        assert!(row.starts_with(&format!("{}… ", "a".repeat(39))));
+        assert!(!details.contains(&long_name));
+        assert!(row.ends_with(" 1970") || row.ends_with(" 1969"));
+    }
+
+    #[test]
+    fn test_listing_name_column_widens_with_the_terminal() {
+        let options = FormatterOptions {
+            terminal_width: 120,
+            ..FormatterOptions::default()
+        };
+        assert_eq!(options.listing_name_width(), 76);
+        assert_eq!(FormatterOptions::default().listing_name_width(), 40);
+        assert_eq!(truncate_name("short", 40), "short");
+    }
+
+    #[test]
+    fn test_format_namespace_listing_shows_sort_order() {
+        let manager = ResponseManager::default();
+
+        let formatted = manager.format_response(&FenrisOutput::NamespaceListing {
+            entries: vec![FenrisMetadata {
+                name: "a.txt".to_string(),
+                size: 1,
+                is_namespace: false,
+                modified_time: 0,
+                created_time: 0,
+                permissions: 0o644,
+            }],
+            sort: ListSort::from_flags(0b1001),
+        });
+
+        let details = formatted.details.unwrap();
+        assert!(details.starts_with("Found 1 entries (sorted by name, reversed):"));
+    }
+
+    #[test]
+    fn test_format_recursive_namespace_listing() {
+        let manager = ResponseManager::default();
+        let entry = |name: &str, size, is_namespace| FenrisMetadata {
+            name: name.to_string(),
+            size,
+            is_namespace,
+            modified_time: 0,
+            created_time: 0,
+            permissions: 0o644,
+        };
+
+        let formatted = manager.format_response(&FenrisOutput::RecursiveNamespaceListing {
+            entries: vec![
+                (PathBuf::from("docs"), entry("docs", 0, true)),
+                (PathBuf::from("docs/a.txt"), entry("a.txt", 5, false)),
+            ],
+        });
+
+        assert_eq!(formatted.message, "Recursive listing (2 entries):");
+        assert_eq!(formatted.details.unwrap(), "docs/\n  a.txt (5 B)\n");
     }
 
     #[test]
     fn test_format_namespace_changed() {
-        let manager = ResponseManager;
+        let manager = ResponseManager::default();
 
         let formatted = manager.format_response(&FenrisOutput::NamespaceChanged {
             path: "/tmp".into(),
@@ -350,7 +1144,7 @@ mod tests {
 
     #[test]
     fn test_format_error_and_terminated() {
-        let manager = ResponseManager;
+        let manager = ResponseManager::default();
 
         let formatted = manager.format_response(&FenrisOutput::Error {
             message: "bad".to_string(),
@@ -363,6 +1157,194 @@ mod tests {
         assert!(formatted.message.contains("terminated"));
     }
 
+    #[test]
+    fn test_format_object_diff() {
+        let manager = ResponseManager::default();
+
+        let formatted = manager.format_response(&FenrisOutput::ObjectDiff {
+            diff: "--- a\n+++ b\n@@ -1 +1 @@\n-old\n+new\n".to_string(),
+        });
+        assert_eq!(formatted.message, "Diff: 1 additions, 1 deletions");
+        assert_eq!(formatted.details_format, DetailsFormat::Diff);
+
+        let formatted = manager.format_response(&FenrisOutput::ObjectDiff {
+            diff: String::new(),
+        });
+        assert_eq!(formatted.message, "Files are identical");
+        assert!(formatted.details.is_none());
+    }
+
+    #[test]
+    fn test_format_broadcast() {
+        let formatted = ResponseManager::default().format_response(&FenrisOutput::Broadcast {
+            message: "maintenance at noon".to_string(),
+        });
+        assert!(formatted.success);
+        assert_eq!(formatted.message, "📢 maintenance at noon");
+    }
+
+    #[test]
+    fn test_format_quota_info() {
+        let manager = ResponseManager::default();
+
+        let limited = manager.format_response(&FenrisOutput::QuotaInfo {
+            used_bytes: 512,
+            limit_bytes: Some(2048),
+        });
+        assert_eq!(limited.message, "Using 512 B of 2.00 KB (25.0%)");
+
+        let unlimited = manager.format_response(&FenrisOutput::QuotaInfo {
+            used_bytes: 0,
+            limit_bytes: None,
+        });
+        assert_eq!(unlimited.message, "Using 0 B (no quota)");
+    }
+
+    #[test]
+    fn test_format_object_checksum() {
+        let mut digest = [0u8; 32];
+        digest[0] = 0xba;
+        digest[31] = 0x0f;
+
+        let formatted =
+            ResponseManager::default().format_response(&FenrisOutput::ObjectChecksum { digest });
+        assert!(formatted.success);
+        assert_eq!(
+            formatted.message,
+            format!("SHA-256: ba{}0f", "00".repeat(30))
+        );
+    }
+
+    #[test]
+    fn test_format_multi_op_labels_each_output() {
+        let formatted = ResponseManager::default().format_response(&FenrisOutput::MultiOp {
+            outputs: vec![
+                FenrisOutput::Success {
+                    message: "File written: /a.txt".to_string(),
+                },
+                FenrisOutput::ObjectContent {
+                    data: b"hi".to_vec(),
+                    total_size: 2,
+                    truncated: false,
+                },
+                FenrisOutput::Error {
+                    message: "File operation failed: not found".to_string(),
+                },
+            ],
+        });
+
+        assert!(!formatted.success);
+        assert_eq!(formatted.message, "Ran 3 operations (1 failed)");
+        assert_eq!(
+            formatted.details.unwrap(),
+            "[1/3] File written: /a.txt\n\
+             [2/3] File content (2 bytes):\n  hi\n\
+             [3/3] File operation failed: not found\n"
+        );
+    }
+
+    #[test]
+    fn test_format_response_json_has_the_same_shape_for_every_output() {
+        let manager = ResponseManager::default();
+        let entry = |name: &str, size, is_namespace| FenrisMetadata {
+            name: name.to_string(),
+            size,
+            is_namespace,
+            modified_time: 0,
+            created_time: 0,
+            permissions: 0o644,
+        };
+        let outputs = [
+            FenrisOutput::Pong,
+            FenrisOutput::Success {
+                message: "done".to_string(),
+            },
+            FenrisOutput::ObjectContent {
+                data: b"hello".to_vec(),
+                total_size: 5,
+                truncated: false,
+            },
+            FenrisOutput::ObjectInfo {
+                metadata: entry("a.txt", 5, false),
+                mime_type: None,
+            },
+            FenrisOutput::NamespaceListing {
+                entries: vec![entry("a.txt", 5, false)],
+                sort: ListSort::default(),
+            },
+            FenrisOutput::RecursiveNamespaceListing {
+                entries: vec![(PathBuf::from("docs"), entry("docs", 0, true))],
+            },
+            FenrisOutput::NamespaceChanged {
+                path: "/tmp".into(),
+            },
+            FenrisOutput::ObjectDiff {
+                diff: "--- a\n+++ b\n".to_string(),
+            },
+            FenrisOutput::ObjectChecksum { digest: [0; 32] },
+            FenrisOutput::Broadcast {
+                message: "hi".to_string(),
+            },
+            FenrisOutput::Banner {
+                message: "welcome".to_string(),
+            },
+            FenrisOutput::SymlinkTarget {
+                target: "releases/v2".into(),
+            },
+            FenrisOutput::Echo {
+                payload: b"ping".to_vec(),
+            },
+            FenrisOutput::QuotaInfo {
+                used_bytes: 1,
+                limit_bytes: None,
+            },
+            FenrisOutput::XattrValue {
+                value: b"camera".to_vec(),
+            },
+            FenrisOutput::EnvValue {
+                value: "r-1042".to_string(),
+            },
+            FenrisOutput::Terminated,
+            FenrisOutput::Error {
+                message: "bad".to_string(),
+            },
+        ];
+
+        for output in &outputs {
+            let formatted = manager.format_response(output);
+            let json: serde_json::Value = serde_json::from_str(&formatted.to_json()).unwrap();
+            let object = json.as_object().unwrap();
+
+            assert_eq!(object.len(), 4, "{:?}", output);
+            assert_eq!(object["success"], formatted.success);
+            assert_eq!(object["message"], formatted.message.as_str());
+            match &formatted.details {
+                Some(details) => assert_eq!(object["details"], details.as_str()),
+                None => assert!(object["details"].is_null()),
+            }
+            match &formatted.current_dir {
+                Some(dir) => assert_eq!(object["current_dir"], dir.as_str()),
+                None => assert!(object["current_dir"].is_null()),
+            }
+        }
+
+        let changed: serde_json::Value =
+            serde_json::from_str(&manager.format_response(&outputs[6]).to_json()).unwrap();
+        assert_eq!(changed["current_dir"], "/tmp");
+        let error: serde_json::Value =
+            serde_json::from_str(&manager.format_response(outputs.last().unwrap()).to_json())
+                .unwrap();
+        assert_eq!(error["success"], false);
+    }
+
+    #[test]
+    fn test_colorize_diff_ansi() {
+        assert_eq!(
+            colorize_diff_ansi("-old\n+new\n ctx\n"),
+            "\x1b[31m-old\x1b[0m\n\x1b[32m+new\x1b[0m\n\x1b[90m ctx\x1b[0m\n"
+        );
+    }
+
     #[test]
     fn test_format_size() {
         assert_eq!(format_size(0), "0 B");
@@ -0,0 +1,146 @@
+use common::{FenrisError, Result};
+use std::path::Path;
+
+use crate::connection_manager::ConnectionManager;
+use crate::response_manager::FormattedResponse;
+
+pub(crate) trait CommandRunner {
+    async fn run_command(&mut self, command: &str) -> Result<FormattedResponse>;
+}
+
+impl CommandRunner for ConnectionManager {
+    async fn run_command(&mut self, command: &str) -> Result<FormattedResponse> {
+        self.send_command(command).await
+    }
+}
+
+#[derive(Debug)]
+pub struct ScriptStep {
+    pub command: String,
+    pub result: Result<FormattedResponse>,
+}
+
+impl ScriptStep {
+    pub fn is_success(&self) -> bool {
+        matches!(&self.result, Ok(response) if response.success)
+    }
+}
+
+pub fn read_script(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        FenrisError::file_operation_from(
+            format!("Failed to read script {}: {}", path.display(), e),
+            e,
+        )
+    })?;
+
+    Ok(parse_script(&content))
+}
+
+fn parse_script(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+pub(crate) async fn run_script<R: CommandRunner>(
+    runner: &mut R,
+    commands: Vec<String>,
+    ignore_errors: bool,
+) -> Vec<ScriptStep> {
+    let mut steps = Vec::with_capacity(commands.len());
+
+    for command in commands {
+        let result = runner.run_command(&command).await;
+        let step = ScriptStep { command, result };
+        let failed = !step.is_success();
+        steps.push(step);
+
+        if failed && !ignore_errors {
+            break;
+        }
+    }
+
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response_manager::DetailsFormat;
+
+    #[derive(Default)]
+    struct RecordingRunner {
+        commands: Vec<String>,
+    }
+
+    impl CommandRunner for RecordingRunner {
+        async fn run_command(&mut self, command: &str) -> Result<FormattedResponse> {
+            self.commands.push(command.to_string());
+            if command.starts_with("rm") {
+                return Err(FenrisError::file_operation("missing"));
+            }
+
+            Ok(FormattedResponse {
+                success: true,
+                message: format!("ran {}", command),
+                details: None,
+                current_dir: None,
+                details_format: DetailsFormat::Plain,
+                table_data: None,
+            })
+        }
+    }
+
+    fn write_script(dir: &tempfile::TempDir) -> std::path::PathBuf {
+        let path = dir.path().join("setup.fenris");
+        std::fs::write(
+            &path,
+            "# prepare\nmkdir docs\n\n  create docs/a.txt  \nrm docs/missing.txt\nls docs\n",
+        )
+        .unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn run_script_dispatches_commands_in_order_and_stops_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let commands = read_script(&write_script(&dir)).unwrap();
+        let mut runner = RecordingRunner::default();
+
+        let steps = run_script(&mut runner, commands, false).await;
+
+        assert_eq!(
+            runner.commands,
+            vec!["mkdir docs", "create docs/a.txt", "rm docs/missing.txt"]
+        );
+        assert_eq!(steps.len(), 3);
+        assert!(!steps[2].is_success());
+    }
+
+    #[tokio::test]
+    async fn run_script_continues_past_errors_when_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let commands = read_script(&write_script(&dir)).unwrap();
+        let mut runner = RecordingRunner::default();
+
+        let steps = run_script(&mut runner, commands, true).await;
+
+        assert_eq!(runner.commands.len(), 4);
+        assert_eq!(runner.commands[3], "ls docs");
+        assert!(steps[3].is_success());
+    }
+
+    #[test]
+    fn read_script_reports_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(matches!(
+            read_script(&dir.path().join("missing.fenris")),
+            Err(FenrisError::FileOperationError { .. })
+        ));
+    }
+}
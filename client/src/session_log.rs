@@ -0,0 +1,166 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::request_manager::ClientCommandPlan;
+use crate::response_manager::FormattedResponse;
+
+pub const SESSION_LOG_DIR: &str = ".fenris_sessions";
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// `~/.fenris_sessions/<timestamp>.log`, or `None` when `HOME` is unset.
+pub fn default_session_log_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join(SESSION_LOG_DIR)
+            .join(format!("{}.log", Local::now().format("%Y%m%d-%H%M%S")))
+    })
+}
+
+/// Opens `path` for appending, creating its directory first.
+pub fn open(path: &Path) -> Result<BufWriter<File>> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    Ok(BufWriter::new(file))
+}
+
+/// Writes `> <timestamp> <command>`.
+pub fn write_command(log: &mut impl Write, command: &str) -> std::io::Result<()> {
+    write_lines(log, '>', command)
+}
+
+/// Writes the message and any details as `< <timestamp> <line>`, one line each so every
+/// line of a response can be found with `grep`. With `redact`, the details shrink to
+/// `[REDACTED: N bytes]`.
+pub fn write_response(
+    log: &mut impl Write,
+    formatted: &FormattedResponse,
+    redact: bool,
+) -> std::io::Result<()> {
+    let mut text = formatted.message.clone();
+    if let Some(details) = &formatted.details {
+        text.push('\n');
+        if redact {
+            text.push_str(&format!("[REDACTED: {} bytes]", details.len()));
+        } else {
+            text.push_str(details);
+        }
+    }
+    write_lines(log, '<', &text)
+}
+
+pub fn write_error(log: &mut impl Write, error: &common::FenrisError) -> std::io::Result<()> {
+    write_lines(log, '<', &format!("error: {}", error))
+}
+
+/// Whether the command's output is the contents of a remote file.
+pub fn carries_file_contents(plan: &ClientCommandPlan) -> bool {
+    match plan {
+        ClientCommandPlan::ChunkedRead { .. } => true,
+        ClientCommandPlan::Pipeline { stages } => stages.iter().any(carries_file_contents),
+        ClientCommandPlan::Redirected { plan, .. } => carries_file_contents(plan),
+        _ => false,
+    }
+}
+
+/// Every line is flushed as soon as it is written so the log survives a crash.
+fn write_lines(log: &mut impl Write, marker: char, text: &str) -> std::io::Result<()> {
+    let timestamp = Local::now().format(TIMESTAMP_FORMAT);
+    for line in text.lines() {
+        writeln!(log, "{} {} {}", marker, timestamp, line)?;
+    }
+    log.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response_manager::DetailsFormat;
+
+    fn response(details: Option<&str>) -> FormattedResponse {
+        FormattedResponse {
+            success: true,
+            message: "Read 11 bytes".to_string(),
+            details: details.map(str::to_string),
+            current_dir: None,
+            details_format: DetailsFormat::Plain,
+            table_data: None,
+        }
+    }
+
+    fn logged_lines(log: Vec<u8>) -> Vec<String> {
+        String::from_utf8(log)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_commands_and_responses_are_prefixed_per_line() {
+        let mut log = Vec::new();
+
+        write_command(&mut log, "read notes.txt").unwrap();
+        write_response(&mut log, &response(Some("hello\nworld")), false).unwrap();
+
+        let lines = logged_lines(log);
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("> ") && lines[0].ends_with(" read notes.txt"));
+        assert!(lines[1].starts_with("< ") && lines[1].ends_with(" Read 11 bytes"));
+        assert!(lines[2].ends_with(" hello"));
+        assert!(lines[3].ends_with(" world"));
+    }
+
+    #[test]
+    fn test_redaction_replaces_details_with_their_size() {
+        let mut log = Vec::new();
+
+        write_response(&mut log, &response(Some("hello world")), true).unwrap();
+
+        let lines = logged_lines(log);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].ends_with(" [REDACTED: 11 bytes]"));
+    }
+
+    #[test]
+    fn test_open_creates_missing_directories() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("sessions").join("today.log");
+
+        let mut log = open(&path).unwrap();
+        write_command(&mut log, "ls").unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("> ") && contents.ends_with(" ls\n"));
+    }
+
+    #[test]
+    fn test_only_reads_carry_file_contents() {
+        let read = ClientCommandPlan::ChunkedRead {
+            path: PathBuf::from("notes.txt"),
+        };
+        let list = ClientCommandPlan::Single(common::FenrisCommand::ListNamespace {
+            path: PathBuf::from("."),
+            sort: Default::default(),
+        });
+
+        assert!(carries_file_contents(&read));
+        assert!(!carries_file_contents(&list));
+        assert!(carries_file_contents(&ClientCommandPlan::Pipeline {
+            stages: vec![read, list],
+        }));
+    }
+}
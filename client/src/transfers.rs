@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+pub const TRANSFER_LOG_FILE: &str = ".fenris_transfers.log";
+pub const TRANSFER_HISTORY_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransferRecord {
+    pub timestamp: DateTime<Local>,
+    pub direction: TransferDirection,
+    pub remote_path: String,
+    pub local_path: String,
+    pub bytes: u64,
+    pub duration_ms: u64,
+    pub success: bool,
+}
+
+pub fn transfer_log_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(TRANSFER_LOG_FILE))
+}
+
+/// Opens the log for each record so no handle stays open between transfers.
+pub fn append_record(path: &Path, record: &TransferRecord) -> Result<()> {
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Returns the newest `limit` records, oldest first. Unparseable lines are skipped.
+pub fn read_recent(path: &Path, limit: usize) -> Result<Vec<TransferRecord>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+
+    let mut records = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.with_context(|| format!("failed to read {}", path.display()))?;
+        if let Ok(record) = serde_json::from_str(&line) {
+            records.push(record);
+        }
+    }
+
+    let skip = records.len().saturating_sub(limit);
+    records.drain(..skip);
+    Ok(records)
+}
+
+pub fn clear(path: &Path) -> Result<()> {
+    match File::create(path) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("failed to clear {}", path.display())),
+    }
+}
+
+pub fn format_table(records: &[TransferRecord]) -> Vec<String> {
+    let mut lines = vec![format!(
+        "{:<19}  {:<9}  {:>12}  {:>9}  {:<6}  {}",
+        "TIME", "DIRECTION", "BYTES", "DURATION", "STATUS", "PATHS"
+    )];
+
+    for record in records {
+        let (direction, arrow) = match record.direction {
+            TransferDirection::Upload => ("upload", "->"),
+            TransferDirection::Download => ("download", "<-"),
+        };
+        lines.push(format!(
+            "{:<19}  {:<9}  {:>12}  {:>7}ms  {:<6}  {} {} {}",
+            record.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            direction,
+            record.bytes,
+            record.duration_ms,
+            if record.success { "ok" } else { "failed" },
+            record.local_path,
+            arrow,
+            record.remote_path,
+        ));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(bytes: u64) -> TransferRecord {
+        TransferRecord {
+            timestamp: Local::now(),
+            direction: TransferDirection::Upload,
+            remote_path: "/remote.bin".to_string(),
+            local_path: "local.bin".to_string(),
+            bytes,
+            duration_ms: 12,
+            success: true,
+        }
+    }
+
+    #[test]
+    fn records_round_trip_and_keep_the_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(TRANSFER_LOG_FILE);
+
+        assert!(read_recent(&path, 2).unwrap().is_empty());
+        for bytes in 1..=3 {
+            append_record(&path, &record(bytes)).unwrap();
+        }
+
+        let recent = read_recent(&path, 2).unwrap();
+        assert_eq!(
+            recent.iter().map(|r| r.bytes).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(recent[1].direction, TransferDirection::Upload);
+        assert_eq!(recent[1].local_path, "local.bin");
+
+        clear(&path).unwrap();
+        assert!(read_recent(&path, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn table_lists_one_row_per_record() {
+        let lines = format_table(&[record(2048)]);
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains("upload"));
+        assert!(lines[1].contains("2048"));
+        assert!(lines[1].contains("local.bin -> /remote.bin"));
+    }
+}
@@ -1,25 +1,37 @@
-use crate::app::{Message, MessageKind};
+use crate::app::{App, ContextMenuItem, Message, MessageKind, PaneState, find_matches};
+use crate::response_manager::{format_size, format_timestamp};
+use crate::ui::Theme;
 use ratatui::{
     Frame,
-    layout::{Alignment, Rect},
+    layout::{Alignment, Constraint, Position, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
 };
-use std::time::Instant;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::time::{Duration, Instant};
 
-pub fn render_header(frame: &mut Frame, area: Rect, title: &str, connected: bool) {
+pub fn render_header(
+    frame: &mut Frame,
+    area: Rect,
+    title: &str,
+    connected: bool,
+    tabs: &[String],
+    active_tab: usize,
+    theme: &Theme,
+) {
     let status = if connected {
-        Span::styled(" ● CONNECTED ", Style::default().fg(Color::Green))
+        Span::styled(" ● CONNECTED ", Style::default().fg(theme.success_color))
     } else {
-        Span::styled(" ● DISCONNECTED ", Style::default().fg(Color::Red))
+        Span::styled(" ● DISCONNECTED ", Style::default().fg(theme.error_color))
     };
 
     let title_line = Line::from(vec![
         Span::styled(
             format!(" {} ", title),
             Style::default()
-                .fg(Color::Cyan)
+                .fg(theme.header_fg)
                 .add_modifier(Modifier::BOLD),
         ),
         status,
@@ -27,20 +39,73 @@ pub fn render_header(frame: &mut Frame, area: Rect, title: &str, connected: bool
 
     let header = Paragraph::new(title_line)
         .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL));
+        .style(Style::default().bg(theme.header_bg))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(tab_bar(tabs, active_tab, theme)),
+        );
 
     frame.render_widget(header, area);
 }
 
-pub fn render_messages(frame: &mut Frame, area: Rect, messages: &[Message]) {
+fn tab_bar<'a>(tabs: &'a [String], active_tab: usize, theme: &Theme) -> Line<'a> {
+    let spans: Vec<Span> = tabs
+        .iter()
+        .enumerate()
+        .map(|(index, label)| {
+            let style = if index == active_tab {
+                Style::default()
+                    .fg(theme.selection_fg)
+                    .bg(theme.selection_bg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.muted_color)
+            };
+            Span::styled(format!("[{}]", label), style)
+        })
+        .collect();
+
+    Line::from(spans)
+}
+
+/// The open message search: what to highlight and which message to keep in view.
+pub struct MessageSearch<'a> {
+    pub query: &'a str,
+    pub current: Option<usize>,
+}
+
+pub fn render_messages(
+    frame: &mut Frame,
+    area: Rect,
+    messages: &[Message],
+    visible_kinds: &HashSet<MessageKind>,
+    search: Option<&MessageSearch>,
+    theme: &Theme,
+) {
     let now = Instant::now();
 
-    let lines: Vec<Line> = messages
+    let visible: Vec<(usize, &Message)> = messages
         .iter()
-        .rev()
-        .take(area.height as usize - 2)
-        .rev()
-        .map(|msg| {
+        .enumerate()
+        .filter(|(_, msg)| visible_kinds.contains(&msg.kind))
+        .collect();
+
+    // Show the newest messages, unless that would hide the current search match.
+    let height = area.height.saturating_sub(2) as usize;
+    let mut start = visible.len().saturating_sub(height);
+    if let Some(position) = search
+        .and_then(|search| search.current)
+        .and_then(|current| visible.iter().position(|(index, _)| *index == current))
+    {
+        start = start.min(position);
+    }
+
+    let query = search.map_or("", |search| search.query);
+    let lines: Vec<Line> = visible[start..]
+        .iter()
+        .take(height)
+        .map(|(_, msg)| {
             let elapsed = now.duration_since(msg.timestamp).as_secs();
             let time_str = if elapsed < 60 {
                 format!("[{}s ago]", elapsed)
@@ -49,27 +114,36 @@ pub fn render_messages(frame: &mut Frame, area: Rect, messages: &[Message]) {
             };
 
             let (icon, color) = match msg.kind {
-                MessageKind::Info => ("ℹ", Color::Blue),
-                MessageKind::Success => ("✓", Color::Green),
-                MessageKind::Error => ("✗", Color::Red),
+                MessageKind::Info => ("ℹ", theme.info_color),
+                MessageKind::Warning => ("⚠", theme.warning_color),
+                MessageKind::Success => ("✓", theme.success_color),
+                MessageKind::Error => ("✗", theme.error_color),
+                MessageKind::DiffAdded => (" ", theme.success_color),
+                MessageKind::DiffRemoved => (" ", theme.error_color),
+                MessageKind::DiffContext => (" ", theme.diff_context_color),
+            };
+            let content_style = match msg.kind {
+                MessageKind::DiffAdded | MessageKind::DiffRemoved | MessageKind::DiffContext => {
+                    Style::default().fg(color)
+                }
+                _ => Style::default(),
             };
 
-            Line::from(vec![
-                Span::styled(
-                    format!("{} ", icon),
-                    Style::default().fg(color).add_modifier(Modifier::BOLD),
-                ),
-                Span::raw(&msg.content),
-                Span::styled(
-                    format!(" {}", time_str),
-                    Style::default().fg(Color::DarkGray),
-                ),
-            ])
+            let mut spans = vec![Span::styled(
+                format!("{} ", icon),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            )];
+            spans.extend(highlight_matches(&msg.content, query, content_style));
+            spans.push(Span::styled(
+                format!(" {}", time_str),
+                Style::default().fg(theme.muted_color),
+            ));
+            Line::from(spans)
         })
         .collect();
 
     let block = Block::default()
-        .title(" Output ")
+        .title(output_title(visible_kinds))
         .borders(Borders::ALL)
         .style(Style::default());
 
@@ -80,19 +154,322 @@ pub fn render_messages(frame: &mut Frame, area: Rect, messages: &[Message]) {
     frame.render_widget(paragraph, area);
 }
 
+/// Splits `content` so every occurrence of `query` stands out against `style`.
+fn highlight_matches<'a>(content: &'a str, query: &str, style: Style) -> Vec<Span<'a>> {
+    let highlight = Style::default().bg(Color::Yellow).fg(Color::Black);
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for range in find_matches(content, query) {
+        if range.start > end {
+            spans.push(Span::styled(&content[end..range.start], style));
+        }
+        spans.push(Span::styled(&content[range.clone()], highlight));
+        end = range.end;
+    }
+    if end < content.len() || spans.is_empty() {
+        spans.push(Span::styled(&content[end..], style));
+    }
+    spans
+}
+
+pub fn render_search_bar(
+    frame: &mut Frame,
+    area: Rect,
+    query: &str,
+    selection: Option<(usize, usize)>,
+    theme: &Theme,
+) {
+    let position = match selection {
+        Some((current, total)) => format!(" {}/{}", current, total),
+        None if query.is_empty() => String::new(),
+        None => " no matches".to_string(),
+    };
+    let line = Line::from(vec![
+        Span::styled(" Search: ", Style::default().fg(theme.muted_color)),
+        Span::raw(query),
+        Span::styled(position, Style::default().fg(theme.muted_color)),
+    ]);
+
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+/// Names the shown kinds, e.g. `" Output [E][S] "`, unless nothing is filtered out.
+fn output_title(visible_kinds: &HashSet<MessageKind>) -> String {
+    if MessageKind::ALL
+        .iter()
+        .all(|kind| visible_kinds.contains(kind))
+    {
+        return " Output ".to_string();
+    }
+
+    let tags: String = [
+        (MessageKind::Error, "[E]"),
+        (MessageKind::Success, "[S]"),
+        (MessageKind::Info, "[I]"),
+    ]
+    .iter()
+    .filter(|(kind, _)| visible_kinds.contains(kind))
+    .map(|(_, tag)| *tag)
+    .collect();
+    format!(" Output {} ", tags)
+}
+
+/// `widths` are column percentages summing to at most 100.
+pub fn render_table(
+    frame: &mut Frame,
+    area: Rect,
+    headers: &[&str],
+    rows: &[Vec<String>],
+    widths: &[u16],
+) {
+    render_table_with_selection(
+        frame,
+        area,
+        headers,
+        rows,
+        widths,
+        None,
+        &mut TableState::default(),
+    );
+}
+
+/// Like [`render_table`], highlighting `selected` and scrolling `state` to keep it in view.
+pub fn render_table_with_selection(
+    frame: &mut Frame,
+    area: Rect,
+    headers: &[&str],
+    rows: &[Vec<String>],
+    widths: &[u16],
+    selected: Option<usize>,
+    state: &mut TableState,
+) {
+    let header = Row::new(headers.iter().map(|header| Cell::from(*header)))
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let rows = rows
+        .iter()
+        .map(|row| Row::new(row.iter().map(|cell| Cell::from(cell.as_str()))));
+    let widths = widths.iter().map(|width| Constraint::Percentage(*width));
+
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().title(" Output ").borders(Borders::ALL))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    state.select(selected);
+    frame.render_stateful_widget(table, area, state);
+}
+
+/// Percentages proportional to each column's widest cell, summing to at most 100.
+pub fn table_column_widths(headers: &[&str], rows: &[Vec<String>]) -> Vec<u16> {
+    let widest: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(column, header)| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.chars().count())
+                .chain([header.chars().count()])
+                .max()
+                .unwrap_or(0)
+                .max(1)
+        })
+        .collect();
+    let total: usize = widest.iter().sum();
+
+    widest
+        .iter()
+        .map(|width| (width * 100 / total.max(1)) as u16)
+        .collect()
+}
+
+const PANE_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// One side of the split view: name, size and modification time of each entry. The focused
+/// pane gets the header color on its border and keeps its selection highlighted.
+pub fn render_pane(frame: &mut Frame, area: Rect, pane: &PaneState, focused: bool, theme: &Theme) {
+    let rows = pane.listing.iter().map(|entry| {
+        let (name, size, color) = if entry.is_namespace {
+            (format!("{}/", entry.name), "-".to_string(), theme.dir_color)
+        } else {
+            (
+                entry.name.clone(),
+                format_size(entry.size),
+                theme.file_color,
+            )
+        };
+        Row::new([
+            Cell::from(name),
+            Cell::from(size),
+            Cell::from(format_timestamp(entry.modified_time, PANE_DATE_FORMAT)),
+        ])
+        .style(Style::default().fg(color))
+    });
+    let header =
+        Row::new(["Name", "Size", "Modified"]).style(Style::default().add_modifier(Modifier::BOLD));
+    let border = if focused {
+        theme.header_fg
+    } else {
+        theme.muted_color
+    };
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(10),
+            Constraint::Length(10),
+            Constraint::Length(16),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .title(format!(" {} ", pane.current_dir))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(border)),
+    )
+    .row_highlight_style(
+        Style::default()
+            .fg(theme.selection_fg)
+            .bg(theme.selection_bg),
+    );
+
+    let mut state = TableState::default().with_offset(pane_row_offset(pane, focused, area));
+    if focused && !pane.listing.is_empty() {
+        state.select(Some(pane.selected));
+    }
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+/// Rows of a pane drawn in `area`: inside the borders and below the header.
+pub fn pane_rows_area(area: Rect) -> Rect {
+    Rect::new(
+        area.x + 1,
+        area.y + 2,
+        area.width.saturating_sub(2),
+        area.height.saturating_sub(3),
+    )
+}
+
+/// The first listing entry shown: only a focused pane scrolls, just far enough to keep its
+/// selection on screen.
+pub fn pane_row_offset(pane: &PaneState, focused: bool, area: Rect) -> usize {
+    let visible = pane_rows_area(area).height as usize;
+    if focused && visible > 0 {
+        pane.selected.saturating_sub(visible - 1)
+    } else {
+        0
+    }
+}
+
+/// The listing index under `(column, row)` in a pane drawn in `area`, if any.
+pub fn pane_item_at(
+    pane: &PaneState,
+    focused: bool,
+    area: Rect,
+    column: u16,
+    row: u16,
+) -> Option<usize> {
+    let rows = pane_rows_area(area);
+    if !rows.contains(Position::new(column, row)) {
+        return None;
+    }
+    let index = pane_row_offset(pane, focused, area) + (row - rows.y) as usize;
+    (index < pane.listing.len()).then_some(index)
+}
+
+/// Where the right-click menu opened at `(column, row)` fits on `screen`.
+pub fn context_menu_area(screen: Rect, column: u16, row: u16) -> Rect {
+    let width = ContextMenuItem::ALL
+        .iter()
+        .map(|item| item.label().len() as u16 + 4)
+        .max()
+        .unwrap_or(0)
+        .min(screen.width);
+    let height = (ContextMenuItem::ALL.len() as u16 + 2).min(screen.height);
+    Rect::new(
+        column.min(screen.right().saturating_sub(width)),
+        row.min(screen.bottom().saturating_sub(height)),
+        width,
+        height,
+    )
+}
+
+/// The menu entry under `(column, row)` for a menu drawn in `area`.
+pub fn context_menu_item_at(area: Rect, column: u16, row: u16) -> Option<ContextMenuItem> {
+    let inner = Rect::new(
+        area.x + 1,
+        area.y + 1,
+        area.width.saturating_sub(2),
+        area.height.saturating_sub(2),
+    );
+    if !inner.contains(Position::new(column, row)) {
+        return None;
+    }
+    ContextMenuItem::ALL.get((row - inner.y) as usize).copied()
+}
+
+pub fn render_context_menu(frame: &mut Frame, area: Rect, theme: &Theme) {
+    let lines: Vec<Line> = ContextMenuItem::ALL
+        .iter()
+        .map(|item| Line::from(format!(" {} ", item.label())))
+        .collect();
+    let menu = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.header_fg)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(menu, area);
+}
+
+pub fn render_watch_list(frame: &mut Frame, area: Rect, paths: &[String], theme: &Theme) {
+    let lines: Vec<Line> = paths
+        .iter()
+        .map(|path| {
+            Line::from(vec![
+                Span::styled("📁 ", Style::default().fg(theme.dir_color)),
+                Span::styled(path.as_str(), Style::default().fg(theme.file_color)),
+            ])
+        })
+        .collect();
+
+    let block = Block::default()
+        .title(" Watching ")
+        .borders(Borders::ALL)
+        .style(Style::default());
+
+    let paragraph = Paragraph::new(lines).block(block);
+
+    frame.render_widget(paragraph, area);
+}
+
 pub fn render_input(
     frame: &mut Frame,
     area: Rect,
     prompt: &str,
     input: &str,
     cursor_position: usize,
+    completed: Option<Range<usize>>,
+    theme: &Theme,
 ) {
-    let input_text = format!("{}{}", prompt, input);
+    let input_text = match completed {
+        Some(range) => Line::from(vec![
+            Span::raw(prompt),
+            Span::raw(&input[..range.start]),
+            Span::styled(
+                &input[range.clone()],
+                Style::default().fg(theme.muted_color),
+            ),
+            Span::raw(&input[range.end..]),
+        ]),
+        None => Line::from(format!("{}{}", prompt, input)),
+    };
 
     let block = Block::default()
         .title(" Input ")
         .borders(Borders::ALL)
-        .style(Style::default());
+        .border_style(Style::default().fg(theme.input_border));
 
     let paragraph = Paragraph::new(input_text)
         .block(block)
@@ -108,7 +485,41 @@ pub fn render_input(
     }
 }
 
-pub fn render_help_text(frame: &mut Frame, area: Rect, shortcuts: &[(&str, &str)]) {
+pub fn render_status_bar(frame: &mut Frame, area: Rect, app: &App) {
+    let tab = app.tab();
+    let connected = match tab.connected_at {
+        Some(since) => format_duration(since.elapsed()),
+        None if tab.reconnecting => "reconnecting".to_string(),
+        None => "--:--:--".to_string(),
+    };
+
+    let latency = tab
+        .connection_manager
+        .latest_latency()
+        .map(|rtt| format!("{:.1} ms", rtt.as_secs_f64() * 1000.0))
+        .unwrap_or_else(|| "--".to_string());
+
+    let status = format!(
+        " Server: {} | Connected: {} | Latency: {} | Sent: {} | Received: {} | Dir: {}",
+        tab.label(),
+        connected,
+        latency,
+        format_size(tab.connection_manager.bytes_sent()),
+        format_size(tab.connection_manager.bytes_received()),
+        tab.current_dir,
+    );
+
+    let paragraph = Paragraph::new(status).style(Style::default().fg(app.theme.header_fg));
+
+    frame.render_widget(paragraph, area);
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+pub fn render_help_text(frame: &mut Frame, area: Rect, shortcuts: &[(&str, &str)], theme: &Theme) {
     let help_spans: Vec<Span> = shortcuts
         .iter()
         .flat_map(|(key, desc)| {
@@ -116,7 +527,7 @@ pub fn render_help_text(frame: &mut Frame, area: Rect, shortcuts: &[(&str, &str)
                 Span::styled(
                     format!(" {} ", key),
                     Style::default()
-                        .fg(Color::Yellow)
+                        .fg(theme.warning_color)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::raw(format!("{} │", desc)),
@@ -128,7 +539,122 @@ pub fn render_help_text(frame: &mut Frame, area: Rect, shortcuts: &[(&str, &str)
 
     let paragraph = Paragraph::new(help_line)
         .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::DarkGray));
+        .style(Style::default().fg(theme.muted_color));
 
     frame.render_widget(paragraph, area);
 }
+
+pub fn render_command_palette(
+    frame: &mut Frame,
+    query: &str,
+    matches: &[(&str, &str)],
+    selected: usize,
+    theme: &Theme,
+) {
+    let screen = frame.area();
+    let width = (screen.width * 3 / 5).max(40).min(screen.width);
+    let height = (matches.len() as u16 + 4).clamp(5, screen.height.saturating_sub(2).max(5));
+    let area = Rect::new(
+        screen.x + (screen.width.saturating_sub(width)) / 2,
+        screen.y + (screen.height.saturating_sub(height)) / 3,
+        width,
+        height.min(screen.height),
+    );
+
+    let visible = area.height.saturating_sub(3) as usize;
+    let offset = selected.saturating_sub(visible.saturating_sub(1));
+
+    let mut lines = vec![Line::from(vec![
+        Span::styled("> ", Style::default().fg(theme.header_fg)),
+        Span::raw(query),
+    ])];
+    if matches.is_empty() {
+        lines.push(Line::from(Span::styled(
+            " No matching commands",
+            Style::default().fg(theme.muted_color),
+        )));
+    }
+    lines.extend(matches.iter().enumerate().skip(offset).take(visible).map(
+        |(index, (cmd, desc))| {
+            let style = if index == selected {
+                Style::default()
+                    .fg(theme.selection_fg)
+                    .bg(theme.selection_bg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.warning_color)
+            };
+            Line::from(vec![
+                Span::styled(format!(" {:20}", cmd), style),
+                Span::raw(format!(" {}", desc)),
+            ])
+        },
+    ));
+
+    let block = Block::default()
+        .title(" Command Palette ")
+        .borders(Borders::ALL)
+        .style(Style::default());
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+    frame.set_cursor_position((area.x + 3 + query.len() as u16, area.y + 1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::FenrisMetadata;
+
+    fn pane_with_entries(count: usize) -> PaneState {
+        let mut pane = PaneState::default();
+        pane.set_listing(
+            (0..count)
+                .map(|index| FenrisMetadata {
+                    name: format!("file{}", index),
+                    size: 0,
+                    is_namespace: false,
+                    modified_time: 0,
+                    created_time: 0,
+                    permissions: 0o644,
+                })
+                .collect(),
+        );
+        pane
+    }
+
+    #[test]
+    fn pane_clicks_map_to_listing_rows() {
+        let mut pane = pane_with_entries(10);
+        // Borders and the header leave rows 12..15 for three entries.
+        let area = Rect::new(0, 10, 30, 6);
+
+        assert_eq!(pane_item_at(&pane, true, area, 5, 11), None);
+        assert_eq!(pane_item_at(&pane, true, area, 5, 12), Some(0));
+        assert_eq!(pane_item_at(&pane, true, area, 0, 12), None);
+
+        // A selection below the fold scrolls the focused pane only.
+        pane.selected = 7;
+        assert_eq!(pane_item_at(&pane, true, area, 5, 12), Some(5));
+        assert_eq!(pane_item_at(&pane, false, area, 5, 12), Some(0));
+    }
+
+    #[test]
+    fn context_menu_stays_on_screen() {
+        let screen = Rect::new(0, 0, 40, 20);
+
+        let area = context_menu_area(screen, 38, 19);
+        assert_eq!(area.right(), 40);
+        assert_eq!(area.bottom(), 20);
+
+        assert_eq!(
+            context_menu_item_at(area, area.x + 2, area.y + 1),
+            Some(ContextMenuItem::Open)
+        );
+        assert_eq!(
+            context_menu_item_at(area, area.x + 2, area.y + 4),
+            Some(ContextMenuItem::CopyPath)
+        );
+        assert_eq!(context_menu_item_at(area, area.x, area.y + 1), None);
+    }
+}
@@ -2,18 +2,113 @@ pub mod components;
 pub mod screens;
 pub mod terminal;
 
-use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use anyhow::{Context, Result};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use ratatui::Frame;
-use std::time::Duration;
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use crate::app::{App, ConnectionFocus, Screen};
+use crate::app::{App, ConnectionFocus, MessageKind, PaneAction, PaneIndex, RenderLayout, Screen};
+
+pub const THEME_FILE: &str = ".fenris_theme.toml";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    #[serde(deserialize_with = "parse_color")]
+    pub header_fg: Color,
+    #[serde(deserialize_with = "parse_color")]
+    pub header_bg: Color,
+    #[serde(deserialize_with = "parse_color")]
+    pub success_color: Color,
+    #[serde(deserialize_with = "parse_color")]
+    pub error_color: Color,
+    #[serde(deserialize_with = "parse_color")]
+    pub info_color: Color,
+    #[serde(deserialize_with = "parse_color")]
+    pub warning_color: Color,
+    #[serde(deserialize_with = "parse_color")]
+    pub input_border: Color,
+    #[serde(deserialize_with = "parse_color")]
+    pub dir_color: Color,
+    #[serde(deserialize_with = "parse_color")]
+    pub file_color: Color,
+    #[serde(deserialize_with = "parse_color")]
+    pub muted_color: Color,
+    #[serde(deserialize_with = "parse_color")]
+    pub selection_fg: Color,
+    #[serde(deserialize_with = "parse_color")]
+    pub selection_bg: Color,
+    #[serde(deserialize_with = "parse_color")]
+    pub diff_context_color: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            header_fg: Color::Cyan,
+            header_bg: Color::Reset,
+            success_color: Color::Green,
+            error_color: Color::Red,
+            info_color: Color::Blue,
+            warning_color: Color::Yellow,
+            input_border: Color::Reset,
+            dir_color: Color::Blue,
+            file_color: Color::Reset,
+            muted_color: Color::DarkGray,
+            selection_fg: Color::Black,
+            selection_bg: Color::Cyan,
+            diff_context_color: Color::Gray,
+        }
+    }
+}
+
+impl Theme {
+    pub fn load(path: &Path) -> Result<Theme> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read theme {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("failed to parse theme {}", path.display()))
+    }
+
+    /// Loads `~/.fenris_theme.toml` when present, otherwise the built-in colors.
+    pub fn load_user() -> Result<Theme> {
+        match theme_path() {
+            Some(path) if path.exists() => Self::load(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+}
+
+pub fn theme_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(THEME_FILE))
+}
+
+fn parse_color<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Color, D::Error> {
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
 
 pub fn render(frame: &mut Frame, app: &App) {
-    match app.screen {
+    match app.tab().screen {
         Screen::Connection => screens::connection::render(frame, app),
         Screen::Command => screens::command::render(frame, app),
         Screen::Help => screens::help::render(frame, app),
+        Screen::CommandPalette => {
+            screens::command::render(frame, app);
+            components::render_command_palette(
+                frame,
+                &app.palette_query,
+                &app.palette_matches(),
+                app.palette_selection,
+                &app.theme,
+            );
+        }
     }
 }
 
@@ -23,46 +118,95 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<()> {
         return Ok(());
     }
 
-    match app.screen {
+    match app.tab().screen {
         Screen::Connection => handle_connection_input(app, key),
+        Screen::Command if app.search_query.is_some() => handle_search_input(app, key),
         Screen::Command => handle_command_input(app, key),
         Screen::Help => handle_help_input(app, key),
+        Screen::CommandPalette => handle_palette_input(app, key),
     }
 }
 
 fn handle_connection_input(app: &mut App, key: KeyEvent) -> Result<()> {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('v') {
+        app.paste();
+        return Ok(());
+    }
+
+    let tab = app.tab_mut();
     match key.code {
-        KeyCode::Char(c) => match app.connection_focus {
-            ConnectionFocus::Address => app.server_addr.push(c),
-            ConnectionFocus::Port => {
-                if c.is_ascii_digit() && app.server_port.len() < 5 {
-                    app.server_port.push(c);
-                }
-            }
-        },
-        KeyCode::Backspace => match app.connection_focus {
-            ConnectionFocus::Address => {
-                app.server_addr.pop();
-            }
-            ConnectionFocus::Port => {
-                app.server_port.pop();
-            }
-        },
-        KeyCode::Tab => {
-            app.connection_focus = match app.connection_focus {
-                ConnectionFocus::Address => crate::app::ConnectionFocus::Port,
-                ConnectionFocus::Port => crate::app::ConnectionFocus::Address,
+        KeyCode::Char(c) => tab.connection_insert_char(c),
+        KeyCode::Backspace => {
+            match tab.connection_focus {
+                ConnectionFocus::Address => tab.server_addr.pop(),
+                ConnectionFocus::Port => tab.server_port.pop(),
             };
+            tab.connection_error = None;
         }
+        KeyCode::Tab | KeyCode::BackTab => tab.toggle_connection_focus(),
         _ => {}
     }
     Ok(())
 }
 
 fn handle_command_input(app: &mut App, key: KeyEvent) -> Result<()> {
+    if app.context_menu.is_some() && key.code == KeyCode::Esc {
+        app.context_menu = None;
+        return Ok(());
+    }
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('d') {
+        app.toggle_split_pane();
+        return Ok(());
+    }
+    if app.split_pane && handle_pane_input(app, key) {
+        return Ok(());
+    }
+
     match key.code {
         KeyCode::F(1) => {
-            app.screen = Screen::Help;
+            app.tab_mut().screen = Screen::Help;
+        }
+        KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.open_palette();
+        }
+        KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.open_search();
+        }
+        KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.paste();
+        }
+        KeyCode::Char('y') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.copy_last_response();
+        }
+        KeyCode::Char('1') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.toggle_kind(MessageKind::Info);
+        }
+        KeyCode::Char('2') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.toggle_kind(MessageKind::Success);
+        }
+        KeyCode::Char('3') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.toggle_kind(MessageKind::Error);
+        }
+        KeyCode::Char('0') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.show_all_kinds();
+        }
+        KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.show_only_errors();
+        }
+        KeyCode::Tab => {
+            app.auto_complete(true);
+        }
+        KeyCode::BackTab => {
+            app.auto_complete(false);
+        }
+        KeyCode::PageUp => {
+            app.tab_mut().move_table_selection(-1);
+        }
+        KeyCode::PageDown => {
+            app.tab_mut().move_table_selection(1);
+        }
+        KeyCode::Esc if app.tab().table.is_some() => {
+            app.tab_mut().show_table(None);
         }
         KeyCode::Up => {
             app.history_previous();
@@ -70,6 +214,12 @@ fn handle_command_input(app: &mut App, key: KeyEvent) -> Result<()> {
         KeyCode::Down => {
             app.history_next();
         }
+        KeyCode::Left if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.move_cursor_word_left();
+        }
+        KeyCode::Right if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.move_cursor_word_right();
+        }
         KeyCode::Left => {
             app.move_cursor_left();
         }
@@ -85,6 +235,13 @@ fn handle_command_input(app: &mut App, key: KeyEvent) -> Result<()> {
         KeyCode::Char(c) => {
             app.insert_char(c);
         }
+        KeyCode::Backspace
+            if key
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            app.delete_word_left();
+        }
         KeyCode::Backspace => {
             app.delete_char();
         }
@@ -93,16 +250,105 @@ fn handle_command_input(app: &mut App, key: KeyEvent) -> Result<()> {
     Ok(())
 }
 
+/// The split view's own keys; everything else still edits the command line.
+fn handle_pane_input(app: &mut App, key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Up => app.pane_move_selection(-1),
+        KeyCode::Down => app.pane_move_selection(1),
+        KeyCode::F(5) => app.queue_pane_transfer(PaneAction::Copy),
+        KeyCode::F(6) => app.queue_pane_transfer(PaneAction::Move),
+        KeyCode::Enter if app.command_input.is_empty() => app.pane_open_selected(),
+        KeyCode::Backspace if app.command_input.is_empty() => app.pane_parent(),
+        _ => return false,
+    }
+    true
+}
+
+/// Clicks only mean something on the split view's panes and the menu they open.
+pub fn handle_mouse_event(app: &mut App, mouse: MouseEvent) {
+    if !app.mouse_enabled || app.tab().screen != Screen::Command || !app.split_pane {
+        return;
+    }
+    let layout = app.last_render_layout.get();
+    let (column, row) = (mouse.column, mouse.row);
+
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            if app.context_menu.take().is_some() {
+                if let Some(item) = layout
+                    .context_menu
+                    .and_then(|area| components::context_menu_item_at(area, column, row))
+                {
+                    app.run_context_menu_item(item);
+                }
+                return;
+            }
+            if let Some((pane, index)) = pane_item_at(app, layout, column, row) {
+                app.click_pane_item(pane, index, Instant::now());
+            }
+        }
+        MouseEventKind::Down(MouseButton::Right) => {
+            app.context_menu = None;
+            if let Some((pane, index)) = pane_item_at(app, layout, column, row) {
+                app.select_pane_item(pane, index);
+                app.context_menu = Some((column, row));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn pane_item_at(
+    app: &App,
+    layout: RenderLayout,
+    column: u16,
+    row: u16,
+) -> Option<(PaneIndex, usize)> {
+    let areas = layout.panes?;
+    areas
+        .into_iter()
+        .zip([PaneIndex::Left, PaneIndex::Right])
+        .find_map(|(area, pane)| {
+            components::pane_item_at(app.pane(pane), app.active_pane == pane, area, column, row)
+                .map(|index| (pane, index))
+        })
+}
+
 fn handle_help_input(app: &mut App, key: KeyEvent) -> Result<()> {
     match key.code {
         KeyCode::F(1) | KeyCode::Esc => {
-            app.screen = Screen::Command;
+            app.tab_mut().screen = Screen::Command;
         }
         _ => {}
     }
     Ok(())
 }
 
+fn handle_search_input(app: &mut App, key: KeyEvent) -> Result<()> {
+    match key.code {
+        KeyCode::Esc => app.close_search(),
+        KeyCode::Enter if key.modifiers.contains(KeyModifiers::SHIFT) => app.search_previous(),
+        KeyCode::Enter => app.search_next(),
+        KeyCode::Backspace => app.search_delete_char(),
+        KeyCode::Char(c) => app.search_insert_char(c),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_palette_input(app: &mut App, key: KeyEvent) -> Result<()> {
+    match key.code {
+        KeyCode::Esc => app.close_palette(),
+        KeyCode::Enter => app.accept_palette(),
+        KeyCode::Up => app.palette_previous(),
+        KeyCode::Down => app.palette_next(),
+        KeyCode::Backspace => app.palette_delete_char(),
+        KeyCode::Char(c) => app.palette_insert_char(c),
+        _ => {}
+    }
+    Ok(())
+}
+
 pub fn poll_events(timeout: Duration) -> Result<Option<Event>> {
     if event::poll(timeout)? {
         Ok(Some(event::read()?))
@@ -110,3 +356,33 @@ pub fn poll_events(timeout: Duration) -> Result<Option<Event>> {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_theme_load_overrides_only_listed_colors() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "success_color = \"#00ff00\"\nheader_fg = \"light-magenta\""
+        )
+        .unwrap();
+
+        let theme = Theme::load(file.path()).unwrap();
+
+        assert_eq!(theme.success_color, Color::Rgb(0, 255, 0));
+        assert_eq!(theme.header_fg, Color::LightMagenta);
+        assert_eq!(theme.error_color, Theme::default().error_color);
+    }
+
+    #[test]
+    fn test_theme_load_rejects_unknown_colors() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "error_color = \"not-a-color\"").unwrap();
+
+        assert!(Theme::load(file.path()).is_err());
+    }
+}
@@ -1,8 +1,9 @@
-use crate::app::App;
-use crate::ui::components;
+use crate::app::{App, PaneIndex, RenderLayout};
+use crate::ui::components::{self, MessageSearch};
 use ratatui::{
     Frame,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::TableState,
 };
 
 pub fn render(frame: &mut Frame, app: &App) {
@@ -11,32 +12,185 @@ pub fn render(frame: &mut Frame, app: &App) {
         .constraints([
             Constraint::Length(3), // Header
             Constraint::Min(0),    // Messages
+            Constraint::Length(1), // Status bar
             Constraint::Length(3), // Input
             Constraint::Length(1), // Footer
         ])
         .split(frame.area());
 
-    components::render_header(frame, chunks[0], "FENRIS CLIENT", app.connected);
+    components::render_header(
+        frame,
+        chunks[0],
+        "FENRIS CLIENT",
+        app.tab().connected,
+        &app.tab_labels(),
+        app.active_tab,
+        &app.theme,
+    );
+
+    let output = if app.tab().watched_paths.is_empty() {
+        chunks[1]
+    } else {
+        let body = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Min(0),     // Messages
+                Constraint::Length(28), // Watched paths
+            ])
+            .split(chunks[1]);
+
+        components::render_watch_list(frame, body[1], &app.tab().watched_paths, &app.theme);
+        body[0]
+    };
+
+    let mut layout = RenderLayout::default();
+
+    // The panes take the top of the body; the log below still shows what F5/F6 did.
+    let output = if app.split_pane {
+        let body = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(70), // Panes
+                Constraint::Min(3),         // Messages
+            ])
+            .split(output);
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(body[0]);
+
+        for (area, index) in panes.iter().zip([PaneIndex::Left, PaneIndex::Right]) {
+            components::render_pane(
+                frame,
+                *area,
+                app.pane(index),
+                app.active_pane == index,
+                &app.theme,
+            );
+        }
+        layout.panes = Some([panes[0], panes[1]]);
+        body[1]
+    } else {
+        output
+    };
+
+    let search = app.search_query.as_deref().map(|query| MessageSearch {
+        query,
+        current: app.current_search_match(),
+    });
+    let output = match &search {
+        Some(search) => {
+            let output = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Min(0),    // Messages
+                    Constraint::Length(1), // Search bar
+                ])
+                .split(output);
+
+            let selection = app
+                .current_search_match()
+                .map(|_| (app.search_selection + 1, app.search_results.len()));
+            components::render_search_bar(frame, output[1], search.query, selection, &app.theme);
+            output[0]
+        }
+        None => output,
+    };
 
-    components::render_messages(frame, chunks[1], &app.messages);
+    match &app.tab().table {
+        Some((headers, rows)) => {
+            let output = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Min(3),                                        // Messages
+                    Constraint::Length((rows.len() as u16).saturating_add(3)), // Table
+                ])
+                .split(output);
 
-    let prompt = format!("{} -> ", app.current_dir);
+            components::render_messages(
+                frame,
+                output[0],
+                &app.tab().messages,
+                &app.visible_kinds,
+                search.as_ref(),
+                &app.theme,
+            );
+            render_table(frame, output[1], headers, rows, app.tab().table_selection);
+        }
+        None => components::render_messages(
+            frame,
+            output,
+            &app.tab().messages,
+            &app.visible_kinds,
+            search.as_ref(),
+            &app.theme,
+        ),
+    }
+
+    components::render_status_bar(frame, chunks[2], app);
+
+    let prompt = format!("{} -> ", app.tab().current_dir);
     components::render_input(
         frame,
-        chunks[2],
+        chunks[3],
         &prompt,
         &app.command_input,
         app.cursor_position,
+        app.completed_range(),
+        &app.theme,
     );
 
-    let cursor_x = chunks[2].x + prompt.len() as u16 + app.cursor_position as u16 + 1;
-    let cursor_y = chunks[2].y + 1;
+    let cursor_x = chunks[3].x + prompt.len() as u16 + app.cursor_position as u16 + 1;
+    let cursor_y = chunks[3].y + 1;
 
     frame.set_cursor_position((cursor_x, cursor_y));
 
     components::render_help_text(
         frame,
-        chunks[3],
-        &[("F1", "Help"), ("↑↓", "History"), ("Ctrl+C", "Quit")],
+        chunks[4],
+        &[
+            ("F1", "Help"),
+            ("↑↓", "History"),
+            ("Ctrl+P", "Commands"),
+            ("Ctrl+F", "Search"),
+            ("Ctrl+D", "Split view"),
+            ("Ctrl+V/Y", "Paste/Copy"),
+            ("Tab", "Complete/Next tab"),
+            ("Ctrl+T", "New tab"),
+            ("Ctrl+W", "Close tab"),
+            ("Ctrl+C", "Quit"),
+        ],
+        &app.theme,
     );
+
+    if let Some((column, row)) = app.context_menu {
+        let area = components::context_menu_area(frame.area(), column, row);
+        components::render_context_menu(frame, area, &app.theme);
+        layout.context_menu = Some(area);
+    }
+    app.last_render_layout.set(layout);
+}
+
+fn render_table(
+    frame: &mut Frame,
+    area: Rect,
+    headers: &[String],
+    rows: &[Vec<String>],
+    selected: Option<usize>,
+) {
+    let headers: Vec<&str> = headers.iter().map(String::as_str).collect();
+    let widths = components::table_column_widths(&headers, rows);
+
+    match selected {
+        Some(_) => components::render_table_with_selection(
+            frame,
+            area,
+            &headers,
+            rows,
+            &widths,
+            selected,
+            &mut TableState::default(),
+        ),
+        None => components::render_table(frame, area, &headers, rows, &widths),
+    }
 }
@@ -3,7 +3,7 @@ use crate::ui::components;
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph},
 };
@@ -18,7 +18,15 @@ pub fn render(frame: &mut Frame, app: &App) {
         ])
         .split(frame.area());
 
-    components::render_header(frame, chunks[0], "FENRIS CLIENT", app.connected);
+    components::render_header(
+        frame,
+        chunks[0],
+        "FENRIS CLIENT",
+        app.tab().connected,
+        &app.tab_labels(),
+        app.active_tab,
+        &app.theme,
+    );
 
     render_connection_form(frame, chunks[1], app);
 
@@ -28,13 +36,16 @@ pub fn render(frame: &mut Frame, app: &App) {
         &[
             ("Tab", "Switch field"),
             ("Enter", "Connect"),
+            ("Ctrl+W", "Close tab"),
             ("F1", "Help"),
             ("Ctrl+C", "Quit"),
         ],
+        &app.theme,
     );
 }
 
 fn render_connection_form(frame: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
     let form_width = 60;
     let form_height = 15;
 
@@ -50,7 +61,7 @@ fn render_connection_form(frame: &mut Frame, area: Rect, app: &App) {
             Constraint::Length(1), // Spacing
             Constraint::Length(3), // Address input
             Constraint::Length(3), // Port input
-            Constraint::Length(1), // Spacing
+            Constraint::Length(1), // Validation error
             Constraint::Min(0),    // Instructions
         ])
         .split(centered);
@@ -58,17 +69,17 @@ fn render_connection_form(frame: &mut Frame, area: Rect, app: &App) {
     let border = Block::default()
         .title(" Connect to Server ")
         .borders(Borders::ALL)
-        .style(Style::default().fg(Color::Cyan));
+        .style(Style::default().fg(theme.header_fg));
     frame.render_widget(border, centered);
 
     let title = Paragraph::new("Enter server connection details")
         .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::Yellow));
+        .style(Style::default().fg(theme.warning_color));
     frame.render_widget(title, chunks[0]);
 
-    let address_focused = matches!(app.connection_focus, ConnectionFocus::Address);
+    let address_focused = matches!(app.tab().connection_focus, ConnectionFocus::Address);
     let address_style = if address_focused {
-        Style::default().fg(Color::Yellow)
+        Style::default().fg(theme.warning_color)
     } else {
         Style::default()
     };
@@ -79,20 +90,20 @@ fn render_connection_form(frame: &mut Frame, area: Rect, app: &App) {
         .title(address_title)
         .borders(Borders::ALL)
         .border_style(if address_focused {
-            Style::default().fg(Color::Yellow)
+            Style::default().fg(theme.warning_color)
         } else {
             Style::default()
         });
 
-    let address_input = Paragraph::new(app.server_addr.as_str())
+    let address_input = Paragraph::new(app.tab().server_addr.as_str())
         .style(address_style)
         .block(address_block);
 
     frame.render_widget(address_input, chunks[2]);
 
-    let port_focused = matches!(app.connection_focus, ConnectionFocus::Port);
+    let port_focused = matches!(app.tab().connection_focus, ConnectionFocus::Port);
     let port_style = if port_focused {
-        Style::default().fg(Color::Yellow)
+        Style::default().fg(theme.warning_color)
     } else {
         Style::default()
     };
@@ -103,30 +114,47 @@ fn render_connection_form(frame: &mut Frame, area: Rect, app: &App) {
         .title(port_title)
         .borders(Borders::ALL)
         .border_style(if port_focused {
-            Style::default().fg(Color::Yellow)
+            Style::default().fg(theme.warning_color)
         } else {
             Style::default()
         });
 
-    let port_input = Paragraph::new(app.server_port.as_str())
+    let port_input = Paragraph::new(app.tab().server_port.as_str())
         .style(port_style)
         .block(port_block);
 
     frame.render_widget(port_input, chunks[3]);
 
+    if let Some(error) = &app.tab().connection_error {
+        let error_line = Paragraph::new(error.as_str())
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(theme.error_color));
+        frame.render_widget(error_line, chunks[4]);
+    }
+
+    let (field, text) = if address_focused {
+        (chunks[2], &app.tab().server_addr)
+    } else {
+        (chunks[3], &app.tab().server_port)
+    };
+    let cursor_x = field.x + 1 + text.chars().count() as u16;
+    if cursor_x < field.x + field.width - 1 {
+        frame.set_cursor_position((cursor_x, field.y + 1));
+    }
+
     let instructions = vec![Line::from(vec![
         Span::raw("Use "),
         Span::styled(
             "Tab",
             Style::default()
-                .fg(Color::Green)
+                .fg(theme.success_color)
                 .add_modifier(Modifier::BOLD),
         ),
         Span::raw(" to switch fields, "),
         Span::styled(
             "Enter",
             Style::default()
-                .fg(Color::Green)
+                .fg(theme.success_color)
                 .add_modifier(Modifier::BOLD),
         ),
         Span::raw(" to connect"),
@@ -134,7 +162,7 @@ fn render_connection_form(frame: &mut Frame, area: Rect, app: &App) {
 
     let instructions_paragraph = Paragraph::new(instructions)
         .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::DarkGray));
+        .style(Style::default().fg(theme.muted_color));
 
     frame.render_widget(instructions_paragraph, chunks[5]);
 }
@@ -1,9 +1,9 @@
-use crate::app::App;
-use crate::ui::components;
+use crate::app::{App, known_commands};
+use crate::ui::{Theme, components};
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, Paragraph},
 };
@@ -18,14 +18,27 @@ pub fn render(frame: &mut Frame, app: &App) {
         ])
         .split(frame.area());
 
-    components::render_header(frame, chunks[0], "FENRIS HELP", app.connected);
+    components::render_header(
+        frame,
+        chunks[0],
+        "FENRIS HELP",
+        app.tab().connected,
+        &app.tab_labels(),
+        app.active_tab,
+        &app.theme,
+    );
 
-    render_help_content(frame, chunks[1]);
+    render_help_content(frame, chunks[1], &app.theme);
 
-    components::render_help_text(frame, chunks[2], &[("F1/Esc", "Back"), ("Ctrl+C", "Quit")]);
+    components::render_help_text(
+        frame,
+        chunks[2],
+        &[("F1/Esc", "Back"), ("Ctrl+C", "Quit")],
+        &app.theme,
+    );
 }
 
-fn render_help_content(frame: &mut Frame, area: Rect) {
+fn render_help_content(frame: &mut Frame, area: Rect, theme: &Theme) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(2)
@@ -39,7 +52,7 @@ fn render_help_content(frame: &mut Frame, area: Rect) {
         Line::from(Span::styled(
             "FENRIS - Fast Encrypted Networked Robust Information Storage",
             Style::default()
-                .fg(Color::Cyan)
+                .fg(theme.header_fg)
                 .add_modifier(Modifier::BOLD),
         )),
         Line::from(""),
@@ -49,33 +62,14 @@ fn render_help_content(frame: &mut Frame, area: Rect) {
 
     frame.render_widget(title, chunks[0]);
 
-    let commands = vec![
-        ("ping", "Test connection to server"),
-        ("ls [dir]", "List directory contents"),
-        ("cd <dir>", "Change directory"),
-        ("read <file>", "Read file contents"),
-        ("write <file>", "Write to file"),
-        ("create <file>", "Create new file"),
-        ("rm <file>", "Delete file"),
-        ("mkdir <dir>", "Create directory"),
-        ("rmdir <dir>", "Delete directory"),
-        (
-            "upload <client_file> <server_location>",
-            "Upload a file from local machine to server",
-        ),
-        ("info <file>", "Get file information"),
-        ("help", "Show this help"),
-        ("exit", "Disconnect and quit"),
-    ];
-
-    let items: Vec<ListItem> = commands
-        .iter()
+    let items: Vec<ListItem> = known_commands()
+        .into_iter()
         .map(|(cmd, desc)| {
             let line = Line::from(vec![
                 Span::styled(
                     format!(" {:20}", cmd),
                     Style::default()
-                        .fg(Color::Yellow)
+                        .fg(theme.warning_color)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::raw(desc.to_string()),
@@ -1,6 +1,7 @@
 use anyhow::Result;
 
 use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
@@ -11,8 +12,12 @@ use std::io;
 
 pub type Tui = Terminal<CrosstermBackend<io::Stdout>>;
 
-pub fn init() -> Result<Tui> {
+/// Capturing the mouse stops the terminal's own text selection, so it is opt-in.
+pub fn init(mouse_capture: bool) -> Result<Tui> {
     execute!(io::stdout(), EnterAlternateScreen)?;
+    if mouse_capture {
+        execute!(io::stdout(), EnableMouseCapture)?;
+    }
     enable_raw_mode()?;
 
     let backend = CrosstermBackend::new(io::stdout());
@@ -21,7 +26,7 @@ pub fn init() -> Result<Tui> {
 }
 
 pub fn restore() -> Result<()> {
-    execute!(io::stdout(), LeaveAlternateScreen)?;
+    execute!(io::stdout(), DisableMouseCapture, LeaveAlternateScreen)?;
     disable_raw_mode()?;
     Ok(())
 }
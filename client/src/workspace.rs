@@ -0,0 +1,38 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::app::MessageKind;
+
+pub const WORKSPACE_FILE: &str = ".fenris_workspace.json";
+pub const WORKSPACE_MESSAGE_LIMIT: usize = 200;
+
+/// What the TUI restores between runs. Nothing about the session itself is kept, so a loaded
+/// workspace always needs a fresh connection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub server_addr: String,
+    pub server_port: u16,
+    pub command_history: Vec<String>,
+    pub bookmarks: Vec<(String, String)>,
+    pub recent_messages: Vec<(MessageKind, String)>,
+}
+
+pub fn workspace_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(WORKSPACE_FILE))
+}
+
+impl Workspace {
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse {}", path.display()))
+    }
+}
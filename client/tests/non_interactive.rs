@@ -0,0 +1,120 @@
+use common::{ServerIdentityKey, ServerIdentityPublicKey, TokioFsStorage};
+use server::{Server, ServerConfig, ServerHandle};
+use std::net::SocketAddr;
+use std::process::{Output, Stdio};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+async fn start_server(
+    base_dir: &std::path::Path,
+) -> (SocketAddr, ServerIdentityPublicKey, ServerHandle) {
+    let storage = Arc::new(TokioFsStorage::new(base_dir.to_path_buf()));
+    let identity_key = Arc::new(ServerIdentityKey::generate());
+    let public_key = identity_key.public_key();
+
+    let (server, handle) = Server::bind_authenticated(
+        "127.0.0.1:0",
+        storage,
+        identity_key,
+        ServerConfig::default(),
+    )
+    .await
+    .unwrap();
+    let addr = server.local_addr().unwrap();
+    tokio::spawn(server.run());
+
+    (addr, public_key, handle)
+}
+
+async fn run_client(
+    addr: SocketAddr,
+    identity: ServerIdentityPublicKey,
+    work_dir: &std::path::Path,
+    input: &str,
+) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_client"))
+        .current_dir(work_dir)
+        .args([
+            "--server-identity",
+            &identity.to_hex(),
+            "--non-interactive",
+            "--address",
+            &addr.ip().to_string(),
+            "--port",
+            &addr.port().to_string(),
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(input.as_bytes()).await.unwrap();
+    drop(stdin);
+
+    child.wait_with_output().await.unwrap()
+}
+
+#[tokio::test]
+async fn non_interactive_client_runs_piped_commands() {
+    let base_dir = tempfile::tempdir().unwrap();
+    let work_dir = tempfile::tempdir().unwrap();
+    let (addr, identity, handle) = start_server(base_dir.path()).await;
+
+    let output = run_client(
+        addr,
+        identity,
+        work_dir.path(),
+        "create notes.txt\nwrite notes.txt hello world\nread notes.txt\n",
+    )
+    .await;
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("hello world"), "stdout: {}", stdout);
+    assert!(output.stderr.is_empty());
+
+    handle.shutdown();
+}
+
+#[tokio::test]
+async fn non_interactive_client_reports_failures_and_stops_at_exit() {
+    let base_dir = tempfile::tempdir().unwrap();
+    let work_dir = tempfile::tempdir().unwrap();
+    let (addr, identity, handle) = start_server(base_dir.path()).await;
+
+    let output = run_client(
+        addr,
+        identity,
+        work_dir.path(),
+        "read missing.txt\nexit\ncreate after-exit.txt\n",
+    )
+    .await;
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!output.stderr.is_empty());
+    assert!(!base_dir.path().join("after-exit.txt").exists());
+
+    handle.shutdown();
+}
+
+#[tokio::test]
+async fn non_interactive_client_exits_with_error_when_connect_fails() {
+    let work_dir = tempfile::tempdir().unwrap();
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+
+    let identity = ServerIdentityKey::generate().public_key();
+    let output = run_client(addr, identity, work_dir.path(), "ping\n").await;
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(!output.stderr.is_empty());
+}
@@ -0,0 +1,286 @@
+//! Gzipped tarballs behind `TAR` and `UNTAR`. Everything here blocks, so callers run it on
+//! `spawn_blocking`.
+
+use crate::error::{FenrisError, Result};
+use crate::file_ops::is_system_file_name;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+use tar::EntryType;
+use tracing::debug;
+
+/// Writes the directory `src` to `dst` as a gzipped tarball with every entry under `root`.
+/// Symlinks are stored as links instead of being followed, so nothing outside `src` ends up
+/// in the archive. The server's own `.fenris_` files are left out. Returns the size of the
+/// archive.
+pub fn create_tar_gz(src: &Path, dst: &Path, root: &Path) -> std::io::Result<u64> {
+    let file = File::create(dst)?;
+    let mut builder =
+        tar::Builder::new(GzEncoder::new(BufWriter::new(file), Compression::default()));
+    builder.follow_symlinks(false);
+    append_tree(&mut builder, src, root)?;
+
+    let mut writer = builder.into_inner()?.finish()?;
+    writer.flush()?;
+    let file = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(file.metadata()?.len())
+}
+
+fn append_tree<W: Write>(
+    builder: &mut tar::Builder<W>,
+    dir: &Path,
+    name: &Path,
+) -> std::io::Result<()> {
+    builder.append_dir(name, dir)?;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if is_system_file_name(&entry.file_name()) {
+            continue;
+        }
+        let entry_name = name.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            append_tree(builder, &entry.path(), &entry_name)?;
+        } else {
+            builder.append_path_with_name(entry.path(), &entry_name)?;
+        }
+    }
+    Ok(())
+}
+
+/// Unpacks the gzipped tarball `archive` into the directory `dst` and returns the number of
+/// file bytes written. Each entry is checked before it touches the disk: its path must stay
+/// below `dst` and must not name a `.fenris_` file, links are refused unless `allow_links`
+/// and must then point inside `dst`, and devices and fifos are skipped. `admit` sees the
+/// size of every file first and can stop the extraction, e.g. to enforce a quota; entries
+/// unpacked before that stay on disk.
+pub fn extract_tar_gz(
+    archive: &Path,
+    dst: &Path,
+    allow_links: bool,
+    mut admit: impl FnMut(u64) -> Result<()>,
+) -> Result<u64> {
+    let file = File::open(archive).map_err(|e| {
+        FenrisError::file_operation_from(format!("Failed to open archive: {}", e), e)
+    })?;
+    let mut archive = tar::Archive::new(GzDecoder::new(BufReader::new(file)));
+    let mut extracted = 0u64;
+
+    for entry in archive.entries().map_err(invalid_archive)? {
+        let mut entry = entry.map_err(invalid_archive)?;
+        let path = entry.path().map_err(invalid_archive)?.into_owned();
+        if !path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(escapes(&path));
+        }
+        if path.iter().any(is_system_file_name) {
+            return Err(FenrisError::PermissionDenied(format!(
+                "archive entry {} is reserved for the server",
+                path.display()
+            )));
+        }
+
+        let entry_type = entry.header().entry_type();
+        let mut file_bytes = 0;
+        match entry_type {
+            EntryType::Regular | EntryType::Continuous | EntryType::GNUSparse => {
+                file_bytes = entry.size();
+                admit(file_bytes)?;
+            }
+            EntryType::Directory => {}
+            EntryType::Symlink | EntryType::Link => {
+                if !allow_links {
+                    return Err(FenrisError::PermissionDenied(format!(
+                        "archive entry {} is a link",
+                        path.display()
+                    )));
+                }
+                let target = entry
+                    .link_name()
+                    .map_err(invalid_archive)?
+                    .ok_or_else(|| FenrisError::file_operation("Archive link has no target"))?;
+                // Hard link targets name another entry; symlinks are relative to their own
+                // directory.
+                let resolved = if entry_type == EntryType::Link {
+                    target.to_path_buf()
+                } else {
+                    path.parent().unwrap_or(Path::new("")).join(&target)
+                };
+                if contained(&resolved).is_none() {
+                    return Err(escapes(&path));
+                }
+            }
+            other => {
+                debug!("Skipping archive entry {:?} of type {:?}", path, other);
+                continue;
+            }
+        }
+
+        if !entry.unpack_in(dst).map_err(|e| {
+            FenrisError::file_operation_from(
+                format!("Failed to extract {}: {}", path.display(), e),
+                e,
+            )
+        })? {
+            return Err(escapes(&path));
+        }
+        extracted += file_bytes;
+    }
+
+    Ok(extracted)
+}
+
+/// `path` with `.` components dropped, or `None` if it is absolute or climbs above its
+/// starting point with `..`.
+fn contained(path: &Path) -> Option<PathBuf> {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normal.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normal.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(normal)
+}
+
+fn escapes(path: &Path) -> FenrisError {
+    FenrisError::PermissionDenied(format!(
+        "archive entry {} escapes the destination directory",
+        path.display()
+    ))
+}
+
+fn invalid_archive(e: std::io::Error) -> FenrisError {
+    FenrisError::file_operation_from(format!("Invalid archive: {}", e), e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive_with(entries: &[(&str, &[u8])], links: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (name, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            // `set_path` refuses `..`, so hostile names are written into the header directly.
+            let name_field = &mut header.as_old_mut().name;
+            name_field[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_cksum();
+            builder.append(&header, *data).unwrap();
+        }
+        for (name, target) in links {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(EntryType::Symlink);
+            header.set_size(0);
+            builder.append_link(&mut header, name, target).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn write_archive(dir: &Path, bytes: &[u8]) -> PathBuf {
+        let path = dir.join("archive.tar.gz");
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_create_and_extract_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("project");
+        std::fs::create_dir_all(src.join("src")).unwrap();
+        std::fs::write(src.join("README"), b"hello").unwrap();
+        std::fs::write(src.join("src/main.rs"), b"fn main() {}").unwrap();
+        let archive = temp_dir.path().join("project.tar.gz");
+
+        let size = create_tar_gz(&src, &archive, Path::new("project")).unwrap();
+        assert_eq!(size, std::fs::metadata(&archive).unwrap().len());
+
+        let out = temp_dir.path().join("out");
+        std::fs::create_dir(&out).unwrap();
+        let extracted = extract_tar_gz(&archive, &out, false, |_| Ok(())).unwrap();
+
+        assert_eq!(extracted, 17);
+        assert_eq!(std::fs::read(out.join("project/README")).unwrap(), b"hello");
+        assert_eq!(
+            std::fs::read(out.join("project/src/main.rs")).unwrap(),
+            b"fn main() {}"
+        );
+    }
+
+    #[test]
+    fn test_extract_rejects_parent_dir_entries() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive = write_archive(
+            temp_dir.path(),
+            &archive_with(&[("../evil.txt", b"pwned")], &[]),
+        );
+        let out = temp_dir.path().join("out");
+        std::fs::create_dir(&out).unwrap();
+
+        let result = extract_tar_gz(&archive, &out, true, |_| Ok(()));
+
+        assert!(matches!(result, Err(FenrisError::PermissionDenied(_))));
+        assert!(!temp_dir.path().join("evil.txt").exists());
+    }
+
+    #[test]
+    fn test_extract_rejects_links_unless_allowed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let inside = write_archive(
+            temp_dir.path(),
+            &archive_with(&[], &[("docs/latest", "v2")]),
+        );
+        let out = temp_dir.path().join("out");
+        std::fs::create_dir(&out).unwrap();
+
+        assert!(matches!(
+            extract_tar_gz(&inside, &out, false, |_| Ok(())),
+            Err(FenrisError::PermissionDenied(_))
+        ));
+        extract_tar_gz(&inside, &out, true, |_| Ok(())).unwrap();
+
+        let outside = write_archive(
+            temp_dir.path(),
+            &archive_with(&[], &[("docs/escape", "../../..")]),
+        );
+        assert!(matches!(
+            extract_tar_gz(&outside, &out, true, |_| Ok(())),
+            Err(FenrisError::PermissionDenied(_))
+        ));
+    }
+
+    #[test]
+    fn test_admit_can_stop_extraction() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive = write_archive(
+            temp_dir.path(),
+            &archive_with(&[("small.txt", b"ok"), ("large.txt", b"too large")], &[]),
+        );
+        let out = temp_dir.path().join("out");
+        std::fs::create_dir(&out).unwrap();
+
+        let result = extract_tar_gz(&archive, &out, false, |size| {
+            if size > 4 {
+                Err(FenrisError::InvalidRequest("too large".to_string()))
+            } else {
+                Ok(())
+            }
+        });
+
+        assert!(matches!(result, Err(FenrisError::InvalidRequest(_))));
+        assert!(out.join("small.txt").exists());
+        assert!(!out.join("large.txt").exists());
+    }
+}
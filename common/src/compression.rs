@@ -1,17 +1,71 @@
 use crate::error::{FenrisError, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+#[async_trait::async_trait]
 pub trait Compressor: Send + Sync {
     fn compress(&self, data: &[u8]) -> Result<Vec<u8>>;
 
     fn decompress(&self, data: &[u8]) -> Result<Vec<u8>>;
 
     fn name(&self) -> &str;
+
+    /// Compresses everything `reader` yields into `writer` and returns the bytes written.
+    /// The writer is not shut down. Compressors without a streaming codec buffer the input.
+    async fn compress_stream(
+        &self,
+        reader: &mut (dyn AsyncRead + Send + Unpin),
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> Result<u64> {
+        let compressed = self.compress(&read_all(reader).await?)?;
+        write_all(writer, &compressed).await
+    }
+
+    async fn decompress_stream(
+        &self,
+        reader: &mut (dyn AsyncRead + Send + Unpin),
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> Result<u64> {
+        let decompressed = self.decompress(&read_all(reader).await?)?;
+        write_all(writer, &decompressed).await
+    }
+}
+
+async fn read_all(reader: &mut (dyn AsyncRead + Send + Unpin)) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader
+        .read_to_end(&mut data)
+        .await
+        .map_err(|e| FenrisError::compression_from(e.to_string(), e))?;
+    Ok(data)
+}
+
+async fn write_all(writer: &mut (dyn AsyncWrite + Send + Unpin), data: &[u8]) -> Result<u64> {
+    writer
+        .write_all(data)
+        .await
+        .map_err(|e| FenrisError::compression_from(e.to_string(), e))?;
+    Ok(data.len() as u64)
+}
+
+/// Copies a codec's output to `writer`; read errors come from the codec or its source.
+async fn copy_stream(
+    codec: &mut (dyn AsyncRead + Send + Unpin),
+    writer: &mut (dyn AsyncWrite + Send + Unpin),
+) -> Result<u64> {
+    tokio::io::copy(codec, writer)
+        .await
+        .map_err(|e| FenrisError::compression_from(e.to_string(), e))
 }
 
 // Default
+use async_compression::Level;
+use async_compression::tokio::bufread::{
+    ZlibDecoder as AsyncZlibDecoder, ZlibEncoder as AsyncZlibEncoder,
+};
 use flate2::Compression;
 use flate2::write::{ZlibDecoder, ZlibEncoder};
 use std::io::Write;
+use tokio::io::BufReader;
 
 #[derive(Debug, Clone)]
 pub struct ZlibCompressor {
@@ -38,30 +92,50 @@ impl Default for ZlibCompressor {
     }
 }
 
+#[async_trait::async_trait]
 impl Compressor for ZlibCompressor {
     fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
         let mut encoder = ZlibEncoder::new(Vec::new(), self.level);
         encoder
             .write_all(data)
-            .map_err(|e| FenrisError::CompressionError(e.to_string()))?;
+            .map_err(|e| FenrisError::compression_from(e.to_string(), e))?;
         encoder
             .finish()
-            .map_err(|e| FenrisError::CompressionError(e.to_string()))
+            .map_err(|e| FenrisError::compression_from(e.to_string(), e))
     }
 
     fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
         let mut decoder = ZlibDecoder::new(Vec::new());
         decoder
             .write_all(data)
-            .map_err(|e| FenrisError::CompressionError(e.to_string()))?;
+            .map_err(|e| FenrisError::compression_from(e.to_string(), e))?;
         decoder
             .finish()
-            .map_err(|e| FenrisError::CompressionError(e.to_string()))
+            .map_err(|e| FenrisError::compression_from(e.to_string(), e))
     }
 
     fn name(&self) -> &str {
         "zlib"
     }
+
+    async fn compress_stream(
+        &self,
+        reader: &mut (dyn AsyncRead + Send + Unpin),
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> Result<u64> {
+        let level = Level::Precise(self.level.level() as i32);
+        let mut encoder = AsyncZlibEncoder::with_quality(BufReader::new(reader), level);
+        copy_stream(&mut encoder, writer).await
+    }
+
+    async fn decompress_stream(
+        &self,
+        reader: &mut (dyn AsyncRead + Send + Unpin),
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> Result<u64> {
+        let mut decoder = AsyncZlibDecoder::new(BufReader::new(reader));
+        copy_stream(&mut decoder, writer).await
+    }
 }
 
 #[cfg(feature = "zstd")]
@@ -94,7 +168,7 @@ impl Default for ZstdCompressor {
 impl Compressor for ZstdCompressor {
     fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
         zstd::stream::encode_all(data, self.level)
-            .map_err(|e| FenrisError::CompressionError(e.to_string()))
+            .map_err(|e| FenrisError::compression_from(e.to_string(), e))
     }
 
     fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
@@ -123,6 +197,68 @@ impl Compressor for NullCompressor {
     }
 }
 
+const ADAPTIVE_RAW_MARKER: u8 = 0x00;
+const ADAPTIVE_COMPRESSED_MARKER: u8 = 0x01;
+
+pub struct AdaptiveCompressor<C: Compressor> {
+    inner: C,
+    threshold_bytes: usize,
+    ratio_threshold: f32,
+}
+
+impl<C: Compressor> AdaptiveCompressor<C> {
+    pub fn new(inner: C, threshold_bytes: usize, ratio_threshold: f32) -> Self {
+        Self {
+            inner,
+            threshold_bytes,
+            ratio_threshold,
+        }
+    }
+
+    fn raw(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(data.len() + 1);
+        out.push(ADAPTIVE_RAW_MARKER);
+        out.extend_from_slice(data);
+        out
+    }
+}
+
+impl<C: Compressor> Compressor for AdaptiveCompressor<C> {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        if data.len() < self.threshold_bytes {
+            return Ok(Self::raw(data));
+        }
+
+        let compressed = self.inner.compress(data)?;
+        if compressed.len() as f32 > data.len() as f32 * self.ratio_threshold {
+            return Ok(Self::raw(data));
+        }
+
+        let mut out = Vec::with_capacity(compressed.len() + 1);
+        out.push(ADAPTIVE_COMPRESSED_MARKER);
+        out.extend_from_slice(&compressed);
+        Ok(out)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match data.split_first() {
+            Some((&ADAPTIVE_RAW_MARKER, rest)) => Ok(rest.to_vec()),
+            Some((&ADAPTIVE_COMPRESSED_MARKER, rest)) => self.inner.decompress(rest),
+            Some((marker, _)) => Err(FenrisError::DecompressionError(format!(
+                "unknown compression marker: {:#04x}",
+                marker
+            ))),
+            None => Err(FenrisError::DecompressionError(
+                "missing compression marker".to_string(),
+            )),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "adaptive"
+    }
+}
+
 pub struct CompressionManager<C: Compressor> {
     compressor: C,
 }
@@ -140,6 +276,22 @@ impl<C: Compressor> CompressionManager<C> {
         self.compressor.decompress(data)
     }
 
+    pub async fn compress_stream<R, W>(&self, mut reader: R, writer: &mut W) -> Result<u64>
+    where
+        R: AsyncRead + Send + Unpin,
+        W: AsyncWrite + Send + Unpin,
+    {
+        self.compressor.compress_stream(&mut reader, writer).await
+    }
+
+    pub async fn decompress_stream<R, W>(&self, mut reader: R, writer: &mut W) -> Result<u64>
+    where
+        R: AsyncRead + Send + Unpin,
+        W: AsyncWrite + Send + Unpin,
+    {
+        self.compressor.decompress_stream(&mut reader, writer).await
+    }
+
     pub fn compressor_name(&self) -> &str {
         self.compressor.name()
     }
@@ -193,6 +345,110 @@ mod tests {
         assert_eq!(null_manager.compressor_name(), "zlib");
     }
 
+    #[test]
+    fn test_adaptive_skips_small_and_incompressible_data() {
+        let manager =
+            CompressionManager::new(AdaptiveCompressor::new(ZlibCompressor::new(), 64, 0.9));
+
+        let small = b"tiny";
+        let compressed = manager.compress(small).unwrap();
+        assert_eq!(compressed[0], 0x00);
+        assert_eq!(manager.decompress(&compressed).unwrap(), small);
+
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let noise: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 32) as u8
+            })
+            .collect();
+        let compressed = manager.compress(&noise).unwrap();
+        assert_eq!(compressed[0], 0x00);
+        assert_eq!(manager.decompress(&compressed).unwrap(), noise);
+    }
+
+    #[test]
+    fn test_adaptive_compresses_repetitive_data() {
+        let manager =
+            CompressionManager::new(AdaptiveCompressor::new(ZlibCompressor::new(), 64, 0.9));
+
+        let data = b"log line: all good\n".repeat(200);
+        let compressed = manager.compress(&data).unwrap();
+
+        assert_eq!(compressed[0], 0x01);
+        assert!(compressed.len() < data.len() / 10);
+        assert_eq!(manager.decompress(&compressed).unwrap(), data);
+        assert_eq!(manager.compressor_name(), "adaptive");
+    }
+
+    #[tokio::test]
+    async fn test_zlib_streams_a_large_buffer() {
+        let manager = CompressionManager::new(ZlibCompressor::new());
+        let data: Vec<u8> = (0..10 * 1024 * 1024u32)
+            .map(|i| (i % 251) as u8 ^ (i >> 16) as u8)
+            .collect();
+
+        let mut compressed = Vec::new();
+        let written = manager
+            .compress_stream(data.as_slice(), &mut compressed)
+            .await
+            .unwrap();
+        assert_eq!(written, compressed.len() as u64);
+        assert!(compressed.len() < data.len() / 10);
+        assert_eq!(manager.decompress(&compressed).unwrap(), data);
+
+        let mut decompressed = Vec::new();
+        let written = manager
+            .decompress_stream(
+                manager.compress(&data).unwrap().as_slice(),
+                &mut decompressed,
+            )
+            .await
+            .unwrap();
+        assert_eq!(written, data.len() as u64);
+        assert_eq!(decompressed, data);
+    }
+
+    #[tokio::test]
+    async fn test_stream_defaults_buffer_through_compress() {
+        let manager =
+            CompressionManager::new(AdaptiveCompressor::new(ZlibCompressor::new(), 64, 0.9));
+        let data = b"log line: all good\n".repeat(200);
+
+        let mut compressed = Vec::new();
+        manager
+            .compress_stream(data.as_slice(), &mut compressed)
+            .await
+            .unwrap();
+        assert_eq!(compressed, manager.compress(&data).unwrap());
+
+        let mut decompressed = Vec::new();
+        manager
+            .decompress_stream(compressed.as_slice(), &mut decompressed)
+            .await
+            .unwrap();
+        assert_eq!(decompressed, data);
+        assert!(
+            manager
+                .decompress_stream(&[0x07u8][..], &mut Vec::new())
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_adaptive_rejects_unknown_marker() {
+        let compressor = AdaptiveCompressor::new(NullCompressor, 0, 0.9);
+
+        assert!(matches!(
+            compressor.decompress(&[0x07, 1, 2]),
+            Err(FenrisError::DecompressionError(_))
+        ));
+        assert!(compressor.decompress(&[]).is_err());
+    }
+
     #[cfg(feature = "zstd")]
     #[test]
     fn test_zstd_compress_decompress() {
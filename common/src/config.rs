@@ -4,12 +4,16 @@ use crate::{
     CompressionManager, CryptoManager, ProtobufCodec, ZlibCompressor,
     compression::{Compressor, NullCompressor},
     crypto::{
-        AesGcmEncryptor, Encryptor, HkdfSha256Deriver, KeyDeriver, KeyExchanger, X25519KeyExchanger,
+        AeadEncryptor, AesGcmEncryptor, HkdfSha256Deriver, HkdfSha512Deriver, KeyDeriver,
+        KeyExchanger, P256KeyExchanger, X25519KeyExchanger,
     },
 };
 
+pub type DefaultKeyDeriver = HkdfSha256Deriver;
+pub type StrongKeyDeriver = HkdfSha512Deriver;
+
 pub trait CryptoConfig {
-    type Encryptor: Encryptor;
+    type Encryptor: AeadEncryptor;
     type KeyExchanger: KeyExchanger;
     type KeyDeriver: KeyDeriver;
 
@@ -61,13 +65,13 @@ pub struct DefaultSuite;
 impl CryptoConfig for DefaultSuite {
     type Encryptor = AesGcmEncryptor;
     type KeyExchanger = X25519KeyExchanger;
-    type KeyDeriver = HkdfSha256Deriver;
+    type KeyDeriver = DefaultKeyDeriver;
 
     fn crypto() -> CryptoManager<Self::Encryptor, Self::KeyExchanger, Self::KeyDeriver> {
         CryptoManager::new(
             AesGcmEncryptor,
             X25519KeyExchanger,
-            HkdfSha256Deriver::default(),
+            DefaultKeyDeriver::default(),
         )
     }
 }
@@ -80,6 +84,40 @@ impl CompressionConfig for DefaultSuite {
     }
 }
 
+/// X25519 and AES-256-GCM with session keys derived through HKDF-SHA512.
+pub struct StrongSuite;
+
+impl CryptoConfig for StrongSuite {
+    type Encryptor = AesGcmEncryptor;
+    type KeyExchanger = X25519KeyExchanger;
+    type KeyDeriver = StrongKeyDeriver;
+
+    fn crypto() -> CryptoManager<Self::Encryptor, Self::KeyExchanger, Self::KeyDeriver> {
+        CryptoManager::new(
+            AesGcmEncryptor,
+            X25519KeyExchanger,
+            StrongKeyDeriver::default(),
+        )
+    }
+}
+
+/// NIST-only primitives: ECDH over P-256, AES-256-GCM and HKDF-SHA256.
+pub struct NistCryptoSuite;
+
+impl CryptoConfig for NistCryptoSuite {
+    type Encryptor = AesGcmEncryptor;
+    type KeyExchanger = P256KeyExchanger;
+    type KeyDeriver = HkdfSha256Deriver;
+
+    fn crypto() -> CryptoManager<Self::Encryptor, Self::KeyExchanger, Self::KeyDeriver> {
+        CryptoManager::new(
+            AesGcmEncryptor,
+            P256KeyExchanger,
+            HkdfSha256Deriver::default(),
+        )
+    }
+}
+
 pub struct Zlib;
 
 impl CompressionConfig for Zlib {
@@ -163,6 +201,43 @@ mod tests {
         assert_eq!(decrypted, plaintext);
     }
 
+    #[test]
+    fn strong_suite_derives_keys_with_hkdf_sha512() {
+        let crypto: CryptoManager<AesGcmEncryptor, X25519KeyExchanger, StrongKeyDeriver> =
+            StrongSuite::crypto();
+        let shared_secret = [3u8; 32];
+
+        let key = crypto.derive_key(&shared_secret, b"strong").unwrap();
+        let expected = HkdfSha512Deriver::default()
+            .derive_key(&shared_secret, b"strong", KEY_SIZE)
+            .unwrap();
+
+        assert_eq!(&*key, expected.as_slice());
+        assert_ne!(
+            &*key,
+            &*DefaultSuite::crypto()
+                .derive_key(&shared_secret, b"strong")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn nist_suite_derives_matching_keys_over_p256() {
+        let crypto: CryptoManager<AesGcmEncryptor, P256KeyExchanger, HkdfSha256Deriver> =
+            NistCryptoSuite::crypto();
+
+        let (alice_priv, alice_pub) = crypto.generate_keypair();
+        let (bob_priv, bob_pub) = crypto.generate_keypair();
+        let alice_shared = crypto.compute_shared_secret(&alice_priv, &bob_pub).unwrap();
+        let bob_shared = crypto.compute_shared_secret(&bob_priv, &alice_pub).unwrap();
+        let alice_key = crypto.derive_key(&alice_shared, b"nist").unwrap();
+        let bob_key = crypto.derive_key(&bob_shared, b"nist").unwrap();
+
+        assert_eq!(alice_pub.len(), 65);
+        assert_eq!(&*alice_key, &*bob_key);
+        assert_eq!(alice_key.len(), KEY_SIZE);
+    }
+
     #[test]
     fn default_suite_compression_uses_null_compressor() {
         let compression: CompressionManager<NullCompressor> = DefaultSuite::compression();
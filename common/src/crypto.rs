@@ -1,4 +1,7 @@
 use crate::error::{FenrisError, Result};
+use std::fmt;
+use std::ops::Deref;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 pub const KEY_SIZE: usize = 32;
 
@@ -8,6 +11,37 @@ pub const TAG_SIZE: usize = 16;
 
 pub const ECDH_KEY_SIZE: usize = 32;
 
+/// SEC1 uncompressed point sizes (`0x04 || x || y`).
+pub const P256_PUBLIC_KEY_SIZE: usize = 65;
+
+pub const P384_PUBLIC_KEY_SIZE: usize = 97;
+
+pub type ZeroizingVec = Zeroizing<Vec<u8>>;
+
+/// Symmetric session key; the bytes are wiped when the key is dropped.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct SessionKey(Vec<u8>);
+
+impl From<Vec<u8>> for SessionKey {
+    fn from(key: Vec<u8>) -> Self {
+        Self(key)
+    }
+}
+
+impl Deref for SessionKey {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SessionKey([REDACTED; {}])", self.0.len())
+    }
+}
+
 pub trait Encryptor: Send + Sync {
     fn encrypt(&self, plaintext: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>>;
 
@@ -20,10 +54,33 @@ pub trait Encryptor: Send + Sync {
     fn iv_size(&self) -> usize;
 }
 
+/// Encryptors that can authenticate additional data which is sent in the clear.
+pub trait AeadEncryptor: Encryptor {
+    fn encrypt_with_aad(
+        &self,
+        plaintext: &[u8],
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>>;
+
+    fn decrypt_with_aad(
+        &self,
+        ciphertext: &[u8],
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>>;
+}
+
 pub trait KeyExchanger: Send + Sync {
-    fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>);
+    fn generate_keypair(&self) -> (ZeroizingVec, Vec<u8>);
 
-    fn compute_shared_secret(&self, private_key: &[u8], peer_public_key: &[u8]) -> Result<Vec<u8>>;
+    fn compute_shared_secret(
+        &self,
+        private_key: &[u8],
+        peer_public_key: &[u8],
+    ) -> Result<ZeroizingVec>;
 
     fn key_size(&self) -> usize;
 }
@@ -39,17 +96,27 @@ pub trait KeyDeriver: Send + Sync {
 
 use aes_gcm::{
     Aes256Gcm, Nonce,
-    aead::{Aead, KeyInit, OsRng, rand_core::RngCore},
+    aead::{Aead, KeyInit, OsRng, Payload, rand_core::RngCore},
 };
 use hkdf::Hkdf;
-use sha2::Sha256;
+use sha2::{Sha256, Sha512};
 use x25519_dalek::{PublicKey, StaticSecret};
 
+pub struct SecureRandom;
+
+impl SecureRandom {
+    pub fn random_suffix() -> String {
+        let mut bytes = [0u8; 8];
+        OsRng.fill_bytes(&mut bytes);
+        hex::encode(bytes)
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct AesGcmEncryptor;
 
-impl Encryptor for AesGcmEncryptor {
-    fn encrypt(&self, plaintext: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+impl AesGcmEncryptor {
+    fn check_sizes(&self, key: &[u8], iv: &[u8]) -> Result<()> {
         if key.len() != self.key_size() {
             return Err(FenrisError::InvalidKeySize {
                 expected: self.key_size(),
@@ -64,30 +131,68 @@ impl Encryptor for AesGcmEncryptor {
             });
         }
 
+        Ok(())
+    }
+}
+
+impl Encryptor for AesGcmEncryptor {
+    fn encrypt(&self, plaintext: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+        self.encrypt_with_aad(plaintext, key, iv, &[])
+    }
+
+    fn decrypt(&self, ciphertext: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+        self.decrypt_with_aad(ciphertext, key, iv, &[])
+    }
+
+    fn generate_iv(&self) -> Vec<u8> {
+        let mut iv = vec![0u8; self.iv_size()];
+        OsRng.fill_bytes(&mut iv);
+        iv
+    }
+
+    fn key_size(&self) -> usize {
+        KEY_SIZE
+    }
+
+    fn iv_size(&self) -> usize {
+        IV_SIZE
+    }
+}
+
+impl AeadEncryptor for AesGcmEncryptor {
+    fn encrypt_with_aad(
+        &self,
+        plaintext: &[u8],
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>> {
+        self.check_sizes(key, iv)?;
+
         let cipher = Aes256Gcm::new_from_slice(key)
-            .map_err(|e| FenrisError::EncryptionError(e.to_string()))?;
+            .map_err(|e| FenrisError::encryption_from(e.to_string(), e))?;
 
         let nonce = Nonce::from_slice(iv);
 
         cipher
-            .encrypt(nonce, plaintext)
-            .map_err(|e| FenrisError::EncryptionError(e.to_string()))
+            .encrypt(
+                nonce,
+                Payload {
+                    msg: plaintext,
+                    aad,
+                },
+            )
+            .map_err(|e| FenrisError::encryption(e.to_string()))
     }
 
-    fn decrypt(&self, ciphertext: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
-        if key.len() != self.key_size() {
-            return Err(FenrisError::InvalidKeySize {
-                expected: self.key_size(),
-                got: key.len(),
-            });
-        }
-
-        if iv.len() != self.iv_size() {
-            return Err(FenrisError::InvalidIvSize {
-                expected: self.iv_size(),
-                got: iv.len(),
-            });
-        }
+    fn decrypt_with_aad(
+        &self,
+        ciphertext: &[u8],
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>> {
+        self.check_sizes(key, iv)?;
 
         if ciphertext.len() < TAG_SIZE {
             return Err(FenrisError::DecryptionError(
@@ -96,42 +201,42 @@ impl Encryptor for AesGcmEncryptor {
         }
 
         let cipher = Aes256Gcm::new_from_slice(key)
-            .map_err(|e| FenrisError::EncryptionError(e.to_string()))?;
+            .map_err(|e| FenrisError::encryption_from(e.to_string(), e))?;
 
         let nonce = Nonce::from_slice(iv);
 
         cipher
-            .decrypt(nonce, ciphertext)
+            .decrypt(
+                nonce,
+                Payload {
+                    msg: ciphertext,
+                    aad,
+                },
+            )
             .map_err(|e| FenrisError::DecryptionError(e.to_string()))
     }
-
-    fn generate_iv(&self) -> Vec<u8> {
-        let mut iv = vec![0u8; self.iv_size()];
-        OsRng.fill_bytes(&mut iv);
-        iv
-    }
-
-    fn key_size(&self) -> usize {
-        KEY_SIZE
-    }
-
-    fn iv_size(&self) -> usize {
-        IV_SIZE
-    }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct X25519KeyExchanger;
 
 impl KeyExchanger for X25519KeyExchanger {
-    fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+    fn generate_keypair(&self) -> (ZeroizingVec, Vec<u8>) {
         let secret = StaticSecret::random_from_rng(OsRng);
         let public = PublicKey::from(&secret);
+        let secret_bytes = Zeroizing::new(secret.to_bytes());
 
-        (secret.to_bytes().to_vec(), public.as_bytes().to_vec())
+        (
+            Zeroizing::new(secret_bytes.to_vec()),
+            public.as_bytes().to_vec(),
+        )
     }
 
-    fn compute_shared_secret(&self, private_key: &[u8], peer_public_key: &[u8]) -> Result<Vec<u8>> {
+    fn compute_shared_secret(
+        &self,
+        private_key: &[u8],
+        peer_public_key: &[u8],
+    ) -> Result<ZeroizingVec> {
         if private_key.len() != self.key_size() {
             return Err(FenrisError::InvalidKeySize {
                 expected: self.key_size(),
@@ -146,15 +251,15 @@ impl KeyExchanger for X25519KeyExchanger {
             });
         }
 
-        let secret_bytes: [u8; 32] = private_key.try_into().unwrap();
+        let secret_bytes: Zeroizing<[u8; 32]> = Zeroizing::new(private_key.try_into().unwrap());
         let public_bytes: [u8; 32] = peer_public_key.try_into().unwrap();
 
-        let secret = StaticSecret::from(secret_bytes);
+        let secret = StaticSecret::from(*secret_bytes);
         let public = PublicKey::from(public_bytes);
 
         let shared = secret.diffie_hellman(&public);
 
-        Ok(shared.as_bytes().to_vec())
+        Ok(Zeroizing::new(shared.as_bytes().to_vec()))
     }
 
     fn key_size(&self) -> usize {
@@ -162,6 +267,68 @@ impl KeyExchanger for X25519KeyExchanger {
     }
 }
 
+macro_rules! nist_key_exchanger {
+    ($name:ident, $curve:ident, $public_key_size:expr) => {
+        #[derive(Debug, Clone, Default)]
+        pub struct $name;
+
+        impl $name {
+            const PRIVATE_KEY_SIZE: usize = ($public_key_size - 1) / 2;
+        }
+
+        impl KeyExchanger for $name {
+            fn generate_keypair(&self) -> (ZeroizingVec, Vec<u8>) {
+                use $curve::elliptic_curve::sec1::ToEncodedPoint;
+
+                let secret = $curve::SecretKey::random(&mut OsRng);
+                let public = secret.public_key().to_encoded_point(false);
+
+                (
+                    Zeroizing::new(secret.to_bytes().to_vec()),
+                    public.as_bytes().to_vec(),
+                )
+            }
+
+            fn compute_shared_secret(
+                &self,
+                private_key: &[u8],
+                peer_public_key: &[u8],
+            ) -> Result<ZeroizingVec> {
+                if private_key.len() != Self::PRIVATE_KEY_SIZE {
+                    return Err(FenrisError::InvalidKeySize {
+                        expected: Self::PRIVATE_KEY_SIZE,
+                        got: private_key.len(),
+                    });
+                }
+
+                if peer_public_key.len() != self.key_size() {
+                    return Err(FenrisError::InvalidKeySize {
+                        expected: self.key_size(),
+                        got: peer_public_key.len(),
+                    });
+                }
+
+                let secret = $curve::SecretKey::from_slice(private_key)
+                    .map_err(|_| FenrisError::encryption("invalid private key"))?;
+                let public = $curve::PublicKey::from_sec1_bytes(peer_public_key)
+                    .map_err(|_| FenrisError::encryption("invalid public key"))?;
+
+                let shared =
+                    $curve::ecdh::diffie_hellman(secret.to_nonzero_scalar(), public.as_affine());
+
+                Ok(Zeroizing::new(shared.raw_secret_bytes().to_vec()))
+            }
+
+            fn key_size(&self) -> usize {
+                $public_key_size
+            }
+        }
+    };
+}
+
+nist_key_exchanger!(P256KeyExchanger, p256, P256_PUBLIC_KEY_SIZE);
+nist_key_exchanger!(P384KeyExchanger, p384, P384_PUBLIC_KEY_SIZE);
+
 #[derive(Debug, Clone, Default)]
 pub struct HkdfSha256Deriver {
     salt: Vec<u8>,
@@ -180,22 +347,52 @@ impl KeyDeriver for HkdfSha256Deriver {
         context: &[u8],
         output_size: usize,
     ) -> Result<Vec<u8>> {
-        let salt = if self.salt.is_empty() {
-            b"fenris-encryption-salt-v1"
-        } else {
-            self.salt.as_slice()
-        };
+        let hkdf = Hkdf::<Sha256>::new(Some(hkdf_salt(&self.salt)), shared_secret);
+
+        let mut key = vec![0u8; output_size];
+        hkdf.expand(context, &mut key)
+            .map_err(|e| FenrisError::encryption(e.to_string()))?;
+
+        Ok(key)
+    }
+}
 
-        let hkdf = Hkdf::<Sha256>::new(Some(salt), shared_secret);
+#[derive(Debug, Clone, Default)]
+pub struct HkdfSha512Deriver {
+    salt: Vec<u8>,
+}
+
+impl HkdfSha512Deriver {
+    pub fn with_salt(salt: Vec<u8>) -> Self {
+        Self { salt }
+    }
+}
+
+impl KeyDeriver for HkdfSha512Deriver {
+    fn derive_key(
+        &self,
+        shared_secret: &[u8],
+        context: &[u8],
+        output_size: usize,
+    ) -> Result<Vec<u8>> {
+        let hkdf = Hkdf::<Sha512>::new(Some(hkdf_salt(&self.salt)), shared_secret);
 
         let mut key = vec![0u8; output_size];
         hkdf.expand(context, &mut key)
-            .map_err(|e| FenrisError::EncryptionError(e.to_string()))?;
+            .map_err(|e| FenrisError::encryption(e.to_string()))?;
 
         Ok(key)
     }
 }
 
+fn hkdf_salt(salt: &[u8]) -> &[u8] {
+    if salt.is_empty() {
+        b"fenris-encryption-salt-v1"
+    } else {
+        salt
+    }
+}
+
 pub struct CryptoManager<E: Encryptor, K: KeyExchanger, D: KeyDeriver> {
     encryptor: E,
     key_exchanger: K,
@@ -223,7 +420,7 @@ impl<E: Encryptor, K: KeyExchanger, D: KeyDeriver> CryptoManager<E, K, D> {
         self.encryptor.generate_iv()
     }
 
-    pub fn generate_keypair(&self) -> (Vec<u8>, Vec<u8>) {
+    pub fn generate_keypair(&self) -> (ZeroizingVec, Vec<u8>) {
         self.key_exchanger.generate_keypair()
     }
 
@@ -231,15 +428,16 @@ impl<E: Encryptor, K: KeyExchanger, D: KeyDeriver> CryptoManager<E, K, D> {
         &self,
         private_key: &[u8],
         peer_public_key: &[u8],
-    ) -> Result<Vec<u8>> {
+    ) -> Result<ZeroizingVec> {
         self.key_exchanger
             .compute_shared_secret(private_key, peer_public_key)
     }
 
-    pub fn derive_key(&self, shared_secret: &[u8], context: &[u8]) -> Result<Vec<u8>> {
+    pub fn derive_key(&self, shared_secret: &[u8], context: &[u8]) -> Result<SessionKey> {
         let output_size = self.encryptor.key_size();
         self.key_deriver
             .derive_key(shared_secret, context, output_size)
+            .map(SessionKey::from)
     }
     // seals packet as `iv || ciphertext`
     pub fn seal(&self, plaintext: &[u8], key: &[u8]) -> Result<Vec<u8>> {
@@ -267,10 +465,46 @@ impl<E: Encryptor, K: KeyExchanger, D: KeyDeriver> CryptoManager<E, K, D> {
     }
 }
 
+impl<E: AeadEncryptor, K: KeyExchanger, D: KeyDeriver> CryptoManager<E, K, D> {
+    // seals packet as `iv || ciphertext`, authenticating `aad` without encrypting it
+    pub fn seal_with_aad(&self, plaintext: &[u8], key: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        let iv = self.generate_iv();
+        let mut ciphertext = self.encryptor.encrypt_with_aad(plaintext, key, &iv, aad)?;
+
+        let mut sealed = iv;
+        sealed.append(&mut ciphertext);
+
+        Ok(sealed)
+    }
+
+    pub fn open_with_aad(&self, sealed: &[u8], key: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        let iv_size = self.encryptor.iv_size();
+        if sealed.len() < iv_size {
+            return Err(FenrisError::DecryptionError(
+                "Sealed data is too short to contain IV".to_string(),
+            ));
+        }
+
+        let (iv, ciphertext) = sealed.split_at(iv_size);
+
+        self.encryptor.decrypt_with_aad(ciphertext, key, iv, aad)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_random_suffix_is_16_hex_chars() {
+        let first = SecureRandom::random_suffix();
+        let second = SecureRandom::random_suffix();
+
+        assert_eq!(first.len(), 16);
+        assert!(first.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(first, second);
+    }
+
     #[test]
     fn test_default_crypto_manager() {
         let manager = CryptoManager::new(
@@ -310,6 +544,123 @@ mod tests {
         assert_eq!(alice_shared, bob_shared);
     }
 
+    fn assert_dh_commutes<K: KeyExchanger>(exchanger: K) {
+        let (alice_priv, alice_pub) = exchanger.generate_keypair();
+        let (bob_priv, bob_pub) = exchanger.generate_keypair();
+
+        assert_eq!(alice_pub.len(), exchanger.key_size());
+        assert_eq!(alice_pub[0], 0x04);
+
+        let alice_shared = exchanger
+            .compute_shared_secret(&alice_priv, &bob_pub)
+            .unwrap();
+        let bob_shared = exchanger
+            .compute_shared_secret(&bob_priv, &alice_pub)
+            .unwrap();
+
+        assert_eq!(alice_shared, bob_shared);
+    }
+
+    fn sec1_point(x: &str, y: &str) -> Vec<u8> {
+        let mut point = vec![0x04];
+        point.extend(hex::decode(x).unwrap());
+        point.extend(hex::decode(y).unwrap());
+        point
+    }
+
+    #[test]
+    fn test_nist_key_exchange_commutes() {
+        assert_dh_commutes(P256KeyExchanger);
+        assert_dh_commutes(P384KeyExchanger);
+    }
+
+    // NIST CAVS ECC CDH primitive test vectors (SP 800-56A), COUNT = 0.
+    #[test]
+    fn test_p256_known_answer() {
+        let private_key =
+            hex::decode("7d7dc5f71eb29ddaf80d6214632eeae03d9058af1fb6d22ed80badb62bc1a534")
+                .unwrap();
+        let peer_public_key = sec1_point(
+            "700c48f77f56584c5cc632ca65640db91b6bacce3a4df6b42ce7cc838833d287",
+            "db71e509e3fd9b060ddb20ba5c51dcc5948d46fbf640dfe0441782cab85fa4ac",
+        );
+
+        let shared = P256KeyExchanger
+            .compute_shared_secret(&private_key, &peer_public_key)
+            .unwrap();
+
+        assert_eq!(
+            hex::encode(&*shared),
+            "46fc62106420ff012e54a434fbdd2d25ccc5852060561e68040dd7778997bd7b"
+        );
+    }
+
+    #[test]
+    fn test_p384_known_answer() {
+        let private_key = hex::decode(
+            "3cc3122a68f0d95027ad38c067916ba0eb8c38894d22e1b15618b6818a661774ad463b205da88cf699ab4d43c9cf98a1",
+        )
+        .unwrap();
+        let peer_public_key = sec1_point(
+            "a7c76b970c3b5fe8b05d2838ae04ab47697b9eaf52e764592efda27fe7513272734466b400091adbf2d68c58e0c50066",
+            "ac68f19f2e1cb879aed43a9969b91a0839c4c38a49749b661efedf243451915ed0905a32b060992b468c64766fc8437a",
+        );
+
+        let shared = P384KeyExchanger
+            .compute_shared_secret(&private_key, &peer_public_key)
+            .unwrap();
+
+        assert_eq!(
+            hex::encode(&*shared),
+            "5f9d29dc5e31a163060356213669c8ce132e22f57c9a04f40ba7fcead493b457e5621e766c40a2e3d4d6a04b25e533f1"
+        );
+    }
+
+    #[test]
+    fn test_p256_rejects_invalid_public_key() {
+        let (private_key, _) = P256KeyExchanger.generate_keypair();
+        let mut not_on_curve = vec![0x04];
+        not_on_curve.extend([1u8; 64]);
+
+        assert!(matches!(
+            P256KeyExchanger.compute_shared_secret(&private_key, &not_on_curve),
+            Err(FenrisError::EncryptionError { .. })
+        ));
+        assert!(matches!(
+            P256KeyExchanger.compute_shared_secret(&private_key, &[0x04; 33]),
+            Err(FenrisError::InvalidKeySize { expected: 65, .. })
+        ));
+    }
+
+    // RFC 5869 test case 1 inputs, with the output recomputed for HMAC-SHA512.
+    #[test]
+    fn test_hkdf_sha512_known_answer() {
+        let deriver = HkdfSha512Deriver::with_salt((0x00..=0x0c).collect());
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+
+        let okm = deriver.derive_key(&[0x0b; 22], &info, 42).unwrap();
+
+        assert_eq!(
+            hex::encode(okm),
+            "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c1481579338da362cb8d9f925d7cb"
+        );
+    }
+
+    #[test]
+    fn test_hkdf_sha512_differs_from_sha256() {
+        let secret = [9u8; 32];
+
+        let strong = HkdfSha512Deriver::default()
+            .derive_key(&secret, b"ctx", KEY_SIZE)
+            .unwrap();
+        let default = HkdfSha256Deriver::default()
+            .derive_key(&secret, b"ctx", KEY_SIZE)
+            .unwrap();
+
+        assert_eq!(strong.len(), KEY_SIZE);
+        assert_ne!(strong, default);
+    }
+
     #[test]
     fn test_full_workflow() {
         let manager = CryptoManager::new(
@@ -403,4 +754,53 @@ mod tests {
         let opened = manager.open(&sealed, &key).unwrap();
         assert_eq!(opened, plaintext);
     }
+
+    #[test]
+    fn test_aad_is_authenticated() {
+        let manager = CryptoManager::new(
+            AesGcmEncryptor,
+            X25519KeyExchanger,
+            HkdfSha256Deriver::default(),
+        );
+        let key = [9u8; KEY_SIZE];
+
+        let sealed = manager
+            .seal_with_aad(b"payload", &key, b"header-1")
+            .unwrap();
+        assert_eq!(
+            manager.open_with_aad(&sealed, &key, b"header-1").unwrap(),
+            b"payload"
+        );
+        assert!(matches!(
+            manager.open_with_aad(&sealed, &key, b"header-2"),
+            Err(FenrisError::DecryptionError(_))
+        ));
+        assert!(manager.open(&sealed, &key).is_err());
+
+        let sealed = manager.seal(b"payload", &key).unwrap();
+        assert_eq!(
+            manager.open_with_aad(&sealed, &key, b"").unwrap(),
+            b"payload"
+        );
+    }
+
+    #[test]
+    fn test_session_key_zeroizes_its_buffer() {
+        fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+        assert_zeroize_on_drop::<SessionKey>();
+
+        let mut key = SessionKey::from(vec![7u8; KEY_SIZE]);
+        let ptr = key.as_ptr();
+        key.zeroize();
+
+        // `Vec::zeroize` wipes the whole allocation and clears the length without freeing it,
+        // so the old buffer can still be inspected through the saved pointer.
+        let wiped = unsafe { std::slice::from_raw_parts(ptr, KEY_SIZE) };
+        assert!(wiped.iter().all(|byte| *byte == 0));
+        assert!(key.is_empty());
+        assert_eq!(
+            format!("{:?}", SessionKey::from(vec![1; 4])),
+            "SessionKey([REDACTED; 4])"
+        );
+    }
 }
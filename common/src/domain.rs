@@ -1,15 +1,37 @@
-use std::path::PathBuf;
+use prost::Message;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use crate::{
     FenrisError, FileMetadata, Request, RequestType, Response, ResponseType,
     proto::{
-        DirectoryListing, FileInfo, TransferAck, TransferChunk as ProtoTransferChunk, TransferMode,
-        TransferStart, request, response,
+        DirFilter as ProtoDirFilter, DirectoryListing, FileInfo, ManifestEntry, ManifestResponse,
+        MultiOpResponse, QuotaInfo, TransferAck, TransferChunk as ProtoTransferChunk, TransferMode,
+        TransferStart, WatchChange, WatchEvent as ProtoWatchEvent, request, response,
     },
 };
 
 pub const DEFAULT_TRANSFER_CHUNK_SIZE: usize = 64 * 1024;
 
+/// Most files a CHECKSUM_MANIFEST lists; larger trees come back truncated.
+pub const MAX_MANIFEST_ENTRIES: usize = 10_000;
+
+/// Session variable names follow the shell's rules: `[A-Za-z_][A-Za-z0-9_]*`.
+pub fn is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The `filename` of a MULTI_OP request that stops at the first failing command.
+const MULTI_OP_FAIL_FAST: &str = "fail_fast";
+
+/// The leading `data` byte of an INFO_FILE request that asks for MIME detection.
+const MIME_DETECTION_FLAG: u8 = 0x01;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ObjectWriteMode {
     Write,
@@ -17,6 +39,33 @@ pub enum ObjectWriteMode {
     Upload,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListSortKey {
+    Name,
+    Size,
+    Modified,
+}
+
+/// Ordering for a directory listing; the default keeps the storage backend's order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListSort {
+    pub key: Option<ListSortKey>,
+    pub reverse: bool,
+    pub dirs_first: bool,
+}
+
+/// Conditions a filtered listing applies on the server; `None` and an empty type mask leave
+/// the corresponding condition out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirFilter {
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Any of [`DirFilter::FILES`], [`DirFilter::DIRS`] and [`DirFilter::SYMLINKS`].
+    pub file_types: u32,
+    /// A glob where `*` matches any run of characters and `?` any single one.
+    pub name_pattern: Option<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TransferChunk {
     pub offset: u64,
@@ -25,6 +74,20 @@ pub struct TransferChunk {
     pub total_size: u64,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchEventKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchEvent {
+    pub path: PathBuf,
+    pub kind: WatchEventKind,
+    pub subscription: PathBuf,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FenrisCommand {
     Ping,
@@ -57,12 +120,25 @@ pub enum FenrisCommand {
     WriteObjectChunk(TransferChunk),
     ObjectInfo {
         path: PathBuf,
+        /// Also sniff the file's content type, at the cost of reading its first bytes.
+        detect_mime: bool,
     },
     CreateNamespace {
         path: PathBuf,
     },
+    /// Like `CreateNamespace`, also creating missing parents and accepting an existing
+    /// namespace.
+    CreateNamespaceAll {
+        path: PathBuf,
+    },
     ListNamespace {
         path: PathBuf,
+        sort: ListSort,
+    },
+    /// Lists only the entries of `path` that pass `filter`.
+    ListNamespaceFiltered {
+        path: PathBuf,
+        filter: DirFilter,
     },
     ChangeNamespace {
         path: PathBuf,
@@ -70,6 +146,128 @@ pub enum FenrisCommand {
     DeleteNamespace {
         path: PathBuf,
     },
+    Subscribe {
+        path: PathBuf,
+    },
+    Unsubscribe {
+        path: PathBuf,
+    },
+    FetchUrl {
+        url: String,
+        path: PathBuf,
+    },
+    DiffObjects {
+        left: PathBuf,
+        right: PathBuf,
+    },
+    ListNamespaceRecursive {
+        path: PathBuf,
+        max_depth: u32,
+    },
+    ChecksumObject {
+        path: PathBuf,
+    },
+    Broadcast {
+        message: String,
+    },
+    /// Asks the server to send `payload` back untouched.
+    Echo {
+        payload: Vec<u8>,
+    },
+    /// The first message on a connection to a server with a users file.
+    Login {
+        username: String,
+        password: String,
+    },
+    CompressObject {
+        path: PathBuf,
+        extension: Option<String>,
+    },
+    DecompressObject {
+        path: PathBuf,
+        extension: Option<String>,
+    },
+    HeadObject {
+        path: PathBuf,
+        lines: u32,
+    },
+    TailObject {
+        path: PathBuf,
+        bytes: u64,
+    },
+    CreateSymlink {
+        target: PathBuf,
+        link: PathBuf,
+    },
+    ReadSymlink {
+        link: PathBuf,
+    },
+    /// A directory junction on Windows servers, a plain symlink everywhere else.
+    CreateDirJunction {
+        target: PathBuf,
+        link: PathBuf,
+    },
+    SetMtime {
+        path: PathBuf,
+        mtime: u64,
+    },
+    /// Renames a file or directory, copying across filesystems when needed.
+    MoveObject {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// Reads the extended attribute `name` of a file.
+    GetXattr {
+        path: PathBuf,
+        name: String,
+    },
+    SetXattr {
+        path: PathBuf,
+        name: String,
+        value: Vec<u8>,
+    },
+    /// Reads a variable from this session's server-side environment.
+    GetEnv {
+        name: String,
+    },
+    /// Sets a variable in this session's server-side environment; it is gone once the client
+    /// disconnects.
+    SetEnv {
+        name: String,
+        value: String,
+    },
+    /// SHA-256 of every file below `path`, for checking a transfer arrived intact.
+    ChecksumManifest {
+        path: PathBuf,
+    },
+    /// Packs the directory `dir` into a gzipped tarball at `archive`, or `<dir>.tar.gz`.
+    CreateArchive {
+        dir: PathBuf,
+        archive: Option<PathBuf>,
+    },
+    /// Unpacks a gzipped tarball into `dest`, or the current directory.
+    ExtractArchive {
+        archive: PathBuf,
+        dest: Option<PathBuf>,
+    },
+    /// Reads the caller's storage quota, or sets it to `limit` bytes.
+    Quota {
+        limit: Option<u64>,
+    },
+    /// Runs `commands` in order in one round trip. Unless `fail_fast` is set, a failing
+    /// command does not stop the ones after it.
+    MultiOp {
+        commands: Vec<FenrisCommand>,
+        fail_fast: bool,
+    },
+    Timed {
+        timeout: Duration,
+        command: Box<FenrisCommand>,
+    },
+    Correlated {
+        request_id: u64,
+        command: Box<FenrisCommand>,
+    },
     Terminate,
 }
 
@@ -79,6 +277,8 @@ pub struct FenrisMetadata {
     pub size: u64,
     pub is_namespace: bool,
     pub modified_time: u64,
+    /// Unix seconds, or 0 when the platform does not record creation times.
+    pub created_time: u64,
     pub permissions: u32,
 }
 
@@ -96,9 +296,14 @@ pub enum FenrisOutput {
     ObjectContentChunk(TransferChunk),
     ObjectInfo {
         metadata: FenrisMetadata,
+        mime_type: Option<String>,
     },
     NamespaceListing {
         entries: Vec<FenrisMetadata>,
+        sort: ListSort,
+    },
+    RecursiveNamespaceListing {
+        entries: Vec<(PathBuf, FenrisMetadata)>,
     },
     NamespaceChanged {
         path: PathBuf,
@@ -109,16 +314,137 @@ pub enum FenrisOutput {
     TransferProgress {
         offset: u64,
     },
+    WatchEvent(WatchEvent),
+    ObjectDiff {
+        diff: String,
+    },
+    ObjectChecksum {
+        digest: [u8; 32],
+    },
+    /// Paths are relative to the directory the manifest was taken of, in sorted order.
+    /// `truncated` is set when the directory held more than [`MAX_MANIFEST_ENTRIES`] files.
+    ChecksumManifest {
+        entries: Vec<(PathBuf, [u8; 32])>,
+        truncated: bool,
+    },
+    Broadcast {
+        message: String,
+    },
+    /// The server's message of the day, sent unprompted once the connection is set up.
+    Banner {
+        message: String,
+    },
+    SymlinkTarget {
+        target: PathBuf,
+    },
+    Echo {
+        payload: Vec<u8>,
+    },
+    QuotaInfo {
+        used_bytes: u64,
+        limit_bytes: Option<u64>,
+    },
+    EnvValue {
+        value: String,
+    },
+    XattrValue {
+        value: Vec<u8>,
+    },
+    /// One output per command of a `FenrisCommand::MultiOp` that ran.
+    MultiOp {
+        outputs: Vec<FenrisOutput>,
+    },
     Terminated,
     Error {
         message: String,
     },
+    Correlated {
+        request_id: u64,
+        output: Box<FenrisOutput>,
+    },
+}
+
+impl FenrisCommand {
+    pub fn request_type(&self) -> RequestType {
+        match self {
+            FenrisCommand::Ping => RequestType::Ping,
+            FenrisCommand::CreateObject { .. } => RequestType::CreateFile,
+            FenrisCommand::ReadObject { .. } => RequestType::ReadFile,
+            FenrisCommand::WriteObject { .. } => RequestType::WriteFile,
+            FenrisCommand::AppendObject { .. } => RequestType::AppendFile,
+            FenrisCommand::DeleteObject { .. } => RequestType::DeleteFile,
+            FenrisCommand::UploadObject { .. } => RequestType::UploadFile,
+            FenrisCommand::BeginObjectWrite { .. } => RequestType::BeginObjectWrite,
+            FenrisCommand::WriteObjectChunk(_) => RequestType::WriteObjectChunk,
+            FenrisCommand::ObjectInfo { .. } => RequestType::InfoFile,
+            FenrisCommand::CreateNamespace { .. } => RequestType::CreateDir,
+            FenrisCommand::CreateNamespaceAll { .. } => RequestType::CreateDirAll,
+            FenrisCommand::ListNamespace { .. } => RequestType::ListDir,
+            FenrisCommand::ChangeNamespace { .. } => RequestType::ChangeDir,
+            FenrisCommand::DeleteNamespace { .. } => RequestType::DeleteDir,
+            FenrisCommand::Subscribe { .. } => RequestType::Subscribe,
+            FenrisCommand::Unsubscribe { .. } => RequestType::Unsubscribe,
+            FenrisCommand::FetchUrl { .. } => RequestType::FetchUrl,
+            FenrisCommand::DiffObjects { .. } => RequestType::DiffFiles,
+            FenrisCommand::ListNamespaceRecursive { .. } => RequestType::ListDirRecursive,
+            FenrisCommand::ChecksumObject { .. } => RequestType::Checksum,
+            FenrisCommand::Broadcast { .. } => RequestType::SendBroadcast,
+            FenrisCommand::Echo { .. } => RequestType::Echo,
+            FenrisCommand::Login { .. } => RequestType::Login,
+            FenrisCommand::CompressObject { .. } => RequestType::Compress,
+            FenrisCommand::DecompressObject { .. } => RequestType::Decompress,
+            FenrisCommand::HeadObject { .. } => RequestType::Head,
+            FenrisCommand::TailObject { .. } => RequestType::Tail,
+            FenrisCommand::CreateSymlink { .. } => RequestType::Symlink,
+            FenrisCommand::ReadSymlink { .. } => RequestType::ReadSymlink,
+            FenrisCommand::CreateDirJunction { .. } => RequestType::JunctionDir,
+            FenrisCommand::ListNamespaceFiltered { .. } => RequestType::ListDirWithFilter,
+            FenrisCommand::SetMtime { .. } => RequestType::SetMtime,
+            FenrisCommand::MoveObject { .. } => RequestType::RenameFile,
+            FenrisCommand::GetXattr { .. } => RequestType::GetAttr,
+            FenrisCommand::SetXattr { .. } => RequestType::SetAttr,
+            FenrisCommand::GetEnv { .. } => RequestType::GetEnv,
+            FenrisCommand::SetEnv { .. } => RequestType::SetEnv,
+            FenrisCommand::ChecksumManifest { .. } => RequestType::ChecksumManifest,
+            FenrisCommand::CreateArchive { .. } => RequestType::Tar,
+            FenrisCommand::ExtractArchive { .. } => RequestType::Untar,
+            FenrisCommand::Quota { .. } => RequestType::Quota,
+            FenrisCommand::MultiOp { .. } => RequestType::MultiOp,
+            FenrisCommand::Timed { command, .. } => command.request_type(),
+            FenrisCommand::Correlated { command, .. } => command.request_type(),
+            FenrisCommand::Terminate => RequestType::Terminate,
+        }
+    }
 }
 
 impl TryFrom<Request> for FenrisCommand {
     type Error = FenrisError;
 
     fn try_from(request: Request) -> Result<Self, Self::Error> {
+        if request.request_id != 0 {
+            let request_id = request.request_id;
+            let command = Self::try_from(Request {
+                request_id: 0,
+                ..request
+            })?;
+            return Ok(Self::Correlated {
+                request_id,
+                command: Box::new(command),
+            });
+        }
+
+        if request.timeout_ms != 0 {
+            let timeout = Duration::from_millis(request.timeout_ms.into());
+            let command = Self::try_from(Request {
+                timeout_ms: 0,
+                ..request
+            })?;
+            return Ok(Self::Timed {
+                timeout,
+                command: Box::new(command),
+            });
+        }
+
         let command = RequestType::try_from(request.command)
             .map_err(|_| FenrisError::InvalidProtocolMessage)?;
         let path = PathBuf::from(request.filename);
@@ -136,9 +462,16 @@ impl TryFrom<Request> for FenrisCommand {
                 data: request.data,
             }),
             RequestType::DeleteFile => Ok(Self::DeleteObject { path }),
-            RequestType::InfoFile => Ok(Self::ObjectInfo { path }),
+            RequestType::InfoFile => Ok(Self::ObjectInfo {
+                path,
+                detect_mime: request.data.first() == Some(&MIME_DETECTION_FLAG),
+            }),
             RequestType::CreateDir => Ok(Self::CreateNamespace { path }),
-            RequestType::ListDir => Ok(Self::ListNamespace { path }),
+            RequestType::CreateDirAll => Ok(Self::CreateNamespaceAll { path }),
+            RequestType::ListDir => Ok(Self::ListNamespace {
+                path,
+                sort: ListSort::from_data(&request.data),
+            }),
             RequestType::ChangeDir => Ok(Self::ChangeNamespace { path }),
             RequestType::DeleteDir => Ok(Self::DeleteNamespace { path }),
             RequestType::UploadFile => Ok(Self::UploadObject {
@@ -162,6 +495,174 @@ impl TryFrom<Request> for FenrisCommand {
                 }
                 _ => Err(FenrisError::InvalidProtocolMessage),
             },
+            RequestType::Subscribe => Ok(Self::Subscribe { path }),
+            RequestType::Unsubscribe => Ok(Self::Unsubscribe { path }),
+            RequestType::FetchUrl => Ok(Self::FetchUrl {
+                url: String::from_utf8(request.data)
+                    .map_err(|_| FenrisError::InvalidProtocolMessage)?,
+                path,
+            }),
+            RequestType::DiffFiles => Ok(Self::DiffObjects {
+                left: path,
+                right: PathBuf::from(
+                    String::from_utf8(request.data)
+                        .map_err(|_| FenrisError::InvalidProtocolMessage)?,
+                ),
+            }),
+            RequestType::ListDirRecursive => {
+                let depth = request
+                    .data
+                    .first_chunk::<4>()
+                    .ok_or(FenrisError::InvalidProtocolMessage)?;
+                Ok(Self::ListNamespaceRecursive {
+                    path,
+                    max_depth: u32::from_be_bytes(*depth),
+                })
+            }
+            RequestType::Checksum => Ok(Self::ChecksumObject { path }),
+            RequestType::SendBroadcast => Ok(Self::Broadcast {
+                message: String::from_utf8(request.data)
+                    .map_err(|_| FenrisError::InvalidProtocolMessage)?,
+            }),
+            RequestType::Echo => Ok(Self::Echo {
+                payload: request.data,
+            }),
+            RequestType::Login => Ok(Self::Login {
+                username: path.to_string_lossy().into_owned(),
+                password: String::from_utf8(request.data)
+                    .map_err(|_| FenrisError::InvalidProtocolMessage)?,
+            }),
+            RequestType::Compress => Ok(Self::CompressObject {
+                path,
+                extension: extension_from_data(request.data)?,
+            }),
+            RequestType::Decompress => Ok(Self::DecompressObject {
+                path,
+                extension: extension_from_data(request.data)?,
+            }),
+            RequestType::Head => {
+                let lines = request
+                    .data
+                    .first_chunk::<4>()
+                    .ok_or(FenrisError::InvalidProtocolMessage)?;
+                Ok(Self::HeadObject {
+                    path,
+                    lines: u32::from_be_bytes(*lines),
+                })
+            }
+            RequestType::Tail => {
+                let bytes = request
+                    .data
+                    .first_chunk::<8>()
+                    .ok_or(FenrisError::InvalidProtocolMessage)?;
+                Ok(Self::TailObject {
+                    path,
+                    bytes: u64::from_be_bytes(*bytes),
+                })
+            }
+            RequestType::Symlink => Ok(Self::CreateSymlink {
+                target: PathBuf::from(
+                    String::from_utf8(request.data)
+                        .map_err(|_| FenrisError::InvalidProtocolMessage)?,
+                ),
+                link: path,
+            }),
+            RequestType::ReadSymlink => Ok(Self::ReadSymlink { link: path }),
+            RequestType::JunctionDir => Ok(Self::CreateDirJunction {
+                target: PathBuf::from(
+                    String::from_utf8(request.data)
+                        .map_err(|_| FenrisError::InvalidProtocolMessage)?,
+                ),
+                link: path,
+            }),
+            RequestType::ListDirWithFilter => Ok(Self::ListNamespaceFiltered {
+                path,
+                filter: ProtoDirFilter::decode(request.data.as_slice())
+                    .map_err(|_| FenrisError::InvalidProtocolMessage)?
+                    .into(),
+            }),
+            RequestType::RenameFile => Ok(Self::MoveObject {
+                from: path,
+                to: PathBuf::from(
+                    String::from_utf8(request.data)
+                        .map_err(|_| FenrisError::InvalidProtocolMessage)?,
+                ),
+            }),
+            RequestType::SetMtime => {
+                let mtime = request
+                    .data
+                    .first_chunk::<8>()
+                    .ok_or(FenrisError::InvalidProtocolMessage)?;
+                Ok(Self::SetMtime {
+                    path,
+                    mtime: u64::from_be_bytes(*mtime),
+                })
+            }
+            RequestType::GetAttr => Ok(Self::GetXattr {
+                path,
+                name: String::from_utf8(request.data)
+                    .map_err(|_| FenrisError::InvalidProtocolMessage)?,
+            }),
+            RequestType::SetAttr => {
+                let separator = request
+                    .data
+                    .iter()
+                    .position(|&b| b == 0)
+                    .ok_or(FenrisError::InvalidProtocolMessage)?;
+                let name = std::str::from_utf8(&request.data[..separator])
+                    .map_err(|_| FenrisError::InvalidProtocolMessage)?
+                    .to_string();
+                Ok(Self::SetXattr {
+                    path,
+                    name,
+                    value: request.data[separator + 1..].to_vec(),
+                })
+            }
+            RequestType::GetEnv => Ok(Self::GetEnv {
+                name: path.to_string_lossy().into_owned(),
+            }),
+            RequestType::SetEnv => Ok(Self::SetEnv {
+                name: path.to_string_lossy().into_owned(),
+                value: String::from_utf8(request.data)
+                    .map_err(|_| FenrisError::InvalidProtocolMessage)?,
+            }),
+            RequestType::ChecksumManifest => Ok(Self::ChecksumManifest { path }),
+            RequestType::Tar => Ok(Self::CreateArchive {
+                dir: path,
+                archive: path_from_data(request.data)?,
+            }),
+            RequestType::Untar => Ok(Self::ExtractArchive {
+                archive: path,
+                dest: path_from_data(request.data)?,
+            }),
+            RequestType::Quota => {
+                let limit = match request.data.as_slice() {
+                    [] => None,
+                    data => Some(u64::from_be_bytes(
+                        data.try_into()
+                            .map_err(|_| FenrisError::InvalidProtocolMessage)?,
+                    )),
+                };
+                Ok(Self::Quota { limit })
+            }
+            RequestType::MultiOp => {
+                let mut data = request.data.as_slice();
+                let mut commands = Vec::new();
+                while !data.is_empty() {
+                    let sub_request = Request::decode_length_delimited(&mut data)
+                        .map_err(|_| FenrisError::InvalidProtocolMessage)?;
+                    // Checked before recursing: a few bytes per level would otherwise let a
+                    // deeply nested payload overflow the decoder's stack.
+                    if sub_request.command == RequestType::MultiOp as i32 {
+                        return Err(FenrisError::InvalidProtocolMessage);
+                    }
+                    commands.push(Self::try_from(sub_request)?);
+                }
+                Ok(Self::MultiOp {
+                    commands,
+                    fail_fast: path == Path::new(MULTI_OP_FAIL_FAST),
+                })
+            }
             RequestType::Terminate => Ok(Self::Terminate),
         }
     }
@@ -207,12 +708,23 @@ impl From<FenrisCommand> for Request {
                 Vec::new(),
                 Some(request::Details::TransferChunk(chunk.into())),
             ),
-            FenrisCommand::ObjectInfo { path } => request(RequestType::InfoFile, path, Vec::new()),
+            FenrisCommand::ObjectInfo { path, detect_mime } => request(
+                RequestType::InfoFile,
+                path,
+                if detect_mime {
+                    vec![MIME_DETECTION_FLAG]
+                } else {
+                    Vec::new()
+                },
+            ),
             FenrisCommand::CreateNamespace { path } => {
                 request(RequestType::CreateDir, path, Vec::new())
             }
-            FenrisCommand::ListNamespace { path } => {
-                request(RequestType::ListDir, path, Vec::new())
+            FenrisCommand::CreateNamespaceAll { path } => {
+                request(RequestType::CreateDirAll, path, Vec::new())
+            }
+            FenrisCommand::ListNamespace { path, sort } => {
+                request(RequestType::ListDir, path, sort.to_data())
             }
             FenrisCommand::ChangeNamespace { path } => {
                 request(RequestType::ChangeDir, path, Vec::new())
@@ -220,11 +732,157 @@ impl From<FenrisCommand> for Request {
             FenrisCommand::DeleteNamespace { path } => {
                 request(RequestType::DeleteDir, path, Vec::new())
             }
+            FenrisCommand::Subscribe { path } => request(RequestType::Subscribe, path, Vec::new()),
+            FenrisCommand::Unsubscribe { path } => {
+                request(RequestType::Unsubscribe, path, Vec::new())
+            }
+            FenrisCommand::FetchUrl { url, path } => {
+                request(RequestType::FetchUrl, path, url.into_bytes())
+            }
+            FenrisCommand::DiffObjects { left, right } => request(
+                RequestType::DiffFiles,
+                left,
+                right.to_string_lossy().as_bytes().to_vec(),
+            ),
+            FenrisCommand::ListNamespaceRecursive { path, max_depth } => request(
+                RequestType::ListDirRecursive,
+                path,
+                max_depth.to_be_bytes().to_vec(),
+            ),
+            FenrisCommand::ChecksumObject { path } => {
+                request(RequestType::Checksum, path, Vec::new())
+            }
+            FenrisCommand::Broadcast { message } => request(
+                RequestType::SendBroadcast,
+                PathBuf::new(),
+                message.into_bytes(),
+            ),
+            FenrisCommand::Echo { payload } => request(RequestType::Echo, PathBuf::new(), payload),
+            FenrisCommand::Login { username, password } => request(
+                RequestType::Login,
+                PathBuf::from(username),
+                password.into_bytes(),
+            ),
+            FenrisCommand::CompressObject { path, extension } => request(
+                RequestType::Compress,
+                path,
+                extension.map(String::into_bytes).unwrap_or_default(),
+            ),
+            FenrisCommand::DecompressObject { path, extension } => request(
+                RequestType::Decompress,
+                path,
+                extension.map(String::into_bytes).unwrap_or_default(),
+            ),
+            FenrisCommand::HeadObject { path, lines } => {
+                request(RequestType::Head, path, lines.to_be_bytes().to_vec())
+            }
+            FenrisCommand::TailObject { path, bytes } => {
+                request(RequestType::Tail, path, bytes.to_be_bytes().to_vec())
+            }
+            FenrisCommand::CreateSymlink { target, link } => request(
+                RequestType::Symlink,
+                link,
+                target.to_string_lossy().as_bytes().to_vec(),
+            ),
+            FenrisCommand::ReadSymlink { link } => {
+                request(RequestType::ReadSymlink, link, Vec::new())
+            }
+            FenrisCommand::ListNamespaceFiltered { path, filter } => request(
+                RequestType::ListDirWithFilter,
+                path,
+                ProtoDirFilter::from(filter).encode_to_vec(),
+            ),
+            FenrisCommand::CreateDirJunction { target, link } => request(
+                RequestType::JunctionDir,
+                link,
+                target.to_string_lossy().as_bytes().to_vec(),
+            ),
+            FenrisCommand::SetMtime { path, mtime } => {
+                request(RequestType::SetMtime, path, mtime.to_be_bytes().to_vec())
+            }
+            FenrisCommand::MoveObject { from, to } => request(
+                RequestType::RenameFile,
+                from,
+                to.to_string_lossy().as_bytes().to_vec(),
+            ),
+            FenrisCommand::GetXattr { path, name } => {
+                request(RequestType::GetAttr, path, name.into_bytes())
+            }
+            FenrisCommand::SetXattr { path, name, value } => {
+                let mut data = name.into_bytes();
+                data.push(0);
+                data.extend_from_slice(&value);
+                request(RequestType::SetAttr, path, data)
+            }
+            FenrisCommand::GetEnv { name } => {
+                request(RequestType::GetEnv, PathBuf::from(name), Vec::new())
+            }
+            FenrisCommand::SetEnv { name, value } => {
+                request(RequestType::SetEnv, PathBuf::from(name), value.into_bytes())
+            }
+            FenrisCommand::ChecksumManifest { path } => {
+                request(RequestType::ChecksumManifest, path, Vec::new())
+            }
+            FenrisCommand::CreateArchive { dir, archive } => {
+                request(RequestType::Tar, dir, path_to_data(archive))
+            }
+            FenrisCommand::ExtractArchive { archive, dest } => {
+                request(RequestType::Untar, archive, path_to_data(dest))
+            }
+            FenrisCommand::Quota { limit } => request(
+                RequestType::Quota,
+                PathBuf::new(),
+                limit.map(|l| l.to_be_bytes().to_vec()).unwrap_or_default(),
+            ),
+            FenrisCommand::MultiOp {
+                commands,
+                fail_fast,
+            } => {
+                let data = commands
+                    .into_iter()
+                    .flat_map(|command| Request::from(command).encode_length_delimited_to_vec())
+                    .collect();
+                let path = if fail_fast {
+                    PathBuf::from(MULTI_OP_FAIL_FAST)
+                } else {
+                    PathBuf::new()
+                };
+                request(RequestType::MultiOp, path, data)
+            }
+            FenrisCommand::Timed { timeout, command } => Request {
+                timeout_ms: u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX),
+                ..Request::from(*command)
+            },
+            FenrisCommand::Correlated {
+                request_id,
+                command,
+            } => Request {
+                request_id,
+                ..Request::from(*command)
+            },
             FenrisCommand::Terminate => request(RequestType::Terminate, PathBuf::new(), Vec::new()),
         }
     }
 }
 
+fn extension_from_data(data: Vec<u8>) -> Result<Option<String>, FenrisError> {
+    if data.is_empty() {
+        return Ok(None);
+    }
+    String::from_utf8(data)
+        .map(Some)
+        .map_err(|_| FenrisError::InvalidProtocolMessage)
+}
+
+fn path_from_data(data: Vec<u8>) -> Result<Option<PathBuf>, FenrisError> {
+    extension_from_data(data).map(|path| path.map(PathBuf::from))
+}
+
+fn path_to_data(path: Option<PathBuf>) -> Vec<u8> {
+    path.map(|path| path.to_string_lossy().as_bytes().to_vec())
+        .unwrap_or_default()
+}
+
 fn request(command: RequestType, path: PathBuf, data: Vec<u8>) -> Request {
     request_with_details(command, path, data, None)
 }
@@ -241,6 +899,8 @@ fn request_with_details(
         ip_addr: 0,
         data,
         details,
+        timeout_ms: 0,
+        request_id: 0,
     }
 }
 
@@ -248,6 +908,18 @@ impl TryFrom<Response> for FenrisOutput {
     type Error = FenrisError;
 
     fn try_from(response: Response) -> Result<Self, FenrisError> {
+        if response.request_id != 0 {
+            let request_id = response.request_id;
+            let output = Self::try_from(Response {
+                request_id: 0,
+                ..response
+            })?;
+            return Ok(Self::Correlated {
+                request_id,
+                output: Box::new(output),
+            });
+        }
+
         if !response.success {
             return Ok(Self::Error {
                 message: response.error_message,
@@ -260,12 +932,11 @@ impl TryFrom<Response> for FenrisOutput {
         match response_type {
             ResponseType::Pong => Ok(Self::Pong),
             ResponseType::FileInfo => match response.details {
-                Some(response::Details::FileInfo(info)) => Ok(Self::ObjectInfo {
+                Some(response::Details::FileInfo(mut info)) => Ok(Self::ObjectInfo {
+                    mime_type: info.mime_type.take(),
                     metadata: info.into(),
                 }),
-                _ => Err(FenrisError::SerializationError(
-                    "missing file info".to_string(),
-                )),
+                _ => Err(FenrisError::serialization("missing file info")),
             },
             ResponseType::FileContent => {
                 let total_size = response.data.len() as u64;
@@ -282,10 +953,9 @@ impl TryFrom<Response> for FenrisOutput {
                         .into_iter()
                         .map(FenrisMetadata::from)
                         .collect(),
+                    sort: ListSort::from_data(&response.data),
                 }),
-                _ => Err(FenrisError::SerializationError(
-                    "missing directory listing".to_string(),
-                )),
+                _ => Err(FenrisError::serialization("missing directory listing")),
             },
             ResponseType::Success => Ok(Self::Success {
                 message: String::from_utf8_lossy(&response.data).to_string(),
@@ -301,26 +971,104 @@ impl TryFrom<Response> for FenrisOutput {
                 Some(response::Details::TransferAck(ack)) => Ok(Self::TransferReady {
                     chunk_size: ack.chunk_size as usize,
                 }),
-                _ => Err(FenrisError::SerializationError(
-                    "missing transfer ack".to_string(),
-                )),
+                _ => Err(FenrisError::serialization("missing transfer ack")),
             },
             ResponseType::TransferProgress => match response.details {
                 Some(response::Details::TransferAck(ack)) => {
                     Ok(Self::TransferProgress { offset: ack.offset })
                 }
-                _ => Err(FenrisError::SerializationError(
-                    "missing transfer ack".to_string(),
-                )),
+                _ => Err(FenrisError::serialization("missing transfer ack")),
             },
             ResponseType::FileContentChunk => match response.details {
                 Some(response::Details::TransferChunk(chunk)) => {
                     Ok(Self::ObjectContentChunk(chunk.into()))
                 }
-                _ => Err(FenrisError::SerializationError(
-                    "missing transfer chunk".to_string(),
+                _ => Err(FenrisError::serialization("missing transfer chunk")),
+            },
+            ResponseType::WatchEvent => match response.details {
+                Some(response::Details::WatchEvent(event)) => {
+                    Ok(Self::WatchEvent(WatchEvent::try_from(event)?))
+                }
+                _ => Err(FenrisError::serialization("missing watch event")),
+            },
+            ResponseType::RecursiveDirListing => match response.details {
+                Some(response::Details::DirectoryListing(listing))
+                    if listing.entries.len() == listing.relative_paths.len() =>
+                {
+                    Ok(Self::RecursiveNamespaceListing {
+                        entries: listing
+                            .relative_paths
+                            .into_iter()
+                            .map(PathBuf::from)
+                            .zip(listing.entries.into_iter().map(FenrisMetadata::from))
+                            .collect(),
+                    })
+                }
+                _ => Err(FenrisError::serialization(
+                    "missing recursive directory listing",
                 )),
             },
+            ResponseType::FileDiff => Ok(Self::ObjectDiff {
+                diff: String::from_utf8_lossy(&response.data).to_string(),
+            }),
+            ResponseType::FileChecksum => Ok(Self::ObjectChecksum {
+                digest: response
+                    .data
+                    .try_into()
+                    .map_err(|_| FenrisError::serialization("invalid checksum digest"))?,
+            }),
+            ResponseType::Manifest => match response.details {
+                Some(response::Details::Manifest(manifest)) => Ok(Self::ChecksumManifest {
+                    entries: manifest
+                        .entries
+                        .into_iter()
+                        .map(|entry| {
+                            let digest = entry.sha256.try_into().map_err(|_| {
+                                FenrisError::serialization("invalid checksum digest")
+                            })?;
+                            Ok((PathBuf::from(entry.relative_path), digest))
+                        })
+                        .collect::<Result<_, FenrisError>>()?,
+                    truncated: manifest.truncated,
+                }),
+                _ => Err(FenrisError::serialization("missing checksum manifest")),
+            },
+            ResponseType::Broadcast => Ok(Self::Broadcast {
+                message: String::from_utf8_lossy(&response.data).to_string(),
+            }),
+            ResponseType::Banner => Ok(Self::Banner {
+                message: String::from_utf8_lossy(&response.data).to_string(),
+            }),
+            ResponseType::SymlinkTarget => Ok(Self::SymlinkTarget {
+                target: PathBuf::from(String::from_utf8_lossy(&response.data).to_string()),
+            }),
+            ResponseType::EchoReply => Ok(Self::Echo {
+                payload: response.data,
+            }),
+            ResponseType::AttrValue => Ok(Self::XattrValue {
+                value: response.data,
+            }),
+            ResponseType::EnvValue => Ok(Self::EnvValue {
+                value: String::from_utf8(response.data)
+                    .map_err(|_| FenrisError::InvalidProtocolMessage)?,
+            }),
+            ResponseType::QuotaInfo => match response.details {
+                Some(response::Details::QuotaInfo(info)) => Ok(Self::QuotaInfo {
+                    used_bytes: info.used_bytes,
+                    limit_bytes: (info.limit_bytes != 0).then_some(info.limit_bytes),
+                }),
+                _ => Err(FenrisError::serialization("missing quota info")),
+            },
+            ResponseType::MultiOpResult => match response.details {
+                Some(response::Details::MultiOp(multi_op)) => Ok(Self::MultiOp {
+                    outputs: multi_op
+                        .responses
+                        .into_iter()
+                        .map(Self::try_from)
+                        .collect::<Result<_, _>>()?,
+                }),
+                _ => Err(FenrisError::serialization("missing multi-op responses")),
+            },
         }
     }
 }
@@ -349,22 +1097,47 @@ impl From<FenrisOutput> for Response {
                     Some(response::Details::TransferChunk(chunk.into())),
                 )
             }
-            FenrisOutput::ObjectInfo { metadata } => response(
+            FenrisOutput::ObjectInfo {
+                metadata,
+                mime_type,
+            } => response(
                 ResponseType::FileInfo,
                 true,
                 String::new(),
                 vec![],
-                Some(response::Details::FileInfo(metadata.into())),
+                Some(response::Details::FileInfo(FileInfo {
+                    mime_type,
+                    ..metadata.into()
+                })),
             ),
-            FenrisOutput::NamespaceListing { entries } => response(
+            FenrisOutput::NamespaceListing { entries, sort } => response(
                 ResponseType::DirListing,
                 true,
                 String::new(),
-                vec![],
+                sort.to_data(),
                 Some(response::Details::DirectoryListing(DirectoryListing {
                     entries: entries.into_iter().map(FileInfo::from).collect(),
+                    relative_paths: Vec::new(),
                 })),
             ),
+            FenrisOutput::RecursiveNamespaceListing { entries } => {
+                let (relative_paths, entries): (Vec<_>, Vec<_>) = entries
+                    .into_iter()
+                    .map(|(path, metadata)| {
+                        (path.to_string_lossy().to_string(), FileInfo::from(metadata))
+                    })
+                    .unzip();
+                response(
+                    ResponseType::RecursiveDirListing,
+                    true,
+                    String::new(),
+                    vec![],
+                    Some(response::Details::DirectoryListing(DirectoryListing {
+                        entries,
+                        relative_paths,
+                    })),
+                )
+            }
             FenrisOutput::NamespaceChanged { path } => response(
                 ResponseType::ChangedDir,
                 true,
@@ -392,12 +1165,109 @@ impl From<FenrisOutput> for Response {
                     chunk_size: 0,
                 })),
             ),
+            FenrisOutput::WatchEvent(event) => response(
+                ResponseType::WatchEvent,
+                true,
+                String::new(),
+                vec![],
+                Some(response::Details::WatchEvent(event.into())),
+            ),
+            FenrisOutput::ObjectDiff { diff } => response(
+                ResponseType::FileDiff,
+                true,
+                String::new(),
+                diff.into_bytes(),
+                None,
+            ),
+            FenrisOutput::ObjectChecksum { digest } => response(
+                ResponseType::FileChecksum,
+                true,
+                String::new(),
+                digest.to_vec(),
+                None,
+            ),
+            FenrisOutput::ChecksumManifest { entries, truncated } => response(
+                ResponseType::Manifest,
+                true,
+                String::new(),
+                vec![],
+                Some(response::Details::Manifest(ManifestResponse {
+                    entries: entries
+                        .into_iter()
+                        .map(|(path, digest)| ManifestEntry {
+                            relative_path: path.to_string_lossy().to_string(),
+                            sha256: digest.to_vec(),
+                        })
+                        .collect(),
+                    truncated,
+                })),
+            ),
+            FenrisOutput::Broadcast { message } => response(
+                ResponseType::Broadcast,
+                true,
+                String::new(),
+                message.into_bytes(),
+                None,
+            ),
+            FenrisOutput::Banner { message } => response(
+                ResponseType::Banner,
+                true,
+                String::new(),
+                message.into_bytes(),
+                None,
+            ),
+            FenrisOutput::Echo { payload } => {
+                response(ResponseType::EchoReply, true, String::new(), payload, None)
+            }
+            FenrisOutput::QuotaInfo {
+                used_bytes,
+                limit_bytes,
+            } => response(
+                ResponseType::QuotaInfo,
+                true,
+                String::new(),
+                vec![],
+                Some(response::Details::QuotaInfo(QuotaInfo {
+                    used_bytes,
+                    limit_bytes: limit_bytes.unwrap_or(0),
+                })),
+            ),
+            FenrisOutput::XattrValue { value } => {
+                response(ResponseType::AttrValue, true, String::new(), value, None)
+            }
+            FenrisOutput::EnvValue { value } => response(
+                ResponseType::EnvValue,
+                true,
+                String::new(),
+                value.into_bytes(),
+                None,
+            ),
+            FenrisOutput::SymlinkTarget { target } => response(
+                ResponseType::SymlinkTarget,
+                true,
+                String::new(),
+                target.to_string_lossy().as_bytes().to_vec(),
+                None,
+            ),
+            FenrisOutput::MultiOp { outputs } => response(
+                ResponseType::MultiOpResult,
+                true,
+                String::new(),
+                vec![],
+                Some(response::Details::MultiOp(MultiOpResponse {
+                    responses: outputs.into_iter().map(Response::from).collect(),
+                })),
+            ),
             FenrisOutput::Terminated => {
                 response(ResponseType::Terminated, true, String::new(), vec![], None)
             }
             FenrisOutput::Error { message } => {
                 response(ResponseType::Error, false, message, vec![], None)
             }
+            FenrisOutput::Correlated { request_id, output } => Response {
+                request_id,
+                ..Response::from(*output)
+            },
         }
     }
 }
@@ -447,6 +1317,218 @@ impl From<TransferChunk> for ProtoTransferChunk {
     }
 }
 
+impl DirFilter {
+    pub const FILES: u32 = 1 << 0;
+    pub const DIRS: u32 = 1 << 1;
+    pub const SYMLINKS: u32 = 1 << 2;
+    const ALL_TYPES: u32 = Self::FILES | Self::DIRS | Self::SYMLINKS;
+
+    /// Whether telling symlinks apart matters, which costs the server an extra lookup per
+    /// entry.
+    pub fn checks_symlinks(&self) -> bool {
+        self.file_types != 0 && self.file_types & Self::ALL_TYPES != Self::ALL_TYPES
+    }
+
+    /// A symlink counts only as a symlink here, never as the kind of entry it points at.
+    pub fn matches(&self, entry: &FenrisMetadata, is_symlink: bool) -> bool {
+        let kind = if is_symlink {
+            Self::SYMLINKS
+        } else if entry.is_namespace {
+            Self::DIRS
+        } else {
+            Self::FILES
+        };
+        (self.file_types == 0 || self.file_types & kind != 0)
+            && self.min_size.is_none_or(|min| entry.size >= min)
+            && self.max_size.is_none_or(|max| entry.size <= max)
+            && self
+                .name_pattern
+                .as_deref()
+                .is_none_or(|pattern| glob_matches(pattern, &entry.name))
+    }
+}
+
+/// Matches `name` against a pattern of literal characters, `*` and `?`.
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was and how much of `name` it has swallowed so far.
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, swallowed)) => {
+                    p = star + 1;
+                    n = swallowed + 1;
+                    backtrack = Some((star, swallowed + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+impl From<ProtoDirFilter> for DirFilter {
+    fn from(filter: ProtoDirFilter) -> Self {
+        Self {
+            min_size: (filter.min_size != 0).then_some(filter.min_size),
+            max_size: (filter.max_size != 0).then_some(filter.max_size),
+            file_types: filter.file_types,
+            name_pattern: (!filter.name_pattern.is_empty()).then_some(filter.name_pattern),
+        }
+    }
+}
+
+impl From<DirFilter> for ProtoDirFilter {
+    fn from(filter: DirFilter) -> Self {
+        Self {
+            min_size: filter.min_size.unwrap_or(0),
+            max_size: filter.max_size.unwrap_or(0),
+            file_types: filter.file_types,
+            name_pattern: filter.name_pattern.unwrap_or_default(),
+        }
+    }
+}
+
+impl ListSort {
+    const BY_NAME: u8 = 1 << 0;
+    const BY_SIZE: u8 = 1 << 1;
+    const BY_MODIFIED: u8 = 1 << 2;
+    const REVERSE: u8 = 1 << 3;
+    const DIRS_FIRST: u8 = 1 << 4;
+
+    pub fn from_flags(flags: u8) -> Self {
+        let key = if flags & Self::BY_NAME != 0 {
+            Some(ListSortKey::Name)
+        } else if flags & Self::BY_SIZE != 0 {
+            Some(ListSortKey::Size)
+        } else if flags & Self::BY_MODIFIED != 0 {
+            Some(ListSortKey::Modified)
+        } else {
+            None
+        };
+
+        Self {
+            key,
+            reverse: flags & Self::REVERSE != 0,
+            dirs_first: flags & Self::DIRS_FIRST != 0,
+        }
+    }
+
+    pub fn flags(&self) -> u8 {
+        let key = match self.key {
+            Some(ListSortKey::Name) => Self::BY_NAME,
+            Some(ListSortKey::Size) => Self::BY_SIZE,
+            Some(ListSortKey::Modified) => Self::BY_MODIFIED,
+            None => 0,
+        };
+        let reverse = if self.reverse { Self::REVERSE } else { 0 };
+        let dirs_first = if self.dirs_first { Self::DIRS_FIRST } else { 0 };
+        key | reverse | dirs_first
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn apply(&self, entries: &mut [FenrisMetadata]) {
+        match self.key {
+            Some(ListSortKey::Name) => entries.sort_by(|a, b| a.name.cmp(&b.name)),
+            Some(ListSortKey::Size) => entries.sort_by_key(|entry| entry.size),
+            Some(ListSortKey::Modified) => entries.sort_by_key(|entry| entry.modified_time),
+            None => {}
+        }
+        if self.reverse {
+            entries.reverse();
+        }
+        if self.dirs_first {
+            entries.sort_by_key(|entry| !entry.is_namespace);
+        }
+    }
+
+    fn from_data(data: &[u8]) -> Self {
+        data.first()
+            .copied()
+            .map(Self::from_flags)
+            .unwrap_or_default()
+    }
+
+    fn to_data(self) -> Vec<u8> {
+        if self.is_default() {
+            Vec::new()
+        } else {
+            vec![self.flags()]
+        }
+    }
+}
+
+impl fmt::Display for ListSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = match self.key {
+            Some(ListSortKey::Name) => "name",
+            Some(ListSortKey::Size) => "size",
+            Some(ListSortKey::Modified) => "mtime",
+            None => "filesystem order",
+        };
+        write!(f, "{}", key)?;
+        if self.reverse {
+            write!(f, ", reversed")?;
+        }
+        if self.dirs_first {
+            write!(f, ", directories first")?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<ProtoWatchEvent> for WatchEvent {
+    type Error = FenrisError;
+
+    fn try_from(event: ProtoWatchEvent) -> Result<Self, Self::Error> {
+        let kind = match WatchChange::try_from(event.change) {
+            Ok(WatchChange::WatchCreated) => WatchEventKind::Created,
+            Ok(WatchChange::WatchModified) => WatchEventKind::Modified,
+            Ok(WatchChange::WatchDeleted) => WatchEventKind::Deleted,
+            Ok(WatchChange::Unspecified) | Err(_) => {
+                return Err(FenrisError::InvalidProtocolMessage);
+            }
+        };
+
+        Ok(Self {
+            path: PathBuf::from(event.path),
+            kind,
+            subscription: PathBuf::from(event.subscription),
+        })
+    }
+}
+
+impl From<WatchEvent> for ProtoWatchEvent {
+    fn from(event: WatchEvent) -> Self {
+        let change = match event.kind {
+            WatchEventKind::Created => WatchChange::WatchCreated,
+            WatchEventKind::Modified => WatchChange::WatchModified,
+            WatchEventKind::Deleted => WatchChange::WatchDeleted,
+        };
+
+        Self {
+            path: event.path.to_string_lossy().to_string(),
+            change: change as i32,
+            subscription: event.subscription.to_string_lossy().to_string(),
+        }
+    }
+}
+
 impl From<FileMetadata> for FenrisMetadata {
     fn from(metadata: FileMetadata) -> Self {
         Self {
@@ -454,6 +1536,7 @@ impl From<FileMetadata> for FenrisMetadata {
             size: metadata.size,
             is_namespace: metadata.is_directory,
             modified_time: metadata.modified_time,
+            created_time: metadata.created_time,
             permissions: metadata.permissions,
         }
     }
@@ -466,6 +1549,7 @@ impl From<FileInfo> for FenrisMetadata {
             size: info.size,
             is_namespace: info.is_directory,
             modified_time: info.modified_time,
+            created_time: info.created_time,
             permissions: info.permissions,
         }
     }
@@ -478,7 +1562,9 @@ impl From<FenrisMetadata> for FileInfo {
             size: metadata.size,
             is_directory: metadata.is_namespace,
             modified_time: metadata.modified_time,
+            created_time: metadata.created_time,
             permissions: metadata.permissions,
+            mime_type: None,
         }
     }
 }
@@ -496,6 +1582,7 @@ fn response(
         error_message,
         data,
         details,
+        request_id: 0,
     }
 }
 
@@ -508,88 +1595,424 @@ mod tests {
     fn protobuf_request_decodes_into_domain_commands() {
         let cases = [
             (
-                request(RequestType::Ping, PathBuf::new(), Vec::new()),
-                FenrisCommand::Ping,
+                request(RequestType::Ping, PathBuf::new(), Vec::new()),
+                FenrisCommand::Ping,
+            ),
+            (
+                request(RequestType::CreateFile, PathBuf::from("a.txt"), Vec::new()),
+                FenrisCommand::CreateObject {
+                    path: PathBuf::from("a.txt"),
+                },
+            ),
+            (
+                request(RequestType::ReadFile, PathBuf::from("a.txt"), Vec::new()),
+                FenrisCommand::ReadObject {
+                    path: PathBuf::from("a.txt"),
+                },
+            ),
+            (
+                request(
+                    RequestType::WriteFile,
+                    PathBuf::from("a.txt"),
+                    b"data".to_vec(),
+                ),
+                FenrisCommand::WriteObject {
+                    path: PathBuf::from("a.txt"),
+                    data: b"data".to_vec(),
+                },
+            ),
+            (
+                request(
+                    RequestType::AppendFile,
+                    PathBuf::from("a.txt"),
+                    b"more".to_vec(),
+                ),
+                FenrisCommand::AppendObject {
+                    path: PathBuf::from("a.txt"),
+                    data: b"more".to_vec(),
+                },
+            ),
+            (
+                request(RequestType::DeleteFile, PathBuf::from("a.txt"), Vec::new()),
+                FenrisCommand::DeleteObject {
+                    path: PathBuf::from("a.txt"),
+                },
+            ),
+            (
+                request(RequestType::InfoFile, PathBuf::from("a.txt"), Vec::new()),
+                FenrisCommand::ObjectInfo {
+                    path: PathBuf::from("a.txt"),
+                    detect_mime: false,
+                },
+            ),
+            (
+                request(RequestType::InfoFile, PathBuf::from("a.txt"), vec![0x01]),
+                FenrisCommand::ObjectInfo {
+                    path: PathBuf::from("a.txt"),
+                    detect_mime: true,
+                },
+            ),
+            (
+                request(RequestType::CreateDir, PathBuf::from("dir"), Vec::new()),
+                FenrisCommand::CreateNamespace {
+                    path: PathBuf::from("dir"),
+                },
+            ),
+            (
+                request(RequestType::CreateDirAll, PathBuf::from("a/b"), Vec::new()),
+                FenrisCommand::CreateNamespaceAll {
+                    path: PathBuf::from("a/b"),
+                },
+            ),
+            (
+                request(RequestType::ListDir, PathBuf::from("dir"), Vec::new()),
+                FenrisCommand::ListNamespace {
+                    path: PathBuf::from("dir"),
+                    sort: ListSort::default(),
+                },
+            ),
+            (
+                request(RequestType::ListDir, PathBuf::from("dir"), vec![0b1_0101]),
+                FenrisCommand::ListNamespace {
+                    path: PathBuf::from("dir"),
+                    sort: ListSort {
+                        key: Some(ListSortKey::Name),
+                        reverse: false,
+                        dirs_first: true,
+                    },
+                },
+            ),
+            (
+                request(RequestType::ChangeDir, PathBuf::from("dir"), Vec::new()),
+                FenrisCommand::ChangeNamespace {
+                    path: PathBuf::from("dir"),
+                },
+            ),
+            (
+                request(RequestType::DeleteDir, PathBuf::from("dir"), Vec::new()),
+                FenrisCommand::DeleteNamespace {
+                    path: PathBuf::from("dir"),
+                },
+            ),
+            (
+                request(
+                    RequestType::UploadFile,
+                    PathBuf::from("a.txt"),
+                    b"upload".to_vec(),
+                ),
+                FenrisCommand::UploadObject {
+                    path: PathBuf::from("a.txt"),
+                    data: b"upload".to_vec(),
+                },
+            ),
+            (
+                request(RequestType::Subscribe, PathBuf::from("dir"), Vec::new()),
+                FenrisCommand::Subscribe {
+                    path: PathBuf::from("dir"),
+                },
+            ),
+            (
+                request(RequestType::Unsubscribe, PathBuf::from("dir"), Vec::new()),
+                FenrisCommand::Unsubscribe {
+                    path: PathBuf::from("dir"),
+                },
+            ),
+            (
+                request(
+                    RequestType::FetchUrl,
+                    PathBuf::from("dl.bin"),
+                    b"https://example.com/file".to_vec(),
+                ),
+                FenrisCommand::FetchUrl {
+                    url: "https://example.com/file".to_string(),
+                    path: PathBuf::from("dl.bin"),
+                },
+            ),
+            (
+                request(
+                    RequestType::DiffFiles,
+                    PathBuf::from("a.conf"),
+                    b"b.conf".to_vec(),
+                ),
+                FenrisCommand::DiffObjects {
+                    left: PathBuf::from("a.conf"),
+                    right: PathBuf::from("b.conf"),
+                },
+            ),
+            (
+                request(
+                    RequestType::ListDirRecursive,
+                    PathBuf::from("dir"),
+                    vec![0, 0, 0, 7],
+                ),
+                FenrisCommand::ListNamespaceRecursive {
+                    path: PathBuf::from("dir"),
+                    max_depth: 7,
+                },
+            ),
+            (
+                request(RequestType::Checksum, PathBuf::from("a.txt"), Vec::new()),
+                FenrisCommand::ChecksumObject {
+                    path: PathBuf::from("a.txt"),
+                },
+            ),
+            (
+                request(
+                    RequestType::SendBroadcast,
+                    PathBuf::new(),
+                    b"maintenance at 5".to_vec(),
+                ),
+                FenrisCommand::Broadcast {
+                    message: "maintenance at 5".to_string(),
+                },
+            ),
+            (
+                request(
+                    RequestType::Echo,
+                    PathBuf::new(),
+                    vec![0, 0xff, 0xc3, b'\n'],
+                ),
+                FenrisCommand::Echo {
+                    payload: vec![0, 0xff, 0xc3, b'\n'],
+                },
+            ),
+            (
+                request(
+                    RequestType::Login,
+                    PathBuf::from("alice"),
+                    b"correct horse".to_vec(),
+                ),
+                FenrisCommand::Login {
+                    username: "alice".to_string(),
+                    password: "correct horse".to_string(),
+                },
+            ),
+            (
+                request(RequestType::Compress, PathBuf::from("app.log"), Vec::new()),
+                FenrisCommand::CompressObject {
+                    path: PathBuf::from("app.log"),
+                    extension: None,
+                },
+            ),
+            (
+                request(
+                    RequestType::Decompress,
+                    PathBuf::from("app.log.gz"),
+                    b"gz".to_vec(),
+                ),
+                FenrisCommand::DecompressObject {
+                    path: PathBuf::from("app.log.gz"),
+                    extension: Some("gz".to_string()),
+                },
+            ),
+            (
+                request(
+                    RequestType::Head,
+                    PathBuf::from("app.log"),
+                    vec![0, 0, 0, 10],
+                ),
+                FenrisCommand::HeadObject {
+                    path: PathBuf::from("app.log"),
+                    lines: 10,
+                },
+            ),
+            (
+                request(
+                    RequestType::Tail,
+                    PathBuf::from("app.log"),
+                    vec![0, 0, 0, 0, 0, 0, 16, 0],
+                ),
+                FenrisCommand::TailObject {
+                    path: PathBuf::from("app.log"),
+                    bytes: 4096,
+                },
+            ),
+            (
+                request(RequestType::Quota, PathBuf::new(), Vec::new()),
+                FenrisCommand::Quota { limit: None },
+            ),
+            (
+                request(
+                    RequestType::Quota,
+                    PathBuf::new(),
+                    vec![0, 0, 0, 0, 0, 0x10, 0, 0],
+                ),
+                FenrisCommand::Quota {
+                    limit: Some(1 << 20),
+                },
+            ),
+            (
+                request(
+                    RequestType::SetMtime,
+                    PathBuf::from("restored.txt"),
+                    vec![0, 0, 0, 0, 0x65, 0x53, 0xf1, 0x00],
+                ),
+                FenrisCommand::SetMtime {
+                    path: PathBuf::from("restored.txt"),
+                    mtime: 1_700_000_000,
+                },
             ),
             (
-                request(RequestType::CreateFile, PathBuf::from("a.txt"), Vec::new()),
-                FenrisCommand::CreateObject {
-                    path: PathBuf::from("a.txt"),
+                request(
+                    RequestType::Symlink,
+                    PathBuf::from("latest"),
+                    b"releases/v2".to_vec(),
+                ),
+                FenrisCommand::CreateSymlink {
+                    target: PathBuf::from("releases/v2"),
+                    link: PathBuf::from("latest"),
                 },
             ),
             (
-                request(RequestType::ReadFile, PathBuf::from("a.txt"), Vec::new()),
-                FenrisCommand::ReadObject {
-                    path: PathBuf::from("a.txt"),
+                request(
+                    RequestType::JunctionDir,
+                    PathBuf::from("current"),
+                    b"releases/v2".to_vec(),
+                ),
+                FenrisCommand::CreateDirJunction {
+                    target: PathBuf::from("releases/v2"),
+                    link: PathBuf::from("current"),
                 },
             ),
             (
                 request(
-                    RequestType::WriteFile,
-                    PathBuf::from("a.txt"),
-                    b"data".to_vec(),
+                    RequestType::ListDirWithFilter,
+                    PathBuf::from("logs"),
+                    ProtoDirFilter {
+                        min_size: 1024,
+                        max_size: 0,
+                        file_types: DirFilter::FILES,
+                        name_pattern: "*.log".to_string(),
+                    }
+                    .encode_to_vec(),
                 ),
-                FenrisCommand::WriteObject {
-                    path: PathBuf::from("a.txt"),
-                    data: b"data".to_vec(),
+                FenrisCommand::ListNamespaceFiltered {
+                    path: PathBuf::from("logs"),
+                    filter: DirFilter {
+                        min_size: Some(1024),
+                        max_size: None,
+                        file_types: DirFilter::FILES,
+                        name_pattern: Some("*.log".to_string()),
+                    },
                 },
             ),
             (
                 request(
-                    RequestType::AppendFile,
-                    PathBuf::from("a.txt"),
-                    b"more".to_vec(),
+                    RequestType::GetAttr,
+                    PathBuf::from("photo.jpg"),
+                    b"user.origin".to_vec(),
                 ),
-                FenrisCommand::AppendObject {
-                    path: PathBuf::from("a.txt"),
-                    data: b"more".to_vec(),
+                FenrisCommand::GetXattr {
+                    path: PathBuf::from("photo.jpg"),
+                    name: "user.origin".to_string(),
                 },
             ),
             (
-                request(RequestType::DeleteFile, PathBuf::from("a.txt"), Vec::new()),
-                FenrisCommand::DeleteObject {
-                    path: PathBuf::from("a.txt"),
+                request(
+                    RequestType::SetAttr,
+                    PathBuf::from("photo.jpg"),
+                    b"user.origin\0camera\x001".to_vec(),
+                ),
+                FenrisCommand::SetXattr {
+                    path: PathBuf::from("photo.jpg"),
+                    name: "user.origin".to_string(),
+                    value: b"camera\x001".to_vec(),
                 },
             ),
             (
-                request(RequestType::InfoFile, PathBuf::from("a.txt"), Vec::new()),
-                FenrisCommand::ObjectInfo {
-                    path: PathBuf::from("a.txt"),
+                request(RequestType::GetEnv, PathBuf::from("BUILD_ID"), Vec::new()),
+                FenrisCommand::GetEnv {
+                    name: "BUILD_ID".to_string(),
                 },
             ),
             (
-                request(RequestType::CreateDir, PathBuf::from("dir"), Vec::new()),
-                FenrisCommand::CreateNamespace {
-                    path: PathBuf::from("dir"),
+                request(
+                    RequestType::SetEnv,
+                    PathBuf::from("BUILD_ID"),
+                    b"r-1042".to_vec(),
+                ),
+                FenrisCommand::SetEnv {
+                    name: "BUILD_ID".to_string(),
+                    value: "r-1042".to_string(),
                 },
             ),
             (
-                request(RequestType::ListDir, PathBuf::from("dir"), Vec::new()),
-                FenrisCommand::ListNamespace {
-                    path: PathBuf::from("dir"),
+                request(
+                    RequestType::ChecksumManifest,
+                    PathBuf::from("photos"),
+                    Vec::new(),
+                ),
+                FenrisCommand::ChecksumManifest {
+                    path: PathBuf::from("photos"),
                 },
             ),
             (
-                request(RequestType::ChangeDir, PathBuf::from("dir"), Vec::new()),
-                FenrisCommand::ChangeNamespace {
-                    path: PathBuf::from("dir"),
+                request(
+                    RequestType::Tar,
+                    PathBuf::from("project"),
+                    b"backups/project.tar.gz".to_vec(),
+                ),
+                FenrisCommand::CreateArchive {
+                    dir: PathBuf::from("project"),
+                    archive: Some(PathBuf::from("backups/project.tar.gz")),
                 },
             ),
             (
-                request(RequestType::DeleteDir, PathBuf::from("dir"), Vec::new()),
-                FenrisCommand::DeleteNamespace {
-                    path: PathBuf::from("dir"),
+                request(
+                    RequestType::Untar,
+                    PathBuf::from("project.tar.gz"),
+                    Vec::new(),
+                ),
+                FenrisCommand::ExtractArchive {
+                    archive: PathBuf::from("project.tar.gz"),
+                    dest: None,
                 },
             ),
             (
                 request(
-                    RequestType::UploadFile,
-                    PathBuf::from("a.txt"),
-                    b"upload".to_vec(),
+                    RequestType::ReadSymlink,
+                    PathBuf::from("latest"),
+                    Vec::new(),
                 ),
-                FenrisCommand::UploadObject {
-                    path: PathBuf::from("a.txt"),
-                    data: b"upload".to_vec(),
+                FenrisCommand::ReadSymlink {
+                    link: PathBuf::from("latest"),
+                },
+            ),
+            (
+                request(
+                    RequestType::RenameFile,
+                    PathBuf::from("draft.txt"),
+                    b"archive/final.txt".to_vec(),
+                ),
+                FenrisCommand::MoveObject {
+                    from: PathBuf::from("draft.txt"),
+                    to: PathBuf::from("archive/final.txt"),
+                },
+            ),
+            (
+                Request {
+                    timeout_ms: 250,
+                    ..request(RequestType::ReadFile, PathBuf::from("slow.bin"), Vec::new())
+                },
+                FenrisCommand::Timed {
+                    timeout: Duration::from_millis(250),
+                    command: Box::new(FenrisCommand::ReadObject {
+                        path: PathBuf::from("slow.bin"),
+                    }),
+                },
+            ),
+            (
+                Request {
+                    request_id: 7,
+                    timeout_ms: 250,
+                    ..request(RequestType::Ping, PathBuf::new(), Vec::new())
+                },
+                FenrisCommand::Correlated {
+                    request_id: 7,
+                    command: Box::new(FenrisCommand::Timed {
+                        timeout: Duration::from_millis(250),
+                        command: Box::new(FenrisCommand::Ping),
+                    }),
                 },
             ),
             (
@@ -599,6 +2022,7 @@ mod tests {
         ];
 
         for (request, expected) in cases {
+            assert_eq!(expected.request_type(), request.command());
             assert_eq!(FenrisCommand::try_from(request).unwrap(), expected);
         }
     }
@@ -648,6 +2072,7 @@ mod tests {
             size: 4,
             is_namespace: false,
             modified_time: 5,
+            created_time: 3,
             permissions: 0o644,
         };
 
@@ -692,6 +2117,23 @@ mod tests {
                 ),
                 FenrisOutput::ObjectInfo {
                     metadata: metadata.clone(),
+                    mime_type: None,
+                },
+            ),
+            (
+                response(
+                    ResponseType::FileInfo,
+                    true,
+                    String::new(),
+                    vec![],
+                    Some(response::Details::FileInfo(FileInfo {
+                        mime_type: Some("image/png".to_string()),
+                        ..metadata.clone().into()
+                    })),
+                ),
+                FenrisOutput::ObjectInfo {
+                    metadata: metadata.clone(),
+                    mime_type: Some("image/png".to_string()),
                 },
             ),
             (
@@ -702,10 +2144,12 @@ mod tests {
                     vec![],
                     Some(response::Details::DirectoryListing(DirectoryListing {
                         entries: vec![metadata.clone().into()],
+                        relative_paths: Vec::new(),
                     })),
                 ),
                 FenrisOutput::NamespaceListing {
                     entries: vec![metadata],
+                    sort: ListSort::default(),
                 },
             ),
             (
@@ -720,6 +2164,135 @@ mod tests {
                     path: PathBuf::from("/tmp"),
                 },
             ),
+            (
+                response(
+                    ResponseType::FileDiff,
+                    true,
+                    String::new(),
+                    b"-a\n+b\n".to_vec(),
+                    None,
+                ),
+                FenrisOutput::ObjectDiff {
+                    diff: "-a\n+b\n".to_string(),
+                },
+            ),
+            (
+                response(
+                    ResponseType::FileChecksum,
+                    true,
+                    String::new(),
+                    vec![0xab; 32],
+                    None,
+                ),
+                FenrisOutput::ObjectChecksum { digest: [0xab; 32] },
+            ),
+            (
+                response(
+                    ResponseType::Manifest,
+                    true,
+                    String::new(),
+                    vec![],
+                    Some(response::Details::Manifest(ManifestResponse {
+                        entries: vec![ManifestEntry {
+                            relative_path: "2024/beach.jpg".to_string(),
+                            sha256: vec![0xcd; 32],
+                        }],
+                        truncated: true,
+                    })),
+                ),
+                FenrisOutput::ChecksumManifest {
+                    entries: vec![(PathBuf::from("2024/beach.jpg"), [0xcd; 32])],
+                    truncated: true,
+                },
+            ),
+            (
+                response(
+                    ResponseType::Broadcast,
+                    true,
+                    String::new(),
+                    b"restarting".to_vec(),
+                    None,
+                ),
+                FenrisOutput::Broadcast {
+                    message: "restarting".to_string(),
+                },
+            ),
+            (
+                response(
+                    ResponseType::Banner,
+                    true,
+                    String::new(),
+                    b"Maintenance at 02:00 UTC".to_vec(),
+                    None,
+                ),
+                FenrisOutput::Banner {
+                    message: "Maintenance at 02:00 UTC".to_string(),
+                },
+            ),
+            (
+                response(
+                    ResponseType::EchoReply,
+                    true,
+                    String::new(),
+                    vec![0, 0xfe, 0x80],
+                    None,
+                ),
+                FenrisOutput::Echo {
+                    payload: vec![0, 0xfe, 0x80],
+                },
+            ),
+            (
+                response(
+                    ResponseType::QuotaInfo,
+                    true,
+                    String::new(),
+                    vec![],
+                    Some(response::Details::QuotaInfo(QuotaInfo {
+                        used_bytes: 512,
+                        limit_bytes: 0,
+                    })),
+                ),
+                FenrisOutput::QuotaInfo {
+                    used_bytes: 512,
+                    limit_bytes: None,
+                },
+            ),
+            (
+                response(
+                    ResponseType::AttrValue,
+                    true,
+                    String::new(),
+                    vec![0xff, 0],
+                    None,
+                ),
+                FenrisOutput::XattrValue {
+                    value: vec![0xff, 0],
+                },
+            ),
+            (
+                response(
+                    ResponseType::EnvValue,
+                    true,
+                    String::new(),
+                    b"r-1042".to_vec(),
+                    None,
+                ),
+                FenrisOutput::EnvValue {
+                    value: "r-1042".to_string(),
+                },
+            ),
+            (
+                response(
+                    ResponseType::SymlinkTarget,
+                    true,
+                    String::new(),
+                    b"../shared/data".to_vec(),
+                    None,
+                ),
+                FenrisOutput::SymlinkTarget {
+                    target: PathBuf::from("../shared/data"),
+                },
+            ),
             (
                 response(ResponseType::Terminated, true, String::new(), vec![], None),
                 FenrisOutput::Terminated,
@@ -730,6 +2303,18 @@ mod tests {
                     message: "nope".to_string(),
                 },
             ),
+            (
+                Response {
+                    request_id: 7,
+                    ..response(ResponseType::Error, false, "nope".to_string(), vec![], None)
+                },
+                FenrisOutput::Correlated {
+                    request_id: 7,
+                    output: Box::new(FenrisOutput::Error {
+                        message: "nope".to_string(),
+                    }),
+                },
+            ),
         ];
 
         for (response, expected) in cases {
@@ -744,11 +2329,13 @@ mod tests {
             size: 0,
             is_namespace: true,
             modified_time: 7,
+            created_time: 0,
             permissions: 0o755,
         };
 
         let output = FenrisOutput::NamespaceListing {
             entries: vec![metadata.clone()],
+            sort: ListSort::default(),
         };
         let response = Response::from(output);
         assert_eq!(response.r#type, ResponseType::DirListing as i32);
@@ -765,7 +2352,10 @@ mod tests {
         assert!(!response.success);
         assert_eq!(response.error_message, "bad");
 
-        let response = Response::from(FenrisOutput::ObjectInfo { metadata });
+        let response = Response::from(FenrisOutput::ObjectInfo {
+            metadata,
+            mime_type: None,
+        });
         assert_eq!(response.r#type, ResponseType::FileInfo as i32);
         assert!(matches!(
             response.details,
@@ -832,6 +2422,108 @@ mod tests {
         );
     }
 
+    #[test]
+    fn glob_patterns_match_wildcards() {
+        assert!(glob_matches("*.log", "server.log"));
+        assert!(glob_matches("a?c", "abc"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("a*b*c", "axxbyyc"));
+        assert!(!glob_matches("*.log", "server.txt"));
+        assert!(!glob_matches("a?c", "ac"));
+        assert!(!glob_matches("abc", "abcd"));
+    }
+
+    #[test]
+    fn dir_filter_applies_every_condition() {
+        let entry = |name: &str, size, is_namespace| FenrisMetadata {
+            name: name.to_string(),
+            size,
+            is_namespace,
+            modified_time: 0,
+            created_time: 0,
+            permissions: 0,
+        };
+        let filter = DirFilter {
+            min_size: Some(1024),
+            max_size: Some(4096),
+            file_types: DirFilter::FILES,
+            name_pattern: Some("*.bin".to_string()),
+        };
+
+        assert!(filter.matches(&entry("big.bin", 2048, false), false));
+        assert!(!filter.matches(&entry("small.bin", 100, false), false));
+        assert!(!filter.matches(&entry("huge.bin", 8192, false), false));
+        assert!(!filter.matches(&entry("big.txt", 2048, false), false));
+        assert!(!filter.matches(&entry("dir.bin", 2048, true), false));
+        assert!(!filter.matches(&entry("link.bin", 2048, false), true));
+        assert!(DirFilter::default().matches(&entry("anything", 0, true), true));
+    }
+
+    #[test]
+    fn watch_event_output_maps_to_protobuf_details() {
+        let event = WatchEvent {
+            path: PathBuf::from("/docs/a.txt"),
+            kind: WatchEventKind::Deleted,
+            subscription: PathBuf::from("/docs"),
+        };
+
+        let response = Response::from(FenrisOutput::WatchEvent(event.clone()));
+        assert_eq!(response.r#type, ResponseType::WatchEvent as i32);
+        assert!(matches!(
+            response.details,
+            Some(response::Details::WatchEvent(_))
+        ));
+        assert_eq!(
+            FenrisOutput::try_from(response).unwrap(),
+            FenrisOutput::WatchEvent(event)
+        );
+    }
+
+    #[test]
+    fn recursive_listing_output_round_trips_relative_paths() {
+        let metadata = FenrisMetadata {
+            name: "a.txt".to_string(),
+            size: 1,
+            is_namespace: false,
+            modified_time: 0,
+            created_time: 0,
+            permissions: 0o644,
+        };
+        let output = FenrisOutput::RecursiveNamespaceListing {
+            entries: vec![(PathBuf::from("docs/a.txt"), metadata)],
+        };
+
+        let response = Response::from(output.clone());
+        assert_eq!(response.r#type, ResponseType::RecursiveDirListing as i32);
+        assert_eq!(FenrisOutput::try_from(response).unwrap(), output);
+
+        let short = request(RequestType::ListDirRecursive, PathBuf::from("dir"), vec![1]);
+        assert!(FenrisCommand::try_from(short).is_err());
+
+        let tail = request(RequestType::Tail, PathBuf::from("a.log"), vec![0, 0, 0, 1]);
+        assert!(FenrisCommand::try_from(tail).is_err());
+    }
+
+    #[test]
+    fn checksum_output_requires_full_digest() {
+        let output = FenrisOutput::ObjectChecksum { digest: [7; 32] };
+        let encoded = Response::from(output.clone());
+        assert_eq!(encoded.r#type, ResponseType::FileChecksum as i32);
+        assert_eq!(FenrisOutput::try_from(encoded).unwrap(), output);
+
+        let short = response(
+            ResponseType::FileChecksum,
+            true,
+            String::new(),
+            vec![7; 31],
+            None,
+        );
+        assert!(matches!(
+            FenrisOutput::try_from(short),
+            Err(FenrisError::SerializationError { .. })
+        ));
+    }
+
     #[test]
     fn invalid_transfer_details_are_rejected() {
         let request = request_with_details(
@@ -856,6 +2548,12 @@ mod tests {
             Err(FenrisError::InvalidProtocolMessage)
         ));
 
+        let quota = request_with_details(RequestType::Quota, PathBuf::new(), vec![0; 4], None);
+        assert!(matches!(
+            FenrisCommand::try_from(quota),
+            Err(FenrisError::InvalidProtocolMessage)
+        ));
+
         let response = response(
             ResponseType::FileContentChunk,
             true,
@@ -865,7 +2563,7 @@ mod tests {
         );
         assert!(matches!(
             FenrisOutput::try_from(response),
-            Err(FenrisError::SerializationError(_))
+            Err(FenrisError::SerializationError { .. })
         ));
     }
 
@@ -894,6 +2592,82 @@ mod tests {
         assert_eq!(decoded, output);
     }
 
+    #[test]
+    fn multi_op_round_trips_its_commands_and_outputs() {
+        for fail_fast in [false, true] {
+            let command = FenrisCommand::MultiOp {
+                commands: vec![
+                    FenrisCommand::WriteObject {
+                        path: PathBuf::from("a.txt"),
+                        data: b"payload".to_vec(),
+                    },
+                    FenrisCommand::ReadObject {
+                        path: PathBuf::from("a.txt"),
+                    },
+                    FenrisCommand::DeleteObject {
+                        path: PathBuf::from("a.txt"),
+                    },
+                ],
+                fail_fast,
+            };
+
+            let encoded = ProtobufCodec::encode(&command).unwrap();
+            let decoded: FenrisCommand = ProtobufCodec::decode(&encoded).unwrap();
+            assert_eq!(decoded, command);
+        }
+
+        let output = FenrisOutput::MultiOp {
+            outputs: vec![
+                FenrisOutput::Success {
+                    message: "written".to_string(),
+                },
+                FenrisOutput::Error {
+                    message: "nope".to_string(),
+                },
+            ],
+        };
+        let encoded = ProtobufCodec::encode(&output).unwrap();
+        let decoded: FenrisOutput = ProtobufCodec::decode(&encoded).unwrap();
+        assert_eq!(decoded, output);
+
+        let truncated = request(RequestType::MultiOp, PathBuf::new(), vec![10, 1]);
+        assert!(FenrisCommand::try_from(truncated).is_err());
+    }
+
+    #[test]
+    fn deeply_nested_multi_op_is_rejected() {
+        // Built bytewise; encoding a nested `FenrisCommand` this deep would recurse too.
+        let mut nested = request(RequestType::Ping, PathBuf::new(), Vec::new());
+        for _ in 0..10_000 {
+            nested = request(
+                RequestType::MultiOp,
+                PathBuf::new(),
+                nested.encode_length_delimited_to_vec(),
+            );
+        }
+
+        assert!(matches!(
+            FenrisCommand::try_from(nested),
+            Err(FenrisError::InvalidProtocolMessage)
+        ));
+
+        let timed = FenrisCommand::MultiOp {
+            commands: vec![FenrisCommand::Timed {
+                timeout: Duration::from_secs(1),
+                command: Box::new(FenrisCommand::MultiOp {
+                    commands: vec![FenrisCommand::Ping],
+                    fail_fast: false,
+                }),
+            }],
+            fail_fast: false,
+        };
+
+        assert!(matches!(
+            FenrisCommand::try_from(Request::from(timed)),
+            Err(FenrisError::InvalidProtocolMessage)
+        ));
+    }
+
     #[test]
     fn invalid_request_and_response_types_are_rejected() {
         let request = Request {
@@ -902,6 +2676,8 @@ mod tests {
             ip_addr: 0,
             data: vec![],
             details: None,
+            timeout_ms: 0,
+            request_id: 0,
         };
         assert!(matches!(
             FenrisCommand::try_from(request),
@@ -914,10 +2690,21 @@ mod tests {
             error_message: String::new(),
             data: vec![],
             details: None,
+            request_id: 0,
         };
         assert!(matches!(
             FenrisOutput::try_from(response),
             Err(FenrisError::InvalidProtocolMessage)
         ));
     }
+
+    #[test]
+    fn env_names_follow_shell_rules() {
+        for name in ["HOME", "_private", "build_2"] {
+            assert!(is_valid_env_name(name), "{}", name);
+        }
+        for name in ["", "2fast", "with-dash", "has space", "ÜBER"] {
+            assert!(!is_valid_env_name(name), "{}", name);
+        }
+    }
 }
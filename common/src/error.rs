@@ -1,9 +1,16 @@
 use thiserror::Error;
 
+/// The underlying error kept as a variant's `source()`.
+pub type ErrorCause = Box<dyn std::error::Error + Send + Sync>;
+
 #[derive(Error, Debug)]
 pub enum FenrisError {
-    #[error("Encryption error: {0}")]
-    EncryptionError(String),
+    #[error("Encryption error: {message}")]
+    EncryptionError {
+        message: String,
+        #[source]
+        cause: Option<ErrorCause>,
+    },
 
     #[error("Decryption error: {0}")]
     DecryptionError(String),
@@ -14,8 +21,12 @@ pub enum FenrisError {
     #[error("Invalid IV size: expected {expected}, got {got}")]
     InvalidIvSize { expected: usize, got: usize },
 
-    #[error("Compression error: {0}")]
-    CompressionError(String),
+    #[error("Compression error: {message}")]
+    CompressionError {
+        message: String,
+        #[source]
+        cause: Option<ErrorCause>,
+    },
 
     #[error("Decompression error: {0}")]
     DecompressionError(String),
@@ -29,30 +40,117 @@ pub enum FenrisError {
     #[error("Invalid frame: {0}")]
     InvalidFrame(String),
 
+    #[error("Checksum mismatch: expected {expected:#010x}, got {got:#010x}")]
+    ChecksumMismatch { expected: u32, got: u32 },
+
     #[error("Connection closed")]
     ConnectionClosed,
 
     #[error("Invalid protocol message")]
     InvalidProtocolMessage,
 
+    #[error("Incompatible protocol version: client {client}, server {server}")]
+    IncompatibleProtocolVersion { client: u8, server: u8 },
+
+    #[error("Incompatible handshake extension: {0}")]
+    IncompatibleHandshakeExtension(String),
+
     #[error("Authentication error: {0}")]
     AuthenticationError(String),
 
+    #[error("Authentication failed")]
+    AuthenticationFailed,
+
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
 
     #[error("Missing required field: {0}")]
     MissingField(String),
 
-    #[error("File operation failed: {0}")]
-    FileOperationError(String),
+    #[error("File operation failed: {message}")]
+    FileOperationError {
+        message: String,
+        #[source]
+        cause: Option<ErrorCause>,
+    },
 
-    #[error("Serialization error: {0}")]
-    SerializationError(String),
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("request timeout after {}ms", .0.as_millis())]
+    RequestTimeout(std::time::Duration),
+
+    #[error("Storage quota exceeded: {required} bytes required, quota is {quota} bytes")]
+    StorageQuotaExceeded { quota: u64, required: u64 },
+
+    #[error("Serialization error: {message}")]
+    SerializationError {
+        message: String,
+        #[source]
+        cause: Option<ErrorCause>,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, FenrisError>;
 
+impl FenrisError {
+    pub fn encryption(message: impl Into<String>) -> Self {
+        Self::EncryptionError {
+            message: message.into(),
+            cause: None,
+        }
+    }
+
+    pub fn encryption_from(message: impl Into<String>, cause: impl Into<ErrorCause>) -> Self {
+        Self::EncryptionError {
+            message: message.into(),
+            cause: Some(cause.into()),
+        }
+    }
+
+    pub fn compression(message: impl Into<String>) -> Self {
+        Self::CompressionError {
+            message: message.into(),
+            cause: None,
+        }
+    }
+
+    pub fn compression_from(message: impl Into<String>, cause: impl Into<ErrorCause>) -> Self {
+        Self::CompressionError {
+            message: message.into(),
+            cause: Some(cause.into()),
+        }
+    }
+
+    pub fn file_operation(message: impl Into<String>) -> Self {
+        Self::FileOperationError {
+            message: message.into(),
+            cause: None,
+        }
+    }
+
+    pub fn file_operation_from(message: impl Into<String>, cause: impl Into<ErrorCause>) -> Self {
+        Self::FileOperationError {
+            message: message.into(),
+            cause: Some(cause.into()),
+        }
+    }
+
+    pub fn serialization(message: impl Into<String>) -> Self {
+        Self::SerializationError {
+            message: message.into(),
+            cause: None,
+        }
+    }
+
+    pub fn serialization_from(message: impl Into<String>, cause: impl Into<ErrorCause>) -> Self {
+        Self::SerializationError {
+            message: message.into(),
+            cause: Some(cause.into()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -88,4 +186,31 @@ mod tests {
             _ => panic!("Wrong error variant"),
         }
     }
+
+    #[test]
+    fn test_wrapped_errors_keep_their_cause() {
+        use prost::Message;
+        use std::error::Error;
+        use std::io;
+
+        let decode_err = crate::Request::decode(&[0xff][..]).unwrap_err();
+        let errors = [
+            FenrisError::file_operation_from(
+                "open failed",
+                io::Error::new(io::ErrorKind::NotFound, "missing"),
+            ),
+            FenrisError::encryption_from("bad key", io::Error::other("key")),
+            FenrisError::compression_from(
+                "inflate failed",
+                io::Error::new(io::ErrorKind::InvalidData, "corrupt deflate stream"),
+            ),
+            FenrisError::serialization_from("decode failed", decode_err),
+        ];
+
+        for err in &errors {
+            assert!(err.source().is_some(), "{err} lost its cause");
+        }
+        assert_eq!(errors[0].to_string(), "File operation failed: open failed");
+        assert!(FenrisError::file_operation("plain").source().is_none());
+    }
 }
@@ -1,22 +1,86 @@
+use crate::archive;
+use crate::compression::Compressor;
 use crate::error::{FenrisError, Result};
+use crate::file_watch::{self, FileChangeEvent};
+use crate::quota::{ANONYMOUS_ACCOUNT, QuotaManager, QuotaUsage};
+use dashmap::DashMap;
+use sha2::{Digest, Sha256};
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::task::{Context, Poll, ready};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::fs;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter, ReadBuf};
+use tokio::sync::{Mutex, OwnedMutexGuard, mpsc};
 use tracing::{debug, warn};
 
+pub const MAX_LIST_DEPTH: u32 = 20;
+
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Names starting with this are kept by the server for itself and hidden from clients.
+pub const SYSTEM_FILE_PREFIX: &str = ".fenris_";
+
+/// JSON-lines record of modifications, kept in the base directory when auditing is enabled.
+pub const AUDIT_LOG_FILE: &str = ".fenris_audit.log";
+
+const CHECKSUM_BUFFER_SIZE: usize = 64 * 1024;
+const TRANSCODE_PIPE_SIZE: usize = 64 * 1024;
+
+type LockTable = Arc<DashMap<PathBuf, Arc<Mutex<()>>>>;
+
+/// Advisory per-path lock held for the duration of a mutating file operation.
+/// Only serializes operations within this process.
+struct PathLock {
+    locks: LockTable,
+    path: PathBuf,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for PathLock {
+    fn drop(&mut self) {
+        drop(self.guard.take());
+        remove_unused_lock(&self.locks, &self.path);
+    }
+}
+
+fn remove_unused_lock(locks: &LockTable, path: &Path) {
+    locks.remove_if(path, |_, lock| Arc::strong_count(lock) == 1);
+}
+
+pub(crate) fn is_system_file_name(name: &OsStr) -> bool {
+    name.as_encoded_bytes()
+        .starts_with(SYSTEM_FILE_PREFIX.as_bytes())
+}
+
+pub(crate) fn capped_list_depth(max_depth: u32) -> u32 {
+    if max_depth == 0 {
+        MAX_LIST_DEPTH
+    } else {
+        max_depth.min(MAX_LIST_DEPTH)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct FileMetadata {
     pub name: String,
     pub size: u64,
     pub is_directory: bool,
     pub modified_time: u64,
+    /// Unix seconds, or 0 when the platform or filesystem does not record creation times.
+    pub created_time: u64,
     pub permissions: u32,
+    /// SHA-256 digest; only filled in when explicitly requested since it reads the whole file.
+    pub checksum: Option<[u8; 32]>,
 }
 
 impl FileMetadata {
     pub async fn from_path(path: &Path) -> Result<Self> {
         let metadata = fs::metadata(path).await.map_err(|e| {
-            FenrisError::FileOperationError(format!("Failed to get metadata: {}", e))
+            FenrisError::file_operation_from(format!("Failed to get metadata: {}", e), e)
         })?;
 
         let name = path
@@ -25,12 +89,8 @@ impl FileMetadata {
             .unwrap_or("")
             .to_string();
 
-        let modified_time = metadata
-            .modified()
-            .ok()
-            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
+        let modified_time = unix_seconds(metadata.modified());
+        let created_time = unix_seconds(metadata.created());
 
         #[cfg(unix)]
         let permissions = {
@@ -50,7 +110,9 @@ impl FileMetadata {
             size: metadata.len(),
             is_directory: metadata.is_dir(),
             modified_time,
+            created_time,
             permissions,
+            checksum: None,
         })
     }
 }
@@ -69,12 +131,97 @@ pub trait FileOperations: Send + Sync {
 
     async fn file_info(&self, path: &Path) -> Result<FileMetadata>;
 
+    async fn checksum_file(&self, path: &Path) -> Result<[u8; 32]>;
+
+    /// SHA-256 of the first `limit` files below `root`, by path relative to it in sorted
+    /// order. Walks at most [`MAX_LIST_DEPTH`] levels and leaves symlinks out.
+    async fn checksum_manifest(
+        &self,
+        root: &Path,
+        limit: usize,
+    ) -> Result<Vec<(PathBuf, [u8; 32])>>;
+
+    async fn compress_file(
+        &self,
+        src: &Path,
+        dst: &Path,
+        compressor: &dyn Compressor,
+    ) -> Result<u64> {
+        let compressed = compressor.compress(&self.read_file(src).await?)?;
+        self.write_file(dst, &compressed).await?;
+        Ok(compressed.len() as u64)
+    }
+
+    async fn decompress_file(
+        &self,
+        src: &Path,
+        dst: &Path,
+        compressor: &dyn Compressor,
+    ) -> Result<u64> {
+        let decompressed = compressor.decompress(&self.read_file(src).await?)?;
+        self.write_file(dst, &decompressed).await?;
+        Ok(decompressed.len() as u64)
+    }
+
+    /// `target` is stored verbatim and is not checked against the base directory.
+    async fn create_symlink(&self, link: &Path, target: &Path) -> Result<()>;
+
+    async fn read_symlink(&self, link: &Path) -> Result<PathBuf>;
+
+    /// Links `link` to the directory `target`: a directory link on Windows, where plain
+    /// symlinks cannot point at directories, and an ordinary symlink on Unix.
+    async fn create_dir_junction(&self, link: &Path, target: &Path) -> Result<()>;
+
+    /// Sets the modification time of a file or directory to `mtime` Unix seconds, leaving its
+    /// contents and access time alone. Times in the future are allowed. On Windows the path is
+    /// opened for writing to change the time, so read-only files are rejected there.
+    async fn set_mtime(&self, path: &Path, mtime: u64) -> Result<()>;
+
+    /// Reads the extended attribute `name`, e.g. `user.origin`. Only Linux and macOS have
+    /// xattrs; elsewhere this always fails.
+    async fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>>;
+
+    async fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> Result<()>;
+
+    /// Packs the directory `src` into the gzipped tarball `dst`, with entries rooted at the
+    /// directory's own name. Returns the size of the archive.
+    async fn create_archive(&self, src: &Path, dst: &Path) -> Result<u64>;
+
+    /// Unpacks the gzipped tarball `archive` into the directory `dst`, creating it if needed.
+    /// Entries that would land outside `dst` fail the whole extraction. Returns the number of
+    /// file bytes written.
+    async fn extract_archive(&self, archive: &Path, dst: &Path) -> Result<u64>;
+
+    /// Reports changes to the entries directly inside the directory `path` until the receiver
+    /// is dropped. Each call registers its own watch; it needs a running Tokio runtime. Uses
+    /// inotify on Linux, kqueue on macOS and `ReadDirectoryChangesW` on Windows, and fails
+    /// elsewhere.
+    fn watch_dir(&self, path: &Path) -> Result<mpsc::Receiver<FileChangeEvent>>;
+
+    /// Fails if `path` already exists or its parent does not, like `mkdir`.
     async fn create_dir(&self, path: &Path) -> Result<()>;
 
+    /// Creates `path` and any missing parents. An existing directory is not an error.
+    async fn create_dir_all(&self, path: &Path) -> Result<()>;
+
     async fn list_dir(&self, path: &Path) -> Result<Vec<FileMetadata>>;
 
+    async fn list_dir_recursive(
+        &self,
+        root: &Path,
+        max_depth: u32,
+    ) -> Result<Vec<(PathBuf, FileMetadata)>>;
+
     async fn delete_dir(&self, path: &Path) -> Result<()>;
 
+    /// Renames `src` to `dst`, copying and then deleting when they sit on different
+    /// filesystems.
+    async fn move_file(&self, src: &Path, dst: &Path) -> Result<()>;
+
+    /// Like [`Self::move_file`] for a whole directory tree. The fallback refuses to merge into
+    /// an existing `dst`.
+    async fn move_dir(&self, src: &Path, dst: &Path) -> Result<()>;
+
     async fn exists(&self, path: &Path) -> bool;
 
     async fn is_dir(&self, path: &Path) -> bool;
@@ -82,22 +229,255 @@ pub trait FileOperations: Send + Sync {
     async fn is_file(&self, path: &Path) -> bool;
 }
 
+#[derive(Debug, Clone)]
+struct QuotaAccount {
+    quotas: Arc<QuotaManager>,
+    account: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct DefaultFileOperations {
     base_dir: PathBuf,
+    locks: LockTable,
+    lock_timeout: Duration,
+    quota: Option<QuotaAccount>,
+    usage: Arc<AtomicU64>,
+    symlinks_allowed: bool,
+    audit: Option<Arc<Mutex<()>>>,
 }
 
 impl DefaultFileOperations {
-    pub fn new(base_dir: PathBuf) -> Self {
-        let base_dir = base_dir.canonicalize().unwrap_or(base_dir);
-        Self { base_dir }
+    /// Canonicalizes `base_dir`, which must exist.
+    pub async fn new(base_dir: PathBuf) -> Result<Self> {
+        let base_dir = fs::canonicalize(&base_dir).await.map_err(|e| {
+            FenrisError::file_operation_from(
+                format!("Failed to resolve base dir {}: {}", base_dir.display(), e),
+                e,
+            )
+        })?;
+        Ok(Self::new_unchecked(base_dir))
+    }
+
+    /// Uses `base_dir` as given. Symlink checks compare against it, so it should already be
+    /// canonical.
+    pub fn new_unchecked(base_dir: PathBuf) -> Self {
+        Self {
+            base_dir,
+            locks: Arc::default(),
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            quota: None,
+            usage: Arc::default(),
+            symlinks_allowed: false,
+            audit: None,
+        }
+    }
+
+    /// Charges writes to the anonymous account's limit in `quotas`, read afresh on each write.
+    /// Quota enforcement is advisory: the check and the write are not atomic, so concurrent
+    /// writers may overshoot slightly.
+    pub async fn new_with_quota(base_dir: PathBuf, quotas: Arc<QuotaManager>) -> Result<Self> {
+        let mut file_ops = Self::new(base_dir).await?;
+        let dir = file_ops.base_dir.clone();
+        let usage = tokio::task::spawn_blocking(move || disk_usage(&dir))
+            .await
+            .map_err(|e| {
+                FenrisError::file_operation_from(format!("Failed to size base dir: {}", e), e)
+            })?;
+        file_ops.usage = Arc::new(AtomicU64::new(usage));
+        file_ops.quota = Some(QuotaAccount {
+            quotas,
+            account: ANONYMOUS_ACCOUNT.to_string(),
+        });
+        Ok(file_ops)
+    }
+
+    /// The same settings rooted at `base_dir`, which must exist. Quota usage is measured
+    /// afresh for the new tree.
+    pub async fn rebased(&self, base_dir: PathBuf) -> Result<Self> {
+        let file_ops = match &self.quota {
+            Some(quota) => Self::new_with_quota(base_dir, Arc::clone(&quota.quotas))
+                .await?
+                .with_quota_account(&quota.account),
+            None => Self::new(base_dir).await?,
+        };
+        Ok(file_ops
+            .with_lock_timeout(self.lock_timeout)
+            .with_symlinks_allowed(self.symlinks_allowed)
+            .with_audit_log(self.audit.is_some()))
     }
 
     pub fn with_current_dir() -> Result<Self> {
         let base_dir = std::env::current_dir().map_err(|e| {
-            FenrisError::FileOperationError(format!("Failed to get current dir: {}", e))
+            FenrisError::file_operation_from(format!("Failed to get current dir: {}", e), e)
+        })?;
+        Ok(Self::new_unchecked(base_dir))
+    }
+
+    pub async fn with_current_dir_async() -> Result<Self> {
+        let base_dir = fs::canonicalize(".").await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to get current dir: {}", e), e)
         })?;
-        Ok(Self { base_dir })
+        Ok(Self::new_unchecked(base_dir))
+    }
+
+    pub fn with_lock_timeout(mut self, lock_timeout: Duration) -> Self {
+        self.lock_timeout = lock_timeout;
+        self
+    }
+
+    /// When disabled (the default), any path that passes through a symlink is rejected.
+    pub fn with_symlinks_allowed(mut self, symlinks_allowed: bool) -> Self {
+        self.symlinks_allowed = symlinks_allowed;
+        self
+    }
+
+    /// When enabled, every successful create, write, append, delete and rename is appended
+    /// to [`AUDIT_LOG_FILE`] in the base directory as a JSON line.
+    pub fn with_audit_log(mut self, audit: bool) -> Self {
+        self.audit = audit.then(Arc::default);
+        self
+    }
+
+    /// Charges writes to `account` instead of the anonymous account. No effect without a
+    /// quota manager.
+    pub fn with_quota_account(mut self, account: &str) -> Self {
+        if let Some(quota) = &mut self.quota {
+            quota.account = account.to_string();
+        }
+        self
+    }
+
+    pub fn current_usage(&self) -> u64 {
+        self.usage.load(Ordering::Relaxed)
+    }
+
+    fn quota_limit(&self) -> Option<u64> {
+        self.quota
+            .as_ref()
+            .and_then(|quota| quota.quotas.limit(&quota.account))
+    }
+
+    /// `None` when usage is not being tracked.
+    pub fn quota_usage(&self) -> Option<QuotaUsage> {
+        self.quota.as_ref().map(|_| QuotaUsage {
+            used_bytes: self.current_usage(),
+            limit_bytes: self.quota_limit(),
+        })
+    }
+
+    /// Sets the limit of the account this tree is charged to.
+    pub async fn set_quota_limit(&self, limit: u64) -> Result<()> {
+        match &self.quota {
+            Some(quota) => quota.quotas.set_limit(&quota.account, limit).await,
+            None => Err(FenrisError::InvalidRequest(
+                "quotas are not enabled for this directory".to_string(),
+            )),
+        }
+    }
+
+    /// Bytes that may still be written to a file currently `replaced` bytes long.
+    fn quota_allowance(&self, replaced: u64) -> u64 {
+        match self.quota_limit() {
+            Some(quota) => quota.saturating_sub(self.current_usage().saturating_sub(replaced)),
+            None => u64::MAX,
+        }
+    }
+
+    fn check_quota(&self, replaced: u64, added: u64) -> Result<()> {
+        match self.quota_limit() {
+            Some(quota) if added > self.quota_allowance(replaced) => {
+                Err(FenrisError::StorageQuotaExceeded {
+                    quota,
+                    required: self.current_usage().saturating_sub(replaced) + added,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn record_usage(&self, removed: u64, added: u64) {
+        let _ = self
+            .usage
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |usage| {
+                Some(usage.saturating_sub(removed).saturating_add(added))
+            });
+    }
+
+    /// Appends `{"ts", "op", "path", "bytes"}` to the audit log, plus `to` for renames. The
+    /// operation has already happened, so a failed write is only logged.
+    async fn audit(&self, op: &str, full_path: &Path, to: Option<&Path>, bytes: u64) {
+        let Some(audit) = &self.audit else {
+            return;
+        };
+
+        let relative = |path: &Path| {
+            path.strip_prefix(&self.base_dir)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .into_owned()
+        };
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let mut record = serde_json::json!({
+            "ts": ts,
+            "op": op,
+            "path": relative(full_path),
+            "bytes": bytes,
+        });
+        if let Some(to) = to {
+            record["to"] = relative(to).into();
+        }
+        let mut line = record.to_string();
+        line.push('\n');
+
+        let _guard = audit.lock().await;
+        // Tokio finishes file writes in the background, so the flush is what makes the
+        // record visible before the operation returns.
+        let written = async {
+            let mut log = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.base_dir.join(AUDIT_LOG_FILE))
+                .await?;
+            log.write_all(line.as_bytes()).await?;
+            log.flush().await
+        };
+        if let Err(e) = written.await {
+            warn!("Failed to write audit record for {:?}: {}", full_path, e);
+        }
+    }
+
+    async fn lock_path(&self, full_path: &Path) -> Result<PathLock> {
+        let lock = self
+            .locks
+            .entry(full_path.to_path_buf())
+            .or_default()
+            .clone();
+
+        match tokio::time::timeout(self.lock_timeout, lock.lock_owned()).await {
+            Ok(guard) => Ok(PathLock {
+                locks: self.locks.clone(),
+                path: full_path.to_path_buf(),
+                guard: Some(guard),
+            }),
+            Err(_) => {
+                remove_unused_lock(&self.locks, full_path);
+                Err(FenrisError::file_operation("lock timeout"))
+            }
+        }
+    }
+
+    /// Locks both ends of a move in path order so two opposite moves can't deadlock.
+    async fn lock_pair(&self, first: &Path, second: &Path) -> Result<(PathLock, PathLock)> {
+        if first <= second {
+            let a = self.lock_path(first).await?;
+            Ok((a, self.lock_path(second).await?))
+        } else {
+            let b = self.lock_path(second).await?;
+            Ok((self.lock_path(first).await?, b))
+        }
     }
 
     pub(crate) fn resolve_path(&self, path: &Path) -> Result<PathBuf> {
@@ -112,22 +492,328 @@ impl DefaultFileOperations {
             {
                 return Ok(canonical_parent.join(filename));
             }
-            Err(FenrisError::FileOperationError("Invalid path".to_string()))
+            Err(FenrisError::file_operation("Invalid path"))
         })?;
 
         if !canonical.starts_with(&self.base_dir) {
             warn!("Path traversal attempt: {:?}", path);
-            return Err(FenrisError::FileOperationError(
-                "Path outside base directory".to_string(),
-            ));
+            return Err(FenrisError::file_operation("Path outside base directory"));
+        }
+        if canonical
+            .strip_prefix(&self.base_dir)
+            .unwrap_or(&canonical)
+            .iter()
+            .any(is_system_file_name)
+        {
+            return Err(FenrisError::PermissionDenied(format!(
+                "{} is reserved for the server",
+                path.display()
+            )));
         }
 
+        self.check_symlinks(path)?;
+
         Ok(canonical)
     }
 
+    /// Resolves only the parent of `link`; the last component is never followed, so an
+    /// existing link cannot redirect the operation.
+    fn resolve_link_path(&self, link: &Path) -> Result<PathBuf> {
+        let file_name = link
+            .file_name()
+            .ok_or_else(|| FenrisError::file_operation("Invalid symlink path"))?;
+        let parent = link.parent().unwrap_or(Path::new(""));
+        Ok(self.resolve_path(parent)?.join(file_name))
+    }
+
+    /// New links are refused outright unless symlinks are allowed, since every later path
+    /// through them would be rejected anyway.
+    fn check_link_creation(&self, link: &Path) -> Result<()> {
+        if self.symlinks_allowed {
+            return Ok(());
+        }
+        warn!("Symlink creation rejected: {:?}", link);
+        Err(FenrisError::PermissionDenied(format!(
+            "{}: symlinks are disabled",
+            link.display()
+        )))
+    }
+
+    /// Re-checks each existing component below `base_dir` with `symlink_metadata`, so a link
+    /// swapped in after canonicalisation cannot point the operation outside the sandbox.
+    fn check_symlinks(&self, path: &Path) -> Result<()> {
+        let mut current = self.base_dir.clone();
+
+        for component in path.components() {
+            current.push(component);
+
+            let Ok(metadata) = std::fs::symlink_metadata(&current) else {
+                break;
+            };
+            if !metadata.file_type().is_symlink() {
+                continue;
+            }
+
+            if !self.symlinks_allowed {
+                warn!("Symlink in path rejected: {:?}", current);
+                return Err(FenrisError::PermissionDenied(format!(
+                    "{} is a symlink",
+                    path.display()
+                )));
+            }
+
+            let target = current.canonicalize().map_err(|e| {
+                FenrisError::file_operation_from(format!("Failed to resolve symlink: {}", e), e)
+            })?;
+            if !target.starts_with(&self.base_dir) {
+                warn!(
+                    "Symlink escapes base directory: {:?} -> {:?}",
+                    current, target
+                );
+                return Err(FenrisError::file_operation("Path outside base directory"));
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn base_dir(&self) -> &Path {
         &self.base_dir
     }
+
+    pub async fn read_file_streaming(&self, path: &Path) -> Result<Pin<Box<dyn AsyncRead + Send>>> {
+        let full_path = self.resolve_path(path)?;
+
+        debug!("Opening file for streaming read: {:?}", full_path);
+
+        let file = fs::File::open(&full_path).await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to open file: {}", e), e)
+        })?;
+
+        Ok(Box::pin(BufReader::new(file)))
+    }
+
+    /// Streams `reader` into a hidden temporary file beside `path` and renames it into place
+    /// once the whole stream fits the quota, so a rejected write leaves the old file intact.
+    pub async fn write_file_from_reader(
+        &self,
+        path: &Path,
+        reader: &mut (dyn AsyncRead + Send + Unpin),
+    ) -> Result<u64> {
+        let full_path = self.resolve_path(path)?;
+        let _lock = self.lock_path(&full_path).await?;
+        let replaced = file_size(&full_path).await;
+        let allowance = self.quota_allowance(replaced);
+
+        debug!("Streaming write to {:?}", full_path);
+
+        let parent = full_path.parent().unwrap_or(&self.base_dir);
+        fs::create_dir_all(parent).await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to create parent dirs: {}", e), e)
+        })?;
+        // Dropping `temp_path` on any early return removes the partial file.
+        let (file, temp_path) = create_temp_file(parent)?;
+
+        let mut writer = BufWriter::new(fs::File::from_std(file));
+        let written = tokio::io::copy(&mut reader.take(allowance.saturating_add(1)), &mut writer)
+            .await
+            .map_err(|e| {
+                FenrisError::file_operation_from(format!("Failed to write file: {}", e), e)
+            })?;
+        writer.flush().await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to flush file: {}", e), e)
+        })?;
+        writer.into_inner().sync_all().await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to sync file: {}", e), e)
+        })?;
+
+        self.check_quota(replaced, written)?;
+        temp_path.persist(&full_path).map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to replace file: {}", e), e.error)
+        })?;
+
+        self.record_usage(replaced, written);
+        self.audit("write", &full_path, None, written).await;
+        Ok(written)
+    }
+
+    /// Pipes `src` through the compressor into `dst` so neither file is held in memory. A
+    /// codec error fails the write before it replaces `dst`.
+    async fn transcode_file(
+        &self,
+        src: &Path,
+        dst: &Path,
+        compressor: &dyn Compressor,
+        compress: bool,
+    ) -> Result<u64> {
+        let mut reader = self.read_file_streaming(src).await?;
+        let (mut pipe_writer, pipe_reader) = tokio::io::duplex(TRANSCODE_PIPE_SIZE);
+        let failed = AtomicBool::new(false);
+        let mut pipe_reader = FailableReader {
+            inner: pipe_reader,
+            failed: &failed,
+        };
+
+        let transcode = async {
+            let result = if compress {
+                compressor
+                    .compress_stream(&mut reader, &mut pipe_writer)
+                    .await
+            } else {
+                compressor
+                    .decompress_stream(&mut reader, &mut pipe_writer)
+                    .await
+            };
+            // Ends the pipe either way; after a failure the writing side reads an error
+            // instead of EOF and keeps the old `dst`.
+            failed.store(result.is_err(), Ordering::Release);
+            let _ = pipe_writer.shutdown().await;
+            result
+        };
+        let (transcoded, written) = tokio::join!(
+            transcode,
+            self.write_file_from_reader(dst, &mut pipe_reader)
+        );
+
+        match (transcoded, written) {
+            (Err(e), _) => Err(e),
+            (Ok(_), written) => written,
+        }
+    }
+}
+
+/// Turns the EOF of `inner` into an error once `failed` is set, so a streamed write whose
+/// producer gave up is never mistaken for a complete one.
+struct FailableReader<'a, R> {
+    inner: R,
+    failed: &'a AtomicBool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for FailableReader<'_, R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        if buf.filled().len() == filled && self.failed.load(Ordering::Acquire) {
+            return Poll::Ready(Err(std::io::Error::other("source stream failed")));
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn unix_seconds(time: std::io::Result<SystemTime>) -> u64 {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Creates a uniquely named [`SYSTEM_FILE_PREFIX`] file in `dir`, which clients never see,
+/// with the same permissions a plain create would give it.
+fn create_temp_file(dir: &Path) -> Result<(std::fs::File, tempfile::TempPath)> {
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(SYSTEM_FILE_PREFIX).suffix(".part");
+    #[cfg(unix)]
+    builder.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o666));
+
+    let temp = builder.tempfile_in(dir).map_err(|e| {
+        FenrisError::file_operation_from(format!("Failed to create file: {}", e), e)
+    })?;
+    Ok(temp.into_parts())
+}
+
+async fn file_size(full_path: &Path) -> u64 {
+    fs::metadata(full_path)
+        .await
+        .map(|metadata| metadata.len())
+        .unwrap_or(0)
+}
+
+fn set_file_mtime(path: &Path, modified: SystemTime) -> std::io::Result<()> {
+    // Directories can only be opened on Windows with backup semantics.
+    #[cfg(windows)]
+    let file = {
+        use std::os::windows::fs::OpenOptionsExt;
+        const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+        std::fs::OpenOptions::new()
+            .write(true)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(path)?
+    };
+    #[cfg(not(windows))]
+    let file = std::fs::File::open(path)?;
+
+    file.set_modified(modified)
+}
+
+fn is_cross_device(error: &std::io::Error) -> bool {
+    error.kind() == std::io::ErrorKind::CrossesDevices
+}
+
+/// Copies then deletes `src`. Whatever goes wrong, `dst` is removed again so only one copy
+/// of the file is left behind.
+async fn copy_then_remove_file(src: &Path, dst: &Path) -> std::io::Result<()> {
+    if let Err(e) = fs::copy(src, dst).await {
+        let _ = fs::remove_file(dst).await;
+        return Err(e);
+    }
+    if let Err(e) = fs::remove_file(src).await {
+        let _ = fs::remove_file(dst).await;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Copies the tree then deletes `src`. A failed copy is cleaned up; a failed delete keeps
+/// the copy, since part of `src` may already be gone.
+async fn copy_then_remove_dir(src: &Path, dst: &Path) -> std::io::Result<()> {
+    if fs::try_exists(dst).await? {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} already exists", dst.display()),
+        ));
+    }
+    if let Err(e) = copy_dir(src, dst).await {
+        let _ = fs::remove_dir_all(dst).await;
+        return Err(e);
+    }
+    fs::remove_dir_all(src).await
+}
+
+async fn copy_dir(src: &Path, dst: &Path) -> std::io::Result<()> {
+    let mut pending = vec![(src.to_path_buf(), dst.to_path_buf())];
+    while let Some((from, to)) = pending.pop() {
+        fs::create_dir(&to).await?;
+        let mut entries = fs::read_dir(&from).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let target = to.join(entry.file_name());
+            if entry.file_type().await?.is_dir() {
+                pending.push((entry.path(), target));
+            } else {
+                fs::copy(entry.path(), target).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn disk_usage(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .filter(|entry| !is_system_file_name(&entry.file_name()))
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => disk_usage(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
 }
 
 #[async_trait::async_trait]
@@ -139,13 +825,14 @@ impl FileOperations for DefaultFileOperations {
 
         if let Some(parent) = full_path.parent() {
             fs::create_dir_all(parent).await.map_err(|e| {
-                FenrisError::FileOperationError(format!("Failed to create parent dirs: {}", e))
+                FenrisError::file_operation_from(format!("Failed to create parent dirs: {}", e), e)
             })?;
         }
 
         fs::File::create(&full_path).await.map_err(|e| {
-            FenrisError::FileOperationError(format!("Failed to create file: {}", e))
+            FenrisError::file_operation_from(format!("Failed to create file: {}", e), e)
         })?;
+        self.audit("create", &full_path, None, 0).await;
 
         debug!("File created: {:?}", full_path);
 
@@ -157,14 +844,14 @@ impl FileOperations for DefaultFileOperations {
 
         debug!("Reading file: {:?}", full_path);
 
-        let mut file = fs::File::open(&full_path)
-            .await
-            .map_err(|e| FenrisError::FileOperationError(format!("Failed to open file: {}", e)))?;
+        let mut file = fs::File::open(&full_path).await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to open file: {}", e), e)
+        })?;
 
         let mut contents = Vec::new();
-        file.read_to_end(&mut contents)
-            .await
-            .map_err(|e| FenrisError::FileOperationError(format!("Failed to read file: {}", e)))?;
+        file.read_to_end(&mut contents).await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to read file: {}", e), e)
+        })?;
 
         debug!("Read {} bytes from {:?}", contents.len(), full_path);
 
@@ -173,27 +860,33 @@ impl FileOperations for DefaultFileOperations {
 
     async fn write_file(&self, path: &Path, data: &[u8]) -> Result<()> {
         let full_path = self.resolve_path(path)?;
+        let _lock = self.lock_path(&full_path).await?;
+        let replaced = file_size(&full_path).await;
+        self.check_quota(replaced, data.len() as u64)?;
 
         debug!("Writing {} bytes to {:?}", data.len(), full_path);
 
         if let Some(parent) = full_path.parent() {
             fs::create_dir_all(parent).await.map_err(|e| {
-                FenrisError::FileOperationError(format!("Failed to create parent dirs: {}", e))
+                FenrisError::file_operation_from(format!("Failed to create parent dirs: {}", e), e)
             })?;
         }
 
         let mut file = fs::File::create(&full_path).await.map_err(|e| {
-            FenrisError::FileOperationError(format!("Failed to create file: {}", e))
+            FenrisError::file_operation_from(format!("Failed to create file: {}", e), e)
         })?;
 
-        file.write_all(data)
-            .await
-            .map_err(|e| FenrisError::FileOperationError(format!("Failed to write file: {}", e)))?;
+        file.write_all(data).await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to write file: {}", e), e)
+        })?;
 
-        file.sync_all()
-            .await
-            .map_err(|e| FenrisError::FileOperationError(format!("Failed to sync file: {}", e)))?;
+        file.sync_all().await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to sync file: {}", e), e)
+        })?;
 
+        self.record_usage(replaced, data.len() as u64);
+        self.audit("write", &full_path, None, data.len() as u64)
+            .await;
         debug!("Wrote {} bytes to {:?}", data.len(), full_path);
 
         Ok(())
@@ -201,6 +894,8 @@ impl FileOperations for DefaultFileOperations {
 
     async fn append_file(&self, path: &Path, data: &[u8]) -> Result<()> {
         let full_path = self.resolve_path(path)?;
+        let _lock = self.lock_path(&full_path).await?;
+        self.check_quota(0, data.len() as u64)?;
 
         debug!("Appending {} bytes to {:?}", data.len(), full_path);
 
@@ -211,17 +906,23 @@ impl FileOperations for DefaultFileOperations {
             .open(&full_path)
             .await
             .map_err(|e| {
-                FenrisError::FileOperationError(format!("Failed to open file for append: {}", e))
+                FenrisError::file_operation_from(
+                    format!("Failed to open file for append: {}", e),
+                    e,
+                )
             })?;
 
         file.write_all(data).await.map_err(|e| {
-            FenrisError::FileOperationError(format!("Failed to append to file: {}", e))
+            FenrisError::file_operation_from(format!("Failed to append to file: {}", e), e)
         })?;
 
-        file.sync_all()
-            .await
-            .map_err(|e| FenrisError::FileOperationError(format!("Failed to sync file: {}", e)))?;
+        file.sync_all().await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to sync file: {}", e), e)
+        })?;
 
+        self.record_usage(0, data.len() as u64);
+        self.audit("append", &full_path, None, data.len() as u64)
+            .await;
         debug!("Appended {} bytes to {:?}", data.len(), full_path);
 
         Ok(())
@@ -229,12 +930,16 @@ impl FileOperations for DefaultFileOperations {
 
     async fn delete_file(&self, path: &Path) -> Result<()> {
         let full_path = self.resolve_path(path)?;
+        let _lock = self.lock_path(&full_path).await?;
 
         debug!("Deleting file: {:?}", full_path);
 
+        let removed = file_size(&full_path).await;
         fs::remove_file(&full_path).await.map_err(|e| {
-            FenrisError::FileOperationError(format!("Failed to delete file: {}", e))
+            FenrisError::file_operation_from(format!("Failed to delete file: {}", e), e)
         })?;
+        self.record_usage(removed, 0);
+        self.audit("delete", &full_path, None, removed).await;
 
         debug!("File deleted: {:?}", full_path);
 
@@ -249,91 +954,548 @@ impl FileOperations for DefaultFileOperations {
         FileMetadata::from_path(&full_path).await
     }
 
-    async fn create_dir(&self, path: &Path) -> Result<()> {
+    async fn checksum_file(&self, path: &Path) -> Result<[u8; 32]> {
         let full_path = self.resolve_path(path)?;
 
-        debug!("Creating directory: {:?}", full_path);
+        debug!("Computing checksum: {:?}", full_path);
 
-        fs::create_dir_all(&full_path).await.map_err(|e| {
-            FenrisError::FileOperationError(format!("Failed to create directory: {}", e))
+        let mut file = fs::File::open(&full_path).await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to open file: {}", e), e)
         })?;
 
-        debug!("Directory created: {:?}", full_path);
+        let mut hasher = Sha256::new();
+        let mut buffer = Vec::with_capacity(CHECKSUM_BUFFER_SIZE);
+        loop {
+            buffer.clear();
+            let read = file.read_buf(&mut buffer).await.map_err(|e| {
+                FenrisError::file_operation_from(format!("Failed to read file: {}", e), e)
+            })?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer);
+        }
 
-        Ok(())
+        Ok(hasher.finalize().into())
     }
 
-    async fn list_dir(&self, path: &Path) -> Result<Vec<FileMetadata>> {
-        let full_path = self.resolve_path(path)?;
-
-        debug!("Listing directory:  {:?}", full_path);
+    async fn checksum_manifest(
+        &self,
+        root: &Path,
+        limit: usize,
+    ) -> Result<Vec<(PathBuf, [u8; 32])>> {
+        let full_root = self.resolve_path(root)?;
 
-        let mut entries = Vec::new();
-        let mut dir = fs::read_dir(&full_path).await.map_err(|e| {
-            FenrisError::FileOperationError(format!("Failed to read directory: {}", e))
-        })?;
+        debug!("Computing checksum manifest: {:?}", full_root);
 
-        while let Some(entry) = dir
-            .next_entry()
-            .await
-            .map_err(|e| FenrisError::FileOperationError(format!("Failed to read entry: {}", e)))?
-        {
-            let entry_path = entry.path();
-            match FileMetadata::from_path(&entry_path).await {
-                Ok(metadata) => entries.push(metadata),
-                Err(e) => {
-                    warn!("Failed to get metadata for {:?}: {}", entry_path, e);
-                }
+        let mut manifest = Vec::new();
+        for (relative, metadata) in self.list_dir_recursive(root, MAX_LIST_DEPTH).await? {
+            if manifest.len() == limit {
+                break;
+            }
+            let is_symlink = fs::symlink_metadata(full_root.join(&relative))
+                .await
+                .map(|metadata| metadata.file_type().is_symlink())
+                .unwrap_or(true);
+            if metadata.is_directory || is_symlink {
+                continue;
             }
+            let digest = self.checksum_file(&root.join(&relative)).await?;
+            manifest.push((relative, digest));
         }
 
-        debug!("Listed {} entries in {:?}", entries.len(), full_path);
-
-        Ok(entries)
+        Ok(manifest)
     }
 
-    async fn delete_dir(&self, path: &Path) -> Result<()> {
-        let full_path = self.resolve_path(path)?;
-
-        debug!("Deleting directory: {:?}", full_path);
-
-        fs::remove_dir(&full_path).await.map_err(|e| {
-            FenrisError::FileOperationError(format!("Failed to delete directory: {}", e))
-        })?;
-
-        debug!("Directory deleted: {:?}", full_path);
-
-        Ok(())
+    async fn compress_file(
+        &self,
+        src: &Path,
+        dst: &Path,
+        compressor: &dyn Compressor,
+    ) -> Result<u64> {
+        debug!(
+            "Compressing {:?} to {:?} with {}",
+            src,
+            dst,
+            compressor.name()
+        );
+        self.transcode_file(src, dst, compressor, true).await
     }
 
-    async fn exists(&self, path: &Path) -> bool {
-        if let Ok(full_path) = self.resolve_path(path) {
-            fs::metadata(&full_path).await.is_ok()
-        } else {
-            false
-        }
+    async fn decompress_file(
+        &self,
+        src: &Path,
+        dst: &Path,
+        compressor: &dyn Compressor,
+    ) -> Result<u64> {
+        debug!(
+            "Decompressing {:?} to {:?} with {}",
+            src,
+            dst,
+            compressor.name()
+        );
+        self.transcode_file(src, dst, compressor, false).await
     }
 
-    async fn is_dir(&self, path: &Path) -> bool {
-        if let Ok(full_path) = self.resolve_path(path)
-            && let Ok(metadata) = fs::metadata(&full_path).await
+    async fn create_symlink(&self, link: &Path, target: &Path) -> Result<()> {
+        self.check_link_creation(link)?;
+        let full_link = self.resolve_link_path(link)?;
+        let _lock = self.lock_path(&full_link).await?;
+
+        debug!("Creating symlink: {:?} -> {:?}", full_link, target);
+
+        #[cfg(unix)]
         {
-            return metadata.is_dir();
+            fs::symlink(target, &full_link).await.map_err(|e| {
+                FenrisError::file_operation_from(format!("Failed to create symlink: {}", e), e)
+            })
         }
-        false
-    }
-
-    async fn is_file(&self, path: &Path) -> bool {
-        if let Ok(full_path) = self.resolve_path(path)
-            && let Ok(metadata) = fs::metadata(&full_path).await
+        #[cfg(not(unix))]
         {
-            return metadata.is_file();
+            let _ = target;
+            Err(FenrisError::file_operation(
+                "Symlinks are only supported on Unix",
+            ))
         }
-        false
     }
-}
 
-#[cfg(test)]
+    async fn create_dir_junction(&self, link: &Path, target: &Path) -> Result<()> {
+        self.check_link_creation(link)?;
+        let full_link = self.resolve_link_path(link)?;
+        let _lock = self.lock_path(&full_link).await?;
+
+        debug!(
+            "Creating directory junction: {:?} -> {:?}",
+            full_link, target
+        );
+
+        #[cfg(windows)]
+        let created = fs::symlink_dir(target, &full_link).await;
+        #[cfg(unix)]
+        let created = fs::symlink(target, &full_link).await;
+        #[cfg(not(any(windows, unix)))]
+        let created: std::io::Result<()> = {
+            let _ = target;
+            Err(std::io::Error::from(std::io::ErrorKind::Unsupported))
+        };
+
+        created.map_err(|e| {
+            FenrisError::file_operation_from(
+                format!("Failed to create directory junction: {}", e),
+                e,
+            )
+        })
+    }
+
+    async fn read_symlink(&self, link: &Path) -> Result<PathBuf> {
+        let full_link = self.resolve_link_path(link)?;
+
+        debug!("Reading symlink: {:?}", full_link);
+
+        fs::read_link(&full_link).await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to read symlink: {}", e), e)
+        })
+    }
+
+    async fn set_mtime(&self, path: &Path, mtime: u64) -> Result<()> {
+        let full_path = self.resolve_path(path)?;
+        let _lock = self.lock_path(&full_path).await?;
+
+        debug!("Setting mtime of {:?} to {}", full_path, mtime);
+
+        let modified = UNIX_EPOCH + Duration::from_secs(mtime);
+        tokio::task::spawn_blocking(move || set_file_mtime(&full_path, modified))
+            .await
+            .map_err(|e| {
+                FenrisError::file_operation_from(format!("Failed to set mtime: {}", e), e)
+            })?
+            .map_err(|e| FenrisError::file_operation_from(format!("Failed to set mtime: {}", e), e))
+    }
+
+    async fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>> {
+        let full_path = self.resolve_path(path)?;
+
+        debug!("Reading xattr {} of {:?}", name, full_path);
+
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        {
+            let attr = name.to_string();
+            tokio::task::spawn_blocking(move || xattr::get(&full_path, &attr))
+                .await
+                .map_err(|e| {
+                    FenrisError::file_operation_from(format!("Failed to read xattr: {}", e), e)
+                })?
+                .map_err(|e| {
+                    FenrisError::file_operation_from(format!("Failed to read xattr: {}", e), e)
+                })?
+                .ok_or_else(|| FenrisError::file_operation(format!("No xattr named {}", name)))
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        {
+            Err(FenrisError::file_operation(
+                "xattrs not supported on this platform",
+            ))
+        }
+    }
+
+    async fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> Result<()> {
+        let full_path = self.resolve_path(path)?;
+        let _lock = self.lock_path(&full_path).await?;
+
+        debug!("Setting xattr {} of {:?}", name, full_path);
+
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        {
+            let name = name.to_string();
+            let value = value.to_vec();
+            tokio::task::spawn_blocking(move || xattr::set(&full_path, &name, &value))
+                .await
+                .map_err(|e| {
+                    FenrisError::file_operation_from(format!("Failed to set xattr: {}", e), e)
+                })?
+                .map_err(|e| {
+                    FenrisError::file_operation_from(format!("Failed to set xattr: {}", e), e)
+                })
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        {
+            let _ = value;
+            Err(FenrisError::file_operation(
+                "xattrs not supported on this platform",
+            ))
+        }
+    }
+
+    fn watch_dir(&self, path: &Path) -> Result<mpsc::Receiver<FileChangeEvent>> {
+        let full_path = self.resolve_path(path)?;
+        if !full_path.is_dir() {
+            return Err(FenrisError::file_operation("Not a directory"));
+        }
+
+        debug!("Watching directory: {:?}", full_path);
+        file_watch::watch_dir(full_path, path.to_path_buf())
+    }
+
+    async fn create_archive(&self, src: &Path, dst: &Path) -> Result<u64> {
+        let full_src = self.resolve_path(src)?;
+        let full_dst = self.resolve_path(dst)?;
+        if !fs::metadata(&full_src)
+            .await
+            .map(|metadata| metadata.is_dir())
+            .unwrap_or(false)
+        {
+            return Err(FenrisError::file_operation(
+                "Archive source is not a directory",
+            ));
+        }
+        if full_dst.starts_with(&full_src) {
+            return Err(FenrisError::InvalidRequest(
+                "archive cannot be written inside the directory being archived".to_string(),
+            ));
+        }
+        let _lock = self.lock_path(&full_dst).await?;
+
+        debug!("Creating archive: {:?} -> {:?}", full_src, full_dst);
+
+        let replaced = file_size(&full_dst).await;
+        let root = PathBuf::from(full_src.file_name().unwrap_or(OsStr::new(".")));
+        let archive_path = full_dst.clone();
+        let written = tokio::task::spawn_blocking(move || {
+            archive::create_tar_gz(&full_src, &archive_path, &root)
+        })
+        .await
+        .map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to create archive: {}", e), e)
+        })?;
+        let written = match written {
+            Ok(written) => written,
+            Err(e) => {
+                let _ = fs::remove_file(&full_dst).await;
+                self.record_usage(replaced, 0);
+                return Err(FenrisError::file_operation_from(
+                    format!("Failed to create archive: {}", e),
+                    e,
+                ));
+            }
+        };
+
+        if let Err(e) = self.check_quota(replaced, written) {
+            let _ = fs::remove_file(&full_dst).await;
+            self.record_usage(replaced, 0);
+            return Err(e);
+        }
+
+        self.record_usage(replaced, written);
+        Ok(written)
+    }
+
+    async fn extract_archive(&self, archive: &Path, dst: &Path) -> Result<u64> {
+        let full_archive = self.resolve_path(archive)?;
+        let full_dst = self.resolve_path(dst)?;
+        let _lock = self.lock_path(&full_dst).await?;
+
+        debug!("Extracting archive: {:?} -> {:?}", full_archive, full_dst);
+
+        fs::create_dir_all(&full_dst).await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to create directory: {}", e), e)
+        })?;
+
+        let ops = self.clone();
+        tokio::task::spawn_blocking(move || {
+            archive::extract_tar_gz(&full_archive, &full_dst, ops.symlinks_allowed, |size| {
+                ops.check_quota(0, size)?;
+                ops.record_usage(0, size);
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to extract archive: {}", e), e)
+        })?
+    }
+
+    async fn create_dir(&self, path: &Path) -> Result<()> {
+        let full_path = self.resolve_path(path)?;
+
+        debug!("Creating directory: {:?}", full_path);
+
+        fs::create_dir(&full_path).await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::AlreadyExists {
+                FenrisError::file_operation_from(
+                    format!(
+                        "Failed to create directory: {} already exists",
+                        path.display()
+                    ),
+                    e,
+                )
+            } else {
+                FenrisError::file_operation_from(format!("Failed to create directory: {}", e), e)
+            }
+        })?;
+
+        debug!("Directory created: {:?}", full_path);
+
+        Ok(())
+    }
+
+    async fn create_dir_all(&self, path: &Path) -> Result<()> {
+        debug!("Creating directory with parents: {:?}", path);
+
+        // resolve_path needs an existing parent, so each level is resolved and checked
+        // against the sandbox once the one above it exists.
+        let mut partial = PathBuf::new();
+        for component in path.components() {
+            partial.push(component);
+            let full_path = self.resolve_path(&partial)?;
+            match fs::create_dir(&full_path).await {
+                Ok(()) => {}
+                Err(e)
+                    if e.kind() == std::io::ErrorKind::AlreadyExists
+                        && fs::metadata(&full_path)
+                            .await
+                            .is_ok_and(|metadata| metadata.is_dir()) => {}
+                Err(e) => {
+                    return Err(FenrisError::file_operation_from(
+                        format!("Failed to create directory: {}", e),
+                        e,
+                    ));
+                }
+            }
+        }
+
+        debug!("Directory created: {:?}", path);
+
+        Ok(())
+    }
+
+    async fn list_dir(&self, path: &Path) -> Result<Vec<FileMetadata>> {
+        let full_path = self.resolve_path(path)?;
+
+        debug!("Listing directory:  {:?}", full_path);
+
+        let mut entries = Vec::new();
+        let mut dir = fs::read_dir(&full_path).await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to read directory: {}", e), e)
+        })?;
+
+        while let Some(entry) = dir.next_entry().await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to read entry: {}", e), e)
+        })? {
+            if is_system_file_name(&entry.file_name()) {
+                continue;
+            }
+            let entry_path = entry.path();
+            match FileMetadata::from_path(&entry_path).await {
+                Ok(metadata) => entries.push(metadata),
+                Err(e) => {
+                    warn!("Failed to get metadata for {:?}: {}", entry_path, e);
+                }
+            }
+        }
+
+        debug!("Listed {} entries in {:?}", entries.len(), full_path);
+
+        Ok(entries)
+    }
+
+    async fn list_dir_recursive(
+        &self,
+        root: &Path,
+        max_depth: u32,
+    ) -> Result<Vec<(PathBuf, FileMetadata)>> {
+        let full_root = self.resolve_path(root)?;
+        let max_depth = capped_list_depth(max_depth);
+
+        debug!(
+            "Listing directory recursively: {:?} (depth {})",
+            full_root, max_depth
+        );
+
+        let mut entries = Vec::new();
+        let mut pending = vec![(full_root.clone(), 1)];
+
+        while let Some((dir_path, depth)) = pending.pop() {
+            let mut dir = fs::read_dir(&dir_path).await.map_err(|e| {
+                FenrisError::file_operation_from(format!("Failed to read directory: {}", e), e)
+            })?;
+
+            while let Some(entry) = dir.next_entry().await.map_err(|e| {
+                FenrisError::file_operation_from(format!("Failed to read entry: {}", e), e)
+            })? {
+                if is_system_file_name(&entry.file_name()) {
+                    continue;
+                }
+                let entry_path = entry.path();
+                let metadata = match FileMetadata::from_path(&entry_path).await {
+                    Ok(metadata) => metadata,
+                    Err(e) => {
+                        warn!("Failed to get metadata for {:?}: {}", entry_path, e);
+                        continue;
+                    }
+                };
+
+                let is_symlink = entry
+                    .file_type()
+                    .await
+                    .map(|file_type| file_type.is_symlink())
+                    .unwrap_or(true);
+                if metadata.is_directory && !is_symlink && depth < max_depth {
+                    pending.push((entry_path.clone(), depth + 1));
+                }
+
+                let relative = entry_path
+                    .strip_prefix(&full_root)
+                    .unwrap_or(&entry_path)
+                    .to_path_buf();
+                entries.push((relative, metadata));
+            }
+        }
+
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        debug!("Listed {} entries under {:?}", entries.len(), full_root);
+
+        Ok(entries)
+    }
+
+    async fn delete_dir(&self, path: &Path) -> Result<()> {
+        let full_path = self.resolve_path(path)?;
+
+        debug!("Deleting directory: {:?}", full_path);
+
+        fs::remove_dir(&full_path).await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to delete directory: {}", e), e)
+        })?;
+        self.audit("delete_dir", &full_path, None, 0).await;
+
+        debug!("Directory deleted: {:?}", full_path);
+
+        Ok(())
+    }
+
+    async fn move_file(&self, src: &Path, dst: &Path) -> Result<()> {
+        let full_src = self.resolve_path(src)?;
+        let full_dst = self.resolve_path(dst)?;
+        let _locks = self.lock_pair(&full_src, &full_dst).await?;
+
+        debug!("Moving file: {:?} -> {:?}", full_src, full_dst);
+
+        match fs::rename(&full_src, &full_dst).await {
+            Ok(()) => Ok(()),
+            Err(e) if is_cross_device(&e) => {
+                debug!("Rename crosses filesystems, copying instead");
+                copy_then_remove_file(&full_src, &full_dst)
+                    .await
+                    .map_err(|e| {
+                        FenrisError::file_operation_from(format!("Failed to move file: {}", e), e)
+                    })
+            }
+            Err(e) => Err(FenrisError::file_operation_from(
+                format!("Failed to move file: {}", e),
+                e,
+            )),
+        }?;
+        self.audit("rename", &full_src, Some(&full_dst), 0).await;
+        Ok(())
+    }
+
+    async fn move_dir(&self, src: &Path, dst: &Path) -> Result<()> {
+        let full_src = self.resolve_path(src)?;
+        let full_dst = self.resolve_path(dst)?;
+        let _locks = self.lock_pair(&full_src, &full_dst).await?;
+
+        debug!("Moving directory: {:?} -> {:?}", full_src, full_dst);
+
+        match fs::rename(&full_src, &full_dst).await {
+            Ok(()) => Ok(()),
+            Err(e) if is_cross_device(&e) => {
+                debug!("Rename crosses filesystems, copying instead");
+                copy_then_remove_dir(&full_src, &full_dst)
+                    .await
+                    .map_err(|e| {
+                        FenrisError::file_operation_from(
+                            format!("Failed to move directory: {}", e),
+                            e,
+                        )
+                    })
+            }
+            Err(e) => Err(FenrisError::file_operation_from(
+                format!("Failed to move directory: {}", e),
+                e,
+            )),
+        }?;
+        self.audit("rename", &full_src, Some(&full_dst), 0).await;
+        Ok(())
+    }
+
+    async fn exists(&self, path: &Path) -> bool {
+        if let Ok(full_path) = self.resolve_path(path) {
+            fs::metadata(&full_path).await.is_ok()
+        } else {
+            false
+        }
+    }
+
+    async fn is_dir(&self, path: &Path) -> bool {
+        if let Ok(full_path) = self.resolve_path(path)
+            && let Ok(metadata) = fs::metadata(&full_path).await
+        {
+            return metadata.is_dir();
+        }
+        false
+    }
+
+    async fn is_file(&self, path: &Path) -> bool {
+        if let Ok(full_path) = self.resolve_path(path)
+            && let Ok(metadata) = fs::metadata(&full_path).await
+        {
+            return metadata.is_file();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::TempDir;
@@ -341,7 +1503,7 @@ mod tests {
     #[tokio::test]
     async fn test_create_and_read_file() {
         let temp_dir = TempDir::new().unwrap();
-        let file_ops = DefaultFileOperations::new(temp_dir.path().to_path_buf());
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
 
         let path = Path::new("test.txt");
         file_ops.create_file(path).await.unwrap();
@@ -350,10 +1512,61 @@ mod tests {
         assert!(file_ops.is_file(path).await);
     }
 
+    #[tokio::test]
+    async fn test_new_canonicalizes_base_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("inner")).unwrap();
+
+        let file_ops = DefaultFileOperations::new(temp_dir.path().join("inner/.."))
+            .await
+            .unwrap();
+        assert_eq!(file_ops.base_dir(), temp_dir.path().canonicalize().unwrap());
+
+        let missing = DefaultFileOperations::new(temp_dir.path().join("missing")).await;
+        assert!(matches!(
+            missing,
+            Err(FenrisError::FileOperationError { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_with_current_dir_async_matches_sync_variant() {
+        let async_ops = DefaultFileOperations::with_current_dir_async()
+            .await
+            .unwrap();
+        let sync_ops = DefaultFileOperations::with_current_dir().unwrap();
+
+        assert_eq!(
+            async_ops.base_dir(),
+            sync_ops.base_dir().canonicalize().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streaming_write_and_read_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
+
+        let path = Path::new("stream.bin");
+        let data = vec![7u8; 64 * 1024 + 3];
+
+        let written = file_ops
+            .write_file_from_reader(path, &mut data.as_slice())
+            .await
+            .unwrap();
+        assert_eq!(written, data.len() as u64);
+
+        let mut reader = file_ops.read_file_streaming(path).await.unwrap();
+        let mut read_data = Vec::new();
+        reader.read_to_end(&mut read_data).await.unwrap();
+
+        assert_eq!(read_data, data);
+    }
+
     #[tokio::test]
     async fn test_write_and_read_file() {
         let temp_dir = TempDir::new().unwrap();
-        let file_ops = DefaultFileOperations::new(temp_dir.path().to_path_buf());
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
 
         let path = Path::new("test.txt");
         let data = b"Hello, World!";
@@ -367,7 +1580,7 @@ mod tests {
     #[tokio::test]
     async fn test_append_file() {
         let temp_dir = TempDir::new().unwrap();
-        let file_ops = DefaultFileOperations::new(temp_dir.path().to_path_buf());
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
 
         let path = Path::new("test.txt");
 
@@ -381,7 +1594,7 @@ mod tests {
     #[tokio::test]
     async fn test_append_file_creates_missing_file() {
         let temp_dir = TempDir::new().unwrap();
-        let file_ops = DefaultFileOperations::new(temp_dir.path().to_path_buf());
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
 
         let path = Path::new("new.txt");
 
@@ -395,7 +1608,7 @@ mod tests {
     #[tokio::test]
     async fn test_delete_file() {
         let temp_dir = TempDir::new().unwrap();
-        let file_ops = DefaultFileOperations::new(temp_dir.path().to_path_buf());
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
 
         let path = Path::new("test.txt");
         file_ops.create_file(path).await.unwrap();
@@ -405,10 +1618,94 @@ mod tests {
         assert!(!file_ops.exists(path).await);
     }
 
+    #[tokio::test]
+    async fn test_audit_log_records_modifications_and_stays_hidden() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new(temp_dir.path().to_path_buf())
+            .await
+            .unwrap()
+            .with_audit_log(true);
+        #[cfg(any(target_os = "linux", target_os = "macos", windows))]
+        let mut changes = file_ops.watch_dir(Path::new("/")).unwrap();
+
+        std::fs::create_dir(temp_dir.path().join("docs")).unwrap();
+        file_ops
+            .write_file(Path::new("docs/notes.txt"), b"hello")
+            .await
+            .unwrap();
+        file_ops
+            .append_file(Path::new("docs/notes.txt"), b"!")
+            .await
+            .unwrap();
+        file_ops
+            .move_file(Path::new("docs/notes.txt"), Path::new("notes.txt"))
+            .await
+            .unwrap();
+        file_ops.delete_file(Path::new("notes.txt")).await.unwrap();
+        file_ops
+            .write_file_from_reader(Path::new("streamed.txt"), &mut &b"streamed"[..])
+            .await
+            .unwrap();
+
+        // Neither the log nor the temp file behind the streamed write shows up in a watch.
+        #[cfg(any(target_os = "linux", target_os = "macos", windows))]
+        {
+            let mut seen = Vec::new();
+            while let Ok(Some(change)) =
+                tokio::time::timeout(Duration::from_millis(200), changes.recv()).await
+            {
+                seen.push(change);
+            }
+            assert!(
+                seen.iter()
+                    .all(|change| !format!("{:?}", change).contains(SYSTEM_FILE_PREFIX)),
+                "{:?}",
+                seen
+            );
+            assert!(seen.contains(&FileChangeEvent {
+                path: PathBuf::from("/streamed.txt"),
+                kind: ChangeKind::Created,
+            }));
+        }
+
+        let log = std::fs::read_to_string(temp_dir.path().join(AUDIT_LOG_FILE)).unwrap();
+        let records: Vec<serde_json::Value> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let ops: Vec<_> = records.iter().map(|record| &record["op"]).collect();
+        assert_eq!(ops, ["write", "append", "rename", "delete", "write"]);
+        assert_eq!(records[0]["path"], "docs/notes.txt");
+        assert_eq!(records[0]["bytes"], 5);
+        assert_eq!(records[2]["to"], "notes.txt");
+        assert_eq!(records[3]["bytes"], 6);
+        assert!(records[0]["ts"].as_u64().unwrap() > 0);
+
+        let mut names: Vec<_> = file_ops
+            .list_dir(Path::new("/"))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["docs", "streamed.txt"]);
+        assert!(matches!(
+            file_ops.file_info(Path::new(AUDIT_LOG_FILE)).await,
+            Err(FenrisError::PermissionDenied(_))
+        ));
+        assert!(matches!(
+            file_ops
+                .write_file(Path::new("docs/../.fenris_audit.log"), b"forged")
+                .await,
+            Err(FenrisError::PermissionDenied(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_create_and_list_dir() {
         let temp_dir = TempDir::new().unwrap();
-        let file_ops = DefaultFileOperations::new(temp_dir.path().to_path_buf());
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
 
         let dir_path = Path::new("testdir");
         file_ops.create_dir(dir_path).await.unwrap();
@@ -429,19 +1726,590 @@ mod tests {
         assert_eq!(entries.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_create_dir_rejects_existing_and_create_dir_all_does_not() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
+
+        file_ops.create_dir(Path::new("docs")).await.unwrap();
+        let err = file_ops.create_dir(Path::new("docs")).await.unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        assert!(file_ops.create_dir(Path::new("a/b/c")).await.is_err());
+
+        file_ops.create_dir_all(Path::new("a/b/c")).await.unwrap();
+        file_ops.create_dir_all(Path::new("a/b/c")).await.unwrap();
+        file_ops.create_dir_all(Path::new("docs")).await.unwrap();
+        assert!(file_ops.is_dir(Path::new("a/b/c")).await);
+    }
+
+    #[tokio::test]
+    async fn test_list_dir_recursive_respects_depth() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
+
+        for dir in ["root", "root/a", "root/a/b"] {
+            file_ops.create_dir(Path::new(dir)).await.unwrap();
+        }
+        file_ops
+            .write_file(Path::new("root/top.txt"), b"top")
+            .await
+            .unwrap();
+        file_ops
+            .write_file(Path::new("root/a/b/deep.txt"), b"deep")
+            .await
+            .unwrap();
+
+        let entries = file_ops
+            .list_dir_recursive(Path::new("root"), 2)
+            .await
+            .unwrap();
+        let paths: Vec<PathBuf> = entries.into_iter().map(|(path, _)| path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("a"),
+                PathBuf::from("a/b"),
+                PathBuf::from("top.txt")
+            ]
+        );
+
+        let entries = file_ops
+            .list_dir_recursive(Path::new("root"), 0)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[2].0, PathBuf::from("a/b/deep.txt"));
+        assert_eq!(entries[2].1.size, 4);
+
+        assert!(
+            file_ops
+                .list_dir_recursive(Path::new("../outside"), 1)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writes_are_serialized_and_locks_released() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
+        let path = Path::new("shared.log");
+
+        let writers = (0..8u8).map(|i| {
+            let file_ops = file_ops.clone();
+            tokio::spawn(async move {
+                file_ops
+                    .append_file(Path::new("shared.log"), &[b'a' + i; 256])
+                    .await
+            })
+        });
+        for writer in writers {
+            writer.await.unwrap().unwrap();
+        }
+
+        let contents = file_ops.read_file(path).await.unwrap();
+        assert_eq!(contents.len(), 8 * 256);
+        for chunk in contents.chunks(256) {
+            assert!(chunk.iter().all(|byte| *byte == chunk[0]));
+        }
+        assert!(file_ops.locks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_write_times_out_while_path_is_locked() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf())
+            .with_lock_timeout(Duration::from_millis(20));
+        let path = Path::new("busy.txt");
+        file_ops.write_file(path, b"first").await.unwrap();
+
+        let held = file_ops
+            .lock_path(&file_ops.resolve_path(path).unwrap())
+            .await
+            .unwrap();
+        let result = file_ops.write_file(path, b"second").await;
+        assert!(matches!(
+            result,
+            Err(FenrisError::FileOperationError { ref message, .. }) if message == "lock timeout"
+        ));
+        assert_eq!(file_ops.read_file(path).await.unwrap(), b"first");
+
+        drop(held);
+        assert!(file_ops.locks.is_empty());
+        file_ops.delete_file(path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_streaming_write_waits_for_the_path_lock() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf())
+            .with_lock_timeout(Duration::from_millis(20));
+        let path = Path::new("busy.txt");
+        file_ops.write_file(path, b"first").await.unwrap();
+
+        let held = file_ops
+            .lock_path(&file_ops.resolve_path(path).unwrap())
+            .await
+            .unwrap();
+        let result = file_ops
+            .write_file_from_reader(path, &mut &b"second"[..])
+            .await;
+        assert!(matches!(
+            result,
+            Err(FenrisError::FileOperationError { ref message, .. }) if message == "lock timeout"
+        ));
+        assert_eq!(file_ops.read_file(path).await.unwrap(), b"first");
+
+        drop(held);
+        file_ops
+            .write_file_from_reader(path, &mut &b"second"[..])
+            .await
+            .unwrap();
+        assert_eq!(file_ops.read_file(path).await.unwrap(), b"second");
+    }
+
+    #[tokio::test]
+    async fn test_quota_counts_existing_files_and_rejects_overage() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("docs")).unwrap();
+        std::fs::write(temp_dir.path().join("docs/old.txt"), [0u8; 40]).unwrap();
+        let file_ops = DefaultFileOperations::new_with_quota(
+            temp_dir.path().to_path_buf(),
+            Arc::new(QuotaManager::new(Some(100))),
+        )
+        .await
+        .unwrap();
+        assert_eq!(file_ops.current_usage(), 40);
+
+        file_ops
+            .write_file(Path::new("a.txt"), &[1; 50])
+            .await
+            .unwrap();
+        assert_eq!(file_ops.current_usage(), 90);
+
+        let result = file_ops.append_file(Path::new("a.txt"), &[1; 20]).await;
+        assert!(matches!(
+            result,
+            Err(FenrisError::StorageQuotaExceeded {
+                quota: 100,
+                required: 110
+            })
+        ));
+
+        file_ops
+            .write_file(Path::new("a.txt"), &[2; 60])
+            .await
+            .unwrap();
+        assert_eq!(file_ops.current_usage(), 100);
+
+        file_ops
+            .delete_file(Path::new("docs/old.txt"))
+            .await
+            .unwrap();
+        assert_eq!(file_ops.current_usage(), 60);
+        file_ops
+            .append_file(Path::new("a.txt"), &[3; 40])
+            .await
+            .unwrap();
+        assert_eq!(file_ops.current_usage(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_quota_rejects_oversized_streaming_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_with_quota(
+            temp_dir.path().to_path_buf(),
+            Arc::new(QuotaManager::new(Some(64))),
+        )
+        .await
+        .unwrap();
+        let path = Path::new("upload.bin");
+
+        let written = file_ops
+            .write_file_from_reader(path, &mut &[7u8; 64][..])
+            .await
+            .unwrap();
+        assert_eq!(written, 64);
+
+        let result = file_ops
+            .write_file_from_reader(Path::new("more.bin"), &mut &[7u8; 8][..])
+            .await;
+        assert!(matches!(
+            result,
+            Err(FenrisError::StorageQuotaExceeded { .. })
+        ));
+        assert!(!file_ops.exists(Path::new("more.bin")).await);
+        assert_eq!(file_ops.current_usage(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_streaming_write_keeps_the_old_file() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("report.txt"), b"original").unwrap();
+        let file_ops = DefaultFileOperations::new_with_quota(
+            temp_dir.path().to_path_buf(),
+            Arc::new(QuotaManager::new(Some(64))),
+        )
+        .await
+        .unwrap();
+        let path = Path::new("report.txt");
+
+        let result = file_ops
+            .write_file_from_reader(path, &mut &[7u8; 100][..])
+            .await;
+        assert!(matches!(
+            result,
+            Err(FenrisError::StorageQuotaExceeded { .. })
+        ));
+        assert_eq!(file_ops.read_file(path).await.unwrap(), b"original");
+        assert_eq!(file_ops.current_usage(), 8);
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+        file_ops
+            .write_file_from_reader(path, &mut &[7u8; 64][..])
+            .await
+            .unwrap();
+        assert_eq!(file_ops.read_file(path).await.unwrap(), [7u8; 64]);
+        assert_eq!(file_ops.current_usage(), 64);
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_quota_limits_are_read_per_account_on_each_write() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("alice")).unwrap();
+        let quotas = Arc::new(QuotaManager::new(None));
+        let file_ops =
+            DefaultFileOperations::new_with_quota(temp_dir.path().to_path_buf(), quotas.clone())
+                .await
+                .unwrap();
+        let alice = file_ops
+            .rebased(temp_dir.path().join("alice"))
+            .await
+            .unwrap()
+            .with_quota_account("alice");
+
+        alice
+            .write_file(Path::new("a.bin"), &[0; 32])
+            .await
+            .unwrap();
+        quotas.set_limit("alice", 40).await.unwrap();
+        assert_eq!(
+            alice.quota_usage(),
+            Some(QuotaUsage {
+                used_bytes: 32,
+                limit_bytes: Some(40)
+            })
+        );
+        assert!(
+            alice
+                .append_file(Path::new("a.bin"), &[0; 16])
+                .await
+                .is_err()
+        );
+
+        // The anonymous account is unaffected by alice's limit.
+        file_ops
+            .write_file(Path::new("b.bin"), &[0; 64])
+            .await
+            .unwrap();
+        assert_eq!(file_ops.quota_usage().unwrap().limit_bytes, None);
+        assert_eq!(
+            DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf()).quota_usage(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_checksum_file_matches_sha256sum() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
+
+        // Expected digests from `sha256sum`.
+        let cases: [(&str, &[u8], &str); 2] = [
+            (
+                "empty.txt",
+                b"",
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                "abc.txt",
+                b"abc",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+        ];
+        for (name, data, expected) in cases {
+            file_ops.write_file(Path::new(name), data).await.unwrap();
+            let digest = file_ops.checksum_file(Path::new(name)).await.unwrap();
+            assert_eq!(hex::encode(digest), expected);
+        }
+
+        let large = vec![b'a'; CHECKSUM_BUFFER_SIZE * 3 + 17];
+        file_ops
+            .write_file(Path::new("large.bin"), &large)
+            .await
+            .unwrap();
+        let digest = file_ops
+            .checksum_file(Path::new("large.bin"))
+            .await
+            .unwrap();
+        assert_eq!(digest, <[u8; 32]>::from(Sha256::digest(&large)));
+
+        let metadata = file_ops.file_info(Path::new("abc.txt")).await.unwrap();
+        assert_eq!(metadata.checksum, None);
+        assert!(file_ops.checksum_file(Path::new("missing")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_checksum_manifest_lists_files_in_order_up_to_the_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
+        file_ops
+            .create_dir_all(Path::new("photos/2024"))
+            .await
+            .unwrap();
+        for (name, data) in [
+            ("photos/b.jpg", b"b".as_slice()),
+            ("photos/2024/a.jpg", b"a"),
+            ("photos/c.jpg", b"c"),
+        ] {
+            file_ops.write_file(Path::new(name), data).await.unwrap();
+        }
+
+        let manifest = file_ops
+            .checksum_manifest(Path::new("photos"), 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            manifest,
+            vec![
+                (PathBuf::from("2024/a.jpg"), Sha256::digest(b"a").into()),
+                (PathBuf::from("b.jpg"), Sha256::digest(b"b").into()),
+                (PathBuf::from("c.jpg"), Sha256::digest(b"c").into()),
+            ]
+        );
+
+        let capped = file_ops
+            .checksum_manifest(Path::new("photos"), 2)
+            .await
+            .unwrap();
+        assert_eq!(capped, manifest[..2]);
+    }
+
+    #[test]
+    fn test_capped_list_depth() {
+        assert_eq!(capped_list_depth(0), MAX_LIST_DEPTH);
+        assert_eq!(capped_list_depth(3), 3);
+        assert_eq!(capped_list_depth(500), MAX_LIST_DEPTH);
+    }
+
     #[tokio::test]
     async fn test_path_traversal_prevention() {
         let temp_dir = TempDir::new().unwrap();
-        let file_ops = DefaultFileOperations::new(temp_dir.path().to_path_buf());
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
 
         let result = file_ops.read_file(Path::new("../../../etc/passwd")).await;
         assert!(result.is_err());
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlinks_are_rejected_by_default() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), b"secret").unwrap();
+        std::fs::create_dir(temp_dir.path().join("real")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("escape")).unwrap();
+        std::os::unix::fs::symlink(temp_dir.path().join("real"), temp_dir.path().join("inner"))
+            .unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
+
+        let result = file_ops.read_file(Path::new("escape/secret.txt")).await;
+        assert!(result.is_err());
+
+        let result = file_ops
+            .write_file(Path::new("escape/planted.txt"), b"x")
+            .await;
+        assert!(result.is_err());
+        assert!(!outside.path().join("planted.txt").exists());
+
+        let result = file_ops.write_file(Path::new("inner/a.txt"), b"x").await;
+        assert!(matches!(result, Err(FenrisError::PermissionDenied(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_allowed_symlinks_must_stay_inside_base_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), b"secret").unwrap();
+        std::fs::create_dir(temp_dir.path().join("real")).unwrap();
+        std::fs::write(temp_dir.path().join("real/a.txt"), b"inside").unwrap();
+        std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("escape")).unwrap();
+        std::os::unix::fs::symlink(temp_dir.path().join("real"), temp_dir.path().join("inner"))
+            .unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf())
+            .with_symlinks_allowed(true);
+
+        assert_eq!(
+            file_ops.read_file(Path::new("inner/a.txt")).await.unwrap(),
+            b"inside"
+        );
+        assert!(
+            file_ops
+                .read_file(Path::new("escape/secret.txt"))
+                .await
+                .is_err()
+        );
+        assert!(
+            file_ops
+                .read_file(Path::new("real/../escape/secret.txt"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_symlink_creation_is_rejected_unless_allowed() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("releases")).unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
+
+        let result = file_ops
+            .create_symlink(Path::new("latest"), Path::new("releases"))
+            .await;
+        assert!(matches!(result, Err(FenrisError::PermissionDenied(_))));
+        let result = file_ops
+            .create_dir_junction(Path::new("current"), Path::new("releases"))
+            .await;
+        assert!(matches!(result, Err(FenrisError::PermissionDenied(_))));
+        assert!(std::fs::symlink_metadata(temp_dir.path().join("latest")).is_err());
+        assert!(std::fs::symlink_metadata(temp_dir.path().join("current")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlinks_are_created_without_following_the_link_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), b"secret").unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf())
+            .with_symlinks_allowed(true);
+
+        file_ops
+            .create_symlink(Path::new("/out"), outside.path())
+            .await
+            .unwrap();
+        file_ops
+            .create_symlink(Path::new("rel"), Path::new("../elsewhere"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            file_ops.read_symlink(Path::new("out")).await.unwrap(),
+            outside.path()
+        );
+        assert_eq!(
+            file_ops.read_symlink(Path::new("rel")).await.unwrap(),
+            Path::new("../elsewhere")
+        );
+        assert!(
+            file_ops
+                .read_file(Path::new("out/secret.txt"))
+                .await
+                .is_err()
+        );
+        assert!(
+            file_ops
+                .create_symlink(Path::new("out/planted"), Path::new("x"))
+                .await
+                .is_err()
+        );
+        assert!(!outside.path().join("planted").exists());
+        assert!(
+            file_ops
+                .create_symlink(Path::new(".."), Path::new("x"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn test_dir_junction_falls_back_to_a_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("releases")).unwrap();
+        std::fs::write(temp_dir.path().join("releases/app.txt"), b"v2").unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf())
+            .with_symlinks_allowed(true);
+
+        file_ops
+            .create_dir_junction(Path::new("current"), Path::new("releases"))
+            .await
+            .unwrap();
+
+        let link = temp_dir.path().join("current");
+        assert!(std::fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(
+            file_ops.read_symlink(Path::new("current")).await.unwrap(),
+            Path::new("releases")
+        );
+        assert_eq!(std::fs::read(link.join("app.txt")).unwrap(), b"v2");
+    }
+
+    #[cfg(target_os = "windows")]
+    #[tokio::test]
+    async fn test_dir_junction_links_a_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("releases");
+        std::fs::create_dir(&target).unwrap();
+        std::fs::write(target.join("app.txt"), b"v2").unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf())
+            .with_symlinks_allowed(true);
+
+        // Directory links need Developer Mode or an elevated shell on Windows.
+        if file_ops
+            .create_dir_junction(Path::new("current"), &target)
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        let link = temp_dir.path().join("current");
+        assert!(std::fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(std::fs::read(link.join("app.txt")).unwrap(), b"v2");
+    }
+
+    #[tokio::test]
+    async fn test_file_info_reports_creation_time_when_available() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
+        let before = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        file_ops
+            .write_file(Path::new("born.txt"), b"hello")
+            .await
+            .unwrap();
+        let info = file_ops.file_info(Path::new("born.txt")).await.unwrap();
+
+        if std::fs::metadata(temp_dir.path().join("born.txt"))
+            .and_then(|metadata| metadata.created())
+            .is_ok()
+        {
+            assert!(info.created_time >= before && info.created_time <= before + 60);
+        } else {
+            assert_eq!(info.created_time, 0);
+        }
+    }
+
     #[tokio::test]
     async fn test_file_metadata() {
         let temp_dir = TempDir::new().unwrap();
-        let file_ops = DefaultFileOperations::new(temp_dir.path().to_path_buf());
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
 
         let path = Path::new("test.txt");
         let data = b"Hello, World! ";
@@ -452,4 +2320,300 @@ mod tests {
         assert_eq!(metadata.size, data.len() as u64);
         assert!(!metadata.is_directory);
     }
+
+    #[tokio::test]
+    async fn test_set_mtime_on_files_and_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
+        file_ops
+            .write_file(Path::new("restored.txt"), b"unchanged")
+            .await
+            .unwrap();
+        file_ops.create_dir(Path::new("dir")).await.unwrap();
+
+        let future = 4_102_444_800;
+        for (path, mtime) in [("restored.txt", 0), ("dir", future)] {
+            file_ops.set_mtime(Path::new(path), mtime).await.unwrap();
+            let info = file_ops.file_info(Path::new(path)).await.unwrap();
+            assert_eq!(info.modified_time, mtime);
+        }
+        assert_eq!(
+            file_ops.read_file(Path::new("restored.txt")).await.unwrap(),
+            b"unchanged"
+        );
+        assert!(
+            file_ops
+                .set_mtime(Path::new("missing.txt"), 0)
+                .await
+                .is_err()
+        );
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[tokio::test]
+    async fn test_xattrs_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
+        file_ops
+            .write_file(Path::new("labelled.txt"), b"data")
+            .await
+            .unwrap();
+        // Some filesystems (older tmpfs, for one) refuse user xattrs altogether.
+        if xattr::set(temp_dir.path().join("labelled.txt"), "user.probe", b"").is_err() {
+            return;
+        }
+
+        file_ops
+            .set_xattr(Path::new("labelled.txt"), "user.origin", b"camera-1")
+            .await
+            .unwrap();
+        assert_eq!(
+            file_ops
+                .get_xattr(Path::new("labelled.txt"), "user.origin")
+                .await
+                .unwrap(),
+            b"camera-1"
+        );
+        assert!(
+            file_ops
+                .get_xattr(Path::new("labelled.txt"), "user.missing")
+                .await
+                .is_err()
+        );
+        assert!(
+            file_ops
+                .set_xattr(Path::new("missing.txt"), "user.origin", b"x")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_move_file_and_dir_within_base_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let ops = DefaultFileOperations::new(temp_dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        ops.write_file(Path::new("a.txt"), b"moved").await.unwrap();
+        ops.create_dir(Path::new("sub")).await.unwrap();
+        ops.move_file(Path::new("a.txt"), Path::new("sub/b.txt"))
+            .await
+            .unwrap();
+        assert!(!ops.exists(Path::new("a.txt")).await);
+
+        ops.move_dir(Path::new("sub"), Path::new("renamed"))
+            .await
+            .unwrap();
+        assert_eq!(
+            ops.read_file(Path::new("renamed/b.txt")).await.unwrap(),
+            b"moved"
+        );
+        assert!(
+            ops.move_file(Path::new("missing"), Path::new("x"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_copy_fallbacks_leave_a_single_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("file"), b"data").await.unwrap();
+        fs::create_dir_all(root.join("tree/nested")).await.unwrap();
+        fs::write(root.join("tree/nested/leaf"), b"leaf")
+            .await
+            .unwrap();
+
+        copy_then_remove_file(&root.join("file"), &root.join("file.moved"))
+            .await
+            .unwrap();
+        assert!(!root.join("file").exists());
+        assert_eq!(fs::read(root.join("file.moved")).await.unwrap(), b"data");
+
+        copy_then_remove_dir(&root.join("tree"), &root.join("tree.moved"))
+            .await
+            .unwrap();
+        assert!(!root.join("tree").exists());
+        assert_eq!(
+            fs::read(root.join("tree.moved/nested/leaf")).await.unwrap(),
+            b"leaf"
+        );
+
+        // A missing source leaves no partial destination behind.
+        assert!(
+            copy_then_remove_file(&root.join("gone"), &root.join("gone.moved"))
+                .await
+                .is_err()
+        );
+        assert!(!root.join("gone.moved").exists());
+        assert!(
+            copy_then_remove_dir(&root.join("file.moved"), &root.join("tree.moved"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compress_file_streams_through_the_codec() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
+        let compressor = crate::compression::ZlibCompressor::new();
+        let data = b"streamed line of text\n".repeat(50_000);
+        file_ops
+            .write_file(Path::new("plain"), &data)
+            .await
+            .unwrap();
+
+        let written = file_ops
+            .compress_file(Path::new("plain"), Path::new("plain.z"), &compressor)
+            .await
+            .unwrap();
+        let compressed = file_ops.read_file(Path::new("plain.z")).await.unwrap();
+        assert_eq!(written, compressed.len() as u64);
+        assert_eq!(compressor.decompress(&compressed).unwrap(), data);
+
+        file_ops
+            .decompress_file(Path::new("plain.z"), Path::new("restored"), &compressor)
+            .await
+            .unwrap();
+        assert_eq!(
+            file_ops.read_file(Path::new("restored")).await.unwrap(),
+            data
+        );
+
+        // A corrupt source leaves no partial output behind.
+        assert!(
+            file_ops
+                .decompress_file(Path::new("plain"), Path::new("broken"), &compressor)
+                .await
+                .is_err()
+        );
+        assert!(!file_ops.exists(Path::new("broken")).await);
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_input_leaves_the_existing_destination_intact() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_ops = DefaultFileOperations::new_unchecked(temp_dir.path().to_path_buf());
+        let compressor = crate::compression::ZlibCompressor::new();
+        let mut corrupt = compressor
+            .compress(&b"streamed line of text\n".repeat(50_000))
+            .unwrap();
+        corrupt.truncate(corrupt.len() / 2);
+        corrupt.extend_from_slice(b"not zlib");
+        file_ops
+            .write_file(Path::new("x.zlib"), &corrupt)
+            .await
+            .unwrap();
+        file_ops
+            .write_file(Path::new("x"), b"existing content")
+            .await
+            .unwrap();
+
+        assert!(
+            file_ops
+                .decompress_file(Path::new("x.zlib"), Path::new("x"), &compressor)
+                .await
+                .is_err()
+        );
+
+        assert_eq!(
+            file_ops.read_file(Path::new("x")).await.unwrap(),
+            b"existing content"
+        );
+        let leftovers = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().ends_with(".part")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", windows))]
+    use crate::file_watch::ChangeKind;
+
+    /// Skips unrelated changes, such as a second modify event for an earlier write.
+    #[cfg(any(target_os = "linux", target_os = "macos", windows))]
+    async fn expect_change(
+        changes: &mut mpsc::Receiver<FileChangeEvent>,
+        path: &str,
+        kind: ChangeKind,
+    ) {
+        let expected = FileChangeEvent {
+            path: PathBuf::from(path),
+            kind,
+        };
+        let wait = async {
+            while let Some(change) = changes.recv().await {
+                if change == expected {
+                    return;
+                }
+            }
+            panic!("watch ended before {:?}", expected);
+        };
+        tokio::time::timeout(Duration::from_millis(500), wait)
+            .await
+            .unwrap_or_else(|_| panic!("{:?} not reported within 500ms", expected));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", windows))]
+    #[tokio::test]
+    async fn test_watch_dir_reports_created_modified_and_deleted_files() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("watched")).unwrap();
+        std::fs::write(temp_dir.path().join("watched/old.txt"), b"old").unwrap();
+        let file_ops = DefaultFileOperations::new(temp_dir.path().to_path_buf())
+            .await
+            .unwrap();
+        let mut changes = file_ops.watch_dir(Path::new("watched")).unwrap();
+
+        std::fs::File::create(temp_dir.path().join("watched/new.txt")).unwrap();
+        expect_change(&mut changes, "watched/new.txt", ChangeKind::Created).await;
+
+        std::fs::write(temp_dir.path().join("watched/old.txt"), b"changed").unwrap();
+        expect_change(&mut changes, "watched/old.txt", ChangeKind::Modified).await;
+
+        std::fs::remove_file(temp_dir.path().join("watched/new.txt")).unwrap();
+        expect_change(&mut changes, "watched/new.txt", ChangeKind::Deleted).await;
+    }
+
+    // kqueue cannot pair the two halves of a rename.
+    #[cfg(any(target_os = "linux", windows))]
+    #[tokio::test]
+    async fn test_watch_dir_reports_renames() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("before.txt"), b"data").unwrap();
+        let file_ops = DefaultFileOperations::new(temp_dir.path().to_path_buf())
+            .await
+            .unwrap();
+        let mut changes = file_ops.watch_dir(Path::new("")).unwrap();
+
+        std::fs::rename(
+            temp_dir.path().join("before.txt"),
+            temp_dir.path().join("after.txt"),
+        )
+        .unwrap();
+
+        expect_change(
+            &mut changes,
+            "before.txt",
+            ChangeKind::Renamed(PathBuf::from("after.txt")),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_watch_dir_rejects_files() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("file.txt"), b"data").unwrap();
+        let file_ops = DefaultFileOperations::new(temp_dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        assert!(file_ops.watch_dir(Path::new("file.txt")).is_err());
+    }
 }
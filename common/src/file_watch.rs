@@ -0,0 +1,492 @@
+use crate::error::{FenrisError, Result};
+#[cfg(any(target_os = "linux", target_os = "macos", windows))]
+use crate::file_ops::is_system_file_name;
+#[cfg(any(target_os = "linux", target_os = "macos", windows))]
+use std::path::Path;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+
+const WATCH_CHANNEL_CAPACITY: usize = 64;
+
+// How often an idle watcher checks whether its receiver is gone, and so how long a dropped
+// watch lingers before the OS handle is released.
+#[cfg(any(target_os = "linux", target_os = "macos", windows))]
+const WATCH_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+    /// The entry moved to the given path inside the same directory. Moves in or out of the
+    /// directory show up as `Created` and `Deleted` instead.
+    Renamed(PathBuf),
+}
+
+/// One change to an entry directly inside a watched directory. `path` is the watched path as
+/// the caller gave it joined with the entry's name, never the path on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChangeEvent {
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+}
+
+/// Starts watching `full_path` on a blocking thread. The watch is registered before this
+/// returns, so changes made afterwards are never missed, and it is removed once the receiver
+/// is dropped.
+pub(crate) fn watch_dir(
+    full_path: PathBuf,
+    reported: PathBuf,
+) -> Result<mpsc::Receiver<FileChangeEvent>> {
+    let (tx, rx) = mpsc::channel(WATCH_CHANNEL_CAPACITY);
+    platform::watch(full_path, reported, tx)?;
+    Ok(rx)
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos", windows))]
+fn watch_error(error: std::io::Error) -> FenrisError {
+    FenrisError::file_operation_from(format!("Failed to watch directory: {}", error), error)
+}
+
+/// Sends `changes` in order, collapsing repeats such as the several modify events one write
+/// can raise. Returns `false` once nobody is listening.
+#[cfg(any(target_os = "linux", target_os = "macos", windows))]
+fn forward(tx: &mpsc::Sender<FileChangeEvent>, changes: Vec<FileChangeEvent>) -> bool {
+    let mut changes: Vec<_> = changes.into_iter().filter_map(visible_change).collect();
+    changes.dedup();
+    changes
+        .into_iter()
+        .all(|change| tx.blocking_send(change).is_ok())
+}
+
+/// Drops changes to system files, which clients never see. A rename across that line is
+/// treated like a move in or out of the directory, so a temp file renamed into place reports
+/// as `Created`.
+#[cfg(any(target_os = "linux", target_os = "macos", windows))]
+fn visible_change(change: FileChangeEvent) -> Option<FileChangeEvent> {
+    let hidden = |path: &Path| path.file_name().is_some_and(is_system_file_name);
+    match change.kind {
+        ChangeKind::Renamed(to) => match (hidden(&change.path), hidden(&to)) {
+            (false, false) => Some(FileChangeEvent {
+                path: change.path,
+                kind: ChangeKind::Renamed(to),
+            }),
+            (true, false) => Some(FileChangeEvent {
+                path: to,
+                kind: ChangeKind::Created,
+            }),
+            (false, true) => Some(FileChangeEvent {
+                path: change.path,
+                kind: ChangeKind::Deleted,
+            }),
+            (true, true) => None,
+        },
+        _ if hidden(&change.path) => None,
+        _ => Some(change),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+    use inotify::{EventMask, Inotify, WatchMask};
+    use std::collections::HashMap;
+    use std::io::ErrorKind;
+    use tracing::warn;
+
+    pub(super) fn watch(
+        full_path: PathBuf,
+        reported: PathBuf,
+        tx: mpsc::Sender<FileChangeEvent>,
+    ) -> Result<()> {
+        let mut inotify = Inotify::init().map_err(watch_error)?;
+        inotify
+            .watches()
+            .add(
+                &full_path,
+                WatchMask::CREATE
+                    | WatchMask::MODIFY
+                    | WatchMask::DELETE
+                    | WatchMask::MOVED_FROM
+                    | WatchMask::MOVED_TO,
+            )
+            .map_err(watch_error)?;
+
+        tokio::task::spawn_blocking(move || {
+            let mut buffer = [0u8; 4096];
+            loop {
+                // The descriptor is non-blocking, so an idle watch comes back here to notice
+                // a dropped receiver. Closing it on return removes the watch.
+                let events = match inotify.read_events(&mut buffer) {
+                    Ok(events) => events,
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                        if tx.is_closed() {
+                            return;
+                        }
+                        std::thread::sleep(WATCH_POLL_INTERVAL);
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!("Watch on {:?} stopped: {}", full_path, e);
+                        return;
+                    }
+                };
+
+                // Both halves of a rename arrive in the same read, tied by a cookie.
+                let mut moved_from = HashMap::new();
+                let mut changes = Vec::new();
+                for event in events {
+                    let Some(name) = event.name else {
+                        continue;
+                    };
+                    let path = reported.join(name);
+                    let kind = if event.mask.contains(EventMask::MOVED_FROM) {
+                        moved_from.insert(event.cookie, path);
+                        continue;
+                    } else if event.mask.contains(EventMask::MOVED_TO) {
+                        match moved_from.remove(&event.cookie) {
+                            Some(from) => {
+                                changes.push(FileChangeEvent {
+                                    path: from,
+                                    kind: ChangeKind::Renamed(path),
+                                });
+                                continue;
+                            }
+                            None => ChangeKind::Created,
+                        }
+                    } else if event.mask.contains(EventMask::CREATE) {
+                        ChangeKind::Created
+                    } else if event.mask.contains(EventMask::DELETE) {
+                        ChangeKind::Deleted
+                    } else if event.mask.contains(EventMask::MODIFY) {
+                        ChangeKind::Modified
+                    } else {
+                        continue;
+                    };
+                    changes.push(FileChangeEvent { path, kind });
+                }
+                changes.extend(moved_from.into_values().map(|path| FileChangeEvent {
+                    path,
+                    kind: ChangeKind::Deleted,
+                }));
+
+                if !forward(&tx, changes) {
+                    return;
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+    use kqueue::{EventFilter, FilterFlag, Ident, Watcher};
+    use std::collections::HashSet;
+    use std::ffi::OsString;
+    use tracing::warn;
+
+    fn entry_flags() -> FilterFlag {
+        FilterFlag::NOTE_WRITE | FilterFlag::NOTE_EXTEND
+    }
+
+    fn entries(dir: &Path) -> std::io::Result<HashSet<OsString>> {
+        std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect()
+    }
+
+    /// kqueue only says that a directory changed, not what changed in it, so the watcher keeps
+    /// the last listing to diff against and a separate watch on every entry for writes.
+    /// Renames therefore show up as a `Deleted` and a `Created`.
+    pub(super) fn watch(
+        full_path: PathBuf,
+        reported: PathBuf,
+        tx: mpsc::Sender<FileChangeEvent>,
+    ) -> Result<()> {
+        let mut watcher = Watcher::new().map_err(watch_error)?;
+        let mut known = entries(&full_path).map_err(watch_error)?;
+        watcher
+            .add_filename(
+                &full_path,
+                EventFilter::EVFILT_VNODE,
+                FilterFlag::NOTE_WRITE,
+            )
+            .map_err(watch_error)?;
+        for name in &known {
+            // An entry that vanished in the meantime turns up in the first diff instead.
+            let _ = watcher.add_filename(
+                full_path.join(name),
+                EventFilter::EVFILT_VNODE,
+                entry_flags(),
+            );
+        }
+        watcher.watch().map_err(watch_error)?;
+
+        tokio::task::spawn_blocking(move || {
+            loop {
+                let Some(event) = watcher.poll(Some(WATCH_POLL_INTERVAL)) else {
+                    if tx.is_closed() {
+                        return;
+                    }
+                    continue;
+                };
+                let Ident::Filename(_, changed) = &event.ident else {
+                    continue;
+                };
+
+                let changes = if Path::new(changed) == full_path {
+                    let current = match entries(&full_path) {
+                        Ok(current) => current,
+                        Err(e) => {
+                            warn!("Watch on {:?} stopped: {}", full_path, e);
+                            return;
+                        }
+                    };
+                    let mut changes = Vec::new();
+                    for name in current.difference(&known) {
+                        let _ = watcher.add_filename(
+                            full_path.join(name),
+                            EventFilter::EVFILT_VNODE,
+                            entry_flags(),
+                        );
+                        changes.push(FileChangeEvent {
+                            path: reported.join(name),
+                            kind: ChangeKind::Created,
+                        });
+                    }
+                    for name in known.difference(&current) {
+                        let _ = watcher
+                            .remove_filename(full_path.join(name), EventFilter::EVFILT_VNODE);
+                        changes.push(FileChangeEvent {
+                            path: reported.join(name),
+                            kind: ChangeKind::Deleted,
+                        });
+                    }
+                    if let Err(e) = watcher.watch() {
+                        warn!("Watch on {:?} stopped: {}", full_path, e);
+                        return;
+                    }
+                    known = current;
+                    changes
+                } else {
+                    match Path::new(changed).file_name() {
+                        Some(name) => vec![FileChangeEvent {
+                            path: reported.join(name),
+                            kind: ChangeKind::Modified,
+                        }],
+                        None => continue,
+                    }
+                };
+
+                if !forward(&tx, changes) {
+                    return;
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::ffi::OsString;
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    use std::ptr;
+    use tracing::warn;
+    use winapi::shared::minwindef::{DWORD, FALSE, TRUE};
+    use winapi::shared::winerror::WAIT_TIMEOUT;
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::fileapi::{CreateFileW, OPEN_EXISTING};
+    use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+    use winapi::um::ioapiset::{CancelIoEx, GetOverlappedResult, GetOverlappedResultEx};
+    use winapi::um::minwinbase::OVERLAPPED;
+    use winapi::um::synchapi::CreateEventW;
+    use winapi::um::winbase::{
+        FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OVERLAPPED, ReadDirectoryChangesW,
+    };
+    use winapi::um::winnt::{
+        FILE_ACTION_ADDED, FILE_ACTION_MODIFIED, FILE_ACTION_REMOVED, FILE_ACTION_RENAMED_NEW_NAME,
+        FILE_ACTION_RENAMED_OLD_NAME, FILE_LIST_DIRECTORY, FILE_NOTIFY_CHANGE_DIR_NAME,
+        FILE_NOTIFY_CHANGE_FILE_NAME, FILE_NOTIFY_CHANGE_LAST_WRITE, FILE_NOTIFY_CHANGE_SIZE,
+        FILE_NOTIFY_INFORMATION, FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE, HANDLE,
+    };
+
+    /// Owns the directory handle and its completion event so every exit path closes both.
+    struct DirHandle {
+        dir: HANDLE,
+        event: HANDLE,
+    }
+
+    // Both are kernel handles, usable from any thread.
+    unsafe impl Send for DirHandle {}
+
+    impl Drop for DirHandle {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.event);
+                CloseHandle(self.dir);
+            }
+        }
+    }
+
+    pub(super) fn watch(
+        full_path: PathBuf,
+        reported: PathBuf,
+        tx: mpsc::Sender<FileChangeEvent>,
+    ) -> Result<()> {
+        let wide: Vec<u16> = full_path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let dir = unsafe {
+            CreateFileW(
+                wide.as_ptr(),
+                FILE_LIST_DIRECTORY,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                ptr::null_mut(),
+                OPEN_EXISTING,
+                FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OVERLAPPED,
+                ptr::null_mut(),
+            )
+        };
+        if dir == INVALID_HANDLE_VALUE {
+            return Err(watch_error(std::io::Error::last_os_error()));
+        }
+        let event = unsafe { CreateEventW(ptr::null_mut(), TRUE, FALSE, ptr::null()) };
+        if event.is_null() {
+            let error = std::io::Error::last_os_error();
+            unsafe { CloseHandle(dir) };
+            return Err(watch_error(error));
+        }
+        let handle = DirHandle { dir, event };
+
+        tokio::task::spawn_blocking(move || {
+            let handle = handle;
+            // DWORD-aligned, as ReadDirectoryChangesW requires.
+            let mut buffer = vec![0u32; 4096];
+            let mut renamed_from = None;
+            loop {
+                let mut overlapped: OVERLAPPED = unsafe { std::mem::zeroed() };
+                overlapped.hEvent = handle.event;
+                let started = unsafe {
+                    ReadDirectoryChangesW(
+                        handle.dir,
+                        buffer.as_mut_ptr().cast(),
+                        (buffer.len() * 4) as DWORD,
+                        FALSE,
+                        FILE_NOTIFY_CHANGE_FILE_NAME
+                            | FILE_NOTIFY_CHANGE_DIR_NAME
+                            | FILE_NOTIFY_CHANGE_LAST_WRITE
+                            | FILE_NOTIFY_CHANGE_SIZE,
+                        ptr::null_mut(),
+                        &mut overlapped,
+                        None,
+                    )
+                };
+                if started == 0 {
+                    warn!(
+                        "Watch on {:?} stopped: {}",
+                        full_path,
+                        std::io::Error::last_os_error()
+                    );
+                    return;
+                }
+
+                let mut len: DWORD = 0;
+                loop {
+                    let done = unsafe {
+                        GetOverlappedResultEx(
+                            handle.dir,
+                            &mut overlapped,
+                            &mut len,
+                            WATCH_POLL_INTERVAL.as_millis() as DWORD,
+                            FALSE,
+                        )
+                    };
+                    if done != 0 {
+                        break;
+                    }
+                    if unsafe { GetLastError() } != WAIT_TIMEOUT {
+                        warn!(
+                            "Watch on {:?} stopped: {}",
+                            full_path,
+                            std::io::Error::last_os_error()
+                        );
+                        return;
+                    }
+                    if tx.is_closed() {
+                        // The read must finish before `overlapped` and `buffer` go away.
+                        unsafe {
+                            CancelIoEx(handle.dir, &mut overlapped);
+                            GetOverlappedResult(handle.dir, &mut overlapped, &mut len, TRUE);
+                        }
+                        return;
+                    }
+                }
+
+                // Zero bytes means the buffer overflowed and the changes were lost.
+                let mut changes = Vec::new();
+                let mut offset = 0usize;
+                while len > 0 {
+                    let info = unsafe {
+                        &*(buffer.as_ptr().cast::<u8>().add(offset)
+                            as *const FILE_NOTIFY_INFORMATION)
+                    };
+                    let name = unsafe {
+                        std::slice::from_raw_parts(
+                            info.FileName.as_ptr(),
+                            info.FileNameLength as usize / 2,
+                        )
+                    };
+                    let path = reported.join(OsString::from_wide(name));
+                    let kind = match info.Action {
+                        FILE_ACTION_ADDED => Some(ChangeKind::Created),
+                        FILE_ACTION_REMOVED => Some(ChangeKind::Deleted),
+                        FILE_ACTION_MODIFIED => Some(ChangeKind::Modified),
+                        FILE_ACTION_RENAMED_OLD_NAME => {
+                            renamed_from = Some(path.clone());
+                            None
+                        }
+                        FILE_ACTION_RENAMED_NEW_NAME => match renamed_from.take() {
+                            Some(from) => {
+                                changes.push(FileChangeEvent {
+                                    path: from,
+                                    kind: ChangeKind::Renamed(path.clone()),
+                                });
+                                None
+                            }
+                            None => Some(ChangeKind::Created),
+                        },
+                        _ => None,
+                    };
+                    if let Some(kind) = kind {
+                        changes.push(FileChangeEvent { path, kind });
+                    }
+
+                    if info.NextEntryOffset == 0 {
+                        break;
+                    }
+                    offset += info.NextEntryOffset as usize;
+                }
+
+                if !forward(&tx, changes) {
+                    return;
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    use super::*;
+
+    pub(super) fn watch(
+        _full_path: PathBuf,
+        _reported: PathBuf,
+        _tx: mpsc::Sender<FileChangeEvent>,
+    ) -> Result<()> {
+        Err(FenrisError::file_operation("file watching not supported"))
+    }
+}
@@ -1,8 +1,5 @@
 use crate::{FenrisError, Result};
-use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
-    net::TcpStream,
-};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tracing::{debug, trace};
 
 pub const DEFAULT_MAX_FRAME_SIZE: usize = 1024 * 1024;
@@ -20,6 +17,17 @@ impl Default for FrameLimits {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FramingMode {
+    pub checksummed: bool,
+}
+
+impl FramingMode {
+    pub fn checksummed() -> Self {
+        Self { checksummed: true }
+    }
+}
+
 pub struct LengthPrefixedFrame;
 
 impl LengthPrefixedFrame {
@@ -45,7 +53,10 @@ impl LengthPrefixedFrame {
         Ok(len)
     }
 
-    pub async fn send(stream: &mut TcpStream, data: &[u8], limits: FrameLimits) -> Result<()> {
+    pub async fn send<W>(stream: &mut W, data: &[u8], limits: FrameLimits) -> Result<()>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
         if data.len() > limits.max_frame_size {
             return Err(FenrisError::FrameTooLarge {
                 max: limits.max_frame_size,
@@ -63,7 +74,10 @@ impl LengthPrefixedFrame {
         Ok(())
     }
 
-    pub async fn receive(stream: &mut TcpStream, limits: FrameLimits) -> Result<Vec<u8>> {
+    pub async fn receive<R>(stream: &mut R, limits: FrameLimits) -> Result<Vec<u8>>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
         let mut length_buf = [0u8; 4];
         stream.read_exact(&mut length_buf).await?;
 
@@ -78,6 +92,66 @@ impl LengthPrefixedFrame {
     }
 }
 
+pub struct ChecksummedFrame;
+
+impl ChecksummedFrame {
+    pub fn checksum(data: &[u8]) -> u32 {
+        crc32fast::hash(data)
+    }
+
+    pub async fn send<W>(stream: &mut W, data: &[u8], limits: FrameLimits) -> Result<()>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        if data.len() > limits.max_frame_size {
+            return Err(FenrisError::FrameTooLarge {
+                max: limits.max_frame_size,
+                got: data.len(),
+            });
+        }
+
+        let length_buf = LengthPrefixedFrame::encode_len(data.len())?;
+        let checksum = Self::checksum(data);
+
+        trace!(
+            "Sending {} bytes with checksum {:#010x}",
+            data.len(),
+            checksum
+        );
+        stream.write_all(&length_buf).await?;
+        stream.write_all(&checksum.to_be_bytes()).await?;
+        stream.write_all(data).await?;
+        debug!("Sent {} checksummed bytes", data.len());
+
+        Ok(())
+    }
+
+    pub async fn receive<R>(stream: &mut R, limits: FrameLimits) -> Result<Vec<u8>>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        let mut length_buf = [0u8; 4];
+        stream.read_exact(&mut length_buf).await?;
+        let length = LengthPrefixedFrame::decode_len(length_buf, limits)?;
+
+        let mut checksum_buf = [0u8; 4];
+        stream.read_exact(&mut checksum_buf).await?;
+        let expected = u32::from_be_bytes(checksum_buf);
+        trace!("Expecting to receive {} checksummed bytes", length);
+
+        let mut data = vec![0u8; length];
+        stream.read_exact(&mut data).await?;
+
+        let got = Self::checksum(&data);
+        if got != expected {
+            return Err(FenrisError::ChecksumMismatch { expected, got });
+        }
+        debug!("Received {} checksummed bytes", length);
+
+        Ok(data)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -230,4 +304,41 @@ mod tests {
             Err(FenrisError::FrameTooLarge { max: 4, got: 5 })
         ));
     }
+
+    #[tokio::test]
+    async fn checksummed_frame_round_trip() {
+        let (mut client, mut server) = setup_connection().await;
+        let message = b"checksummed frame";
+
+        tokio::spawn(async move {
+            ChecksummedFrame::send(&mut client, message, FrameLimits::default())
+                .await
+                .unwrap();
+        });
+
+        let received = ChecksummedFrame::receive(&mut server, FrameLimits::default())
+            .await
+            .unwrap();
+
+        assert_eq!(received, message);
+    }
+
+    #[tokio::test]
+    async fn checksummed_frame_rejects_corrupted_payload() {
+        let (mut client, mut server) = setup_connection().await;
+        let expected = ChecksummedFrame::checksum(b"payload");
+
+        tokio::spawn(async move {
+            client.write_all(&7u32.to_be_bytes()).await.unwrap();
+            client.write_all(&expected.to_be_bytes()).await.unwrap();
+            client.write_all(b"paylaod").await.unwrap();
+        });
+
+        let result = ChecksummedFrame::receive(&mut server, FrameLimits::default()).await;
+
+        assert!(matches!(
+            result,
+            Err(FenrisError::ChecksumMismatch { expected: e, .. }) if e == expected
+        ));
+    }
 }
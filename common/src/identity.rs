@@ -8,6 +8,7 @@ pub const SERVER_IDENTITY_SIGNATURE_SIZE: usize = 64;
 
 const SERVER_IDENTITY_TRANSCRIPT_LABEL: &[u8] = b"fenris-server-identity-v1";
 const AUTHENTICATED_KDF_CONTEXT_LABEL: &[u8] = b"fenris-authenticated-kdf-v1";
+const EXTENSION_KDF_CONTEXT_LABEL: &[u8] = b"fenris-extension-kdf-v1";
 
 #[derive(Clone)]
 pub struct ServerIdentityKey {
@@ -59,11 +60,14 @@ impl ServerIdentityKey {
     pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self> {
         let path = path.as_ref();
         let encoded = std::fs::read_to_string(path).map_err(|e| {
-            FenrisError::FileOperationError(format!(
-                "Failed to read server identity key {}: {}",
-                path.display(),
-                e
-            ))
+            FenrisError::file_operation_from(
+                format!(
+                    "Failed to read server identity key {}: {}",
+                    path.display(),
+                    e
+                ),
+                e,
+            )
         })?;
 
         Self::from_hex(encoded.trim())
@@ -76,20 +80,26 @@ impl ServerIdentityKey {
             && !parent.as_os_str().is_empty()
         {
             std::fs::create_dir_all(parent).map_err(|e| {
-                FenrisError::FileOperationError(format!(
-                    "Failed to create server identity key directory {}: {}",
-                    parent.display(),
-                    e
-                ))
+                FenrisError::file_operation_from(
+                    format!(
+                        "Failed to create server identity key directory {}: {}",
+                        parent.display(),
+                        e
+                    ),
+                    e,
+                )
             })?;
         }
 
         std::fs::write(path, format!("{}\n", self.to_hex())).map_err(|e| {
-            FenrisError::FileOperationError(format!(
-                "Failed to write server identity key {}: {}",
-                path.display(),
-                e
-            ))
+            FenrisError::file_operation_from(
+                format!(
+                    "Failed to write server identity key {}: {}",
+                    path.display(),
+                    e
+                ),
+                e,
+            )
         })
     }
 
@@ -131,11 +141,14 @@ impl ServerIdentityPublicKey {
     pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self> {
         let path = path.as_ref();
         let encoded = std::fs::read_to_string(path).map_err(|e| {
-            FenrisError::FileOperationError(format!(
-                "Failed to read pinned server identity {}: {}",
-                path.display(),
-                e
-            ))
+            FenrisError::file_operation_from(
+                format!(
+                    "Failed to read pinned server identity {}: {}",
+                    path.display(),
+                    e
+                ),
+                e,
+            )
         })?;
 
         Self::from_hex(encoded.trim())
@@ -185,6 +198,19 @@ pub(crate) fn authenticated_kdf_context(transcript: &[u8]) -> Vec<u8> {
     context
 }
 
+pub(crate) fn extension_kdf_context(
+    kdf_context: &[u8],
+    client_extension: &[u8],
+    server_extension: &[u8],
+) -> Vec<u8> {
+    let mut context = Vec::new();
+    append_transcript_part(&mut context, EXTENSION_KDF_CONTEXT_LABEL);
+    append_transcript_part(&mut context, kdf_context);
+    append_transcript_part(&mut context, client_extension);
+    append_transcript_part(&mut context, server_extension);
+    context
+}
+
 fn append_transcript_part(out: &mut Vec<u8>, part: &[u8]) {
     out.extend_from_slice(&(part.len() as u64).to_be_bytes());
     out.extend_from_slice(part);
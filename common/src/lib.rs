@@ -1,39 +1,72 @@
+pub mod archive;
 pub mod compression;
 pub mod config;
 pub mod crypto;
 pub mod domain;
 pub mod error;
 pub mod file_ops;
+pub mod file_watch;
 pub mod framing;
 pub mod identity;
 pub mod network;
 pub mod proto;
 pub mod protocol;
+pub mod psk;
+pub mod quota;
 pub mod secure_channel;
 pub mod storage;
+#[cfg(feature = "tls")]
+pub mod tls;
+pub mod transport;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
 #[cfg(feature = "zstd")]
 pub use compression::ZstdCompressor;
-pub use compression::{CompressionManager, ZlibCompressor};
+pub use compression::{AdaptiveCompressor, CompressionManager, ZlibCompressor};
 pub use config::{
-    CompressionConfig, CompressionOf, Config, CryptoConfig, CryptoOf, DefaultSuite, Protobuf,
-    ProtocolCodecOf, ProtocolConfig, SecureChannelConfig, Zlib, ZlibWithLevel,
+    CompressionConfig, CompressionOf, Config, CryptoConfig, CryptoOf, DefaultKeyDeriver,
+    DefaultSuite, NistCryptoSuite, Protobuf, ProtocolCodecOf, ProtocolConfig, SecureChannelConfig,
+    StrongKeyDeriver, StrongSuite, Zlib, ZlibWithLevel,
 };
 #[cfg(feature = "zstd")]
 pub use config::{Zstd, ZstdWithLevel};
-pub use crypto::{CryptoManager, IV_SIZE, KEY_SIZE, TAG_SIZE};
+pub use crypto::{CryptoManager, IV_SIZE, KEY_SIZE, SecureRandom, SessionKey, TAG_SIZE};
 pub use domain::{
-    DEFAULT_TRANSFER_CHUNK_SIZE, FenrisCommand, FenrisMetadata, FenrisOutput, ObjectWriteMode,
-    TransferChunk,
+    DEFAULT_TRANSFER_CHUNK_SIZE, DirFilter, FenrisCommand, FenrisMetadata, FenrisOutput, ListSort,
+    ListSortKey, MAX_MANIFEST_ENTRIES, ObjectWriteMode, TransferChunk, WatchEvent, WatchEventKind,
+    is_valid_env_name,
 };
 pub use error::{FenrisError, Result};
-pub use file_ops::{DefaultFileOperations, FileMetadata, FileOperations};
-pub use framing::{DEFAULT_MAX_FRAME_SIZE, FrameLimits, LengthPrefixedFrame};
+pub use file_ops::{
+    DEFAULT_LOCK_TIMEOUT, DefaultFileOperations, FileMetadata, FileOperations, MAX_LIST_DEPTH,
+};
+pub use file_watch::{ChangeKind, FileChangeEvent};
+pub use framing::{
+    ChecksummedFrame, DEFAULT_MAX_FRAME_SIZE, FrameLimits, FramingMode, LengthPrefixedFrame,
+};
 pub use identity::{ServerIdentityKey, ServerIdentityPublicKey};
 pub use network::{
-    receive_prefixed, receive_prefixed_with_limits, send_prefixed, send_prefixed_with_limits,
+    DEFAULT_MAX_MESSAGE_SIZE, receive_prefixed, receive_prefixed_bounded,
+    receive_prefixed_with_checksum, receive_prefixed_with_checksum_bounded,
+    receive_prefixed_with_limits, send_prefixed, send_prefixed_with_checksum,
+    send_prefixed_with_limits,
 };
-pub use proto::{Request, RequestType, Response, ResponseType};
+pub use proto::{HandshakeExtension, Request, RequestType, Response, ResponseType};
 pub use protocol::{ProtobufCodec, ProtocolCodec};
-pub use secure_channel::{DefaultSecureChannel, SecureChannel};
-pub use storage::{MemoryStorage, ObjectChunk, StorageBackend, TokioFsStorage};
+pub use psk::PSK_NONCE_SIZE;
+pub use quota::{ANONYMOUS_ACCOUNT, QUOTAS_FILE, QuotaManager, QuotaUsage};
+#[cfg(feature = "tls")]
+pub use secure_channel::TlsSecureChannel;
+pub use secure_channel::{
+    DEFAULT_COMPRESSION_THRESHOLD, DEFAULT_KDF_CONTEXT, DefaultSecureChannel, PROTOCOL_VERSION,
+    SecureChannel, SecureChannelReader, SecureChannelWriter,
+};
+pub use storage::{MemoryStorage, ObjectChunk, ObjectReader, StorageBackend, TokioFsStorage};
+#[cfg(feature = "tls")]
+pub use tls::{
+    TlsAcceptor, TlsClientConfig, TlsConfig, TlsConnector, TlsReadHalf, TlsTransport, TlsWriteHalf,
+};
+pub use transport::{SplitStream, Transport, TransportReadHalf, TransportWriteHalf};
+#[cfg(feature = "websocket")]
+pub use websocket::{WebSocketReadHalf, WebSocketTransport, WebSocketWriteHalf};
@@ -1,32 +1,130 @@
 use crate::{
-    error::Result,
-    framing::{FrameLimits, LengthPrefixedFrame},
+    error::{FenrisError, Result},
+    framing::{ChecksummedFrame, FrameLimits, LengthPrefixedFrame},
 };
-use tokio::net::TcpStream;
+use std::future::Future;
+use std::io;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
 
-pub async fn send_prefixed(stream: &mut TcpStream, data: &[u8]) -> Result<()> {
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+pub async fn send_prefixed<W>(stream: &mut W, data: &[u8]) -> Result<()>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+{
     send_prefixed_with_limits(stream, data, FrameLimits::default()).await
 }
 
-pub async fn receive_prefixed(stream: &mut TcpStream) -> Result<Vec<u8>> {
-    receive_prefixed_with_limits(stream, FrameLimits::default()).await
+pub async fn receive_prefixed<R>(stream: &mut R) -> Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
+    receive_prefixed_with_timeout(stream, None).await
+}
+
+/// Fails with a `TimedOut` network error if the whole frame has not arrived within `timeout`,
+/// so a peer that stalls mid-frame cannot park the reading task forever.
+pub async fn receive_prefixed_with_timeout<R>(
+    stream: &mut R,
+    timeout: Option<Duration>,
+) -> Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
+    with_read_timeout(
+        timeout,
+        receive_prefixed_with_limits(stream, FrameLimits::default()),
+    )
+    .await
 }
 
-pub async fn send_prefixed_with_limits(
-    stream: &mut TcpStream,
+pub async fn send_prefixed_with_limits<W>(
+    stream: &mut W,
     data: &[u8],
     limits: FrameLimits,
-) -> Result<()> {
+) -> Result<()>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+{
     LengthPrefixedFrame::send(stream, data, limits).await
 }
 
-pub async fn receive_prefixed_with_limits(
-    stream: &mut TcpStream,
-    limits: FrameLimits,
-) -> Result<Vec<u8>> {
+pub async fn receive_prefixed_with_limits<R>(stream: &mut R, limits: FrameLimits) -> Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
     LengthPrefixedFrame::receive(stream, limits).await
 }
 
+/// Rejects a frame whose length prefix exceeds `max_bytes` before its buffer is allocated, so a
+/// peer cannot make us reserve memory just by announcing a large frame.
+pub async fn receive_prefixed_bounded<R>(stream: &mut R, max_bytes: usize) -> Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
+    let limits = FrameLimits {
+        max_frame_size: max_bytes,
+    };
+    LengthPrefixedFrame::receive(stream, limits)
+        .await
+        .map_err(oversized_as_protocol_error)
+}
+
+pub async fn send_prefixed_with_checksum<W>(stream: &mut W, data: &[u8]) -> Result<()>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    ChecksummedFrame::send(stream, data, FrameLimits::default()).await
+}
+
+pub async fn receive_prefixed_with_checksum<R>(stream: &mut R) -> Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
+    ChecksummedFrame::receive(stream, FrameLimits::default()).await
+}
+
+pub async fn receive_prefixed_with_checksum_bounded<R>(
+    stream: &mut R,
+    max_bytes: usize,
+) -> Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
+    let limits = FrameLimits {
+        max_frame_size: max_bytes,
+    };
+    ChecksummedFrame::receive(stream, limits)
+        .await
+        .map_err(oversized_as_protocol_error)
+}
+
+pub(crate) async fn with_read_timeout<T>(
+    timeout: Option<Duration>,
+    read: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let Some(timeout) = timeout else {
+        return read.await;
+    };
+
+    tokio::time::timeout(timeout, read)
+        .await
+        .unwrap_or_else(|_| {
+            Err(FenrisError::NetworkError(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("read timed out after {}ms", timeout.as_millis()),
+            )))
+        })
+}
+
+fn oversized_as_protocol_error(error: FenrisError) -> FenrisError {
+    match error {
+        FenrisError::FrameTooLarge { .. } => FenrisError::InvalidProtocolMessage,
+        error => error,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,4 +203,82 @@ mod tests {
             Err(FenrisError::FrameTooLarge { max: 4, got: 5 })
         ));
     }
+
+    #[tokio::test]
+    async fn receive_prefixed_bounded_rejects_huge_length_prefix_without_waiting_for_body() {
+        let (mut client, mut server) = setup_connection().await;
+
+        // Only the prefix is sent: reading a 4 GiB body would hang rather than fail.
+        client.write_all(&u32::MAX.to_be_bytes()).await.unwrap();
+
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            receive_prefixed_bounded(&mut server, DEFAULT_MAX_MESSAGE_SIZE),
+        )
+        .await
+        .expect("oversized frame should be rejected immediately");
+
+        assert!(matches!(result, Err(FenrisError::InvalidProtocolMessage)));
+    }
+
+    #[tokio::test]
+    async fn receive_prefixed_bounded_accepts_frames_up_to_the_limit() {
+        let (mut client, mut server) = setup_connection().await;
+
+        tokio::spawn(async move {
+            send_prefixed(&mut client, b"1234").await.unwrap();
+        });
+
+        assert_eq!(
+            receive_prefixed_bounded(&mut server, 4).await.unwrap(),
+            b"1234"
+        );
+    }
+
+    #[tokio::test]
+    async fn receive_prefixed_with_timeout_fails_on_a_stalled_frame() {
+        let (mut client, mut server) = setup_connection().await;
+
+        // The prefix promises 5 bytes but only 2 ever arrive.
+        client.write_all(&5u32.to_be_bytes()).await.unwrap();
+        client.write_all(b"he").await.unwrap();
+
+        let result =
+            receive_prefixed_with_timeout(&mut server, Some(Duration::from_millis(50))).await;
+
+        assert!(matches!(
+            result,
+            Err(FenrisError::NetworkError(e)) if e.kind() == io::ErrorKind::TimedOut
+        ));
+    }
+
+    #[tokio::test]
+    async fn receive_prefixed_with_timeout_returns_frames_that_arrive_in_time() {
+        let (mut client, mut server) = setup_connection().await;
+
+        send_prefixed(&mut client, b"hello").await.unwrap();
+
+        let received = receive_prefixed_with_timeout(&mut server, Some(Duration::from_secs(5)))
+            .await
+            .unwrap();
+
+        assert_eq!(received, b"hello");
+    }
+
+    #[tokio::test]
+    async fn test_send_receive_prefixed_with_checksum() {
+        let (mut client, mut server) = setup_connection().await;
+
+        let message = b"Hello, Checksum!";
+
+        tokio::spawn(async move {
+            send_prefixed_with_checksum(&mut client, message)
+                .await
+                .unwrap();
+        });
+
+        let received = receive_prefixed_with_checksum(&mut server).await.unwrap();
+
+        assert_eq!(received, message);
+    }
 }
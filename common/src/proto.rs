@@ -1,6 +1,9 @@
 include!(concat!(env!("OUT_DIR"), "/fenris.rs"));
 
-use crate::{ProtobufCodec, ProtocolCodec, error::Result};
+use crate::{
+    ProtobufCodec, ProtocolCodec,
+    error::{FenrisError, Result},
+};
 
 impl Request {
     pub fn to_bytes(&self) -> Result<Vec<u8>> {
@@ -22,6 +25,37 @@ impl Response {
     }
 }
 
+impl HandshakeExtension {
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        ProtobufCodec::encode(self)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        ProtobufCodec::decode(data)
+    }
+
+    /// Both peers must speak the same extension version and compression. An empty
+    /// `compression` on either side means "whatever the other side uses".
+    pub fn check_compatible(&self, peer: &HandshakeExtension) -> Result<()> {
+        if self.version != peer.version {
+            return Err(FenrisError::IncompatibleHandshakeExtension(format!(
+                "version {} does not match peer version {}",
+                self.version, peer.version
+            )));
+        }
+        if !self.compression.is_empty()
+            && !peer.compression.is_empty()
+            && self.compression != peer.compression
+        {
+            return Err(FenrisError::IncompatibleHandshakeExtension(format!(
+                "compression {} does not match peer compression {}",
+                self.compression, peer.compression
+            )));
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,6 +68,8 @@ mod tests {
             ip_addr: 0,
             data: vec![1, 2, 3],
             details: None,
+            timeout_ms: 0,
+            request_id: 0,
         };
 
         let bytes = request.to_bytes().unwrap();
@@ -52,6 +88,7 @@ mod tests {
             error_message: String::new(),
             data: vec![4, 5, 6],
             details: None,
+            request_id: 0,
         };
 
         let bytes = response.to_bytes().unwrap();
@@ -61,4 +98,37 @@ mod tests {
         assert_eq!(decoded.success, response.success);
         assert_eq!(decoded.data, response.data);
     }
+
+    #[test]
+    fn test_handshake_extension_compatibility() {
+        let local = HandshakeExtension {
+            version: 1,
+            max_message_size: 1024,
+            supported_commands: vec![RequestType::Ping as u32],
+            compression: "zlib".to_string(),
+        };
+        let decoded = HandshakeExtension::from_bytes(&local.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded, local);
+
+        let unspecified = HandshakeExtension {
+            compression: String::new(),
+            ..local.clone()
+        };
+        assert!(local.check_compatible(&unspecified).is_ok());
+
+        let newer = HandshakeExtension {
+            version: 2,
+            ..local.clone()
+        };
+        assert!(matches!(
+            local.check_compatible(&newer),
+            Err(FenrisError::IncompatibleHandshakeExtension(_))
+        ));
+
+        let zstd = HandshakeExtension {
+            compression: "zstd".to_string(),
+            ..local.clone()
+        };
+        assert!(local.check_compatible(&zstd).is_err());
+    }
 }
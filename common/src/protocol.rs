@@ -17,12 +17,12 @@ where
         let mut buf = Vec::new();
         message
             .encode(&mut buf)
-            .map_err(|e| FenrisError::SerializationError(e.to_string()))?;
+            .map_err(|e| FenrisError::serialization_from(e.to_string(), e))?;
         Ok(buf)
     }
 
     fn decode(data: &[u8]) -> Result<M> {
-        M::decode(data).map_err(|e| FenrisError::SerializationError(e.to_string()))
+        M::decode(data).map_err(|e| FenrisError::serialization_from(e.to_string(), e))
     }
 }
 
@@ -63,6 +63,8 @@ mod tests {
             ip_addr: 0,
             data: vec![1, 2, 3],
             details: None,
+            timeout_ms: 0,
+            request_id: 0,
         };
 
         let encoded = ProtobufCodec::encode(&request).unwrap();
@@ -82,6 +84,7 @@ mod tests {
             error_message: String::new(),
             data: vec![4, 5, 6],
             details: None,
+            request_id: 0,
         };
 
         let encoded = ProtobufCodec::encode(&response).unwrap();
@@ -98,6 +101,9 @@ mod tests {
     fn protobuf_codec_rejects_invalid_bytes() {
         let result = <ProtobufCodec as ProtocolCodec<Request>>::decode(&[0xff]);
 
-        assert!(matches!(result, Err(FenrisError::SerializationError(_))));
+        assert!(matches!(
+            result,
+            Err(FenrisError::SerializationError { .. })
+        ));
     }
 }
@@ -0,0 +1,111 @@
+use crate::{FenrisError, Result};
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+pub const PSK_NONCE_SIZE: usize = 32;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub fn generate_psk_nonce() -> [u8; PSK_NONCE_SIZE] {
+    let mut nonce = [0u8; PSK_NONCE_SIZE];
+    OsRng.fill_bytes(&mut nonce);
+    nonce
+}
+
+/// Hash of the handshake both sides just completed. The PSK proof covers it, so a proof
+/// captured on one connection is useless on any other.
+pub fn handshake_transcript_hash(
+    client_public_key: &[u8],
+    server_public_key: &[u8],
+    kdf_context: &[u8],
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in [client_public_key, server_public_key, kdf_context] {
+        hasher.update((part.len() as u32).to_be_bytes());
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn psk_mac(psk: &str, nonce: &[u8], transcript_hash: &[u8]) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(psk.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(nonce);
+    mac.update(transcript_hash);
+    mac
+}
+
+pub fn psk_proof(psk: &str, nonce: &[u8], transcript_hash: &[u8]) -> Vec<u8> {
+    psk_mac(psk, nonce, transcript_hash)
+        .finalize()
+        .into_bytes()
+        .to_vec()
+}
+
+pub fn verify_psk_proof(
+    psk: &str,
+    nonce: &[u8],
+    transcript_hash: &[u8],
+    proof: &[u8],
+) -> Result<()> {
+    psk_mac(psk, nonce, transcript_hash)
+        .verify_slice(proof)
+        .map_err(|_| FenrisError::AuthenticationFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSCRIPT: [u8; 32] = [9; 32];
+
+    #[test]
+    fn proof_verifies_with_matching_psk() {
+        let nonce = generate_psk_nonce();
+        let proof = psk_proof("secret-token", &nonce, &TRANSCRIPT);
+
+        verify_psk_proof("secret-token", &nonce, &TRANSCRIPT, &proof).unwrap();
+    }
+
+    #[test]
+    fn proof_is_rejected_for_wrong_psk() {
+        let nonce = generate_psk_nonce();
+        let proof = psk_proof("wrong-token", &nonce, &TRANSCRIPT);
+
+        assert!(matches!(
+            verify_psk_proof("secret-token", &nonce, &TRANSCRIPT, &proof),
+            Err(FenrisError::AuthenticationFailed)
+        ));
+    }
+
+    #[test]
+    fn proof_is_bound_to_nonce() {
+        let proof = psk_proof("secret-token", &generate_psk_nonce(), &TRANSCRIPT);
+
+        assert!(matches!(
+            verify_psk_proof("secret-token", &generate_psk_nonce(), &TRANSCRIPT, &proof),
+            Err(FenrisError::AuthenticationFailed)
+        ));
+    }
+
+    #[test]
+    fn proof_is_bound_to_transcript() {
+        let nonce = generate_psk_nonce();
+        let proof = psk_proof(
+            "secret-token",
+            &nonce,
+            &handshake_transcript_hash(b"client-a", b"server-a", b"ctx"),
+        );
+
+        assert!(matches!(
+            verify_psk_proof(
+                "secret-token",
+                &nonce,
+                &handshake_transcript_hash(b"client-b", b"server-b", b"ctx"),
+                &proof
+            ),
+            Err(FenrisError::AuthenticationFailed)
+        ));
+    }
+}
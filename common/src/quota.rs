@@ -0,0 +1,133 @@
+use crate::error::{FenrisError, Result};
+use dashmap::DashMap;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+pub const QUOTAS_FILE: &str = "quotas.json";
+
+/// The account of connections that did not log in.
+pub const ANONYMOUS_ACCOUNT: &str = "";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaUsage {
+    pub used_bytes: u64,
+    pub limit_bytes: Option<u64>,
+}
+
+/// Storage limits per account, falling back to a server-wide default. Limits set at runtime
+/// are written back to the file the manager was loaded from, as a JSON object of byte counts
+/// keyed by username.
+#[derive(Debug, Default)]
+pub struct QuotaManager {
+    limits: DashMap<String, u64>,
+    default_limit: Option<u64>,
+    path: Option<PathBuf>,
+}
+
+impl QuotaManager {
+    /// Keeps limits in memory only.
+    pub fn new(default_limit: Option<u64>) -> Self {
+        Self {
+            default_limit,
+            ..Self::default()
+        }
+    }
+
+    /// A missing file starts with no per-account limits.
+    pub async fn load(path: PathBuf, default_limit: Option<u64>) -> Result<Self> {
+        let limits = match fs::read(&path).await {
+            Ok(contents) => {
+                serde_json::from_slice::<BTreeMap<String, u64>>(&contents).map_err(|e| {
+                    FenrisError::serialization_from(
+                        format!("Invalid quotas file {}: {}", path.display(), e),
+                        e,
+                    )
+                })?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                return Err(FenrisError::file_operation_from(
+                    format!("Failed to read quotas file {}: {}", path.display(), e),
+                    e,
+                ));
+            }
+        };
+
+        Ok(Self {
+            limits: limits.into_iter().collect(),
+            default_limit,
+            path: Some(path),
+        })
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn limit(&self, account: &str) -> Option<u64> {
+        self.limits
+            .get(account)
+            .map(|limit| *limit)
+            .or(self.default_limit)
+    }
+
+    pub async fn set_limit(&self, account: &str, limit: u64) -> Result<()> {
+        self.limits.insert(account.to_string(), limit);
+        self.save().await
+    }
+
+    async fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let limits: BTreeMap<String, u64> = self
+            .limits
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect();
+        let contents = serde_json::to_vec_pretty(&limits).map_err(|e| {
+            FenrisError::serialization_from(format!("Failed to encode quotas: {}", e), e)
+        })?;
+
+        fs::write(path, contents).await.map_err(|e| {
+            FenrisError::file_operation_from(
+                format!("Failed to write quotas file {}: {}", path.display(), e),
+                e,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn limits_persist_and_fall_back_to_the_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUOTAS_FILE);
+
+        let quotas = QuotaManager::load(path.clone(), Some(100)).await.unwrap();
+        assert_eq!(quotas.limit("alice"), Some(100));
+        quotas.set_limit("alice", 4096).await.unwrap();
+        quotas.set_limit(ANONYMOUS_ACCOUNT, 10).await.unwrap();
+
+        let reloaded = QuotaManager::load(path, None).await.unwrap();
+        assert_eq!(reloaded.limit("alice"), Some(4096));
+        assert_eq!(reloaded.limit(ANONYMOUS_ACCOUNT), Some(10));
+        assert_eq!(reloaded.limit("bob"), None);
+    }
+
+    #[tokio::test]
+    async fn load_rejects_malformed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUOTAS_FILE);
+        fs::write(&path, b"{\"alice\": -1}").await.unwrap();
+
+        assert!(matches!(
+            QuotaManager::load(path, None).await,
+            Err(FenrisError::SerializationError { .. })
+        ));
+    }
+}
@@ -1,65 +1,240 @@
 use crate::{
-    CompressionOf, Config, CryptoOf, ProtocolCodec, ProtocolCodecOf, Result, SecureChannelConfig,
+    CompressionOf, Config, CryptoOf, DEFAULT_MAX_MESSAGE_SIZE, FramingMode, ProtocolCodec,
+    ProtocolCodecOf, Result, SecureChannelConfig, SessionKey,
     identity::{
         ServerIdentityKey, ServerIdentityPublicKey, authenticated_kdf_context,
-        server_identity_transcript,
+        extension_kdf_context, server_identity_transcript,
     },
     network,
+    proto::HandshakeExtension,
+    psk,
+    transport::SplitStream,
+};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio::net::{
+    TcpStream,
+    tcp::{OwnedReadHalf, OwnedWriteHalf},
 };
-use tokio::net::TcpStream;
 use tracing::debug;
 
 pub const DEFAULT_KDF_CONTEXT: &[u8] = b"fenris-aes-key";
 
+pub const PROTOCOL_VERSION: u8 = 3;
+
+const PROTOCOL_VERSION_REJECTED: u8 = 0;
+
+// Every sealed frame starts with `tag (u32) || sequence (u64)`, both big-endian. The header is
+// sent in the clear but authenticated as AES-GCM associated data.
+const FRAME_TAG_MESSAGE: u32 = 1;
+const FRAME_TAG_PSK: u32 = 2;
+
+const FRAME_HEADER_SIZE: usize = 12;
+
+/// Payloads smaller than this skip compression; zlib's own overhead outweighs any saving on a
+/// ping or a short listing.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 256;
+
+// The first plaintext byte of every sealed frame says whether the rest went through the
+// compressor, so each side can pick its own threshold.
+const PAYLOAD_UNCOMPRESSED: u8 = 0x00;
+const PAYLOAD_COMPRESSED: u8 = 0x01;
+
+// Keys, signatures and PSK proofs are all tiny; nothing in the handshake comes close to this.
+const HANDSHAKE_MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
 pub type DefaultSecureChannel = SecureChannel<Config>;
 
-pub struct SecureChannel<Cfg: SecureChannelConfig> {
-    stream: TcpStream,
-    key: Vec<u8>,
+#[cfg(feature = "tls")]
+pub type TlsSecureChannel = SecureChannel<Config, crate::tls::TlsTransport>;
+
+type SplitReader<Cfg, S> = SecureChannelReader<Cfg, <S as SplitStream>::ReadHalf>;
+type SplitWriter<Cfg, S> = SecureChannelWriter<Cfg, BufWriter<<S as SplitStream>::WriteHalf>>;
+
+pub struct SecureChannel<Cfg: SecureChannelConfig, S = TcpStream> {
+    // Writes are buffered so a frame's header and body leave in one syscall; every send
+    // flushes, so the buffer is always empty between calls. Reads stay unbuffered because
+    // `into_split`/`into_inner` would otherwise drop bytes already read ahead.
+    stream: BufWriter<S>,
+    key: SessionKey,
     crypto: CryptoOf<Cfg>,
     compressor: CompressionOf<Cfg>,
+    framing: FramingMode,
+    max_message_size: usize,
+    read_timeout: Option<Duration>,
+    compression_threshold: usize,
+    send_seq: u64,
+    recv_seq: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+    // Zero unless the channel came out of a handshake.
+    transcript_hash: [u8; 32],
 }
 
-impl<Cfg: SecureChannelConfig> SecureChannel<Cfg> {
+impl<Cfg: SecureChannelConfig, S: AsyncRead + AsyncWrite + Unpin> SecureChannel<Cfg, S> {
     pub fn new(
-        stream: TcpStream,
-        key: Vec<u8>,
+        stream: S,
+        key: SessionKey,
         crypto: CryptoOf<Cfg>,
         compressor: CompressionOf<Cfg>,
+    ) -> Self {
+        Self::new_with_framing(stream, key, crypto, compressor, FramingMode::default())
+    }
+
+    pub fn new_with_framing(
+        stream: S,
+        key: SessionKey,
+        crypto: CryptoOf<Cfg>,
+        compressor: CompressionOf<Cfg>,
+        framing: FramingMode,
     ) -> Self {
         Self {
-            stream,
+            stream: BufWriter::new(stream),
             key,
             crypto,
             compressor,
+            framing,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            read_timeout: None,
+            compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            send_seq: 0,
+            recv_seq: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
+            transcript_hash: [0; 32],
         }
     }
 
-    pub async fn client_handshake(stream: TcpStream) -> Result<Self> {
+    fn with_transcript(
+        mut self,
+        client_public_key: &[u8],
+        server_public_key: &[u8],
+        kdf_context: &[u8],
+    ) -> Self {
+        self.transcript_hash =
+            psk::handshake_transcript_hash(client_public_key, server_public_key, kdf_context);
+        self
+    }
+
+    pub fn with_framing(mut self, framing: FramingMode) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    pub fn framing(&self) -> FramingMode {
+        self.framing
+    }
+
+    /// Caps the size of incoming frames; larger ones fail with `InvalidProtocolMessage`.
+    pub fn set_max_message_size(&mut self, max_message_size: usize) {
+        self.max_message_size = max_message_size;
+    }
+
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    /// Bounds how long a single `recv_msg` may take once called; `None` waits forever. This
+    /// guards against a peer stalling mid-frame and is independent of any idle timeout.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+
+    /// Sends serialized messages shorter than `threshold` bytes uncompressed; 0 compresses
+    /// everything. Only the sending side's threshold matters.
+    pub fn set_compression_threshold(&mut self, threshold: usize) {
+        self.compression_threshold = threshold;
+    }
+
+    pub fn compression_threshold(&self) -> usize {
+        self.compression_threshold
+    }
+
+    pub async fn client_handshake(stream: S) -> Result<Self> {
         Self::client_handshake_with_context(stream, DEFAULT_KDF_CONTEXT).await
     }
 
-    pub async fn client_handshake_with_context(
-        mut stream: TcpStream,
-        context: &[u8],
-    ) -> Result<Self> {
+    pub async fn client_handshake_with_context(mut stream: S, context: &[u8]) -> Result<Self> {
         debug!("Starting client handshake");
+        client_negotiate_version(&mut stream).await?;
 
         let crypto = Cfg::crypto();
         let compressor = Cfg::compression();
 
         let (private_key, public_key) = crypto.generate_keypair();
         network::send_prefixed(&mut stream, &public_key).await?;
+        stream.flush().await?;
 
-        let server_public_key = network::receive_prefixed(&mut stream).await?;
+        let server_public_key =
+            network::receive_prefixed_bounded(&mut stream, HANDSHAKE_MAX_MESSAGE_SIZE).await?;
         let shared_secret = crypto.compute_shared_secret(&private_key, &server_public_key)?;
         let key = crypto.derive_key(&shared_secret, context)?;
 
-        Ok(Self::new(stream, key, crypto, compressor))
+        Ok(Self::new(stream, key, crypto, compressor).with_transcript(
+            &public_key,
+            &server_public_key,
+            context,
+        ))
+    }
+
+    /// Sends `extension` alongside the public key and returns the server's. Extensions travel
+    /// unencrypted but are bound into the key derivation, so any tampering surfaces as a failed
+    /// AEAD open on the first message.
+    pub async fn client_handshake_with_data(
+        mut stream: S,
+        context: &[u8],
+        extension: &[u8],
+    ) -> Result<(Self, Vec<u8>)> {
+        debug!("Starting client handshake with extension data");
+        client_negotiate_version(&mut stream).await?;
+
+        let crypto = Cfg::crypto();
+        let compressor = Cfg::compression();
+
+        let (private_key, public_key) = crypto.generate_keypair();
+        network::send_prefixed(&mut stream, &public_key).await?;
+        network::send_prefixed(&mut stream, extension).await?;
+        stream.flush().await?;
+
+        let server_public_key =
+            network::receive_prefixed_bounded(&mut stream, HANDSHAKE_MAX_MESSAGE_SIZE).await?;
+        let server_extension =
+            network::receive_prefixed_bounded(&mut stream, HANDSHAKE_MAX_MESSAGE_SIZE).await?;
+        let shared_secret = crypto.compute_shared_secret(&private_key, &server_public_key)?;
+        let kdf_context = extension_kdf_context(context, extension, &server_extension);
+        let key = crypto.derive_key(&shared_secret, &kdf_context)?;
+        let channel = Self::new(stream, key, crypto, compressor).with_transcript(
+            &public_key,
+            &server_public_key,
+            &kdf_context,
+        );
+
+        Ok((channel, server_extension))
+    }
+
+    /// Like [`Self::client_handshake_with_data`], but decodes the server's extension and
+    /// rejects it unless it is compatible with `extension`.
+    pub async fn client_handshake_with_extension(
+        stream: S,
+        context: &[u8],
+        extension: &HandshakeExtension,
+    ) -> Result<(Self, HandshakeExtension)> {
+        let (channel, server_extension) =
+            Self::client_handshake_with_data(stream, context, &extension.to_bytes()?).await?;
+        let server_extension = HandshakeExtension::from_bytes(&server_extension)?;
+        extension.check_compatible(&server_extension)?;
+
+        Ok((channel, server_extension))
     }
 
     pub async fn client_handshake_authenticated(
-        stream: TcpStream,
+        stream: S,
         expected_server_identity: ServerIdentityPublicKey,
     ) -> Result<Self> {
         Self::client_handshake_authenticated_with_context(
@@ -71,23 +246,29 @@ impl<Cfg: SecureChannelConfig> SecureChannel<Cfg> {
     }
 
     pub async fn client_handshake_authenticated_with_context(
-        mut stream: TcpStream,
+        mut stream: S,
         expected_server_identity: ServerIdentityPublicKey,
         context: &[u8],
     ) -> Result<Self> {
         debug!("Starting authenticated client handshake");
+        client_negotiate_version(&mut stream).await?;
 
         let crypto = Cfg::crypto();
         let compressor = Cfg::compression();
 
         let (private_key, public_key) = crypto.generate_keypair();
         network::send_prefixed(&mut stream, &public_key).await?;
+        stream.flush().await?;
 
-        let server_public_key = network::receive_prefixed(&mut stream).await?;
+        let server_public_key =
+            network::receive_prefixed_bounded(&mut stream, HANDSHAKE_MAX_MESSAGE_SIZE).await?;
         let server_identity = ServerIdentityPublicKey::from_slice(
-            network::receive_prefixed(&mut stream).await?.as_slice(),
+            network::receive_prefixed_bounded(&mut stream, HANDSHAKE_MAX_MESSAGE_SIZE)
+                .await?
+                .as_slice(),
         )?;
-        let signature = network::receive_prefixed(&mut stream).await?;
+        let signature =
+            network::receive_prefixed_bounded(&mut stream, HANDSHAKE_MAX_MESSAGE_SIZE).await?;
 
         if server_identity != expected_server_identity {
             return Err(crate::FenrisError::AuthenticationError(
@@ -100,37 +281,101 @@ impl<Cfg: SecureChannelConfig> SecureChannel<Cfg> {
         server_identity.verify_transcript(&transcript, &signature)?;
 
         let shared_secret = crypto.compute_shared_secret(&private_key, &server_public_key)?;
-        let key = crypto.derive_key(&shared_secret, &authenticated_kdf_context(&transcript))?;
+        let kdf_context = authenticated_kdf_context(&transcript);
+        let key = crypto.derive_key(&shared_secret, &kdf_context)?;
 
-        Ok(Self::new(stream, key, crypto, compressor))
+        Ok(Self::new(stream, key, crypto, compressor).with_transcript(
+            &public_key,
+            &server_public_key,
+            &kdf_context,
+        ))
     }
 
-    pub async fn server_handshake(stream: TcpStream) -> Result<Self> {
+    pub async fn server_handshake(stream: S) -> Result<Self> {
         Self::server_handshake_with_context(stream, DEFAULT_KDF_CONTEXT).await
     }
 
-    pub async fn server_handshake_with_context(
-        mut stream: TcpStream,
+    pub async fn server_handshake_with_context(stream: S, context: &[u8]) -> Result<Self> {
+        Self::server_handshake_with_versions(stream, context, PROTOCOL_VERSION..=PROTOCOL_VERSION)
+            .await
+    }
+
+    pub async fn server_handshake_with_versions(
+        mut stream: S,
         context: &[u8],
+        versions: RangeInclusive<u8>,
     ) -> Result<Self> {
         debug!("Starting server key exchange");
+        server_negotiate_version(&mut stream, &versions).await?;
 
-        let client_public_key = network::receive_prefixed(&mut stream).await?;
+        let client_public_key =
+            network::receive_prefixed_bounded(&mut stream, HANDSHAKE_MAX_MESSAGE_SIZE).await?;
 
         let crypto = Cfg::crypto();
         let compressor = Cfg::compression();
 
         let (private_key, public_key) = crypto.generate_keypair();
         network::send_prefixed(&mut stream, &public_key).await?;
+        stream.flush().await?;
 
         let shared_secret = crypto.compute_shared_secret(&private_key, &client_public_key)?;
         let key = crypto.derive_key(&shared_secret, context)?;
 
-        Ok(Self::new(stream, key, crypto, compressor))
+        Ok(Self::new(stream, key, crypto, compressor).with_transcript(
+            &client_public_key,
+            &public_key,
+            context,
+        ))
+    }
+
+    pub async fn server_handshake_with_data(
+        mut stream: S,
+        context: &[u8],
+        extension: &[u8],
+    ) -> Result<(Self, Vec<u8>)> {
+        debug!("Starting server key exchange with extension data");
+        server_negotiate_version(&mut stream, &(PROTOCOL_VERSION..=PROTOCOL_VERSION)).await?;
+
+        let client_public_key =
+            network::receive_prefixed_bounded(&mut stream, HANDSHAKE_MAX_MESSAGE_SIZE).await?;
+        let client_extension =
+            network::receive_prefixed_bounded(&mut stream, HANDSHAKE_MAX_MESSAGE_SIZE).await?;
+
+        let crypto = Cfg::crypto();
+        let compressor = Cfg::compression();
+
+        let (private_key, public_key) = crypto.generate_keypair();
+        network::send_prefixed(&mut stream, &public_key).await?;
+        network::send_prefixed(&mut stream, extension).await?;
+        stream.flush().await?;
+
+        let shared_secret = crypto.compute_shared_secret(&private_key, &client_public_key)?;
+        let kdf_context = extension_kdf_context(context, &client_extension, extension);
+        let key = crypto.derive_key(&shared_secret, &kdf_context)?;
+        let channel = Self::new(stream, key, crypto, compressor).with_transcript(
+            &client_public_key,
+            &public_key,
+            &kdf_context,
+        );
+
+        Ok((channel, client_extension))
+    }
+
+    pub async fn server_handshake_with_extension(
+        stream: S,
+        context: &[u8],
+        extension: &HandshakeExtension,
+    ) -> Result<(Self, HandshakeExtension)> {
+        let (channel, client_extension) =
+            Self::server_handshake_with_data(stream, context, &extension.to_bytes()?).await?;
+        let client_extension = HandshakeExtension::from_bytes(&client_extension)?;
+        extension.check_compatible(&client_extension)?;
+
+        Ok((channel, client_extension))
     }
 
     pub async fn server_handshake_authenticated(
-        stream: TcpStream,
+        stream: S,
         server_identity_key: &ServerIdentityKey,
     ) -> Result<Self> {
         Self::server_handshake_authenticated_with_context(
@@ -142,13 +387,30 @@ impl<Cfg: SecureChannelConfig> SecureChannel<Cfg> {
     }
 
     pub async fn server_handshake_authenticated_with_context(
-        mut stream: TcpStream,
+        stream: S,
+        server_identity_key: &ServerIdentityKey,
+        context: &[u8],
+    ) -> Result<Self> {
+        Self::server_handshake_authenticated_with_versions(
+            stream,
+            server_identity_key,
+            context,
+            PROTOCOL_VERSION..=PROTOCOL_VERSION,
+        )
+        .await
+    }
+
+    pub async fn server_handshake_authenticated_with_versions(
+        mut stream: S,
         server_identity_key: &ServerIdentityKey,
         context: &[u8],
+        versions: RangeInclusive<u8>,
     ) -> Result<Self> {
         debug!("Starting authenticated server key exchange");
+        server_negotiate_version(&mut stream, &versions).await?;
 
-        let client_public_key = network::receive_prefixed(&mut stream).await?;
+        let client_public_key =
+            network::receive_prefixed_bounded(&mut stream, HANDSHAKE_MAX_MESSAGE_SIZE).await?;
 
         let crypto = Cfg::crypto();
         let compressor = Cfg::compression();
@@ -162,24 +424,78 @@ impl<Cfg: SecureChannelConfig> SecureChannel<Cfg> {
         network::send_prefixed(&mut stream, &public_key).await?;
         network::send_prefixed(&mut stream, server_identity.as_bytes()).await?;
         network::send_prefixed(&mut stream, &signature).await?;
+        stream.flush().await?;
 
         let shared_secret = crypto.compute_shared_secret(&private_key, &client_public_key)?;
-        let key = crypto.derive_key(&shared_secret, &authenticated_kdf_context(&transcript))?;
+        let kdf_context = authenticated_kdf_context(&transcript);
+        let key = crypto.derive_key(&shared_secret, &kdf_context)?;
+
+        Ok(Self::new(stream, key, crypto, compressor).with_transcript(
+            &client_public_key,
+            &public_key,
+            &kdf_context,
+        ))
+    }
+
+    /// Challenges the client to prove it knows `psk`. The nonce and proof travel as sealed
+    /// frames, and the proof covers this session's handshake transcript.
+    pub async fn server_psk_challenge(&mut self, psk: &str) -> Result<()> {
+        debug!("Sending PSK challenge");
+
+        let nonce = psk::generate_psk_nonce();
+        self.send_sealed(FRAME_TAG_PSK, &nonce).await?;
+
+        let proof = self.recv_sealed(FRAME_TAG_PSK).await?;
+        psk::verify_psk_proof(psk, &nonce, &self.transcript_hash, &proof)
+    }
+
+    pub async fn client_psk_response(&mut self, psk: &str) -> Result<()> {
+        debug!("Answering PSK challenge");
+
+        let nonce = self.recv_sealed(FRAME_TAG_PSK).await?;
+        if nonce.len() != psk::PSK_NONCE_SIZE {
+            return Err(crate::FenrisError::InvalidProtocolMessage);
+        }
+
+        let proof = psk::psk_proof(psk, &nonce, &self.transcript_hash);
+        self.send_sealed(FRAME_TAG_PSK, &proof).await
+    }
 
-        Ok(Self::new(stream, key, crypto, compressor))
+    async fn send_sealed(&mut self, tag: u32, data: &[u8]) -> Result<()> {
+        let packet = seal_frame::<Cfg>(&self.crypto, &self.key, tag, self.send_seq, data)?;
+        send_frame(&mut self.stream, &packet, self.framing).await?;
+        self.send_seq += 1;
+        self.bytes_sent += packet.len() as u64;
+        Ok(())
+    }
+
+    async fn recv_sealed(&mut self, tag: u32) -> Result<Vec<u8>> {
+        let packet = network::with_read_timeout(
+            self.read_timeout,
+            receive_frame(&mut self.stream, self.framing, HANDSHAKE_MAX_MESSAGE_SIZE),
+        )
+        .await?;
+        let data = open_frame::<Cfg>(&self.crypto, &self.key, tag, self.recv_seq, &packet)?;
+        self.recv_seq += 1;
+        self.bytes_received += packet.len() as u64;
+        Ok(data)
     }
 
     pub async fn send_msg<M>(&mut self, msg: &M) -> Result<()>
     where
         ProtocolCodecOf<Cfg>: ProtocolCodec<M>,
     {
-        let buf = <ProtocolCodecOf<Cfg> as ProtocolCodec<M>>::encode(msg)?;
-        debug!("Serialized outgoing message: {} bytes", buf.len());
-
-        // Compress -> Seal (iv||ciphertext) -> Frame+Send
-        let compressed = self.compressor.compress(&buf)?;
-        let packet = self.crypto.seal(&compressed, &self.key)?;
-        network::send_prefixed(&mut self.stream, &packet).await?;
+        let packet = seal_msg::<Cfg, M>(
+            msg,
+            &self.crypto,
+            &self.compressor,
+            self.compression_threshold,
+            &self.key,
+            self.send_seq,
+        )?;
+        send_frame(&mut self.stream, &packet, self.framing).await?;
+        self.send_seq += 1;
+        self.bytes_sent += packet.len() as u64;
         Ok(())
     }
 
@@ -187,18 +503,430 @@ impl<Cfg: SecureChannelConfig> SecureChannel<Cfg> {
     where
         ProtocolCodecOf<Cfg>: ProtocolCodec<M>,
     {
-        let packet = network::receive_prefixed(&mut self.stream).await?;
-        debug!("Received encrypted packet: {} bytes", packet.len());
+        let packet = network::with_read_timeout(
+            self.read_timeout,
+            receive_frame(&mut self.stream, self.framing, self.max_message_size),
+        )
+        .await?;
+        let msg = open_msg::<Cfg, M>(
+            &packet,
+            &self.crypto,
+            &self.compressor,
+            &self.key,
+            self.recv_seq,
+        )?;
+        self.recv_seq += 1;
+        self.bytes_received += packet.len() as u64;
+        Ok(msg)
+    }
+
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream.into_inner()
+    }
+}
+
+impl<Cfg: SecureChannelConfig, S: SplitStream> SecureChannel<Cfg, S> {
+    pub fn into_split(self) -> (SplitReader<Cfg, S>, SplitWriter<Cfg, S>) {
+        let (read_half, write_half) = self.stream.into_inner().split_owned();
+        let crypto = Arc::new(self.crypto);
+        let compressor = Arc::new(self.compressor);
+
+        let mut reader = SecureChannelReader::new(
+            read_half,
+            self.key.clone(),
+            Arc::clone(&crypto),
+            Arc::clone(&compressor),
+        )
+        .with_framing(self.framing)
+        .with_max_message_size(self.max_message_size)
+        .with_read_timeout(self.read_timeout);
+        reader.seq = self.recv_seq;
+        reader.bytes_received = self.bytes_received;
+        reader.transcript_hash = self.transcript_hash;
+        let mut writer =
+            SecureChannelWriter::new(BufWriter::new(write_half), self.key, crypto, compressor)
+                .with_framing(self.framing)
+                .with_compression_threshold(self.compression_threshold);
+        writer.seq = self.send_seq;
+        writer.bytes_sent = self.bytes_sent;
+
+        (reader, writer)
+    }
+
+    /// Rejoins halves produced by [`SecureChannel::into_split`], keeping sequence numbers,
+    /// byte counters and the handshake transcript so the channel can keep being used as a
+    /// whole, PSK challenge included.
+    pub fn reunite(reader: SplitReader<Cfg, S>, writer: SplitWriter<Cfg, S>) -> Result<Self> {
+        let SecureChannelWriter {
+            writer: write_half,
+            crypto: writer_crypto,
+            compressor: writer_compressor,
+            compression_threshold,
+            seq: send_seq,
+            bytes_sent,
+            ..
+        } = writer;
+        drop((writer_crypto, writer_compressor));
+        let SecureChannelReader {
+            reader: read_half,
+            key,
+            crypto,
+            compressor,
+            framing,
+            max_message_size,
+            read_timeout,
+            seq: recv_seq,
+            bytes_received,
+            transcript_hash,
+        } = reader;
+
+        let stream = S::reunite_owned(read_half, write_half.into_inner()).ok_or_else(|| {
+            crate::FenrisError::InvalidFrame("halves belong to different channels".to_string())
+        })?;
+        let not_unique =
+            || crate::FenrisError::InvalidFrame("channel state is still shared".to_string());
+
+        Ok(Self {
+            stream: BufWriter::new(stream),
+            key,
+            crypto: Arc::try_unwrap(crypto).map_err(|_| not_unique())?,
+            compressor: Arc::try_unwrap(compressor).map_err(|_| not_unique())?,
+            framing,
+            max_message_size,
+            read_timeout,
+            compression_threshold,
+            send_seq,
+            recv_seq,
+            bytes_sent,
+            bytes_received,
+            transcript_hash,
+        })
+    }
+}
+
+pub struct SecureChannelReader<Cfg: SecureChannelConfig, R = OwnedReadHalf> {
+    reader: R,
+    key: SessionKey,
+    crypto: Arc<CryptoOf<Cfg>>,
+    compressor: Arc<CompressionOf<Cfg>>,
+    framing: FramingMode,
+    max_message_size: usize,
+    read_timeout: Option<Duration>,
+    seq: u64,
+    bytes_received: u64,
+    // Only carried so `SecureChannel::reunite` can restore it.
+    transcript_hash: [u8; 32],
+}
+
+impl<Cfg: SecureChannelConfig, R: AsyncRead + Unpin> SecureChannelReader<Cfg, R> {
+    pub fn new(
+        reader: R,
+        key: SessionKey,
+        crypto: Arc<CryptoOf<Cfg>>,
+        compressor: Arc<CompressionOf<Cfg>>,
+    ) -> Self {
+        Self {
+            reader,
+            key,
+            crypto,
+            compressor,
+            framing: FramingMode::default(),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            read_timeout: None,
+            seq: 0,
+            bytes_received: 0,
+            transcript_hash: [0; 32],
+        }
+    }
+
+    pub fn with_framing(mut self, framing: FramingMode) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    pub fn with_read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    pub async fn recv_msg<M>(&mut self) -> Result<M>
+    where
+        ProtocolCodecOf<Cfg>: ProtocolCodec<M>,
+    {
+        let packet = network::with_read_timeout(
+            self.read_timeout,
+            receive_frame(&mut self.reader, self.framing, self.max_message_size),
+        )
+        .await?;
+        let msg = open_msg::<Cfg, M>(&packet, &self.crypto, &self.compressor, &self.key, self.seq)?;
+        self.seq += 1;
+        self.bytes_received += packet.len() as u64;
+        Ok(msg)
+    }
+
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+pub struct SecureChannelWriter<Cfg: SecureChannelConfig, W = BufWriter<OwnedWriteHalf>> {
+    writer: W,
+    key: SessionKey,
+    crypto: Arc<CryptoOf<Cfg>>,
+    compressor: Arc<CompressionOf<Cfg>>,
+    framing: FramingMode,
+    compression_threshold: usize,
+    seq: u64,
+    bytes_sent: u64,
+}
+
+impl<Cfg: SecureChannelConfig, W: AsyncWrite + Unpin> SecureChannelWriter<Cfg, W> {
+    pub fn new(
+        writer: W,
+        key: SessionKey,
+        crypto: Arc<CryptoOf<Cfg>>,
+        compressor: Arc<CompressionOf<Cfg>>,
+    ) -> Self {
+        Self {
+            writer,
+            key,
+            crypto,
+            compressor,
+            framing: FramingMode::default(),
+            compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            seq: 0,
+            bytes_sent: 0,
+        }
+    }
+
+    pub fn with_framing(mut self, framing: FramingMode) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    pub fn with_compression_threshold(mut self, threshold: usize) -> Self {
+        self.compression_threshold = threshold;
+        self
+    }
+
+    pub async fn send_msg<M>(&mut self, msg: &M) -> Result<()>
+    where
+        ProtocolCodecOf<Cfg>: ProtocolCodec<M>,
+    {
+        let packet = seal_msg::<Cfg, M>(
+            msg,
+            &self.crypto,
+            &self.compressor,
+            self.compression_threshold,
+            &self.key,
+            self.seq,
+        )?;
+        send_frame(&mut self.writer, &packet, self.framing).await?;
+        self.seq += 1;
+        self.bytes_sent += packet.len() as u64;
+        Ok(())
+    }
+
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+async fn client_negotiate_version<S>(stream: &mut S) -> Result<u8>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(&[PROTOCOL_VERSION]).await?;
+    stream.flush().await?;
+
+    let mut reply = [0u8; 1];
+    stream.read_exact(&mut reply).await?;
+
+    if reply[0] != PROTOCOL_VERSION {
+        return Err(crate::FenrisError::IncompatibleProtocolVersion {
+            client: PROTOCOL_VERSION,
+            server: reply[0],
+        });
+    }
+
+    Ok(reply[0])
+}
+
+async fn server_negotiate_version<S>(stream: &mut S, versions: &RangeInclusive<u8>) -> Result<u8>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut version = [0u8; 1];
+    stream.read_exact(&mut version).await?;
+    let version = version[0];
+
+    if version == PROTOCOL_VERSION_REJECTED || !versions.contains(&version) {
+        debug!("Rejecting client protocol version {}", version);
+        stream.write_all(&[PROTOCOL_VERSION_REJECTED]).await?;
+        stream.flush().await?;
+        let _ = stream.shutdown().await;
+
+        return Err(crate::FenrisError::IncompatibleProtocolVersion {
+            client: version,
+            server: *versions.end(),
+        });
+    }
+
+    stream.write_all(&[version]).await?;
+    stream.flush().await?;
+    Ok(version)
+}
+
+async fn send_frame<W>(stream: &mut W, data: &[u8], framing: FramingMode) -> Result<()>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    if framing.checksummed {
+        network::send_prefixed_with_checksum(stream, data).await?;
+    } else {
+        network::send_prefixed(stream, data).await?;
+    }
+    stream.flush().await?;
+    Ok(())
+}
+
+async fn receive_frame<R>(
+    stream: &mut R,
+    framing: FramingMode,
+    max_message_size: usize,
+) -> Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
+    if framing.checksummed {
+        network::receive_prefixed_with_checksum_bounded(stream, max_message_size).await
+    } else {
+        network::receive_prefixed_bounded(stream, max_message_size).await
+    }
+}
 
-        // Open -> Decompress -> Deserialize
-        let decrypted = self.crypto.open(&packet, &self.key)?;
-        let decompressed = self.compressor.decompress(&decrypted)?;
+fn frame_header(tag: u32, seq: u64) -> [u8; FRAME_HEADER_SIZE] {
+    let mut header = [0u8; FRAME_HEADER_SIZE];
+    header[..4].copy_from_slice(&tag.to_be_bytes());
+    header[4..].copy_from_slice(&seq.to_be_bytes());
+    header
+}
+
+fn seal_frame<Cfg>(
+    crypto: &CryptoOf<Cfg>,
+    key: &[u8],
+    tag: u32,
+    seq: u64,
+    payload: &[u8],
+) -> Result<Vec<u8>>
+where
+    Cfg: SecureChannelConfig + ?Sized,
+{
+    let header = frame_header(tag, seq);
+    let mut packet = header.to_vec();
+    packet.append(&mut crypto.seal_with_aad(payload, key, &header)?);
+    Ok(packet)
+}
 
-        <ProtocolCodecOf<Cfg> as ProtocolCodec<M>>::decode(decompressed.as_slice())
+fn open_frame<Cfg>(
+    crypto: &CryptoOf<Cfg>,
+    key: &[u8],
+    tag: u32,
+    expected_seq: u64,
+    packet: &[u8],
+) -> Result<Vec<u8>>
+where
+    Cfg: SecureChannelConfig + ?Sized,
+{
+    let (header, sealed) = packet
+        .split_first_chunk::<FRAME_HEADER_SIZE>()
+        .ok_or(crate::FenrisError::InvalidProtocolMessage)?;
+    if *header != frame_header(tag, expected_seq) {
+        return Err(crate::FenrisError::DecryptionError(
+            "unexpected frame tag or sequence number".to_string(),
+        ));
     }
+    crypto.open_with_aad(sealed, key, header)
+}
 
-    pub fn into_inner(self) -> TcpStream {
-        self.stream
+fn seal_msg<Cfg, M>(
+    msg: &M,
+    crypto: &CryptoOf<Cfg>,
+    compressor: &CompressionOf<Cfg>,
+    compression_threshold: usize,
+    key: &[u8],
+    seq: u64,
+) -> Result<Vec<u8>>
+where
+    Cfg: SecureChannelConfig + ?Sized,
+    ProtocolCodecOf<Cfg>: ProtocolCodec<M>,
+{
+    let buf = <ProtocolCodecOf<Cfg> as ProtocolCodec<M>>::encode(msg)?;
+    debug!("Serialized outgoing message: {} bytes", buf.len());
+
+    // Compress (unless small) -> Seal (header||iv||ciphertext, header authenticated as AAD)
+    let mut payload = Vec::with_capacity(buf.len() + 1);
+    if buf.len() < compression_threshold {
+        payload.push(PAYLOAD_UNCOMPRESSED);
+        payload.extend_from_slice(&buf);
+    } else {
+        payload.push(PAYLOAD_COMPRESSED);
+        payload.extend_from_slice(&compressor.compress(&buf)?);
+    }
+    seal_frame::<Cfg>(crypto, key, FRAME_TAG_MESSAGE, seq, &payload)
+}
+
+fn open_msg<Cfg, M>(
+    packet: &[u8],
+    crypto: &CryptoOf<Cfg>,
+    compressor: &CompressionOf<Cfg>,
+    key: &[u8],
+    expected_seq: u64,
+) -> Result<M>
+where
+    Cfg: SecureChannelConfig + ?Sized,
+    ProtocolCodecOf<Cfg>: ProtocolCodec<M>,
+{
+    debug!("Received encrypted packet: {} bytes", packet.len());
+
+    // Open -> Decompress (if marked) -> Deserialize
+    let decrypted = open_frame::<Cfg>(crypto, key, FRAME_TAG_MESSAGE, expected_seq, packet)?;
+    match decrypted.split_first() {
+        Some((&PAYLOAD_UNCOMPRESSED, payload)) => {
+            <ProtocolCodecOf<Cfg> as ProtocolCodec<M>>::decode(payload)
+        }
+        Some((&PAYLOAD_COMPRESSED, payload)) => {
+            let decompressed = compressor.decompress(payload)?;
+            <ProtocolCodecOf<Cfg> as ProtocolCodec<M>>::decode(decompressed.as_slice())
+        }
+        Some((marker, _)) => Err(crate::FenrisError::DecompressionError(format!(
+            "unknown compression marker: {:#04x}",
+            marker
+        ))),
+        None => Err(crate::FenrisError::DecompressionError(
+            "missing compression marker".to_string(),
+        )),
     }
 }
 
@@ -223,9 +951,7 @@ mod tests {
         fn decode(data: &[u8]) -> Result<TestMessage> {
             match data {
                 [value] => Ok(TestMessage { value: *value }),
-                _ => Err(FenrisError::SerializationError(
-                    "invalid test message".to_string(),
-                )),
+                _ => Err(FenrisError::serialization("invalid test message")),
             }
         }
     }
@@ -259,7 +985,7 @@ mod tests {
     #[tokio::test]
     async fn secure_channel_uses_configured_non_protobuf_codec() {
         let (client_stream, server_stream) = setup_connection().await;
-        let key = vec![9u8; KEY_SIZE];
+        let key = SessionKey::from(vec![9u8; KEY_SIZE]);
 
         let mut client = SecureChannel::<TestConfig>::new(
             client_stream,
@@ -274,13 +1000,421 @@ mod tests {
             TestConfig::compression(),
         );
 
+        let send_task = tokio::spawn(async move {
+            client
+                .send_msg(&TestMessage { value: 42 })
+                .await
+                .map(|_| client)
+        });
+
+        let received: TestMessage = server.recv_msg().await.unwrap();
+        let client = send_task.await.unwrap().unwrap();
+
+        assert_eq!(received, TestMessage { value: 42 });
+        assert!(client.bytes_sent() > 0);
+        assert_eq!(client.bytes_sent(), server.bytes_received());
+        assert_eq!(server.bytes_sent(), 0);
+    }
+
+    #[tokio::test]
+    async fn recv_msg_rejects_frames_over_the_configured_limit() {
+        let (mut client_stream, server_stream) = setup_connection().await;
+        let mut server = SecureChannel::<TestConfig>::new(
+            server_stream,
+            SessionKey::from(vec![3u8; KEY_SIZE]),
+            TestConfig::crypto(),
+            TestConfig::compression(),
+        );
+        server.set_max_message_size(1024);
+        assert_eq!(server.max_message_size(), 1024);
+
+        client_stream
+            .write_all(&4096u32.to_be_bytes())
+            .await
+            .unwrap();
+
+        // The limit has to survive the split the server does for every connection.
+        let (mut reader, _writer) = server.into_split();
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            reader.recv_msg::<TestMessage>(),
+        )
+        .await
+        .expect("oversized frame should be rejected immediately");
+
+        assert!(matches!(result, Err(FenrisError::InvalidProtocolMessage)));
+    }
+
+    #[tokio::test]
+    async fn recv_msg_times_out_on_a_stalled_frame() {
+        let (mut client_stream, server_stream) = setup_connection().await;
+        let mut server = SecureChannel::<TestConfig>::new(
+            server_stream,
+            SessionKey::from(vec![3u8; KEY_SIZE]),
+            TestConfig::crypto(),
+            TestConfig::compression(),
+        );
+        server.set_read_timeout(Some(std::time::Duration::from_millis(50)));
+
+        // A length prefix with no body behind it.
+        client_stream.write_all(&64u32.to_be_bytes()).await.unwrap();
+
+        // The timeout has to survive the split too.
+        let (mut reader, _writer) = server.into_split();
+        let result = reader.recv_msg::<TestMessage>().await;
+
+        assert!(matches!(
+            result,
+            Err(FenrisError::NetworkError(e)) if e.kind() == std::io::ErrorKind::TimedOut
+        ));
+    }
+
+    #[tokio::test]
+    async fn handshake_runs_over_in_memory_duplex() {
+        let (client_stream, server_stream) = tokio::io::duplex(4096);
+
+        let server_task = tokio::spawn(async move {
+            let mut server = SecureChannel::<TestConfig, _>::server_handshake(server_stream)
+                .await
+                .unwrap();
+            let message: TestMessage = server.recv_msg().await.unwrap();
+            server
+                .send_msg(&TestMessage {
+                    value: message.value + 1,
+                })
+                .await
+                .unwrap();
+        });
+
+        let mut client = SecureChannel::<TestConfig, _>::client_handshake(client_stream)
+            .await
+            .unwrap();
+        client.send_msg(&TestMessage { value: 7 }).await.unwrap();
+        let reply: TestMessage = client.recv_msg().await.unwrap();
+        server_task.await.unwrap();
+
+        assert_eq!(reply, TestMessage { value: 8 });
+    }
+
+    #[tokio::test]
+    async fn handshake_exchanges_extension_data() {
+        let (client_stream, server_stream) = tokio::io::duplex(4096);
+        let server_extension = HandshakeExtension {
+            version: 1,
+            max_message_size: 1 << 20,
+            supported_commands: vec![1, 2, 3],
+            compression: "zlib".to_string(),
+        };
+        let client_extension = HandshakeExtension {
+            supported_commands: vec![1],
+            ..server_extension.clone()
+        };
+
+        let expected_client = client_extension.clone();
+        let server_task = tokio::spawn(async move {
+            let (mut server, from_client) =
+                SecureChannel::<TestConfig, _>::server_handshake_with_extension(
+                    server_stream,
+                    DEFAULT_KDF_CONTEXT,
+                    &server_extension,
+                )
+                .await
+                .unwrap();
+            assert_eq!(from_client, expected_client);
+            let message: TestMessage = server.recv_msg().await.unwrap();
+            server.send_msg(&message).await.unwrap();
+        });
+
+        let (mut client, from_server) =
+            SecureChannel::<TestConfig, _>::client_handshake_with_extension(
+                client_stream,
+                DEFAULT_KDF_CONTEXT,
+                &client_extension,
+            )
+            .await
+            .unwrap();
+        client.send_msg(&TestMessage { value: 3 }).await.unwrap();
+        let reply: TestMessage = client.recv_msg().await.unwrap();
+        server_task.await.unwrap();
+
+        assert_eq!(from_server.supported_commands, vec![1, 2, 3]);
+        assert_eq!(reply, TestMessage { value: 3 });
+    }
+
+    #[tokio::test]
+    async fn handshake_rejects_incompatible_extension_version() {
+        let (client_stream, server_stream) = tokio::io::duplex(4096);
+
+        let server_task = tokio::spawn(async move {
+            SecureChannel::<TestConfig, _>::server_handshake_with_data(
+                server_stream,
+                DEFAULT_KDF_CONTEXT,
+                &HandshakeExtension {
+                    version: 2,
+                    ..Default::default()
+                }
+                .to_bytes()
+                .unwrap(),
+            )
+            .await
+            .map(|(_, extension)| extension)
+        });
+
+        let result = SecureChannel::<TestConfig, _>::client_handshake_with_extension(
+            client_stream,
+            DEFAULT_KDF_CONTEXT,
+            &HandshakeExtension {
+                version: 1,
+                ..Default::default()
+            },
+        )
+        .await;
+        server_task.await.unwrap().unwrap();
+
+        assert!(matches!(
+            result,
+            Err(FenrisError::IncompatibleHandshakeExtension(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn checksummed_framing_round_trips_through_split_halves() {
+        let (client_stream, server_stream) = setup_connection().await;
+        let key = SessionKey::from(vec![5u8; KEY_SIZE]);
+
+        let client = SecureChannel::<TestConfig>::new_with_framing(
+            client_stream,
+            key.clone(),
+            TestConfig::crypto(),
+            TestConfig::compression(),
+            FramingMode::checksummed(),
+        );
+        let mut server = SecureChannel::<TestConfig>::new(
+            server_stream,
+            key,
+            TestConfig::crypto(),
+            TestConfig::compression(),
+        )
+        .with_framing(FramingMode { checksummed: true });
+
+        let (_reader, mut writer) = client.into_split();
         let send_task =
-            tokio::spawn(async move { client.send_msg(&TestMessage { value: 42 }).await });
+            tokio::spawn(async move { writer.send_msg(&TestMessage { value: 11 }).await });
 
         let received: TestMessage = server.recv_msg().await.unwrap();
         send_task.await.unwrap().unwrap();
 
-        assert_eq!(received, TestMessage { value: 42 });
+        assert!(server.framing().checksummed);
+        assert_eq!(received, TestMessage { value: 11 });
+    }
+
+    #[tokio::test]
+    async fn handshake_rejects_unsupported_protocol_version() {
+        let (client_stream, server_stream) = setup_connection().await;
+
+        let client = DefaultSecureChannel::client_handshake(client_stream);
+        let server = DefaultSecureChannel::server_handshake_with_versions(
+            server_stream,
+            DEFAULT_KDF_CONTEXT,
+            PROTOCOL_VERSION + 1..=PROTOCOL_VERSION + 2,
+        );
+        let (client, server) = tokio::join!(client, server);
+
+        assert!(matches!(
+            client,
+            Err(FenrisError::IncompatibleProtocolVersion {
+                client: PROTOCOL_VERSION,
+                server: 0
+            })
+        ));
+        assert!(matches!(
+            server,
+            Err(FenrisError::IncompatibleProtocolVersion {
+                client: PROTOCOL_VERSION,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn split_halves_send_and_receive_independently() {
+        let (client_stream, server_stream) = setup_connection().await;
+        let key = SessionKey::from(vec![3u8; KEY_SIZE]);
+
+        let client = SecureChannel::<TestConfig>::new(
+            client_stream,
+            key.clone(),
+            TestConfig::crypto(),
+            TestConfig::compression(),
+        );
+        let server = SecureChannel::<TestConfig>::new(
+            server_stream,
+            key,
+            TestConfig::crypto(),
+            TestConfig::compression(),
+        );
+
+        let (mut client_reader, mut client_writer) = client.into_split();
+        let (mut server_reader, mut server_writer) = server.into_split();
+
+        let echo_task = tokio::spawn(async move {
+            let received: TestMessage = server_reader.recv_msg().await.unwrap();
+            server_writer
+                .send_msg(&TestMessage {
+                    value: received.value + 1,
+                })
+                .await
+                .unwrap();
+            (server_reader, server_writer)
+        });
+
+        client_writer
+            .send_msg(&TestMessage { value: 10 })
+            .await
+            .unwrap();
+        let reply: TestMessage = client_reader.recv_msg().await.unwrap();
+        let (server_reader, server_writer) = echo_task.await.unwrap();
+
+        assert_eq!(reply, TestMessage { value: 11 });
+
+        let mut client =
+            SecureChannel::<TestConfig, TcpStream>::reunite(client_reader, client_writer).unwrap();
+        let mut server =
+            SecureChannel::<TestConfig, TcpStream>::reunite(server_reader, server_writer).unwrap();
+        assert_eq!(client.bytes_sent(), server.bytes_received());
+
+        client.send_msg(&TestMessage { value: 20 }).await.unwrap();
+        let received: TestMessage = server.recv_msg().await.unwrap();
+        assert_eq!(received, TestMessage { value: 20 });
+    }
+
+    #[tokio::test]
+    async fn psk_challenge_survives_split_and_reunite() {
+        let (client_stream, server_stream) = setup_connection().await;
+        let (client, server) = tokio::join!(
+            SecureChannel::<TestConfig>::client_handshake(client_stream),
+            SecureChannel::<TestConfig>::server_handshake(server_stream)
+        );
+        let mut server = server.unwrap();
+
+        // Only one side is split, so a forgotten transcript would no longer match the peer's.
+        let (reader, writer) = client.unwrap().into_split();
+        let mut client = SecureChannel::<TestConfig, TcpStream>::reunite(reader, writer).unwrap();
+
+        let (client, server) = tokio::join!(
+            client.client_psk_response("shared-token"),
+            server.server_psk_challenge("shared-token")
+        );
+        client.unwrap();
+        server.unwrap();
+    }
+
+    #[test]
+    fn sealed_frames_authenticate_header_and_sequence() {
+        let crypto = TestConfig::crypto();
+        let compressor = TestConfig::compression();
+        let key = SessionKey::from(vec![4u8; KEY_SIZE]);
+        let message = TestMessage { value: 42 };
+
+        let packet = seal_msg::<TestConfig, TestMessage>(
+            &message,
+            &crypto,
+            &compressor,
+            DEFAULT_COMPRESSION_THRESHOLD,
+            &key,
+            0,
+        )
+        .unwrap();
+        let opened: TestMessage =
+            open_msg::<TestConfig, TestMessage>(&packet, &crypto, &compressor, &key, 0).unwrap();
+        assert_eq!(opened, message);
+
+        let replayed = open_msg::<TestConfig, TestMessage>(&packet, &crypto, &compressor, &key, 1);
+        assert!(matches!(replayed, Err(FenrisError::DecryptionError(_))));
+
+        let mut relabelled = packet.clone();
+        relabelled[..FRAME_HEADER_SIZE].copy_from_slice(&frame_header(FRAME_TAG_MESSAGE, 1));
+        let relabelled =
+            open_msg::<TestConfig, TestMessage>(&relabelled, &crypto, &compressor, &key, 1);
+        assert!(matches!(relabelled, Err(FenrisError::DecryptionError(_))));
+    }
+
+    #[test]
+    fn payloads_below_the_threshold_skip_compression() {
+        let crypto = TestConfig::crypto();
+        let compressor = TestConfig::compression();
+        let key = SessionKey::from(vec![6u8; KEY_SIZE]);
+        let message = TestMessage { value: 7 };
+
+        for (threshold, marker) in [(2, PAYLOAD_UNCOMPRESSED), (1, PAYLOAD_COMPRESSED)] {
+            let packet = seal_msg::<TestConfig, TestMessage>(
+                &message,
+                &crypto,
+                &compressor,
+                threshold,
+                &key,
+                0,
+            )
+            .unwrap();
+            let (header, sealed) = packet.split_at(FRAME_HEADER_SIZE);
+            let payload = crypto.open_with_aad(sealed, &key, header).unwrap();
+            assert_eq!(payload[0], marker);
+
+            let opened: TestMessage =
+                open_msg::<TestConfig, TestMessage>(&packet, &crypto, &compressor, &key, 0)
+                    .unwrap();
+            assert_eq!(opened, message);
+        }
+    }
+
+    #[tokio::test]
+    async fn compression_threshold_survives_split_and_reunite() {
+        let (client_stream, _server_stream) = setup_connection().await;
+        let mut channel = SecureChannel::<TestConfig>::new(
+            client_stream,
+            SessionKey::from(vec![7u8; KEY_SIZE]),
+            TestConfig::crypto(),
+            TestConfig::compression(),
+        );
+        assert_eq!(
+            channel.compression_threshold(),
+            DEFAULT_COMPRESSION_THRESHOLD
+        );
+        channel.set_compression_threshold(0);
+
+        let (reader, writer) = channel.into_split();
+        let channel = SecureChannel::<TestConfig, TcpStream>::reunite(reader, writer).unwrap();
+
+        assert_eq!(channel.compression_threshold(), 0);
+    }
+
+    #[tokio::test]
+    async fn split_halves_round_trip_over_duplex_stream() {
+        let (client_io, server_io) = tokio::io::duplex(1024);
+        let (_client_read, client_write) = tokio::io::split(client_io);
+        let (server_read, _server_write) = tokio::io::split(server_io);
+        let key = SessionKey::from(vec![5u8; KEY_SIZE]);
+
+        let mut writer = SecureChannelWriter::<TestConfig, _>::new(
+            client_write,
+            key.clone(),
+            Arc::new(TestConfig::crypto()),
+            Arc::new(TestConfig::compression()),
+        );
+        let mut reader = SecureChannelReader::<TestConfig, _>::new(
+            server_read,
+            key,
+            Arc::new(TestConfig::crypto()),
+            Arc::new(TestConfig::compression()),
+        );
+
+        writer.send_msg(&TestMessage { value: 99 }).await.unwrap();
+        writer.send_msg(&TestMessage { value: 100 }).await.unwrap();
+        let first: TestMessage = reader.recv_msg().await.unwrap();
+        let second: TestMessage = reader.recv_msg().await.unwrap();
+
+        assert_eq!(first, TestMessage { value: 99 });
+        assert_eq!(second, TestMessage { value: 100 });
     }
 
     #[tokio::test]
@@ -310,6 +1444,49 @@ mod tests {
         assert_eq!(received, TestMessage { value: 7 });
     }
 
+    async fn psk_handshake(client_psk: &str, server_psk: &str) -> (Result<()>, Result<()>) {
+        let (client_stream, server_stream) = setup_connection().await;
+        let identity_key = ServerIdentityKey::generate();
+        let expected_identity = identity_key.public_key();
+        let client_psk = client_psk.to_string();
+        let server_psk = server_psk.to_string();
+
+        let client = async {
+            let mut channel = SecureChannel::<TestConfig>::client_handshake_authenticated(
+                client_stream,
+                expected_identity,
+            )
+            .await?;
+            channel.client_psk_response(&client_psk).await
+        };
+        let server = async {
+            let mut channel = SecureChannel::<TestConfig>::server_handshake_authenticated(
+                server_stream,
+                &identity_key,
+            )
+            .await?;
+            channel.server_psk_challenge(&server_psk).await
+        };
+
+        tokio::join!(client, server)
+    }
+
+    #[tokio::test]
+    async fn psk_challenge_accepts_matching_psk() {
+        let (client, server) = psk_handshake("shared-token", "shared-token").await;
+
+        client.unwrap();
+        server.unwrap();
+    }
+
+    #[tokio::test]
+    async fn psk_challenge_rejects_wrong_psk() {
+        let (client, server) = psk_handshake("wrong-token", "shared-token").await;
+
+        assert!(client.is_ok());
+        assert!(matches!(server, Err(FenrisError::AuthenticationFailed)));
+    }
+
     #[tokio::test]
     async fn authenticated_handshake_rejects_wrong_pinned_server_identity() {
         let (client_stream, server_stream) = setup_connection().await;
@@ -348,6 +1525,9 @@ mod tests {
             .await
         });
 
+        server_negotiate_version(&mut server_stream, &(PROTOCOL_VERSION..=PROTOCOL_VERSION))
+            .await
+            .unwrap();
         let client_public_key = network::receive_prefixed(&mut server_stream).await.unwrap();
         let crypto = TestConfig::crypto();
         let (_private_key, server_public_key) = crypto.generate_keypair();
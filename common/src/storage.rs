@@ -1,12 +1,21 @@
-use crate::{DefaultFileOperations, FenrisError, FenrisMetadata, FileOperations, Result};
+use crate::compression::Compressor;
+use crate::file_ops::capped_list_depth;
+use crate::{
+    DefaultFileOperations, FenrisError, FenrisMetadata, FileOperations, QuotaUsage, Result,
+};
+use arc_swap::ArcSwap;
+use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
 use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
 use std::sync::{Arc, Mutex, MutexGuard};
 use tokio::{
     fs::File,
-    io::{AsyncReadExt, AsyncSeekExt, SeekFrom},
+    io::{AsyncRead, AsyncReadExt, AsyncSeekExt, SeekFrom},
 };
 
+pub type ObjectReader = Pin<Box<dyn AsyncRead + Send>>;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ObjectChunk {
     pub offset: u64,
@@ -30,16 +39,216 @@ pub trait StorageBackend: Send + Sync + 'static {
 
     async fn append_object(&self, path: &Path, data: &[u8]) -> Result<()>;
 
+    async fn object_reader(&self, path: &Path) -> Result<ObjectReader> {
+        Ok(Box::pin(std::io::Cursor::new(self.get_object(path).await?)))
+    }
+
+    async fn put_object_from_reader(
+        &self,
+        path: &Path,
+        reader: &mut (dyn AsyncRead + Send + Unpin),
+    ) -> Result<u64> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await.map_err(|e| {
+            FenrisError::file_operation_from(format!("Failed to read object data: {}", e), e)
+        })?;
+        self.put_object(path, &data).await?;
+        Ok(data.len() as u64)
+    }
+
     async fn delete_object(&self, path: &Path) -> Result<()>;
 
+    async fn checksum_object(&self, path: &Path) -> Result<[u8; 32]> {
+        Ok(Sha256::digest(self.get_object(path).await?).into())
+    }
+
+    /// SHA-256 of the first `limit` objects below `path`, keyed by their path relative to it
+    /// in sorted order.
+    async fn checksum_manifest(
+        &self,
+        path: &Path,
+        limit: usize,
+    ) -> Result<Vec<(PathBuf, [u8; 32])>> {
+        let mut manifest = Vec::new();
+        for (relative, metadata) in self.list_namespace_recursive(path, 0).await? {
+            if manifest.len() == limit {
+                break;
+            }
+            if !metadata.is_namespace {
+                let digest = self.checksum_object(&path.join(&relative)).await?;
+                manifest.push((relative, digest));
+            }
+        }
+        Ok(manifest)
+    }
+
+    async fn compress_object(
+        &self,
+        src: &Path,
+        dst: &Path,
+        compressor: &dyn Compressor,
+    ) -> Result<u64> {
+        let compressed = compressor.compress(&self.get_object(src).await?)?;
+        self.put_object(dst, &compressed).await?;
+        Ok(compressed.len() as u64)
+    }
+
+    async fn decompress_object(
+        &self,
+        src: &Path,
+        dst: &Path,
+        compressor: &dyn Compressor,
+    ) -> Result<u64> {
+        let decompressed = compressor.decompress(&self.get_object(src).await?)?;
+        self.put_object(dst, &decompressed).await?;
+        Ok(decompressed.len() as u64)
+    }
+
+    async fn create_symlink(&self, _link: &Path, _target: &Path) -> Result<()> {
+        Err(FenrisError::InvalidRequest(
+            "symlinks are not supported by this storage backend".to_string(),
+        ))
+    }
+
+    async fn read_symlink(&self, _link: &Path) -> Result<PathBuf> {
+        Err(FenrisError::InvalidRequest(
+            "symlinks are not supported by this storage backend".to_string(),
+        ))
+    }
+
+    async fn create_dir_junction(&self, _link: &Path, _target: &Path) -> Result<()> {
+        Err(FenrisError::InvalidRequest(
+            "directory junctions are not supported by this storage backend".to_string(),
+        ))
+    }
+
+    async fn set_mtime(&self, _path: &Path, _mtime: u64) -> Result<()> {
+        Err(FenrisError::InvalidRequest(
+            "modification times cannot be set on this storage backend".to_string(),
+        ))
+    }
+
+    async fn get_xattr(&self, _path: &Path, _name: &str) -> Result<Vec<u8>> {
+        Err(FenrisError::InvalidRequest(
+            "xattrs are not supported by this storage backend".to_string(),
+        ))
+    }
+
+    async fn set_xattr(&self, _path: &Path, _name: &str, _value: &[u8]) -> Result<()> {
+        Err(FenrisError::InvalidRequest(
+            "xattrs are not supported by this storage backend".to_string(),
+        ))
+    }
+
+    async fn create_archive(&self, _src: &Path, _dst: &Path) -> Result<u64> {
+        Err(FenrisError::InvalidRequest(
+            "archives are not supported by this storage backend".to_string(),
+        ))
+    }
+
+    async fn extract_archive(&self, _archive: &Path, _dst: &Path) -> Result<u64> {
+        Err(FenrisError::InvalidRequest(
+            "archives are not supported by this storage backend".to_string(),
+        ))
+    }
+
+    /// Points the backend at a new root. Operations already in progress finish against the
+    /// old one.
+    async fn reload_base_dir(&self, _base_dir: PathBuf) -> Result<()> {
+        Err(FenrisError::InvalidRequest(
+            "this storage backend has no base directory to reload".to_string(),
+        ))
+    }
+
+    /// Bytes stored and the limit on them, when the backend tracks a quota.
+    fn quota_usage(&self) -> Option<QuotaUsage> {
+        None
+    }
+
+    async fn set_quota_limit(&self, _limit: u64) -> Result<()> {
+        Err(FenrisError::InvalidRequest(
+            "quotas are not supported by this storage backend".to_string(),
+        ))
+    }
+
+    /// A separate backend with the same settings, rooted at `base_dir` and charged to
+    /// `account`'s quota.
+    async fn rebased(&self, _base_dir: PathBuf, _account: &str) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Err(FenrisError::InvalidRequest(
+            "this storage backend cannot be rooted at another directory".to_string(),
+        ))
+    }
+
     async fn metadata(&self, path: &Path) -> Result<FenrisMetadata>;
 
+    /// Fails if `path` already exists.
     async fn create_namespace(&self, path: &Path) -> Result<()>;
 
+    /// Creates `path` and any missing parent namespaces. An existing namespace is not an error.
+    async fn create_namespace_all(&self, path: &Path) -> Result<()> {
+        let mut ancestors: Vec<&Path> = path.ancestors().collect();
+        ancestors.reverse();
+        for ancestor in ancestors {
+            if ancestor.as_os_str().is_empty() {
+                continue;
+            }
+            match self.metadata(ancestor).await {
+                Ok(metadata) if metadata.is_namespace => {}
+                Ok(_) => {
+                    return Err(FenrisError::file_operation(format!(
+                        "{} is an object",
+                        ancestor.display()
+                    )));
+                }
+                Err(_) => self.create_namespace(ancestor).await?,
+            }
+        }
+        Ok(())
+    }
+
     async fn list_namespace(&self, path: &Path) -> Result<Vec<FenrisMetadata>>;
 
+    async fn list_namespace_recursive(
+        &self,
+        path: &Path,
+        max_depth: u32,
+    ) -> Result<Vec<(PathBuf, FenrisMetadata)>> {
+        let max_depth = capped_list_depth(max_depth);
+        let mut entries = Vec::new();
+        let mut pending = vec![(PathBuf::new(), 1)];
+
+        while let Some((relative_dir, depth)) = pending.pop() {
+            for metadata in self.list_namespace(&path.join(&relative_dir)).await? {
+                let relative = relative_dir.join(&metadata.name);
+                if metadata.is_namespace && depth < max_depth {
+                    pending.push((relative.clone(), depth + 1));
+                }
+                entries.push((relative, metadata));
+            }
+        }
+
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries)
+    }
+
     async fn delete_namespace(&self, path: &Path) -> Result<()>;
 
+    async fn move_object(&self, _from: &Path, _to: &Path) -> Result<()> {
+        Err(FenrisError::InvalidRequest(
+            "moves are not supported by this storage backend".to_string(),
+        ))
+    }
+
+    /// Moves a namespace and everything under it.
+    async fn move_namespace(&self, _from: &Path, _to: &Path) -> Result<()> {
+        Err(FenrisError::InvalidRequest(
+            "moves are not supported by this storage backend".to_string(),
+        ))
+    }
+
     async fn exists(&self, path: &Path) -> bool;
 
     async fn is_namespace(&self, path: &Path) -> bool;
@@ -49,33 +258,45 @@ pub trait StorageBackend: Send + Sync + 'static {
 
 #[derive(Debug, Clone)]
 pub struct TokioFsStorage {
-    file_ops: DefaultFileOperations,
+    file_ops: Arc<ArcSwap<DefaultFileOperations>>,
 }
 
 impl TokioFsStorage {
+    /// Uses `base_dir` as given; build the file operations with [`DefaultFileOperations::new`]
+    /// and pass them to [`TokioFsStorage::with_file_ops`] to canonicalize it first.
     pub fn new(base_dir: PathBuf) -> Self {
+        Self::with_file_ops(DefaultFileOperations::new_unchecked(base_dir))
+    }
+
+    pub fn with_file_ops(file_ops: DefaultFileOperations) -> Self {
         Self {
-            file_ops: DefaultFileOperations::new(base_dir),
+            file_ops: Arc::new(ArcSwap::from_pointee(file_ops)),
         }
     }
 
-    pub fn with_file_ops(file_ops: DefaultFileOperations) -> Self {
-        Self { file_ops }
+    pub fn base_dir(&self) -> PathBuf {
+        self.file_ops().base_dir().to_path_buf()
+    }
+
+    /// Later operations, including those through clones of this storage, use `file_ops`; ones
+    /// already running keep the instance they started with.
+    pub fn reload_file_ops(&self, file_ops: DefaultFileOperations) {
+        self.file_ops.store(Arc::new(file_ops));
     }
 
-    pub fn base_dir(&self) -> &Path {
-        self.file_ops.base_dir()
+    fn file_ops(&self) -> Arc<DefaultFileOperations> {
+        self.file_ops.load_full()
     }
 }
 
 #[async_trait::async_trait]
 impl StorageBackend for TokioFsStorage {
     async fn put_object(&self, path: &Path, data: &[u8]) -> Result<()> {
-        self.file_ops.write_file(path, data).await
+        self.file_ops().write_file(path, data).await
     }
 
     async fn get_object(&self, path: &Path) -> Result<Vec<u8>> {
-        self.file_ops.read_file(path).await
+        self.file_ops().read_file(path).await
     }
 
     async fn get_object_chunk(
@@ -84,31 +305,132 @@ impl StorageBackend for TokioFsStorage {
         offset: u64,
         max_len: usize,
     ) -> Result<ObjectChunk> {
-        read_file_chunk(&self.file_ops.resolve_path(path)?, offset, max_len).await
+        read_file_chunk(&self.file_ops().resolve_path(path)?, offset, max_len).await
     }
 
     async fn append_object(&self, path: &Path, data: &[u8]) -> Result<()> {
-        self.file_ops.append_file(path, data).await
+        self.file_ops().append_file(path, data).await
+    }
+
+    async fn object_reader(&self, path: &Path) -> Result<ObjectReader> {
+        self.file_ops().read_file_streaming(path).await
+    }
+
+    async fn put_object_from_reader(
+        &self,
+        path: &Path,
+        reader: &mut (dyn AsyncRead + Send + Unpin),
+    ) -> Result<u64> {
+        self.file_ops().write_file_from_reader(path, reader).await
     }
 
     async fn delete_object(&self, path: &Path) -> Result<()> {
-        self.file_ops.delete_file(path).await
+        self.file_ops().delete_file(path).await
+    }
+
+    async fn checksum_object(&self, path: &Path) -> Result<[u8; 32]> {
+        self.file_ops().checksum_file(path).await
+    }
+
+    async fn checksum_manifest(
+        &self,
+        path: &Path,
+        limit: usize,
+    ) -> Result<Vec<(PathBuf, [u8; 32])>> {
+        self.file_ops().checksum_manifest(path, limit).await
+    }
+
+    async fn compress_object(
+        &self,
+        src: &Path,
+        dst: &Path,
+        compressor: &dyn Compressor,
+    ) -> Result<u64> {
+        self.file_ops().compress_file(src, dst, compressor).await
+    }
+
+    async fn decompress_object(
+        &self,
+        src: &Path,
+        dst: &Path,
+        compressor: &dyn Compressor,
+    ) -> Result<u64> {
+        self.file_ops().decompress_file(src, dst, compressor).await
+    }
+
+    async fn create_symlink(&self, link: &Path, target: &Path) -> Result<()> {
+        self.file_ops().create_symlink(link, target).await
+    }
+
+    async fn read_symlink(&self, link: &Path) -> Result<PathBuf> {
+        self.file_ops().read_symlink(link).await
+    }
+
+    async fn create_dir_junction(&self, link: &Path, target: &Path) -> Result<()> {
+        self.file_ops().create_dir_junction(link, target).await
+    }
+
+    async fn set_mtime(&self, path: &Path, mtime: u64) -> Result<()> {
+        self.file_ops().set_mtime(path, mtime).await
+    }
+
+    async fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>> {
+        self.file_ops().get_xattr(path, name).await
+    }
+
+    async fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> Result<()> {
+        self.file_ops().set_xattr(path, name, value).await
+    }
+
+    async fn create_archive(&self, src: &Path, dst: &Path) -> Result<u64> {
+        self.file_ops().create_archive(src, dst).await
+    }
+
+    async fn extract_archive(&self, archive: &Path, dst: &Path) -> Result<u64> {
+        self.file_ops().extract_archive(archive, dst).await
+    }
+
+    async fn reload_base_dir(&self, base_dir: PathBuf) -> Result<()> {
+        let file_ops = self.file_ops().rebased(base_dir).await?;
+        self.reload_file_ops(file_ops);
+        Ok(())
+    }
+
+    fn quota_usage(&self) -> Option<QuotaUsage> {
+        self.file_ops().quota_usage()
+    }
+
+    async fn set_quota_limit(&self, limit: u64) -> Result<()> {
+        self.file_ops().set_quota_limit(limit).await
+    }
+
+    async fn rebased(&self, base_dir: PathBuf, account: &str) -> Result<Self> {
+        Ok(Self::with_file_ops(
+            self.file_ops()
+                .rebased(base_dir)
+                .await?
+                .with_quota_account(account),
+        ))
     }
 
     async fn metadata(&self, path: &Path) -> Result<FenrisMetadata> {
-        self.file_ops
+        self.file_ops()
             .file_info(path)
             .await
             .map(FenrisMetadata::from)
     }
 
     async fn create_namespace(&self, path: &Path) -> Result<()> {
-        self.file_ops.create_dir(path).await
+        self.file_ops().create_dir(path).await
+    }
+
+    async fn create_namespace_all(&self, path: &Path) -> Result<()> {
+        self.file_ops().create_dir_all(path).await
     }
 
     async fn list_namespace(&self, path: &Path) -> Result<Vec<FenrisMetadata>> {
         Ok(self
-            .file_ops
+            .file_ops()
             .list_dir(path)
             .await?
             .into_iter()
@@ -116,20 +438,42 @@ impl StorageBackend for TokioFsStorage {
             .collect())
     }
 
+    async fn list_namespace_recursive(
+        &self,
+        path: &Path,
+        max_depth: u32,
+    ) -> Result<Vec<(PathBuf, FenrisMetadata)>> {
+        Ok(self
+            .file_ops()
+            .list_dir_recursive(path, max_depth)
+            .await?
+            .into_iter()
+            .map(|(relative, metadata)| (relative, FenrisMetadata::from(metadata)))
+            .collect())
+    }
+
     async fn delete_namespace(&self, path: &Path) -> Result<()> {
-        self.file_ops.delete_dir(path).await
+        self.file_ops().delete_dir(path).await
+    }
+
+    async fn move_object(&self, from: &Path, to: &Path) -> Result<()> {
+        self.file_ops().move_file(from, to).await
+    }
+
+    async fn move_namespace(&self, from: &Path, to: &Path) -> Result<()> {
+        self.file_ops().move_dir(from, to).await
     }
 
     async fn exists(&self, path: &Path) -> bool {
-        self.file_ops.exists(path).await
+        self.file_ops().exists(path).await
     }
 
     async fn is_namespace(&self, path: &Path) -> bool {
-        self.file_ops.is_dir(path).await
+        self.file_ops().is_dir(path).await
     }
 
     async fn is_object(&self, path: &Path) -> bool {
-        self.file_ops.is_file(path).await
+        self.file_ops().is_file(path).await
     }
 }
 
@@ -177,9 +521,7 @@ impl MemoryStorage {
                     normalized.pop();
                 }
                 Component::ParentDir | Component::Prefix(_) => {
-                    return Err(FenrisError::FileOperationError(
-                        "Path outside storage root".to_string(),
-                    ));
+                    return Err(FenrisError::file_operation("Path outside storage root"));
                 }
             }
         }
@@ -207,14 +549,15 @@ impl MemoryStorage {
             size,
             is_namespace,
             modified_time: 0,
+            created_time: 0,
             permissions: if is_namespace { 0o755 } else { 0o644 },
         }
     }
 
     fn lock_state(&self) -> Result<MutexGuard<'_, MemoryStorageState>> {
-        self.state.lock().map_err(|_| {
-            FenrisError::FileOperationError("Memory storage lock poisoned".to_string())
-        })
+        self.state
+            .lock()
+            .map_err(|_| FenrisError::file_operation("Memory storage lock poisoned"))
     }
 
     fn ensure_parent_namespace(state: &MemoryStorageState, path: &Path) -> Result<()> {
@@ -225,9 +568,7 @@ impl MemoryStorage {
             return Ok(());
         }
 
-        Err(FenrisError::FileOperationError(
-            "Parent namespace not found".to_string(),
-        ))
+        Err(FenrisError::file_operation("Parent namespace not found"))
     }
 }
 
@@ -238,9 +579,7 @@ impl StorageBackend for MemoryStorage {
         let mut state = self.lock_state()?;
 
         if path == Path::new("/") || state.namespaces.contains(&path) {
-            return Err(FenrisError::FileOperationError(
-                "Path is a namespace".to_string(),
-            ));
+            return Err(FenrisError::file_operation("Path is a namespace"));
         }
 
         Self::ensure_parent_namespace(&state, &path)?;
@@ -254,7 +593,7 @@ impl StorageBackend for MemoryStorage {
             .objects
             .get(&path)
             .cloned()
-            .ok_or_else(|| FenrisError::FileOperationError("Object not found".to_string()))
+            .ok_or_else(|| FenrisError::file_operation("Object not found"))
     }
 
     async fn get_object_chunk(
@@ -274,7 +613,7 @@ impl StorageBackend for MemoryStorage {
         let data = state
             .objects
             .get(&path)
-            .ok_or_else(|| FenrisError::FileOperationError("Object not found".to_string()))?;
+            .ok_or_else(|| FenrisError::file_operation("Object not found"))?;
         let total_size = data.len() as u64;
 
         if offset >= total_size {
@@ -302,9 +641,7 @@ impl StorageBackend for MemoryStorage {
         let mut state = self.lock_state()?;
 
         if path == Path::new("/") || state.namespaces.contains(&path) {
-            return Err(FenrisError::FileOperationError(
-                "Path is a namespace".to_string(),
-            ));
+            return Err(FenrisError::file_operation("Path is a namespace"));
         }
 
         Self::ensure_parent_namespace(&state, &path)?;
@@ -322,9 +659,7 @@ impl StorageBackend for MemoryStorage {
             return Ok(());
         }
 
-        Err(FenrisError::FileOperationError(
-            "Object not found".to_string(),
-        ))
+        Err(FenrisError::file_operation("Object not found"))
     }
 
     async fn metadata(&self, path: &Path) -> Result<FenrisMetadata> {
@@ -339,9 +674,7 @@ impl StorageBackend for MemoryStorage {
             return Ok(Self::metadata_for(&path, 0, true));
         }
 
-        Err(FenrisError::FileOperationError(
-            "Path not found".to_string(),
-        ))
+        Err(FenrisError::file_operation("Path not found"))
     }
 
     async fn create_namespace(&self, path: &Path) -> Result<()> {
@@ -349,9 +682,11 @@ impl StorageBackend for MemoryStorage {
         let mut state = self.lock_state()?;
 
         if state.objects.contains_key(&path) {
-            return Err(FenrisError::FileOperationError(
-                "Path is an object".to_string(),
-            ));
+            return Err(FenrisError::file_operation("Path is an object"));
+        }
+
+        if state.namespaces.contains(&path) {
+            return Err(FenrisError::file_operation("Namespace already exists"));
         }
 
         if path != Path::new("/") {
@@ -367,9 +702,7 @@ impl StorageBackend for MemoryStorage {
         let state = self.lock_state()?;
 
         if !state.namespaces.contains(&path) {
-            return Err(FenrisError::FileOperationError(
-                "Namespace not found".to_string(),
-            ));
+            return Err(FenrisError::file_operation("Namespace not found"));
         }
 
         let mut entries = Vec::new();
@@ -395,15 +728,11 @@ impl StorageBackend for MemoryStorage {
         let mut state = self.lock_state()?;
 
         if path == Path::new("/") {
-            return Err(FenrisError::FileOperationError(
-                "Cannot delete root namespace".to_string(),
-            ));
+            return Err(FenrisError::file_operation("Cannot delete root namespace"));
         }
 
         if !state.namespaces.contains(&path) {
-            return Err(FenrisError::FileOperationError(
-                "Namespace not found".to_string(),
-            ));
+            return Err(FenrisError::file_operation("Namespace not found"));
         }
 
         let has_children = state
@@ -413,15 +742,73 @@ impl StorageBackend for MemoryStorage {
             || state.objects.keys().any(|object| object.starts_with(&path));
 
         if has_children {
-            return Err(FenrisError::FileOperationError(
-                "Namespace is not empty".to_string(),
-            ));
+            return Err(FenrisError::file_operation("Namespace is not empty"));
         }
 
         state.namespaces.remove(&path);
         Ok(())
     }
 
+    async fn move_object(&self, from: &Path, to: &Path) -> Result<()> {
+        let from = Self::normalize_path(from)?;
+        let to = Self::normalize_path(to)?;
+        let mut state = self.lock_state()?;
+
+        if state.namespaces.contains(&to) {
+            return Err(FenrisError::file_operation("Path is a namespace"));
+        }
+        Self::ensure_parent_namespace(&state, &to)?;
+        let data = state
+            .objects
+            .remove(&from)
+            .ok_or_else(|| FenrisError::file_operation("Object not found"))?;
+        state.objects.insert(to, data);
+        Ok(())
+    }
+
+    async fn move_namespace(&self, from: &Path, to: &Path) -> Result<()> {
+        let from = Self::normalize_path(from)?;
+        let to = Self::normalize_path(to)?;
+        let mut state = self.lock_state()?;
+
+        if from == Path::new("/") || !state.namespaces.contains(&from) {
+            return Err(FenrisError::file_operation("Namespace not found"));
+        }
+        if to.starts_with(&from) {
+            return Err(FenrisError::file_operation(
+                "Cannot move a namespace into itself",
+            ));
+        }
+        if state.objects.contains_key(&to) || state.namespaces.contains(&to) {
+            return Err(FenrisError::file_operation("Destination already exists"));
+        }
+        Self::ensure_parent_namespace(&state, &to)?;
+
+        let rebase = |path: &Path| to.join(path.strip_prefix(&from).unwrap_or(path));
+        let namespaces: Vec<PathBuf> = state
+            .namespaces
+            .iter()
+            .filter(|namespace| namespace.starts_with(&from))
+            .cloned()
+            .collect();
+        for namespace in namespaces {
+            state.namespaces.remove(&namespace);
+            state.namespaces.insert(rebase(&namespace));
+        }
+        let objects: Vec<PathBuf> = state
+            .objects
+            .keys()
+            .filter(|object| object.starts_with(&from))
+            .cloned()
+            .collect();
+        for object in objects {
+            if let Some(data) = state.objects.remove(&object) {
+                state.objects.insert(rebase(&object), data);
+            }
+        }
+        Ok(())
+    }
+
     async fn exists(&self, path: &Path) -> bool {
         let Ok(path) = Self::normalize_path(path) else {
             return false;
@@ -462,11 +849,11 @@ async fn read_file_chunk(path: &Path, offset: u64, max_len: usize) -> Result<Obj
 
     let mut file = File::open(path)
         .await
-        .map_err(|e| FenrisError::FileOperationError(format!("Failed to open file: {}", e)))?;
+        .map_err(|e| FenrisError::file_operation_from(format!("Failed to open file: {}", e), e))?;
     let total_size = file
         .metadata()
         .await
-        .map_err(|e| FenrisError::FileOperationError(format!("Failed to get metadata: {}", e)))?
+        .map_err(|e| FenrisError::file_operation_from(format!("Failed to get metadata: {}", e), e))?
         .len();
 
     if offset >= total_size {
@@ -480,14 +867,14 @@ async fn read_file_chunk(path: &Path, offset: u64, max_len: usize) -> Result<Obj
 
     file.seek(SeekFrom::Start(offset))
         .await
-        .map_err(|e| FenrisError::FileOperationError(format!("Failed to seek file: {}", e)))?;
+        .map_err(|e| FenrisError::file_operation_from(format!("Failed to seek file: {}", e), e))?;
 
     let remaining = (total_size - offset) as usize;
     let mut data = vec![0; remaining.min(max_len)];
     let read = file
         .read(&mut data)
         .await
-        .map_err(|e| FenrisError::FileOperationError(format!("Failed to read file: {}", e)))?;
+        .map_err(|e| FenrisError::file_operation_from(format!("Failed to read file: {}", e), e))?;
     data.truncate(read);
 
     Ok(ObjectChunk {
@@ -578,6 +965,25 @@ mod tests {
         assert_eq!(data, b"entry");
     }
 
+    async fn assert_streaming_reader_and_writer_round_trip<S: StorageBackend>(storage: &S) {
+        let data = vec![3u8; 20_000];
+        let mut source: &[u8] = &data;
+
+        let written = storage
+            .put_object_from_reader(Path::new("stream.bin"), &mut source)
+            .await
+            .unwrap();
+        assert_eq!(written, data.len() as u64);
+
+        let mut reader = storage
+            .object_reader(Path::new("stream.bin"))
+            .await
+            .unwrap();
+        let mut read = Vec::new();
+        reader.read_to_end(&mut read).await.unwrap();
+        assert_eq!(read, data);
+    }
+
     async fn assert_delete_object_removes_object<S: StorageBackend>(storage: &S) {
         storage
             .put_object(Path::new("data.txt"), b"hello")
@@ -590,6 +996,39 @@ mod tests {
         assert!(!storage.exists(Path::new("data.txt")).await);
     }
 
+    async fn assert_move_object_and_namespace<S: StorageBackend>(storage: &S) {
+        storage.create_namespace(Path::new("docs")).await.unwrap();
+        storage
+            .put_object(Path::new("docs/a.txt"), b"hello")
+            .await
+            .unwrap();
+
+        storage
+            .move_object(Path::new("docs/a.txt"), Path::new("docs/b.txt"))
+            .await
+            .unwrap();
+        storage
+            .move_namespace(Path::new("docs"), Path::new("archive"))
+            .await
+            .unwrap();
+
+        assert!(!storage.exists(Path::new("docs")).await);
+        assert!(storage.is_namespace(Path::new("archive")).await);
+        assert_eq!(
+            storage
+                .get_object(Path::new("archive/b.txt"))
+                .await
+                .unwrap(),
+            b"hello"
+        );
+        assert!(
+            storage
+                .move_object(Path::new("archive/a.txt"), Path::new("c.txt"))
+                .await
+                .is_err()
+        );
+    }
+
     async fn assert_get_object_chunk_handles_ranges<S: StorageBackend>(storage: &S) {
         storage
             .put_object(Path::new("data.txt"), b"abcdefghij")
@@ -671,6 +1110,85 @@ mod tests {
         assert!(!storage.exists(Path::new("docs/nested")).await);
     }
 
+    async fn assert_namespace_create_all_creates_parents<S: StorageBackend>(storage: &S) {
+        storage.create_namespace(Path::new("docs")).await.unwrap();
+        assert!(storage.create_namespace(Path::new("docs")).await.is_err());
+        assert!(storage.create_namespace(Path::new("a/b")).await.is_err());
+
+        storage
+            .create_namespace_all(Path::new("a/b/c"))
+            .await
+            .unwrap();
+        storage
+            .create_namespace_all(Path::new("a/b/c"))
+            .await
+            .unwrap();
+        assert!(
+            storage
+                .metadata(Path::new("a/b/c"))
+                .await
+                .unwrap()
+                .is_namespace
+        );
+
+        storage
+            .put_object(Path::new("docs/a.txt"), b"a")
+            .await
+            .unwrap();
+        assert!(
+            storage
+                .create_namespace_all(Path::new("docs/a.txt/inner"))
+                .await
+                .is_err()
+        );
+    }
+
+    async fn assert_recursive_listing_returns_relative_paths<S: StorageBackend>(storage: &S) {
+        storage.create_namespace(Path::new("docs")).await.unwrap();
+        storage
+            .create_namespace(Path::new("docs/nested"))
+            .await
+            .unwrap();
+        storage
+            .put_object(Path::new("docs/nested/a.txt"), b"a")
+            .await
+            .unwrap();
+
+        let entries = storage
+            .list_namespace_recursive(Path::new("docs"), 0)
+            .await
+            .unwrap();
+        let paths: Vec<PathBuf> = entries.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("nested"), PathBuf::from("nested/a.txt")]
+        );
+        assert!(entries[0].1.is_namespace);
+
+        let entries = storage
+            .list_namespace_recursive(Path::new("docs"), 1)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+
+    async fn assert_checksum_object_hashes_contents<S: StorageBackend>(storage: &S) {
+        storage
+            .put_object(Path::new("data.txt"), b"abc")
+            .await
+            .unwrap();
+
+        let digest = storage
+            .checksum_object(Path::new("data.txt"))
+            .await
+            .unwrap();
+        assert_eq!(
+            hex::encode(digest),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(storage.checksum_object(Path::new("missing")).await.is_err());
+    }
+
     async fn assert_existence_and_kind_checks_reflect_storage_state<S: StorageBackend>(
         storage: &S,
     ) {
@@ -692,7 +1210,10 @@ mod tests {
     async fn assert_path_traversal_is_rejected<S: StorageBackend>(storage: &S) {
         let result = storage.get_object(Path::new("../../../etc/passwd")).await;
 
-        assert!(matches!(result, Err(FenrisError::FileOperationError(_))));
+        assert!(matches!(
+            result,
+            Err(FenrisError::FileOperationError { .. })
+        ));
     }
 
     macro_rules! storage_contract_tests {
@@ -727,12 +1248,24 @@ mod tests {
                     .await;
                 }
 
+                #[tokio::test]
+                async fn streaming_reader_and_writer_round_trip() {
+                    let backend = $storage();
+                    assert_streaming_reader_and_writer_round_trip(&backend.storage).await;
+                }
+
                 #[tokio::test]
                 async fn delete_object_removes_object() {
                     let backend = $storage();
                     assert_delete_object_removes_object(&backend.storage).await;
                 }
 
+                #[tokio::test]
+                async fn move_object_and_namespace() {
+                    let backend = $storage();
+                    assert_move_object_and_namespace(&backend.storage).await;
+                }
+
                 #[tokio::test]
                 async fn get_object_chunk_handles_ranges() {
                     let backend = $storage();
@@ -751,6 +1284,24 @@ mod tests {
                     assert_namespace_create_list_and_delete(&backend.storage).await;
                 }
 
+                #[tokio::test]
+                async fn namespace_create_all_creates_parents() {
+                    let backend = $storage();
+                    assert_namespace_create_all_creates_parents(&backend.storage).await;
+                }
+
+                #[tokio::test]
+                async fn recursive_listing_returns_relative_paths() {
+                    let backend = $storage();
+                    assert_recursive_listing_returns_relative_paths(&backend.storage).await;
+                }
+
+                #[tokio::test]
+                async fn checksum_object_hashes_contents() {
+                    let backend = $storage();
+                    assert_checksum_object_hashes_contents(&backend.storage).await;
+                }
+
                 #[tokio::test]
                 async fn existence_and_kind_checks_reflect_storage_state() {
                     let backend = $storage();
@@ -769,6 +1320,41 @@ mod tests {
     storage_contract_tests!(tokio_fs_storage_contract, tokio_fs_storage);
     storage_contract_tests!(memory_storage_contract, memory_storage);
 
+    #[tokio::test]
+    async fn tokio_fs_storage_rebased_is_confined_to_the_new_root() {
+        let backend = tokio_fs_storage();
+        backend
+            .storage
+            .create_namespace(Path::new("alice"))
+            .await
+            .unwrap();
+        backend
+            .storage
+            .put_object(Path::new("shared.txt"), b"shared")
+            .await
+            .unwrap();
+
+        let alice = backend
+            .storage
+            .rebased(backend.storage.base_dir().join("alice"), "alice")
+            .await
+            .unwrap();
+        alice
+            .put_object(Path::new("notes.txt"), b"mine")
+            .await
+            .unwrap();
+
+        assert!(backend.storage.exists(Path::new("alice/notes.txt")).await);
+        assert!(!alice.exists(Path::new("shared.txt")).await);
+        assert!(alice.get_object(Path::new("../shared.txt")).await.is_err());
+        assert!(
+            MemoryStorage::new()
+                .rebased(PathBuf::from("/alice"), "alice")
+                .await
+                .is_err()
+        );
+    }
+
     #[tokio::test]
     async fn memory_storage_uses_logical_absolute_paths() {
         let storage = MemoryStorage::new();
@@ -808,7 +1394,10 @@ mod tests {
             .put_object(Path::new("../../../outside.txt"), b"nope")
             .await;
 
-        assert!(matches!(result, Err(FenrisError::FileOperationError(_))));
+        assert!(matches!(
+            result,
+            Err(FenrisError::FileOperationError { .. })
+        ));
         assert!(!storage.exists(Path::new("outside.txt")).await);
     }
 }
@@ -0,0 +1,377 @@
+use crate::{FenrisError, Result, transport::SplitStream};
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf, ReadHalf, WriteHalf};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsStream;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use tokio_rustls::rustls::{self, AlertDescription, RootCertStore};
+pub use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+/// Certificate chain and private key a server presents to TLS clients, both PEM encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub cert_pem_path: PathBuf,
+    pub key_pem_path: PathBuf,
+}
+
+/// What a client trusts when connecting over TLS. `server_name` is checked against the
+/// certificate; when `None` the host the client dials is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsClientConfig {
+    pub ca_pem_path: PathBuf,
+    pub server_name: Option<String>,
+}
+
+impl TlsConfig {
+    pub fn new(cert_pem_path: impl Into<PathBuf>, key_pem_path: impl Into<PathBuf>) -> Self {
+        Self {
+            cert_pem_path: cert_pem_path.into(),
+            key_pem_path: key_pem_path.into(),
+        }
+    }
+
+    /// Loads the certificate and key, so a bad path fails at startup rather than per client.
+    pub fn acceptor(&self) -> Result<TlsAcceptor> {
+        let certs = CertificateDer::pem_file_iter(&self.cert_pem_path)
+            .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+            .map_err(|e| pem_error(&self.cert_pem_path, e))?;
+        let key = PrivateKeyDer::from_pem_file(&self.key_pem_path)
+            .map_err(|e| pem_error(&self.key_pem_path, e))?;
+
+        let config = rustls::ServerConfig::builder_with_provider(crypto_provider())
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map_err(tls_error)?;
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+impl TlsClientConfig {
+    pub fn new(ca_pem_path: impl Into<PathBuf>) -> Self {
+        Self {
+            ca_pem_path: ca_pem_path.into(),
+            server_name: None,
+        }
+    }
+
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = Some(server_name.into());
+        self
+    }
+
+    pub fn connector(&self) -> Result<TlsConnector> {
+        let mut roots = RootCertStore::empty();
+        for cert in CertificateDer::pem_file_iter(&self.ca_pem_path)
+            .map_err(|e| pem_error(&self.ca_pem_path, e))?
+        {
+            let cert = cert.map_err(|e| pem_error(&self.ca_pem_path, e))?;
+            roots.add(cert).map_err(tls_error)?;
+        }
+
+        let config = rustls::ClientConfig::builder_with_provider(crypto_provider())
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Ok(TlsConnector::from(Arc::new(config)))
+    }
+}
+
+/// A TCP connection wrapped in TLS. The secure channel handshake still runs on top, so
+/// traffic is encrypted twice: once by TLS and once by the channel's AES-GCM.
+pub struct TlsTransport {
+    stream: Box<TlsStream<TcpStream>>,
+}
+
+pub struct TlsReadHalf {
+    half: ReadHalf<Box<TlsStream<TcpStream>>>,
+}
+
+pub struct TlsWriteHalf {
+    half: WriteHalf<Box<TlsStream<TcpStream>>>,
+}
+
+impl TlsTransport {
+    /// Runs the server side of the TLS handshake on a freshly accepted connection.
+    pub async fn accept(stream: TcpStream, acceptor: &TlsAcceptor) -> Result<Self> {
+        let stream = acceptor.accept(stream).await.map_err(handshake_error)?;
+        Ok(Self {
+            stream: Box::new(stream.into()),
+        })
+    }
+
+    /// Fails with [`FenrisError::AuthenticationFailed`] when the server's certificate does
+    /// not check out against the connector's roots and `server_name`.
+    pub async fn connect(
+        stream: TcpStream,
+        connector: &TlsConnector,
+        server_name: &str,
+    ) -> Result<Self> {
+        let server_name = ServerName::try_from(server_name.to_string()).map_err(|e| {
+            FenrisError::NetworkError(io::Error::new(io::ErrorKind::InvalidInput, e))
+        })?;
+        let stream = connector
+            .connect(server_name, stream)
+            .await
+            .map_err(handshake_error)?;
+        Ok(Self {
+            stream: Box::new(stream.into()),
+        })
+    }
+}
+
+impl fmt::Debug for TlsTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsTransport").finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for TlsReadHalf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsReadHalf").finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for TlsWriteHalf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsWriteHalf").finish_non_exhaustive()
+    }
+}
+
+impl SplitStream for TlsTransport {
+    type ReadHalf = TlsReadHalf;
+    type WriteHalf = TlsWriteHalf;
+
+    fn split_owned(self) -> (Self::ReadHalf, Self::WriteHalf) {
+        let (read_half, write_half) = tokio::io::split(self.stream);
+        (
+            TlsReadHalf { half: read_half },
+            TlsWriteHalf { half: write_half },
+        )
+    }
+
+    fn reunite_owned(read_half: Self::ReadHalf, write_half: Self::WriteHalf) -> Option<Self> {
+        // `unsplit` panics on halves of different streams, so check first.
+        read_half.half.is_pair_of(&write_half.half).then(|| Self {
+            stream: read_half.half.unsplit(write_half.half),
+        })
+    }
+}
+
+impl AsyncRead for TlsTransport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TlsTransport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+    }
+}
+
+impl AsyncRead for TlsReadHalf {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().half).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TlsWriteHalf {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().half).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().half).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().half).poll_shutdown(cx)
+    }
+}
+
+/// Pinned to ring so the result doesn't depend on which provider other crates happen to enable.
+fn crypto_provider() -> Arc<rustls::crypto::CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+/// Certificate problems on either end surface as authentication failures; anything else is
+/// an ordinary network error.
+fn handshake_error(error: io::Error) -> FenrisError {
+    let rejected = error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+        .is_some_and(|inner| {
+            matches!(
+                inner,
+                rustls::Error::InvalidCertificate(_)
+                    | rustls::Error::NoCertificatesPresented
+                    | rustls::Error::AlertReceived(
+                        AlertDescription::BadCertificate
+                            | AlertDescription::UnknownCA
+                            | AlertDescription::CertificateExpired
+                    )
+            )
+        });
+    if rejected {
+        FenrisError::AuthenticationFailed
+    } else {
+        FenrisError::NetworkError(error)
+    }
+}
+
+fn tls_error(error: impl std::error::Error + Send + Sync + 'static) -> FenrisError {
+    FenrisError::NetworkError(io::Error::new(io::ErrorKind::InvalidInput, error))
+}
+
+fn pem_error(path: &std::path::Path, error: rustls::pki_types::pem::Error) -> FenrisError {
+    FenrisError::NetworkError(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), error),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, SecureChannel};
+    use tempfile::TempDir;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn write_self_signed(dir: &TempDir, name: &str) -> (TlsConfig, TlsClientConfig) {
+        let certified = rcgen::generate_simple_self_signed(vec![name.to_string()]).unwrap();
+        let cert_path = dir.path().join(format!("{}.crt", name));
+        let key_path = dir.path().join(format!("{}.key", name));
+        std::fs::write(&cert_path, certified.cert.pem()).unwrap();
+        std::fs::write(&key_path, certified.signing_key.serialize_pem()).unwrap();
+
+        (
+            TlsConfig::new(&cert_path, &key_path),
+            TlsClientConfig::new(&cert_path).with_server_name(name),
+        )
+    }
+
+    async fn connected_pair(
+        server: &TlsConfig,
+        client: &TlsClientConfig,
+    ) -> (Result<TlsTransport>, Result<TlsTransport>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let acceptor = server.acceptor().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            TlsTransport::accept(stream, &acceptor).await
+        });
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let connector = client.connector().unwrap();
+        let client = TlsTransport::connect(
+            stream,
+            &connector,
+            client.server_name.as_deref().unwrap_or("localhost"),
+        )
+        .await;
+
+        (client, server.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_bytes_round_trip_over_tls() {
+        let dir = TempDir::new().unwrap();
+        let (server_config, client_config) = write_self_signed(&dir, "localhost");
+        let (client, server) = connected_pair(&server_config, &client_config).await;
+        let (mut client, mut server) = (client.unwrap(), server.unwrap());
+
+        client.write_all(b"hello").await.unwrap();
+        client.flush().await.unwrap();
+
+        let mut buf = [0u8; 5];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+    }
+
+    #[tokio::test]
+    async fn test_untrusted_certificate_fails_authentication() {
+        let dir = TempDir::new().unwrap();
+        let (server_config, _) = write_self_signed(&dir, "localhost");
+        let (_, other_client_config) = write_self_signed(&dir, "other");
+        let client_config = TlsClientConfig {
+            server_name: Some("localhost".to_string()),
+            ..other_client_config
+        };
+
+        let (client, _) = connected_pair(&server_config, &client_config).await;
+
+        assert!(matches!(client, Err(FenrisError::AuthenticationFailed)));
+    }
+
+    #[tokio::test]
+    async fn test_secure_channel_handshake_over_tls() {
+        let dir = TempDir::new().unwrap();
+        let (server_config, client_config) = write_self_signed(&dir, "localhost");
+        let (client, server) = connected_pair(&server_config, &client_config).await;
+        let (client, server) = (client.unwrap(), server.unwrap());
+
+        let server = tokio::spawn(async move {
+            let mut channel = SecureChannel::<Config, _>::server_handshake(server)
+                .await
+                .unwrap();
+            let request: crate::Request = channel.recv_msg().await.unwrap();
+            request.command
+        });
+        let mut channel = crate::TlsSecureChannel::client_handshake(client)
+            .await
+            .unwrap();
+        let request = crate::Request {
+            command: crate::RequestType::Ping as i32,
+            ..Default::default()
+        };
+        channel.send_msg(&request).await.unwrap();
+
+        assert_eq!(server.await.unwrap(), crate::RequestType::Ping as i32);
+    }
+
+    #[tokio::test]
+    async fn test_split_halves_reunite() {
+        let dir = TempDir::new().unwrap();
+        let (server_config, client_config) = write_self_signed(&dir, "localhost");
+        let (client, _server) = connected_pair(&server_config, &client_config).await;
+
+        let (read_half, write_half) = client.unwrap().split_owned();
+
+        assert!(TlsTransport::reunite_owned(read_half, write_half).is_some());
+    }
+}
@@ -0,0 +1,327 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio::net::tcp;
+#[cfg(unix)]
+use tokio::net::{UnixStream, unix};
+
+#[cfg(feature = "tls")]
+use crate::tls::{TlsReadHalf, TlsTransport, TlsWriteHalf};
+#[cfg(feature = "websocket")]
+use crate::websocket::{WebSocketReadHalf, WebSocketTransport, WebSocketWriteHalf};
+
+/// A byte stream that can be split into owned halves and put back together, which is what
+/// [`crate::SecureChannel::into_split`] needs to hand each half to its own task.
+pub trait SplitStream: AsyncRead + AsyncWrite + Unpin + Sized {
+    type ReadHalf: AsyncRead + Unpin;
+    type WriteHalf: AsyncWrite + Unpin;
+
+    fn split_owned(self) -> (Self::ReadHalf, Self::WriteHalf);
+
+    /// Returns `None` when the halves came from different streams.
+    fn reunite_owned(read_half: Self::ReadHalf, write_half: Self::WriteHalf) -> Option<Self>;
+}
+
+impl SplitStream for TcpStream {
+    type ReadHalf = tcp::OwnedReadHalf;
+    type WriteHalf = tcp::OwnedWriteHalf;
+
+    fn split_owned(self) -> (Self::ReadHalf, Self::WriteHalf) {
+        self.into_split()
+    }
+
+    fn reunite_owned(read_half: Self::ReadHalf, write_half: Self::WriteHalf) -> Option<Self> {
+        read_half.reunite(write_half).ok()
+    }
+}
+
+#[cfg(unix)]
+impl SplitStream for UnixStream {
+    type ReadHalf = unix::OwnedReadHalf;
+    type WriteHalf = unix::OwnedWriteHalf;
+
+    fn split_owned(self) -> (Self::ReadHalf, Self::WriteHalf) {
+        self.into_split()
+    }
+
+    fn reunite_owned(read_half: Self::ReadHalf, write_half: Self::WriteHalf) -> Option<Self> {
+        read_half.reunite(write_half).ok()
+    }
+}
+
+/// A connection over TCP, TLS, a WebSocket or, on Unix, a local domain socket.
+#[derive(Debug)]
+pub enum Transport {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+    #[cfg(feature = "websocket")]
+    WebSocket(Box<WebSocketTransport>),
+    #[cfg(feature = "tls")]
+    Tls(TlsTransport),
+}
+
+#[derive(Debug)]
+pub enum TransportReadHalf {
+    Tcp(tcp::OwnedReadHalf),
+    #[cfg(unix)]
+    Unix(unix::OwnedReadHalf),
+    #[cfg(feature = "websocket")]
+    WebSocket(WebSocketReadHalf),
+    #[cfg(feature = "tls")]
+    Tls(TlsReadHalf),
+}
+
+#[derive(Debug)]
+pub enum TransportWriteHalf {
+    Tcp(tcp::OwnedWriteHalf),
+    #[cfg(unix)]
+    Unix(unix::OwnedWriteHalf),
+    #[cfg(feature = "websocket")]
+    WebSocket(WebSocketWriteHalf),
+    #[cfg(feature = "tls")]
+    Tls(TlsWriteHalf),
+}
+
+impl From<TcpStream> for Transport {
+    fn from(stream: TcpStream) -> Self {
+        Self::Tcp(stream)
+    }
+}
+
+#[cfg(unix)]
+impl From<UnixStream> for Transport {
+    fn from(stream: UnixStream) -> Self {
+        Self::Unix(stream)
+    }
+}
+
+#[cfg(feature = "websocket")]
+impl From<WebSocketTransport> for Transport {
+    fn from(stream: WebSocketTransport) -> Self {
+        Self::WebSocket(Box::new(stream))
+    }
+}
+
+#[cfg(feature = "tls")]
+impl From<TlsTransport> for Transport {
+    fn from(stream: TlsTransport) -> Self {
+        Self::Tls(stream)
+    }
+}
+
+impl SplitStream for Transport {
+    type ReadHalf = TransportReadHalf;
+    type WriteHalf = TransportWriteHalf;
+
+    fn split_owned(self) -> (Self::ReadHalf, Self::WriteHalf) {
+        match self {
+            Self::Tcp(stream) => {
+                let (read_half, write_half) = stream.into_split();
+                (
+                    TransportReadHalf::Tcp(read_half),
+                    TransportWriteHalf::Tcp(write_half),
+                )
+            }
+            #[cfg(unix)]
+            Self::Unix(stream) => {
+                let (read_half, write_half) = stream.into_split();
+                (
+                    TransportReadHalf::Unix(read_half),
+                    TransportWriteHalf::Unix(write_half),
+                )
+            }
+            #[cfg(feature = "websocket")]
+            Self::WebSocket(stream) => {
+                let (read_half, write_half) = (*stream).split_owned();
+                (
+                    TransportReadHalf::WebSocket(read_half),
+                    TransportWriteHalf::WebSocket(write_half),
+                )
+            }
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => {
+                let (read_half, write_half) = stream.split_owned();
+                (
+                    TransportReadHalf::Tls(read_half),
+                    TransportWriteHalf::Tls(write_half),
+                )
+            }
+        }
+    }
+
+    fn reunite_owned(read_half: Self::ReadHalf, write_half: Self::WriteHalf) -> Option<Self> {
+        match (read_half, write_half) {
+            (TransportReadHalf::Tcp(read_half), TransportWriteHalf::Tcp(write_half)) => {
+                TcpStream::reunite_owned(read_half, write_half).map(Self::Tcp)
+            }
+            #[cfg(unix)]
+            (TransportReadHalf::Unix(read_half), TransportWriteHalf::Unix(write_half)) => {
+                UnixStream::reunite_owned(read_half, write_half).map(Self::Unix)
+            }
+            #[cfg(feature = "websocket")]
+            (
+                TransportReadHalf::WebSocket(read_half),
+                TransportWriteHalf::WebSocket(write_half),
+            ) => WebSocketTransport::reunite_owned(read_half, write_half)
+                .map(|stream| Self::WebSocket(Box::new(stream))),
+            #[cfg(feature = "tls")]
+            (TransportReadHalf::Tls(read_half), TransportWriteHalf::Tls(write_half)) => {
+                TlsTransport::reunite_owned(read_half, write_half).map(Self::Tls)
+            }
+            #[cfg(any(unix, feature = "websocket", feature = "tls"))]
+            _ => None,
+        }
+    }
+}
+
+impl AsyncRead for Transport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "websocket")]
+            Self::WebSocket(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Transport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "websocket")]
+            Self::WebSocket(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "websocket")]
+            Self::WebSocket(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "websocket")]
+            Self::WebSocket(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+impl AsyncRead for TransportReadHalf {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(half) => Pin::new(half).poll_read(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(half) => Pin::new(half).poll_read(cx, buf),
+            #[cfg(feature = "websocket")]
+            Self::WebSocket(half) => Pin::new(half).poll_read(cx, buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(half) => Pin::new(half).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for TransportWriteHalf {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(half) => Pin::new(half).poll_write(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(half) => Pin::new(half).poll_write(cx, buf),
+            #[cfg(feature = "websocket")]
+            Self::WebSocket(half) => Pin::new(half).poll_write(cx, buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(half) => Pin::new(half).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(half) => Pin::new(half).poll_flush(cx),
+            #[cfg(unix)]
+            Self::Unix(half) => Pin::new(half).poll_flush(cx),
+            #[cfg(feature = "websocket")]
+            Self::WebSocket(half) => Pin::new(half).poll_flush(cx),
+            #[cfg(feature = "tls")]
+            Self::Tls(half) => Pin::new(half).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(half) => Pin::new(half).poll_shutdown(cx),
+            #[cfg(unix)]
+            Self::Unix(half) => Pin::new(half).poll_shutdown(cx),
+            #[cfg(feature = "websocket")]
+            Self::WebSocket(half) => Pin::new(half).poll_shutdown(cx),
+            #[cfg(feature = "tls")]
+            Self::Tls(half) => Pin::new(half).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_split_halves_reunite_only_with_their_own_stream() {
+        let (first, _first_peer) = UnixStream::pair().unwrap();
+        let (second, _second_peer) = UnixStream::pair().unwrap();
+        let (first_read, _) = Transport::from(first).split_owned();
+        let (_, second_write) = Transport::from(second).split_owned();
+
+        assert!(Transport::reunite_owned(first_read, second_write).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unix_transport_round_trips_bytes() {
+        let (local, remote) = UnixStream::pair().unwrap();
+        let mut local = Transport::from(local);
+        let mut remote = Transport::from(remote);
+
+        local.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        remote.read_exact(&mut buf).await.unwrap();
+
+        assert_eq!(&buf, b"ping");
+    }
+}
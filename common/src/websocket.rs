@@ -0,0 +1,294 @@
+use crate::{FenrisError, Result, transport::SplitStream};
+use bytes::Bytes;
+use futures_util::stream::{SplitSink, SplitStream as FrameStream};
+use futures_util::{Sink, Stream, StreamExt};
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+
+type WsStream = WebSocketStream<TcpStream>;
+
+/// The fenris byte stream carried inside WebSocket binary frames, so browsers can speak the
+/// same length-prefixed protocol as native clients. Every write goes out as one frame and reads
+/// concatenate frames back together, so frame boundaries carry no meaning on either side.
+pub struct WebSocketTransport {
+    stream: WsStream,
+    pending: Bytes,
+}
+
+pub struct WebSocketReadHalf {
+    stream: FrameStream<WsStream>,
+    pending: Bytes,
+}
+
+pub struct WebSocketWriteHalf {
+    sink: SplitSink<WsStream, Message>,
+}
+
+impl WebSocketTransport {
+    pub fn new(stream: WsStream) -> Self {
+        Self {
+            stream,
+            pending: Bytes::new(),
+        }
+    }
+
+    /// Runs the server side of the HTTP upgrade on a freshly accepted connection.
+    pub async fn accept(stream: TcpStream) -> Result<Self> {
+        let stream = tokio_tungstenite::accept_async(stream)
+            .await
+            .map_err(websocket_error)?;
+        Ok(Self::new(stream))
+    }
+
+    /// Upgrades an already connected socket, e.g. `connect(stream, "ws://localhost:5556/")`.
+    pub async fn connect(stream: TcpStream, url: &str) -> Result<Self> {
+        let (stream, _) = tokio_tungstenite::client_async(url, stream)
+            .await
+            .map_err(websocket_error)?;
+        Ok(Self::new(stream))
+    }
+}
+
+impl fmt::Debug for WebSocketTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketTransport")
+            .field("pending", &self.pending.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for WebSocketReadHalf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketReadHalf")
+            .field("pending", &self.pending.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for WebSocketWriteHalf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketWriteHalf").finish_non_exhaustive()
+    }
+}
+
+impl SplitStream for WebSocketTransport {
+    type ReadHalf = WebSocketReadHalf;
+    type WriteHalf = WebSocketWriteHalf;
+
+    fn split_owned(self) -> (Self::ReadHalf, Self::WriteHalf) {
+        let (sink, stream) = self.stream.split();
+        (
+            WebSocketReadHalf {
+                stream,
+                pending: self.pending,
+            },
+            WebSocketWriteHalf { sink },
+        )
+    }
+
+    fn reunite_owned(read_half: Self::ReadHalf, write_half: Self::WriteHalf) -> Option<Self> {
+        let stream = read_half.stream.reunite(write_half.sink).ok()?;
+        Some(Self {
+            stream,
+            pending: read_half.pending,
+        })
+    }
+}
+
+impl AsyncRead for WebSocketTransport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        poll_read_frames(&mut this.stream, &mut this.pending, cx, buf)
+    }
+}
+
+impl AsyncWrite for WebSocketTransport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        poll_write_frame(&mut self.get_mut().stream, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream)
+            .poll_flush(cx)
+            .map_err(io::Error::other)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream)
+            .poll_close(cx)
+            .map_err(io::Error::other)
+    }
+}
+
+impl AsyncRead for WebSocketReadHalf {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        poll_read_frames(&mut this.stream, &mut this.pending, cx, buf)
+    }
+}
+
+impl AsyncWrite for WebSocketWriteHalf {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        poll_write_frame(&mut self.get_mut().sink, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().sink)
+            .poll_flush(cx)
+            .map_err(io::Error::other)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().sink)
+            .poll_close(cx)
+            .map_err(io::Error::other)
+    }
+}
+
+/// Serves `buf` from the leftover of the last binary frame, pulling the next one when that
+/// runs dry. A close frame reads as end of stream; pings are answered by tungstenite itself.
+fn poll_read_frames<S>(
+    stream: &mut S,
+    pending: &mut Bytes,
+    cx: &mut Context<'_>,
+    buf: &mut ReadBuf<'_>,
+) -> Poll<io::Result<()>>
+where
+    S: Stream<Item = std::result::Result<Message, WsError>> + Unpin,
+{
+    while pending.is_empty() {
+        match ready!(Pin::new(&mut *stream).poll_next(cx)) {
+            Some(Ok(Message::Binary(data))) => *pending = data,
+            Some(Ok(Message::Close(_))) | None => return Poll::Ready(Ok(())),
+            Some(Ok(Message::Text(_))) => {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "expected a binary websocket frame, got text",
+                )));
+            }
+            Some(Ok(_)) => {}
+            Some(Err(e)) => return Poll::Ready(Err(io::Error::other(e))),
+        }
+    }
+
+    let len = pending.len().min(buf.remaining());
+    buf.put_slice(&pending.split_to(len));
+    Poll::Ready(Ok(()))
+}
+
+fn poll_write_frame<S>(sink: &mut S, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>>
+where
+    S: Sink<Message, Error = WsError> + Unpin,
+{
+    ready!(Pin::new(&mut *sink).poll_ready(cx)).map_err(io::Error::other)?;
+    Pin::new(&mut *sink)
+        .start_send(Message::Binary(Bytes::copy_from_slice(buf)))
+        .map_err(io::Error::other)?;
+    Poll::Ready(Ok(buf.len()))
+}
+
+fn websocket_error(error: WsError) -> FenrisError {
+    FenrisError::NetworkError(io::Error::other(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, SecureChannel};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    async fn connected_pair() -> (WebSocketTransport, WebSocketTransport) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            WebSocketTransport::accept(stream).await.unwrap()
+        });
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let client = WebSocketTransport::connect(stream, &format!("ws://{}/", addr))
+            .await
+            .unwrap();
+
+        (client, server.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_bytes_survive_frame_boundaries() {
+        let (mut client, mut server) = connected_pair().await;
+
+        client.write_all(b"hel").await.unwrap();
+        client.write_all(b"lo").await.unwrap();
+        client.flush().await.unwrap();
+
+        let mut buf = [0u8; 5];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+    }
+
+    #[tokio::test]
+    async fn test_close_reads_as_end_of_stream() {
+        let (mut client, mut server) = connected_pair().await;
+
+        client.shutdown().await.unwrap();
+
+        let mut buf = Vec::new();
+        server.read_to_end(&mut buf).await.unwrap();
+        assert!(buf.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_secure_channel_handshake_over_websocket() {
+        let (client, server) = connected_pair().await;
+
+        let server = tokio::spawn(async move {
+            let mut channel = SecureChannel::<Config, _>::server_handshake(server)
+                .await
+                .unwrap();
+            let request: crate::Request = channel.recv_msg().await.unwrap();
+            request.command
+        });
+        let mut channel = SecureChannel::<Config, _>::client_handshake(client)
+            .await
+            .unwrap();
+        let request = crate::Request {
+            command: crate::RequestType::Ping as i32,
+            ..Default::default()
+        };
+        channel.send_msg(&request).await.unwrap();
+
+        assert_eq!(server.await.unwrap(), crate::RequestType::Ping as i32);
+    }
+
+    #[tokio::test]
+    async fn test_split_halves_reunite() {
+        let (client, _server) = connected_pair().await;
+
+        let (read_half, write_half) = client.split_owned();
+
+        assert!(WebSocketTransport::reunite_owned(read_half, write_half).is_some());
+    }
+}
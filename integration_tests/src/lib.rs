@@ -0,0 +1,107 @@
+//! Fixtures for the end-to-end tests in `tests/`, which run a real [`server::Server`] and talk
+//! to it over TCP.
+
+use common::{
+    Config, FenrisCommand, FenrisMetadata, FenrisOutput, MemoryStorage, Result, SecureChannel,
+    ServerIdentityPublicKey, StorageBackend,
+};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+/// A client speaking the typed protocol directly, the way the server's own tests do.
+pub struct TestClient {
+    channel: SecureChannel<Config, TcpStream>,
+}
+
+impl TestClient {
+    pub async fn connect(addr: SocketAddr, identity: ServerIdentityPublicKey) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        let channel = SecureChannel::client_handshake_authenticated(stream, identity).await?;
+        Ok(Self { channel })
+    }
+
+    pub async fn send(&mut self, command: FenrisCommand) -> Result<FenrisOutput> {
+        self.channel.send_msg(&command).await?;
+        self.channel.recv_msg().await
+    }
+
+    /// Waits for the next message without sending anything, e.g. to see the server hang up.
+    pub async fn recv(&mut self) -> Result<FenrisOutput> {
+        self.channel.recv_msg().await
+    }
+}
+
+/// A [`MemoryStorage`] whose reads take `delay`, for exercising timeouts end to end.
+pub struct SlowStorage {
+    inner: MemoryStorage,
+    delay: Duration,
+}
+
+impl SlowStorage {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            inner: MemoryStorage::new(),
+            delay,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl StorageBackend for SlowStorage {
+    async fn put_object(&self, path: &Path, data: &[u8]) -> Result<()> {
+        self.inner.put_object(path, data).await
+    }
+
+    async fn get_object(&self, path: &Path) -> Result<Vec<u8>> {
+        tokio::time::sleep(self.delay).await;
+        self.inner.get_object(path).await
+    }
+
+    async fn get_object_chunk(
+        &self,
+        path: &Path,
+        offset: u64,
+        max_len: usize,
+    ) -> Result<common::ObjectChunk> {
+        tokio::time::sleep(self.delay).await;
+        self.inner.get_object_chunk(path, offset, max_len).await
+    }
+
+    async fn append_object(&self, path: &Path, data: &[u8]) -> Result<()> {
+        self.inner.append_object(path, data).await
+    }
+
+    async fn delete_object(&self, path: &Path) -> Result<()> {
+        self.inner.delete_object(path).await
+    }
+
+    async fn metadata(&self, path: &Path) -> Result<FenrisMetadata> {
+        self.inner.metadata(path).await
+    }
+
+    async fn create_namespace(&self, path: &Path) -> Result<()> {
+        self.inner.create_namespace(path).await
+    }
+
+    async fn list_namespace(&self, path: &Path) -> Result<Vec<FenrisMetadata>> {
+        self.inner.list_namespace(path).await
+    }
+
+    async fn delete_namespace(&self, path: &Path) -> Result<()> {
+        self.inner.delete_namespace(path).await
+    }
+
+    async fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path).await
+    }
+
+    async fn is_namespace(&self, path: &Path) -> bool {
+        self.inner.is_namespace(path).await
+    }
+
+    async fn is_object(&self, path: &Path) -> bool {
+        self.inner.is_object(path).await
+    }
+}
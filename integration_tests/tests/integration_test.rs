@@ -0,0 +1,330 @@
+use common::{
+    FenrisCommand, FenrisOutput, ListSort, ServerIdentityKey, ServerIdentityPublicKey,
+    StorageBackend, TokioFsStorage,
+};
+use integration_tests::{SlowStorage, TestClient};
+use server::{Server, ServerConfig, ServerHandle};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// A server on a random port over a fresh temporary base directory, plus a connected client.
+/// The directory is removed when the returned `TempDir` drops.
+async fn test_pair() -> (ServerHandle, TestClient, TempDir) {
+    let base_dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(TokioFsStorage::new(base_dir.path().to_path_buf()));
+    let (handle, addr, identity) = start_server(storage, ServerConfig::default()).await;
+    let client = TestClient::connect(addr, identity).await.unwrap();
+    (handle, client, base_dir)
+}
+
+async fn start_server<B: StorageBackend>(
+    storage: Arc<B>,
+    config: ServerConfig,
+) -> (ServerHandle, SocketAddr, ServerIdentityPublicKey) {
+    let identity_key = Arc::new(ServerIdentityKey::generate());
+    let identity = identity_key.public_key();
+    let (server, handle) = Server::bind_authenticated("127.0.0.1:0", storage, identity_key, config)
+        .await
+        .unwrap();
+    let addr = server.local_addr().unwrap();
+    tokio::spawn(server.run());
+
+    (handle, addr, identity)
+}
+
+fn path(name: &str) -> PathBuf {
+    PathBuf::from(name)
+}
+
+fn assert_success(output: FenrisOutput) {
+    assert!(
+        matches!(output, FenrisOutput::Success { .. }),
+        "expected success, got {:?}",
+        output
+    );
+}
+
+fn assert_error(output: FenrisOutput) {
+    assert!(
+        matches!(output, FenrisOutput::Error { .. }),
+        "expected an error, got {:?}",
+        output
+    );
+}
+
+/// Connections stream reads back as chunks, so they are collected up to the last one.
+async fn read(client: &mut TestClient, name: &str) -> Vec<u8> {
+    let mut output = client
+        .send(FenrisCommand::ReadObject { path: path(name) })
+        .await
+        .unwrap();
+    let mut data = Vec::new();
+    loop {
+        match output {
+            FenrisOutput::ObjectContentChunk(chunk) => {
+                data.extend_from_slice(&chunk.data);
+                if chunk.is_last {
+                    return data;
+                }
+            }
+            other => panic!("expected file content, got {:?}", other),
+        }
+        output = client.recv().await.unwrap();
+    }
+}
+
+async fn list(client: &mut TestClient, name: &str) -> Vec<String> {
+    let output = client
+        .send(FenrisCommand::ListNamespace {
+            path: path(name),
+            sort: ListSort::default(),
+        })
+        .await
+        .unwrap();
+    let FenrisOutput::NamespaceListing { entries, .. } = output else {
+        panic!("expected a listing, got {:?}", output);
+    };
+    let mut names: Vec<_> = entries.into_iter().map(|entry| entry.name).collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn ping_gets_pong() {
+    let (handle, mut client, _base_dir) = test_pair().await;
+
+    let output = client.send(FenrisCommand::Ping).await.unwrap();
+
+    assert!(matches!(output, FenrisOutput::Pong));
+    handle.shutdown();
+}
+
+#[tokio::test]
+async fn echo_returns_payload() {
+    let (handle, mut client, _base_dir) = test_pair().await;
+
+    let output = client
+        .send(FenrisCommand::Echo {
+            payload: vec![0, 1, 2, 255],
+        })
+        .await
+        .unwrap();
+
+    assert!(matches!(output, FenrisOutput::Echo { payload } if payload == [0, 1, 2, 255]));
+    handle.shutdown();
+}
+
+#[tokio::test]
+async fn file_lifecycle() {
+    let (handle, mut client, base_dir) = test_pair().await;
+
+    assert_success(
+        client
+            .send(FenrisCommand::CreateObject {
+                path: path("notes.txt"),
+            })
+            .await
+            .unwrap(),
+    );
+    assert_success(
+        client
+            .send(FenrisCommand::WriteObject {
+                path: path("notes.txt"),
+                data: b"hello".to_vec(),
+            })
+            .await
+            .unwrap(),
+    );
+    assert_eq!(read(&mut client, "notes.txt").await, b"hello");
+
+    assert_success(
+        client
+            .send(FenrisCommand::AppendObject {
+                path: path("notes.txt"),
+                data: b" world".to_vec(),
+            })
+            .await
+            .unwrap(),
+    );
+    assert_eq!(read(&mut client, "notes.txt").await, b"hello world");
+    assert_eq!(
+        std::fs::read(base_dir.path().join("notes.txt")).unwrap(),
+        b"hello world"
+    );
+
+    let output = client
+        .send(FenrisCommand::ObjectInfo {
+            path: path("notes.txt"),
+            detect_mime: false,
+        })
+        .await
+        .unwrap();
+    assert!(matches!(
+        output,
+        FenrisOutput::ObjectInfo { metadata, .. } if metadata.size == 11 && !metadata.is_namespace
+    ));
+
+    assert_success(
+        client
+            .send(FenrisCommand::DeleteObject {
+                path: path("notes.txt"),
+            })
+            .await
+            .unwrap(),
+    );
+    assert!(!base_dir.path().join("notes.txt").exists());
+    assert_error(
+        client
+            .send(FenrisCommand::ReadObject {
+                path: path("notes.txt"),
+            })
+            .await
+            .unwrap(),
+    );
+    handle.shutdown();
+}
+
+#[tokio::test]
+async fn directory_lifecycle() {
+    let (handle, mut client, base_dir) = test_pair().await;
+
+    assert_success(
+        client
+            .send(FenrisCommand::CreateNamespace { path: path("docs") })
+            .await
+            .unwrap(),
+    );
+    assert_success(
+        client
+            .send(FenrisCommand::CreateObject {
+                path: path("docs/readme.md"),
+            })
+            .await
+            .unwrap(),
+    );
+    assert_eq!(list(&mut client, ".").await, ["docs"]);
+    assert_eq!(list(&mut client, "docs").await, ["readme.md"]);
+
+    let output = client
+        .send(FenrisCommand::ChangeNamespace { path: path("docs") })
+        .await
+        .unwrap();
+    assert!(matches!(
+        output,
+        FenrisOutput::NamespaceChanged { path } if path == Path::new("/docs")
+    ));
+    assert_eq!(list(&mut client, ".").await, ["readme.md"]);
+
+    client
+        .send(FenrisCommand::ChangeNamespace { path: path("..") })
+        .await
+        .unwrap();
+    assert_error(
+        client
+            .send(FenrisCommand::DeleteNamespace { path: path("docs") })
+            .await
+            .unwrap(),
+    );
+    client
+        .send(FenrisCommand::DeleteObject {
+            path: path("docs/readme.md"),
+        })
+        .await
+        .unwrap();
+    assert_success(
+        client
+            .send(FenrisCommand::DeleteNamespace { path: path("docs") })
+            .await
+            .unwrap(),
+    );
+    assert!(!base_dir.path().join("docs").exists());
+    handle.shutdown();
+}
+
+#[tokio::test]
+async fn rename_moves_file() {
+    let (handle, mut client, base_dir) = test_pair().await;
+
+    client
+        .send(FenrisCommand::WriteObject {
+            path: path("old.txt"),
+            data: b"contents".to_vec(),
+        })
+        .await
+        .unwrap();
+    assert_success(
+        client
+            .send(FenrisCommand::MoveObject {
+                from: path("old.txt"),
+                to: path("new.txt"),
+            })
+            .await
+            .unwrap(),
+    );
+
+    assert!(!base_dir.path().join("old.txt").exists());
+    assert_eq!(read(&mut client, "new.txt").await, b"contents");
+    handle.shutdown();
+}
+
+#[tokio::test]
+async fn concurrent_clients_write_without_cross_contamination() {
+    let base_dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(TokioFsStorage::new(base_dir.path().to_path_buf()));
+    let (handle, addr, identity) = start_server(storage, ServerConfig::default()).await;
+
+    let tasks: Vec<_> = (0..50)
+        .map(|i| {
+            tokio::spawn(async move {
+                let mut client = TestClient::connect(addr, identity).await.unwrap();
+                let name = format!("file-{}.txt", i);
+                let data = format!("payload {}", i).repeat(100).into_bytes();
+                client
+                    .send(FenrisCommand::WriteObject {
+                        path: path(&name),
+                        data: data.clone(),
+                    })
+                    .await
+                    .unwrap();
+                assert_eq!(read(&mut client, &name).await, data);
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    for i in 0..50 {
+        let data = std::fs::read(base_dir.path().join(format!("file-{}.txt", i))).unwrap();
+        assert_eq!(data, format!("payload {}", i).repeat(100).into_bytes());
+    }
+    handle.shutdown();
+}
+
+#[tokio::test]
+async fn slow_request_is_not_cut_off_by_idle_timeout() {
+    let storage = Arc::new(SlowStorage::new(Duration::from_millis(1500)));
+    storage
+        .put_object(Path::new("/slow.txt"), b"eventually")
+        .await
+        .unwrap();
+    let config = ServerConfig::builder()
+        .idle_timeout(Some(Duration::from_secs(1)))
+        .build()
+        .unwrap();
+    let (handle, addr, identity) = start_server(storage, config).await;
+    let mut client = TestClient::connect(addr, identity).await.unwrap();
+
+    // The read outlasts the idle timeout, but a connection waiting on the server is not idle.
+    assert_eq!(read(&mut client, "slow.txt").await, b"eventually");
+
+    // Once the client goes quiet, the server hangs up.
+    let hang_up = tokio::time::timeout(Duration::from_secs(5), client.recv())
+        .await
+        .unwrap();
+    assert!(hang_up.is_err());
+    handle.shutdown();
+}
@@ -0,0 +1,136 @@
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::subscriptions::ClientId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientInfo {
+    pub id: ClientId,
+    pub peer: String,
+    pub connected_at: SystemTime,
+    pub current_dir: PathBuf,
+    /// Set once the client has logged in to a server with a users file.
+    pub username: Option<String>,
+    /// Whether the client answered the server's PSK challenge.
+    pub psk_authenticated: bool,
+}
+
+/// Clients that have completed the handshake and are still connected.
+#[derive(Default)]
+pub struct ClientRegistry {
+    clients: DashMap<ClientId, ClientInfo>,
+    /// Each session's SETENV variables, kept only in memory.
+    env: DashMap<ClientId, HashMap<String, String>>,
+}
+
+impl ClientRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, id: ClientId, peer: String) {
+        self.clients.insert(
+            id,
+            ClientInfo {
+                id,
+                peer,
+                connected_at: SystemTime::now(),
+                current_dir: PathBuf::from("/"),
+                username: None,
+                psk_authenticated: false,
+            },
+        );
+    }
+
+    pub fn unregister(&self, id: ClientId) {
+        self.clients.remove(&id);
+        self.env.remove(&id);
+    }
+
+    pub fn set_env(&self, id: ClientId, name: &str, value: &str) {
+        self.env
+            .entry(id)
+            .or_default()
+            .insert(name.to_string(), value.to_string());
+    }
+
+    pub fn env(&self, id: ClientId, name: &str) -> Option<String> {
+        self.env.get(&id)?.get(name).cloned()
+    }
+
+    pub fn set_current_dir(&self, id: ClientId, current_dir: &Path) {
+        if let Some(mut info) = self.clients.get_mut(&id) {
+            info.current_dir = current_dir.to_path_buf();
+        }
+    }
+
+    pub fn set_username(&self, id: ClientId, username: &str) {
+        if let Some(mut info) = self.clients.get_mut(&id) {
+            info.username = Some(username.to_string());
+        }
+    }
+
+    pub fn set_psk_authenticated(&self, id: ClientId) {
+        if let Some(mut info) = self.clients.get_mut(&id) {
+            info.psk_authenticated = true;
+        }
+    }
+
+    pub fn get(&self, id: ClientId) -> Option<ClientInfo> {
+        self.clients.get(&id).map(|info| info.clone())
+    }
+
+    /// Ordered by client id, which is also connection order.
+    pub fn list(&self) -> Vec<ClientInfo> {
+        let mut clients: Vec<_> = self.clients.iter().map(|entry| entry.clone()).collect();
+        clients.sort_by_key(|info| info.id);
+        clients
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_tracks_current_dir_until_unregistered() {
+        let registry = ClientRegistry::new();
+        registry.register(2, "127.0.0.1:4000".to_string());
+        registry.register(1, "127.0.0.1:3000".to_string());
+        registry.set_current_dir(2, Path::new("/docs"));
+        registry.set_current_dir(9, Path::new("/ignored"));
+        registry.set_username(2, "alice");
+        registry.set_psk_authenticated(1);
+
+        let clients = registry.list();
+        assert_eq!(
+            clients.iter().map(|info| info.id).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(clients[0].current_dir, Path::new("/"));
+        assert_eq!(clients[1].current_dir, Path::new("/docs"));
+        assert_eq!(clients[0].username, None);
+        assert_eq!(clients[1].username.as_deref(), Some("alice"));
+        assert!(clients[0].psk_authenticated);
+        assert!(!registry.get(2).unwrap().psk_authenticated);
+
+        registry.unregister(2);
+        assert_eq!(registry.list().len(), 1);
+    }
+
+    #[test]
+    fn env_is_per_client_and_cleared_on_unregister() {
+        let registry = ClientRegistry::new();
+        registry.register(1, "127.0.0.1:3000".to_string());
+        registry.set_env(1, "BUILD_ID", "r-1041");
+        registry.set_env(1, "BUILD_ID", "r-1042");
+
+        assert_eq!(registry.env(1, "BUILD_ID").as_deref(), Some("r-1042"));
+        assert_eq!(registry.env(2, "BUILD_ID"), None);
+
+        registry.unregister(1);
+        assert_eq!(registry.env(1, "BUILD_ID"), None);
+    }
+}
@@ -1,4 +1,42 @@
+#[cfg(feature = "tls")]
+use common::TlsConfig;
+use common::{DEFAULT_LOCK_TIMEOUT, DEFAULT_MAX_MESSAGE_SIZE, PROTOCOL_VERSION};
+use ipnetwork::IpNetwork;
+use std::net::{IpAddr, SocketAddr};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
 use std::time::Duration;
+use thiserror::Error;
+use tracing::warn;
+
+pub const MIN_HANDSHAKE_TIMEOUT: Duration = Duration::from_millis(100);
+
+pub const MIN_IDLE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Past this many queued connections, waiting instead of rejecting is more likely a typo than a
+// plan.
+const LARGE_QUEUED_CONNECTION_LIMIT: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ConfigError {
+    #[error("max_connections must be at least 1")]
+    ZeroMaxConnections,
+
+    #[error("handshake_timeout must be at least {MIN_HANDSHAKE_TIMEOUT:?}, got {0:?}")]
+    HandshakeTimeoutTooShort(Duration),
+
+    #[error("idle_timeout must be at least {MIN_IDLE_TIMEOUT:?}, got {0:?}")]
+    IdleTimeoutTooShort(Duration),
+
+    #[error("max_file_size must be at least 1 byte")]
+    ZeroMaxFileSize,
+
+    #[error("socket_buffer_size must be at least 1 byte")]
+    ZeroSocketBufferSize,
+
+    #[error("min_protocol_version {min} is above max_protocol_version {max}")]
+    InvalidProtocolVersions { min: u8, max: u8 },
+}
 
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
@@ -11,12 +49,82 @@ pub struct ServerConfig {
     pub reject_when_full: bool,
 
     pub tcp_keepalive: Option<Duration>,
+
+    pub tcp_backlog: u32,
+
+    pub tcp_reuseport: bool,
+
+    /// `SO_SNDBUF` and `SO_RCVBUF` for accepted connections; the OS default when unset.
+    pub socket_buffer_size: Option<usize>,
+
+    pub require_psk: Option<String>,
+
+    pub allow_fetch_url: bool,
+
+    pub max_file_size: Option<u64>,
+
+    pub min_protocol_version: u8,
+
+    pub max_protocol_version: u8,
+
+    pub streaming_threshold: u64,
+
+    pub lock_timeout: Duration,
+
+    pub quota_bytes: Option<u64>,
+
+    pub allow_list: Option<Vec<IpNetwork>>,
+
+    pub deny_list: Option<Vec<IpNetwork>>,
+
+    pub max_request_timeout: Option<Duration>,
+
+    pub metrics_addr: Option<SocketAddr>,
+
+    /// Second port, on the same interface as the main listener, that accepts browser clients
+    /// speaking the usual protocol inside WebSocket binary frames.
+    #[cfg(feature = "websocket")]
+    pub websocket_port: Option<u16>,
+
+    /// Wraps every TCP connection in TLS before the secure channel handshake, for networks
+    /// that only let TLS through.
+    #[cfg(feature = "tls")]
+    pub tls: Option<TlsConfig>,
+
+    pub max_message_size: usize,
+
+    /// When set, every connection must log in as one of these users and is confined to that
+    /// user's base directory.
+    pub users_file: Option<PathBuf>,
+
+    /// Message of the day, sent to every client as a banner once the handshake completes.
+    pub motd: Option<String>,
+
+    /// Record every file modification in `.fenris_audit.log` in the base directory.
+    pub audit_log: bool,
+
+    /// Let clients create symlinks and follow those that stay inside the base directory.
+    pub allow_symlinks: bool,
 }
 
 impl ServerConfig {
     pub fn builder() -> ServerConfigBuilder {
         ServerConfigBuilder::default()
     }
+
+    pub fn protocol_versions(&self) -> RangeInclusive<u8> {
+        self.min_protocol_version..=self.max_protocol_version
+    }
+
+    pub fn is_ip_allowed(&self, ip: IpAddr) -> bool {
+        let listed = |networks: &Option<Vec<IpNetwork>>| {
+            networks
+                .as_ref()
+                .map(|networks| networks.iter().any(|network| network.contains(ip)))
+        };
+
+        listed(&self.allow_list).unwrap_or(true) && !listed(&self.deny_list).unwrap_or(false)
+    }
 }
 
 impl Default for ServerConfig {
@@ -27,6 +135,30 @@ impl Default for ServerConfig {
             idle_timeout: Some(Duration::from_secs(300)),
             reject_when_full: true,
             tcp_keepalive: Some(Duration::from_secs(60)),
+            tcp_backlog: 128,
+            tcp_reuseport: false,
+            socket_buffer_size: None,
+            require_psk: None,
+            allow_fetch_url: false,
+            max_file_size: None,
+            min_protocol_version: PROTOCOL_VERSION,
+            max_protocol_version: PROTOCOL_VERSION,
+            streaming_threshold: 1024 * 1024,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            quota_bytes: None,
+            allow_list: None,
+            deny_list: None,
+            max_request_timeout: None,
+            metrics_addr: None,
+            #[cfg(feature = "websocket")]
+            websocket_port: None,
+            #[cfg(feature = "tls")]
+            tls: None,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            users_file: None,
+            motd: None,
+            audit_log: false,
+            allow_symlinks: false,
         }
     }
 }
@@ -35,9 +167,33 @@ impl Default for ServerConfig {
 pub struct ServerConfigBuilder {
     max_connections: Option<usize>,
     handshake_timeout: Option<Duration>,
-    idle_timeout: Option<Duration>,
+    idle_timeout: Option<Option<Duration>>,
     reject_when_full: Option<bool>,
-    tcp_keepalive: Option<Duration>,
+    tcp_keepalive: Option<Option<Duration>>,
+    tcp_backlog: Option<u32>,
+    tcp_reuseport: Option<bool>,
+    socket_buffer_size: Option<usize>,
+    require_psk: Option<String>,
+    allow_fetch_url: Option<bool>,
+    max_file_size: Option<u64>,
+    min_protocol_version: Option<u8>,
+    max_protocol_version: Option<u8>,
+    streaming_threshold: Option<u64>,
+    lock_timeout: Option<Duration>,
+    quota_bytes: Option<u64>,
+    allow_list: Option<Vec<IpNetwork>>,
+    deny_list: Option<Vec<IpNetwork>>,
+    max_request_timeout: Option<Duration>,
+    metrics_addr: Option<SocketAddr>,
+    #[cfg(feature = "websocket")]
+    websocket_port: Option<u16>,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsConfig>,
+    max_message_size: Option<usize>,
+    users_file: Option<PathBuf>,
+    motd: Option<String>,
+    audit_log: Option<bool>,
+    allow_symlinks: Option<bool>,
 }
 
 impl ServerConfigBuilder {
@@ -51,8 +207,9 @@ impl ServerConfigBuilder {
         self
     }
 
+    /// `None` disables the idle timeout.
     pub fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
-        self.idle_timeout = Some(timeout.unwrap_or(Duration::from_secs(0)));
+        self.idle_timeout = Some(timeout);
         self
     }
 
@@ -62,18 +219,291 @@ impl ServerConfigBuilder {
     }
 
     pub fn tcp_keepalive(mut self, keepalive: Option<Duration>) -> Self {
-        self.tcp_keepalive = Some(keepalive.unwrap_or(Duration::from_secs(0)));
+        self.tcp_keepalive = Some(keepalive);
         self
     }
 
-    pub fn build(self) -> ServerConfig {
+    pub fn tcp_backlog(mut self, backlog: u32) -> Self {
+        self.tcp_backlog = Some(backlog);
+        self
+    }
+
+    pub fn tcp_reuseport(mut self, reuseport: bool) -> Self {
+        self.tcp_reuseport = Some(reuseport);
+        self
+    }
+
+    pub fn socket_buffer_size(mut self, size: usize) -> Self {
+        self.socket_buffer_size = Some(size);
+        self
+    }
+
+    pub fn require_psk(mut self, psk: Option<String>) -> Self {
+        self.require_psk = psk;
+        self
+    }
+
+    pub fn allow_fetch_url(mut self, allow: bool) -> Self {
+        self.allow_fetch_url = Some(allow);
+        self
+    }
+
+    pub fn max_file_size(mut self, max: u64) -> Self {
+        self.max_file_size = Some(max);
+        self
+    }
+
+    pub fn min_protocol_version(mut self, version: u8) -> Self {
+        self.min_protocol_version = Some(version);
+        self
+    }
+
+    pub fn max_protocol_version(mut self, version: u8) -> Self {
+        self.max_protocol_version = Some(version);
+        self
+    }
+
+    pub fn streaming_threshold(mut self, threshold: u64) -> Self {
+        self.streaming_threshold = Some(threshold);
+        self
+    }
+
+    pub fn lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = Some(timeout);
+        self
+    }
+
+    pub fn quota_bytes(mut self, quota: u64) -> Self {
+        self.quota_bytes = Some(quota);
+        self
+    }
+
+    pub fn allow_list(mut self, networks: Vec<IpNetwork>) -> Self {
+        self.allow_list = Some(networks);
+        self
+    }
+
+    pub fn deny_list(mut self, networks: Vec<IpNetwork>) -> Self {
+        self.deny_list = Some(networks);
+        self
+    }
+
+    pub fn max_request_timeout(mut self, timeout: Duration) -> Self {
+        self.max_request_timeout = Some(timeout);
+        self
+    }
+
+    pub fn metrics_addr(mut self, addr: SocketAddr) -> Self {
+        self.metrics_addr = Some(addr);
+        self
+    }
+
+    #[cfg(feature = "websocket")]
+    pub fn websocket_port(mut self, port: u16) -> Self {
+        self.websocket_port = Some(port);
+        self
+    }
+
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    pub fn max_message_size(mut self, max: usize) -> Self {
+        self.max_message_size = Some(max);
+        self
+    }
+
+    pub fn users_file(mut self, path: PathBuf) -> Self {
+        self.users_file = Some(path);
+        self
+    }
+
+    pub fn motd(mut self, message: String) -> Self {
+        self.motd = Some(message);
+        self
+    }
+
+    pub fn audit_log(mut self, audit: bool) -> Self {
+        self.audit_log = Some(audit);
+        self
+    }
+
+    pub fn allow_symlinks(mut self, allow: bool) -> Self {
+        self.allow_symlinks = Some(allow);
+        self
+    }
+
+    pub fn build(self) -> Result<ServerConfig, ConfigError> {
         let defaults = ServerConfig::default();
-        ServerConfig {
+        let config = ServerConfig {
             max_connections: self.max_connections.unwrap_or(defaults.max_connections),
             handshake_timeout: self.handshake_timeout.unwrap_or(defaults.handshake_timeout),
-            idle_timeout: self.idle_timeout.or(defaults.idle_timeout),
+            idle_timeout: self.idle_timeout.unwrap_or(defaults.idle_timeout),
             reject_when_full: self.reject_when_full.unwrap_or(defaults.reject_when_full),
-            tcp_keepalive: self.tcp_keepalive.or(defaults.tcp_keepalive),
+            tcp_keepalive: self.tcp_keepalive.unwrap_or(defaults.tcp_keepalive),
+            tcp_backlog: self.tcp_backlog.unwrap_or(defaults.tcp_backlog),
+            tcp_reuseport: self.tcp_reuseport.unwrap_or(defaults.tcp_reuseport),
+            socket_buffer_size: self.socket_buffer_size.or(defaults.socket_buffer_size),
+            require_psk: self.require_psk.or(defaults.require_psk),
+            allow_fetch_url: self.allow_fetch_url.unwrap_or(defaults.allow_fetch_url),
+            max_file_size: self.max_file_size.or(defaults.max_file_size),
+            min_protocol_version: self
+                .min_protocol_version
+                .unwrap_or(defaults.min_protocol_version),
+            max_protocol_version: self
+                .max_protocol_version
+                .unwrap_or(defaults.max_protocol_version),
+            streaming_threshold: self
+                .streaming_threshold
+                .unwrap_or(defaults.streaming_threshold),
+            lock_timeout: self.lock_timeout.unwrap_or(defaults.lock_timeout),
+            quota_bytes: self.quota_bytes.or(defaults.quota_bytes),
+            allow_list: self.allow_list.or(defaults.allow_list),
+            deny_list: self.deny_list.or(defaults.deny_list),
+            max_request_timeout: self.max_request_timeout.or(defaults.max_request_timeout),
+            metrics_addr: self.metrics_addr.or(defaults.metrics_addr),
+            #[cfg(feature = "websocket")]
+            websocket_port: self.websocket_port.or(defaults.websocket_port),
+            #[cfg(feature = "tls")]
+            tls: self.tls.or(defaults.tls),
+            max_message_size: self.max_message_size.unwrap_or(defaults.max_message_size),
+            users_file: self.users_file.or(defaults.users_file),
+            motd: self.motd.or(defaults.motd),
+            audit_log: self.audit_log.unwrap_or(defaults.audit_log),
+            allow_symlinks: self.allow_symlinks.unwrap_or(defaults.allow_symlinks),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl ServerConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_connections == 0 {
+            return Err(ConfigError::ZeroMaxConnections);
+        }
+        if self.handshake_timeout < MIN_HANDSHAKE_TIMEOUT {
+            return Err(ConfigError::HandshakeTimeoutTooShort(
+                self.handshake_timeout,
+            ));
+        }
+        if let Some(timeout) = self.idle_timeout
+            && timeout < MIN_IDLE_TIMEOUT
+        {
+            return Err(ConfigError::IdleTimeoutTooShort(timeout));
+        }
+        if self.max_file_size == Some(0) {
+            return Err(ConfigError::ZeroMaxFileSize);
+        }
+        if self.socket_buffer_size == Some(0) {
+            return Err(ConfigError::ZeroSocketBufferSize);
+        }
+        if self.min_protocol_version > self.max_protocol_version {
+            return Err(ConfigError::InvalidProtocolVersions {
+                min: self.min_protocol_version,
+                max: self.max_protocol_version,
+            });
+        }
+
+        if !self.reject_when_full && self.max_connections > LARGE_QUEUED_CONNECTION_LIMIT {
+            warn!(
+                max_connections = self.max_connections,
+                "connections beyond the limit will queue rather than be rejected"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn networks(cidrs: &[&str]) -> Vec<IpNetwork> {
+        cidrs.iter().map(|cidr| cidr.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn deny_list_is_checked_after_allow_list() {
+        let config = ServerConfig::builder()
+            .allow_list(networks(&["10.0.0.0/8", "::1/128"]))
+            .deny_list(networks(&["10.1.0.0/16"]))
+            .build()
+            .unwrap();
+
+        assert!(config.is_ip_allowed("10.2.3.4".parse().unwrap()));
+        assert!(config.is_ip_allowed("::1".parse().unwrap()));
+        assert!(!config.is_ip_allowed("10.1.2.3".parse().unwrap()));
+        assert!(!config.is_ip_allowed("192.168.0.1".parse().unwrap()));
+        assert!(ServerConfig::default().is_ip_allowed("192.168.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn build_rejects_nonsensical_limits() {
+        let cases = [
+            (
+                ServerConfig::builder().max_connections(0),
+                ConfigError::ZeroMaxConnections,
+            ),
+            (
+                ServerConfig::builder().handshake_timeout(Duration::from_millis(99)),
+                ConfigError::HandshakeTimeoutTooShort(Duration::from_millis(99)),
+            ),
+            (
+                ServerConfig::builder().idle_timeout(Some(Duration::from_millis(500))),
+                ConfigError::IdleTimeoutTooShort(Duration::from_millis(500)),
+            ),
+            (
+                ServerConfig::builder().max_file_size(0),
+                ConfigError::ZeroMaxFileSize,
+            ),
+            (
+                ServerConfig::builder().socket_buffer_size(0),
+                ConfigError::ZeroSocketBufferSize,
+            ),
+            (
+                ServerConfig::builder()
+                    .min_protocol_version(3)
+                    .max_protocol_version(2),
+                ConfigError::InvalidProtocolVersions { min: 3, max: 2 },
+            ),
+        ];
+
+        for (builder, expected) in cases {
+            assert_eq!(builder.build().unwrap_err(), expected);
         }
     }
+
+    #[test]
+    fn build_accepts_boundary_values_and_disabled_idle_timeout() {
+        let config = ServerConfig::builder()
+            .max_connections(1)
+            .handshake_timeout(MIN_HANDSHAKE_TIMEOUT)
+            .idle_timeout(None)
+            .max_file_size(1)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.idle_timeout, None);
+        assert!(
+            ServerConfig::builder()
+                .idle_timeout(Some(MIN_IDLE_TIMEOUT))
+                .build()
+                .is_ok()
+        );
+    }
+
+    #[tracing_test::traced_test]
+    #[test]
+    fn build_warns_about_huge_queued_connection_limits() {
+        let config = ServerConfig::builder()
+            .reject_when_full(false)
+            .max_connections(20_000)
+            .build();
+
+        assert!(config.is_ok());
+        assert!(logs_contain("will queue rather than be rejected"));
+    }
 }
@@ -1,87 +1,166 @@
 use common::{
-    DEFAULT_TRANSFER_CHUNK_SIZE, DefaultSecureChannel, FenrisCommand, FenrisError, FenrisOutput,
-    Result, ServerIdentityKey, StorageBackend,
+    Config, DEFAULT_KDF_CONTEXT, DEFAULT_TRANSFER_CHUNK_SIZE, FenrisCommand, FenrisError,
+    FenrisOutput, Result, SecureChannel, SecureChannelReader, SecureChannelWriter,
+    ServerIdentityKey, StorageBackend, Transport, TransportReadHalf, TransportWriteHalf,
 };
 use std::io;
-use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::net::TcpStream;
+use tokio::io::BufWriter;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use crate::config::ServerConfig;
+use crate::metrics::ServerMetrics;
 use crate::request_handler::{ActiveWriteTransfer, RequestHandler};
+use crate::users::UserDatabase;
 
 pub struct Connection<B: StorageBackend> {
     id: u64,
-    channel: DefaultSecureChannel,
+    channel: SecureChannelWriter<Config, BufWriter<TransportWriteHalf>>,
+    commands: mpsc::Receiver<Result<FenrisCommand>>,
+    events: mpsc::Receiver<FenrisOutput>,
+    reader_task: JoinHandle<()>,
     current_dir: PathBuf,
     handler: Arc<RequestHandler<B>>,
     config: Arc<ServerConfig>,
     active_write: Option<ActiveWriteTransfer>,
+    command_count: u64,
+    bytes_sent: u64,
 }
 
 impl<B: StorageBackend> Connection<B> {
     pub async fn accept(
         id: u64,
-        stream: TcpStream,
-        addr: SocketAddr,
+        stream: Transport,
+        peer: String,
         handler: Arc<RequestHandler<B>>,
         config: Arc<ServerConfig>,
     ) -> Result<Self> {
-        Self::accept_with_identity(id, stream, addr, handler, config, None).await
+        Self::accept_with_identity(id, stream, peer, handler, config, None).await
     }
 
     pub async fn accept_authenticated(
         id: u64,
-        stream: TcpStream,
-        addr: SocketAddr,
+        stream: Transport,
+        peer: String,
         handler: Arc<RequestHandler<B>>,
         config: Arc<ServerConfig>,
         identity_key: Arc<ServerIdentityKey>,
     ) -> Result<Self> {
-        Self::accept_with_identity(id, stream, addr, handler, config, Some(identity_key)).await
+        Self::accept_with_identity(id, stream, peer, handler, config, Some(identity_key)).await
     }
 
     async fn accept_with_identity(
         id: u64,
-        stream: TcpStream,
-        addr: SocketAddr,
+        stream: Transport,
+        peer: String,
         handler: Arc<RequestHandler<B>>,
         config: Arc<ServerConfig>,
         identity_key: Option<Arc<ServerIdentityKey>>,
     ) -> Result<Self> {
         let handshake = async {
-            if let Some(identity_key) = identity_key.as_deref() {
-                DefaultSecureChannel::server_handshake_authenticated(stream, identity_key).await
+            let versions = config.protocol_versions();
+            let mut channel = if let Some(identity_key) = identity_key.as_deref() {
+                SecureChannel::<Config, _>::server_handshake_authenticated_with_versions(
+                    stream,
+                    identity_key,
+                    DEFAULT_KDF_CONTEXT,
+                    versions,
+                )
+                .await?
             } else {
-                DefaultSecureChannel::server_handshake(stream).await
+                SecureChannel::<Config, _>::server_handshake_with_versions(
+                    stream,
+                    DEFAULT_KDF_CONTEXT,
+                    versions,
+                )
+                .await?
+            };
+            channel.set_max_message_size(config.max_message_size);
+
+            if let Some(psk) = config.require_psk.as_deref() {
+                channel.server_psk_challenge(psk).await.inspect_err(|_| {
+                    warn!("Client {} from {} failed PSK authentication", id, peer);
+                })?;
             }
+
+            let login = match handler.users() {
+                Some(users) => Some(
+                    server_login(&mut channel, &handler, users)
+                        .await
+                        .inspect_err(|_| {
+                            warn!("Client {} from {} failed to log in", id, peer);
+                        })?,
+                ),
+                None => None,
+            };
+
+            Ok::<_, FenrisError>((channel, login))
         };
 
-        let channel = tokio::time::timeout(config.handshake_timeout, handshake)
+        let (mut channel, login) = tokio::time::timeout(config.handshake_timeout, handshake)
             .await
             .map_err(|_| {
                 FenrisError::NetworkError(io::Error::new(
                     io::ErrorKind::TimedOut,
                     "Handshake timeout",
                 ))
-            })??;
+            })
+            .flatten()
+            .inspect_err(|_| handler.metrics().handshake_failed())?;
 
-        info!("Client {} connected from {}", id, addr);
+        let handler = match login {
+            Some((username, user_handler)) => {
+                info!("Client {} connected from {} as {}", id, peer, username);
+                handler.clients().register(id, peer);
+                handler.clients().set_username(id, &username);
+                Arc::new(user_handler)
+            }
+            None => {
+                info!("Client {} connected from {}", id, peer);
+                handler.clients().register(id, peer);
+                handler
+            }
+        };
+        if config.require_psk.is_some() {
+            handler.clients().set_psk_authenticated(id);
+        }
+
+        if let Some(motd) = &config.motd {
+            channel
+                .send_msg(&FenrisOutput::Banner {
+                    message: motd.clone(),
+                })
+                .await?;
+        }
+
+        let (reader, channel) = channel.into_split();
+        let (commands, reader_task) = spawn_command_reader(reader, Arc::clone(handler.metrics()));
+        let events = handler.subscriptions().register_client(id);
+        handler.metrics().connection_opened();
 
         Ok(Self {
             id,
             channel,
+            commands,
+            events,
+            reader_task,
             current_dir: PathBuf::from("/"),
             handler,
             config,
             active_write: None,
+            command_count: 0,
+            bytes_sent: 0,
         })
     }
 
     pub async fn run(mut self, shutdown: CancellationToken) -> Result<()> {
+        let mut idle_deadline = self.next_idle_deadline();
+
         loop {
             tokio::select! {
                 _ = shutdown.cancelled() => {
@@ -89,7 +168,16 @@ impl<B: StorageBackend> Connection<B> {
                     break;
                 }
 
-                result = self.receive_command() => {
+                Some(event) = self.events.recv() => {
+                    let result = self.channel.send_msg(&event).await;
+                    self.record_bytes_sent();
+                    if let Err(e) = result {
+                        debug!("Client {} send error: {}", self.id, e);
+                        break;
+                    }
+                }
+
+                result = receive_command(&mut self.commands, idle_deadline) => {
                     match result {
                         Ok(command) => {
                             if Self::is_terminate(&command) {
@@ -97,12 +185,19 @@ impl<B: StorageBackend> Connection<B> {
                                 break;
                             }
 
-                            if let Err(e) = self.handle_command(command).await {
+                            let result = self.handle_command(command).await;
+                            self.record_bytes_sent();
+                            if let Err(e) = result {
                                 debug!("Client {} send error: {}", self.id, e);
                                 break;
                             }
+
+                            idle_deadline = self.next_idle_deadline();
                         }
                         Err(e) => {
+                            if is_idle_timeout(&e) {
+                                self.handler.metrics().idle_timed_out();
+                            }
                             debug!("Client {} recv error: {}", self.id, e);
                             break;
                         }
@@ -111,30 +206,42 @@ impl<B: StorageBackend> Connection<B> {
             }
         }
 
-        info!("Client {} disconnected", self.id);
+        info!(
+            "Client {} disconnected after {} commands",
+            self.id, self.command_count
+        );
         Ok(())
     }
 
-    async fn receive_command(&mut self) -> Result<FenrisCommand> {
-        if let Some(timeout) = self.config.idle_timeout {
-            tokio::time::timeout(timeout, self.channel.recv_msg())
-                .await
-                .map_err(|_| {
-                    FenrisError::NetworkError(io::Error::new(
-                        io::ErrorKind::TimedOut,
-                        "Idle timeout",
-                    ))
-                })?
-        } else {
-            self.channel.recv_msg().await
-        }
+    fn record_bytes_sent(&mut self) {
+        let total = self.channel.bytes_sent();
+        self.handler
+            .metrics()
+            .add_bytes_sent(total - self.bytes_sent);
+        self.bytes_sent = total;
+    }
+
+    fn next_idle_deadline(&self) -> Option<Instant> {
+        self.config
+            .idle_timeout
+            .map(|timeout| Instant::now() + timeout)
     }
 
     fn is_terminate(command: &FenrisCommand) -> bool {
-        matches!(command, FenrisCommand::Terminate)
+        match command {
+            FenrisCommand::Terminate => true,
+            FenrisCommand::Correlated { command, .. } => Self::is_terminate(command),
+            _ => false,
+        }
     }
 
     async fn handle_command(&mut self, command: FenrisCommand) -> Result<()> {
+        self.command_count += 1;
+        self.handler.stats().record(command.request_type());
+        self.handler
+            .metrics()
+            .record_request(command.request_type());
+
         match command {
             FenrisCommand::ReadObject { path } => self.send_object_content_chunks(path).await,
             FenrisCommand::BeginObjectWrite {
@@ -144,10 +251,16 @@ impl<B: StorageBackend> Connection<B> {
             } => self.begin_object_write(path, mode, total_size).await,
             FenrisCommand::WriteObjectChunk(chunk) => self.write_object_chunk(chunk).await,
             command => {
+                let previous_dir = self.current_dir.clone();
                 let response = self
                     .handler
                     .process_command(self.id, &command, &mut self.current_dir)
                     .await;
+                if self.current_dir != previous_dir {
+                    self.handler
+                        .clients()
+                        .set_current_dir(self.id, &self.current_dir);
+                }
                 self.channel.send_msg(&response).await
             }
         }
@@ -258,3 +371,106 @@ impl<B: StorageBackend> Connection<B> {
         self.channel.send_msg(&FenrisOutput::Terminated).await
     }
 }
+
+impl<B: StorageBackend> Drop for Connection<B> {
+    fn drop(&mut self) {
+        self.reader_task.abort();
+        self.handler.subscriptions().unregister_client(self.id);
+        self.handler.clients().unregister(self.id);
+        self.handler.metrics().connection_closed();
+    }
+}
+
+/// The client's first message must log in as one of `users`; anything else fails
+/// authentication. On success, returns a handler rooted at the user's base directory.
+async fn server_login<B: StorageBackend>(
+    channel: &mut SecureChannel<Config, Transport>,
+    handler: &RequestHandler<B>,
+    users: &Arc<UserDatabase>,
+) -> Result<(String, RequestHandler<B>)> {
+    let reject = |message: String| FenrisOutput::Error { message };
+
+    let FenrisCommand::Login { username, password } = channel.recv_msg().await? else {
+        channel
+            .send_msg(&reject("login required".to_string()))
+            .await?;
+        return Err(FenrisError::AuthenticationFailed);
+    };
+
+    let users = Arc::clone(users);
+    let lookup = username.clone();
+    let entry = tokio::task::spawn_blocking(move || users.verify(&lookup, &password).cloned())
+        .await
+        .map_err(|e| FenrisError::AuthenticationError(format!("login check failed: {}", e)))?;
+    let Some(entry) = entry else {
+        channel
+            .send_msg(&reject(FenrisError::AuthenticationFailed.to_string()))
+            .await?;
+        return Err(FenrisError::AuthenticationFailed);
+    };
+
+    let user_handler = match handler.rebased(entry.base_dir, &username).await {
+        Ok(user_handler) => user_handler,
+        Err(e) => {
+            channel.send_msg(&reject(e.to_string())).await?;
+            return Err(e);
+        }
+    };
+    channel
+        .send_msg(&FenrisOutput::Success {
+            message: format!("Logged in as {}", username),
+        })
+        .await?;
+
+    Ok((username, user_handler))
+}
+
+fn spawn_command_reader(
+    mut reader: SecureChannelReader<Config, TransportReadHalf>,
+    metrics: Arc<ServerMetrics>,
+) -> (mpsc::Receiver<Result<FenrisCommand>>, JoinHandle<()>) {
+    let (tx, rx) = mpsc::channel(1);
+
+    let task = tokio::spawn(async move {
+        let mut bytes_received = 0;
+        loop {
+            let result = reader.recv_msg::<FenrisCommand>().await;
+            let failed = result.is_err();
+            metrics.add_bytes_received(reader.bytes_received() - bytes_received);
+            bytes_received = reader.bytes_received();
+
+            if tx.send(result).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    (rx, task)
+}
+
+fn is_idle_timeout(error: &FenrisError) -> bool {
+    matches!(error, FenrisError::NetworkError(e) if e.kind() == io::ErrorKind::TimedOut)
+}
+
+async fn receive_command(
+    commands: &mut mpsc::Receiver<Result<FenrisCommand>>,
+    idle_deadline: Option<Instant>,
+) -> Result<FenrisCommand> {
+    let closed = || {
+        FenrisError::NetworkError(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Connection closed",
+        ))
+    };
+
+    if let Some(deadline) = idle_deadline {
+        tokio::time::timeout_at(deadline, commands.recv())
+            .await
+            .map_err(|_| {
+                FenrisError::NetworkError(io::Error::new(io::ErrorKind::TimedOut, "Idle timeout"))
+            })?
+            .unwrap_or_else(|| Err(closed()))
+    } else {
+        commands.recv().await.unwrap_or_else(|| Err(closed()))
+    }
+}
@@ -1,8 +1,20 @@
+mod clients;
 mod config;
 mod connection;
+mod metrics;
 pub mod request_handler;
 mod server;
+mod stats;
+mod subscriptions;
+mod users;
+#[cfg(feature = "websocket")]
+mod websocket;
 
-pub use config::{ServerConfig, ServerConfigBuilder};
+pub use clients::{ClientInfo, ClientRegistry};
+pub use config::{ConfigError, ServerConfig, ServerConfigBuilder};
+pub use metrics::ServerMetrics;
 pub use request_handler::RequestHandler;
 pub use server::{Server, ServerHandle};
+pub use stats::CommandStats;
+pub use subscriptions::{SubscriptionManager, SubscriptionScope};
+pub use users::{UserDatabase, UserEntry};
@@ -1,7 +1,14 @@
 use anyhow::Result;
 use clap::Parser;
-use common::{ServerIdentityKey, TokioFsStorage};
+#[cfg(feature = "json-logs")]
+use clap::ValueEnum;
+use common::{
+    DEFAULT_MAX_MESSAGE_SIZE, DefaultFileOperations, QUOTAS_FILE, QuotaManager, ServerIdentityKey,
+    TokioFsStorage,
+};
+use ipnetwork::IpNetwork;
 use server::{Server, ServerConfig};
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
@@ -30,17 +37,143 @@ struct Args {
 
     #[arg(long, default_value = "info")]
     log_level: String,
+
+    #[cfg(feature = "json-logs")]
+    #[arg(long, value_enum, default_value = "text")]
+    log_format: LogFormat,
+
+    /// Also write JSON logs to this file, rotated daily with a date suffix.
+    #[cfg(feature = "json-logs")]
+    #[arg(long)]
+    log_file: Option<PathBuf>,
+
+    #[arg(long)]
+    psk: Option<String>,
+
+    #[arg(long)]
+    allow_fetch_url: bool,
+
+    /// Record every file modification in .fenris_audit.log in the base directory.
+    #[arg(long)]
+    audit_log: bool,
+
+    /// Let clients create symlinks and follow those that stay inside the base directory.
+    #[arg(long)]
+    allow_symlinks: bool,
+
+    #[arg(long)]
+    max_file_size: Option<u64>,
+
+    #[arg(long, default_value_t = DEFAULT_MAX_MESSAGE_SIZE)]
+    max_message_size: usize,
+
+    #[arg(long, default_value = "10")]
+    lock_timeout: u64,
+
+    /// Default storage limit for accounts without their own entry in the quotas file.
+    #[arg(long)]
+    quota_bytes: Option<u64>,
+
+    /// Per-account limits set with `quota set`. Defaults to quotas.json in the base directory.
+    #[arg(long)]
+    quotas_file: Option<PathBuf>,
+
+    #[arg(long = "allow-cidr")]
+    allow_cidrs: Vec<IpNetwork>,
+
+    #[arg(long = "deny-cidr")]
+    deny_cidrs: Vec<IpNetwork>,
+
+    #[arg(long, default_value = "128")]
+    tcp_backlog: u32,
+
+    #[arg(long)]
+    reuseport: bool,
+
+    #[arg(long)]
+    max_request_timeout_ms: Option<u64>,
+
+    #[arg(long)]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Also accept browser clients over WebSocket on this port.
+    #[cfg(feature = "websocket")]
+    #[arg(long)]
+    websocket_port: Option<u16>,
+
+    /// Serve TCP clients over TLS with this PEM certificate chain; needs --tls-key.
+    #[cfg(feature = "tls")]
+    #[arg(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for --tls-cert.
+    #[cfg(feature = "tls")]
+    #[arg(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Require clients to log in as a user from this TOML file, each confined to its base_dir.
+    #[arg(long)]
+    users_file: Option<PathBuf>,
+
+    /// Message of the day shown to clients when they connect.
+    #[arg(long)]
+    motd: Option<String>,
+
+    /// Export tracing spans to this OTLP/gRPC collector, e.g. http://localhost:4317.
+    #[cfg(feature = "tracing")]
+    #[arg(long)]
+    tracing_endpoint: Option<String>,
+
+    /// Listen on a Unix domain socket at this path instead of the TCP port.
+    #[cfg(unix)]
+    #[arg(long)]
+    unix_socket: Option<PathBuf>,
+
+    /// On this signal, move storage to the directory named in --reload-file.
+    #[cfg(unix)]
+    #[arg(long, value_enum)]
+    reload_signal: Option<ReloadSignal>,
+
+    #[cfg(unix)]
+    #[arg(long, default_value = "/var/run/fenris.newdir")]
+    reload_file: PathBuf,
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[value(rename_all = "UPPER")]
+enum ReloadSignal {
+    Sighup,
+    Sigusr1,
+    Sigusr2,
+}
+
+#[cfg(unix)]
+impl ReloadSignal {
+    fn kind(self) -> tokio::signal::unix::SignalKind {
+        use tokio::signal::unix::SignalKind;
+
+        match self {
+            Self::Sighup => SignalKind::hangup(),
+            Self::Sigusr1 => SignalKind::user_defined1(),
+            Self::Sigusr2 => SignalKind::user_defined2(),
+        }
+    }
+}
+
+#[cfg(feature = "json-logs")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    Text,
+    Json,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
 
-    tracing_subscriber::fmt()
-        .with_env_filter(args.log_level.clone())
-        .init();
+    let _tracing_guard = init_tracing(&args)?;
 
-    let storage = Arc::new(TokioFsStorage::new(args.base_dir.clone()));
     let identity_key = Arc::new(load_or_create_server_identity(&args.identity_key)?);
 
     let config = ServerConfig::builder()
@@ -51,19 +184,110 @@ async fn main() -> Result<()> {
         } else {
             None
         })
-        .build();
+        .require_psk(args.psk.clone())
+        .allow_fetch_url(args.allow_fetch_url)
+        .audit_log(args.audit_log)
+        .allow_symlinks(args.allow_symlinks)
+        .lock_timeout(Duration::from_secs(args.lock_timeout))
+        .max_message_size(args.max_message_size)
+        .tcp_backlog(args.tcp_backlog)
+        .tcp_reuseport(args.reuseport);
+    let config = match args.max_request_timeout_ms {
+        Some(ms) => config.max_request_timeout(Duration::from_millis(ms)),
+        None => config,
+    };
+    let config = match args.metrics_addr {
+        Some(addr) => config.metrics_addr(addr),
+        None => config,
+    };
+    #[cfg(feature = "websocket")]
+    let config = match args.websocket_port {
+        Some(port) => config.websocket_port(port),
+        None => config,
+    };
+    #[cfg(feature = "tls")]
+    let config = match (args.tls_cert.clone(), args.tls_key.clone()) {
+        (Some(cert), Some(key)) => config.tls(common::TlsConfig::new(cert, key)),
+        _ => config,
+    };
+    let config = match args.users_file.clone() {
+        Some(path) => config.users_file(path),
+        None => config,
+    };
+    let config = match args.motd.clone() {
+        Some(message) => config.motd(message),
+        None => config,
+    };
+    let config = match args.max_file_size {
+        Some(max) => config.max_file_size(max),
+        None => config,
+    };
+    let config = match args.quota_bytes {
+        Some(quota) => config.quota_bytes(quota),
+        None => config,
+    };
+    let config = if args.allow_cidrs.is_empty() {
+        config
+    } else {
+        config.allow_list(args.allow_cidrs.clone())
+    };
+    let config = if args.deny_cidrs.is_empty() {
+        config
+    } else {
+        config.deny_list(args.deny_cidrs.clone())
+    }
+    .build()?;
+
+    let quotas_file = args
+        .quotas_file
+        .clone()
+        .unwrap_or_else(|| args.base_dir.join(QUOTAS_FILE));
+    let quotas = QuotaManager::load(quotas_file, config.quota_bytes).await?;
+    let file_ops = DefaultFileOperations::new_with_quota(args.base_dir.clone(), Arc::new(quotas))
+        .await?
+        .with_lock_timeout(config.lock_timeout)
+        .with_audit_log(config.audit_log)
+        .with_symlinks_allowed(config.allow_symlinks);
+    let base_dir = file_ops.base_dir().to_path_buf();
+    let storage = Arc::new(TokioFsStorage::with_file_ops(file_ops));
 
     let bind_addr = format!("{}:{}", "localhost", args.port);
-    let (server, handle) =
-        Server::bind_authenticated(&bind_addr, storage, identity_key.clone(), config).await?;
+    #[cfg(unix)]
+    let bound = match &args.unix_socket {
+        Some(path) => {
+            Server::bind_unix_authenticated(path, storage, identity_key.clone(), config).await
+        }
+        None => Server::bind_authenticated(&bind_addr, storage, identity_key.clone(), config).await,
+    };
+    #[cfg(not(unix))]
+    let bound = Server::bind_authenticated(&bind_addr, storage, identity_key.clone(), config).await;
+    let (server, handle) = bound?;
 
     println!("Fenris Server v{}", env!("CARGO_PKG_VERSION"));
-    println!("Listening on {}", server.local_addr()?);
-    println!("Base directory: {:?}", args.base_dir.canonicalize()?);
+    println!("Listening on {}", server.listening_on());
+    if let Some(addr) = server.metrics_addr() {
+        println!("Metrics on http://{}/metrics", addr);
+    }
+    #[cfg(feature = "websocket")]
+    if let Some(addr) = server.websocket_addr() {
+        println!("WebSocket clients on ws://{}/", addr);
+    }
+    println!("Base directory: {:?}", base_dir);
     println!("Server identity: {}", identity_key.public_key().to_hex());
     println!("Max connections: {}", args.max_connections);
     println!("Press Ctrl+C to stop");
 
+    #[cfg(unix)]
+    if let Some(signal) = args.reload_signal {
+        spawn_reload_listener(signal, args.reload_file.clone(), handle.clone())?;
+        let name = clap::ValueEnum::to_possible_value(&signal).expect("no skipped variants");
+        println!(
+            "Send {} to reload the base directory from {}",
+            name.get_name(),
+            args.reload_file.display()
+        );
+    }
+
     let shutdown_handle = handle.clone();
     tokio::spawn(async move {
         let _ = tokio::signal::ctrl_c().await;
@@ -75,6 +299,172 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Flushes buffered file logs when dropped, so it must live until `main` returns.
+#[cfg(feature = "json-logs")]
+type LogGuard = tracing_appender::non_blocking::WorkerGuard;
+#[cfg(not(feature = "json-logs"))]
+type LogGuard = std::convert::Infallible;
+
+/// Keeps log and span exporters alive; dropping it flushes whatever they still buffer.
+struct TracingGuard {
+    _log: Option<LogGuard>,
+    #[cfg(feature = "tracing")]
+    tracer_provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for TracingGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.tracer_provider.take()
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("Failed to flush tracing spans: {}", e);
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+type OtelLayer = tracing_opentelemetry::OpenTelemetryLayer<
+    tracing_subscriber::Registry,
+    opentelemetry_sdk::trace::Tracer,
+>;
+
+#[cfg(feature = "tracing")]
+fn otel_layer(
+    args: &Args,
+) -> Result<(
+    Option<OtelLayer>,
+    Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+)> {
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_otlp::WithExportConfig;
+
+    let Some(endpoint) = &args.tracing_endpoint else {
+        return Ok((None, None));
+    };
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()?;
+    let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            opentelemetry_sdk::Resource::builder()
+                .with_service_name("fenris-server")
+                .build(),
+        )
+        .build();
+    let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("fenris-server"));
+
+    Ok((Some(layer), Some(provider)))
+}
+
+#[cfg(not(feature = "tracing"))]
+fn otel_layer(
+    _args: &Args,
+) -> Result<(
+    Option<tracing_subscriber::layer::Identity>,
+    Option<std::convert::Infallible>,
+)> {
+    Ok((None, None))
+}
+
+#[cfg(feature = "json-logs")]
+fn init_tracing(args: &Args) -> Result<TracingGuard> {
+    use tracing_subscriber::{
+        EnvFilter, Layer, fmt, layer::SubscriberExt, util::SubscriberInitExt,
+    };
+
+    let console = match args.log_format {
+        LogFormat::Text => fmt::layer().boxed(),
+        LogFormat::Json => fmt::layer().json().boxed(),
+    };
+
+    let (file, guard) = match &args.log_file {
+        Some(path) => {
+            let file_name = path
+                .file_name()
+                .ok_or_else(|| anyhow::anyhow!("--log-file must name a file"))?;
+            let directory = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+            let (writer, guard) = tracing_appender::non_blocking(tracing_appender::rolling::daily(
+                directory, file_name,
+            ));
+            let layer = fmt::layer().json().with_ansi(false).with_writer(writer);
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+    let (otel, _tracer_provider) = otel_layer(args)?;
+
+    tracing_subscriber::registry()
+        .with(otel)
+        .with(EnvFilter::new(&args.log_level))
+        .with(console)
+        .with(file)
+        .init();
+
+    Ok(TracingGuard {
+        _log: guard,
+        #[cfg(feature = "tracing")]
+        tracer_provider: _tracer_provider,
+    })
+}
+
+#[cfg(not(feature = "json-logs"))]
+fn init_tracing(args: &Args) -> Result<TracingGuard> {
+    use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+
+    let (otel, _tracer_provider) = otel_layer(args)?;
+
+    tracing_subscriber::registry()
+        .with(otel)
+        .with(EnvFilter::new(&args.log_level))
+        .with(fmt::layer())
+        .init();
+
+    Ok(TracingGuard {
+        _log: None,
+        #[cfg(feature = "tracing")]
+        tracer_provider: _tracer_provider,
+    })
+}
+
+/// Each signal re-reads `reload_file`, whose trimmed contents name the new base directory.
+#[cfg(unix)]
+fn spawn_reload_listener(
+    signal: ReloadSignal,
+    reload_file: PathBuf,
+    handle: server::ServerHandle,
+) -> Result<()> {
+    let mut signals = tokio::signal::unix::signal(signal.kind())?;
+    tokio::spawn(async move {
+        while signals.recv().await.is_some() {
+            let reloaded = match tokio::fs::read_to_string(&reload_file).await {
+                Ok(contents) if contents.trim().is_empty() => {
+                    Err(anyhow::anyhow!("{} is empty", reload_file.display()))
+                }
+                Ok(contents) => handle
+                    .reload_file_ops(PathBuf::from(contents.trim()))
+                    .await
+                    .map_err(Into::into),
+                Err(e) => Err(anyhow::anyhow!(
+                    "failed to read {}: {}",
+                    reload_file.display(),
+                    e
+                )),
+            };
+            if let Err(e) = reloaded {
+                tracing::warn!("Base directory reload failed: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
 fn load_or_create_server_identity(path: &Path) -> Result<ServerIdentityKey> {
     ServerIdentityKey::load_or_generate(path).map_err(Into::into)
 }
@@ -0,0 +1,215 @@
+use bytes::Bytes;
+use common::RequestType;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode, header};
+use hyper_util::rt::TokioIo;
+use prometheus::{Encoder, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+pub struct ServerMetrics {
+    registry: Registry,
+    active_connections: IntGauge,
+    connections_accepted: IntCounter,
+    requests: IntCounterVec,
+    bytes_sent: IntCounter,
+    bytes_received: IntCounter,
+    handshake_errors: IntCounter,
+    idle_timeouts: IntCounter,
+}
+
+impl ServerMetrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        let metrics = Self {
+            active_connections: IntGauge::new(
+                "fenris_active_connections",
+                "Connections currently open",
+            )
+            .expect("valid metric"),
+            connections_accepted: IntCounter::new(
+                "fenris_total_connections_accepted",
+                "Connections accepted since startup",
+            )
+            .expect("valid metric"),
+            requests: IntCounterVec::new(
+                Opts::new("fenris_requests_total", "Requests handled by command"),
+                &["command"],
+            )
+            .expect("valid metric"),
+            bytes_sent: IntCounter::new(
+                "fenris_bytes_sent_total",
+                "Encrypted bytes sent to clients",
+            )
+            .expect("valid metric"),
+            bytes_received: IntCounter::new(
+                "fenris_bytes_received_total",
+                "Encrypted bytes received from clients",
+            )
+            .expect("valid metric"),
+            handshake_errors: IntCounter::new(
+                "fenris_handshake_errors",
+                "Connections that failed the handshake",
+            )
+            .expect("valid metric"),
+            idle_timeouts: IntCounter::new(
+                "fenris_idle_timeouts",
+                "Connections closed for being idle",
+            )
+            .expect("valid metric"),
+            registry,
+        };
+
+        let collectors: [Box<dyn prometheus::core::Collector>; 7] = [
+            Box::new(metrics.active_connections.clone()),
+            Box::new(metrics.connections_accepted.clone()),
+            Box::new(metrics.requests.clone()),
+            Box::new(metrics.bytes_sent.clone()),
+            Box::new(metrics.bytes_received.clone()),
+            Box::new(metrics.handshake_errors.clone()),
+            Box::new(metrics.idle_timeouts.clone()),
+        ];
+        for collector in collectors {
+            metrics
+                .registry
+                .register(collector)
+                .expect("metric names are unique");
+        }
+
+        metrics
+    }
+
+    pub fn connection_accepted(&self) {
+        self.connections_accepted.inc();
+    }
+
+    pub fn connection_opened(&self) {
+        self.active_connections.inc();
+    }
+
+    pub fn connection_closed(&self) {
+        self.active_connections.dec();
+    }
+
+    pub fn record_request(&self, request_type: RequestType) {
+        self.requests
+            .with_label_values(&[request_type.as_str_name()])
+            .inc();
+    }
+
+    pub fn add_bytes_sent(&self, bytes: u64) {
+        self.bytes_sent.inc_by(bytes);
+    }
+
+    pub fn add_bytes_received(&self, bytes: u64) {
+        self.bytes_received.inc_by(bytes);
+    }
+
+    pub fn handshake_failed(&self) {
+        self.handshake_errors.inc();
+    }
+
+    pub fn idle_timed_out(&self) {
+        self.idle_timeouts.inc();
+    }
+
+    /// Renders every registered metric in the Prometheus text exposition format.
+    pub fn encode(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("text encoding cannot fail for gathered metrics");
+        String::from_utf8(buffer).expect("text encoder writes UTF-8")
+    }
+}
+
+impl Default for ServerMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) async fn serve_metrics(
+    listener: TcpListener,
+    metrics: Arc<ServerMetrics>,
+    shutdown: CancellationToken,
+) {
+    loop {
+        let stream = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            accept_result = listener.accept() => match accept_result {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("Metrics accept error: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        let metrics = Arc::clone(&metrics);
+        let service = service_fn(move |request| {
+            let metrics = Arc::clone(&metrics);
+            async move { Ok::<_, Infallible>(metrics_response(&request, &metrics)) }
+        });
+
+        tokio::spawn(async move {
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!("Metrics connection error: {}", e);
+            }
+        });
+    }
+
+    info!("Metrics endpoint stopped");
+}
+
+fn metrics_response(request: &Request<Incoming>, metrics: &ServerMetrics) -> Response<Full<Bytes>> {
+    let (status, content_type, body) = if request.uri().path() != "/metrics" {
+        (StatusCode::NOT_FOUND, "text/plain", "not found".to_string())
+    } else if request.method() != Method::GET {
+        (
+            StatusCode::METHOD_NOT_ALLOWED,
+            "text/plain",
+            "method not allowed".to_string(),
+        )
+    } else {
+        (StatusCode::OK, prometheus::TEXT_FORMAT, metrics.encode())
+    };
+
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response parts are valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_exports_every_metric() {
+        let metrics = ServerMetrics::new();
+        metrics.connection_accepted();
+        metrics.connection_opened();
+        metrics.record_request(RequestType::ReadFile);
+        metrics.add_bytes_sent(42);
+
+        let text = metrics.encode();
+        assert!(text.contains("# TYPE fenris_active_connections gauge"));
+        assert!(text.contains("fenris_active_connections 1"));
+        assert!(text.contains("fenris_total_connections_accepted 1"));
+        assert!(text.contains("fenris_requests_total{command=\"READ_FILE\"} 1"));
+        assert!(text.contains("fenris_bytes_sent_total 42"));
+        assert!(text.contains("fenris_handshake_errors 0"));
+        assert!(text.contains("fenris_idle_timeouts 0"));
+    }
+}
@@ -1,13 +1,47 @@
+use common::compression::Compressor;
 use common::{
-    DEFAULT_TRANSFER_CHUNK_SIZE, FenrisCommand, FenrisError, FenrisOutput, ObjectWriteMode, Result,
-    StorageBackend, TransferChunk,
+    DEFAULT_TRANSFER_CHUNK_SIZE, DirFilter, FenrisCommand, FenrisError, FenrisOutput, ListSort,
+    MAX_MANIFEST_ENTRIES, ObjectWriteMode, RequestType, Result, StorageBackend, TransferChunk,
+    WatchEventKind, ZlibCompressor, is_valid_env_name,
 };
+use dashmap::DashMap;
+use similar::TextDiff;
+use std::fmt::Write;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tracing::{debug, error};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tracing::{Span, debug, error, field, info, instrument, warn};
+
+use crate::clients::ClientRegistry;
+use crate::config::ServerConfig;
+use crate::metrics::ServerMetrics;
+use crate::stats::CommandStats;
+use crate::subscriptions::{SubscriptionManager, SubscriptionScope};
+use crate::users::UserDatabase;
+
+const FETCH_MAX_REDIRECTS: usize = 5;
+const FETCH_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(300);
+const FETCH_PIPE_SIZE: usize = 64 * 1024;
+const MAX_DIFF_OUTPUT: usize = 1024 * 1024;
+const HEX_DIFF_LINE_WIDTH: usize = 16;
+const BINARY_SNIFF_LEN: usize = 512;
+
+/// Storage handed to each logged-in user, keyed by base directory and account, so every
+/// session of one user shares its locks and quota usage.
+type UserStorage<B> = DashMap<(PathBuf, String), Arc<B>>;
 
 pub struct RequestHandler<B: StorageBackend> {
     storage: Arc<B>,
+    user_storage: Arc<UserStorage<B>>,
+    subscriptions: SubscriptionScope,
+    clients: Arc<ClientRegistry>,
+    stats: Arc<CommandStats>,
+    metrics: Arc<ServerMetrics>,
+    config: Arc<ServerConfig>,
+    compressor: Arc<dyn Compressor>,
+    users: Option<Arc<UserDatabase>>,
 }
 
 #[derive(Debug, Clone)]
@@ -15,35 +49,172 @@ pub struct ActiveWriteTransfer {
     path: PathBuf,
     expected_offset: u64,
     total_size: u64,
+    change: WatchEventKind,
 }
 
 impl<B: StorageBackend> RequestHandler<B> {
     pub fn new(storage: Arc<B>) -> Self {
-        Self { storage }
+        Self::with_config(storage, Arc::new(ServerConfig::default()))
     }
 
+    pub fn with_config(storage: Arc<B>, config: Arc<ServerConfig>) -> Self {
+        Self {
+            storage,
+            user_storage: Arc::default(),
+            subscriptions: Arc::new(SubscriptionManager::new()).scope(PathBuf::new()),
+            clients: Arc::new(ClientRegistry::new()),
+            stats: Arc::new(CommandStats::new()),
+            metrics: Arc::new(ServerMetrics::new()),
+            config,
+            compressor: Arc::new(ZlibCompressor::default()),
+            users: None,
+        }
+    }
+
+    pub fn with_compressor(mut self, compressor: Arc<dyn Compressor>) -> Self {
+        self.compressor = compressor;
+        self
+    }
+
+    pub fn with_users(mut self, users: Arc<UserDatabase>) -> Self {
+        self.users = Some(users);
+        self
+    }
+
+    /// A handler for one user's connection: storage is rooted at `base_dir` and charged to
+    /// `username`'s quota, and watches only see changes made under that root. Clients, stats
+    /// and metrics stay shared with this one. Every login of the same user gets the same
+    /// storage, so their sessions share locks and quota usage.
+    pub async fn rebased(&self, base_dir: PathBuf, username: &str) -> Result<Self> {
+        let key = (base_dir.clone(), username.to_string());
+        let cached = self
+            .user_storage
+            .get(&key)
+            .map(|storage| Arc::clone(&storage));
+        let storage = match cached {
+            Some(storage) => storage,
+            None => {
+                let storage = Arc::new(self.storage.rebased(base_dir.clone(), username).await?);
+                // A concurrent first login may have won the race; keep whichever came first.
+                Arc::clone(&self.user_storage.entry(key).or_insert(storage))
+            }
+        };
+
+        Ok(Self {
+            storage,
+            user_storage: Arc::clone(&self.user_storage),
+            subscriptions: self.subscriptions.manager().scope(base_dir),
+            clients: Arc::clone(&self.clients),
+            stats: Arc::clone(&self.stats),
+            metrics: Arc::clone(&self.metrics),
+            config: Arc::clone(&self.config),
+            compressor: Arc::clone(&self.compressor),
+            users: self.users.clone(),
+        })
+    }
+
+    pub fn users(&self) -> Option<&Arc<UserDatabase>> {
+        self.users.as_ref()
+    }
+
+    pub fn subscriptions(&self) -> &Arc<SubscriptionManager> {
+        self.subscriptions.manager()
+    }
+
+    pub fn clients(&self) -> &Arc<ClientRegistry> {
+        &self.clients
+    }
+
+    pub fn stats(&self) -> &Arc<CommandStats> {
+        &self.stats
+    }
+
+    pub fn metrics(&self) -> &Arc<ServerMetrics> {
+        &self.metrics
+    }
+
+    async fn put_object(&self, path: &Path, data: &[u8]) -> Result<()> {
+        if data.len() as u64 > self.config.streaming_threshold {
+            let mut reader = data;
+            self.storage
+                .put_object_from_reader(path, &mut reader)
+                .await?;
+            return Ok(());
+        }
+
+        self.storage.put_object(path, data).await
+    }
+
+    async fn change_kind(&self, path: &Path) -> WatchEventKind {
+        if self.storage.exists(path).await {
+            WatchEventKind::Modified
+        } else {
+            WatchEventKind::Created
+        }
+    }
+
+    /// Also records the result on the current span when it declares a `resolved_path` field.
     fn resolve_path(&self, path: &Path, current_dir: &Path) -> PathBuf {
-        if path.as_os_str().is_empty() || path == Path::new(".") {
+        let resolved = if path.as_os_str().is_empty() || path == Path::new(".") {
             current_dir.to_path_buf()
         } else if path.is_absolute() {
             path.to_path_buf()
         } else {
             current_dir.join(path)
-        }
+        };
+        Span::current().record("resolved_path", field::debug(&resolved));
+        resolved
     }
 
+    #[instrument(
+        skip(self, command, current_dir),
+        fields(command = command.request_type().as_str_name())
+    )]
     pub async fn process_command(
         &self,
         client_id: u64,
         command: &FenrisCommand,
         current_dir: &mut PathBuf,
     ) -> FenrisOutput {
+        if let FenrisCommand::Correlated {
+            request_id,
+            command,
+        } = command
+        {
+            let output = Box::pin(self.process_command(client_id, command, current_dir)).await;
+            return FenrisOutput::Correlated {
+                request_id: *request_id,
+                output: Box::new(output),
+            };
+        }
+
         debug!(
             "Processing command from client {} in dir {:?}: {:?}",
             client_id, current_dir, command
         );
 
-        match self.handle_command(command, current_dir).await {
+        let started = std::time::Instant::now();
+        let result = match command {
+            FenrisCommand::Timed { timeout, command } => {
+                let timeout = self.request_timeout(*timeout);
+                tokio::time::timeout(
+                    timeout,
+                    self.handle_command(client_id, command, current_dir),
+                )
+                .await
+                .unwrap_or(Err(FenrisError::RequestTimeout(timeout)))
+            }
+            command => self.handle_command(client_id, command, current_dir).await,
+        };
+        info!(
+            client_id,
+            command = command.request_type().as_str_name(),
+            success = result.is_ok(),
+            elapsed_us = started.elapsed().as_micros() as u64,
+            "request"
+        );
+
+        match result {
             Ok(output) => output,
             Err(e) => {
                 error!("Command failed: {}", e);
@@ -54,13 +225,28 @@ impl<B: StorageBackend> RequestHandler<B> {
         }
     }
 
+    fn request_timeout(&self, requested: Duration) -> Duration {
+        match self.config.max_request_timeout {
+            Some(max) => requested.min(max),
+            None => requested,
+        }
+    }
+
     async fn handle_command(
         &self,
+        client_id: u64,
         command: &FenrisCommand,
         current_dir: &mut PathBuf,
     ) -> Result<FenrisOutput> {
         match command {
             FenrisCommand::Ping => Ok(FenrisOutput::Pong),
+            FenrisCommand::Echo { payload } => Ok(FenrisOutput::Echo {
+                payload: payload.clone(),
+            }),
+            FenrisCommand::Login { .. } => Err(FenrisError::InvalidRequest(
+                "login is only accepted as the first message of a connection".to_string(),
+            )),
+            FenrisCommand::Quota { limit } => self.handle_quota(client_id, *limit).await,
             FenrisCommand::CreateObject { path } => {
                 self.handle_create_object(path, current_dir).await
             }
@@ -83,12 +269,22 @@ impl<B: StorageBackend> RequestHandler<B> {
             FenrisCommand::WriteObjectChunk(_) => Err(FenrisError::InvalidRequest(
                 "chunked write must be handled by a connection".to_string(),
             )),
-            FenrisCommand::ObjectInfo { path } => self.handle_object_info(path, current_dir).await,
+            FenrisCommand::ObjectInfo { path, detect_mime } => {
+                self.handle_object_info(path, *detect_mime, current_dir)
+                    .await
+            }
             FenrisCommand::CreateNamespace { path } => {
-                self.handle_create_namespace(path, current_dir).await
+                self.handle_create_namespace(path, false, current_dir).await
+            }
+            FenrisCommand::CreateNamespaceAll { path } => {
+                self.handle_create_namespace(path, true, current_dir).await
             }
-            FenrisCommand::ListNamespace { path } => {
-                self.handle_list_namespace(path, current_dir).await
+            FenrisCommand::ListNamespace { path, sort } => {
+                self.handle_list_namespace(path, *sort, current_dir).await
+            }
+            FenrisCommand::ListNamespaceFiltered { path, filter } => {
+                self.handle_list_dir_with_filter(path, filter, current_dir)
+                    .await
             }
             FenrisCommand::ChangeNamespace { path } => {
                 self.handle_change_namespace(path, current_dir).await
@@ -96,21 +292,161 @@ impl<B: StorageBackend> RequestHandler<B> {
             FenrisCommand::DeleteNamespace { path } => {
                 self.handle_delete_namespace(path, current_dir).await
             }
+            FenrisCommand::Subscribe { path } => {
+                self.handle_subscribe(client_id, path, current_dir).await
+            }
+            FenrisCommand::Unsubscribe { path } => {
+                self.handle_unsubscribe(client_id, path, current_dir)
+            }
+            FenrisCommand::GetEnv { name } => self.handle_get_env(client_id, name),
+            FenrisCommand::SetEnv { name, value } => self.handle_set_env(client_id, name, value),
+            FenrisCommand::FetchUrl { url, path } => {
+                self.handle_fetch_url(url, path, current_dir).await
+            }
+            FenrisCommand::ListNamespaceRecursive { path, max_depth } => {
+                self.handle_list_namespace_recursive(path, *max_depth, current_dir)
+                    .await
+            }
+            FenrisCommand::DiffObjects { left, right } => {
+                self.handle_diff_objects(left, right, current_dir).await
+            }
+            FenrisCommand::ChecksumManifest { path } => {
+                self.handle_checksum_manifest(path, current_dir).await
+            }
+            FenrisCommand::ChecksumObject { path } => {
+                self.handle_checksum_object(path, current_dir).await
+            }
+            FenrisCommand::Broadcast { message } => self.handle_broadcast(client_id, message),
+            FenrisCommand::CompressObject { path, extension } => {
+                self.handle_compress_object(path, extension.as_deref(), current_dir)
+                    .await
+            }
+            FenrisCommand::DecompressObject { path, extension } => {
+                self.handle_decompress_object(path, extension.as_deref(), current_dir)
+                    .await
+            }
+            FenrisCommand::HeadObject { path, lines } => {
+                self.handle_head_object(path, *lines, current_dir).await
+            }
+            FenrisCommand::TailObject { path, bytes } => {
+                self.handle_tail_object(path, *bytes, current_dir).await
+            }
+            FenrisCommand::CreateSymlink { target, link } => {
+                self.handle_create_symlink(target, link, current_dir).await
+            }
+            FenrisCommand::ReadSymlink { link } => {
+                self.handle_read_symlink(link, current_dir).await
+            }
+            FenrisCommand::CreateDirJunction { target, link } => {
+                self.handle_junction(target, link, current_dir).await
+            }
+            FenrisCommand::SetMtime { path, mtime } => {
+                self.handle_set_mtime(path, *mtime, current_dir).await
+            }
+            FenrisCommand::MoveObject { from, to } => {
+                self.handle_move_object(from, to, current_dir).await
+            }
+            FenrisCommand::GetXattr { path, name } => {
+                self.handle_get_xattr(path, name, current_dir).await
+            }
+            FenrisCommand::SetXattr { path, name, value } => {
+                self.handle_set_xattr(path, name, value, current_dir).await
+            }
+            FenrisCommand::CreateArchive { dir, archive } => {
+                self.handle_create_archive(dir, archive.as_deref(), current_dir)
+                    .await
+            }
+            FenrisCommand::ExtractArchive { archive, dest } => {
+                self.handle_extract_archive(archive, dest.as_deref(), current_dir)
+                    .await
+            }
+            FenrisCommand::MultiOp {
+                commands,
+                fail_fast,
+            } => {
+                self.handle_multi_op(client_id, commands, *fail_fast, current_dir)
+                    .await
+            }
+            FenrisCommand::Timed { .. } => Err(FenrisError::InvalidRequest(
+                "request timeouts cannot be nested".to_string(),
+            )),
+            FenrisCommand::Correlated { .. } => Err(FenrisError::InvalidRequest(
+                "request ids cannot be nested".to_string(),
+            )),
             FenrisCommand::Terminate => Ok(FenrisOutput::Terminated),
         }
     }
 
+    /// Runs each command as if it had arrived on its own, so a `cd` affects the ones after it.
+    async fn handle_multi_op(
+        &self,
+        client_id: u64,
+        commands: &[FenrisCommand],
+        fail_fast: bool,
+        current_dir: &mut PathBuf,
+    ) -> Result<FenrisOutput> {
+        if commands.iter().any(|command| {
+            matches!(
+                command.request_type(),
+                RequestType::MultiOp | RequestType::Terminate
+            )
+        }) {
+            return Err(FenrisError::InvalidRequest(
+                "multi-op cannot contain another multi-op or a terminate".to_string(),
+            ));
+        }
+
+        let mut outputs = Vec::with_capacity(commands.len());
+        for command in commands {
+            let output = Box::pin(self.process_command(client_id, command, current_dir)).await;
+            let failed = matches!(output, FenrisOutput::Error { .. });
+            outputs.push(output);
+            if failed && fail_fast {
+                break;
+            }
+        }
+
+        Ok(FenrisOutput::MultiOp { outputs })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
     async fn handle_create_object(&self, path: &Path, current_dir: &Path) -> Result<FenrisOutput> {
         let path = self.resolve_path(path, current_dir);
+        let change = self.change_kind(&path).await;
         self.storage.put_object(&path, b"").await?;
+        self.subscriptions.notify(&path, change);
+        info!(path = %path.display(), "create_object");
 
         Ok(FenrisOutput::Success {
             message: format!("File created: {}", path.to_string_lossy()),
         })
     }
 
+    #[instrument(skip(self), fields(resolved_path))]
     async fn handle_read_object(&self, path: &Path, current_dir: &Path) -> Result<FenrisOutput> {
         let path = self.resolve_path(path, current_dir);
+        let threshold = self.config.streaming_threshold;
+        let total_size = self.storage.metadata(&path).await?.size;
+
+        if total_size > threshold {
+            let mut data = Vec::new();
+            self.storage
+                .object_reader(&path)
+                .await?
+                .take(threshold)
+                .read_to_end(&mut data)
+                .await
+                .map_err(|e| {
+                    FenrisError::file_operation_from(format!("Failed to read file: {}", e), e)
+                })?;
+
+            return Ok(FenrisOutput::ObjectContent {
+                data,
+                total_size,
+                truncated: true,
+            });
+        }
+
         let data = self.storage.get_object(&path).await?;
         let total_size = data.len() as u64;
 
@@ -121,6 +457,84 @@ impl<B: StorageBackend> RequestHandler<B> {
         })
     }
 
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_head_object(
+        &self,
+        path: &Path,
+        lines: u32,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let path = self.resolve_path(path, current_dir);
+        let total_size = self.storage.metadata(&path).await?.size;
+        let reader = self
+            .storage
+            .object_reader(&path)
+            .await?
+            .take(self.config.streaming_threshold);
+        let mut reader = BufReader::new(reader);
+
+        let mut data = Vec::new();
+        for _ in 0..lines {
+            let read = reader.read_until(b'\n', &mut data).await.map_err(|e| {
+                FenrisError::file_operation_from(format!("Failed to read file: {}", e), e)
+            })?;
+            if read == 0 {
+                break;
+            }
+        }
+
+        Ok(FenrisOutput::ObjectContentChunk(TransferChunk {
+            offset: 0,
+            is_last: data.len() as u64 == total_size,
+            data,
+            total_size,
+        }))
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_tail_object(
+        &self,
+        path: &Path,
+        bytes: u64,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let path = self.resolve_path(path, current_dir);
+        let total_size = self.storage.metadata(&path).await?.size;
+        let len = bytes.min(self.config.streaming_threshold).min(total_size);
+        let mut chunk = self
+            .storage
+            .get_object_chunk(&path, total_size - len, len as usize)
+            .await?;
+
+        // Starting mid-file may split a multi-byte character; drop its continuation bytes.
+        if chunk.offset > 0 && !self.is_binary(&path).await? {
+            let partial = chunk
+                .data
+                .iter()
+                .take(3)
+                .take_while(|byte| (**byte & 0xC0) == 0x80)
+                .count();
+            chunk.data.drain(..partial);
+            chunk.offset += partial as u64;
+        }
+
+        Ok(FenrisOutput::ObjectContentChunk(TransferChunk {
+            offset: chunk.offset,
+            data: chunk.data,
+            is_last: true,
+            total_size: chunk.total_size,
+        }))
+    }
+
+    async fn is_binary(&self, path: &Path) -> Result<bool> {
+        let head = self
+            .storage
+            .get_object_chunk(path, 0, BINARY_SNIFF_LEN)
+            .await?;
+        Ok(head.data.contains(&0))
+    }
+
+    #[instrument(skip(self, data), fields(resolved_path, bytes))]
     async fn handle_write_object(
         &self,
         path: &Path,
@@ -128,13 +542,18 @@ impl<B: StorageBackend> RequestHandler<B> {
         current_dir: &Path,
     ) -> Result<FenrisOutput> {
         let path = self.resolve_path(path, current_dir);
-        self.storage.put_object(&path, data).await?;
+        Span::current().record("bytes", data.len());
+        let change = self.change_kind(&path).await;
+        self.put_object(&path, data).await?;
+        self.subscriptions.notify(&path, change);
+        info!(path = %path.display(), bytes = data.len(), "write_object");
 
         Ok(FenrisOutput::Success {
             message: format!("File written: {} bytes", data.len()),
         })
     }
 
+    #[instrument(skip(self, data), fields(resolved_path, bytes))]
     async fn handle_append_object(
         &self,
         path: &Path,
@@ -142,7 +561,11 @@ impl<B: StorageBackend> RequestHandler<B> {
         current_dir: &Path,
     ) -> Result<FenrisOutput> {
         let path = self.resolve_path(path, current_dir);
+        Span::current().record("bytes", data.len());
+        let change = self.change_kind(&path).await;
         self.storage.append_object(&path, data).await?;
+        self.subscriptions.notify(&path, change);
+        info!(path = %path.display(), bytes = data.len(), "append_object");
 
         Ok(FenrisOutput::Success {
             message: format!(
@@ -153,15 +576,19 @@ impl<B: StorageBackend> RequestHandler<B> {
         })
     }
 
+    #[instrument(skip(self), fields(resolved_path))]
     async fn handle_delete_object(&self, path: &Path, current_dir: &Path) -> Result<FenrisOutput> {
         let path = self.resolve_path(path, current_dir);
         self.storage.delete_object(&path).await?;
+        self.subscriptions.notify(&path, WatchEventKind::Deleted);
+        info!(path = %path.display(), "delete_object");
 
         Ok(FenrisOutput::Success {
             message: format!("File deleted: {}", path.to_string_lossy()),
         })
     }
 
+    #[instrument(skip(self, data), fields(resolved_path, bytes))]
     async fn handle_upload_object(
         &self,
         path: &Path,
@@ -169,7 +596,11 @@ impl<B: StorageBackend> RequestHandler<B> {
         current_dir: &Path,
     ) -> Result<FenrisOutput> {
         let path = self.resolve_path(path, current_dir);
-        self.storage.put_object(&path, data).await?;
+        Span::current().record("bytes", data.len());
+        let change = self.change_kind(&path).await;
+        self.put_object(&path, data).await?;
+        self.subscriptions.notify(&path, change);
+        info!(path = %path.display(), bytes = data.len(), "upload_object");
 
         Ok(FenrisOutput::Success {
             message: format!(
@@ -180,33 +611,110 @@ impl<B: StorageBackend> RequestHandler<B> {
         })
     }
 
-    async fn handle_object_info(&self, path: &Path, current_dir: &Path) -> Result<FenrisOutput> {
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_object_info(
+        &self,
+        path: &Path,
+        detect_mime: bool,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
         let path = self.resolve_path(path, current_dir);
         let metadata = self.storage.metadata(&path).await?;
+        let mime_type = if detect_mime && !metadata.is_namespace {
+            let head = self
+                .storage
+                .get_object_chunk(&path, 0, BINARY_SNIFF_LEN)
+                .await?;
+            Some(guess_mime_type(&head.data))
+        } else {
+            None
+        };
 
-        Ok(FenrisOutput::ObjectInfo { metadata })
+        Ok(FenrisOutput::ObjectInfo {
+            metadata,
+            mime_type,
+        })
     }
 
+    #[instrument(skip(self), fields(resolved_path))]
     async fn handle_create_namespace(
         &self,
         path: &Path,
+        parents: bool,
         current_dir: &Path,
     ) -> Result<FenrisOutput> {
         let path = self.resolve_path(path, current_dir);
-        self.storage.create_namespace(&path).await?;
+        if parents {
+            self.storage.create_namespace_all(&path).await?;
+        } else {
+            self.storage.create_namespace(&path).await?;
+        }
+        self.subscriptions.notify(&path, WatchEventKind::Created);
+        info!(path = %path.display(), parents, "create_namespace");
 
         Ok(FenrisOutput::Success {
             message: format!("Directory created: {}", path.to_string_lossy()),
         })
     }
 
-    async fn handle_list_namespace(&self, path: &Path, current_dir: &Path) -> Result<FenrisOutput> {
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_list_namespace(
+        &self,
+        path: &Path,
+        sort: ListSort,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let path = self.resolve_path(path, current_dir);
+        let mut entries = self.storage.list_namespace(&path).await?;
+        sort.apply(&mut entries);
+
+        Ok(FenrisOutput::NamespaceListing { entries, sort })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_list_dir_with_filter(
+        &self,
+        path: &Path,
+        filter: &DirFilter,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let path = self.resolve_path(path, current_dir);
+        let mut entries = Vec::new();
+        for entry in self.storage.list_namespace(&path).await? {
+            let is_symlink = filter.checks_symlinks()
+                && self
+                    .storage
+                    .read_symlink(&path.join(&entry.name))
+                    .await
+                    .is_ok();
+            if filter.matches(&entry, is_symlink) {
+                entries.push(entry);
+            }
+        }
+
+        Ok(FenrisOutput::NamespaceListing {
+            entries,
+            sort: ListSort::default(),
+        })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_list_namespace_recursive(
+        &self,
+        path: &Path,
+        max_depth: u32,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
         let path = self.resolve_path(path, current_dir);
-        let entries = self.storage.list_namespace(&path).await?;
+        let entries = self
+            .storage
+            .list_namespace_recursive(&path, max_depth)
+            .await?;
 
-        Ok(FenrisOutput::NamespaceListing { entries })
+        Ok(FenrisOutput::RecursiveNamespaceListing { entries })
     }
 
+    #[instrument(skip(self), fields(resolved_path))]
     async fn handle_delete_namespace(
         &self,
         path: &Path,
@@ -214,12 +722,201 @@ impl<B: StorageBackend> RequestHandler<B> {
     ) -> Result<FenrisOutput> {
         let path = self.resolve_path(path, current_dir);
         self.storage.delete_namespace(&path).await?;
+        self.subscriptions.notify(&path, WatchEventKind::Deleted);
+        info!(path = %path.display(), "delete_namespace");
 
         Ok(FenrisOutput::Success {
             message: format!("Directory deleted: {}", path.to_string_lossy()),
         })
     }
 
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_create_symlink(
+        &self,
+        target: &Path,
+        link: &Path,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let link = self.resolve_path(link, current_dir);
+        self.storage.create_symlink(&link, target).await?;
+        self.subscriptions.notify(&link, WatchEventKind::Created);
+        info!(path = %link.display(), target = %target.display(), "create_symlink");
+
+        Ok(FenrisOutput::Success {
+            message: format!(
+                "Symlink created: {} -> {}",
+                link.to_string_lossy(),
+                target.to_string_lossy()
+            ),
+        })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_junction(
+        &self,
+        target: &Path,
+        link: &Path,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let link = self.resolve_path(link, current_dir);
+        self.storage.create_dir_junction(&link, target).await?;
+        self.subscriptions.notify(&link, WatchEventKind::Created);
+        info!(path = %link.display(), target = %target.display(), "create_dir_junction");
+
+        Ok(FenrisOutput::Success {
+            message: format!(
+                "Directory junction created: {} -> {}",
+                link.to_string_lossy(),
+                target.to_string_lossy()
+            ),
+        })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_read_symlink(&self, link: &Path, current_dir: &Path) -> Result<FenrisOutput> {
+        let link = self.resolve_path(link, current_dir);
+        let target = self.storage.read_symlink(&link).await?;
+
+        Ok(FenrisOutput::SymlinkTarget { target })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_move_object(
+        &self,
+        from: &Path,
+        to: &Path,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let from = self.resolve_path(from, current_dir);
+        let to = self.resolve_path(to, current_dir);
+        if self.storage.is_namespace(&from).await {
+            self.storage.move_namespace(&from, &to).await?;
+        } else {
+            self.storage.move_object(&from, &to).await?;
+        }
+        self.subscriptions.notify(&from, WatchEventKind::Deleted);
+        self.subscriptions.notify(&to, WatchEventKind::Created);
+        info!(from = %from.display(), to = %to.display(), "move");
+
+        Ok(FenrisOutput::Success {
+            message: format!(
+                "Moved: {} -> {}",
+                from.to_string_lossy(),
+                to.to_string_lossy()
+            ),
+        })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_get_xattr(
+        &self,
+        path: &Path,
+        name: &str,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let path = self.resolve_path(path, current_dir);
+        let value = self.storage.get_xattr(&path, name).await?;
+
+        Ok(FenrisOutput::XattrValue { value })
+    }
+
+    #[instrument(skip(self, value), fields(resolved_path))]
+    async fn handle_set_xattr(
+        &self,
+        path: &Path,
+        name: &str,
+        value: &[u8],
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let path = self.resolve_path(path, current_dir);
+        self.storage.set_xattr(&path, name, value).await?;
+        self.subscriptions.notify(&path, WatchEventKind::Modified);
+        info!(path = %path.display(), name, "set_xattr");
+
+        Ok(FenrisOutput::Success {
+            message: format!("Set {} on {}", name, path.to_string_lossy()),
+        })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_create_archive(
+        &self,
+        dir: &Path,
+        archive: Option<&Path>,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let dir = self.resolve_path(dir, current_dir);
+        let archive = match archive {
+            Some(archive) => self.resolve_path(archive, current_dir),
+            None => {
+                let mut archive = dir.clone().into_os_string();
+                archive.push(".tar.gz");
+                PathBuf::from(archive)
+            }
+        };
+
+        let change = self.change_kind(&archive).await;
+        let size = self.storage.create_archive(&dir, &archive).await?;
+        self.subscriptions.notify(&archive, change);
+        info!(dir = %dir.display(), archive = %archive.display(), size, "tar");
+
+        Ok(FenrisOutput::Success {
+            message: format!(
+                "Archived {} into {} ({} bytes)",
+                dir.to_string_lossy(),
+                archive.to_string_lossy(),
+                size
+            ),
+        })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_extract_archive(
+        &self,
+        archive: &Path,
+        dest: Option<&Path>,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let archive = self.resolve_path(archive, current_dir);
+        let dest = self.resolve_path(dest.unwrap_or(Path::new(".")), current_dir);
+
+        let change = self.change_kind(&dest).await;
+        let extracted = self.storage.extract_archive(&archive, &dest).await?;
+        self.subscriptions.notify(&dest, change);
+        info!(archive = %archive.display(), dest = %dest.display(), extracted, "untar");
+
+        Ok(FenrisOutput::Success {
+            message: format!(
+                "Extracted {} into {} ({} bytes)",
+                archive.to_string_lossy(),
+                dest.to_string_lossy(),
+                extracted
+            ),
+        })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_set_mtime(
+        &self,
+        path: &Path,
+        mtime: u64,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let path = self.resolve_path(path, current_dir);
+        self.storage.set_mtime(&path, mtime).await?;
+        self.subscriptions.notify(&path, WatchEventKind::Modified);
+        info!(path = %path.display(), mtime, "set_mtime");
+
+        Ok(FenrisOutput::Success {
+            message: format!(
+                "Modification time of {} set to {}",
+                path.to_string_lossy(),
+                mtime
+            ),
+        })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
     async fn handle_change_namespace(
         &self,
         path: &Path,
@@ -239,11 +936,10 @@ impl<B: StorageBackend> RequestHandler<B> {
         } else {
             current_dir.join(path)
         };
+        Span::current().record("resolved_path", field::debug(&target_path));
 
         if !self.storage.is_namespace(&target_path).await {
-            return Err(FenrisError::FileOperationError(
-                "Not a directory".to_string(),
-            ));
+            return Err(FenrisError::file_operation("Not a directory"));
         }
 
         *current_dir = target_path.clone();
@@ -251,31 +947,406 @@ impl<B: StorageBackend> RequestHandler<B> {
         Ok(FenrisOutput::NamespaceChanged { path: target_path })
     }
 
-    pub async fn begin_object_write(
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_subscribe(
         &self,
+        client_id: u64,
         path: &Path,
-        mode: ObjectWriteMode,
-        total_size: u64,
         current_dir: &Path,
-    ) -> Result<ActiveWriteTransfer> {
+    ) -> Result<FenrisOutput> {
         let path = self.resolve_path(path, current_dir);
 
-        match mode {
-            ObjectWriteMode::Write | ObjectWriteMode::Upload => {
-                self.storage.put_object(&path, b"").await?;
-            }
-            ObjectWriteMode::Append => {
-                self.storage.append_object(&path, b"").await?;
-            }
+        if !self.storage.exists(&path).await {
+            return Err(FenrisError::file_operation(format!(
+                "Cannot watch missing path: {}",
+                path.to_string_lossy()
+            )));
+        }
+
+        self.subscriptions.subscribe(client_id, &path);
+
+        Ok(FenrisOutput::Success {
+            message: path.to_string_lossy().to_string(),
+        })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    fn handle_unsubscribe(
+        &self,
+        client_id: u64,
+        path: &Path,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let path = self.resolve_path(path, current_dir);
+
+        if !self.subscriptions.unsubscribe(client_id, &path) {
+            return Err(FenrisError::InvalidRequest(format!(
+                "Not watching: {}",
+                path.to_string_lossy()
+            )));
+        }
+
+        Ok(FenrisOutput::Success {
+            message: path.to_string_lossy().to_string(),
+        })
+    }
+
+    #[instrument(skip(self))]
+    fn handle_get_env(&self, client_id: u64, name: &str) -> Result<FenrisOutput> {
+        check_env_name(name)?;
+        let value = self
+            .clients
+            .env(client_id, name)
+            .ok_or_else(|| FenrisError::InvalidRequest(format!("{} is not set", name)))?;
+
+        Ok(FenrisOutput::EnvValue { value })
+    }
+
+    #[instrument(skip(self, value))]
+    fn handle_set_env(&self, client_id: u64, name: &str, value: &str) -> Result<FenrisOutput> {
+        check_env_name(name)?;
+        self.clients.set_env(client_id, name, value);
+
+        Ok(FenrisOutput::Success {
+            message: format!("Set {}", name),
+        })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_fetch_url(
+        &self,
+        url: &str,
+        path: &Path,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        if !self.config.allow_fetch_url {
+            return Err(FenrisError::InvalidRequest(
+                "FetchUrl disabled by server config".to_string(),
+            ));
+        }
+
+        let url = reqwest::Url::parse(url)
+            .map_err(|e| FenrisError::InvalidRequest(format!("Invalid URL {}: {}", url, e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(FenrisError::InvalidRequest(format!(
+                "Unsupported URL scheme: {}",
+                url.scheme()
+            )));
+        }
+
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::limited(FETCH_MAX_REDIRECTS))
+            .connect_timeout(FETCH_CONNECT_TIMEOUT)
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .map_err(|e| {
+                FenrisError::file_operation_from(format!("Failed to create HTTP client: {}", e), e)
+            })?;
+
+        let mut response = client
+            .get(url.clone())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| {
+                FenrisError::file_operation_from(format!("Failed to fetch {}: {}", url, e), e)
+            })?;
+
+        let max_file_size = self.config.max_file_size;
+        if let (Some(max), Some(length)) = (max_file_size, response.content_length())
+            && length > max
+        {
+            return Err(FenrisError::file_operation(format!(
+                "Remote file too large: {} bytes exceeds limit of {} bytes",
+                length, max
+            )));
+        }
+
+        let path = self.resolve_path(path, current_dir);
+        let change = self.change_kind(&path).await;
+
+        // The body is piped into a streaming write. A failed download drops that write before
+        // it completes, which leaves any existing file at `path` untouched.
+        let (mut pipe_writer, mut pipe_reader) = tokio::io::duplex(FETCH_PIPE_SIZE);
+        let download = async {
+            let mut downloaded = 0u64;
+            while let Some(chunk) = response.chunk().await.map_err(|e| {
+                FenrisError::file_operation_from(format!("Failed to download {}: {}", url, e), e)
+            })? {
+                downloaded += chunk.len() as u64;
+                if let Some(max) = max_file_size
+                    && downloaded > max
+                {
+                    return Err(FenrisError::file_operation(format!(
+                        "Remote file too large: exceeds limit of {} bytes",
+                        max
+                    )));
+                }
+                pipe_writer.write_all(&chunk).await?;
+            }
+            pipe_writer.shutdown().await?;
+            Ok(downloaded)
+        };
+        let (downloaded, _) = tokio::try_join!(
+            download,
+            self.storage.put_object_from_reader(&path, &mut pipe_reader)
+        )?;
+
+        self.subscriptions.notify(&path, change);
+
+        Ok(FenrisOutput::Success {
+            message: format!("Fetched {} bytes to {}", downloaded, path.to_string_lossy()),
+        })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_checksum_object(
+        &self,
+        path: &Path,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let path = self.resolve_path(path, current_dir);
+        let digest = self.storage.checksum_object(&path).await?;
+
+        Ok(FenrisOutput::ObjectChecksum { digest })
+    }
+
+    /// Hashes one file past the cap so a tree of exactly [`MAX_MANIFEST_ENTRIES`] files is
+    /// not reported as truncated.
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_checksum_manifest(
+        &self,
+        path: &Path,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let path = self.resolve_path(path, current_dir);
+        let mut entries = self
+            .storage
+            .checksum_manifest(&path, MAX_MANIFEST_ENTRIES + 1)
+            .await?;
+
+        let truncated = entries.len() > MAX_MANIFEST_ENTRIES;
+        if truncated {
+            entries.truncate(MAX_MANIFEST_ENTRIES);
+            warn!(
+                path = %path.display(),
+                "checksum manifest truncated to {} files", MAX_MANIFEST_ENTRIES
+            );
+        }
+
+        Ok(FenrisOutput::ChecksumManifest { entries, truncated })
+    }
+
+    #[instrument(skip(self))]
+    fn handle_broadcast(&self, client_id: u64, message: &str) -> Result<FenrisOutput> {
+        if !self.is_psk_session(client_id) {
+            return Err(FenrisError::AuthenticationError(
+                "broadcast requires PSK authentication".to_string(),
+            ));
+        }
+
+        let count = self.subscriptions.broadcast(message);
+
+        Ok(FenrisOutput::Success {
+            message: format!("Broadcast sent to {} clients", count),
+        })
+    }
+
+    fn is_psk_session(&self, client_id: u64) -> bool {
+        self.clients
+            .get(client_id)
+            .is_some_and(|info| info.psk_authenticated)
+    }
+
+    /// PSK sessions that did not log in as a user may change server-wide settings. Users
+    /// stay confined to their own account even when they also know the PSK.
+    fn is_admin_session(&self, client_id: u64) -> bool {
+        self.clients
+            .get(client_id)
+            .is_some_and(|info| info.psk_authenticated && info.username.is_none())
+    }
+
+    /// Reports the caller's usage, first setting their account's limit when one is given.
+    /// Only admin sessions may set a limit; everyone else gets read-only access.
+    #[instrument(skip(self))]
+    async fn handle_quota(&self, client_id: u64, limit: Option<u64>) -> Result<FenrisOutput> {
+        if let Some(limit) = limit {
+            if !self.is_admin_session(client_id) {
+                return Err(FenrisError::AuthenticationError(
+                    "setting a quota requires an admin session authenticated with the PSK"
+                        .to_string(),
+                ));
+            }
+            self.storage.set_quota_limit(limit).await?;
+            info!("Quota set to {} bytes", limit);
+        }
+
+        let usage = self.storage.quota_usage().ok_or_else(|| {
+            FenrisError::InvalidRequest("quotas are not enabled on this server".to_string())
+        })?;
+
+        Ok(FenrisOutput::QuotaInfo {
+            used_bytes: usage.used_bytes,
+            limit_bytes: usage.limit_bytes,
+        })
+    }
+
+    fn compressed_extension(&self, extension: Option<&str>) -> String {
+        let extension = extension.unwrap_or(self.compressor.name());
+        format!(".{}", extension.trim_start_matches('.'))
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_compress_object(
+        &self,
+        path: &Path,
+        extension: Option<&str>,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let src = self.resolve_path(path, current_dir);
+        let mut dst = src.clone().into_os_string();
+        dst.push(self.compressed_extension(extension));
+        let dst = PathBuf::from(dst);
+
+        let original_size = self.storage.metadata(&src).await?.size;
+        let change = self.change_kind(&dst).await;
+        let compressed_size = self
+            .storage
+            .compress_object(&src, &dst, self.compressor.as_ref())
+            .await?;
+        self.subscriptions.notify(&dst, change);
+
+        Ok(FenrisOutput::Success {
+            message: format!(
+                "Compressed {} ({} -> {} bytes) into {}",
+                src.to_string_lossy(),
+                original_size,
+                compressed_size,
+                dst.to_string_lossy()
+            ),
+        })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    async fn handle_decompress_object(
+        &self,
+        path: &Path,
+        extension: Option<&str>,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let src = self.resolve_path(path, current_dir);
+        let extension = self.compressed_extension(extension);
+        let dst = src
+            .to_str()
+            .and_then(|src| src.strip_suffix(&extension))
+            .filter(|dst| !dst.is_empty() && !dst.ends_with('/'))
+            .map(PathBuf::from)
+            .ok_or_else(|| {
+                FenrisError::InvalidRequest(format!(
+                    "{} does not end with {}",
+                    src.to_string_lossy(),
+                    extension
+                ))
+            })?;
+
+        let original_size = self.storage.metadata(&src).await?.size;
+        let change = self.change_kind(&dst).await;
+        let decompressed_size = self
+            .storage
+            .decompress_object(&src, &dst, self.compressor.as_ref())
+            .await?;
+        self.subscriptions.notify(&dst, change);
+
+        Ok(FenrisOutput::Success {
+            message: format!(
+                "Decompressed {} ({} -> {} bytes) into {}",
+                src.to_string_lossy(),
+                original_size,
+                decompressed_size,
+                dst.to_string_lossy()
+            ),
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn handle_diff_objects(
+        &self,
+        left: &Path,
+        right: &Path,
+        current_dir: &Path,
+    ) -> Result<FenrisOutput> {
+        let left = self.resolve_path(left, current_dir);
+        let right = self.resolve_path(right, current_dir);
+        // Both sides are held in memory, and binary ones grow threefold as hex.
+        let limit = self.config.streaming_threshold;
+        for path in [&left, &right] {
+            let size = self.storage.metadata(path).await?.size;
+            if size > limit {
+                return Err(FenrisError::InvalidRequest(format!(
+                    "{} is {} bytes; diff only compares files up to {} bytes",
+                    path.to_string_lossy(),
+                    size,
+                    limit
+                )));
+            }
+        }
+        let left_data = self.storage.get_object(&left).await?;
+        let right_data = self.storage.get_object(&right).await?;
+
+        let left_name = left.to_string_lossy();
+        let right_name = right.to_string_lossy();
+        let diff = match (
+            std::str::from_utf8(&left_data),
+            std::str::from_utf8(&right_data),
+        ) {
+            (Ok(left_text), Ok(right_text)) => {
+                unified_diff(left_text, right_text, &left_name, &right_name)
+            }
+            _ => unified_diff(
+                &hex_lines(&left_data),
+                &hex_lines(&right_data),
+                &left_name,
+                &right_name,
+            ),
+        };
+
+        Ok(FenrisOutput::ObjectDiff {
+            diff: truncate_diff(diff),
+        })
+    }
+
+    #[instrument(skip(self), fields(resolved_path))]
+    pub async fn begin_object_write(
+        &self,
+        path: &Path,
+        mode: ObjectWriteMode,
+        total_size: u64,
+        current_dir: &Path,
+    ) -> Result<ActiveWriteTransfer> {
+        let path = self.resolve_path(path, current_dir);
+        let change = self.change_kind(&path).await;
+
+        match mode {
+            ObjectWriteMode::Write | ObjectWriteMode::Upload => {
+                self.storage.put_object(&path, b"").await?;
+            }
+            ObjectWriteMode::Append => {
+                self.storage.append_object(&path, b"").await?;
+            }
         }
 
         Ok(ActiveWriteTransfer {
             path,
             expected_offset: 0,
             total_size,
+            change,
         })
     }
 
+    #[instrument(
+        skip_all,
+        fields(path = ?transfer.path, offset = chunk.offset, bytes = chunk.data.len())
+    )]
     pub async fn write_object_chunk(
         &self,
         transfer: &mut ActiveWriteTransfer,
@@ -317,6 +1388,8 @@ impl<B: StorageBackend> RequestHandler<B> {
                 )));
             }
 
+            self.subscriptions.notify(&transfer.path, transfer.change);
+
             return Ok(FenrisOutput::Success {
                 message: format!("Transfer complete: {} bytes", transfer.total_size),
             });
@@ -327,6 +1400,7 @@ impl<B: StorageBackend> RequestHandler<B> {
         })
     }
 
+    #[instrument(skip(self), fields(resolved_path))]
     pub async fn read_object_chunk(
         &self,
         path: &Path,
@@ -348,10 +1422,70 @@ impl<B: StorageBackend> RequestHandler<B> {
     }
 }
 
+fn check_env_name(name: &str) -> Result<()> {
+    if is_valid_env_name(name) {
+        Ok(())
+    } else {
+        Err(FenrisError::InvalidRequest(format!(
+            "Invalid variable name: {:?}",
+            name
+        )))
+    }
+}
+
+/// Magic-number sniffing first; content without a known signature is called text unless it
+/// looks binary by the same NUL-byte test `is_binary` uses.
+fn guess_mime_type(head: &[u8]) -> String {
+    match infer::get(head) {
+        Some(kind) => kind.mime_type().to_string(),
+        None if head.contains(&0) => "application/octet-stream".to_string(),
+        None => "text/plain".to_string(),
+    }
+}
+
+fn unified_diff(left: &str, right: &str, left_name: &str, right_name: &str) -> String {
+    TextDiff::from_lines(left, right)
+        .unified_diff()
+        .header(left_name, right_name)
+        .to_string()
+}
+
+fn hex_lines(data: &[u8]) -> String {
+    let mut output = String::new();
+
+    for (index, chunk) in data.chunks(HEX_DIFF_LINE_WIDTH).enumerate() {
+        let _ = write!(output, "{:08x}:", index * HEX_DIFF_LINE_WIDTH);
+        for byte in chunk {
+            let _ = write!(output, " {:02x}", byte);
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+fn truncate_diff(mut diff: String) -> String {
+    if diff.len() <= MAX_DIFF_OUTPUT {
+        return diff;
+    }
+
+    let mut end = MAX_DIFF_OUTPUT;
+    while !diff.is_char_boundary(end) {
+        end -= 1;
+    }
+    if let Some(newline) = diff[..end].rfind('\n') {
+        end = newline + 1;
+    }
+
+    diff.truncate(end);
+    diff.push_str("... diff truncated at 1 MiB\n");
+    diff
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use common::MemoryStorage;
+    use common::{ListSortKey, MemoryStorage};
 
     fn create_handler() -> (RequestHandler<MemoryStorage>, Arc<MemoryStorage>) {
         let storage = Arc::new(MemoryStorage::new());
@@ -371,111 +1505,398 @@ mod tests {
         assert_eq!(output, FenrisOutput::Pong);
     }
 
+    #[cfg(unix)]
     #[tokio::test]
-    async fn test_create_file() {
-        let (handler, ops) = create_handler();
-        let mut current_dir = PathBuf::from("/home");
-        ops.create_namespace(&current_dir).await.unwrap();
+    async fn test_symlink_round_trip_keeps_target_verbatim() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_ops = common::DefaultFileOperations::new_unchecked(dir.path().to_path_buf())
+            .with_symlinks_allowed(true);
+        let handler =
+            RequestHandler::new(Arc::new(common::TokioFsStorage::with_file_ops(file_ops)));
+        let mut current_dir = PathBuf::from("/");
 
-        let output = handler
+        let created = handler
             .process_command(
                 1,
-                &FenrisCommand::CreateObject {
-                    path: PathBuf::from("test.txt"),
+                &FenrisCommand::CreateSymlink {
+                    target: PathBuf::from("../outside"),
+                    link: PathBuf::from("latest"),
                 },
                 &mut current_dir,
             )
             .await;
+        assert!(matches!(created, FenrisOutput::Success { .. }));
 
-        assert!(matches!(output, FenrisOutput::Success { .. }));
+        let target = handler
+            .process_command(
+                1,
+                &FenrisCommand::ReadSymlink {
+                    link: PathBuf::from("/latest"),
+                },
+                &mut current_dir,
+            )
+            .await;
+        assert_eq!(
+            target,
+            FenrisOutput::SymlinkTarget {
+                target: PathBuf::from("../outside"),
+            }
+        );
+    }
 
-        assert!(ops.exists(Path::new("/home/test.txt")).await);
+    #[tokio::test]
+    async fn test_move_handles_files_and_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("docs")).unwrap();
+        std::fs::write(dir.path().join("docs/draft.txt"), b"text").unwrap();
+        let handler = RequestHandler::new(Arc::new(common::TokioFsStorage::new(
+            dir.path().to_path_buf(),
+        )));
+        let mut current_dir = PathBuf::from("/docs");
+
+        for (from, to) in [("draft.txt", "final.txt"), ("/docs", "/archive")] {
+            let output = handler
+                .process_command(
+                    1,
+                    &FenrisCommand::MoveObject {
+                        from: PathBuf::from(from),
+                        to: PathBuf::from(to),
+                    },
+                    &mut current_dir,
+                )
+                .await;
+            assert!(matches!(output, FenrisOutput::Success { .. }), "{output:?}");
+        }
+
+        assert!(!dir.path().join("docs").exists());
+        assert_eq!(
+            std::fs::read(dir.path().join("archive/final.txt")).unwrap(),
+            b"text"
+        );
     }
 
     #[tokio::test]
-    async fn test_write_and_read_file() {
-        let (handler, _) = create_handler();
+    async fn test_tar_and_untar_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("project/src")).unwrap();
+        std::fs::write(dir.path().join("project/src/main.rs"), b"fn main() {}").unwrap();
+        let handler = RequestHandler::new(Arc::new(common::TokioFsStorage::new(
+            dir.path().to_path_buf(),
+        )));
         let mut current_dir = PathBuf::from("/");
 
-        let data = b"Hello, World!".to_vec();
-        let write_output = handler
+        let archived = handler
             .process_command(
                 1,
-                &FenrisCommand::WriteObject {
-                    path: PathBuf::from("hello.txt"),
-                    data: data.clone(),
+                &FenrisCommand::CreateArchive {
+                    dir: PathBuf::from("project"),
+                    archive: None,
                 },
                 &mut current_dir,
             )
             .await;
-        assert!(matches!(write_output, FenrisOutput::Success { .. }));
+        assert!(
+            matches!(archived, FenrisOutput::Success { .. }),
+            "{archived:?}"
+        );
+        assert!(dir.path().join("project.tar.gz").exists());
 
-        let read_output = handler
+        let extracted = handler
             .process_command(
                 1,
-                &FenrisCommand::ReadObject {
-                    path: PathBuf::from("hello.txt"),
+                &FenrisCommand::ExtractArchive {
+                    archive: PathBuf::from("project.tar.gz"),
+                    dest: Some(PathBuf::from("restored")),
                 },
                 &mut current_dir,
             )
             .await;
-
+        assert!(
+            matches!(extracted, FenrisOutput::Success { .. }),
+            "{extracted:?}"
+        );
         assert_eq!(
-            read_output,
-            FenrisOutput::ObjectContent {
-                data,
-                total_size: 13,
-                truncated: false
-            }
+            std::fs::read(dir.path().join("restored/project/src/main.rs")).unwrap(),
+            b"fn main() {}"
         );
     }
 
     #[tokio::test]
-    async fn test_append_file() {
-        let (handler, ops) = create_handler();
+    async fn test_env_is_kept_per_client() {
+        let handler = RequestHandler::new(Arc::new(MemoryStorage::new()));
         let mut current_dir = PathBuf::from("/");
+        let get = FenrisCommand::GetEnv {
+            name: "BUILD_ID".to_string(),
+        };
 
-        ops.put_object(Path::new("/log.txt"), b"Init")
-            .await
-            .unwrap();
-
-        let output = handler
+        let set = handler
             .process_command(
                 1,
-                &FenrisCommand::AppendObject {
-                    path: PathBuf::from("log.txt"),
-                    data: b" - More".to_vec(),
+                &FenrisCommand::SetEnv {
+                    name: "BUILD_ID".to_string(),
+                    value: "r-1042".to_string(),
                 },
                 &mut current_dir,
             )
             .await;
+        assert!(matches!(set, FenrisOutput::Success { .. }));
+        assert_eq!(
+            handler.process_command(1, &get, &mut current_dir).await,
+            FenrisOutput::EnvValue {
+                value: "r-1042".to_string(),
+            }
+        );
+        assert!(matches!(
+            handler.process_command(2, &get, &mut current_dir).await,
+            FenrisOutput::Error { message } if message.contains("BUILD_ID is not set")
+        ));
 
-        assert!(matches!(output, FenrisOutput::Success { .. }));
-
-        let content = ops.get_object(Path::new("/log.txt")).await.unwrap();
-        assert_eq!(content, b"Init - More");
+        let invalid = handler
+            .process_command(
+                1,
+                &FenrisCommand::SetEnv {
+                    name: "BUILD-ID".to_string(),
+                    value: "x".to_string(),
+                },
+                &mut current_dir,
+            )
+            .await;
+        assert!(matches!(
+            invalid,
+            FenrisOutput::Error { message } if message.contains("Invalid variable name")
+        ));
     }
 
     #[tokio::test]
-    async fn test_append_file_uses_storage_create_semantics() {
-        let (handler, ops) = create_handler();
+    async fn test_xattrs_are_rejected_by_memory_storage() {
+        let handler = RequestHandler::new(Arc::new(MemoryStorage::new()));
         let mut current_dir = PathBuf::from("/");
 
         let output = handler
             .process_command(
                 1,
-                &FenrisCommand::AppendObject {
-                    path: PathBuf::from("created.log"),
-                    data: b"Created by append".to_vec(),
+                &FenrisCommand::GetXattr {
+                    path: PathBuf::from("photo.jpg"),
+                    name: "user.origin".to_string(),
                 },
                 &mut current_dir,
             )
             .await;
 
-        assert!(matches!(output, FenrisOutput::Success { .. }));
-
-        let content = ops.get_object(Path::new("/created.log")).await.unwrap();
-        assert_eq!(content, b"Created by append");
+        assert!(matches!(
+            output,
+            FenrisOutput::Error { message } if message.contains("xattrs are not supported")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_set_mtime_updates_file_info() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("restored.txt"), b"backup").unwrap();
+        let handler = RequestHandler::new(Arc::new(common::TokioFsStorage::new(
+            dir.path().to_path_buf(),
+        )));
+        let mut current_dir = PathBuf::from("/");
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::SetMtime {
+                    path: PathBuf::from("restored.txt"),
+                    mtime: 1_600_000_000,
+                },
+                &mut current_dir,
+            )
+            .await;
+        assert!(matches!(output, FenrisOutput::Success { .. }));
+
+        let info = handler
+            .process_command(
+                1,
+                &FenrisCommand::ObjectInfo {
+                    path: PathBuf::from("restored.txt"),
+                    detect_mime: false,
+                },
+                &mut current_dir,
+            )
+            .await;
+        assert!(matches!(
+            info,
+            FenrisOutput::ObjectInfo { metadata, .. } if metadata.modified_time == 1_600_000_000
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_symlinks_are_rejected_by_memory_storage() {
+        let (handler, _) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::ReadSymlink {
+                    link: PathBuf::from("latest"),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert!(matches!(output, FenrisOutput::Error { .. }));
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_every_request_emits_a_structured_event() {
+        let (handler, _) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+
+        handler
+            .process_command(7, &FenrisCommand::Ping, &mut current_dir)
+            .await;
+        handler
+            .process_command(
+                7,
+                &FenrisCommand::WriteObject {
+                    path: PathBuf::from("notes.txt"),
+                    data: b"hello".to_vec(),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert!(logs_contain("client_id=7 command=\"PING\" success=true"));
+        assert!(logs_contain("path=/notes.txt bytes=5"));
+        assert!(logs_contain("command=\"WRITE_FILE\" success=true"));
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_handler_spans_record_the_resolved_path() {
+        let (handler, _) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+
+        handler
+            .process_command(
+                3,
+                &FenrisCommand::UploadObject {
+                    path: PathBuf::from("report.txt"),
+                    data: b"abc".to_vec(),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert!(logs_contain(
+            "process_command{client_id=3 command=\"UPLOAD_FILE\"}"
+        ));
+        assert!(logs_contain("resolved_path=\"/report.txt\" bytes=3"));
+    }
+
+    #[tokio::test]
+    async fn test_create_file() {
+        let (handler, ops) = create_handler();
+        let mut current_dir = PathBuf::from("/home");
+        ops.create_namespace(&current_dir).await.unwrap();
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::CreateObject {
+                    path: PathBuf::from("test.txt"),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert!(matches!(output, FenrisOutput::Success { .. }));
+
+        assert!(ops.exists(Path::new("/home/test.txt")).await);
+    }
+
+    #[tokio::test]
+    async fn test_write_and_read_file() {
+        let (handler, _) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+
+        let data = b"Hello, World!".to_vec();
+        let write_output = handler
+            .process_command(
+                1,
+                &FenrisCommand::WriteObject {
+                    path: PathBuf::from("hello.txt"),
+                    data: data.clone(),
+                },
+                &mut current_dir,
+            )
+            .await;
+        assert!(matches!(write_output, FenrisOutput::Success { .. }));
+
+        let read_output = handler
+            .process_command(
+                1,
+                &FenrisCommand::ReadObject {
+                    path: PathBuf::from("hello.txt"),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert_eq!(
+            read_output,
+            FenrisOutput::ObjectContent {
+                data,
+                total_size: 13,
+                truncated: false
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_append_file() {
+        let (handler, ops) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+
+        ops.put_object(Path::new("/log.txt"), b"Init")
+            .await
+            .unwrap();
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::AppendObject {
+                    path: PathBuf::from("log.txt"),
+                    data: b" - More".to_vec(),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert!(matches!(output, FenrisOutput::Success { .. }));
+
+        let content = ops.get_object(Path::new("/log.txt")).await.unwrap();
+        assert_eq!(content, b"Init - More");
+    }
+
+    #[tokio::test]
+    async fn test_append_file_uses_storage_create_semantics() {
+        let (handler, ops) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::AppendObject {
+                    path: PathBuf::from("created.log"),
+                    data: b"Created by append".to_vec(),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert!(matches!(output, FenrisOutput::Success { .. }));
+
+        let content = ops.get_object(Path::new("/created.log")).await.unwrap();
+        assert_eq!(content, b"Created by append");
     }
 
     #[tokio::test]
@@ -569,12 +1990,13 @@ mod tests {
                 1,
                 &FenrisCommand::ListNamespace {
                     path: PathBuf::from("data"),
+                    sort: ListSort::default(),
                 },
                 &mut current_dir,
             )
             .await;
 
-        let FenrisOutput::NamespaceListing { entries } = output else {
+        let FenrisOutput::NamespaceListing { entries, .. } = output else {
             panic!("Expected namespace listing");
         };
 
@@ -585,78 +2007,355 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_create_and_delete_dir() {
-        let (handler, ops) = create_handler();
+    async fn test_list_dir_with_filter() {
+        let (handler, storage) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        storage.create_namespace(Path::new("/mix")).await.unwrap();
+        storage
+            .create_namespace(Path::new("/mix/sub"))
+            .await
+            .unwrap();
+        storage
+            .put_object(Path::new("/mix/small.log"), &[0; 100])
+            .await
+            .unwrap();
+        storage
+            .put_object(Path::new("/mix/big.log"), &[0; 2048])
+            .await
+            .unwrap();
+        storage
+            .put_object(Path::new("/mix/big.bin"), &[0; 4096])
+            .await
+            .unwrap();
+
+        let mut list = async |filter: DirFilter| {
+            let output = handler
+                .process_command(
+                    1,
+                    &FenrisCommand::ListNamespaceFiltered {
+                        path: PathBuf::from("mix"),
+                        filter,
+                    },
+                    &mut current_dir,
+                )
+                .await;
+            let FenrisOutput::NamespaceListing { entries, .. } = output else {
+                panic!("Expected namespace listing");
+            };
+            let mut names: Vec<_> = entries.into_iter().map(|entry| entry.name).collect();
+            names.sort();
+            names
+        };
+
+        let files_only = DirFilter {
+            file_types: DirFilter::FILES,
+            ..DirFilter::default()
+        };
+        assert_eq!(
+            list(files_only).await,
+            vec!["big.bin", "big.log", "small.log"]
+        );
+
+        let at_least_1k = DirFilter {
+            min_size: Some(1024),
+            ..DirFilter::default()
+        };
+        assert!(!list(at_least_1k).await.contains(&"small.log".to_string()));
+
+        let big_logs = DirFilter {
+            min_size: Some(1024),
+            max_size: Some(3000),
+            file_types: DirFilter::FILES,
+            name_pattern: Some("*.log".to_string()),
+        };
+        assert_eq!(list(big_logs).await, vec!["big.log"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_dir_sorted_by_name() {
+        let (handler, storage) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        storage.create_namespace(Path::new("/mix")).await.unwrap();
+        for file in ["/mix/b.txt", "/mix/d.txt", "/mix/a.txt"] {
+            storage.put_object(Path::new(file), b"").await.unwrap();
+        }
+        storage.create_namespace(Path::new("/mix/c")).await.unwrap();
+
+        let mut list = async |sort: ListSort| {
+            let output = handler
+                .process_command(
+                    1,
+                    &FenrisCommand::ListNamespace {
+                        path: PathBuf::from("mix"),
+                        sort,
+                    },
+                    &mut current_dir,
+                )
+                .await;
+            let FenrisOutput::NamespaceListing { entries, .. } = output else {
+                panic!("Expected namespace listing");
+            };
+            entries
+                .into_iter()
+                .map(|entry| entry.name)
+                .collect::<Vec<_>>()
+        };
+
+        let by_name = ListSort {
+            key: Some(ListSortKey::Name),
+            ..ListSort::default()
+        };
+        assert_eq!(list(by_name).await, ["a.txt", "b.txt", "c", "d.txt"]);
+
+        let reversed = ListSort {
+            reverse: true,
+            ..by_name
+        };
+        assert_eq!(list(reversed).await, ["d.txt", "c", "b.txt", "a.txt"]);
+
+        let dirs_first = ListSort {
+            dirs_first: true,
+            ..by_name
+        };
+        assert_eq!(list(dirs_first).await, ["c", "a.txt", "b.txt", "d.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_dir_recursive() {
+        let (handler, storage) = create_handler();
         let mut current_dir = PathBuf::from("/");
+        storage.create_namespace(Path::new("/docs")).await.unwrap();
+        storage
+            .put_object(Path::new("/docs/a.txt"), b"a")
+            .await
+            .unwrap();
 
         let output = handler
             .process_command(
                 1,
-                &FenrisCommand::CreateNamespace {
-                    path: PathBuf::from("newdir"),
+                &FenrisCommand::ListNamespaceRecursive {
+                    path: PathBuf::from("."),
+                    max_depth: 0,
                 },
                 &mut current_dir,
             )
             .await;
-        assert!(matches!(output, FenrisOutput::Success { .. }));
-        assert!(ops.is_namespace(Path::new("/newdir")).await);
+
+        let FenrisOutput::RecursiveNamespaceListing { entries } = output else {
+            panic!("unexpected output: {:?}", output);
+        };
+        let paths: Vec<PathBuf> = entries.into_iter().map(|(path, _)| path).collect();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("docs"), PathBuf::from("docs/a.txt")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multi_op_runs_every_command_in_order() {
+        let (handler, ops) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        let commands = vec![
+            FenrisCommand::WriteObject {
+                path: PathBuf::from("batch.txt"),
+                data: b"batched".to_vec(),
+            },
+            FenrisCommand::ReadObject {
+                path: PathBuf::from("missing.txt"),
+            },
+            FenrisCommand::ReadObject {
+                path: PathBuf::from("batch.txt"),
+            },
+            FenrisCommand::DeleteObject {
+                path: PathBuf::from("batch.txt"),
+            },
+        ];
 
         let output = handler
             .process_command(
                 1,
-                &FenrisCommand::DeleteNamespace {
-                    path: PathBuf::from("newdir"),
+                &FenrisCommand::MultiOp {
+                    commands: commands.clone(),
+                    fail_fast: false,
                 },
                 &mut current_dir,
             )
             .await;
-        assert!(matches!(output, FenrisOutput::Success { .. }));
-        assert!(!ops.is_namespace(Path::new("/newdir")).await);
-    }
-
-    #[tokio::test]
-    async fn test_file_info() {
-        let (handler, ops) = create_handler();
-        let mut current_dir = PathBuf::from("/");
-        ops.put_object(Path::new("/info.txt"), b"").await.unwrap();
+        let FenrisOutput::MultiOp { outputs } = output else {
+            panic!("expected a multi-op result, got {:?}", output);
+        };
+        assert_eq!(outputs.len(), 4);
+        assert!(matches!(outputs[0], FenrisOutput::Success { .. }));
+        assert!(matches!(outputs[1], FenrisOutput::Error { .. }));
+        assert!(matches!(
+            &outputs[2],
+            FenrisOutput::ObjectContent { data, .. } if data == b"batched"
+        ));
+        assert!(matches!(outputs[3], FenrisOutput::Success { .. }));
+        assert!(!ops.exists(Path::new("/batch.txt")).await);
 
         let output = handler
             .process_command(
                 1,
-                &FenrisCommand::ObjectInfo {
-                    path: PathBuf::from("info.txt"),
+                &FenrisCommand::MultiOp {
+                    commands,
+                    fail_fast: true,
                 },
                 &mut current_dir,
             )
             .await;
-
-        let FenrisOutput::ObjectInfo { metadata } = output else {
-            panic!("Expected object info");
+        let FenrisOutput::MultiOp { outputs } = output else {
+            panic!("expected a multi-op result, got {:?}", output);
         };
+        assert_eq!(outputs.len(), 2);
+        assert!(ops.exists(Path::new("/batch.txt")).await);
 
-        assert_eq!(metadata.name, "info.txt");
-        assert!(!metadata.is_namespace);
+        let nested = FenrisCommand::MultiOp {
+            commands: vec![FenrisCommand::Terminate],
+            fail_fast: false,
+        };
+        let output = handler.process_command(1, &nested, &mut current_dir).await;
+        assert!(matches!(output, FenrisOutput::Error { .. }));
     }
 
     #[tokio::test]
-    async fn test_upload_file() {
+    async fn test_create_and_delete_dir() {
         let (handler, ops) = create_handler();
         let mut current_dir = PathBuf::from("/");
 
-        let data = b"Upload Data".to_vec();
         let output = handler
             .process_command(
                 1,
-                &FenrisCommand::UploadObject {
-                    path: PathBuf::from("upload.dat"),
-                    data: data.clone(),
+                &FenrisCommand::CreateNamespace {
+                    path: PathBuf::from("newdir"),
                 },
                 &mut current_dir,
             )
             .await;
         assert!(matches!(output, FenrisOutput::Success { .. }));
+        assert!(ops.is_namespace(Path::new("/newdir")).await);
 
-        let file_data = ops.get_object(Path::new("/upload.dat")).await.unwrap();
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::DeleteNamespace {
+                    path: PathBuf::from("newdir"),
+                },
+                &mut current_dir,
+            )
+            .await;
+        assert!(matches!(output, FenrisOutput::Success { .. }));
+        assert!(!ops.is_namespace(Path::new("/newdir")).await);
+    }
+
+    #[tokio::test]
+    async fn test_create_dir_all_creates_parents() {
+        let (handler, ops) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+
+        let nested = FenrisCommand::CreateNamespace {
+            path: PathBuf::from("a/b"),
+        };
+        let output = handler.process_command(1, &nested, &mut current_dir).await;
+        assert!(matches!(output, FenrisOutput::Error { .. }));
+
+        let nested = FenrisCommand::CreateNamespaceAll {
+            path: PathBuf::from("a/b"),
+        };
+        for _ in 0..2 {
+            let output = handler.process_command(1, &nested, &mut current_dir).await;
+            assert!(matches!(output, FenrisOutput::Success { .. }));
+        }
+        assert!(ops.is_namespace(Path::new("/a/b")).await);
+
+        let existing = FenrisCommand::CreateNamespace {
+            path: PathBuf::from("a"),
+        };
+        let output = handler
+            .process_command(1, &existing, &mut current_dir)
+            .await;
+        assert!(matches!(output, FenrisOutput::Error { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_file_info() {
+        let (handler, ops) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        ops.put_object(Path::new("/info.txt"), b"").await.unwrap();
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::ObjectInfo {
+                    path: PathBuf::from("info.txt"),
+                    detect_mime: false,
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        let FenrisOutput::ObjectInfo {
+            metadata,
+            mime_type,
+        } = output
+        else {
+            panic!("Expected object info");
+        };
+
+        assert_eq!(metadata.name, "info.txt");
+        assert!(!metadata.is_namespace);
+        assert_eq!(mime_type, None);
+    }
+
+    #[tokio::test]
+    async fn test_file_info_detects_mime_type() {
+        let (handler, ops) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        ops.put_object(Path::new("/photo.jpg"), &[0xff, 0xd8, 0xff, 0xe0, 0, 0x10])
+            .await
+            .unwrap();
+        ops.put_object(Path::new("/notes.txt"), b"hello world")
+            .await
+            .unwrap();
+
+        for (name, expected) in [("photo.jpg", "image/jpeg"), ("notes.txt", "text/plain")] {
+            let output = handler
+                .process_command(
+                    1,
+                    &FenrisCommand::ObjectInfo {
+                        path: PathBuf::from(name),
+                        detect_mime: true,
+                    },
+                    &mut current_dir,
+                )
+                .await;
+
+            assert!(matches!(
+                output,
+                FenrisOutput::ObjectInfo { mime_type: Some(mime), .. } if mime == expected
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_file() {
+        let (handler, ops) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+
+        let data = b"Upload Data".to_vec();
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::UploadObject {
+                    path: PathBuf::from("upload.dat"),
+                    data: data.clone(),
+                },
+                &mut current_dir,
+            )
+            .await;
+        assert!(matches!(output, FenrisOutput::Success { .. }));
+
+        let file_data = ops.get_object(Path::new("/upload.dat")).await.unwrap();
         assert_eq!(file_data, data);
     }
 
@@ -908,6 +2607,31 @@ mod tests {
         assert_eq!(output, FenrisOutput::Terminated);
     }
 
+    #[tokio::test]
+    async fn test_correlated_command_echoes_request_id() {
+        let (handler, _) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::Correlated {
+                    request_id: 42,
+                    command: Box::new(FenrisCommand::Ping),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert_eq!(
+            output,
+            FenrisOutput::Correlated {
+                request_id: 42,
+                output: Box::new(FenrisOutput::Pong),
+            }
+        );
+    }
+
     #[tokio::test]
     async fn test_missing_object_returns_error_output() {
         let (handler, _) = create_handler();
@@ -925,4 +2649,964 @@ mod tests {
 
         assert!(matches!(output, FenrisOutput::Error { .. }));
     }
+
+    #[tokio::test]
+    async fn test_subscribe_receives_events_for_changes() {
+        let (handler, ops) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        ops.create_namespace(Path::new("/docs")).await.unwrap();
+        let mut events = handler.subscriptions().register_client(1);
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::Subscribe {
+                    path: PathBuf::from("docs"),
+                },
+                &mut current_dir,
+            )
+            .await;
+        assert_eq!(
+            output,
+            FenrisOutput::Success {
+                message: "/docs".to_string()
+            }
+        );
+
+        for command in [
+            FenrisCommand::CreateObject {
+                path: PathBuf::from("docs/a.txt"),
+            },
+            FenrisCommand::AppendObject {
+                path: PathBuf::from("docs/a.txt"),
+                data: b"x".to_vec(),
+            },
+            FenrisCommand::DeleteObject {
+                path: PathBuf::from("docs/a.txt"),
+            },
+        ] {
+            handler.process_command(2, &command, &mut current_dir).await;
+        }
+
+        let kinds: Vec<_> = std::iter::from_fn(|| events.try_recv().ok())
+            .map(|output| match output {
+                FenrisOutput::WatchEvent(event) => {
+                    assert_eq!(event.path, PathBuf::from("/docs/a.txt"));
+                    event.kind
+                }
+                other => panic!("unexpected output: {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                WatchEventKind::Created,
+                WatchEventKind::Modified,
+                WatchEventKind::Deleted
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_users_share_storage_per_login_but_not_watch_events() {
+        let dir = tempfile::tempdir().unwrap();
+        for user in ["alice", "bob"] {
+            std::fs::create_dir_all(dir.path().join(user).join("docs")).unwrap();
+        }
+        let file_ops = common::DefaultFileOperations::new(dir.path().to_path_buf())
+            .await
+            .unwrap();
+        let handler =
+            RequestHandler::new(Arc::new(common::TokioFsStorage::with_file_ops(file_ops)));
+        let alice = handler
+            .rebased(dir.path().join("alice"), "alice")
+            .await
+            .unwrap();
+        let alice_again = handler
+            .rebased(dir.path().join("alice"), "alice")
+            .await
+            .unwrap();
+        let bob = handler
+            .rebased(dir.path().join("bob"), "bob")
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&alice.storage, &alice_again.storage));
+        assert!(!Arc::ptr_eq(&alice.storage, &bob.storage));
+
+        let mut alice_events = handler.subscriptions().register_client(1);
+        let mut bob_events = handler.subscriptions().register_client(2);
+        let mut current_dir = PathBuf::from("/");
+        let subscribe = FenrisCommand::Subscribe {
+            path: PathBuf::from("docs"),
+        };
+        alice.process_command(1, &subscribe, &mut current_dir).await;
+        bob.process_command(2, &subscribe, &mut current_dir).await;
+
+        let create = FenrisCommand::CreateObject {
+            path: PathBuf::from("docs/a.txt"),
+        };
+        alice_again
+            .process_command(3, &create, &mut current_dir)
+            .await;
+
+        assert!(matches!(
+            alice_events.try_recv().unwrap(),
+            FenrisOutput::WatchEvent(event) if event.path == Path::new("/docs/a.txt")
+        ));
+        assert!(bob_events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_rejects_missing_path() {
+        let (handler, _) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::Subscribe {
+                    path: PathBuf::from("missing"),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert!(matches!(output, FenrisOutput::Error { .. }));
+    }
+
+    async fn serve_http_once(response: &'static [u8]) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await.unwrap();
+            stream.write_all(response).await.unwrap();
+        });
+
+        format!("http://{}/file", addr)
+    }
+
+    fn fetch_handler(config: ServerConfig) -> (RequestHandler<MemoryStorage>, Arc<MemoryStorage>) {
+        let storage = Arc::new(MemoryStorage::new());
+        let handler = RequestHandler::with_config(storage.clone(), Arc::new(config));
+        (handler, storage)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_url_disabled_by_default() {
+        let (handler, _) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::FetchUrl {
+                    url: "http://127.0.0.1:9/file".to_string(),
+                    path: PathBuf::from("file"),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert!(matches!(
+            output,
+            FenrisOutput::Error { message } if message.contains("FetchUrl disabled")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_url_downloads_to_storage() {
+        let (handler, storage) = fetch_handler(
+            ServerConfig::builder()
+                .allow_fetch_url(true)
+                .build()
+                .unwrap(),
+        );
+        let mut current_dir = PathBuf::from("/");
+        let url = serve_http_once(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+        )
+        .await;
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::FetchUrl {
+                    url,
+                    path: PathBuf::from("fetched.txt"),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert!(matches!(
+            output,
+            FenrisOutput::Success { message } if message.starts_with("Fetched 5 bytes")
+        ));
+        assert_eq!(
+            storage.get_object(Path::new("/fetched.txt")).await.unwrap(),
+            b"hello"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_url_rejects_error_status_and_oversized_body() {
+        let config = ServerConfig::builder()
+            .allow_fetch_url(true)
+            .max_file_size(4)
+            .build()
+            .unwrap();
+        let (handler, storage) = fetch_handler(config);
+        let mut current_dir = PathBuf::from("/");
+
+        for response in [
+            &b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"[..],
+            &b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello"[..],
+        ] {
+            let url = serve_http_once(response).await;
+            let output = handler
+                .process_command(
+                    1,
+                    &FenrisCommand::FetchUrl {
+                        url,
+                        path: PathBuf::from("fetched.txt"),
+                    },
+                    &mut current_dir,
+                )
+                .await;
+
+            assert!(matches!(output, FenrisOutput::Error { .. }));
+        }
+
+        assert!(!storage.exists(Path::new("/fetched.txt")).await);
+    }
+
+    #[tokio::test]
+    async fn test_failed_fetch_keeps_the_existing_file() {
+        let config = ServerConfig::builder()
+            .allow_fetch_url(true)
+            .max_file_size(4)
+            .build()
+            .unwrap();
+        let (handler, storage) = fetch_handler(config);
+        storage
+            .put_object(Path::new("/fetched.txt"), b"old")
+            .await
+            .unwrap();
+        let mut current_dir = PathBuf::from("/");
+        // Without a Content-Length the size limit can only trip mid-download.
+        let url = serve_http_once(b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nhello").await;
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::FetchUrl {
+                    url,
+                    path: PathBuf::from("fetched.txt"),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert!(matches!(
+            output,
+            FenrisOutput::Error { message } if message.contains("too large")
+        ));
+        assert_eq!(
+            storage.get_object(Path::new("/fetched.txt")).await.unwrap(),
+            b"old"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_url_rejects_non_http_scheme() {
+        let (handler, _) = fetch_handler(
+            ServerConfig::builder()
+                .allow_fetch_url(true)
+                .build()
+                .unwrap(),
+        );
+        let mut current_dir = PathBuf::from("/");
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::FetchUrl {
+                    url: "file:///etc/passwd".to_string(),
+                    path: PathBuf::from("passwd"),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert!(matches!(
+            output,
+            FenrisOutput::Error { message } if message.contains("Unsupported URL scheme")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_large_read_is_truncated_to_streaming_threshold() {
+        let config = ServerConfig::builder()
+            .streaming_threshold(4)
+            .build()
+            .unwrap();
+        let storage = Arc::new(MemoryStorage::new());
+        let handler = RequestHandler::with_config(storage.clone(), Arc::new(config));
+        let mut current_dir = PathBuf::from("/");
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::WriteObject {
+                    path: PathBuf::from("big.txt"),
+                    data: b"0123456789".to_vec(),
+                },
+                &mut current_dir,
+            )
+            .await;
+        assert!(matches!(output, FenrisOutput::Success { .. }));
+        assert_eq!(
+            storage.get_object(Path::new("/big.txt")).await.unwrap(),
+            b"0123456789"
+        );
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::ReadObject {
+                    path: PathBuf::from("big.txt"),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert_eq!(
+            output,
+            FenrisOutput::ObjectContent {
+                data: b"0123".to_vec(),
+                total_size: 10,
+                truncated: true,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_diff_objects_returns_unified_diff() {
+        let (handler, storage) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        storage
+            .put_object(Path::new("/a.conf"), b"port=1\nhost=a\n")
+            .await
+            .unwrap();
+        storage
+            .put_object(Path::new("/b.conf"), b"port=2\nhost=a\n")
+            .await
+            .unwrap();
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::DiffObjects {
+                    left: PathBuf::from("a.conf"),
+                    right: PathBuf::from("b.conf"),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        let FenrisOutput::ObjectDiff { diff } = output else {
+            panic!("unexpected output: {:?}", output);
+        };
+        assert!(diff.starts_with("--- /a.conf\n+++ /b.conf\n"));
+        assert!(diff.contains("-port=1\n+port=2\n host=a\n"));
+    }
+
+    #[tokio::test]
+    async fn test_diff_objects_falls_back_to_hex_for_binary_data() {
+        let (handler, storage) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        storage
+            .put_object(Path::new("/a.bin"), &[0xff, 0x00])
+            .await
+            .unwrap();
+        storage
+            .put_object(Path::new("/b.bin"), &[0xff, 0x01])
+            .await
+            .unwrap();
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::DiffObjects {
+                    left: PathBuf::from("a.bin"),
+                    right: PathBuf::from("b.bin"),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        let FenrisOutput::ObjectDiff { diff } = output else {
+            panic!("unexpected output: {:?}", output);
+        };
+        assert!(diff.contains("-00000000: ff 00\n+00000000: ff 01\n"));
+    }
+
+    #[tokio::test]
+    async fn test_diff_objects_rejects_files_over_the_streaming_threshold() {
+        let config = ServerConfig::builder()
+            .streaming_threshold(4)
+            .build()
+            .unwrap();
+        let storage = Arc::new(MemoryStorage::new());
+        let handler = RequestHandler::with_config(storage.clone(), Arc::new(config));
+        let mut current_dir = PathBuf::from("/");
+        storage
+            .put_object(Path::new("/small.txt"), b"abc")
+            .await
+            .unwrap();
+        storage
+            .put_object(Path::new("/big.txt"), b"0123456789")
+            .await
+            .unwrap();
+
+        for (left, right) in [("small.txt", "big.txt"), ("big.txt", "small.txt")] {
+            let output = handler
+                .process_command(
+                    1,
+                    &FenrisCommand::DiffObjects {
+                        left: PathBuf::from(left),
+                        right: PathBuf::from(right),
+                    },
+                    &mut current_dir,
+                )
+                .await;
+
+            let FenrisOutput::Error { message } = output else {
+                panic!("unexpected output: {:?}", output);
+            };
+            assert!(message.contains("/big.txt is 10 bytes"), "{}", message);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checksum_manifest_covers_nested_objects() {
+        let (handler, storage) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        storage
+            .create_namespace_all(Path::new("/photos/2024"))
+            .await
+            .unwrap();
+        storage
+            .put_object(Path::new("/photos/2024/beach.jpg"), b"abc")
+            .await
+            .unwrap();
+        storage
+            .put_object(Path::new("/photos/index.txt"), b"")
+            .await
+            .unwrap();
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::ChecksumManifest {
+                    path: PathBuf::from("photos"),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        let FenrisOutput::ChecksumManifest { entries, truncated } = output else {
+            panic!("unexpected output: {:?}", output);
+        };
+        assert!(!truncated);
+        let paths: Vec<_> = entries.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("2024/beach.jpg"), PathBuf::from("index.txt")]
+        );
+        assert_eq!(entries[0].1[..2], [0xba, 0x78]);
+    }
+
+    #[tokio::test]
+    async fn test_checksum_object_returns_sha256_digest() {
+        let (handler, storage) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        storage
+            .put_object(Path::new("/abc.txt"), b"abc")
+            .await
+            .unwrap();
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::ChecksumObject {
+                    path: PathBuf::from("abc.txt"),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        let FenrisOutput::ObjectChecksum { digest } = output else {
+            panic!("unexpected output: {:?}", output);
+        };
+        let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        assert_eq!(
+            hex,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_requires_psk_mode() {
+        let broadcast = FenrisCommand::Broadcast {
+            message: "maintenance".to_string(),
+        };
+        let mut current_dir = PathBuf::from("/");
+
+        let (handler, _) = create_handler();
+        let output = handler
+            .process_command(1, &broadcast, &mut current_dir)
+            .await;
+        assert!(matches!(
+            output,
+            FenrisOutput::Error { message } if message.contains("PSK")
+        ));
+
+        let config = ServerConfig::builder()
+            .require_psk(Some("secret".to_string()))
+            .build()
+            .unwrap();
+        let handler = RequestHandler::with_config(Arc::new(MemoryStorage::new()), Arc::new(config));
+        let mut events = handler.subscriptions().register_client(2);
+        handler.clients().register(1, "127.0.0.1:3000".to_string());
+        let output = handler
+            .process_command(1, &broadcast, &mut current_dir)
+            .await;
+        assert!(matches!(
+            output,
+            FenrisOutput::Error { message } if message.contains("PSK")
+        ));
+        assert!(events.try_recv().is_err());
+
+        handler.clients().set_psk_authenticated(1);
+        let output = handler
+            .process_command(1, &broadcast, &mut current_dir)
+            .await;
+        assert!(matches!(output, FenrisOutput::Success { .. }));
+        assert_eq!(
+            events.try_recv().unwrap(),
+            FenrisOutput::Broadcast {
+                message: "maintenance".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quota_reports_usage_and_sets_limits_in_psk_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let quotas = Arc::new(common::QuotaManager::new(None));
+        let file_ops = common::DefaultFileOperations::new_with_quota(
+            dir.path().to_path_buf(),
+            Arc::clone(&quotas),
+        )
+        .await
+        .unwrap();
+        let storage = Arc::new(common::TokioFsStorage::with_file_ops(file_ops));
+        storage
+            .put_object(Path::new("data.bin"), &[0; 100])
+            .await
+            .unwrap();
+        let mut current_dir = PathBuf::from("/");
+        let set = FenrisCommand::Quota { limit: Some(150) };
+
+        let handler = RequestHandler::new(Arc::clone(&storage));
+        let output = handler
+            .process_command(1, &FenrisCommand::Quota { limit: None }, &mut current_dir)
+            .await;
+        assert_eq!(
+            output,
+            FenrisOutput::QuotaInfo {
+                used_bytes: 100,
+                limit_bytes: None
+            }
+        );
+        let output = handler.process_command(1, &set, &mut current_dir).await;
+        assert!(matches!(output, FenrisOutput::Error { message } if message.contains("PSK")));
+
+        let config = ServerConfig::builder()
+            .require_psk(Some("secret".to_string()))
+            .build()
+            .unwrap();
+        let handler = RequestHandler::with_config(storage, Arc::new(config));
+        handler.clients().register(1, "127.0.0.1:3000".to_string());
+        handler.clients().set_psk_authenticated(1);
+        let output = handler.process_command(1, &set, &mut current_dir).await;
+        assert_eq!(
+            output,
+            FenrisOutput::QuotaInfo {
+                used_bytes: 100,
+                limit_bytes: Some(150)
+            }
+        );
+        assert_eq!(quotas.limit(common::ANONYMOUS_ACCOUNT), Some(150));
+
+        let (handler, _) = create_handler();
+        let output = handler
+            .process_command(1, &FenrisCommand::Quota { limit: None }, &mut current_dir)
+            .await;
+        assert!(matches!(output, FenrisOutput::Error { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_logged_in_users_cannot_set_their_quota() {
+        let dir = tempfile::tempdir().unwrap();
+        let quotas = Arc::new(common::QuotaManager::new(Some(100)));
+        let file_ops = common::DefaultFileOperations::new_with_quota(
+            dir.path().to_path_buf(),
+            Arc::clone(&quotas),
+        )
+        .await
+        .unwrap();
+        let config = ServerConfig::builder()
+            .require_psk(Some("secret".to_string()))
+            .build()
+            .unwrap();
+        let handler = RequestHandler::with_config(
+            Arc::new(common::TokioFsStorage::with_file_ops(file_ops)),
+            Arc::new(config),
+        );
+        handler.clients().register(1, "127.0.0.1:3000".to_string());
+        handler.clients().set_psk_authenticated(1);
+        handler.clients().set_username(1, "alice");
+        let mut current_dir = PathBuf::from("/");
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::Quota {
+                    limit: Some(1 << 30),
+                },
+                &mut current_dir,
+            )
+            .await;
+        assert!(matches!(
+            output,
+            FenrisOutput::Error { message } if message.contains("admin")
+        ));
+        assert_eq!(quotas.limit(common::ANONYMOUS_ACCOUNT), Some(100));
+
+        let output = handler
+            .process_command(1, &FenrisCommand::Quota { limit: None }, &mut current_dir)
+            .await;
+        assert_eq!(
+            output,
+            FenrisOutput::QuotaInfo {
+                used_bytes: 0,
+                limit_bytes: Some(100)
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compress_and_decompress_round_trip() {
+        let (handler, storage) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        let log = b"GET /index.html 200\n".repeat(64);
+        storage
+            .put_object(Path::new("/app.log"), &log)
+            .await
+            .unwrap();
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::CompressObject {
+                    path: PathBuf::from("app.log"),
+                    extension: None,
+                },
+                &mut current_dir,
+            )
+            .await;
+        assert!(matches!(
+            output,
+            FenrisOutput::Success { message } if message.contains("1280 ->")
+        ));
+        let compressed = storage
+            .get_object(Path::new("/app.log.zlib"))
+            .await
+            .unwrap();
+        assert!(compressed.len() < log.len());
+
+        storage.delete_object(Path::new("/app.log")).await.unwrap();
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::DecompressObject {
+                    path: PathBuf::from("app.log.zlib"),
+                    extension: None,
+                },
+                &mut current_dir,
+            )
+            .await;
+        assert!(matches!(
+            output,
+            FenrisOutput::Success { message } if message.contains("-> 1280 bytes")
+        ));
+        assert_eq!(
+            storage.get_object(Path::new("/app.log")).await.unwrap(),
+            log
+        );
+    }
+
+    #[tokio::test]
+    async fn test_decompress_rejects_missing_extension() {
+        let (handler, storage) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        storage
+            .put_object(Path::new("/app.log"), b"plain")
+            .await
+            .unwrap();
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::DecompressObject {
+                    path: PathBuf::from("app.log"),
+                    extension: Some("gz".to_string()),
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert!(matches!(
+            output,
+            FenrisOutput::Error { message } if message.contains("does not end with .gz")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_head_returns_first_lines() {
+        let (handler, storage) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        storage
+            .put_object(Path::new("/app.log"), b"one\ntwo\nthree\n")
+            .await
+            .unwrap();
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::HeadObject {
+                    path: PathBuf::from("app.log"),
+                    lines: 2,
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert_eq!(
+            output,
+            FenrisOutput::ObjectContentChunk(TransferChunk {
+                offset: 0,
+                data: b"one\ntwo\n".to_vec(),
+                is_last: false,
+                total_size: 14,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tail_skips_partial_utf8_character() {
+        let (handler, storage) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        storage
+            .put_object(Path::new("/notes.txt"), "café au lait".as_bytes())
+            .await
+            .unwrap();
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::TailObject {
+                    path: PathBuf::from("notes.txt"),
+                    bytes: 9,
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert_eq!(
+            output,
+            FenrisOutput::ObjectContentChunk(TransferChunk {
+                offset: 5,
+                data: b" au lait".to_vec(),
+                is_last: true,
+                total_size: 13,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tail_keeps_binary_bytes() {
+        let (handler, storage) = create_handler();
+        let mut current_dir = PathBuf::from("/");
+        storage
+            .put_object(Path::new("/blob.bin"), &[0, 1, 0x80, 0x81, 2])
+            .await
+            .unwrap();
+
+        let output = handler
+            .process_command(
+                1,
+                &FenrisCommand::TailObject {
+                    path: PathBuf::from("blob.bin"),
+                    bytes: 3,
+                },
+                &mut current_dir,
+            )
+            .await;
+
+        assert!(matches!(
+            output,
+            FenrisOutput::ObjectContentChunk(chunk) if chunk.offset == 2 && chunk.data == [0x80, 0x81, 2]
+        ));
+    }
+
+    struct SlowStorage {
+        inner: MemoryStorage,
+        delay: Duration,
+    }
+
+    #[async_trait::async_trait]
+    impl StorageBackend for SlowStorage {
+        async fn put_object(&self, path: &Path, data: &[u8]) -> Result<()> {
+            self.inner.put_object(path, data).await
+        }
+
+        async fn get_object(&self, path: &Path) -> Result<Vec<u8>> {
+            tokio::time::sleep(self.delay).await;
+            self.inner.get_object(path).await
+        }
+
+        async fn get_object_chunk(
+            &self,
+            path: &Path,
+            offset: u64,
+            max_len: usize,
+        ) -> Result<common::ObjectChunk> {
+            self.inner.get_object_chunk(path, offset, max_len).await
+        }
+
+        async fn append_object(&self, path: &Path, data: &[u8]) -> Result<()> {
+            self.inner.append_object(path, data).await
+        }
+
+        async fn delete_object(&self, path: &Path) -> Result<()> {
+            self.inner.delete_object(path).await
+        }
+
+        async fn metadata(&self, path: &Path) -> Result<common::FenrisMetadata> {
+            self.inner.metadata(path).await
+        }
+
+        async fn create_namespace(&self, path: &Path) -> Result<()> {
+            self.inner.create_namespace(path).await
+        }
+
+        async fn list_namespace(&self, path: &Path) -> Result<Vec<common::FenrisMetadata>> {
+            self.inner.list_namespace(path).await
+        }
+
+        async fn delete_namespace(&self, path: &Path) -> Result<()> {
+            self.inner.delete_namespace(path).await
+        }
+
+        async fn exists(&self, path: &Path) -> bool {
+            self.inner.exists(path).await
+        }
+
+        async fn is_namespace(&self, path: &Path) -> bool {
+            self.inner.is_namespace(path).await
+        }
+
+        async fn is_object(&self, path: &Path) -> bool {
+            self.inner.is_object(path).await
+        }
+    }
+
+    async fn slow_handler(config: ServerConfig) -> RequestHandler<SlowStorage> {
+        let storage = SlowStorage {
+            inner: MemoryStorage::new(),
+            delay: Duration::from_secs(5),
+        };
+        storage
+            .put_object(Path::new("/slow.bin"), b"data")
+            .await
+            .unwrap();
+        RequestHandler::with_config(Arc::new(storage), Arc::new(config))
+    }
+
+    fn timed_read(timeout: Duration) -> FenrisCommand {
+        FenrisCommand::Timed {
+            timeout,
+            command: Box::new(FenrisCommand::ReadObject {
+                path: PathBuf::from("slow.bin"),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timed_request_is_interrupted() {
+        let handler = slow_handler(ServerConfig::default()).await;
+        let mut current_dir = PathBuf::from("/");
+
+        let started = std::time::Instant::now();
+        let output = handler
+            .process_command(1, &timed_read(Duration::from_millis(50)), &mut current_dir)
+            .await;
+
+        assert_eq!(
+            output,
+            FenrisOutput::Error {
+                message: "request timeout after 50ms".to_string()
+            }
+        );
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_is_capped_by_server() {
+        let config = ServerConfig::builder()
+            .max_request_timeout(Duration::from_millis(20))
+            .build()
+            .unwrap();
+        let handler = slow_handler(config).await;
+        let mut current_dir = PathBuf::from("/");
+
+        let output = handler
+            .process_command(1, &timed_read(Duration::from_secs(60)), &mut current_dir)
+            .await;
+
+        assert_eq!(
+            output,
+            FenrisOutput::Error {
+                message: "request timeout after 20ms".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_truncate_diff_caps_output_on_line_boundary() {
+        let diff = "+line\n".repeat(MAX_DIFF_OUTPUT / 4);
+
+        let truncated = truncate_diff(diff);
+
+        assert!(truncated.len() <= MAX_DIFF_OUTPUT + 64);
+        assert!(truncated.ends_with("+line\n... diff truncated at 1 MiB\n"));
+    }
 }
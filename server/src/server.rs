@@ -1,25 +1,99 @@
-use common::{FenrisError, Result, ServerIdentityKey, StorageBackend};
+use common::{FenrisError, Result, ServerIdentityKey, StorageBackend, Transport};
+#[cfg(feature = "tls")]
+use common::{TlsAcceptor, TlsConfig, TlsTransport};
+use socket2::{Domain, Protocol, SockRef, Socket, TcpKeepalive, Type};
+use std::collections::HashMap;
 use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(unix)]
+use tokio::net::UnixListener;
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::Semaphore;
+use tokio::sync::{Semaphore, mpsc};
 use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
+use crate::clients::{ClientInfo, ClientRegistry};
 use crate::config::ServerConfig;
 use crate::connection::Connection;
+use crate::metrics::serve_metrics;
 use crate::request_handler::RequestHandler;
+use crate::stats::CommandStats;
+use crate::subscriptions::SubscriptionManager;
+use crate::users::UserDatabase;
+#[cfg(feature = "websocket")]
+use crate::websocket::serve_websocket;
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+impl Listener {
+    /// Unix peers have no IP address, so they come back without one and skip the allow-list.
+    async fn accept(
+        &self,
+        config: &ServerConfig,
+    ) -> std::io::Result<(Transport, Option<SocketAddr>)> {
+        match self {
+            Self::Tcp(listener) => {
+                let (stream, addr) = listener.accept().await?;
+                if let Err(e) = configure_tcp_stream(&stream, config) {
+                    warn!("Failed to set socket options for {}: {}", addr, e);
+                }
+                Ok((stream.into(), Some(addr)))
+            }
+            #[cfg(unix)]
+            Self::Unix(listener, _) => {
+                let (stream, _) = listener.accept().await?;
+                Ok((stream.into(), None))
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Tcp(listener) => listener
+                .local_addr()
+                .map_or_else(|e| e.to_string(), |addr| addr.to_string()),
+            #[cfg(unix)]
+            Self::Unix(_, path) => format!("unix:{}", path.display()),
+        }
+    }
+
+    /// WebSocket clients share the main listener's interface; Unix servers fall back to
+    /// loopback since there is no interface to share.
+    #[cfg(feature = "websocket")]
+    fn websocket_addr(&self, port: u16) -> SocketAddr {
+        let ip = match self {
+            Self::Tcp(listener) => listener
+                .local_addr()
+                .map_or(std::net::Ipv4Addr::LOCALHOST.into(), |addr| addr.ip()),
+            #[cfg(unix)]
+            Self::Unix(..) => std::net::Ipv4Addr::LOCALHOST.into(),
+        };
+        SocketAddr::new(ip, port)
+    }
+}
 
 pub struct Server<B: StorageBackend> {
-    listener: TcpListener,
+    listener: Listener,
     handler: Arc<RequestHandler<B>>,
     config: Arc<ServerConfig>,
     shutdown: CancellationToken,
     connection_limiter: Arc<Semaphore>,
     next_id: Arc<AtomicU64>,
     identity_key: Option<Arc<ServerIdentityKey>>,
+    metrics_listener: Option<TcpListener>,
+    #[cfg(feature = "websocket")]
+    websocket_listener: Option<TcpListener>,
+    #[cfg(feature = "tls")]
+    tls_acceptor: Option<TlsAcceptor>,
 }
 
 impl<B: StorageBackend> Server<B> {
@@ -40,45 +114,157 @@ impl<B: StorageBackend> Server<B> {
         Self::bind_with_identity(addr, storage, config, Some(identity_key)).await
     }
 
+    #[cfg(unix)]
+    pub async fn bind_unix(
+        socket_path: &Path,
+        storage: Arc<B>,
+        config: ServerConfig,
+    ) -> Result<(Self, ServerHandle)> {
+        let listener = bind_unix_listener(socket_path)?;
+        Self::with_listener(listener, storage, config, None).await
+    }
+
+    #[cfg(unix)]
+    pub async fn bind_unix_authenticated(
+        socket_path: &Path,
+        storage: Arc<B>,
+        identity_key: Arc<ServerIdentityKey>,
+        config: ServerConfig,
+    ) -> Result<(Self, ServerHandle)> {
+        let listener = bind_unix_listener(socket_path)?;
+        Self::with_listener(listener, storage, config, Some(identity_key)).await
+    }
+
     async fn bind_with_identity(
         addr: &str,
         storage: Arc<B>,
         config: ServerConfig,
         identity_key: Option<Arc<ServerIdentityKey>>,
     ) -> Result<(Self, ServerHandle)> {
-        let listener = TcpListener::bind(addr)
-            .await
-            .map_err(FenrisError::NetworkError)?;
+        let listener = Listener::Tcp(bind_listener(addr, &config).await?);
+        Self::with_listener(listener, storage, config, identity_key).await
+    }
+
+    async fn with_listener(
+        listener: Listener,
+        storage: Arc<B>,
+        config: ServerConfig,
+        identity_key: Option<Arc<ServerIdentityKey>>,
+    ) -> Result<(Self, ServerHandle)> {
+        let metrics_listener = match config.metrics_addr {
+            Some(metrics_addr) => Some(
+                TcpListener::bind(metrics_addr)
+                    .await
+                    .map_err(FenrisError::NetworkError)?,
+            ),
+            None => None,
+        };
+        #[cfg(feature = "websocket")]
+        let websocket_listener = match config.websocket_port {
+            Some(port) => Some(
+                TcpListener::bind(listener.websocket_addr(port))
+                    .await
+                    .map_err(FenrisError::NetworkError)?,
+            ),
+            None => None,
+        };
+        #[cfg(feature = "tls")]
+        let tls_acceptor = config.tls.as_ref().map(TlsConfig::acceptor).transpose()?;
 
         let config = Arc::new(config);
+        let mut handler = RequestHandler::with_config(Arc::clone(&storage), Arc::clone(&config));
+        if let Some(path) = &config.users_file {
+            let users = UserDatabase::load(path)?;
+            info!("Loaded {} users from {}", users.len(), path.display());
+            handler = handler.with_users(Arc::new(users));
+        }
+
         let shutdown = CancellationToken::new();
         let connection_limiter = Arc::new(Semaphore::new(config.max_connections));
 
+        let handle_storage: Arc<dyn StorageBackend> = storage.clone();
         let server = Self {
             listener,
-            handler: Arc::new(RequestHandler::new(storage)),
+            handler: Arc::new(handler),
             config,
             shutdown: shutdown.clone(),
             connection_limiter,
             next_id: Arc::new(AtomicU64::new(1)),
             identity_key,
+            metrics_listener,
+            #[cfg(feature = "websocket")]
+            websocket_listener,
+            #[cfg(feature = "tls")]
+            tls_acceptor,
         };
 
         let handle = ServerHandle {
             shutdown: shutdown.clone(),
+            subscriptions: Arc::clone(server.handler.subscriptions()),
+            clients: Arc::clone(server.handler.clients()),
+            stats: Arc::clone(server.handler.stats()),
+            storage: handle_storage,
         };
 
         Ok((server, handle))
     }
 
     pub fn local_addr(&self) -> Result<SocketAddr> {
-        self.listener
-            .local_addr()
-            .map_err(FenrisError::NetworkError)
+        match &self.listener {
+            Listener::Tcp(listener) => listener.local_addr().map_err(FenrisError::NetworkError),
+            #[cfg(unix)]
+            Listener::Unix(..) => Err(FenrisError::NetworkError(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "server is listening on a unix socket",
+            ))),
+        }
+    }
+
+    /// The TCP address or `unix:<path>` the server accepts connections on.
+    pub fn listening_on(&self) -> String {
+        self.listener.describe()
+    }
+
+    pub fn metrics_addr(&self) -> Option<SocketAddr> {
+        self.metrics_listener
+            .as_ref()
+            .and_then(|listener| listener.local_addr().ok())
     }
 
-    pub async fn run(self) -> Result<()> {
-        info!("Server listening on {}", self.local_addr()?);
+    #[cfg(feature = "websocket")]
+    pub fn websocket_addr(&self) -> Option<SocketAddr> {
+        self.websocket_listener
+            .as_ref()
+            .and_then(|listener| listener.local_addr().ok())
+    }
+
+    pub async fn run(mut self) -> Result<()> {
+        info!("Server listening on {}", self.listening_on());
+
+        if let Some(listener) = self.metrics_listener.take() {
+            info!("Metrics available on {}", listener.local_addr()?);
+            tokio::spawn(serve_metrics(
+                listener,
+                Arc::clone(self.handler.metrics()),
+                self.shutdown.clone(),
+            ));
+        }
+
+        // Upgraded WebSocket connections arrive here; without a WebSocket listener the sender
+        // is dropped straight away and the branch below never fires.
+        let (websocket_tx, mut websocket_rx) =
+            mpsc::channel::<(Transport, SocketAddr)>(self.config.tcp_backlog.max(1) as usize);
+        #[cfg(feature = "websocket")]
+        if let Some(listener) = self.websocket_listener.take() {
+            info!("WebSocket clients accepted on {}", listener.local_addr()?);
+            tokio::spawn(serve_websocket(
+                listener,
+                Arc::clone(&self.config),
+                websocket_tx.clone(),
+                self.shutdown.clone(),
+            ));
+        }
+        drop(websocket_tx);
 
         let mut tasks = JoinSet::new();
 
@@ -95,7 +281,11 @@ impl<B: StorageBackend> Server<B> {
                     }
                 }
 
-                accept_result = self.listener.accept() => {
+                Some((stream, addr)) = websocket_rx.recv() => {
+                    self.spawn_connection(stream, Some(addr), &mut tasks).await;
+                }
+
+                accept_result = self.listener.accept(&self.config) => {
                     match accept_result {
                         Ok((stream, addr)) => {
                             self.spawn_connection(stream, addr, &mut tasks).await;
@@ -117,38 +307,74 @@ impl<B: StorageBackend> Server<B> {
             }
         }
 
+        #[cfg(unix)]
+        if let Listener::Unix(_, path) = &self.listener {
+            let _ = std::fs::remove_file(path);
+        }
+
         info!("Server stopped");
         Ok(())
     }
 
     async fn spawn_connection(
         &self,
-        stream: TcpStream,
-        addr: SocketAddr,
+        stream: Transport,
+        addr: Option<SocketAddr>,
         tasks: &mut JoinSet<Result<()>>,
     ) {
+        if let Some(addr) = addr
+            && !self.config.is_ip_allowed(addr.ip())
+        {
+            warn!("Rejecting connection from {}: address not allowed", addr);
+            return;
+        }
+        let peer = addr.map_or_else(|| "unix socket".to_string(), |addr| addr.to_string());
+
         let permit = match self.connection_limiter.clone().try_acquire_owned() {
             Ok(p) => p,
             Err(_) => {
-                warn!("Connection limit reached, rejecting {}", addr);
+                warn!("Connection limit reached, rejecting {}", peer);
                 return;
             }
         };
 
+        self.handler.metrics().connection_accepted();
         let id = self.next_id.fetch_add(1, Ordering::Relaxed);
         let handler = Arc::clone(&self.handler);
         let config = Arc::clone(&self.config);
         let identity_key = self.identity_key.clone();
         let shutdown = self.shutdown.clone();
+        #[cfg(feature = "tls")]
+        let tls_acceptor = self.tls_acceptor.clone();
 
         tasks.spawn(async move {
             let _permit = permit;
 
+            // Only plain TCP gets wrapped; Unix and WebSocket peers keep their own transport.
+            #[cfg(feature = "tls")]
+            let stream = match (tls_acceptor, stream) {
+                (Some(acceptor), Transport::Tcp(stream)) => {
+                    let accept = TlsTransport::accept(stream, &acceptor);
+                    tokio::time::timeout(config.handshake_timeout, accept)
+                        .await
+                        .map_err(|_| {
+                            FenrisError::NetworkError(std::io::Error::new(
+                                std::io::ErrorKind::TimedOut,
+                                "TLS handshake timeout",
+                            ))
+                        })
+                        .flatten()
+                        .inspect_err(|_| handler.metrics().handshake_failed())?
+                        .into()
+                }
+                (_, stream) => stream,
+            };
+
             let connection = if let Some(identity_key) = identity_key {
-                Connection::accept_authenticated(id, stream, addr, handler, config, identity_key)
+                Connection::accept_authenticated(id, stream, peer, handler, config, identity_key)
                     .await?
             } else {
-                Connection::accept(id, stream, addr, handler, config).await?
+                Connection::accept(id, stream, peer, handler, config).await?
             };
             connection.run(shutdown).await
         });
@@ -158,10 +384,711 @@ impl<B: StorageBackend> Server<B> {
 #[derive(Clone)]
 pub struct ServerHandle {
     shutdown: CancellationToken,
+    subscriptions: Arc<SubscriptionManager>,
+    clients: Arc<ClientRegistry>,
+    stats: Arc<CommandStats>,
+    storage: Arc<dyn StorageBackend>,
 }
 
 impl ServerHandle {
     pub fn shutdown(&self) {
         self.shutdown.cancel();
     }
+
+    /// Best-effort: returns how many client queues accepted the message, not deliveries.
+    pub fn broadcast(&self, message: &str) -> usize {
+        self.subscriptions.broadcast(message)
+    }
+
+    /// Connected clients, with the directory each one last changed into.
+    pub fn list_clients(&self) -> Vec<ClientInfo> {
+        self.clients.list()
+    }
+
+    /// Requests processed since startup or the last reset, keyed by request type name.
+    pub fn command_stats(&self) -> HashMap<String, u64> {
+        self.stats.snapshot()
+    }
+
+    pub fn reset_stats(&self) {
+        self.stats.reset();
+    }
+
+    /// Moves storage to `new_base_dir` without a restart. Requests already running finish
+    /// against the old directory; each connection's working directory is kept as a path, so
+    /// clients may want to `cd /` afterwards.
+    pub async fn reload_file_ops(&self, new_base_dir: PathBuf) -> Result<()> {
+        self.storage.reload_base_dir(new_base_dir.clone()).await?;
+        info!(
+            "Storage base directory reloaded: {}",
+            new_base_dir.display()
+        );
+        Ok(())
+    }
+}
+
+async fn bind_listener(addr: &str, config: &ServerConfig) -> Result<TcpListener> {
+    let mut last_error = None;
+
+    for addr in tokio::net::lookup_host(addr)
+        .await
+        .map_err(FenrisError::NetworkError)?
+    {
+        match bind_socket(addr, config) {
+            Ok(listener) => return Ok(listener),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(FenrisError::NetworkError(last_error.unwrap_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "could not resolve to any address",
+        )
+    })))
+}
+
+fn bind_socket(addr: SocketAddr, config: &ServerConfig) -> std::io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+    if config.tcp_reuseport {
+        set_reuse_port(&socket)?;
+    }
+
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(config.tcp_backlog.min(i32::MAX as u32) as i32)?;
+
+    TcpListener::from_std(socket.into())
+}
+
+/// Disables Nagle's algorithm and applies the keepalive and buffer sizes from `config`.
+pub(crate) fn configure_tcp_stream(
+    stream: &TcpStream,
+    config: &ServerConfig,
+) -> std::io::Result<()> {
+    stream.set_nodelay(true)?;
+
+    let socket = SockRef::from(stream);
+    if let Some(time) = config.tcp_keepalive {
+        let keepalive = TcpKeepalive::new().with_time(time).with_interval(time / 3);
+        socket.set_tcp_keepalive(&keepalive)?;
+    }
+    if let Some(size) = config.socket_buffer_size {
+        socket.set_send_buffer_size(size)?;
+        socket.set_recv_buffer_size(size)?;
+    }
+    Ok(())
+}
+
+/// Removes a socket file left behind by a previous run; any other kind of file is kept and the
+/// bind fails instead of clobbering it.
+#[cfg(unix)]
+fn bind_unix_listener(socket_path: &Path) -> Result<Listener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if let Ok(metadata) = std::fs::symlink_metadata(socket_path)
+        && metadata.file_type().is_socket()
+    {
+        std::fs::remove_file(socket_path).map_err(FenrisError::NetworkError)?;
+    }
+
+    let listener = UnixListener::bind(socket_path).map_err(FenrisError::NetworkError)?;
+    Ok(Listener::Unix(listener, socket_path.to_path_buf()))
+}
+
+#[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+fn set_reuse_port(socket: &Socket) -> std::io::Result<()> {
+    socket.set_reuse_port(true)
+}
+
+#[cfg(not(all(unix, not(any(target_os = "solaris", target_os = "illumos")))))]
+fn set_reuse_port(_socket: &Socket) -> std::io::Result<()> {
+    warn!("SO_REUSEPORT is not supported on this platform; ignoring tcp_reuseport");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{DefaultSecureChannel, FenrisCommand, FenrisOutput, MemoryStorage};
+    use std::time::Duration;
+
+    async fn handshake_with_allow_list(cidr: &str) -> Result<DefaultSecureChannel> {
+        let config = ServerConfig::builder()
+            .allow_list(vec![cidr.parse().unwrap()])
+            .build()
+            .unwrap();
+        let (server, handle) = Server::bind("127.0.0.1:0", Arc::new(MemoryStorage::new()), config)
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let channel = DefaultSecureChannel::client_handshake(stream).await;
+        handle.shutdown();
+        channel
+    }
+
+    #[tokio::test]
+    async fn accepted_tcp_streams_get_the_configured_socket_options() {
+        let config = ServerConfig::builder()
+            .tcp_keepalive(Some(Duration::from_secs(30)))
+            .socket_buffer_size(64 * 1024)
+            .build()
+            .unwrap();
+        let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let _client = TcpStream::connect(tcp.local_addr().unwrap()).await.unwrap();
+        let listener = Listener::Tcp(tcp);
+
+        let (transport, addr) = listener.accept(&config).await.unwrap();
+        assert!(addr.is_some());
+        let Transport::Tcp(stream) = transport else {
+            panic!("expected a TCP transport");
+        };
+        assert!(stream.nodelay().unwrap());
+
+        let socket = SockRef::from(&stream);
+        assert!(socket.keepalive().unwrap());
+        #[cfg(target_os = "linux")]
+        {
+            assert_eq!(
+                socket.tcp_keepalive_time().unwrap(),
+                Duration::from_secs(30)
+            );
+            assert_eq!(
+                socket.tcp_keepalive_interval().unwrap(),
+                Duration::from_secs(10)
+            );
+            // Linux doubles the requested size to leave room for bookkeeping.
+            assert!(socket.recv_buffer_size().unwrap() >= 64 * 1024);
+        }
+    }
+
+    #[tokio::test]
+    async fn allow_list_accepts_matching_network() {
+        assert!(handshake_with_allow_list("127.0.0.0/8").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn allow_list_closes_other_addresses_before_handshake() {
+        assert!(handshake_with_allow_list("10.0.0.0/8").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn echo_returns_binary_payloads_byte_for_byte() {
+        let (server, handle) = Server::bind(
+            "127.0.0.1:0",
+            Arc::new(MemoryStorage::new()),
+            ServerConfig::default(),
+        )
+        .await
+        .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut channel = DefaultSecureChannel::client_handshake(stream)
+            .await
+            .unwrap();
+        let payloads = [
+            Vec::new(),
+            vec![0u8; 64],
+            (0..=255u8).collect::<Vec<_>>(),
+            b"\xc3\x28 invalid utf-8 \xff\xfe".to_vec(),
+            (0..100_000u32).map(|i| (i % 251) as u8).collect(),
+        ];
+        for payload in payloads {
+            channel
+                .send_msg(&FenrisCommand::Echo {
+                    payload: payload.clone(),
+                })
+                .await
+                .unwrap();
+            let output = channel.recv_msg::<FenrisOutput>().await.unwrap();
+            assert_eq!(output, FenrisOutput::Echo { payload });
+        }
+
+        handle.shutdown();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn reuseport_lets_two_servers_share_a_port() {
+        let config = ServerConfig::builder().tcp_reuseport(true).build().unwrap();
+        let (first, _) = Server::bind(
+            "127.0.0.1:0",
+            Arc::new(MemoryStorage::new()),
+            config.clone(),
+        )
+        .await
+        .unwrap();
+        let addr = first.local_addr().unwrap().to_string();
+
+        let (second, _) = Server::bind(&addr, Arc::new(MemoryStorage::new()), config)
+            .await
+            .unwrap();
+        assert_eq!(second.local_addr().unwrap(), first.local_addr().unwrap());
+
+        let without_reuseport = Server::bind(
+            &addr,
+            Arc::new(MemoryStorage::new()),
+            ServerConfig::default(),
+        )
+        .await;
+        assert!(without_reuseport.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_serves_upload_and_download() {
+        use common::{Config, SecureChannel, TransferChunk};
+        use tokio::net::UnixStream;
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("fenris.sock");
+        let (server, handle) = Server::bind_unix(
+            &socket_path,
+            Arc::new(MemoryStorage::new()),
+            ServerConfig::default(),
+        )
+        .await
+        .unwrap();
+        assert!(server.local_addr().is_err());
+        let server_task = tokio::spawn(server.run());
+
+        let stream = UnixStream::connect(&socket_path).await.unwrap();
+        let mut channel = SecureChannel::<Config, _>::client_handshake(stream)
+            .await
+            .unwrap();
+        let commands = [
+            FenrisCommand::WriteObject {
+                path: "/hello.txt".into(),
+                data: b"over a unix socket".to_vec(),
+            },
+            FenrisCommand::ReadObject {
+                path: "/hello.txt".into(),
+            },
+        ];
+        let mut outputs = Vec::new();
+        for command in &commands {
+            channel.send_msg(command).await.unwrap();
+            outputs.push(channel.recv_msg::<FenrisOutput>().await.unwrap());
+        }
+
+        assert!(matches!(outputs[0], FenrisOutput::Success { .. }));
+        assert_eq!(
+            outputs[1],
+            FenrisOutput::ObjectContentChunk(TransferChunk {
+                offset: 0,
+                data: b"over a unix socket".to_vec(),
+                is_last: true,
+                total_size: 18,
+            })
+        );
+
+        handle.shutdown();
+        server_task.await.unwrap().unwrap();
+        assert!(!socket_path.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn bind_unix_replaces_stale_socket_but_not_regular_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("stale.sock");
+        drop(std::os::unix::net::UnixListener::bind(&socket_path).unwrap());
+
+        let bound = Server::bind_unix(
+            &socket_path,
+            Arc::new(MemoryStorage::new()),
+            ServerConfig::default(),
+        )
+        .await;
+        assert!(bound.is_ok());
+
+        let file_path = dir.path().join("regular");
+        std::fs::write(&file_path, b"keep me").unwrap();
+        let bound = Server::bind_unix(
+            &file_path,
+            Arc::new(MemoryStorage::new()),
+            ServerConfig::default(),
+        )
+        .await;
+        assert!(bound.is_err());
+        assert_eq!(std::fs::read(&file_path).unwrap(), b"keep me");
+    }
+
+    #[tokio::test]
+    async fn handle_reports_and_resets_command_stats() {
+        let (server, handle) = Server::bind(
+            "127.0.0.1:0",
+            Arc::new(MemoryStorage::new()),
+            ServerConfig::default(),
+        )
+        .await
+        .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut channel = DefaultSecureChannel::client_handshake(stream)
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            channel.send_msg(&FenrisCommand::Ping).await.unwrap();
+            let output: FenrisOutput = channel.recv_msg().await.unwrap();
+            assert_eq!(output, FenrisOutput::Pong);
+        }
+
+        assert_eq!(handle.command_stats().get("Ping"), Some(&2));
+        handle.reset_stats();
+        assert!(handle.command_stats().is_empty());
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn motd_is_sent_before_the_first_response() {
+        let config = ServerConfig::builder()
+            .motd("Maintenance at 02:00 UTC".to_string())
+            .build()
+            .unwrap();
+        let (server, handle) = Server::bind("127.0.0.1:0", Arc::new(MemoryStorage::new()), config)
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut channel = DefaultSecureChannel::client_handshake(stream)
+            .await
+            .unwrap();
+        let banner: FenrisOutput = channel.recv_msg().await.unwrap();
+        assert_eq!(
+            banner,
+            FenrisOutput::Banner {
+                message: "Maintenance at 02:00 UTC".to_string(),
+            }
+        );
+
+        channel.send_msg(&FenrisCommand::Ping).await.unwrap();
+        let output: FenrisOutput = channel.recv_msg().await.unwrap();
+        assert_eq!(output, FenrisOutput::Pong);
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn list_clients_follows_directory_changes() {
+        let (server, handle) = Server::bind(
+            "127.0.0.1:0",
+            Arc::new(MemoryStorage::new()),
+            ServerConfig::default(),
+        )
+        .await
+        .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut channel = DefaultSecureChannel::client_handshake(stream)
+            .await
+            .unwrap();
+        for command in [
+            FenrisCommand::CreateNamespace {
+                path: "/docs".into(),
+            },
+            FenrisCommand::ChangeNamespace {
+                path: "docs".into(),
+            },
+        ] {
+            channel.send_msg(&command).await.unwrap();
+            let _: FenrisOutput = channel.recv_msg().await.unwrap();
+        }
+
+        let clients = handle.list_clients();
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].current_dir, PathBuf::from("/docs"));
+        assert!(clients[0].peer.starts_with("127.0.0.1:"));
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn users_log_in_to_their_own_base_dir() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("alice")).unwrap();
+        std::fs::write(root.path().join("secret.txt"), b"root only").unwrap();
+        let users_file = root.path().join("users.toml");
+        std::fs::write(
+            &users_file,
+            format!(
+                "[alice]\npassword_hash = \"{}\"\nbase_dir = \"{}\"\n",
+                bcrypt::hash("hunter2", 4).unwrap(),
+                root.path().join("alice").display()
+            ),
+        )
+        .unwrap();
+        let config = ServerConfig::builder()
+            .users_file(users_file)
+            .build()
+            .unwrap();
+        let (server, handle) = Server::bind(
+            "127.0.0.1:0",
+            Arc::new(common::TokioFsStorage::new(root.path().to_path_buf())),
+            config,
+        )
+        .await
+        .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+        let login = |password: &str| FenrisCommand::Login {
+            username: "alice".to_string(),
+            password: password.to_string(),
+        };
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut rejected = DefaultSecureChannel::client_handshake(stream)
+            .await
+            .unwrap();
+        rejected.send_msg(&login("wrong")).await.unwrap();
+        let output: FenrisOutput = rejected.recv_msg().await.unwrap();
+        assert_eq!(
+            output,
+            FenrisOutput::Error {
+                message: "Authentication failed".to_string()
+            }
+        );
+        assert!(rejected.recv_msg::<FenrisOutput>().await.is_err());
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut channel = DefaultSecureChannel::client_handshake(stream)
+            .await
+            .unwrap();
+        channel.send_msg(&login("hunter2")).await.unwrap();
+        let output: FenrisOutput = channel.recv_msg().await.unwrap();
+        assert!(matches!(output, FenrisOutput::Success { .. }));
+        let mut outputs = Vec::new();
+        for command in [
+            FenrisCommand::WriteObject {
+                path: "/notes.txt".into(),
+                data: b"mine".to_vec(),
+            },
+            FenrisCommand::ObjectInfo {
+                path: "/secret.txt".into(),
+                detect_mime: false,
+            },
+        ] {
+            channel.send_msg(&command).await.unwrap();
+            outputs.push(channel.recv_msg::<FenrisOutput>().await.unwrap());
+        }
+
+        assert!(matches!(outputs[0], FenrisOutput::Success { .. }));
+        assert!(matches!(outputs[1], FenrisOutput::Error { .. }));
+
+        assert_eq!(
+            std::fs::read(root.path().join("alice/notes.txt")).unwrap(),
+            b"mine"
+        );
+        let clients = handle.list_clients();
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].username.as_deref(), Some("alice"));
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn handle_reloads_the_storage_base_dir() {
+        let old_dir = tempfile::tempdir().unwrap();
+        let new_dir = tempfile::tempdir().unwrap();
+        std::fs::write(old_dir.path().join("old.txt"), b"old").unwrap();
+        std::fs::write(new_dir.path().join("new.txt"), b"new").unwrap();
+        let file_ops = common::DefaultFileOperations::new(old_dir.path().to_path_buf())
+            .await
+            .unwrap();
+        let (server, handle) = Server::bind(
+            "127.0.0.1:0",
+            Arc::new(common::TokioFsStorage::with_file_ops(file_ops)),
+            ServerConfig::default(),
+        )
+        .await
+        .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut channel = DefaultSecureChannel::client_handshake(stream)
+            .await
+            .unwrap();
+        let info = |path: &str| FenrisCommand::ObjectInfo {
+            path: path.into(),
+            detect_mime: false,
+        };
+
+        channel.send_msg(&info("/old.txt")).await.unwrap();
+        let output: FenrisOutput = channel.recv_msg().await.unwrap();
+        assert!(matches!(output, FenrisOutput::ObjectInfo { .. }));
+
+        assert!(
+            handle
+                .reload_file_ops(old_dir.path().join("missing"))
+                .await
+                .is_err()
+        );
+        handle
+            .reload_file_ops(new_dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        channel.send_msg(&info("/new.txt")).await.unwrap();
+        let output: FenrisOutput = channel.recv_msg().await.unwrap();
+        assert!(matches!(output, FenrisOutput::ObjectInfo { .. }));
+        channel.send_msg(&info("/old.txt")).await.unwrap();
+        let output: FenrisOutput = channel.recv_msg().await.unwrap();
+        assert!(matches!(output, FenrisOutput::Error { .. }));
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn oversized_frame_closes_the_connection() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let config = ServerConfig::builder()
+            .max_message_size(1024)
+            .build()
+            .unwrap();
+        let (server, handle) = Server::bind("127.0.0.1:0", Arc::new(MemoryStorage::new()), config)
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let channel = DefaultSecureChannel::client_handshake(stream)
+            .await
+            .unwrap();
+        let mut stream = channel.into_inner();
+        stream.write_all(&u32::MAX.to_be_bytes()).await.unwrap();
+
+        let mut rest = Vec::new();
+        let closed = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            stream.read_to_end(&mut rest),
+        )
+        .await;
+        assert!(closed.is_ok(), "server kept waiting for the frame body");
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn metrics_endpoint_serves_prometheus_text() {
+        let config = ServerConfig::builder()
+            .metrics_addr("127.0.0.1:0".parse().unwrap())
+            .build()
+            .unwrap();
+        let (server, handle) = Server::bind("127.0.0.1:0", Arc::new(MemoryStorage::new()), config)
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        let metrics_addr = server.metrics_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut channel = DefaultSecureChannel::client_handshake(stream)
+            .await
+            .unwrap();
+        channel.send_msg(&FenrisCommand::Ping).await.unwrap();
+        let _: FenrisOutput = channel.recv_msg().await.unwrap();
+
+        let response = reqwest::get(format!("http://{}/metrics", metrics_addr))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body = response.text().await.unwrap();
+        handle.shutdown();
+
+        assert!(body.contains("# TYPE fenris_requests_total counter"));
+        assert!(body.contains("fenris_requests_total{command=\"PING\"} 1"));
+        assert!(body.contains("fenris_active_connections 1"));
+        assert!(body.contains("fenris_total_connections_accepted 1"));
+        for line in body.lines().filter(|line| !line.starts_with('#')) {
+            let (_, value) = line.rsplit_once(' ').unwrap();
+            assert!(value.parse::<f64>().is_ok(), "bad sample line: {}", line);
+        }
+    }
+
+    #[cfg(feature = "websocket")]
+    #[tokio::test]
+    async fn websocket_clients_complete_the_usual_handshake() {
+        let config = ServerConfig::builder().websocket_port(0).build().unwrap();
+        let (server, handle) = Server::bind("127.0.0.1:0", Arc::new(MemoryStorage::new()), config)
+            .await
+            .unwrap();
+        let websocket_addr = server.websocket_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let stream = TcpStream::connect(websocket_addr).await.unwrap();
+        let transport =
+            common::WebSocketTransport::connect(stream, &format!("ws://{}/", websocket_addr))
+                .await
+                .unwrap();
+        let mut channel = common::SecureChannel::<common::Config, _>::client_handshake(transport)
+            .await
+            .unwrap();
+        channel.send_msg(&FenrisCommand::Ping).await.unwrap();
+        let output: FenrisOutput = channel.recv_msg().await.unwrap();
+        handle.shutdown();
+
+        assert!(matches!(output, FenrisOutput::Pong));
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn tls_clients_complete_the_usual_handshake() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_path = dir.path().join("server.crt");
+        let key_path = dir.path().join("server.key");
+        std::fs::write(&cert_path, certified.cert.pem()).unwrap();
+        std::fs::write(&key_path, certified.signing_key.serialize_pem()).unwrap();
+
+        let config = ServerConfig::builder()
+            .tls(common::TlsConfig::new(&cert_path, &key_path))
+            .build()
+            .unwrap();
+        let (server, handle) = Server::bind("127.0.0.1:0", Arc::new(MemoryStorage::new()), config)
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let connector = common::TlsClientConfig::new(&cert_path)
+            .connector()
+            .unwrap();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let transport = common::TlsTransport::connect(stream, &connector, "localhost")
+            .await
+            .unwrap();
+        let mut channel = common::TlsSecureChannel::client_handshake(transport)
+            .await
+            .unwrap();
+        channel.send_msg(&FenrisCommand::Ping).await.unwrap();
+        let output: FenrisOutput = channel.recv_msg().await.unwrap();
+        handle.shutdown();
+
+        assert!(matches!(output, FenrisOutput::Pong));
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn bind_fails_when_the_tls_certificate_is_missing() {
+        let config = ServerConfig::builder()
+            .tls(common::TlsConfig::new("missing.crt", "missing.key"))
+            .build()
+            .unwrap();
+
+        let result = Server::bind("127.0.0.1:0", Arc::new(MemoryStorage::new()), config).await;
+
+        assert!(result.is_err());
+    }
 }
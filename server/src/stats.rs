@@ -0,0 +1,70 @@
+use common::RequestType;
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Default)]
+pub struct CommandStats {
+    counts: DashMap<RequestType, AtomicU64>,
+}
+
+impl CommandStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, request_type: RequestType) {
+        if let Some(count) = self.counts.get(&request_type) {
+            count.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.counts
+            .entry(request_type)
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Invocation counts keyed by the `RequestType` debug name.
+    pub fn snapshot(&self) -> HashMap<String, u64> {
+        self.counts
+            .iter()
+            .map(|entry| {
+                (
+                    format!("{:?}", entry.key()),
+                    entry.value().load(Ordering::Relaxed),
+                )
+            })
+            .collect()
+    }
+
+    pub fn reset(&self) {
+        self.counts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_counts_each_request_type() {
+        let stats = CommandStats::new();
+        stats.record(RequestType::Ping);
+        stats.record(RequestType::Ping);
+        stats.record(RequestType::ReadFile);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.get("Ping"), Some(&2));
+        assert_eq!(snapshot.get("ReadFile"), Some(&1));
+        assert_eq!(snapshot.len(), 2);
+    }
+
+    #[test]
+    fn test_reset_clears_counts() {
+        let stats = CommandStats::new();
+        stats.record(RequestType::Checksum);
+        stats.reset();
+
+        assert!(stats.snapshot().is_empty());
+    }
+}
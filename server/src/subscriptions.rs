@@ -0,0 +1,271 @@
+use common::{FenrisOutput, WatchEvent, WatchEventKind};
+use dashmap::DashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+const EVENT_QUEUE_CAPACITY: usize = 64;
+
+pub type ClientId = u64;
+
+/// Watch subscriptions and event queues for every connected client. Subscriptions are kept
+/// per storage root, since the same path names different files for different users; go
+/// through a [`SubscriptionScope`] to subscribe or notify.
+#[derive(Default)]
+pub struct SubscriptionManager {
+    subscriptions: DashMap<(PathBuf, PathBuf), Vec<ClientId>>,
+    clients: DashMap<ClientId, mpsc::Sender<FenrisOutput>>,
+}
+
+/// A [`SubscriptionManager`] seen from one storage root.
+#[derive(Clone)]
+pub struct SubscriptionScope {
+    manager: Arc<SubscriptionManager>,
+    root: PathBuf,
+}
+
+impl SubscriptionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscriptions made through the scope only see events notified through a scope with
+    /// the same `root`.
+    pub fn scope(self: &Arc<Self>, root: PathBuf) -> SubscriptionScope {
+        SubscriptionScope {
+            manager: Arc::clone(self),
+            root,
+        }
+    }
+
+    pub fn register_client(&self, client_id: ClientId) -> mpsc::Receiver<FenrisOutput> {
+        let (tx, rx) = mpsc::channel(EVENT_QUEUE_CAPACITY);
+        self.clients.insert(client_id, tx);
+        rx
+    }
+
+    pub fn unregister_client(&self, client_id: ClientId) {
+        self.clients.remove(&client_id);
+        self.subscriptions.retain(|_, subscribers| {
+            subscribers.retain(|id| *id != client_id);
+            !subscribers.is_empty()
+        });
+    }
+
+    fn subscribe(&self, client_id: ClientId, key: (PathBuf, PathBuf)) {
+        let mut subscribers = self.subscriptions.entry(key).or_default();
+        if !subscribers.contains(&client_id) {
+            subscribers.push(client_id);
+        }
+    }
+
+    fn unsubscribe(&self, client_id: ClientId, key: &(PathBuf, PathBuf)) -> bool {
+        let Some(mut subscribers) = self.subscriptions.get_mut(key) else {
+            return false;
+        };
+
+        let before = subscribers.len();
+        subscribers.retain(|id| *id != client_id);
+        let removed = subscribers.len() != before;
+        let empty = subscribers.is_empty();
+        drop(subscribers);
+
+        if empty {
+            self.subscriptions
+                .remove_if(key, |_, subscribers| subscribers.is_empty());
+        }
+
+        removed
+    }
+
+    fn subscriber_count(&self, key: &(PathBuf, PathBuf)) -> usize {
+        self.subscriptions
+            .get(key)
+            .map(|subscribers| subscribers.len())
+            .unwrap_or(0)
+    }
+
+    fn notify(&self, root: &Path, path: &Path, kind: WatchEventKind) {
+        if self.subscriptions.is_empty() {
+            return;
+        }
+
+        let watched = std::iter::once(path).chain(path.parent());
+
+        for subscription in watched {
+            let key = (root.to_path_buf(), subscription.to_path_buf());
+            let Some(subscribers) = self.subscriptions.get(&key) else {
+                continue;
+            };
+
+            for client_id in subscribers.iter() {
+                let Some(sender) = self.clients.get(client_id) else {
+                    continue;
+                };
+
+                let event = FenrisOutput::WatchEvent(WatchEvent {
+                    path: path.to_path_buf(),
+                    kind,
+                    subscription: subscription.to_path_buf(),
+                });
+
+                if sender.try_send(event).is_err() {
+                    warn!("Dropping watch event for client {}: queue full", client_id);
+                }
+            }
+        }
+    }
+
+    pub fn broadcast(&self, message: &str) -> usize {
+        let mut enqueued = 0;
+        for client in self.clients.iter() {
+            let output = FenrisOutput::Broadcast {
+                message: message.to_string(),
+            };
+            match client.value().try_send(output) {
+                Ok(()) => enqueued += 1,
+                Err(_) => warn!("Dropping broadcast for client {}: queue full", client.key()),
+            }
+        }
+        enqueued
+    }
+}
+
+impl SubscriptionScope {
+    pub fn manager(&self) -> &Arc<SubscriptionManager> {
+        &self.manager
+    }
+
+    pub fn subscribe(&self, client_id: ClientId, path: &Path) {
+        self.manager.subscribe(client_id, self.key(path));
+    }
+
+    pub fn unsubscribe(&self, client_id: ClientId, path: &Path) -> bool {
+        self.manager.unsubscribe(client_id, &self.key(path))
+    }
+
+    pub fn subscriber_count(&self, path: &Path) -> usize {
+        self.manager.subscriber_count(&self.key(path))
+    }
+
+    pub fn notify(&self, path: &Path, kind: WatchEventKind) {
+        self.manager.notify(&self.root, path, kind);
+    }
+
+    /// Broadcasts reach every client, whatever their scope.
+    pub fn broadcast(&self, message: &str) -> usize {
+        self.manager.broadcast(message)
+    }
+
+    fn key(&self, path: &Path) -> (PathBuf, PathBuf) {
+        (self.root.clone(), path.to_path_buf())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expect_event(rx: &mut mpsc::Receiver<FenrisOutput>) -> WatchEvent {
+        match rx.try_recv().unwrap() {
+            FenrisOutput::WatchEvent(event) => event,
+            other => panic!("unexpected output: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn notify_reaches_subscribers_of_path_and_parent() {
+        let manager = Arc::new(SubscriptionManager::new());
+        let scope = manager.scope(PathBuf::new());
+        let mut dir_rx = manager.register_client(1);
+        let mut file_rx = manager.register_client(2);
+        scope.subscribe(1, Path::new("/docs"));
+        scope.subscribe(2, Path::new("/docs/a.txt"));
+
+        scope.notify(Path::new("/docs/a.txt"), WatchEventKind::Modified);
+
+        let event = expect_event(&mut dir_rx);
+        assert_eq!(event.path, PathBuf::from("/docs/a.txt"));
+        assert_eq!(event.subscription, PathBuf::from("/docs"));
+        assert_eq!(event.kind, WatchEventKind::Modified);
+
+        let event = expect_event(&mut file_rx);
+        assert_eq!(event.subscription, PathBuf::from("/docs/a.txt"));
+    }
+
+    #[test]
+    fn broadcast_skips_clients_with_full_queues() {
+        let manager = SubscriptionManager::new();
+        let mut rx = manager.register_client(1);
+        let _full_rx = manager.register_client(2);
+        for _ in 0..EVENT_QUEUE_CAPACITY {
+            manager
+                .clients
+                .get(&2)
+                .unwrap()
+                .try_send(FenrisOutput::Pong)
+                .unwrap();
+        }
+
+        assert_eq!(manager.broadcast("maintenance"), 1);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            FenrisOutput::Broadcast {
+                message: "maintenance".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn notify_ignores_unrelated_paths() {
+        let manager = Arc::new(SubscriptionManager::new());
+        let scope = manager.scope(PathBuf::new());
+        let mut rx = manager.register_client(1);
+        scope.subscribe(1, Path::new("/docs"));
+
+        scope.notify(Path::new("/other/a.txt"), WatchEventKind::Created);
+        scope.notify(Path::new("/docs/nested/a.txt"), WatchEventKind::Created);
+
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn unsubscribe_and_unregister_remove_client() {
+        let manager = Arc::new(SubscriptionManager::new());
+        let scope = manager.scope(PathBuf::new());
+        let mut rx = manager.register_client(1);
+        scope.subscribe(1, Path::new("/docs"));
+        scope.subscribe(1, Path::new("/docs"));
+        assert_eq!(scope.subscriber_count(Path::new("/docs")), 1);
+
+        assert!(scope.unsubscribe(1, Path::new("/docs")));
+        assert!(!scope.unsubscribe(1, Path::new("/docs")));
+        scope.notify(Path::new("/docs/a.txt"), WatchEventKind::Created);
+        assert!(rx.try_recv().is_err());
+
+        scope.subscribe(1, Path::new("/docs"));
+        manager.unregister_client(1);
+        assert_eq!(scope.subscriber_count(Path::new("/docs")), 0);
+    }
+
+    #[test]
+    fn scopes_with_different_roots_do_not_share_events() {
+        let manager = Arc::new(SubscriptionManager::new());
+        let alice = manager.scope(PathBuf::from("/srv/alice"));
+        let bob = manager.scope(PathBuf::from("/srv/bob"));
+        let mut alice_rx = manager.register_client(1);
+        let mut bob_rx = manager.register_client(2);
+        alice.subscribe(1, Path::new("/docs"));
+        bob.subscribe(2, Path::new("/docs"));
+
+        alice.notify(Path::new("/docs/a.txt"), WatchEventKind::Created);
+
+        assert_eq!(
+            expect_event(&mut alice_rx).path,
+            PathBuf::from("/docs/a.txt")
+        );
+        assert!(bob_rx.try_recv().is_err());
+        assert_eq!(bob.broadcast("maintenance"), 2);
+    }
+}
@@ -0,0 +1,132 @@
+use common::{FenrisError, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Checked in place of a real hash when the username is unknown, so a failed login costs the
+/// same bcrypt round either way and timing does not reveal which accounts exist.
+static DUMMY_PASSWORD_HASH: LazyLock<String> = LazyLock::new(|| {
+    bcrypt::hash("fenris-unknown-user", bcrypt::DEFAULT_COST)
+        .expect("hashing a fixed password cannot fail")
+});
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserEntry {
+    /// A bcrypt hash, as produced by `bcrypt::hash` or `htpasswd -B`.
+    pub password_hash: String,
+    pub base_dir: PathBuf,
+}
+
+/// The accounts in a `users.toml`, one table per username:
+///
+/// ```toml
+/// [alice]
+/// password_hash = "$2b$12$..."
+/// base_dir = "/srv/fenris/alice"
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UserDatabase {
+    users: HashMap<String, UserEntry>,
+}
+
+impl UserDatabase {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            FenrisError::file_operation_from(
+                format!("Failed to read users file {}: {}", path.display(), e),
+                e,
+            )
+        })?;
+        Self::parse(&contents, &path.display().to_string())
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        Self::parse(contents, "users file")
+    }
+
+    fn parse(contents: &str, source: &str) -> Result<Self> {
+        let users = toml::from_str(contents).map_err(|e| {
+            FenrisError::serialization_from(format!("Invalid {}: {}", source, e), e)
+        })?;
+        Ok(Self { users })
+    }
+
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    /// Returns the user's entry when `password` matches. bcrypt is deliberately slow, so
+    /// callers on the async runtime should run this on a blocking thread.
+    pub fn verify(&self, username: &str, password: &str) -> Option<&UserEntry> {
+        let Some(entry) = self.users.get(username) else {
+            let _ = bcrypt::verify(password, &DUMMY_PASSWORD_HASH);
+            return None;
+        };
+        match bcrypt::verify(password, &entry.password_hash) {
+            Ok(true) => Some(entry),
+            Ok(false) => None,
+            Err(e) => {
+                tracing::warn!("Unusable password hash for user {}: {}", username, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database() -> UserDatabase {
+        let hash = bcrypt::hash("hunter2", 4).unwrap();
+        UserDatabase::from_toml(&format!(
+            "[alice]\npassword_hash = \"{}\"\nbase_dir = \"/srv/alice\"\n\n\
+             [bob]\npassword_hash = \"not-a-hash\"\nbase_dir = \"/srv/bob\"\n",
+            hash
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn verify_accepts_only_the_matching_password() {
+        let users = database();
+
+        assert_eq!(users.len(), 2);
+        assert_eq!(
+            users.verify("alice", "hunter2").unwrap().base_dir,
+            Path::new("/srv/alice")
+        );
+        assert!(users.verify("alice", "hunter3").is_none());
+        assert!(users.verify("bob", "not-a-hash").is_none());
+        assert!(users.verify("carol", "hunter2").is_none());
+    }
+
+    #[test]
+    fn unknown_users_are_checked_against_a_real_hash() {
+        assert!(matches!(
+            bcrypt::verify("hunter2", &DUMMY_PASSWORD_HASH),
+            Ok(false)
+        ));
+    }
+
+    #[test]
+    fn load_rejects_unknown_fields() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(
+            &mut file,
+            b"[alice]\npassword_hash = \"x\"\nbase_dir = \"/srv\"\nadmin = true\n",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            UserDatabase::load(file.path()),
+            Err(FenrisError::SerializationError { .. })
+        ));
+    }
+}
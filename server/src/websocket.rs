@@ -0,0 +1,56 @@
+use common::{Transport, WebSocketTransport};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use crate::config::ServerConfig;
+use crate::server::configure_tcp_stream;
+
+/// Accepts browser connections and hands each one to the main accept loop once its HTTP
+/// upgrade completes, so WebSocket peers go through the same limits and handshake as TCP ones.
+pub async fn serve_websocket(
+    listener: TcpListener,
+    config: Arc<ServerConfig>,
+    upgraded: mpsc::Sender<(Transport, SocketAddr)>,
+    shutdown: CancellationToken,
+) {
+    loop {
+        let (stream, addr) = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("WebSocket accept error: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        // Checked again once upgraded, but there is no point finishing the upgrade first.
+        if !config.is_ip_allowed(addr.ip()) {
+            warn!(
+                "Rejecting WebSocket connection from {}: address not allowed",
+                addr
+            );
+            continue;
+        }
+        if let Err(e) = configure_tcp_stream(&stream, &config) {
+            warn!("Failed to set socket options for {}: {}", addr, e);
+        }
+
+        let upgraded = upgraded.clone();
+        let timeout = config.handshake_timeout;
+        tokio::spawn(async move {
+            match tokio::time::timeout(timeout, WebSocketTransport::accept(stream)).await {
+                Ok(Ok(transport)) => {
+                    let _ = upgraded.send((transport.into(), addr)).await;
+                }
+                Ok(Err(e)) => debug!("WebSocket upgrade from {} failed: {}", addr, e),
+                Err(_) => debug!("WebSocket upgrade from {} timed out", addr),
+            }
+        });
+    }
+}